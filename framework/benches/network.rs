@@ -0,0 +1,99 @@
+//! Benchmarks of the solver, routers, and exporters on the crate's fixtures. Run with
+//! `cargo bench`, optionally followed by `-- FILTER` to select benchmarks by name.
+
+use mmft_framework::{
+    base::primitives::Point,
+    base::river::RiverRouting,
+    dmf::routing::route,
+    export::{dxf::network_to_dxf, gerber::network_to_gerber, svg::network_to_svg},
+    fixtures::{droplet_crossings, gradient_tree, trap_array},
+    simulation::{fluid::Fluid, solver::solve},
+};
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+/// Time spent measuring each benchmark
+const MEASUREMENT: Duration = Duration::from_secs(1);
+
+/// Runs the benchmark in batches until the measurement time passed and prints the median time
+/// per iteration
+fn bench<R>(filter: &Option<String>, name: &str, mut f: impl FnMut() -> R) {
+    if filter
+        .as_ref()
+        .is_some_and(|filter| !name.contains(filter.as_str()))
+    {
+        return;
+    }
+    // Batches of at least a millisecond keep the timer resolution negligible
+    let start = Instant::now();
+    black_box(f());
+    let once = start.elapsed().max(Duration::from_nanos(1));
+    let batch = (Duration::from_millis(1).as_nanos() / once.as_nanos()).max(1) as u32;
+
+    let mut samples = Vec::new();
+    let start = Instant::now();
+    while start.elapsed() < MEASUREMENT || samples.len() < 5 {
+        let batch_start = Instant::now();
+        for _ in 0..batch {
+            black_box(f());
+        }
+        samples.push(batch_start.elapsed() / batch);
+    }
+    samples.sort();
+    println!(
+        "{name:<32} {:>12.3?}/iter ({} samples of {batch})",
+        samples[samples.len() / 2],
+        samples.len()
+    );
+}
+
+fn main() {
+    let filter = std::env::args().skip(1).find(|a| !a.starts_with("--"));
+    let water = Fluid::water();
+
+    for (rows, columns) in [(4, 8), (16, 32)] {
+        let fixture = trap_array(rows, columns);
+        bench(
+            &filter,
+            &format!("solve/trap_array_{rows}x{columns}"),
+            || solve(&fixture.network, &water, &fixture.boundaries).unwrap(),
+        );
+    }
+    for depth in [8, 32] {
+        let fixture = gradient_tree(depth);
+        bench(&filter, &format!("solve/gradient_tree_{depth}"), || {
+            solve(&fixture.network, &water, &fixture.boundaries).unwrap()
+        });
+    }
+
+    for pairs in [2, 8] {
+        let (chip, requests) = droplet_crossings(pairs);
+        bench(&filter, &format!("route/droplet_crossings_{pairs}"), || {
+            route(&chip, &requests, 100).unwrap()
+        });
+    }
+    let routing = RiverRouting {
+        spacing: 10.,
+        bend_radius: Some(2.),
+    };
+    let sources: Vec<Point> = (0..64).map(|i| Point([100. * i as f64, 0.])).collect();
+    let targets: Vec<Point> = (0..64)
+        .map(|i| Point([20. * i as f64 + 50., 5000.]))
+        .collect();
+    bench(&filter, "route/river_64", || {
+        routing.route(&sources, &targets).unwrap()
+    });
+
+    let fixture = trap_array(16, 32);
+    bench(&filter, "export/svg_trap_array_16x32", || {
+        network_to_svg(&fixture.network)
+    });
+    bench(&filter, "export/dxf_trap_array_16x32", || {
+        network_to_dxf(&fixture.network)
+    });
+    bench(&filter, "export/gerber_trap_array_16x32", || {
+        network_to_gerber(&fixture.network, 1e6)
+    });
+}
@@ -0,0 +1,205 @@
+//! Worst-case pressures against the bond strengths of the layer stack. The fluid pushes the
+//! layers around a channel apart, loading the bond of the channel's physical layer to the one
+//! below and the bond of the layer above to it; bonds weaker than the highest pressure over
+//! all operating conditions risk delamination. Pressures are gauge pressures with the outside
+//! of the chip at zero.
+
+use crate::{
+    base::network::{Network, NodeId},
+    simulation::{
+        fluid::Fluid,
+        solver::{solve, Boundary},
+        SimulationError,
+    },
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Worst-case load of a channel
+pub struct ChannelLoad {
+    /// Id of the channel
+    pub channel: usize,
+
+    /// Highest pressure in the channel over all operating conditions, at one of its ends
+    pub pressure: f64,
+
+    /// Index of the operating condition with the highest pressure
+    pub condition: usize,
+
+    /// Strength of the weakest bond loaded by the channel, none without layer stack, a
+    /// physical layer for the channel, or a bond next to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<f64>,
+
+    /// Index of the physical layer whose bond to the one below is the weakest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bond: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Burst analysis of a network
+pub struct BurstReport {
+    /// Highest pressure anywhere in the network
+    pub max_pressure: f64,
+
+    /// Node of the highest pressure
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_node: Option<NodeId>,
+
+    /// Load per channel
+    pub channels: Vec<ChannelLoad>,
+
+    /// Ids of the channels whose pressure times the safety factor exceeds their limit
+    pub at_risk: Vec<usize>,
+}
+
+/// Solves the network for each operating condition, a set of boundaries, and flags the
+/// channels whose worst-case pressure times the safety factor exceeds the strength of a
+/// bond they load
+pub fn burst_report(
+    network: &Network,
+    fluid: &Fluid,
+    conditions: &[Vec<Boundary>],
+    safety_factor: f64,
+) -> Result<BurstReport, SimulationError> {
+    let solutions = (conditions.iter())
+        .map(|boundaries| solve(network, fluid, boundaries))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut report = BurstReport {
+        max_pressure: 0.,
+        max_node: None,
+        channels: Vec::new(),
+        at_risk: Vec::new(),
+    };
+    for solution in &solutions {
+        for (node, pressure) in &solution.pressures {
+            if report.max_node.is_none() || *pressure > report.max_pressure {
+                report.max_pressure = *pressure;
+                report.max_node = Some(*node);
+            }
+        }
+    }
+
+    let stack = network.layer_stack.as_ref();
+    for channel in &network.channels {
+        let mut load = ChannelLoad {
+            channel: channel.id,
+            pressure: f64::NEG_INFINITY,
+            condition: 0,
+            limit: None,
+            bond: None,
+        };
+        for (condition, solution) in solutions.iter().enumerate() {
+            let ends = [channel.node_a, channel.node_b];
+            let pressure = (ends.iter())
+                .filter_map(|node| solution.pressures.get(node))
+                .fold(f64::NEG_INFINITY, |max, p| max.max(*p));
+            if pressure > load.pressure {
+                load.pressure = pressure;
+                load.condition = condition;
+            }
+        }
+        if let Some(stack) = stack {
+            let weakest = (stack.physical_layer(channel.layer).into_iter())
+                .flat_map(|index| [index, index + 1])
+                .filter_map(|index| Some((index, stack.bond_strength(index)?)))
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((index, strength)) = weakest {
+                load.bond = Some(index);
+                load.limit = Some(strength);
+            }
+        }
+        if load
+            .limit
+            .is_some_and(|limit| load.pressure * safety_factor > limit)
+        {
+            report.at_risk.push(channel.id);
+        }
+        report.channels.push(load);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{Channel, RectangularShape, Shape},
+            layers::{Bonding, Layer, LayerStack, Material},
+            network::Node,
+        },
+        simulation::solver::BoundaryCondition,
+    };
+
+    #[test]
+    fn delamination_risk() {
+        let channel = |id: usize, layer| Channel {
+            id,
+            node_a: NodeId(id),
+            node_b: NodeId(id + 1),
+            shape: Shape::Rectangular(RectangularShape {
+                width: 100e-6,
+                height: 50e-6,
+            }),
+            path: None,
+            length: Some(10e-3),
+            layer,
+            metadata: Default::default(),
+        };
+        let layer = |material, network_layer, bonding| Layer {
+            name: String::new(),
+            material,
+            thickness: 1e-3,
+            network_layer,
+            bonding,
+            bond_strength: None,
+        };
+        // A plasma-bonded PDMS flow layer on glass, the second layer clamped on top
+        let mut network = Network {
+            nodes: (0..3).map(|i| Node::new(NodeId(i))).collect(),
+            channels: vec![channel(0, 0), channel(1, 1)],
+            layer_stack: Some(LayerStack {
+                layers: vec![
+                    layer(Material::Glass, None, None),
+                    layer(Material::Pdms, Some(0), Some(Bonding::Plasma)),
+                    layer(Material::Pmma, Some(1), Some(Bonding::Clamped)),
+                ],
+            }),
+            ..Default::default()
+        };
+        let boundaries = |inlet| {
+            [(0, inlet), (2, 0.)]
+                .map(|(node, p)| Boundary {
+                    node: NodeId(node),
+                    condition: BoundaryCondition::Pressure(p),
+                })
+                .to_vec()
+        };
+        let conditions = [boundaries(1e5), boundaries(2.5e5)];
+        let report = burst_report(&network, &Fluid::water(), &conditions, 1.).unwrap();
+        assert_eq!(
+            (report.max_pressure, report.max_node),
+            (2.5e5, Some(NodeId(0)))
+        );
+        let load = report.channels[0];
+        assert_eq!((load.pressure, load.condition), (2.5e5, 1));
+        assert_eq!((load.limit, load.bond), (Some(1e5), Some(2)));
+        assert!((report.channels[1].pressure - 1.25e5).abs() < 1e-6);
+        assert_eq!(report.at_risk, [0, 1]);
+
+        let stack = network.layer_stack.as_mut().unwrap();
+        stack.layers[2].bond_strength = Some(2e6);
+        let report = burst_report(&network, &Fluid::water(), &conditions, 1.).unwrap();
+        assert_eq!(report.channels[0].limit, Some(3e5));
+        assert!(report.at_risk.is_empty());
+        assert!(!burst_report(&network, &Fluid::water(), &conditions, 2.)
+            .unwrap()
+            .at_risk
+            .is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+//! Diffusive mixing of two co-flowing streams entering side by side, e.g., behind a T-junction.
+//! Across the channel width the concentration follows one-dimensional diffusion between
+//! impermeable walls, which gives the residence time, and with the mean velocity the channel
+//! length, needed for a mixing degree. Secondary flows in bends only speed mixing up, so the
+//! estimate is conservative for serpentines.
+
+use crate::base::channel::Shape;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Mixing degree 1 - σ / σ₀ of the concentration across a channel of the width, σ being its
+/// standard deviation after diffusing for the time and σ₀ the one of the unmixed streams
+pub fn mixing_degree(width: f64, diffusivity: f64, time: f64) -> f64 {
+    1. - relative_deviation(diffusivity * time / (width * width))
+}
+
+/// σ / σ₀ at the dimensionless time D t / w² as Fourier series of the initial step profile;
+/// only odd modes contribute
+fn relative_deviation(tau: f64) -> f64 {
+    if tau <= 0. {
+        return 1.;
+    }
+    let mut variance = 0.;
+    for n in (1..20_001).step_by(2) {
+        let n = n as f64;
+        let term = 8. / (n * n * PI * PI) * (-2. * n * n * PI * PI * tau).exp();
+        variance += term;
+        if term < 1e-17 * variance {
+            break;
+        }
+    }
+    variance.min(1.).sqrt()
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Mixing requirement of a channel carrying two streams
+pub struct MixingSpec {
+    pub shape: Shape,
+
+    /// Total flow rate of both streams
+    pub flow: f64,
+
+    /// Diffusion coefficient of the mixed species
+    pub diffusivity: f64,
+
+    /// Mixing degree to reach, in (0, 1), e.g., 0.95
+    pub degree: f64,
+}
+
+impl MixingSpec {
+    /// Residence time needed for the mixing degree, None if the degree isn't in (0, 1)
+    pub fn time(&self) -> Option<f64> {
+        if !(self.degree > 0. && self.degree < 1.) {
+            return None;
+        }
+        // The deviation decreases monotonically, bisect the dimensionless time logarithmically
+        let target = 1. - self.degree;
+        let (mut low, mut high) = (1e-12f64, 1.);
+        while relative_deviation(high) > target {
+            high *= 2.;
+        }
+        for _ in 0..100 {
+            let middle = (low * high).sqrt();
+            if relative_deviation(middle) > target {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        let width = self.shape.width();
+        Some(high * width * width / self.diffusivity)
+    }
+
+    /// Channel length needed for the mixing degree at the mean flow velocity, e.g., the
+    /// centerline length of a serpentine mixer
+    pub fn length(&self) -> Option<f64> {
+        Some(self.time()? * self.flow.abs() / self.shape.area())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::channel::RectangularShape;
+
+    #[test]
+    fn serpentine_length() {
+        let spec = MixingSpec {
+            shape: Shape::Rectangular(RectangularShape {
+                width: 100e-6,
+                height: 50e-6,
+            }),
+            flow: 1e-11,
+            diffusivity: 1e-9,
+            degree: 0.95,
+        };
+        // Only the first mode remains: 1 - m = 2√2 / π exp(-π² τ)
+        let tau = (2. * 2f64.sqrt() / PI / 0.05).ln() / (PI * PI);
+        let length = spec.length().unwrap();
+        let expected = tau * 1e-8 / 1e-9 * 1e-11 / 5e-9;
+        assert!((length / expected - 1.).abs() < 1e-6, "{length} {expected}");
+        assert!((mixing_degree(100e-6, 1e-9, spec.time().unwrap()) - 0.95).abs() < 1e-9);
+
+        assert_eq!(mixing_degree(1., 1., 0.), 0.);
+        let faster = MixingSpec {
+            degree: 0.5,
+            ..spec
+        };
+        assert!(faster.length().unwrap() < length);
+        assert_eq!(MixingSpec { degree: 1., ..spec }.length(), None);
+    }
+}
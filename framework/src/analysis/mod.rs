@@ -0,0 +1,9 @@
+//! Post-processing of networks and flow solutions
+
+pub mod burst;
+pub mod mixing;
+pub mod regime;
+pub mod sensitivity;
+pub mod synthesis;
+pub mod tolerance;
+pub mod volume;
@@ -0,0 +1,262 @@
+use crate::{
+    base::network::Network,
+    simulation::{fluid::Fluid, resistance::wall_shear_rate, solver::FlowSolution},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Thresholds of the flow regime assumed by the 1D model
+pub struct RegimeLimits {
+    /// Largest Reynolds number considered laminar
+    pub max_reynolds: f64,
+
+    /// Largest hydrodynamic entrance length, as fraction of the channel length, for which the
+    /// flow is considered fully developed
+    pub max_entrance_fraction: f64,
+
+    /// Optional upper bound of the Péclet number, e.g., to flag diffusion-limited mixing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_peclet: Option<f64>,
+
+    /// Optional upper bound of the capillary number, e.g., for droplet break-up regimes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_capillary: Option<f64>,
+}
+
+impl Default for RegimeLimits {
+    fn default() -> Self {
+        RegimeLimits {
+            max_reynolds: 2000.,
+            max_entrance_fraction: 0.1,
+            max_peclet: None,
+            max_capillary: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Dimensionless numbers of the flow in a channel
+pub struct ChannelRegime {
+    /// Id of the channel
+    pub channel: usize,
+
+    /// Mean flow velocity
+    pub velocity: f64,
+
+    /// Reynolds number based on the hydraulic diameter
+    pub reynolds: f64,
+
+    /// Péclet number, if the fluid's diffusivity is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peclet: Option<f64>,
+
+    /// Capillary number, if the fluid's surface tension is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capillary: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Way in which the flow in a channel leaves the assumed regime
+pub enum RegimeIssue {
+    /// Reynolds number above the laminar limit
+    Turbulent {
+        /// Reynolds number of the channel
+        reynolds: f64,
+    },
+
+    /// The entrance length is a significant part of the channel
+    DevelopingFlow {
+        /// Estimated hydrodynamic entrance length, 0.06 Re D_h
+        entrance_length: f64,
+
+        /// Length of the channel
+        length: f64,
+    },
+
+    /// Péclet number above the limit
+    HighPeclet {
+        /// Péclet number of the channel
+        peclet: f64,
+    },
+
+    /// Capillary number above the limit
+    HighCapillary {
+        /// Capillary number of the channel
+        capillary: f64,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Warning about a channel leaving the assumed regime
+pub struct RegimeWarning {
+    /// Id of the channel
+    pub channel: usize,
+
+    /// Detected issue
+    pub issue: RegimeIssue,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Flow regime analysis of a network
+pub struct RegimeReport {
+    /// Dimensionless numbers per channel with a solved flow
+    pub channels: Vec<ChannelRegime>,
+
+    /// Channels violating the limits
+    pub warnings: Vec<RegimeWarning>,
+}
+
+/// Computes Reynolds, Péclet, and capillary numbers of every channel with a solved flow and
+/// checks them against the limits
+pub fn regime_report(
+    network: &Network,
+    fluid: &Fluid,
+    solution: &FlowSolution,
+    limits: &RegimeLimits,
+) -> RegimeReport {
+    let mut report = RegimeReport {
+        channels: Vec::new(),
+        warnings: Vec::new(),
+    };
+    for channel in &network.channels {
+        let flow = match solution.flows.get(&channel.id) {
+            Some(flow) => flow.abs(),
+            None => continue,
+        };
+        let diameter = channel.shape.hydraulic_diameter();
+        let velocity = flow / channel.shape.area();
+        let viscosity = fluid.apparent_viscosity(wall_shear_rate(&channel.shape, flow));
+        let regime = ChannelRegime {
+            channel: channel.id,
+            velocity,
+            reynolds: fluid.density * velocity * diameter / viscosity,
+            peclet: fluid.diffusivity.map(|d| velocity * diameter / d),
+            capillary: fluid.surface_tension.map(|s| viscosity * velocity / s),
+        };
+
+        let mut warn = |issue| {
+            report.warnings.push(RegimeWarning {
+                channel: channel.id,
+                issue,
+            })
+        };
+        if regime.reynolds > limits.max_reynolds {
+            warn(RegimeIssue::Turbulent {
+                reynolds: regime.reynolds,
+            });
+        }
+        if let Some(length) = channel.length() {
+            let entrance_length = 0.06 * regime.reynolds * diameter;
+            if entrance_length > limits.max_entrance_fraction * length {
+                warn(RegimeIssue::DevelopingFlow {
+                    entrance_length,
+                    length,
+                });
+            }
+        }
+        if let (Some(peclet), Some(max)) = (regime.peclet, limits.max_peclet) {
+            if peclet > max {
+                warn(RegimeIssue::HighPeclet { peclet });
+            }
+        }
+        if let (Some(capillary), Some(max)) = (regime.capillary, limits.max_capillary) {
+            if capillary > max {
+                warn(RegimeIssue::HighCapillary { capillary });
+            }
+        }
+        report.channels.push(regime);
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{Channel, RectangularShape, Shape},
+            network::{Node, NodeId},
+        },
+        simulation::fluid::Rheology,
+    };
+
+    #[test]
+    fn dimensionless_numbers() {
+        let channel = |id| Channel {
+            id,
+            node_a: NodeId(0),
+            node_b: NodeId(1),
+            shape: Shape::Rectangular(RectangularShape {
+                width: 100e-6,
+                height: 50e-6,
+            }),
+            path: None,
+            length: Some(10e-3),
+            layer: 0,
+            metadata: Default::default(),
+        };
+        let network = Network {
+            nodes: vec![Node::new(NodeId(0)), Node::new(NodeId(1))],
+            channels: vec![channel(0), channel(1)],
+            ..Default::default()
+        };
+        // 1 µl/s against the channel direction, channel 1 has no solved flow
+        let solution = FlowSolution {
+            pressures: Default::default(),
+            flows: [(0, -1e-9)].into(),
+        };
+        let fluid = Fluid {
+            diffusivity: Some(1e-9),
+            ..Fluid::water()
+        };
+        let diameter = network.channels[0].shape.hydraulic_diameter();
+        let report = regime_report(&network, &fluid, &solution, &RegimeLimits::default());
+        assert_eq!(report.channels.len(), 1);
+        let regime = report.channels[0];
+        assert!((regime.velocity - 0.2).abs() < 1e-12);
+        assert!((regime.reynolds / (998. * 0.2 * diameter / 1e-3) - 1.).abs() < 1e-12);
+        assert!((regime.peclet.unwrap() / (0.2 * diameter / 1e-9) - 1.).abs() < 1e-12);
+        assert!((regime.capillary.unwrap() / (1e-3 * 0.2 / 0.0728) - 1.).abs() < 1e-12);
+        assert!(report.warnings.is_empty());
+
+        let limits = RegimeLimits {
+            max_reynolds: 10.,
+            max_entrance_fraction: 1e-3,
+            max_peclet: Some(1e3),
+            max_capillary: Some(1e-3),
+        };
+        let issues: Vec<_> = (regime_report(&network, &fluid, &solution, &limits).warnings)
+            .iter()
+            .map(|w| w.issue)
+            .collect();
+        assert!(matches!(
+            issues[..],
+            [
+                RegimeIssue::Turbulent { .. },
+                RegimeIssue::DevelopingFlow { length: 10e-3, .. },
+                RegimeIssue::HighPeclet { .. },
+                RegimeIssue::HighCapillary { .. },
+            ]
+        ));
+
+        // Both Reynolds and capillary number use the apparent viscosity, so their product
+        // doesn't depend on it
+        let fluid = Fluid {
+            rheology: Rheology::PowerLaw {
+                consistency: 1e-2,
+                flow_index: 0.5,
+            },
+            ..fluid
+        };
+        let thinned = regime_report(&network, &fluid, &solution, &limits).channels[0];
+        assert!(thinned.reynolds > 2. * regime.reynolds);
+        let product = 998. * 0.2 * 0.2 * diameter / 0.0728;
+        assert!((thinned.reynolds * thinned.capillary.unwrap() / product - 1.).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::{
+    base::{
+        channel::Shape,
+        network::{Network, NodeId},
+    },
+    parallel,
+    simulation::{
+        fluid::Fluid,
+        solver::{solve, Boundary, FlowSolution},
+        SimulationError,
+    },
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(
+    Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+/// Cross-section dimension of a channel
+pub enum ChannelDimension {
+    /// Width of a rectangular channel
+    Width,
+
+    /// Height of a rectangular channel
+    Height,
+
+    /// Radius of a cylindrical channel
+    Radius,
+}
+
+impl ChannelDimension {
+    /// Dimensions that apply to the shape
+    pub fn of(shape: &Shape) -> &'static [ChannelDimension] {
+        match shape {
+            Shape::Rectangular(_) => &[ChannelDimension::Width, ChannelDimension::Height],
+            Shape::Cylindrical(_) => &[ChannelDimension::Radius],
+        }
+    }
+
+    /// Value of the dimension, if it applies to the shape
+    pub fn value(&self, shape: &Shape) -> Option<f64> {
+        match (self, shape) {
+            (ChannelDimension::Width, Shape::Rectangular(s)) => Some(s.width),
+            (ChannelDimension::Height, Shape::Rectangular(s)) => Some(s.height),
+            (ChannelDimension::Radius, Shape::Cylindrical(s)) => Some(s.radius),
+            _ => None,
+        }
+    }
+
+    /// Shape with the dimension set to the value, if it applies to the shape
+    pub fn with_value(&self, shape: &Shape, value: f64) -> Option<Shape> {
+        let mut shape = *shape;
+        match (self, &mut shape) {
+            (ChannelDimension::Width, Shape::Rectangular(s)) => s.width = value,
+            (ChannelDimension::Height, Shape::Rectangular(s)) => s.height = value,
+            (ChannelDimension::Radius, Shape::Cylindrical(s)) => s.radius = value,
+            _ => return None,
+        }
+        Some(shape)
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Derivatives of the solution with respect to one channel dimension
+pub struct DimensionSensitivity {
+    /// Id of the channel
+    pub channel: usize,
+
+    /// Perturbed dimension
+    pub dimension: ChannelDimension,
+
+    /// Derivative of every node pressure
+    pub pressures: BTreeMap<NodeId, f64>,
+
+    /// Derivative of every channel flow rate
+    pub flows: BTreeMap<usize, f64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Linearized deviation of a result under the fabrication tolerance
+pub struct Spread {
+    /// Value of the nominal design
+    pub nominal: f64,
+
+    /// Bound if all dimensions deviate by the tolerance in the worst direction
+    pub worst_case: f64,
+
+    /// Root sum of squares, the expected deviation for independent errors
+    pub rss: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Robustness of a flow solution to manufacturing variation
+pub struct SensitivityReport {
+    /// Nominal solution
+    pub nominal: FlowSolution,
+
+    /// Derivatives per channel and dimension
+    pub sensitivities: Vec<DimensionSensitivity>,
+
+    /// Spread of every node pressure
+    pub pressures: BTreeMap<NodeId, Spread>,
+
+    /// Spread of every channel flow rate
+    pub flows: BTreeMap<usize, Spread>,
+}
+
+/// Perturbs every cross-section dimension by central finite differences and combines the
+/// derivatives with the absolute fabrication tolerance (± tolerance on each dimension)
+pub fn sensitivity_report(
+    network: &Network,
+    fluid: &Fluid,
+    boundaries: &[Boundary],
+    tolerance: f64,
+) -> Result<SensitivityReport, SimulationError> {
+    let nominal = solve(network, fluid, boundaries)?;
+    let perturbations: Vec<(usize, ChannelDimension)> = network
+        .channels
+        .iter()
+        .enumerate()
+        .flat_map(|(i, c)| ChannelDimension::of(&c.shape).iter().map(move |d| (i, *d)))
+        .collect();
+
+    let sensitivities = parallel::map(&perturbations, |(index, dimension)| {
+        let shape = network.channels[*index].shape;
+        let value = dimension.value(&shape).unwrap();
+        let step = 1e-4 * value;
+        let solve_with = |value| {
+            let mut network = network.clone();
+            network.channels[*index].shape = dimension.with_value(&shape, value).unwrap();
+            solve(&network, fluid, boundaries)
+        };
+        let (upper, lower) = (solve_with(value + step)?, solve_with(value - step)?);
+        let derivative = |a: f64, b: f64| (a - b) / (2. * step);
+        Ok(DimensionSensitivity {
+            channel: network.channels[*index].id,
+            dimension: *dimension,
+            pressures: upper
+                .pressures
+                .iter()
+                .map(|(id, p)| (*id, derivative(*p, lower.pressures[id])))
+                .collect(),
+            flows: upper
+                .flows
+                .iter()
+                .map(|(id, q)| (*id, derivative(*q, lower.flows[id])))
+                .collect(),
+        })
+    })
+    .into_iter()
+    .collect::<Result<Vec<_>, SimulationError>>()?;
+
+    let spread = |nominal: f64, derivatives: Vec<f64>| Spread {
+        nominal,
+        worst_case: tolerance * derivatives.iter().map(|d| d.abs()).sum::<f64>(),
+        rss: tolerance * derivatives.iter().map(|d| d * d).sum::<f64>().sqrt(),
+    };
+    let pressures = nominal
+        .pressures
+        .iter()
+        .map(|(id, p)| {
+            let derivatives = sensitivities.iter().map(|s| s.pressures[id]).collect();
+            (*id, spread(*p, derivatives))
+        })
+        .collect();
+    let flows = nominal
+        .flows
+        .iter()
+        .map(|(id, q)| {
+            let derivatives = sensitivities.iter().map(|s| s.flows[id]).collect();
+            (*id, spread(*q, derivatives))
+        })
+        .collect();
+
+    Ok(SensitivityReport {
+        nominal,
+        sensitivities,
+        pressures,
+        flows,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{Channel, RectangularShape},
+            network::Node,
+        },
+        simulation::solver::BoundaryCondition,
+    };
+
+    #[test]
+    fn single_channel() {
+        let (width, height, length) = (100e-6, 50e-6, 10e-3);
+        let network = Network {
+            nodes: vec![Node::new(NodeId(0)), Node::new(NodeId(1))],
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(1),
+                shape: Shape::Rectangular(RectangularShape { width, height }),
+                path: None,
+                length: Some(length),
+                layer: 0,
+                metadata: Default::default(),
+            }],
+            ..Default::default()
+        };
+        let boundaries = [(0, 1e4), (1, 0.)].map(|(node, p)| Boundary {
+            node: NodeId(node),
+            condition: BoundaryCondition::Pressure(p),
+        });
+        let fluid = Fluid::water();
+        let report = sensitivity_report(&network, &fluid, &boundaries, 1e-6).unwrap();
+
+        // R = 12 μ L / (w h³ - 0.63 h⁴), so dR/dw = -12 μ L h³ / (w h³ - 0.63 h⁴)²
+        let denominator = width * height.powi(3) - 0.63 * height.powi(4);
+        let resistance = 12. * fluid.viscosity * length / denominator;
+        let dr_dw = -12. * fluid.viscosity * length * height.powi(3) / denominator.powi(2);
+        // Q = Δp / R, so dQ/dw = -Δp / R² dR/dw
+        let dq_dw = -1e4 / resistance.powi(2) * dr_dw;
+        let sensitivity = &report.sensitivities[0];
+        assert_eq!(
+            (sensitivity.channel, sensitivity.dimension),
+            (0, ChannelDimension::Width)
+        );
+        assert!((sensitivity.flows[&0] / dq_dw - 1.).abs() < 1e-6);
+        assert_eq!(sensitivity.pressures[&NodeId(0)], 0.);
+
+        let dq_dh = report.sensitivities[1].flows[&0];
+        let spread = report.flows[&0];
+        assert!((spread.nominal - 1e4 / resistance).abs() < 1e-9 * spread.nominal);
+        assert!((spread.worst_case / (1e-6 * (dq_dw.abs() + dq_dh.abs())) - 1.).abs() < 1e-6);
+        assert!(spread.rss < spread.worst_case);
+    }
+}
@@ -0,0 +1,397 @@
+//! Inverse design: channel dimensions or lengths are solved for such that given boundary
+//! conditions produce target flow rates at the outlets. The search is a Levenberg–Marquardt
+//! iteration on the logarithms of the variables, which keeps them positive and scales
+//! resistances of very different magnitude alike.
+
+use super::sensitivity::ChannelDimension;
+use crate::{
+    base::network::{Network, NodeId},
+    simulation::{
+        fluid::Fluid,
+        solver::{gauss, solve, Boundary, FlowSolution},
+        SimulationError,
+    },
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Channel property adjusted by the synthesis
+pub enum SizingParameter {
+    /// A cross-section dimension
+    Dimension(ChannelDimension),
+
+    /// The explicit length, which takes precedence over the path length
+    Length,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Channel property to solve for, within bounds
+pub struct SizingVariable {
+    /// Id of the channel
+    pub channel: usize,
+
+    pub parameter: SizingParameter,
+
+    /// Lower bound, positive
+    pub min: f64,
+
+    /// Upper bound
+    pub max: f64,
+}
+
+impl SizingVariable {
+    /// Value at the logarithmic search coordinate, exactly within the bounds
+    fn value(&self, x: f64) -> f64 {
+        x.exp().clamp(self.min, self.max)
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Flow rate that should leave the network at a node, usually a pressure outlet
+pub struct FlowTarget {
+    pub node: NodeId,
+    pub flow: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Control of the synthesis iteration
+pub struct SynthesisSettings {
+    /// Maximum number of accepted steps
+    pub max_iterations: usize,
+
+    /// Largest deviation of a flow from its target, relative to the target, to stop at
+    pub tolerance: f64,
+}
+
+impl Default for SynthesisSettings {
+    fn default() -> Self {
+        SynthesisSettings {
+            max_iterations: 100,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Boundary conditions, targets, and variables of a synthesis
+pub struct SynthesisProblem {
+    pub boundaries: Vec<Boundary>,
+    pub targets: Vec<FlowTarget>,
+    pub variables: Vec<SizingVariable>,
+
+    #[serde(default)]
+    pub settings: SynthesisSettings,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Outcome of a synthesis; the values are also written back into the network
+pub struct SynthesisResult {
+    /// Variable values in order of the variables
+    pub values: Vec<f64>,
+
+    /// Largest deviation of a flow from its target, relative to the target
+    pub residual: f64,
+
+    /// Whether the residual is within the tolerance; otherwise the values are the best found,
+    /// e.g., because the targets are out of reach within the bounds
+    pub converged: bool,
+
+    pub iterations: usize,
+
+    /// Flows with the final values
+    pub solution: FlowSolution,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Reasons a synthesis can't run
+pub enum SynthesisError {
+    /// No channel with the id
+    UnknownChannel(usize),
+
+    /// The dimension doesn't apply to the shape of the channel
+    InapplicableDimension {
+        channel: usize,
+        dimension: ChannelDimension,
+    },
+
+    /// The bounds of the channel's variable are empty or not positive
+    InvalidBounds(usize),
+
+    Simulation(SimulationError),
+}
+
+impl std::fmt::Display for SynthesisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SynthesisError::UnknownChannel(id) => write!(f, "channel {id} does not exist"),
+            SynthesisError::InapplicableDimension { channel, dimension } => {
+                write!(f, "channel {channel} has no {dimension:?}")
+            }
+            SynthesisError::InvalidBounds(id) => {
+                write!(f, "bounds of channel {id} must be positive and ordered")
+            }
+            SynthesisError::Simulation(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for SynthesisError {}
+
+impl From<SimulationError> for SynthesisError {
+    fn from(error: SimulationError) -> Self {
+        SynthesisError::Simulation(error)
+    }
+}
+
+/// Flow leaving the network at the node through its boundary
+pub fn outflow(network: &Network, solution: &FlowSolution, node: NodeId) -> f64 {
+    network
+        .channels
+        .iter()
+        .map(|c| {
+            let q = solution.flows.get(&c.id).copied().unwrap_or(0.);
+            match (c.node_a == node, c.node_b == node) {
+                (false, true) => q,
+                (true, false) => -q,
+                _ => 0.,
+            }
+        })
+        .sum()
+}
+
+/// Sets the variable in the network; the channel index must be valid
+fn assign(network: &mut Network, index: usize, parameter: &SizingParameter, value: f64) {
+    let channel = &mut network.channels[index];
+    match parameter {
+        SizingParameter::Dimension(dimension) => {
+            channel.shape = dimension.with_value(&channel.shape, value).unwrap();
+        }
+        SizingParameter::Length => channel.length = Some(value),
+    }
+}
+
+/// Solves for the variables such that the outlet flows meet the targets, writing the best
+/// values found into the channels' shapes and lengths
+pub fn synthesize(
+    network: &mut Network,
+    fluid: &Fluid,
+    problem: &SynthesisProblem,
+) -> Result<SynthesisResult, SynthesisError> {
+    let mut indices = Vec::with_capacity(problem.variables.len());
+    let mut x = Vec::with_capacity(problem.variables.len());
+    for variable in &problem.variables {
+        let index = network
+            .channels
+            .iter()
+            .position(|c| c.id == variable.channel)
+            .ok_or(SynthesisError::UnknownChannel(variable.channel))?;
+        if !(variable.min > 0. && variable.min <= variable.max) {
+            return Err(SynthesisError::InvalidBounds(variable.channel));
+        }
+        let channel = &network.channels[index];
+        let value =
+            match variable.parameter {
+                SizingParameter::Dimension(dimension) => dimension.value(&channel.shape).ok_or(
+                    SynthesisError::InapplicableDimension {
+                        channel: channel.id,
+                        dimension,
+                    },
+                )?,
+                SizingParameter::Length => channel
+                    .length()
+                    .ok_or(SimulationError::MissingLength(channel.id))?,
+            };
+        indices.push(index);
+        x.push(value.clamp(variable.min, variable.max).ln());
+    }
+
+    let clamp = |x: Vec<f64>| -> Vec<f64> {
+        x.into_iter()
+            .zip(&problem.variables)
+            .map(|(x, v)| x.clamp(v.min.ln(), v.max.ln()))
+            .collect()
+    };
+    let evaluate = |network: &mut Network, x: &[f64]| {
+        for ((index, variable), x) in indices.iter().zip(&problem.variables).zip(x) {
+            assign(network, *index, &variable.parameter, variable.value(*x));
+        }
+        let solution = solve(network, fluid, &problem.boundaries)?;
+        let residuals: Vec<f64> = problem
+            .targets
+            .iter()
+            .map(|t| {
+                (outflow(network, &solution, t.node) - t.flow) / t.flow.abs().max(f64::MIN_POSITIVE)
+            })
+            .collect();
+        Ok::<_, SimulationError>((solution, residuals))
+    };
+    let cost = |r: &[f64]| r.iter().map(|r| r * r).sum::<f64>();
+    let largest = |r: &[f64]| r.iter().fold(0f64, |m, r| m.max(r.abs()));
+
+    let (mut solution, mut residuals) = evaluate(network, &x)?;
+    let mut damping = 1e-3;
+    let mut iterations = 0;
+    while iterations < problem.settings.max_iterations
+        && largest(&residuals) > problem.settings.tolerance
+    {
+        // Forward-difference Jacobian of the residuals in log space
+        let step = 1e-6;
+        let mut jacobian = vec![vec![0.; x.len()]; residuals.len()];
+        for j in 0..x.len() {
+            let mut shifted = x.clone();
+            shifted[j] += step;
+            let (_, perturbed) = evaluate(network, &shifted)?;
+            for (i, row) in jacobian.iter_mut().enumerate() {
+                row[j] = (perturbed[i] - residuals[i]) / step;
+            }
+        }
+        let n = x.len();
+        let mut normal = vec![vec![0.; n]; n];
+        let mut gradient = vec![0.; n];
+        for (row, r) in jacobian.iter().zip(&residuals) {
+            for a in 0..n {
+                gradient[a] -= row[a] * r;
+                for b in 0..n {
+                    normal[a][b] += row[a] * row[b];
+                }
+            }
+        }
+
+        let mut improved = None;
+        while damping < 1e12 {
+            let mut damped = normal.clone();
+            for (a, row) in damped.iter_mut().enumerate() {
+                row[a] += damping * (row[a] + 1e-12);
+            }
+            if let Some(delta) = gauss(damped, gradient.clone()) {
+                let candidate = clamp(x.iter().zip(&delta).map(|(x, d)| x + d).collect());
+                let (s, r) = evaluate(network, &candidate)?;
+                if cost(&r) < cost(&residuals) {
+                    improved = Some((candidate, s, r));
+                    damping = (damping / 10.).max(1e-12);
+                    break;
+                }
+            }
+            damping *= 10.;
+        }
+        let Some((candidate, s, r)) = improved else {
+            // No step within the bounds reduces the deviation
+            break;
+        };
+        (x, solution, residuals) = (candidate, s, r);
+        iterations += 1;
+    }
+
+    // Leave the network at the final values, not at the last probe
+    let values: Vec<f64> = problem
+        .variables
+        .iter()
+        .zip(&x)
+        .map(|(v, x)| v.value(*x))
+        .collect();
+    for ((index, variable), value) in indices.iter().zip(&problem.variables).zip(&values) {
+        assign(network, *index, &variable.parameter, *value);
+    }
+    let residual = largest(&residuals);
+    Ok(SynthesisResult {
+        values,
+        residual,
+        converged: residual <= problem.settings.tolerance,
+        iterations,
+        solution,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{Channel, RectangularShape, Shape},
+            network::Node,
+        },
+        simulation::solver::BoundaryCondition,
+    };
+
+    #[test]
+    fn split_ratio() {
+        // Inlet 0 feeds junction 1, which splits towards the outlets 2 and 3
+        let channel = |id: usize, a: usize, b: usize| Channel {
+            id,
+            node_a: NodeId(a),
+            node_b: NodeId(b),
+            shape: Shape::Rectangular(RectangularShape {
+                width: 100e-6,
+                height: 50e-6,
+            }),
+            path: None,
+            length: Some(0.01),
+            layer: 0,
+            metadata: Default::default(),
+        };
+        let mut network = Network {
+            nodes: (0..4).map(|i| Node::new(NodeId(i))).collect(),
+            channels: vec![channel(0, 0, 1), channel(1, 1, 2), channel(2, 1, 3)],
+            ..Default::default()
+        };
+        let pressure = |node, p| Boundary {
+            node: NodeId(node),
+            condition: BoundaryCondition::Pressure(p),
+        };
+        let boundaries = vec![pressure(0, 1000.), pressure(2, 0.), pressure(3, 0.)];
+        let fluid = Fluid::water();
+        let nominal = solve(&network, &fluid, &boundaries).unwrap();
+        let total = outflow(&network, &nominal, NodeId(2)) * 2.;
+        assert!((outflow(&network, &nominal, NodeId(2)) - nominal.flows[&1]).abs() < 1e-20);
+
+        // A 1:3 split of the same total flow
+        let width = |channel| SizingVariable {
+            channel,
+            parameter: SizingParameter::Dimension(ChannelDimension::Width),
+            min: 20e-6,
+            max: 500e-6,
+        };
+        let mut problem = SynthesisProblem {
+            boundaries,
+            targets: vec![
+                FlowTarget {
+                    node: NodeId(2),
+                    flow: total / 4.,
+                },
+                FlowTarget {
+                    node: NodeId(3),
+                    flow: total * 3. / 4.,
+                },
+            ],
+            variables: vec![width(1), width(2)],
+            settings: Default::default(),
+        };
+        let result = synthesize(&mut network, &fluid, &problem).unwrap();
+        assert!(result.converged, "{result:?}");
+        assert!(result.values[0] < 100e-6 && result.values[1] > 100e-6);
+        let check = solve(&network, &fluid, &problem.boundaries).unwrap();
+        assert!((check.flows[&2] / check.flows[&1] - 3.).abs() < 1e-4);
+
+        // Out of reach within the bounds
+        problem.targets[1].flow *= 1e3;
+        let result = synthesize(&mut network, &fluid, &problem).unwrap();
+        assert!(!result.converged);
+        assert_eq!(result.values[1], 500e-6);
+
+        problem.variables[0].parameter = SizingParameter::Dimension(ChannelDimension::Radius);
+        assert_eq!(
+            synthesize(&mut network, &fluid, &problem),
+            Err(SynthesisError::InapplicableDimension {
+                channel: 1,
+                dimension: ChannelDimension::Radius
+            })
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use super::sensitivity::ChannelDimension;
+use crate::{
+    base::network::{Network, NodeId},
+    parallel,
+    random::{Rng, SplitMix},
+    simulation::{
+        fluid::Fluid,
+        solver::{solve, Boundary},
+        SimulationError,
+    },
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Distribution of the deviation from the nominal dimension
+pub enum Distribution {
+    /// Normal distribution around the nominal value, optionally truncated to [min, max]. Draws
+    /// outside are rejected; bounds far in the tail are clamped to after
+    /// [MAX_REJECTIONS](Distribution::MAX_REJECTIONS) rejections in a row.
+    Normal {
+        std_dev: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        bounds: Option<[f64; 2]>,
+    },
+
+    /// Uniform distribution between the deviations min and max
+    Uniform { min: f64, max: f64 },
+}
+
+impl Distribution {
+    pub const MAX_REJECTIONS: usize = 100;
+
+    /// Whether the parameters are finite, the standard deviation isn't negative, and the
+    /// bounds aren't inverted
+    pub fn is_valid(&self) -> bool {
+        let ordered = |min: f64, max: f64| min.is_finite() && max.is_finite() && min <= max;
+        match self {
+            Distribution::Normal { std_dev, bounds } => {
+                std_dev.is_finite()
+                    && *std_dev >= 0.
+                    && bounds.is_none_or(|[min, max]| ordered(min, max))
+            }
+            Distribution::Uniform { min, max } => ordered(*min, *max),
+        }
+    }
+
+    fn sample(&self, rng: &mut SplitMix) -> f64 {
+        match self {
+            Distribution::Normal { std_dev, bounds } => {
+                let mut deviation = std_dev * rng.normal();
+                let Some([min, max]) = bounds else {
+                    return deviation;
+                };
+                for _ in 0..Self::MAX_REJECTIONS {
+                    if (*min..=*max).contains(&deviation) {
+                        return deviation;
+                    }
+                    deviation = std_dev * rng.normal();
+                }
+                deviation.clamp(*min, *max)
+            }
+            Distribution::Uniform { min, max } => min + rng.next_f64() * (max - min),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Fabrication tolerance of one channel dimension
+pub struct DimensionTolerance {
+    /// Id of the channel
+    pub channel: usize,
+
+    /// Varied dimension
+    pub dimension: ChannelDimension,
+
+    /// Deviation from the nominal value
+    pub distribution: Distribution,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Sample statistics of a result
+pub struct Statistics {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Statistics {
+    fn of(values: &[f64]) -> Statistics {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = if values.len() > 1 {
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.)
+        } else {
+            0.
+        };
+        Statistics {
+            mean,
+            std_dev: variance.sqrt(),
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Outcome of a Monte Carlo tolerance analysis
+pub struct ToleranceReport {
+    /// Number of solved instantiations
+    pub samples: usize,
+
+    /// Statistics of every node pressure
+    pub pressures: BTreeMap<NodeId, Statistics>,
+
+    /// Statistics of every channel flow rate
+    pub flows: BTreeMap<usize, Statistics>,
+}
+
+/// Solves `samples` instantiations of the network with randomly deviating dimensions. Every
+/// sample draws from its own stream of the seed, so results don't depend on the thread count.
+/// Fails with [SimulationError::InvalidTolerance] if a distribution isn't valid.
+pub fn monte_carlo(
+    network: &Network,
+    fluid: &Fluid,
+    boundaries: &[Boundary],
+    tolerances: &[DimensionTolerance],
+    samples: usize,
+    rng: &Rng,
+) -> Result<ToleranceReport, SimulationError> {
+    if let Some(invalid) = tolerances.iter().find(|t| !t.distribution.is_valid()) {
+        return Err(SimulationError::InvalidTolerance(invalid.channel));
+    }
+    let indices: Vec<u64> = (0..samples as u64).collect();
+    let solutions = parallel::map(&indices, |index| {
+        let mut rng = rng.stream(*index);
+        let mut network = network.clone();
+        for tolerance in tolerances {
+            if let Some(channel) = network
+                .channels
+                .iter_mut()
+                .find(|c| c.id == tolerance.channel)
+            {
+                if let Some(value) = tolerance.dimension.value(&channel.shape) {
+                    let value = value + tolerance.distribution.sample(&mut rng);
+                    channel.shape = tolerance
+                        .dimension
+                        .with_value(&channel.shape, value)
+                        .unwrap();
+                }
+            }
+        }
+        solve(&network, fluid, boundaries)
+    })
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()?;
+
+    let mut pressures: BTreeMap<NodeId, Vec<f64>> = BTreeMap::new();
+    let mut flows: BTreeMap<usize, Vec<f64>> = BTreeMap::new();
+    for solution in &solutions {
+        for (id, p) in &solution.pressures {
+            pressures.entry(*id).or_default().push(*p);
+        }
+        for (id, q) in &solution.flows {
+            flows.entry(*id).or_default().push(*q);
+        }
+    }
+    Ok(ToleranceReport {
+        samples: solutions.len(),
+        pressures: pressures
+            .into_iter()
+            .map(|(id, v)| (id, Statistics::of(&v)))
+            .collect(),
+        flows: flows
+            .into_iter()
+            .map(|(id, v)| (id, Statistics::of(&v)))
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{Channel, CylindricalShape, Shape},
+            network::Node,
+        },
+        simulation::solver::BoundaryCondition,
+    };
+
+    #[test]
+    fn radius_scatter() {
+        let network = Network {
+            nodes: (0..2).map(|i| Node::new(NodeId(i))).collect(),
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(1),
+                shape: Shape::Cylindrical(CylindricalShape { radius: 1e-4 }),
+                path: None,
+                length: Some(0.01),
+                layer: 0,
+                metadata: Default::default(),
+            }],
+            ..Default::default()
+        };
+        let boundaries = [
+            Boundary {
+                node: NodeId(0),
+                condition: BoundaryCondition::Pressure(1000.),
+            },
+            Boundary {
+                node: NodeId(1),
+                condition: BoundaryCondition::Pressure(0.),
+            },
+        ];
+        let tolerances = [DimensionTolerance {
+            channel: 0,
+            dimension: ChannelDimension::Radius,
+            distribution: Distribution::Normal {
+                std_dev: 2e-6,
+                bounds: Some([-5e-6, 5e-6]),
+            },
+        }];
+        let fluid = Fluid::water();
+        let report = monte_carlo(
+            &network,
+            &fluid,
+            &boundaries,
+            &tolerances,
+            500,
+            &Rng::new(1),
+        )
+        .unwrap();
+        assert_eq!(report.samples, 500);
+
+        // Q ∝ r⁴: relative spread ≈ 4 σ / r, bounded by the truncation
+        let nominal = solve(&network, &fluid, &boundaries).unwrap().flows[&0];
+        let flow = report.flows[&0];
+        assert!((flow.std_dev / nominal - 0.08).abs() < 0.01);
+        assert!(flow.max <= nominal * (1.05f64).powi(4));
+        assert!(flow.min >= nominal * (0.95f64).powi(4));
+        assert_eq!(report.pressures[&NodeId(0)].std_dev, 0.);
+
+        let again = monte_carlo(
+            &network,
+            &fluid,
+            &boundaries,
+            &tolerances,
+            500,
+            &Rng::new(1),
+        )
+        .unwrap();
+        assert_eq!(report, again);
+
+        // Bounds far in the tail end up clamped, inverted bounds are rejected
+        let truncated = |bounds| Distribution::Normal {
+            std_dev: 2e-6,
+            bounds: Some(bounds),
+        };
+        let mut rng = Rng::new(1).stream(0);
+        let tail = truncated([20e-6, 30e-6]);
+        assert!((0..10)
+            .map(|_| tail.sample(&mut rng))
+            .all(|d| (20e-6..=30e-6).contains(&d)));
+        let inverted = [DimensionTolerance {
+            distribution: truncated([5e-6, -5e-6]),
+            ..tolerances[0]
+        }];
+        assert_eq!(
+            monte_carlo(&network, &fluid, &boundaries, &inverted, 10, &Rng::new(1)),
+            Err(SimulationError::InvalidTolerance(0))
+        );
+    }
+}
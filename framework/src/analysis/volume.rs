@@ -0,0 +1,268 @@
+use crate::{
+    base::network::{Network, NodeId},
+    simulation::{solver::FlowSolution, SimulationError},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Volume and transport figures of a single channel
+pub struct ChannelVolume {
+    /// Id of the channel
+    pub channel: usize,
+
+    /// Internal volume, cross-section area times length
+    pub volume: f64,
+
+    /// Flow rate from the solution, if one was given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flow: Option<f64>,
+
+    /// Mean residence time, volume over absolute flow rate; not set for stagnant channels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub residence_time: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Connected group of stagnant channels
+pub struct DeadBranch {
+    /// Ids of the channels in the branch
+    pub channels: Vec<usize>,
+
+    /// Total volume of the branch
+    pub volume: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Volume of the channels connecting interface nodes of a module
+pub struct ModuleVolume {
+    /// Id of the module
+    pub module: usize,
+
+    /// Ids of the channels with both nodes in the module's interface
+    pub channels: Vec<usize>,
+
+    /// Total volume of these channels
+    pub volume: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Volume analysis of a network
+pub struct VolumeReport {
+    /// Figures per channel
+    pub channels: Vec<ChannelVolume>,
+
+    /// Volume of all channels
+    pub total_volume: f64,
+
+    /// Volume of all stagnant channels
+    pub dead_volume: f64,
+
+    /// Stagnant channels grouped into connected branches
+    pub dead_branches: Vec<DeadBranch>,
+
+    /// Volume aggregated per module
+    pub modules: Vec<ModuleVolume>,
+}
+
+/// Computes channel volumes and, given a flow solution, residence times and dead volume.
+/// Channels whose absolute flow is at most `dead_flow_tolerance` times the largest absolute flow
+/// count as stagnant.
+pub fn volume_report(
+    network: &Network,
+    solution: Option<&FlowSolution>,
+    dead_flow_tolerance: f64,
+) -> Result<VolumeReport, SimulationError> {
+    let channels = network
+        .channels
+        .iter()
+        .map(|channel| {
+            let length = channel
+                .length()
+                .ok_or(SimulationError::MissingLength(channel.id))?;
+            let volume = channel.shape.area() * length;
+            let flow = solution.and_then(|s| s.flows.get(&channel.id).copied());
+            Ok(ChannelVolume {
+                channel: channel.id,
+                volume,
+                flow,
+                residence_time: flow.filter(|q| *q != 0.).map(|q| volume / q.abs()),
+            })
+        })
+        .collect::<Result<Vec<_>, SimulationError>>()?;
+    let volumes: BTreeMap<usize, f64> = channels.iter().map(|c| (c.channel, c.volume)).collect();
+
+    let max_flow = channels
+        .iter()
+        .filter_map(|c| c.flow)
+        .fold(0f64, |m, q| m.max(q.abs()));
+    let dead: BTreeSet<usize> = channels
+        .iter()
+        .filter(|c| {
+            c.flow
+                .is_some_and(|q| q.abs() <= dead_flow_tolerance * max_flow)
+        })
+        .map(|c| c.channel)
+        .collect();
+
+    let modules = network
+        .modules
+        .iter()
+        .map(|module| {
+            let channels: Vec<usize> = network
+                .channels
+                .iter()
+                .filter(|c| module.nodes.contains(&c.node_a) && module.nodes.contains(&c.node_b))
+                .map(|c| c.id)
+                .collect();
+            ModuleVolume {
+                module: module.id,
+                volume: channels.iter().map(|id| volumes[id]).sum(),
+                channels,
+            }
+        })
+        .collect();
+
+    let dead_branches = dead_branches(network, &dead)
+        .into_iter()
+        .map(|channels| DeadBranch {
+            volume: channels.iter().map(|id| volumes[id]).sum(),
+            channels,
+        })
+        .collect();
+
+    Ok(VolumeReport {
+        total_volume: volumes.values().sum(),
+        dead_volume: dead.iter().map(|id| volumes[id]).sum(),
+        channels,
+        dead_branches,
+        modules,
+    })
+}
+
+/// Groups the given channels into components connected through shared nodes
+fn dead_branches(network: &Network, dead: &BTreeSet<usize>) -> Vec<Vec<usize>> {
+    let mut by_node: BTreeMap<NodeId, Vec<usize>> = BTreeMap::new();
+    let mut ends = BTreeMap::new();
+    for channel in network.channels.iter().filter(|c| dead.contains(&c.id)) {
+        by_node.entry(channel.node_a).or_default().push(channel.id);
+        by_node.entry(channel.node_b).or_default().push(channel.id);
+        ends.insert(channel.id, [channel.node_a, channel.node_b]);
+    }
+
+    let mut visited = BTreeSet::new();
+    let mut branches = Vec::new();
+    for start in dead {
+        if !visited.insert(*start) {
+            continue;
+        }
+        let mut branch = vec![];
+        let mut stack = vec![*start];
+        while let Some(id) = stack.pop() {
+            branch.push(id);
+            for node in ends[&id] {
+                for neighbor in &by_node[&node] {
+                    if visited.insert(*neighbor) {
+                        stack.push(*neighbor);
+                    }
+                }
+            }
+        }
+        branch.sort();
+        branches.push(branch);
+    }
+    branches
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{Channel, RectangularShape, Shape},
+            network::{Module, Node},
+            primitives::{Dimensions, Point},
+        },
+        simulation::{
+            fluid::Fluid,
+            solver::{solve, Boundary, BoundaryCondition},
+        },
+    };
+
+    #[test]
+    fn dead_end_branch() {
+        let channel = |id, a, b| Channel {
+            id,
+            node_a: NodeId(a),
+            node_b: NodeId(b),
+            shape: Shape::Rectangular(RectangularShape {
+                width: 100e-6,
+                height: 50e-6,
+            }),
+            path: None,
+            length: Some(10e-3),
+            layer: 0,
+            metadata: Default::default(),
+        };
+        // Inlet 0 to outlet 2 through node 1, the branch 1 - 3 - 4 ends blind
+        let mut network = Network {
+            nodes: (0..5).map(|i| Node::new(NodeId(i))).collect(),
+            channels: vec![
+                channel(0, 0, 1),
+                channel(1, 1, 2),
+                channel(2, 1, 3),
+                channel(3, 3, 4),
+            ],
+            modules: vec![Module {
+                id: 0,
+                position: Point([0., 0.]),
+                size: Dimensions([1e-3, 1e-3]),
+                nodes: [1, 3, 4].map(NodeId).to_vec(),
+                implementation: None,
+                model: None,
+                footprint: None,
+                orientation: Default::default(),
+                kind: Default::default(),
+                metadata: Default::default(),
+            }],
+            ..Default::default()
+        };
+        let boundaries = [(0, 1e4), (2, 0.)].map(|(node, p)| Boundary {
+            node: NodeId(node),
+            condition: BoundaryCondition::Pressure(p),
+        });
+        let solution = solve(&network, &Fluid::water(), &boundaries).unwrap();
+        let report = volume_report(&network, Some(&solution), 1e-6).unwrap();
+
+        // 100 µm x 50 µm x 10 mm
+        let volume = 5e-11;
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9 * b;
+        assert!(report.channels.iter().all(|c| close(c.volume, volume)));
+        assert!(close(report.total_volume, 4. * volume));
+        let inlet = &report.channels[0];
+        let flow = solution.flows[&0];
+        assert_eq!(inlet.flow, Some(flow));
+        assert!(close(inlet.residence_time.unwrap(), volume / flow));
+        assert!(close(report.dead_volume, 2. * volume));
+        assert_eq!(report.dead_branches.len(), 1);
+        assert_eq!(report.dead_branches[0].channels, [2, 3]);
+        assert!(close(report.dead_branches[0].volume, 2. * volume));
+        assert_eq!(report.modules[0].channels, [2, 3]);
+
+        // Without a solution nothing is stagnant
+        let report = volume_report(&network, None, 1e-6).unwrap();
+        assert!(report.dead_branches.is_empty() && report.dead_volume == 0.);
+        assert!(report.channels.iter().all(|c| c.residence_time.is_none()));
+
+        network.channels[3].length = None;
+        assert_eq!(
+            volume_report(&network, None, 1e-6),
+            Err(SimulationError::MissingLength(3))
+        );
+    }
+}
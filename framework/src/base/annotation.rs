@@ -0,0 +1,233 @@
+use super::{
+    channel::SVGPath,
+    network::Network,
+    primitives::{Dimensions, Point, Rect},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Position an annotation is attached to
+pub enum Anchor {
+    /// Fixed position in the layout
+    Point(Point),
+
+    /// Midpoint of the path of the channel with the given id
+    Channel(usize),
+
+    /// Center of the module with the given id
+    Module(usize),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Text label
+pub struct Label {
+    /// Displayed text
+    pub text: String,
+
+    /// Position of the label's baseline start
+    pub anchor: Anchor,
+
+    /// Offset of the text from the anchor
+    #[serde(default = "Label::no_offset")]
+    pub offset: Dimensions,
+
+    /// Text height
+    pub height: f64,
+}
+
+impl Label {
+    fn no_offset() -> Dimensions {
+        Dimensions([0., 0.])
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Dimension line measuring the distance between two anchors
+pub struct DimensionAnnotation {
+    /// First measured position
+    pub from: Anchor,
+
+    /// Second measured position
+    pub to: Anchor,
+
+    /// Distance of the dimension line from the measured positions, to the left of from → to
+    pub offset: f64,
+
+    /// Text height
+    pub height: f64,
+
+    /// Displayed text, the measured distance if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Documentation entity without physical meaning
+pub enum Annotation {
+    /// Text label
+    Label(Label),
+
+    /// Dimension line
+    Dimension(DimensionAnnotation),
+}
+
+impl Network {
+    /// Layout position of an anchor, None if the referenced entity doesn't exist or has no
+    /// geometry
+    pub fn resolve_anchor(&self, anchor: &Anchor) -> Option<Point> {
+        match anchor {
+            Anchor::Point(point) => Some(*point),
+            Anchor::Channel(id) => {
+                let channel = self.channels.iter().find(|c| c.id == *id)?;
+                let path = channel.path.as_ref()?;
+                path.point_at_length(path.length().0 / 2.)
+            }
+            Anchor::Module(id) => {
+                // Center of the placed outline, which respects footprint and orientation
+                let module = self.modules.iter().find(|m| m.id == *id)?;
+                let Rect { min, max } = module.outline().bounding_box()?;
+                Some(Point([
+                    (min.0[0] + max.0[0]) / 2.,
+                    (min.0[1] + max.0[1]) / 2.,
+                ]))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{Channel, ChannelPath, LineSegment, PathPiece, RectangularShape, Shape},
+            footprint::Orientation,
+            network::{Module, Node, NodeId},
+            render::{NumberFormat, RenderConfig},
+        },
+        export::{
+            dxf::{network_to_dxf, network_to_dxf_with},
+            svg::{network_to_svg, network_to_svg_with},
+        },
+    };
+
+    #[test]
+    fn anchors_and_exports() {
+        let mut network = Network {
+            nodes: vec![Node::new(NodeId(0)), Node::new(NodeId(1))],
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(1),
+                shape: Shape::Rectangular(RectangularShape {
+                    width: 100.,
+                    height: 50.,
+                }),
+                path: Some(ChannelPath {
+                    pieces: vec![PathPiece::LineSegment(LineSegment {
+                        start: Point([0., 0.]),
+                        end: Point([2000., 0.]),
+                    })],
+                    closed: false,
+                }),
+                length: None,
+                layer: 0,
+                metadata: Default::default(),
+            }],
+            modules: vec![Module {
+                id: 0,
+                position: Point([0., 1000.]),
+                size: Dimensions([1000., 500.]),
+                nodes: vec![],
+                implementation: None,
+                model: None,
+                footprint: None,
+                orientation: Orientation::rotated(std::f64::consts::FRAC_PI_2),
+                kind: Default::default(),
+                metadata: Default::default(),
+            }],
+            annotations: vec![
+                Annotation::Label(Label {
+                    text: "Inlet & outlet".into(),
+                    anchor: Anchor::Channel(0),
+                    offset: Dimensions([0., 100.]),
+                    height: 100.,
+                }),
+                Annotation::Dimension(DimensionAnnotation {
+                    from: Anchor::Point(Point([0., -500.])),
+                    to: Anchor::Point(Point([2000., -500.])),
+                    offset: -200.,
+                    height: 100.,
+                    text: None,
+                }),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            network.resolve_anchor(&Anchor::Point(Point([1., 2.]))),
+            Some(Point([1., 2.]))
+        );
+        assert_eq!(
+            network.resolve_anchor(&Anchor::Channel(0)),
+            Some(Point([1000., 0.]))
+        );
+        // The module is rotated about its position, its center moves along
+        let Point([x, y]) = network.resolve_anchor(&Anchor::Module(0)).unwrap();
+        assert!(
+            (x + 250.).abs() < 1e-9 && (y - 1500.).abs() < 1e-9,
+            "{x} {y}"
+        );
+        assert_eq!(network.resolve_anchor(&Anchor::Channel(1)), None);
+        assert_eq!(network.resolve_anchor(&Anchor::Module(1)), None);
+
+        // Labels start at the offset anchor, dimension texts are centered beyond the line
+        let svg = network_to_svg(&network);
+        for element in [
+            r#"<line x1="0" y1="500" x2="0" y2="700" stroke-width="10"/>"#,
+            r#"<line x1="0" y1="700" x2="2000" y2="700" stroke-width="10"/>"#,
+            r#"<text x="1000" y="-100" font-size="100" text-anchor="start">Inlet &amp; outlet<"#,
+            r#"<text x="1000" y="775" font-size="100" text-anchor="middle">2000</text>"#,
+        ] {
+            assert!(svg.contains(element), "{element}");
+        }
+        let dxf = network_to_dxf(&network);
+        for entity in [
+            "TEXT\n8\nANNOTATIONS\n10\n1000\n20\n100\n40\n100\n1\nInlet & outlet\n0\n",
+            "LINE\n8\nANNOTATIONS\n10\n0\n20\n-700\n11\n2000\n21\n-700\n",
+            "TEXT\n8\nANNOTATIONS\n10\n1000\n20\n-775\n40\n100\n1\n2000\n72\n1\n11\n1000\n",
+        ] {
+            assert!(dxf.contains(entity), "{entity}");
+        }
+
+        // Measured lengths are formatted like the coordinates
+        network.annotations[1] = Annotation::Dimension(DimensionAnnotation {
+            from: Anchor::Point(Point([0., 0.])),
+            to: Anchor::Point(Point([1000., 1000.])),
+            offset: 0.,
+            height: 100.,
+            text: None,
+        });
+        let config = RenderConfig {
+            number_format: NumberFormat::compact(2),
+            ..RenderConfig::y_down()
+        };
+        assert!(network_to_svg_with(&network, &config).contains(">1414.21</text>"));
+        let config = RenderConfig {
+            number_format: NumberFormat::compact(1),
+            ..Default::default()
+        };
+        assert!(network_to_dxf_with(&network, &config).contains("\n1\n1414.2\n"));
+        assert!(network_to_svg(&network).contains(">1414.213562373095</text>"));
+
+        // Annotations of missing entities are left out
+        network.channels[0].path = None;
+        let texts = |svg: &str| svg.matches("<text").count();
+        assert_eq!(texts(&network_to_svg(&network)), 1);
+        assert_eq!(network_to_dxf(&network).matches("TEXT").count(), 1);
+    }
+}
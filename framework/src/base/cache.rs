@@ -0,0 +1,229 @@
+//! Memoized channel lengths and resistances, so repeated solves during optimization don't
+//! recompute them for unchanged channels. Entries are keyed by a fingerprint of the channel's
+//! geometry and never go stale; [`Network::invalidate`] drops the entries of edited or removed
+//! channels to free their memory. Every lookup hashes the path to check the fingerprint, so
+//! the non-Newtonian iteration looks up each channel once per solve and scales the resistance
+//! per unit viscosity by the apparent viscosities.
+
+use super::{
+    channel::{Channel, PathPiece, Shape},
+    network::Network,
+};
+use crate::simulation::resistance::resistance_for_length;
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap},
+    hash::Hasher,
+    sync::Mutex,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Entry {
+    fingerprint: u64,
+    length: Option<f64>,
+
+    /// Resistance per unit viscosity, which the resistance is proportional to
+    resistance: Option<f64>,
+}
+
+#[derive(Debug, Default)]
+/// Cached values per channel id. Caches never affect equality and aren't serialized.
+pub struct ChannelCache(Mutex<BTreeMap<usize, Entry>>);
+
+impl Clone for ChannelCache {
+    fn clone(&self) -> Self {
+        ChannelCache(Mutex::new(self.0.lock().unwrap().clone()))
+    }
+}
+
+impl PartialEq for ChannelCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// Hash of everything the length and resistance of a channel depend on
+fn fingerprint(channel: &Channel) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let mut write = |value: f64| hasher.write_u64(value.to_bits());
+    match channel.shape {
+        Shape::Rectangular(shape) => {
+            write(shape.width);
+            write(shape.height);
+        }
+        Shape::Cylindrical(shape) => write(shape.radius),
+    }
+    write(channel.length.unwrap_or(f64::NAN));
+    for piece in channel.path.iter().flat_map(|p| &p.pieces) {
+        let points = match piece {
+            PathPiece::Arc(arc) => {
+                write(if arc.right { 1. } else { -1. });
+                [arc.start, arc.end, arc.center]
+            }
+            PathPiece::LineSegment(line) => [line.start, line.end, line.end],
+        };
+        for point in points {
+            write(point.0[0]);
+            write(point.0[1]);
+        }
+    }
+    hasher.finish()
+}
+
+impl ChannelCache {
+    /// Entry of the channel, recomputed if its geometry changed
+    fn lookup<R>(&self, channel: &Channel, f: impl FnOnce(&mut Entry) -> R) -> R {
+        let fingerprint = fingerprint(channel);
+        let mut entries = self.0.lock().unwrap();
+        let entry = entries.entry(channel.id).or_insert(Entry {
+            fingerprint,
+            length: channel.length(),
+            resistance: None,
+        });
+        if entry.fingerprint != fingerprint {
+            *entry = Entry {
+                fingerprint,
+                length: channel.length(),
+                resistance: None,
+            };
+        }
+        f(entry)
+    }
+}
+
+impl Network {
+    /// Length of the channel like [Channel::length], memoized
+    pub fn cached_length(&self, channel: &Channel) -> Option<f64> {
+        self.cache.lookup(channel, |entry| entry.length)
+    }
+
+    /// Hydraulic resistance of the channel for the viscosity, memoized for all viscosities;
+    /// None if the channel has no length
+    pub fn cached_resistance(&self, channel: &Channel, viscosity: f64) -> Option<f64> {
+        self.cache.lookup(channel, |entry| {
+            let length = entry.length?;
+            let unit = *entry
+                .resistance
+                .get_or_insert_with(|| resistance_for_length(&channel.shape, 1., length));
+            Some(viscosity * unit)
+        })
+    }
+
+    /// Drops the cached values of the channel
+    pub fn invalidate(&mut self, channel_id: usize) {
+        self.cache.0.get_mut().unwrap().remove(&channel_id);
+    }
+
+    /// Drops all cached values
+    pub fn invalidate_all(&mut self) {
+        self.cache.0.get_mut().unwrap().clear();
+    }
+
+    /// Whether values of the channel are cached
+    pub fn is_cached(&self, channel_id: usize) -> bool {
+        self.cache.0.lock().unwrap().contains_key(&channel_id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::{ChannelPath, LineSegment, RectangularShape},
+        network::{Node, NodeId},
+        primitives::Point,
+    };
+
+    fn network() -> Network {
+        Network {
+            nodes: vec![Node::new(NodeId(0)), Node::new(NodeId(1))],
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(1),
+                shape: Shape::Rectangular(RectangularShape {
+                    width: 100e-6,
+                    height: 50e-6,
+                }),
+                path: Some(ChannelPath {
+                    pieces: vec![PathPiece::LineSegment(LineSegment {
+                        start: Point([0., 0.]),
+                        end: Point([0.01, 0.]),
+                    })],
+                    closed: false,
+                }),
+                length: None,
+                layer: 0,
+                metadata: Default::default(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    /// Replaces the cached resistance per unit viscosity, so lookups served from the cache stand out
+    fn tamper(network: &Network, unit: f64) {
+        network
+            .cache
+            .0
+            .lock()
+            .unwrap()
+            .get_mut(&0)
+            .unwrap()
+            .resistance = Some(unit);
+    }
+
+    #[test]
+    fn memoization() {
+        let mut network = network();
+        let resistance = |network: &Network| network.cached_resistance(&network.channels[0], 1e-3);
+        let first = resistance(&network).unwrap();
+        assert!(network.is_cached(0));
+        assert_eq!(network.clone(), network);
+
+        // Editing the geometry is detected without invalidation
+        let Some(PathPiece::LineSegment(line)) =
+            network.channels[0].path.as_mut().map(|p| &mut p.pieces[0])
+        else {
+            unreachable!()
+        };
+        line.end = Point([0.02, 0.]);
+        assert!((resistance(&network).unwrap() - 2. * first).abs() < 1e-9 * first);
+        assert_eq!(network.cached_length(&network.channels[0]), Some(0.02));
+
+        network.invalidate(0);
+        assert!(!network.is_cached(0));
+        network.channels[0].path = None;
+        assert_eq!(resistance(&network), None);
+    }
+
+    #[test]
+    fn cache_hits() {
+        let network = network();
+        let channel = &network.channels[0];
+        let first = network.cached_resistance(channel, 1e-3).unwrap();
+        assert!((network.cached_resistance(channel, 4e-3).unwrap() - 4. * first).abs() < 1e-9);
+
+        // Alternating viscosities, as in the non-Newtonian iteration, reuse the entry
+        tamper(&network, 2.);
+        assert_eq!(network.cached_resistance(channel, 1e-3), Some(2e-3));
+        assert_eq!(network.cached_resistance(channel, 3e-3), Some(6e-3));
+        assert_eq!(network.cached_resistance(channel, 1e-3), Some(2e-3));
+        assert_eq!(network.cached_length(channel), Some(0.01));
+    }
+
+    #[test]
+    fn invalidation() {
+        let mut network = network();
+        let first = network.cached_resistance(&network.channels[0], 1e-3);
+        tamper(&network, 2.);
+        network.invalidate(1);
+        assert!(network.is_cached(0));
+        network.invalidate(0);
+        assert!(!network.is_cached(0));
+        assert_eq!(network.cached_resistance(&network.channels[0], 1e-3), first);
+
+        tamper(&network, 2.);
+        network.invalidate_all();
+        assert!(!network.is_cached(0));
+        assert_eq!(network.cached_resistance(&network.channels[0], 1e-3), first);
+    }
+}
@@ -0,0 +1,252 @@
+//! Reconstruction of channels from their outlines, the inverse of offsetting, e.g., to recover
+//! the network of an existing mask imported as polygons. The outline's two flat ends are found
+//! from their corners, and the centerline runs through the midpoints between the two sides.
+
+use super::{
+    channel::{Channel, ChannelPath, LineSegment, PathPiece, RectangularShape, Shape},
+    network::{Metadata, NodeId},
+    polygon::Polygon,
+    primitives::Point,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::FRAC_PI_3;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Channel width at a position along the centerline
+pub struct WidthSample {
+    /// Arc length from the start of the centerline
+    pub position: f64,
+    pub width: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Centerline and width profile recovered from a channel outline
+pub struct Centerline {
+    /// Centerline from the midpoint of the end coming first in the outline's vertex order to
+    /// the other end, simplified to segments and arcs
+    pub path: ChannelPath,
+
+    /// Widths in order of increasing position, from the unsimplified centerline
+    pub widths: Vec<WidthSample>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Reasons an outline isn't recognized as a channel
+pub enum CenterlineError {
+    /// The outline has fewer than four distinct vertices or no area
+    Degenerate,
+
+    /// The outline lacks two separate flat ends with convex corners
+    NoEnds,
+}
+
+impl std::fmt::Display for CenterlineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CenterlineError::Degenerate => write!(f, "outline has no area"),
+            CenterlineError::NoEnds => write!(f, "outline has no two flat channel ends"),
+        }
+    }
+}
+
+impl std::error::Error for CenterlineError {}
+
+/// Closest point to the point on the polyline
+fn closest(polyline: &[Point], point: Point) -> Point {
+    let mut best = (f64::INFINITY, polyline[0]);
+    for w in polyline.windows(2) {
+        let direction = w[1] - w[0];
+        let squared = direction.dot(direction);
+        let t = if squared == 0. {
+            0.
+        } else {
+            ((point - w[0]).dot(direction) / squared).clamp(0., 1.)
+        };
+        let candidate = w[0] + direction * t;
+        let distance = (point - candidate).length();
+        if distance < best.0 {
+            best = (distance, candidate);
+        }
+    }
+    best.1
+}
+
+fn midpoint(a: Point, b: Point) -> Point {
+    a + (b - a) * 0.5
+}
+
+impl Centerline {
+    /// Centerline of a channel outline with flat ends, as drawn by mask exporters. The ends are
+    /// the two shortest edges that aren't adjacent and whose corners both turn outward by at
+    /// least 60°. One side is sampled at the spacing; the midpoints to the closest points of the
+    /// other side form the centerline, their distances the widths. The centerline is then
+    /// simplified with the tolerance, see [ChannelPath::simplify].
+    pub fn extract(
+        outline: &Polygon,
+        spacing: f64,
+        tolerance: f64,
+    ) -> Result<Centerline, CenterlineError> {
+        let mut vertices = outline.0.clone();
+        vertices.dedup();
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        let n = vertices.len();
+        let area = Polygon(vertices.clone()).signed_area();
+        if n < 4 || area == 0. {
+            return Err(CenterlineError::Degenerate);
+        }
+
+        // Turning angle at every vertex, positive when turning outward
+        let convex: Vec<bool> = (0..n)
+            .map(|i| {
+                let incoming = vertices[i] - vertices[(i + n - 1) % n];
+                let outgoing = vertices[(i + 1) % n] - vertices[i];
+                let turn = incoming.cross(outgoing).atan2(incoming.dot(outgoing));
+                turn * area.signum() >= FRAC_PI_3
+            })
+            .collect();
+        let length = |i: usize| (vertices[(i + 1) % n] - vertices[i]).length();
+        let mut ends: Vec<usize> = (0..n)
+            .filter(|i| convex[*i] && convex[(i + 1) % n])
+            .collect();
+        ends.sort_by(|a, b| length(*a).total_cmp(&length(*b)));
+        let adjacent = |a: usize, b: usize| (a + 1) % n == b || (b + 1) % n == a;
+        let (a, b) = ends
+            .iter()
+            .enumerate()
+            .flat_map(|(k, a)| ends[k + 1..].iter().map(move |b| (*a, *b)))
+            .find(|(a, b)| !adjacent(*a, *b))
+            .ok_or(CenterlineError::NoEnds)?;
+        let (a, b) = (a.min(b), a.max(b));
+
+        // Both sides run from end a to end b
+        let side: Vec<Point> = (a + 1..=b).map(|i| vertices[i]).collect();
+        let other: Vec<Point> = (b + 1..=a + n).rev().map(|i| vertices[i % n]).collect();
+
+        let mut points = vec![midpoint(vertices[a], vertices[a + 1])];
+        let mut widths = vec![WidthSample {
+            position: 0.,
+            width: length(a),
+        }];
+        let mut push = |point: Point, width: f64| {
+            let last = *points.last().unwrap();
+            let step = (point - last).length();
+            if step > spacing * 1e-6 {
+                let position = widths.last().unwrap().position + step;
+                points.push(point);
+                widths.push(WidthSample { position, width });
+            }
+        };
+        for w in side.windows(2) {
+            let samples = ((w[1] - w[0]).length() / spacing).ceil().max(1.) as usize;
+            for i in 0..samples {
+                let sample = w[0] + (w[1] - w[0]) * (i as f64 / samples as f64);
+                let opposite = closest(&other, sample);
+                push(midpoint(sample, opposite), (sample - opposite).length());
+            }
+        }
+        push(midpoint(vertices[b], vertices[(b + 1) % n]), length(b));
+
+        let mut path = ChannelPath::new();
+        for w in points.windows(2) {
+            path.add(PathPiece::LineSegment(LineSegment {
+                start: w[0],
+                end: w[1],
+            }));
+        }
+        path.simplify(tolerance);
+        Ok(Centerline { path, widths })
+    }
+
+    /// Width averaged over the length of the centerline
+    pub fn mean_width(&self) -> f64 {
+        let total = self.widths.last().map_or(0., |w| w.position);
+        if total == 0. {
+            return self.widths.first().map_or(0., |w| w.width);
+        }
+        let integral: f64 = self
+            .widths
+            .windows(2)
+            .map(|w| (w[1].position - w[0].position) * (w[0].width + w[1].width) / 2.)
+            .sum();
+        integral / total
+    }
+
+    /// Rectangular channel along the centerline with the mean width and the given height
+    pub fn channel(&self, id: usize, node_a: NodeId, node_b: NodeId, height: f64) -> Channel {
+        Channel {
+            id,
+            node_a,
+            node_b,
+            shape: Shape::Rectangular(RectangularShape {
+                width: self.mean_width(),
+                height,
+            }),
+            path: Some(self.path.clone()),
+            length: None,
+            layer: 0,
+            metadata: Metadata::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::channel::{Arc, SVGPath};
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    #[test]
+    fn line_and_arc_outline() {
+        let mut path = ChannelPath::new();
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: Point([0., 0.]),
+            end: Point([100., 0.]),
+        }));
+        path.add(PathPiece::Arc(Arc::from_center_angles(
+            Point([100., 50.]),
+            50.,
+            -FRAC_PI_2,
+            FRAC_PI_2,
+        )));
+        // Sides 5 to the right and left, arcs discretized into 64 chords
+        let side = |distance: f64| {
+            let offset = path.offset(distance).unwrap();
+            let mut points = vec![offset.pieces[0].start()];
+            points.extend((1..=64).map(|i| offset.pieces[1].point_at(i as f64 / 64.)));
+            points
+        };
+        let mut outline = side(-5.);
+        outline.extend(side(5.).into_iter().rev());
+
+        let mut centerline = Centerline::extract(&Polygon(outline), 1., 0.05).unwrap();
+        // The end between the sides comes first in vertex order
+        assert!((centerline.path.pieces[0].start() - Point([150., 50.])).length() < 1e-9);
+        centerline.path.reverse();
+        let pieces = &centerline.path.pieces;
+        assert_eq!(pieces.len(), 2);
+        assert!(pieces[0]
+            .start()
+            .approx_eq(&Point([0., 0.]), &Default::default()));
+        assert!((pieces[1].end() - Point([150., 50.])).length() < 1e-9);
+        let PathPiece::Arc(arc) = pieces[1] else {
+            panic!("expected an arc");
+        };
+        assert!((arc.radius() - 50.).abs() < 0.05);
+        assert!((centerline.path.length().0 - 100. - 25. * PI).abs() < 0.05);
+        assert!((centerline.mean_width() - 10.).abs() < 0.01);
+
+        let channel = centerline.channel(0, NodeId(0), NodeId(1), 2.);
+        assert!((channel.shape.width() - 10.).abs() < 0.01);
+
+        let triangle = Polygon(vec![Point([0., 0.]), Point([1., 0.]), Point([1., 1.])]);
+        assert_eq!(
+            Centerline::extract(&triangle, 1., 0.05),
+            Err(CenterlineError::Degenerate)
+        );
+    }
+}
@@ -1,11 +1,18 @@
-use super::{network::NodeId, primitives::Point};
-use geometry_predicates::orient2d;
+use super::{
+    footprint::Orientation,
+    network::{Metadata, NodeId},
+    polygon::Winding,
+    primitives::{Point, Rect, Tolerance},
+    render::RenderConfig,
+};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, JsonSchema, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema, PartialEq)]
 #[serde(rename_all = "snake_case")]
-/// A structure holding a microfluidic channel
+/// A structure holding a microfluidic channel. The channel is directed from node_a to node_b:
+/// flows are positive in that direction and the path runs from node_a to node_b.
 pub struct Channel {
     /// Id of the channel
     pub id: usize,
@@ -18,6 +25,49 @@ pub struct Channel {
 
     /// Channel Shape
     pub shape: Shape,
+
+    /// Optional geometry of the channel's centerline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<ChannelPath>,
+
+    /// Nominal channel length, takes precedence over the path length if set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<f64>,
+
+    /// Network layer of the channel, e.g., for multi-layer chips
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub layer: usize,
+
+    /// Tool-specific data attached to the channel
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata: Metadata,
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+impl Channel {
+    /// Explicit length if set, path length otherwise
+    pub fn length(&self) -> Option<f64> {
+        self.length
+            .or_else(|| self.path.as_ref().map(|p| p.length().0))
+    }
+
+    /// Swaps the ends of the channel and reverses its path, so that a flow keeps its direction
+    /// but changes its sign
+    pub fn reverse(&mut self) {
+        std::mem::swap(&mut self.node_a, &mut self.node_b);
+        if let Some(path) = &mut self.path {
+            path.reverse();
+        }
+    }
+
+    /// Bounding box of the channel outline, if the channel has a path
+    pub fn bounding_box(&self) -> Option<Rect> {
+        let path = self.path.as_ref()?.bounding_box()?;
+        Some(path.inflate(self.shape.width() / 2.))
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, JsonSchema, PartialEq)]
@@ -31,14 +81,44 @@ pub enum Shape {
     Cylindrical(CylindricalShape),
 }
 
+impl Shape {
+    /// Cross-section area
+    pub fn area(&self) -> f64 {
+        match self {
+            Shape::Rectangular(shape) => shape.width * shape.height,
+            Shape::Cylindrical(shape) => PI * shape.radius * shape.radius,
+        }
+    }
+
+    /// Hydraulic diameter, four times area over wetted perimeter
+    pub fn hydraulic_diameter(&self) -> f64 {
+        match self {
+            Shape::Rectangular(shape) => {
+                2. * shape.width * shape.height / (shape.width + shape.height)
+            }
+            Shape::Cylindrical(shape) => 2. * shape.radius,
+        }
+    }
+
+    /// Extent of the cross-section in the layout plane
+    pub fn width(&self) -> f64 {
+        match self {
+            Shape::Rectangular(shape) => shape.width,
+            Shape::Cylindrical(shape) => 2. * shape.radius,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, JsonSchema, PartialEq)]
 #[serde(rename_all = "snake_case")]
 /// Rectangular channel cross-section
 pub struct RectangularShape {
     /// Channel width
+    #[schemars(schema_with = "positive")]
     pub width: f64,
 
     /// Channel height
+    #[schemars(schema_with = "positive")]
     pub height: f64,
 }
 
@@ -47,53 +127,316 @@ pub struct RectangularShape {
 /// Round channel cross-section
 pub struct CylindricalShape {
     /// Cross-section radius
+    #[schemars(schema_with = "positive")]
     pub radius: f64,
 }
 
+/// Schema of a dimension that must be larger than zero
+fn positive(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+    use schemars::schema::{InstanceType, NumberValidation, SchemaObject};
+    SchemaObject {
+        instance_type: Some(InstanceType::Number.into()),
+        format: Some("double".into()),
+        number: Some(Box::new(NumberValidation {
+            exclusive_minimum: Some(0.),
+            ..Default::default()
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, JsonSchema, PartialEq)]
 #[serde(rename_all = "snake_case")]
 /// A continuous channel (Dubin's) path with arcs and straight segments
 pub struct ChannelPath {
     /// Single pieces of the path
     pub pieces: Vec<PathPiece>,
+
+    /// Whether the path is an outline, e.g., of a chamber or reservoir, that returns to its
+    /// start; a remaining gap is closed by a straight segment
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub closed: bool,
+}
+
+impl Default for ChannelPath {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ChannelPath {
     pub fn new() -> Self {
-        ChannelPath { pieces: Vec::new() }
+        ChannelPath {
+            pieces: Vec::new(),
+            closed: false,
+        }
     }
 
     pub fn add(&mut self, piece: PathPiece) {
         self.pieces.push(piece)
     }
+
+    /// Copy of the path placed in the orientation at the position, like the contents of a
+    /// module; mirroring reverses the direction of arcs
+    pub fn placed(&self, position: Point, orientation: &Orientation) -> ChannelPath {
+        let place = |p: Point| orientation.place(position, p);
+        ChannelPath {
+            pieces: self
+                .pieces
+                .iter()
+                .map(|piece| match piece {
+                    PathPiece::Arc(arc) => PathPiece::Arc(Arc {
+                        right: arc.right != orientation.mirrored,
+                        start: place(arc.start),
+                        end: place(arc.end),
+                        center: place(arc.center),
+                    }),
+                    PathPiece::LineSegment(line) => PathPiece::LineSegment(LineSegment {
+                        start: place(line.start),
+                        end: place(line.end),
+                    }),
+                })
+                .collect(),
+            closed: self.closed,
+        }
+    }
+
+    /// Copy of the path moved by the given offset
+    pub fn translated(&self, offset: Point) -> ChannelPath {
+        let shift = |p: Point| p.translated(offset);
+        ChannelPath {
+            pieces: self
+                .pieces
+                .iter()
+                .map(|piece| match piece {
+                    PathPiece::Arc(arc) => PathPiece::Arc(Arc {
+                        start: shift(arc.start),
+                        end: shift(arc.end),
+                        center: shift(arc.center),
+                        ..*arc
+                    }),
+                    PathPiece::LineSegment(line) => PathPiece::LineSegment(LineSegment {
+                        start: shift(line.start),
+                        end: shift(line.end),
+                    }),
+                })
+                .collect(),
+            closed: self.closed,
+        }
+    }
+
+    /// Closed outline from the pieces
+    pub fn closed(pieces: Vec<PathPiece>) -> Self {
+        ChannelPath {
+            pieces,
+            closed: true,
+        }
+    }
+
+    /// Closed counterclockwise outline of the rectangle
+    pub fn rectangle(rect: &Rect) -> Self {
+        let (Point([x0, y0]), Point([x1, y1])) = (rect.min, rect.max);
+        let corners = [[x0, y0], [x1, y0], [x1, y1], [x0, y1]].map(Point);
+        ChannelPath::closed(
+            (0..4)
+                .map(|i| {
+                    PathPiece::LineSegment(LineSegment {
+                        start: corners[i],
+                        end: corners[(i + 1) % 4],
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// Signed enclosed area of a closed path, positive for counterclockwise outlines; None for
+    /// open paths
+    pub fn signed_area(&self) -> Option<f64> {
+        if !self.closed {
+            return None;
+        }
+        let cross = |Point([ax, ay]): Point, Point([bx, by]): Point| (ax * by - ay * bx) / 2.;
+        let mut area: f64 = self
+            .pieces
+            .iter()
+            .map(|piece| {
+                let chord = cross(piece.start(), piece.end());
+                match piece {
+                    PathPiece::LineSegment(_) => chord,
+                    // Circular segment between chord and arc
+                    PathPiece::Arc(arc) => {
+                        let ArcAngles { sweep, .. } = arc.angles();
+                        chord + arc.radius().powi(2) * (sweep - sweep.sin()) / 2.
+                    }
+                }
+            })
+            .sum();
+        if let (Some(first), Some(last)) = (self.pieces.first(), self.pieces.last()) {
+            area += cross(last.end(), first.start());
+        }
+        Some(area)
+    }
+
+    /// Enclosed area of a closed path, None for open paths
+    pub fn area(&self) -> Option<f64> {
+        self.signed_area().map(f64::abs)
+    }
+
+    /// Orientation of a closed path, None for open or degenerate paths
+    pub fn winding(&self) -> Option<Winding> {
+        match self.signed_area() {
+            Some(area) if area > 0. => Some(Winding::Counterclockwise),
+            Some(area) if area < 0. => Some(Winding::Clockwise),
+            _ => None,
+        }
+    }
+
+    /// Point at the given arc length from the start, clamped to the path; None for an empty path
+    pub fn point_at_length(&self, length: f64) -> Option<Point> {
+        let mut travelled = 0.;
+        for piece in &self.pieces {
+            let piece_length = piece.length().0;
+            if travelled + piece_length >= length {
+                let t = if piece_length > 0. {
+                    ((length - travelled) / piece_length).max(0.)
+                } else {
+                    0.
+                };
+                return Some(piece.point_at(t));
+            }
+            travelled += piece_length;
+        }
+        self.pieces.last().map(|p| p.end())
+    }
+
+    /// Bounding box of the path's centerline, None for an empty path
+    pub fn bounding_box(&self) -> Option<Rect> {
+        self.pieces
+            .iter()
+            .map(|p| p.bounding_box())
+            .reduce(|a, b| a.union(&b))
+    }
+
+    /// Smallest bend radius of all arcs, None for paths without arcs
+    pub fn min_radius(&self) -> Option<f64> {
+        self.pieces
+            .iter()
+            .filter_map(PathPiece::bend_radius)
+            .reduce(f64::min)
+    }
+
+    /// Indices of the arcs bending tighter than the given radius
+    pub fn tighter_than(&self, min_radius: f64) -> Vec<usize> {
+        self.pieces
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.bend_radius().is_some_and(|r| r < min_radius))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Parallel path at the signed distance to the left of the direction of travel, None if an
+    /// arc would collapse. Tangent-continuous paths stay continuous.
+    pub fn offset(&self, distance: f64) -> Option<ChannelPath> {
+        let pieces = self
+            .pieces
+            .iter()
+            .map(|piece| piece.offset(distance))
+            .collect::<Option<Vec<_>>>()?;
+        Some(ChannelPath {
+            pieces,
+            closed: self.closed,
+        })
+    }
+
+    /// Reverses the direction of travel in place
+    pub fn reverse(&mut self) {
+        self.pieces.reverse();
+        for piece in &mut self.pieces {
+            *piece = piece.reversed();
+        }
+    }
+
+    /// Splits the path at the given arc length from the start, clamped to the path
+    pub fn split_at(&self, length: f64) -> (ChannelPath, ChannelPath) {
+        let (mut head, mut tail) = (ChannelPath::new(), ChannelPath::new());
+        let mut travelled = 0.;
+        for piece in &self.pieces {
+            let piece_length = piece.length().0;
+            if travelled + piece_length <= length {
+                head.add(*piece);
+            } else if travelled >= length {
+                tail.add(*piece);
+            } else {
+                let (a, b) = piece.split((length - travelled) / piece_length);
+                head.add(a);
+                tail.add(b);
+            }
+            travelled += piece_length;
+        }
+        (head, tail)
+    }
+
+    /// Appends another path; a gap between this path's end and the other's start that exceeds
+    /// the default tolerance is bridged by a straight segment
+    pub fn concat(&mut self, other: &ChannelPath) {
+        if let (Some(end), Some(start)) =
+            (self.pieces.last().map(|p| p.end()), other.pieces.first())
+        {
+            if !end.approx_eq(&start.start(), &Tolerance::default()) {
+                self.add(PathPiece::LineSegment(LineSegment {
+                    start: end,
+                    end: start.start(),
+                }));
+            }
+        }
+        self.pieces.extend_from_slice(&other.pieces);
+    }
+
+    /// Part of the path between two arc lengths from the start; traversed backwards if `from`
+    /// is larger than `to`
+    pub fn subpath(&self, from: f64, to: f64) -> ChannelPath {
+        if from > to {
+            let mut path = self.subpath(to, from);
+            path.reverse();
+            return path;
+        }
+        let (head, _) = self.split_at(to);
+        head.split_at(from).1
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
 pub struct PathLength(pub f64);
 
 pub trait SVGPath {
-    fn svg_path_command(&self, invert_y: bool) -> String;
+    /// SVG path data in the output coordinates of the configuration
+    fn svg_path_command(&self, config: &RenderConfig) -> String;
     fn length(&self) -> PathLength;
 }
 
 impl SVGPath for ChannelPath {
-    fn svg_path_command(&self, invert_y: bool) -> String {
-        if self.pieces.len() == 0 {
+    fn svg_path_command(&self, config: &RenderConfig) -> String {
+        if self.pieces.is_empty() {
             return "".to_string();
         }
 
-        let Point([x, y]) = match &self.pieces[0] {
+        let start = match &self.pieces[0] {
             PathPiece::Arc(arc) => arc.start,
             PathPiece::LineSegment(line) => line.start,
         };
 
-        let mut s = format!("M {x} {y} ").to_owned();
+        let mut s = format!("M {} ", config.point(start)).to_owned();
         for piece in self.pieces.iter() {
             match piece {
-                PathPiece::Arc(arc) => s.push_str(&arc.svg_path_command(invert_y)),
-                PathPiece::LineSegment(line) => s.push_str(&line.svg_path_command(invert_y)),
+                PathPiece::Arc(arc) => s.push_str(&arc.svg_path_command(config)),
+                PathPiece::LineSegment(line) => s.push_str(&line.svg_path_command(config)),
             }
         }
+        if self.closed {
+            s.push_str(if config.relative_commands { "z " } else { "Z " });
+        }
         s.to_string()
     }
 
@@ -121,6 +464,158 @@ pub enum PathPiece {
     LineSegment(LineSegment),
 }
 
+impl PathPiece {
+    pub fn start(&self) -> Point {
+        match self {
+            PathPiece::Arc(arc) => arc.start,
+            PathPiece::LineSegment(line) => line.start,
+        }
+    }
+
+    pub fn end(&self) -> Point {
+        match self {
+            PathPiece::Arc(arc) => arc.end,
+            PathPiece::LineSegment(line) => line.end,
+        }
+    }
+
+    /// Point at parameter t in [0, 1], linear in arc length
+    pub fn point_at(&self, t: f64) -> Point {
+        match self {
+            PathPiece::Arc(arc) => arc.point_at(t),
+            PathPiece::LineSegment(line) => line.point_at(t),
+        }
+    }
+
+    pub fn bounding_box(&self) -> Rect {
+        match self {
+            PathPiece::Arc(arc) => arc.bounding_box(),
+            PathPiece::LineSegment(line) => line.bounding_box(),
+        }
+    }
+
+    /// Signed curvature, positive when turning left (counterclockwise) and 0 for lines
+    pub fn curvature(&self) -> f64 {
+        match self {
+            PathPiece::Arc(arc) => arc.curvature(),
+            PathPiece::LineSegment(_) => 0.,
+        }
+    }
+
+    /// Radius of an arc, None for straight pieces
+    pub fn bend_radius(&self) -> Option<f64> {
+        match self {
+            PathPiece::Arc(arc) => Some(arc.radius()),
+            PathPiece::LineSegment(_) => None,
+        }
+    }
+
+    /// Parallel piece at the signed distance to the left of the direction of travel, None if
+    /// an arc would collapse
+    pub fn offset(&self, distance: f64) -> Option<PathPiece> {
+        match *self {
+            PathPiece::LineSegment(line) => {
+                let (Point([sx, sy]), Point([ex, ey])) = (line.start, line.end);
+                let length = f64::hypot(ex - sx, ey - sy);
+                if length == 0. {
+                    return Some(*self);
+                }
+                let normal = Point([
+                    -(ey - sy) / length * distance,
+                    (ex - sx) / length * distance,
+                ]);
+                Some(PathPiece::LineSegment(LineSegment {
+                    start: line.start.translated(normal),
+                    end: line.end.translated(normal),
+                }))
+            }
+            PathPiece::Arc(arc) => {
+                // The left side is inside of counterclockwise arcs
+                let radius = arc.radius();
+                let new_radius = if arc.right {
+                    radius + distance
+                } else {
+                    radius - distance
+                };
+                if new_radius <= 0. {
+                    return None;
+                }
+                let Point([cx, cy]) = arc.center;
+                let scale = |Point([x, y]): Point| {
+                    let f = new_radius / radius;
+                    Point([cx + (x - cx) * f, cy + (y - cy) * f])
+                };
+                Some(PathPiece::Arc(Arc {
+                    start: scale(arc.start),
+                    end: scale(arc.end),
+                    ..arc
+                }))
+            }
+        }
+    }
+
+    /// Same geometry traversed from end to start
+    pub fn reversed(&self) -> PathPiece {
+        match *self {
+            PathPiece::Arc(arc) => PathPiece::Arc(Arc {
+                right: !arc.right,
+                start: arc.end,
+                end: arc.start,
+                ..arc
+            }),
+            PathPiece::LineSegment(line) => PathPiece::LineSegment(LineSegment {
+                start: line.end,
+                end: line.start,
+            }),
+        }
+    }
+
+    /// Splits the piece at parameter t in (0, 1); arcs keep their center and orientation
+    pub fn split(&self, t: f64) -> (PathPiece, PathPiece) {
+        let point = self.point_at(t);
+        match *self {
+            PathPiece::Arc(arc) => (
+                PathPiece::Arc(Arc { end: point, ..arc }),
+                PathPiece::Arc(Arc {
+                    start: point,
+                    ..arc
+                }),
+            ),
+            PathPiece::LineSegment(line) => (
+                PathPiece::LineSegment(LineSegment { end: point, ..line }),
+                PathPiece::LineSegment(LineSegment {
+                    start: point,
+                    ..line
+                }),
+            ),
+        }
+    }
+
+    /// Moves the start point, keeping an arc's center
+    pub(crate) fn set_start(&mut self, point: Point) {
+        match self {
+            PathPiece::Arc(arc) => arc.start = point,
+            PathPiece::LineSegment(line) => line.start = point,
+        }
+    }
+}
+
+impl SVGPath for PathPiece {
+    fn svg_path_command(&self, config: &RenderConfig) -> String {
+        match self {
+            PathPiece::Arc(arc) => arc.svg_path_command(config),
+            PathPiece::LineSegment(line) => line.svg_path_command(config),
+        }
+    }
+
+    fn length(&self) -> PathLength {
+        match self {
+            PathPiece::Arc(arc) => arc.length(),
+            PathPiece::LineSegment(line) => line.length(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, JsonSchema, PartialEq)]
 #[serde(rename_all = "snake_case")]
 /// A straight line segment
@@ -132,10 +627,42 @@ pub struct LineSegment {
     pub end: Point,
 }
 
+impl LineSegment {
+    /// Point at parameter t in [0, 1]
+    pub fn point_at(&self, t: f64) -> Point {
+        let Point([sx, sy]) = self.start;
+        let Point([ex, ey]) = self.end;
+        Point([sx + t * (ex - sx), sy + t * (ey - sy)])
+    }
+
+    pub fn bounding_box(&self) -> Rect {
+        Rect::enclosing([self.start, self.end]).unwrap()
+    }
+
+    /// Euclidean distance from a point to the segment
+    pub fn distance(&self, point: Point) -> f64 {
+        let Point([sx, sy]) = self.start;
+        let Point([ex, ey]) = self.end;
+        let Point([px, py]) = point;
+        let (dx, dy) = (ex - sx, ey - sy);
+        let squared = dx * dx + dy * dy;
+        let t = if squared == 0. {
+            0.
+        } else {
+            (((px - sx) * dx + (py - sy) * dy) / squared).clamp(0., 1.)
+        };
+        let Point([x, y]) = self.point_at(t);
+        f64::hypot(px - x, py - y)
+    }
+}
+
 impl SVGPath for LineSegment {
-    fn svg_path_command(&self, _: bool) -> String {
-        let Point([x, y]) = self.end;
-        format!("L {x} {y} ")
+    fn svg_path_command(&self, config: &RenderConfig) -> String {
+        if config.relative_commands {
+            format!("l {} ", config.offset(self.start, self.end))
+        } else {
+            format!("L {} ", config.point(self.end))
+        }
     }
 
     fn length(&self) -> PathLength {
@@ -171,111 +698,257 @@ struct LargeArcFlag(bool);
 #[derive(Debug, PartialEq)]
 struct SweepFlag(bool);
 
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Angular extent of an arc in radians, measured counterclockwise from the positive x axis
+pub struct ArcAngles {
+    /// Angle of the start point in (-pi, pi]
+    pub start: f64,
+
+    /// Angle of the end point, equal to start + sweep and therefore not normalized
+    pub end: f64,
+
+    /// Signed angle swept from start to end, negative for clockwise arcs; a full circle if
+    /// start and end coincide
+    pub sweep: f64,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// Reasons the points of an arc don't describe one
+pub enum ArcError {
+    /// Start and center coincide
+    ZeroRadius,
+
+    /// The end point isn't on the circle through the start point
+    EndOffCircle {
+        /// Distance of the start point from the center
+        start_radius: f64,
+
+        /// Distance of the end point from the center
+        end_radius: f64,
+    },
+
+    /// The three points are collinear and lie on no circle
+    Collinear,
+}
+
+impl std::fmt::Display for ArcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArcError::ZeroRadius => write!(f, "arc has zero radius"),
+            ArcError::EndOffCircle {
+                start_radius,
+                end_radius,
+            } => write!(
+                f,
+                "arc end is at radius {end_radius}, but its start at radius {start_radius}"
+            ),
+            ArcError::Collinear => write!(f, "collinear points don't define an arc"),
+        }
+    }
+}
+
+impl std::error::Error for ArcError {}
+
 impl Arc {
+    /// Arc around the center starting at the angle (radians, counterclockwise from the positive
+    /// x axis) and sweeping the signed angle, clockwise if negative; sweeps of a full turn or
+    /// more give a full circle
+    pub fn from_center_angles(center: Point, radius: f64, start_angle: f64, sweep: f64) -> Arc {
+        let Point([cx, cy]) = center;
+        let at = |angle: f64| Point([cx + radius * angle.cos(), cy + radius * angle.sin()]);
+        let start = at(start_angle);
+        Arc {
+            right: sweep < 0.,
+            start,
+            end: match sweep.abs() >= TAU {
+                true => start,
+                false => at(start_angle + sweep),
+            },
+            center,
+        }
+    }
+
+    /// Arc from `a` through `b` to `c`
+    pub fn from_three_points(a: Point, b: Point, c: Point) -> Result<Arc, ArcError> {
+        let (Point([ax, ay]), Point([bx, by]), Point([cx, cy])) = (a, b, c);
+        let cross = (bx - ax) * (cy - ay) - (by - ay) * (cx - ax);
+        let scale = f64::hypot(bx - ax, by - ay) * f64::hypot(cx - ax, cy - ay);
+        if cross.abs() <= 1e-12 * scale {
+            return Err(ArcError::Collinear);
+        }
+        let (a2, b2, c2) = (ax * ax + ay * ay, bx * bx + by * by, cx * cx + cy * cy);
+        let d = 2. * cross;
+        let center = Point([
+            (a2 * (by - cy) + b2 * (cy - ay) + c2 * (ay - by)) / d,
+            (a2 * (cx - bx) + b2 * (ax - cx) + c2 * (bx - ax)) / d,
+        ]);
+        Ok(Arc {
+            right: cross < 0.,
+            start: a,
+            end: c,
+            center,
+        })
+    }
+
+    /// Arc from its points, checked with [Arc::validate]
+    pub fn checked(
+        start: Point,
+        end: Point,
+        center: Point,
+        right: bool,
+        tolerance: f64,
+    ) -> Result<Arc, ArcError> {
+        let arc = Arc {
+            right,
+            start,
+            end,
+            center,
+        };
+        arc.validate(tolerance)?;
+        Ok(arc)
+    }
+
+    /// Checks that the radius is positive and the end point is on the circle within the
+    /// tolerance
+    pub fn validate(&self, tolerance: f64) -> Result<(), ArcError> {
+        let Point([cx, cy]) = self.center;
+        let Point([ex, ey]) = self.end;
+        let (start_radius, end_radius) = (self.radius(), f64::hypot(ex - cx, ey - cy));
+        if start_radius <= tolerance {
+            Err(ArcError::ZeroRadius)
+        } else if (start_radius - end_radius).abs() > tolerance {
+            Err(ArcError::EndOffCircle {
+                start_radius,
+                end_radius,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
     fn svg_representation_values(&self, invert: bool) -> (Radius, LargeArcFlag, SweepFlag) {
+        let ArcAngles { sweep, .. } = self.angles();
+        (
+            Radius(self.radius()),
+            LargeArcFlag(sweep.abs() > PI),
+            SweepFlag((sweep < 0.) ^ invert),
+        )
+    }
+
+    pub fn radius(&self) -> f64 {
         let Point([cx, cy]) = self.center;
         let Point([sx, sy]) = self.start;
-        let radius = f64::hypot(cx - sx, cy - sy);
-
-        if self.start == self.end {
-            return (Radius(radius), LargeArcFlag(true), SweepFlag(false));
-        } else if self.start == self.center {
-            panic!()
-        }
-
-        let o0 = orient2d(self.start.0, self.center.0, self.end.0);
-        let o1 = orient2d(self.start.0, [sx + sy - cy, sy + cx - sx], self.end.0);
-
-        if o0 > 0. {
-            if o1 > 0. {
-                (
-                    Radius(radius),
-                    LargeArcFlag(!self.right),
-                    SweepFlag(!self.right ^ invert),
-                )
-            } else if o1 < 0. {
-                (
-                    Radius(radius),
-                    LargeArcFlag(!self.right),
-                    SweepFlag(self.right ^ invert),
-                )
-            } else {
-                panic!()
-            }
-        } else if o0 < 0. {
-            if o1 > 0. {
-                (
-                    Radius(radius),
-                    LargeArcFlag(self.right),
-                    SweepFlag(!self.right ^ invert),
-                )
-            } else if o1 < 0. {
-                (
-                    Radius(radius),
-                    LargeArcFlag(self.right),
-                    SweepFlag(self.right ^ invert),
-                )
-            } else {
-                panic!()
-            }
+        f64::hypot(sx - cx, sy - cy)
+    }
+
+    /// Signed curvature 1 / r, negative for clockwise arcs
+    pub fn curvature(&self) -> f64 {
+        let curvature = 1. / self.radius();
+        if self.right {
+            -curvature
         } else {
-            if o1 > 0. {
-                (
-                    Radius(radius),
-                    LargeArcFlag(false),
-                    SweepFlag(!self.right ^ invert),
-                )
-            } else if o1 < 0. {
-                (
-                    Radius(radius),
-                    LargeArcFlag(false),
-                    SweepFlag(self.right ^ invert),
-                )
-            } else {
-                (Radius(radius), LargeArcFlag(false), SweepFlag(false))
-            }
+            curvature
         }
     }
 
-    fn radius(&self) -> f64 {
+    /// Start, end, and sweep angles of the arc
+    pub fn angles(&self) -> ArcAngles {
         let Point([cx, cy]) = self.center;
         let Point([sx, sy]) = self.start;
-        f64::hypot(sx - cx, sy - cy)
+        let Point([ex, ey]) = self.end;
+        let start = f64::atan2(sy - cy, sx - cx);
+        let delta = f64::atan2(ey - cy, ex - cx) - start;
+        let positive = |angle: f64| match angle.rem_euclid(TAU) {
+            0. => TAU,
+            a => a,
+        };
+        let sweep = if self.right {
+            -positive(-delta)
+        } else {
+            positive(delta)
+        };
+        ArcAngles {
+            start,
+            end: start + sweep,
+            sweep,
+        }
+    }
+
+    /// Point on the circle at the given angle
+    fn point_at_angle(&self, angle: f64) -> Point {
+        let Point([cx, cy]) = self.center;
+        let r = self.radius();
+        Point([cx + r * angle.cos(), cy + r * angle.sin()])
+    }
+
+    /// Point at parameter t in [0, 1], linear in arc length
+    pub fn point_at(&self, t: f64) -> Point {
+        let ArcAngles { start, sweep, .. } = self.angles();
+        self.point_at_angle(start + t * sweep)
+    }
+
+    /// Whether the direction from the center at the given angle lies within the swept range,
+    /// with a slack of `tolerance` radians on both ends
+    pub fn covers_angle(&self, angle: f64, tolerance: f64) -> bool {
+        let ArcAngles { start, sweep, .. } = self.angles();
+        let offset = ((angle - start) * sweep.signum() + tolerance).rem_euclid(TAU);
+        offset <= sweep.abs() + 2. * tolerance
+    }
+
+    /// Euclidean distance from a point to the arc
+    pub fn distance(&self, point: Point) -> f64 {
+        let Point([cx, cy]) = self.center;
+        let Point([px, py]) = point;
+        let to_endpoint = |Point([x, y]): Point| f64::hypot(px - x, py - y);
+        if self.covers_angle(f64::atan2(py - cy, px - cx), 0.) {
+            (f64::hypot(px - cx, py - cy) - self.radius()).abs()
+        } else {
+            to_endpoint(self.start).min(to_endpoint(self.end))
+        }
+    }
+
+    /// Exact bounding box of the arc
+    pub fn bounding_box(&self) -> Rect {
+        let extremes = (0..4)
+            .map(|k| k as f64 * FRAC_PI_2)
+            .filter(|angle| self.covers_angle(*angle, 0.))
+            .map(|angle| self.point_at_angle(angle));
+        Rect::enclosing([self.start, self.end].into_iter().chain(extremes)).unwrap()
     }
 }
 
 impl SVGPath for Arc {
-    fn svg_path_command(&self, invert_y: bool) -> String {
+    fn svg_path_command(&self, config: &RenderConfig) -> String {
+        // The sweep flag refers to increasing angles in output coordinates, which are
+        // clockwise arcs of the layout if the output mirrors it
         let (Radius(radius), LargeArcFlag(large_arc_flag), SweepFlag(sweep_flag)) =
-            self.svg_representation_values(invert_y);
+            self.svg_representation_values(!config.coordinate_system.flips_y());
         let laf = if large_arc_flag { '1' } else { '0' };
         let sf = if sweep_flag { '1' } else { '0' };
-        let Point([x, y]) = self.end;
-        format!("A {radius} {radius} 0 {laf} {sf} {x} {y} ")
+        let radius = config.length(radius);
+        if config.relative_commands {
+            format!(
+                "a {radius} {radius} 0 {laf} {sf} {} ",
+                config.offset(self.start, self.end)
+            )
+        } else {
+            format!(
+                "A {radius} {radius} 0 {laf} {sf} {} ",
+                config.point(self.end)
+            )
+        }
     }
 
     fn length(&self) -> PathLength {
-        let (_, LargeArcFlag(large_arc_flag), _) = self.svg_representation_values(false);
-        let r = self.radius();
-        let two_r = 2. * r;
-        let Point([sx, sy]) = self.start;
-        let Point([ex, ey]) = self.end;
-        let s = f64::hypot(sx - ex, sy - ey);
-        let short = if s < two_r {
-            two_r * f64::asin(s / two_r)
-        } else {
-            r * std::f64::consts::PI
-        };
-        match large_arc_flag {
-            true => PathLength(two_r - short),
-            false => PathLength(short),
-        }
+        PathLength(self.radius() * self.angles().sweep.abs())
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::base::render::{NumberFormat, Precision};
     mod arc_values {
         use super::*;
 
@@ -335,4 +1008,277 @@ mod test {
             )
         }
     }
+
+    mod arc_angles {
+        use super::*;
+
+        fn quarter(right: bool) -> Arc {
+            Arc {
+                start: Point([1., 0.]),
+                end: Point([0., 1.]),
+                center: Point([0., 0.]),
+                right,
+            }
+        }
+
+        #[test]
+        fn sweep_direction() {
+            assert_eq!(quarter(false).angles().sweep, FRAC_PI_2);
+            assert_eq!(quarter(true).angles().sweep, -3. * FRAC_PI_2);
+            assert!((quarter(false).length().0 - FRAC_PI_2).abs() < 1e-12);
+            assert!((quarter(true).length().0 - 3. * FRAC_PI_2).abs() < 1e-12);
+        }
+
+        #[test]
+        fn svg_coordinates() {
+            let mut path = ChannelPath::new();
+            path.add(PathPiece::Arc(quarter(false)));
+            assert_eq!(
+                path.svg_path_command(&RenderConfig::default()),
+                "M 1 0 A 1 1 0 0 1 0 1 "
+            );
+            // Mirrored into SVG's downward Y axis the same arc turns clockwise
+            let mut config = RenderConfig::y_down();
+            config.coordinate_system.scale = 10.;
+            config.number_format.precision = Precision::Decimals(1);
+            assert_eq!(
+                path.svg_path_command(&config),
+                "M 10.0 0.0 A 10.0 10.0 0 0 0 0.0 -10.0 "
+            );
+
+            path.add(PathPiece::LineSegment(LineSegment {
+                start: Point([0., 1.]),
+                end: Point([-0.123456, 1.]),
+            }));
+            config.number_format = NumberFormat::compact(2);
+            config.relative_commands = true;
+            assert_eq!(
+                path.svg_path_command(&config),
+                "M 10 0 a 10 10 0 0 0 -10 -10 l -1.23 0 "
+            );
+        }
+
+        #[test]
+        fn number_format() {
+            let significant = |digits| NumberFormat {
+                precision: Precision::Significant(digits),
+                trim_zeros: true,
+            };
+            assert_eq!(significant(3).format(125.00000000000001), "125");
+            assert_eq!(significant(3).format(0.0012345), "0.00123");
+            assert_eq!(significant(2).format(-12345.), "-12000");
+            assert_eq!(NumberFormat::compact(3).format(-0.0001), "0");
+            assert_eq!(NumberFormat::compact(3).format(2.5), "2.5");
+            assert_eq!(NumberFormat::default().format(2.5), "2.5");
+        }
+
+        #[test]
+        fn half_circle_length() {
+            let arc = Arc {
+                start: Point([-2., 0.]),
+                end: Point([2., 0.]),
+                center: Point([0., 0.]),
+                right: true,
+            };
+            assert!((arc.length().0 - 2. * PI).abs() < 1e-12);
+            let Point([x, y]) = arc.point_at(0.5);
+            assert!(x.abs() < 1e-12 && (y - 2.).abs() < 1e-12);
+        }
+
+        #[test]
+        fn bounding_box() {
+            let Rect { min, max } = quarter(true).bounding_box();
+            assert_eq!(min, Point([-1., -1.]));
+            assert_eq!(max.0[1], 1.);
+            assert!((max.0[0] - 1.).abs() < 1e-12);
+            let Rect { min, max } = quarter(false).bounding_box();
+            assert!(min.0[0].abs() < 1e-12 && min.0[1] == 0.);
+            assert_eq!(max, Point([1., 1.]));
+        }
+    }
+
+    mod path_operations {
+        use super::*;
+
+        fn path() -> ChannelPath {
+            let mut path = ChannelPath::new();
+            path.add(PathPiece::LineSegment(LineSegment {
+                start: Point([-2., 0.]),
+                end: Point([0., 0.]),
+            }));
+            path.add(PathPiece::Arc(Arc {
+                start: Point([0., 0.]),
+                end: Point([2., 2.]),
+                center: Point([0., 2.]),
+                right: false,
+            }));
+            path
+        }
+
+        fn close(a: Point, b: Point) -> bool {
+            f64::hypot(a.0[0] - b.0[0], a.0[1] - b.0[1]) < 1e-12
+        }
+
+        #[test]
+        fn split_and_concat() {
+            let path = path();
+            let length = path.length().0;
+            let (mut head, tail) = path.split_at(2. + PI / 2.);
+            assert_eq!(head.pieces.len(), 2);
+            assert!((head.length().0 + tail.length().0 - length).abs() < 1e-12);
+            assert!(close(
+                head.pieces[1].end(),
+                Point([2f64.sqrt(), 2. - 2f64.sqrt()])
+            ));
+            head.concat(&tail);
+            assert_eq!(head.pieces.len(), 3);
+            assert!(close(head.pieces[2].end(), Point([2., 2.])));
+
+            let (empty, all) = path.split_at(0.);
+            assert!(empty.pieces.is_empty());
+            assert_eq!(all, path);
+        }
+
+        #[test]
+        fn reverse_and_subpath() {
+            let mut reversed = path();
+            reversed.reverse();
+            assert_eq!(reversed.pieces[0].start(), Point([2., 2.]));
+            assert!(matches!(
+                reversed.pieces[0],
+                PathPiece::Arc(Arc { right: true, .. })
+            ));
+            assert!((reversed.length().0 - path().length().0).abs() < 1e-12);
+
+            let offset = path().offset(0.5).unwrap();
+            assert_eq!(offset.pieces[0].start(), Point([-2., 0.5]));
+            assert!(close(offset.pieces[1].start(), Point([0., 0.5])));
+            assert!(close(offset.pieces[1].end(), Point([1.5, 2.])));
+            assert_eq!(path().offset(2.5), None);
+
+            let sub = path().subpath(3., 1.);
+            assert!((sub.length().0 - 2.).abs() < 1e-12);
+            assert!(close(sub.pieces.last().unwrap().end(), Point([-1., 0.])));
+            assert!(close(
+                sub.pieces[0].start(),
+                path().point_at_length(3.).unwrap()
+            ));
+        }
+    }
+
+    mod construction {
+        use super::*;
+
+        #[test]
+        fn from_angles_and_points() {
+            let arc = Arc::from_center_angles(Point([1., 1.]), 2., 0., -FRAC_PI_2);
+            assert!(arc.right);
+            assert_eq!(arc.start, Point([3., 1.]));
+            assert!(arc.validate(1e-12).is_ok());
+            assert!((arc.angles().sweep + FRAC_PI_2).abs() < 1e-12);
+            let full = Arc::from_center_angles(Point([0., 0.]), 1., 1., TAU);
+            assert_eq!(full.start, full.end);
+            assert!((full.angles().sweep - TAU).abs() < 1e-12);
+
+            let arc =
+                Arc::from_three_points(Point([1., 0.]), Point([0., 1.]), Point([-1., 0.])).unwrap();
+            assert!(!arc.right);
+            assert!(f64::hypot(arc.center.0[0], arc.center.0[1]) < 1e-12);
+            assert!((arc.angles().sweep - PI).abs() < 1e-12);
+            let clockwise =
+                Arc::from_three_points(Point([-1., 0.]), Point([0., 1.]), Point([1., 0.])).unwrap();
+            assert!(clockwise.right);
+            let half = Arc::from_three_points(Point([2., 0.]), Point([1., 1.]), Point([0., 0.]));
+            assert_eq!(half.unwrap().center, Point([1., 0.]));
+            // Off the origin, the middle point has to be on the arc, not on its complement
+            for points in [
+                [Point([8., 2.]), Point([6., 6.]), Point([-1., 5.])],
+                [Point([-1., 5.]), Point([6., 6.]), Point([8., 2.])],
+            ] {
+                let arc = Arc::from_three_points(points[0], points[1], points[2]).unwrap();
+                let Point([x, y]) = arc.center;
+                assert!(f64::hypot(x - 3., y - 2.) < 1e-12, "{x} {y}");
+                assert!(points.iter().all(|p| arc.distance(*p) < 1e-12));
+            }
+            assert_eq!(
+                Arc::from_three_points(Point([0., 0.]), Point([1., 1.]), Point([2., 2.])),
+                Err(ArcError::Collinear)
+            );
+
+            assert_eq!(
+                Arc::checked(
+                    Point([1., 0.]),
+                    Point([0., 1.1]),
+                    Point([0., 0.]),
+                    false,
+                    1e-3
+                ),
+                Err(ArcError::EndOffCircle {
+                    start_radius: 1.,
+                    end_radius: 1.1
+                })
+            );
+            assert_eq!(
+                Arc::checked(
+                    Point([0., 0.]),
+                    Point([0., 0.]),
+                    Point([0., 0.]),
+                    false,
+                    1e-3
+                ),
+                Err(ArcError::ZeroRadius)
+            );
+        }
+    }
+
+    mod closed_paths {
+        use super::*;
+
+        #[test]
+        fn stadium() {
+            // Counterclockwise stadium of two half circles with radius 1 and straight length 2
+            let line = |a, b| {
+                PathPiece::LineSegment(LineSegment {
+                    start: Point(a),
+                    end: Point(b),
+                })
+            };
+            let arc = |a, b, c| {
+                PathPiece::Arc(Arc {
+                    start: Point(a),
+                    end: Point(b),
+                    center: Point(c),
+                    right: false,
+                })
+            };
+            let mut path = ChannelPath::closed(vec![
+                line([0., 0.], [2., 0.]),
+                arc([2., 0.], [2., 2.], [2., 1.]),
+                line([2., 2.], [0., 2.]),
+                arc([0., 2.], [0., 0.], [0., 1.]),
+            ]);
+            let area = 4. + PI;
+            assert_eq!(path.min_radius(), Some(1.));
+            assert_eq!(path.tighter_than(1.5), [1, 3]);
+            assert_eq!(path.pieces[1].curvature(), 1.);
+            assert_eq!(path.pieces[0].curvature(), 0.);
+            assert!((path.signed_area().unwrap() - area).abs() < 1e-12);
+            assert_eq!(path.winding(), Some(Winding::Counterclockwise));
+            assert!(path
+                .svg_path_command(&RenderConfig::default())
+                .ends_with("Z "));
+            assert_eq!(path.check_invariants(), Ok(()));
+
+            path.reverse();
+            assert!((path.signed_area().unwrap() + area).abs() < 1e-12);
+            assert_eq!(path.winding(), Some(Winding::Clockwise));
+            assert_eq!(path.pieces[0].curvature(), -1.);
+
+            // The gap of a missing half circle is closed implicitly
+            path.pieces.remove(0);
+            assert!((path.area().unwrap() - (4. + PI / 2.)).abs() < 1e-12);
+            path.closed = false;
+            assert_eq!(path.area(), None);
+        }
+    }
 }
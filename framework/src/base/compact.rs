@@ -0,0 +1,167 @@
+//! Storage of path geometry with a configurable scalar type. The geometry core computes in
+//! f64; deployments holding many path pieces, e.g., WASM viewers, can keep them as f32 and
+//! convert on use, which halves their memory footprint.
+
+use super::{
+    channel::{Arc, ChannelPath, LineSegment, PathPiece},
+    primitives::Point,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Floating-point type coordinates can be stored in
+pub trait Scalar: Copy + PartialEq + std::fmt::Debug {
+    fn from_f64(value: f64) -> Self;
+
+    fn to_f64(self) -> f64;
+}
+
+impl Scalar for f32 {
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Scalar for f64 {
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+fn store<S: Scalar>(Point([x, y]): Point) -> [S; 2] {
+    [S::from_f64(x), S::from_f64(y)]
+}
+
+fn load<S: Scalar>([x, y]: [S; 2]) -> Point {
+    Point([x.to_f64(), y.to_f64()])
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Path piece with coordinates of the scalar type
+pub enum CompactPiece<S> {
+    Arc {
+        /// Clockwise if set, see [Arc::right]
+        right: bool,
+        start: [S; 2],
+        end: [S; 2],
+        center: [S; 2],
+    },
+
+    LineSegment {
+        start: [S; 2],
+        end: [S; 2],
+    },
+}
+
+impl<S: Scalar> From<&PathPiece> for CompactPiece<S> {
+    fn from(piece: &PathPiece) -> Self {
+        match piece {
+            PathPiece::Arc(arc) => CompactPiece::Arc {
+                right: arc.right,
+                start: store(arc.start),
+                end: store(arc.end),
+                center: store(arc.center),
+            },
+            PathPiece::LineSegment(line) => CompactPiece::LineSegment {
+                start: store(line.start),
+                end: store(line.end),
+            },
+        }
+    }
+}
+
+impl<S: Scalar> CompactPiece<S> {
+    /// The piece in full precision
+    pub fn to_piece(&self) -> PathPiece {
+        match *self {
+            CompactPiece::Arc {
+                right,
+                start,
+                end,
+                center,
+            } => PathPiece::Arc(Arc {
+                right,
+                start: load(start),
+                end: load(end),
+                center: load(center),
+            }),
+            CompactPiece::LineSegment { start, end } => PathPiece::LineSegment(LineSegment {
+                start: load(start),
+                end: load(end),
+            }),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Channel path with coordinates of the scalar type, f32 by default
+pub struct CompactPath<S = f32> {
+    pub pieces: Vec<CompactPiece<S>>,
+
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub closed: bool,
+}
+
+impl<S: Scalar> From<&ChannelPath> for CompactPath<S> {
+    fn from(path: &ChannelPath) -> Self {
+        CompactPath {
+            pieces: path.pieces.iter().map(CompactPiece::from).collect(),
+            closed: path.closed,
+        }
+    }
+}
+
+impl<S: Scalar> CompactPath<S> {
+    /// The path in full precision. Rounded arcs may no longer have exactly equal start and end
+    /// radii; the deviation is within the precision of the scalar type.
+    pub fn to_path(&self) -> ChannelPath {
+        ChannelPath {
+            pieces: self.pieces.iter().map(|p| p.to_piece()).collect(),
+            closed: self.closed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::primitives::Tolerance;
+
+    #[test]
+    fn round_trip() {
+        let mut path = ChannelPath::new();
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: Point([0., 0.]),
+            end: Point([1000.25, 0.]),
+        }));
+        path.add(PathPiece::Arc(Arc::from_center_angles(
+            Point([1000.25, 50.]),
+            50.,
+            -std::f64::consts::FRAC_PI_2,
+            std::f64::consts::PI,
+        )));
+        let compact: CompactPath = (&path).into();
+        assert!(
+            std::mem::size_of::<CompactPiece<f32>>() * 2 <= std::mem::size_of::<PathPiece>() + 8
+        );
+        let restored = compact.to_path();
+        assert_eq!(restored.pieces[0], path.pieces[0]);
+        assert!(restored
+            .check_invariants_within(&Tolerance {
+                absolute: 0.,
+                relative: f32::EPSILON as f64,
+            })
+            .is_ok());
+        assert_eq!(CompactPath::<f64>::from(&path).to_path(), path);
+    }
+}
@@ -0,0 +1,310 @@
+//! Control layers of multilayer soft lithography chips. Valves are pressurized control channels
+//! crossing a flow channel on a second layer; the membrane between the layers deflects into the
+//! flow channel where they overlap. Given valve modules placed over flow channels, the control
+//! layer gets a dead-end channel per valve from a port to the membrane.
+
+use super::{
+    channel::{Channel, ChannelPath, LineSegment, PathPiece, SVGPath, Shape},
+    marking::Marking,
+    network::{ModuleKind, Network, Node, NodeId},
+    port::PortHole,
+    primitives::{Dimensions, Point, Vector2},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Marking layers of the membrane regions are this prefix and the network layer, e.g.,
+/// "MEMBRANES_L1"
+pub const MEMBRANE_LAYER: &str = "MEMBRANES";
+
+/// Points per channel path tested against module footprints
+const SAMPLES: usize = 32;
+
+/// Marking layer of the membrane regions on the network layer
+pub fn membrane_layer(network_layer: usize) -> String {
+    format!("{MEMBRANE_LAYER}_L{network_layer}")
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Geometry of the generated control layer
+pub struct ControlLayerConfig {
+    /// Network layer of the control channels
+    pub layer: usize,
+
+    /// Cross-section of the control channels
+    pub shape: Shape,
+
+    /// Extent of the membranes across and along the flow channel
+    pub membrane: Dimensions,
+
+    /// Length of the control channels from the membrane center to their port, to the left of
+    /// the flow channel's direction
+    pub lead_length: f64,
+
+    /// Hole of the control ports
+    pub port: PortHole,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Control line generated for a valve
+pub struct ControlLine {
+    /// Id of the valve module
+    pub valve: usize,
+
+    /// Id of the flow channel under the valve
+    pub flow_channel: usize,
+
+    /// Id of the control channel
+    pub channel: usize,
+
+    /// Port node of the control channel
+    pub port: NodeId,
+
+    /// Center of the membrane on the flow channel's centerline
+    pub center: Point,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Reasons a control layer can't be generated
+pub enum ControlLayerError {
+    /// The valve module covers no flow channel with a path
+    NoFlowChannel(usize),
+}
+
+impl std::fmt::Display for ControlLayerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ControlLayerError::NoFlowChannel(id) => {
+                write!(f, "valve {id} isn't placed over a flow channel")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ControlLayerError {}
+
+impl Network {
+    /// Marks the membrane rectangle of the dimensions across and along the direction on both
+    /// layers
+    pub(crate) fn add_membrane(
+        &mut self,
+        center: Point,
+        along: Vector2,
+        membrane: Dimensions,
+        layers: [usize; 2],
+    ) {
+        let across = along.perpendicular();
+        let Dimensions([width, extent]) = membrane;
+        let corners = [(-1., -1.), (1., -1.), (1., 1.), (-1., 1.)]
+            .map(|(u, v)| center + along * (u * extent / 2.) + across * (v * width / 2.));
+        let pieces = (0..4)
+            .map(|i| {
+                PathPiece::LineSegment(LineSegment {
+                    start: corners[i],
+                    end: corners[(i + 1) % 4],
+                })
+            })
+            .collect();
+        let membrane = ChannelPath::closed(pieces);
+        for layer in layers {
+            self.markings.push(Marking {
+                layer: membrane_layer(layer),
+                path: membrane.clone(),
+                width: 0.,
+            });
+        }
+    }
+
+    /// Ids of the channels whose paths pass through the footprint of the module
+    pub fn pinched_channels(&self, module: usize) -> Vec<usize> {
+        let Some(module) = self.modules.iter().find(|m| m.id == module) else {
+            return Vec::new();
+        };
+        (self.channels.iter())
+            .filter(|channel| {
+                let Some(path) = &channel.path else {
+                    return false;
+                };
+                let length = path.length().0;
+                (0..SAMPLES)
+                    .filter_map(|i| {
+                        path.point_at_length((i as f64 + 0.5) / SAMPLES as f64 * length)
+                    })
+                    .any(|point| module.contains(point))
+            })
+            .map(|channel| channel.id)
+            .collect()
+    }
+
+    /// Adds a control channel with a port and markings of the membrane region on both layers
+    /// for every valve module. The membrane sits where the valve covers the first flow channel
+    /// not on the control layer, at the point of its centerline closest to the valve center.
+    pub fn add_control_layer(
+        &mut self,
+        config: &ControlLayerConfig,
+    ) -> Result<Vec<ControlLine>, ControlLayerError> {
+        let valves: Vec<_> = (self.modules.iter())
+            .filter(|m| m.kind == ModuleKind::Valve)
+            .map(|m| {
+                let bounds = m.bounding_box();
+                let center = bounds.min + (bounds.max - bounds.min) * 0.5;
+                (m.id, center)
+            })
+            .collect();
+
+        let mut lines = Vec::new();
+        for (valve, center) in valves {
+            let (flow_channel, flow_layer, path) = (self.pinched_channels(valve).into_iter())
+                .filter_map(|id| self.channels.iter().find(|c| c.id == id))
+                .find(|c| c.layer != config.layer)
+                .map(|c| (c.id, c.layer, c.path.clone().unwrap()))
+                .ok_or(ControlLayerError::NoFlowChannel(valve))?;
+
+            // Closest centerline sample and the direction of travel there
+            let length = path.length().0;
+            let step = length / (4 * SAMPLES) as f64;
+            let at = |s: f64| path.point_at_length(s.clamp(0., length)).unwrap();
+            let closest = (0..=4 * SAMPLES)
+                .map(|i| i as f64 * step)
+                .min_by(|a, b| {
+                    let distance = |s| (at(s) - center).length();
+                    distance(*a).total_cmp(&distance(*b))
+                })
+                .unwrap();
+            let along = (at(closest + step) - at(closest - step))
+                .normalized()
+                .unwrap_or(Vector2([1., 0.]));
+            let across = along.perpendicular();
+            let membrane_center = at(closest);
+
+            self.add_membrane(
+                membrane_center,
+                along,
+                config.membrane,
+                [flow_layer, config.layer],
+            );
+
+            let port = self.next_node_id();
+            let end = NodeId(port.0 + 1);
+            let port_position = membrane_center + across * config.lead_length;
+            self.nodes.push(Node {
+                port: Some(config.port.clone()),
+                ..Node::at(port, port_position)
+            });
+            self.nodes.push(Node::at(end, membrane_center));
+            let mut control = ChannelPath::new();
+            control.add(PathPiece::LineSegment(LineSegment {
+                start: port_position,
+                end: membrane_center,
+            }));
+            let channel = self.next_channel_id();
+            self.channels.push(Channel {
+                id: channel,
+                node_a: port,
+                node_b: end,
+                shape: config.shape,
+                path: Some(control),
+                length: None,
+                layer: config.layer,
+                metadata: Default::default(),
+            });
+            lines.push(ControlLine {
+                valve,
+                flow_channel,
+                channel,
+                port,
+                center: membrane_center,
+            });
+        }
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::RectangularShape,
+            network::{Module, ModuleKind},
+            port::ConnectorType,
+        },
+        export::{dxf::network_to_dxf, svg::network_to_svg},
+    };
+
+    #[test]
+    fn control_layer() {
+        let mut path = ChannelPath::new();
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: Point([0., 0.]),
+            end: Point([2000., 0.]),
+        }));
+        let shape = Shape::Rectangular(RectangularShape {
+            width: 100.,
+            height: 25.,
+        });
+        let mut network = Network {
+            nodes: vec![
+                Node::at(NodeId(0), Point([0., 0.])),
+                Node::at(NodeId(1), Point([2000., 0.])),
+            ],
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(1),
+                shape,
+                path: Some(path),
+                length: None,
+                layer: 0,
+                metadata: Default::default(),
+            }],
+            ..Default::default()
+        };
+        network.modules.push(Module {
+            id: 5,
+            position: Point([900., -80.]),
+            size: Dimensions([200., 200.]),
+            nodes: Vec::new(),
+            implementation: None,
+            model: None,
+            footprint: None,
+            orientation: Default::default(),
+            kind: ModuleKind::Valve,
+            metadata: Default::default(),
+        });
+        let config = ControlLayerConfig {
+            layer: 1,
+            shape,
+            membrane: Dimensions([300., 200.]),
+            lead_length: 1500.,
+            port: PortHole {
+                diameter: 750.,
+                connector: ConnectorType::PressFit,
+            },
+        };
+        let lines = network.add_control_layer(&config).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!((lines[0].valve, lines[0].flow_channel), (5, 0));
+        assert!((lines[0].center - Point([1000., 0.])).length() < 2000. / 128.);
+        let control = &network.channels[1];
+        assert_eq!(control.layer, 1);
+        let port = network.node_position(lines[0].port).unwrap();
+        assert!((port.0[1] - 1500.).abs() < 1e-9);
+        assert_eq!(network.markings.len(), 2);
+
+        let dxf = network_to_dxf(&network);
+        for layer in ["CHANNELS_L1", "MEMBRANES_L0", "MEMBRANES_L1"] {
+            assert!(dxf.contains(&format!("8\n{layer}\n")), "{layer}");
+        }
+        assert!(network_to_svg(&network).contains(r#"<g id="channels-l1""#));
+
+        network.modules[0].position = Point([900., 500.]);
+        assert_eq!(
+            network.add_control_layer(&config),
+            Err(ControlLayerError::NoFlowChannel(5))
+        );
+    }
+}
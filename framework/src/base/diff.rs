@@ -0,0 +1,350 @@
+use super::{
+    annotation::Annotation,
+    channel::Channel,
+    feature::SurfaceFeature,
+    group::Group,
+    keepout::KeepOut,
+    layers::LayerStack,
+    marking::Marking,
+    network::{Metadata, Module, Network, Node, NodeId},
+    units::LengthUnit,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, hash::Hash};
+
+/// Network entities that carry a unique id
+pub trait Identifiable {
+    type Key: Clone + Ord + Hash;
+
+    fn key(&self) -> Self::Key;
+}
+
+impl Identifiable for Node {
+    type Key = NodeId;
+
+    fn key(&self) -> NodeId {
+        self.id
+    }
+}
+
+impl Identifiable for Channel {
+    type Key = usize;
+
+    fn key(&self) -> usize {
+        self.id
+    }
+}
+
+impl Identifiable for Module {
+    type Key = usize;
+
+    fn key(&self) -> usize {
+        self.id
+    }
+}
+
+impl Identifiable for KeepOut {
+    type Key = usize;
+
+    fn key(&self) -> usize {
+        self.id
+    }
+}
+
+impl Identifiable for Group {
+    type Key = String;
+
+    fn key(&self) -> String {
+        self.name.clone()
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// State of an entity before and after a modification
+pub struct Modification<T> {
+    /// Entity before the change
+    pub before: T,
+
+    /// Entity after the change
+    pub after: T,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Changes to one kind of entity, matched by id
+pub struct Changes<T> {
+    /// Entities only present in the new network
+    pub added: Vec<T>,
+
+    /// Entities only present in the old network
+    pub removed: Vec<T>,
+
+    /// Entities present in both networks with differing content
+    pub modified: Vec<Modification<T>>,
+}
+
+impl<T> Default for Changes<T> {
+    fn default() -> Self {
+        Changes {
+            added: Vec::new(),
+            removed: Vec::new(),
+            modified: Vec::new(),
+        }
+    }
+}
+
+impl<T: Identifiable + Clone + PartialEq> Changes<T> {
+    fn between(old: &[T], new: &[T]) -> Self {
+        let old_by_key: BTreeMap<_, _> = old.iter().map(|e| (e.key(), e)).collect();
+        let new_by_key: BTreeMap<_, _> = new.iter().map(|e| (e.key(), e)).collect();
+        Changes {
+            added: new
+                .iter()
+                .filter(|e| !old_by_key.contains_key(&e.key()))
+                .cloned()
+                .collect(),
+            removed: old
+                .iter()
+                .filter(|e| !new_by_key.contains_key(&e.key()))
+                .cloned()
+                .collect(),
+            modified: old
+                .iter()
+                .filter_map(|before| match new_by_key.get(&before.key()) {
+                    Some(after) if *after != before => Some(Modification {
+                        before: before.clone(),
+                        after: (*after).clone(),
+                    }),
+                    _ => None,
+                })
+                .collect(),
+        }
+    }
+
+    fn apply(&self, entities: &mut Vec<T>) {
+        entities.retain(|e| !self.removed.iter().any(|r| r.key() == e.key()));
+        for modification in &self.modified {
+            match entities
+                .iter_mut()
+                .find(|e| e.key() == modification.after.key())
+            {
+                Some(entity) => *entity = modification.after.clone(),
+                None => entities.push(modification.after.clone()),
+            }
+        }
+        for added in &self.added {
+            match entities.iter_mut().find(|e| e.key() == added.key()) {
+                Some(entity) => *entity = added.clone(),
+                None => entities.push(added.clone()),
+            }
+        }
+    }
+
+    fn inverse(&self) -> Self {
+        Changes {
+            added: self.removed.clone(),
+            removed: self.added.clone(),
+            modified: self
+                .modified
+                .iter()
+                .map(|m| Modification {
+                    before: m.after.clone(),
+                    after: m.before.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl<T: Clone + PartialEq> Modification<T> {
+    /// Replacement of a section without ids as a whole, None if it is unchanged
+    fn between(before: &T, after: &T) -> Option<Self> {
+        (before != after).then(|| Modification {
+            before: before.clone(),
+            after: after.clone(),
+        })
+    }
+
+    fn inverse(&self) -> Self {
+        Modification {
+            before: self.after.clone(),
+            after: self.before.clone(),
+        }
+    }
+}
+
+fn replace<T: Clone>(section: &mut T, modification: &Option<Modification<T>>) {
+    if let Some(modification) = modification {
+        *section = modification.after.clone();
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Structured set of changes transforming one network into another. Entities with ids or
+/// names are matched individually, the sections without are replaced as a whole.
+pub struct Changeset {
+    /// Node changes
+    pub nodes: Changes<Node>,
+
+    /// Channel changes
+    pub channels: Changes<Channel>,
+
+    /// Module changes
+    pub modules: Changes<Module>,
+
+    /// Keep-out changes
+    #[serde(default)]
+    pub keep_outs: Changes<KeepOut>,
+
+    /// Group changes, matched by name
+    #[serde(default)]
+    pub groups: Changes<Group>,
+
+    /// Replaced annotations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Modification<Vec<Annotation>>>,
+
+    /// Replaced surface features
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub surface_features: Option<Modification<Vec<SurfaceFeature>>>,
+
+    /// Replaced markings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub markings: Option<Modification<Vec<Marking>>>,
+
+    /// Replaced layer stack
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer_stack: Option<Modification<Option<LayerStack>>>,
+
+    /// Replaced length unit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length_unit: Option<Modification<Option<LengthUnit>>>,
+
+    /// Replaced metadata
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Modification<Metadata>>,
+}
+
+impl Changeset {
+    /// Changeset reverting this one
+    pub fn inverse(&self) -> Changeset {
+        Changeset {
+            nodes: self.nodes.inverse(),
+            channels: self.channels.inverse(),
+            modules: self.modules.inverse(),
+            keep_outs: self.keep_outs.inverse(),
+            groups: self.groups.inverse(),
+            annotations: self.annotations.as_ref().map(Modification::inverse),
+            surface_features: self.surface_features.as_ref().map(Modification::inverse),
+            markings: self.markings.as_ref().map(Modification::inverse),
+            layer_stack: self.layer_stack.as_ref().map(Modification::inverse),
+            length_unit: self.length_unit.as_ref().map(Modification::inverse),
+            metadata: self.metadata.as_ref().map(Modification::inverse),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+            && self.channels.is_empty()
+            && self.modules.is_empty()
+            && self.keep_outs.is_empty()
+            && self.groups.is_empty()
+            && self.annotations.is_none()
+            && self.surface_features.is_none()
+            && self.markings.is_none()
+            && self.layer_stack.is_none()
+            && self.length_unit.is_none()
+            && self.metadata.is_none()
+    }
+}
+
+impl Network {
+    /// Changes needed to turn this network into `other`
+    pub fn diff(&self, other: &Network) -> Changeset {
+        Changeset {
+            nodes: Changes::between(&self.nodes, &other.nodes),
+            channels: Changes::between(&self.channels, &other.channels),
+            modules: Changes::between(&self.modules, &other.modules),
+            keep_outs: Changes::between(&self.keep_outs, &other.keep_outs),
+            groups: Changes::between(&self.groups, &other.groups),
+            annotations: Modification::between(&self.annotations, &other.annotations),
+            surface_features: Modification::between(
+                &self.surface_features,
+                &other.surface_features,
+            ),
+            markings: Modification::between(&self.markings, &other.markings),
+            layer_stack: Modification::between(&self.layer_stack, &other.layer_stack),
+            length_unit: Modification::between(&self.length_unit, &other.length_unit),
+            metadata: Modification::between(&self.metadata, &other.metadata),
+        }
+    }
+
+    /// Applies a changeset. Removals are applied first, modifications of missing entities
+    /// and additions of existing ids overwrite, so merging is idempotent.
+    pub fn merge(&mut self, changeset: &Changeset) {
+        changeset.nodes.apply(&mut self.nodes);
+        changeset.channels.apply(&mut self.channels);
+        changeset.modules.apply(&mut self.modules);
+        changeset.keep_outs.apply(&mut self.keep_outs);
+        changeset.groups.apply(&mut self.groups);
+        replace(&mut self.annotations, &changeset.annotations);
+        replace(&mut self.surface_features, &changeset.surface_features);
+        replace(&mut self.markings, &changeset.markings);
+        replace(&mut self.layer_stack, &changeset.layer_stack);
+        replace(&mut self.length_unit, &changeset.length_unit);
+        replace(&mut self.metadata, &changeset.metadata);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            annotation::{Anchor, Label},
+            network::EntityRef,
+            primitives::{Dimensions, Point},
+        },
+        fixtures::trap_array,
+    };
+
+    #[test]
+    fn diff_merge_round_trip() {
+        let a = trap_array(1, 2).network;
+        let mut b = a.clone();
+        let removed = b.channels.pop().unwrap();
+        b.channels[0].length = Some(1e-3);
+        b.nodes.push(Node::new(b.next_node_id()));
+        b.add_to_group("inlets", EntityRef::Channel(b.channels[0].id));
+        b.annotations.push(Annotation::Label(Label {
+            text: "inlet".into(),
+            anchor: Anchor::Point(Point([0., 0.])),
+            offset: Dimensions([0., 0.]),
+            height: 1e-4,
+        }));
+        b.metadata.insert("revision".into(), 2.into());
+
+        let changeset = a.diff(&b);
+        assert_eq!(changeset.channels.removed, [removed]);
+        assert_eq!(changeset.channels.modified.len(), 1);
+        assert_eq!(changeset.groups.added.len(), 1);
+        assert!(changeset.annotations.is_some() && changeset.surface_features.is_none());
+        let mut merged = a.clone();
+        merged.merge(&changeset);
+        assert_eq!(merged, b);
+        merged.merge(&changeset);
+        assert_eq!(merged, b);
+
+        merged.merge(&changeset.inverse());
+        assert_eq!(merged, a);
+        assert_eq!(changeset.inverse().inverse(), changeset);
+        assert!(a.diff(&a).is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+use super::{
+    channel::Channel,
+    diff::{Changes, Changeset, Modification},
+    network::{Module, Network, Node, NodeId},
+    primitives::Point,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Editing operation on a network
+pub enum Command {
+    /// Adds a node, the id must not be in use
+    AddNode(Node),
+
+    /// Adds a channel, the id must not be in use
+    AddChannel(Channel),
+
+    /// Adds a module, the id must not be in use
+    AddModule(Module),
+
+    /// Removes a node together with all channels attached to it
+    RemoveNode(NodeId),
+
+    /// Removes a channel
+    RemoveChannel(usize),
+
+    /// Removes a module
+    RemoveModule(usize),
+
+    /// Moves a module to a new position
+    MoveModule {
+        /// Id of the module
+        id: usize,
+
+        /// New position of the module
+        position: Point,
+    },
+
+    /// Connects a channel to a new pair of nodes
+    RerouteChannel {
+        /// Id of the channel
+        id: usize,
+
+        /// New start node
+        node_a: NodeId,
+
+        /// New end node
+        node_b: NodeId,
+    },
+
+    /// Splits a channel by inserting a node, see [Network::split_channel]
+    SplitChannel {
+        /// Id of the channel
+        id: usize,
+
+        /// Arc length along the channel path to split at
+        length: f64,
+    },
+
+    /// Merges a node into another, see [Network::merge_nodes]
+    MergeNodes {
+        /// Node the channels and modules attach to afterwards
+        keep: NodeId,
+
+        /// Node that is removed
+        remove: NodeId,
+    },
+
+    /// Joins the two channels at a node, see [Network::collapse_node]
+    CollapseNode(NodeId),
+
+    /// Removes a channel and the nodes it leaves unattached, see [Network::delete_channel]
+    DeleteChannel(usize),
+
+    /// Removes a node, its channels, and the nodes left unattached, see [Network::delete_node]
+    DeleteNode(NodeId),
+}
+
+/// Changes of a topology edit applied to a copy of the network, None if it fails
+fn edited(network: &Network, edit: impl FnOnce(&mut Network) -> bool) -> Option<Changeset> {
+    let mut after = network.clone();
+    edit(&mut after).then(|| network.diff(&after))
+}
+
+impl Command {
+    /// Changes performed by the command when executed on the given network, or None if the
+    /// command isn't applicable
+    pub fn changeset(&self, network: &Network) -> Option<Changeset> {
+        let mut changeset = Changeset::default();
+        let node_exists = |id: NodeId| network.nodes.iter().any(|n| n.id == id);
+        match self {
+            Command::AddNode(node) => {
+                if node_exists(node.id) {
+                    return None;
+                }
+                changeset.nodes.added.push(node.clone());
+            }
+            Command::AddChannel(channel) => {
+                if network.channels.iter().any(|c| c.id == channel.id)
+                    || !node_exists(channel.node_a)
+                    || !node_exists(channel.node_b)
+                {
+                    return None;
+                }
+                changeset.channels.added.push(channel.clone());
+            }
+            Command::AddModule(module) => {
+                if network.modules.iter().any(|m| m.id == module.id)
+                    || !module.nodes.iter().all(|n| node_exists(*n))
+                {
+                    return None;
+                }
+                changeset.modules.added.push(module.clone());
+            }
+            Command::RemoveNode(id) => {
+                let node = network.nodes.iter().find(|n| n.id == *id)?;
+                changeset.nodes.removed.push(node.clone());
+                changeset.channels.removed = network
+                    .channels
+                    .iter()
+                    .filter(|c| c.node_a == *id || c.node_b == *id)
+                    .cloned()
+                    .collect();
+                changeset.modules.modified = network
+                    .modules
+                    .iter()
+                    .filter(|m| m.nodes.contains(id))
+                    .map(|m| Modification {
+                        before: m.clone(),
+                        after: Module {
+                            nodes: m.nodes.iter().copied().filter(|n| n != id).collect(),
+                            model: None,
+                            ..m.clone()
+                        },
+                    })
+                    .collect();
+            }
+            Command::RemoveChannel(id) => {
+                let channel = network.channels.iter().find(|c| c.id == *id)?;
+                changeset.channels.removed.push(channel.clone());
+            }
+            Command::RemoveModule(id) => {
+                let module = network.modules.iter().find(|m| m.id == *id)?;
+                changeset.modules.removed.push(module.clone());
+            }
+            Command::MoveModule { id, position } => {
+                let module = network.modules.iter().find(|m| m.id == *id)?;
+                changeset.modules.modified.push(Modification {
+                    before: module.clone(),
+                    after: Module {
+                        position: *position,
+                        ..module.clone()
+                    },
+                });
+            }
+            Command::RerouteChannel { id, node_a, node_b } => {
+                let channel = network.channels.iter().find(|c| c.id == *id)?;
+                if !node_exists(*node_a) || !node_exists(*node_b) {
+                    return None;
+                }
+                changeset.channels = Changes {
+                    modified: vec![Modification {
+                        before: channel.clone(),
+                        after: Channel {
+                            node_a: *node_a,
+                            node_b: *node_b,
+                            ..channel.clone()
+                        },
+                    }],
+                    ..Default::default()
+                };
+            }
+            Command::SplitChannel { id, length } => {
+                return edited(network, |n| n.split_channel(*id, *length).is_some());
+            }
+            Command::MergeNodes { keep, remove } => {
+                return edited(network, |n| n.merge_nodes(*keep, *remove));
+            }
+            Command::CollapseNode(id) => {
+                return edited(network, |n| n.collapse_node(*id).is_some());
+            }
+            Command::DeleteChannel(id) => return edited(network, |n| n.delete_channel(*id)),
+            Command::DeleteNode(id) => return edited(network, |n| n.delete_node(*id)),
+        }
+        Some(changeset)
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// A network together with its editing history
+pub struct EditSession {
+    /// The edited network
+    pub network: Network,
+
+    /// Changesets of executed commands, most recent last
+    undo_stack: Vec<Changeset>,
+
+    /// Changesets of undone commands, most recent last
+    redo_stack: Vec<Changeset>,
+}
+
+impl EditSession {
+    pub fn new(network: Network) -> Self {
+        EditSession {
+            network,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    /// Executes a command and records it in the history. Returns false if the command isn't
+    /// applicable to the current network.
+    pub fn execute(&mut self, command: &Command) -> bool {
+        match command.changeset(&self.network) {
+            Some(changeset) => {
+                self.network.merge(&changeset);
+                self.undo_stack.push(changeset);
+                self.redo_stack.clear();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reverts the most recent command, returns false if there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(changeset) => {
+                self.network.merge(&changeset.inverse());
+                self.redo_stack.push(changeset);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Re-executes the most recently undone command, returns false if there is nothing to redo
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(changeset) => {
+                self.network.merge(&changeset);
+                self.undo_stack.push(changeset);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn undo_redo_history() {
+        let node = |id| Node::at(NodeId(id), Point([id as f64, 0.]));
+        let mut session = EditSession::new(Network::default());
+        assert!(!session.undo() && !session.redo());
+        assert!(session.execute(&Command::AddNode(node(0))));
+        assert!(session.execute(&Command::AddNode(node(1))));
+        assert!(session.execute(&Command::RemoveNode(NodeId(0))));
+        assert_eq!(session.network.nodes, [node(1)]);
+
+        // Undo reverts the most recent command first, redo re-executes in the original order
+        assert!(session.undo());
+        assert_eq!(session.network.nodes, [node(1), node(0)]);
+        assert!(session.undo());
+        assert_eq!(session.network.nodes, [node(0)]);
+        assert!(session.redo());
+        assert_eq!(session.network.nodes, [node(0), node(1)]);
+        assert!(session.can_undo() && session.can_redo());
+
+        // Rejected commands leave the network and both stacks untouched
+        let before = session.clone();
+        assert!(!session.execute(&Command::AddNode(node(0))));
+        assert!(!session.execute(&Command::RemoveChannel(0)));
+        assert!(!session.execute(&Command::MergeNodes {
+            keep: NodeId(0),
+            remove: NodeId(0),
+        }));
+        assert_eq!(session, before);
+
+        // A new command discards the undone ones
+        assert!(session.execute(&Command::AddNode(node(2))));
+        assert!(!session.can_redo() && !session.redo());
+        assert_eq!(session.undo_stack.len(), 3);
+        while session.undo() {}
+        assert!(session.network.nodes.is_empty());
+        assert_eq!(session.redo_stack.len(), 3);
+    }
+}
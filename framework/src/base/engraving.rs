@@ -0,0 +1,210 @@
+//! Engraved text: labels converted to strokes of a built-in single-line font, so that chip ids
+//! and port labels appear on masks and milled parts as geometry rather than as text entities,
+//! which fabrication formats drop or render in arbitrary fonts.
+
+use super::{
+    annotation::Annotation,
+    channel::{ChannelPath, LineSegment, PathPiece},
+    marking::Marking,
+    network::Network,
+    primitives::Point,
+};
+
+/// Glyph cells are 4 units wide and 6 units high, the cap height
+const CELL_HEIGHT: f64 = 6.;
+
+/// Distance between the origins of neighboring glyphs in font units
+const ADVANCE: f64 = 6.;
+
+/// Strokes of a glyph as space separated polylines of points, each written as the digits of
+/// its x and y in font units; None for characters without a glyph
+fn glyph(c: char) -> Option<&'static str> {
+    Some(match c.to_ascii_uppercase() {
+        'A' => "0004264440 0343",
+        'B' => "00063645443303 3342413000",
+        'C' => "4536160501103041",
+        'D' => "00062644422000",
+        'E' => "40000646 0333",
+        'F' => "000646 0333",
+        'G' => "45361605011030414323",
+        'H' => "0006 4046 0343",
+        'I' => "1030 2026 1636",
+        'J' => "0110203136 2646",
+        'K' => "0006 4602 1340",
+        'L' => "060040",
+        'M' => "0006234640",
+        'N' => "00064046",
+        'O' => "100105163645413010",
+        'P' => "00063645443303",
+        'Q' => "100105163645413010 2240",
+        'R' => "00063645443303 2340",
+        'S' => "453616050413334241301001",
+        'T' => "0646 2620",
+        'U' => "060110304146",
+        'V' => "062046",
+        'W' => "0610233046",
+        'X' => "0046 0640",
+        'Y' => "062346 2320",
+        'Z' => "06464000",
+        '0' => "100105163645413010 0145",
+        '1' => "152620 1030",
+        '2' => "05163645440040",
+        '3' => "0516364544334241301001 1333",
+        '4' => "30360242",
+        '5' => "4606043443413000",
+        '6' => "4536160501103041423303",
+        '7' => "064610",
+        '8' => "13040516364544331302011030414233",
+        '9' => "0110304145361605041343",
+        '-' => "0343",
+        '+' => "0343 2125",
+        '.' => "2021",
+        '_' => "0040",
+        '/' => "0046",
+        ':' => "2122 2425",
+        '(' => "36252130",
+        ')' => "16252110",
+        ' ' => "",
+        _ => return None,
+    })
+}
+
+/// Width of the text in the built-in font at the cap height
+pub fn text_width(text: &str, height: f64) -> f64 {
+    let count = text.chars().count();
+    let cells = if count == 0 {
+        0.
+    } else {
+        (count - 1) as f64 * ADVANCE + 4.
+    };
+    cells * height / CELL_HEIGHT
+}
+
+/// Open strokes of the text in the built-in font, starting at the position on the baseline,
+/// with the cap height. Characters without a glyph, anything but letters, digits, and
+/// `-+._/:()`, are left blank.
+pub fn text_strokes(text: &str, position: Point, height: f64) -> Vec<ChannelPath> {
+    let scale = height / CELL_HEIGHT;
+    let Point([x0, y0]) = position;
+    let mut strokes = Vec::new();
+    for (i, c) in text.chars().enumerate() {
+        let Some(glyph) = glyph(c) else {
+            continue;
+        };
+        let origin = x0 + i as f64 * ADVANCE * scale;
+        for stroke in glyph.split_whitespace() {
+            let points: Vec<Point> = (stroke.as_bytes().chunks(2))
+                .map(|digits| {
+                    let [x, y] = [digits[0], digits[1]].map(|d| (d - b'0') as f64);
+                    Point([origin + x * scale, y0 + y * scale])
+                })
+                .collect();
+            let mut path = ChannelPath::new();
+            for pair in points.windows(2) {
+                path.add(PathPiece::LineSegment(LineSegment {
+                    start: pair[0],
+                    end: pair[1],
+                }));
+            }
+            strokes.push(path);
+        }
+    }
+    strokes
+}
+
+impl Network {
+    /// Engraves the text as markings on the layer, stroked with the width
+    pub fn engrave_text(
+        &mut self,
+        layer: &str,
+        text: &str,
+        position: Point,
+        height: f64,
+        stroke_width: f64,
+    ) {
+        self.markings.extend(
+            text_strokes(text, position, height)
+                .into_iter()
+                .map(|path| Marking {
+                    layer: layer.into(),
+                    path,
+                    width: stroke_width,
+                }),
+        );
+    }
+
+    /// Replaces all labels with a resolvable anchor by engraved markings of their text on the
+    /// layer. Returns the number of engraved labels.
+    pub fn engrave_labels(&mut self, layer: &str, stroke_width: f64) -> usize {
+        let mut engraved = Vec::new();
+        let annotations = std::mem::take(&mut self.annotations);
+        for annotation in annotations {
+            match &annotation {
+                Annotation::Label(label) => match self.resolve_anchor(&label.anchor) {
+                    Some(anchor) => {
+                        let [dx, dy] = label.offset.0;
+                        engraved.push((
+                            label.text.clone(),
+                            anchor.translated(Point([dx, dy])),
+                            label.height,
+                        ));
+                    }
+                    None => self.annotations.push(annotation),
+                },
+                Annotation::Dimension(_) => self.annotations.push(annotation),
+            }
+        }
+        for (text, position, height) in &engraved {
+            self.engrave_text(layer, text, *position, *height, stroke_width);
+        }
+        engraved.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            annotation::{Anchor, Label},
+            primitives::Dimensions,
+        },
+        export::{
+            dxf::network_to_dxf,
+            gcode::{network_to_gcode, MachineProfile},
+        },
+    };
+
+    #[test]
+    fn engraved_labels() {
+        let strokes = text_strokes("A1?", Point([10., 0.]), 60.);
+        assert_eq!(strokes.len(), 4);
+        assert_eq!(strokes[2].pieces[0].start(), Point([10. + 60. + 10., 50.]));
+        assert_eq!(text_width("A1?", 60.), 160.);
+        for c in "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-+._/:()".chars() {
+            for stroke in glyph(c).unwrap().split_whitespace() {
+                assert!(stroke.len() >= 4 && stroke.len() % 2 == 0, "{c}");
+                assert!(stroke.bytes().all(|d| (b'0'..=b'6').contains(&d)), "{c}");
+            }
+        }
+
+        let mut network = Network::default();
+        network.annotations.push(Annotation::Label(Label {
+            text: "ID-7".into(),
+            anchor: Anchor::Point(Point([0., 0.])),
+            offset: Dimensions([100., 0.]),
+            height: 600.,
+        }));
+        assert_eq!(network.engrave_labels("TEXT", 100.), 1);
+        assert!(network.annotations.is_empty());
+        assert_eq!(network.markings.len(), 3 + 1 + 1 + 1);
+        assert!(network_to_dxf(&network).contains("8\nTEXT\n"));
+
+        let profile = MachineProfile {
+            engraving_depth: 20.,
+            ..Default::default()
+        };
+        let gcode = network_to_gcode(&network, &profile).unwrap();
+        assert_eq!(gcode.matches("G1 Z-0.0200").count(), 6);
+    }
+}
@@ -0,0 +1,185 @@
+use super::{
+    diff::{Changes, Changeset, Identifiable},
+    edit::Command,
+    network::{EntityRef, Network},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Change notification for a single entity
+pub enum NetworkEvent {
+    /// The entity was added to the network
+    Added(EntityRef),
+
+    /// The entity was removed from the network
+    Removed(EntityRef),
+
+    /// The content of the entity changed
+    Modified(EntityRef),
+}
+
+impl Changeset {
+    /// Events describing the node, channel, and module changes of the changeset, in the order
+    /// removals, modifications, additions
+    pub fn events(&self) -> Vec<NetworkEvent> {
+        fn collect<T: Identifiable>(
+            changes: &Changes<T>,
+            entity: impl Fn(T::Key) -> EntityRef,
+            events: &mut [Vec<NetworkEvent>; 3],
+        ) {
+            let [removed, modified, added] = events;
+            removed.extend(
+                changes
+                    .removed
+                    .iter()
+                    .map(|e| NetworkEvent::Removed(entity(e.key()))),
+            );
+            modified.extend(
+                changes
+                    .modified
+                    .iter()
+                    .map(|m| NetworkEvent::Modified(entity(m.after.key()))),
+            );
+            added.extend(
+                changes
+                    .added
+                    .iter()
+                    .map(|e| NetworkEvent::Added(entity(e.key()))),
+            );
+        }
+
+        let mut events = [Vec::new(), Vec::new(), Vec::new()];
+        collect(&self.nodes, EntityRef::Node, &mut events);
+        collect(&self.channels, EntityRef::Channel, &mut events);
+        collect(&self.modules, EntityRef::Module, &mut events);
+        events.concat()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Handle of a registered observer, used to unsubscribe
+pub struct ObserverId(usize);
+
+type Observer = Box<dyn FnMut(&Network, &NetworkEvent)>;
+
+/// A network that notifies registered observers about every mutation applied through it
+pub struct ObservableNetwork {
+    network: Network,
+    observers: Vec<(ObserverId, Observer)>,
+    next_observer: usize,
+}
+
+impl ObservableNetwork {
+    pub fn new(network: Network) -> Self {
+        ObservableNetwork {
+            network,
+            observers: Vec::new(),
+            next_observer: 0,
+        }
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    pub fn into_inner(self) -> Network {
+        self.network
+    }
+
+    /// Registers a callback that receives the updated network and each event
+    pub fn subscribe(
+        &mut self,
+        observer: impl FnMut(&Network, &NetworkEvent) + 'static,
+    ) -> ObserverId {
+        let id = ObserverId(self.next_observer);
+        self.next_observer += 1;
+        self.observers.push((id, Box::new(observer)));
+        id
+    }
+
+    /// Removes an observer, returns false if it wasn't registered
+    pub fn unsubscribe(&mut self, id: ObserverId) -> bool {
+        let count = self.observers.len();
+        self.observers.retain(|(observer, _)| *observer != id);
+        self.observers.len() != count
+    }
+
+    /// Applies a changeset and notifies all observers
+    pub fn apply(&mut self, changeset: &Changeset) {
+        self.network.merge(changeset);
+        for event in changeset.events() {
+            for (_, observer) in self.observers.iter_mut() {
+                observer(&self.network, &event);
+            }
+        }
+    }
+
+    /// Executes a command and notifies all observers. Returns the applied changeset, or None
+    /// if the command isn't applicable.
+    pub fn execute(&mut self, command: &Command) -> Option<Changeset> {
+        let changeset = command.changeset(&self.network)?;
+        self.apply(&changeset);
+        Some(changeset)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::{Channel, CylindricalShape, Shape},
+        network::{Node, NodeId},
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn observed_mutations() {
+        let network = Network {
+            nodes: vec![Node::new(NodeId(0)), Node::new(NodeId(1))],
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(1),
+                shape: Shape::Cylindrical(CylindricalShape { radius: 1. }),
+                path: None,
+                length: Some(10.),
+                layer: 0,
+                metadata: Default::default(),
+            }],
+            ..Default::default()
+        };
+        let mut observable = ObservableNetwork::new(network.clone());
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let observer = observable.subscribe({
+            let events = events.clone();
+            move |network, event| events.borrow_mut().push((network.nodes.len(), *event))
+        });
+
+        // Observers see the network after the whole changeset was applied
+        let changeset = observable.execute(&Command::RemoveNode(NodeId(0))).unwrap();
+        let removed = [
+            (1, NetworkEvent::Removed(EntityRef::Node(NodeId(0)))),
+            (1, NetworkEvent::Removed(EntityRef::Channel(0))),
+        ];
+        assert_eq!(*events.borrow(), removed);
+        assert!(observable.execute(&Command::RemoveChannel(0)).is_none());
+        assert_eq!(events.borrow().len(), 2);
+
+        events.borrow_mut().clear();
+        observable.apply(&changeset.inverse());
+        let added = [
+            (2, NetworkEvent::Added(EntityRef::Node(NodeId(0)))),
+            (2, NetworkEvent::Added(EntityRef::Channel(0))),
+        ];
+        assert_eq!(*events.borrow(), added);
+        assert_eq!(observable.network().channels, network.channels);
+
+        assert!(observable.unsubscribe(observer));
+        assert!(!observable.unsubscribe(observer));
+        observable.execute(&Command::RemoveChannel(0)).unwrap();
+        assert_eq!(events.borrow().len(), 2);
+        assert!(observable.into_inner().channels.is_empty());
+    }
+}
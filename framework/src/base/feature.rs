@@ -0,0 +1,220 @@
+//! Surface features structured into a channel's floor or ceiling, e.g., the grooves of a
+//! staggered herringbone mixer (Stroock et al., 2002). Grooves are shallower than the channel
+//! and fabricated as a second lithography layer, so they are kept apart from the channel's
+//! cross-section and exported as polygons of their own.
+
+use super::{
+    channel::{Channel, ChannelPath, SVGPath},
+    network::Network,
+    polygon::Polygon,
+    primitives::{Point, Vector2},
+};
+use crate::parallel;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Arrangement of the grooves across the channel
+pub enum GroovePattern {
+    /// Chevrons whose apex switches sides after every cycle of grooves, driving two
+    /// counter-rotating vortices of alternating size
+    Herringbone {
+        /// Position of the apex across the width, from the right to the left wall in flow
+        /// direction, e.g., 2/3
+        asymmetry: f64,
+
+        /// Number of grooves before the apex switches sides
+        grooves_per_cycle: usize,
+    },
+
+    /// Straight grooves across the whole width, driving a single helical vortex
+    Slanted,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Grooves along part of a channel
+pub struct SurfaceFeature {
+    /// Id of the channel
+    pub channel: usize,
+
+    pub pattern: GroovePattern,
+
+    /// Arc length along the channel path where the grooves begin
+    pub start: f64,
+
+    /// Arc length along the channel path where the grooves end
+    pub end: f64,
+
+    /// Extent of a groove along the channel
+    pub groove_width: f64,
+
+    /// Distance along the channel between the starts of neighboring grooves
+    pub pitch: f64,
+
+    /// Angle in radians between the grooves and the channel axis, e.g., π/4
+    pub angle: f64,
+
+    /// Depth of the grooves into the channel wall
+    pub depth: f64,
+}
+
+impl ChannelPath {
+    /// Point and unit direction of travel at the given arc length from the start, clamped to the
+    /// path; None for an empty path
+    pub fn frame_at_length(&self, length: f64) -> Option<(Point, Vector2)> {
+        let mut travelled = 0.;
+        for (i, piece) in self.pieces.iter().enumerate() {
+            let piece_length = piece.length().0;
+            if travelled + piece_length >= length || i + 1 == self.pieces.len() {
+                let t = if piece_length > 0. {
+                    ((length - travelled) / piece_length).clamp(0., 1.)
+                } else {
+                    0.
+                };
+                return Some((piece.point_at(t), Vector2(piece.tangent_at(t))));
+            }
+            travelled += piece_length;
+        }
+        None
+    }
+}
+
+impl SurfaceFeature {
+    /// Outlines of the grooves in layout coordinates, following the channel path; empty if the
+    /// channel has no path. Grooves that wouldn't end before the feature's end are left out.
+    pub fn grooves(&self, channel: &Channel) -> Vec<Polygon> {
+        let Some(path) = &channel.path else {
+            return Vec::new();
+        };
+        let half = channel.shape.width() / 2.;
+        let slope = 1. / self.angle.tan();
+        // Across-channel positions of the groove's vertices, left of the axis positive
+        let apexes = |index: usize| match self.pattern {
+            GroovePattern::Herringbone {
+                asymmetry,
+                grooves_per_cycle,
+            } => {
+                let apex = (asymmetry - 0.5) * 2. * half;
+                let flipped = (index / grooves_per_cycle.max(1)) % 2 == 1;
+                vec![-half, if flipped { -apex } else { apex }, half]
+            }
+            GroovePattern::Slanted => vec![-half, half],
+        };
+        let place = |along: f64, across: f64| {
+            let (point, direction) = path.frame_at_length(along).unwrap();
+            point + direction.perpendicular() * across
+        };
+
+        let mut grooves = Vec::new();
+        for index in 0.. {
+            let base = self.start + index as f64 * self.pitch;
+            let across = apexes(index);
+            // Along-channel offset of each vertex behind the leading one
+            let leading = *across
+                .get(1)
+                .filter(|_| across.len() == 3)
+                .unwrap_or(&-half);
+            let offsets: Vec<f64> = across.iter().map(|n| (n - leading).abs() * slope).collect();
+            let length = offsets.iter().fold(0f64, |m, o| m.max(*o)) + self.groove_width;
+            if self.pitch <= 0. || base + length > self.end {
+                break;
+            }
+            // Counterclockwise: the front edge from left to right, the back edge returning
+            let front = across
+                .iter()
+                .zip(&offsets)
+                .rev()
+                .map(|(n, o)| place(base + o, *n));
+            let back = across
+                .iter()
+                .zip(&offsets)
+                .map(|(n, o)| place(base + o + self.groove_width, *n));
+            grooves.push(Polygon(front.chain(back).collect()));
+        }
+        grooves
+    }
+}
+
+impl Network {
+    /// Groove outlines of all surface features whose channel has a path, in feature order;
+    /// the features are outlined in parallel
+    pub fn groove_outlines(&self) -> Vec<Polygon> {
+        parallel::map(&self.surface_features, |feature| {
+            let channel = self.channels.iter().find(|c| c.id == feature.channel);
+            channel.map(|channel| feature.grooves(channel))
+        })
+        .into_iter()
+        .flatten()
+        .flatten()
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{LineSegment, PathPiece, RectangularShape, Shape},
+            network::NodeId,
+            polygon::Winding,
+        },
+        export::dxf::network_to_dxf,
+    };
+    use std::f64::consts::FRAC_PI_4;
+
+    #[test]
+    fn herringbone() {
+        let mut path = ChannelPath::new();
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: Point([0., 0.]),
+            end: Point([1000., 0.]),
+        }));
+        let network = Network {
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(1),
+                shape: Shape::Rectangular(RectangularShape {
+                    width: 300.,
+                    height: 80.,
+                }),
+                path: Some(path),
+                length: None,
+                layer: 0,
+                metadata: Default::default(),
+            }],
+            surface_features: vec![SurfaceFeature {
+                channel: 0,
+                pattern: GroovePattern::Herringbone {
+                    asymmetry: 2. / 3.,
+                    grooves_per_cycle: 2,
+                },
+                start: 100.,
+                end: 900.,
+                groove_width: 50.,
+                pitch: 100.,
+                angle: FRAC_PI_4,
+                depth: 25.,
+            }],
+            ..Default::default()
+        };
+        // Each chevron ends 250 behind its apex, so six fit between 100 and 900
+        let grooves = network.groove_outlines();
+        assert_eq!(grooves.len(), 6);
+        let tolerance = Default::default();
+        assert!(grooves[0].0[1].approx_eq(&Point([100., 50.]), &tolerance));
+        assert!(grooves[2].0[1].approx_eq(&Point([300., -50.]), &tolerance));
+        assert!(grooves[0].0[0].approx_eq(&Point([200., 150.]), &tolerance));
+        assert_eq!(grooves[0].winding(), Some(Winding::Counterclockwise));
+
+        assert_eq!(
+            network_to_dxf(&network)
+                .matches("POLYLINE\n8\nGROOVES")
+                .count(),
+            6
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Content fingerprints of networks for change detection, e.g., by caches and CI of downstream
+//! tools. The fingerprint hashes a canonical JSON text: object keys sorted, no whitespace, and
+//! numbers in one spelling, so networks read from files differing only in key order, layout,
+//! or number formatting such as `1` and `1.0e0` fingerprint alike. Entity order and coordinate
+//! noise still count as changes; see [Network::normalize] where they shouldn't.
+
+use super::network::Network;
+use crate::export::checksum::{hex, sha256};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Integral numbers beyond this magnitude aren't all representable as floats
+const EXACT_INTEGERS: f64 = 9_007_199_254_740_992.;
+
+fn write_number(out: &mut String, value: f64) {
+    if value.fract() == 0. && value.abs() < EXACT_INTEGERS {
+        // Also maps -0 to 0
+        write!(out, "{}", value as i64).unwrap();
+    } else {
+        write!(out, "{value:?}").unwrap();
+    }
+}
+
+fn write_canonical(out: &mut String, value: &Value) {
+    match value {
+        Value::Null | Value::Bool(_) | Value::String(_) => out.push_str(&value.to_string()),
+        Value::Number(number) => match (number.as_i64(), number.as_u64(), number.as_f64()) {
+            (Some(integer), ..) => write!(out, "{integer}").unwrap(),
+            (None, Some(integer), _) => write!(out, "{integer}").unwrap(),
+            (None, None, Some(float)) => write_number(out, float),
+            (None, None, None) => out.push_str(&number.to_string()),
+        },
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(out, item);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// Canonical JSON text of any serializable value
+pub fn canonical_json<T: Serialize>(value: &T) -> String {
+    let value = serde_json::to_value(value).unwrap();
+    let mut out = String::new();
+    write_canonical(&mut out, &value);
+    out
+}
+
+impl Network {
+    /// SHA-256 digest of the canonical JSON of the network as lowercase hex digits
+    pub fn fingerprint(&self) -> String {
+        hex(&sha256(canonical_json(self).as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn formatting_independence() {
+        let a: Network = serde_json::from_str(
+            r#"{"nodes": [{"id": 0, "position": [1, -0.0]}], "channels": [], "modules": [],
+                "metadata": {"tool": {"scale": 2, "name": "x"}}}"#,
+        )
+        .unwrap();
+        let b: Network = serde_json::from_str(
+            r#"{"modules":[],"metadata":{"tool":{"name":"x","scale":2.0}},"channels":[],
+                "nodes":[{"position":[1.0e0,0],"id":0}]}"#,
+        )
+        .unwrap();
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_eq!(
+            canonical_json(&a),
+            r#"{"channels":[],"metadata":{"tool":{"name":"x","scale":2}},"modules":[],"nodes":[{"id":0,"position":[1,0]}]}"#
+        );
+
+        let mut moved = a.clone();
+        moved.nodes[0].position = Some(crate::base::primitives::Point([1.5, 0.]));
+        assert_ne!(moved.fingerprint(), a.fingerprint());
+        assert!(canonical_json(&moved).contains("[1.5,0]"));
+    }
+}
@@ -0,0 +1,347 @@
+//! Module outlines other than the default rectangle, e.g., of round chambers or irregular
+//! commercial components, and the orientation modules are placed in.
+
+use super::{
+    network::{Module, Network},
+    polygon::Polygon,
+    primitives::{Point, Rect, Vector2},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{PI, TAU};
+
+/// Number of edges approximating circular footprints in polygonal checks
+pub const CIRCLE_SEGMENTS: usize = 64;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Outline of a footprint, relative to the module's position
+pub enum FootprintShape {
+    /// Polygonal outline
+    Polygon(Polygon),
+
+    /// Circular outline
+    Circle { center: Point, radius: f64 },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Shape of a module, replacing the rectangle spanned by its size
+pub struct Footprint {
+    pub shape: FootprintShape,
+
+    /// Counterclockwise rotation of the shape about the module's position in radians
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub rotation: f64,
+}
+
+fn is_zero(value: &f64) -> bool {
+    *value == 0.
+}
+
+impl Footprint {
+    /// Position of a point of the shape relative to the module's position
+    fn place(&self, Point(local): Point) -> Point {
+        Point(Vector2(local).rotated(self.rotation).0)
+    }
+
+    /// Outline relative to the module's position; circles are approximated by an inscribed
+    /// polygon of [CIRCLE_SEGMENTS] edges
+    pub fn outline(&self) -> Polygon {
+        match &self.shape {
+            FootprintShape::Polygon(Polygon(vertices)) => {
+                Polygon(vertices.iter().map(|v| self.place(*v)).collect())
+            }
+            FootprintShape::Circle { center, radius } => {
+                let center = self.place(*center);
+                Polygon(
+                    (0..CIRCLE_SEGMENTS)
+                        .map(|i| {
+                            let angle = TAU * i as f64 / CIRCLE_SEGMENTS as f64;
+                            center + Vector2::from_angle(angle) * *radius
+                        })
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// Center relative to the module's position and radius of circular footprints
+    pub fn circle(&self) -> Option<(Point, f64)> {
+        match self.shape {
+            FootprintShape::Circle { center, radius } => Some((self.place(center), radius)),
+            FootprintShape::Polygon(_) => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Placement of a module relative to its canonical orientation: the module is first mirrored
+/// at the vertical axis through its position, if set, then rotated about its position
+pub struct Orientation {
+    /// Counterclockwise rotation in radians
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub rotation: f64,
+
+    /// Whether the module is mirrored, e.g., when placed on the bottom side
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mirrored: bool,
+}
+
+impl Orientation {
+    /// Rotation without mirroring
+    pub fn rotated(rotation: f64) -> Orientation {
+        Orientation {
+            rotation,
+            mirrored: false,
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.rotation == 0. && !self.mirrored
+    }
+
+    /// Canonical displacement in the orientation
+    pub fn apply(&self, Vector2([x, y]): Vector2) -> Vector2 {
+        let x = if self.mirrored { -x } else { x };
+        Vector2([x, y]).rotated(self.rotation)
+    }
+
+    /// Canonical displacement of a displacement in the orientation
+    pub fn invert(&self, vector: Vector2) -> Vector2 {
+        let Vector2([x, y]) = vector.rotated(-self.rotation);
+        Vector2([if self.mirrored { -x } else { x }, y])
+    }
+
+    /// Direction in radians of a canonical direction in the orientation
+    pub fn apply_heading(&self, heading: f64) -> f64 {
+        let heading = if self.mirrored { PI - heading } else { heading };
+        heading + self.rotation
+    }
+
+    /// Layout position of a point given relative to a placement at the position
+    pub fn place(&self, position: Point, Point(local): Point) -> Point {
+        position + self.apply(Vector2(local))
+    }
+
+    /// Orientation of something oriented by `inner` within a placement in this orientation
+    pub fn compose(&self, inner: &Orientation) -> Orientation {
+        Orientation {
+            rotation: match self.mirrored {
+                true => self.rotation - inner.rotation,
+                false => self.rotation + inner.rotation,
+            },
+            mirrored: self.mirrored != inner.mirrored,
+        }
+    }
+}
+
+impl Module {
+    /// Layout position of a point given relative to the position of the module in its
+    /// canonical orientation
+    pub fn to_layout(&self, local: Point) -> Point {
+        self.orientation.place(self.position, local)
+    }
+
+    /// Position relative to the module in its canonical orientation of a layout point
+    pub fn to_local(&self, point: Point) -> Point {
+        Point(self.orientation.invert(point - self.position).0)
+    }
+
+    /// Outline of the module in layout coordinates: its footprint, or the rectangle spanned
+    /// by its size
+    pub fn outline(&self) -> Polygon {
+        let canonical = match &self.footprint {
+            Some(footprint) => footprint.outline(),
+            None => Polygon::rectangle(&Rect {
+                min: Point([0., 0.]),
+                max: Point(self.size.0),
+            }),
+        };
+        let mut outline = Polygon(canonical.0.into_iter().map(|p| self.to_layout(p)).collect());
+        if self.orientation.mirrored {
+            // Keep the winding of the canonical outline
+            outline = outline.reversed();
+        }
+        outline
+    }
+
+    /// Whether the outline is the axis-aligned rectangle spanned from the position by the size
+    pub fn is_plain_rectangle(&self) -> bool {
+        self.footprint.is_none() && self.orientation.is_identity()
+    }
+
+    /// Exact extent of an oriented module or one with a footprint
+    pub(crate) fn outline_bounding_box(&self) -> Rect {
+        match self.footprint.as_ref().and_then(|f| f.circle()) {
+            Some((center, radius)) => {
+                let center = self.to_layout(center);
+                Rect {
+                    min: center,
+                    max: center,
+                }
+                .inflate(radius)
+            }
+            None => self.outline().bounding_box().unwrap_or(Rect {
+                min: self.position,
+                max: self.position,
+            }),
+        }
+    }
+
+    /// Whether the point is inside or on the outline of the module
+    pub fn contains(&self, point: Point) -> bool {
+        if self.is_plain_rectangle() {
+            return self.bounding_box().contains(point);
+        }
+        match self.footprint.as_ref().and_then(|f| f.circle()) {
+            Some((center, radius)) => (point - self.to_layout(center)).length() <= radius,
+            None => self.outline().contains(point),
+        }
+    }
+}
+
+impl Network {
+    /// Changes the orientation of a module, moving its interface nodes along; channel paths
+    /// attached to the nodes are extended by straight leads. Returns false if there is no
+    /// module with the id.
+    pub fn orient_module(&mut self, id: usize, orientation: Orientation) -> bool {
+        let Some(module) = self.modules.iter_mut().find(|m| m.id == id) else {
+            return false;
+        };
+        let before = module.clone();
+        module.orientation = orientation;
+        let after = module.clone();
+        for node in &before.nodes {
+            if let Some(position) = self.node_position(*node) {
+                self.move_node(*node, after.to_layout(before.to_local(position)));
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::{Channel, ChannelPath, CylindricalShape, LineSegment, PathPiece, Shape},
+        keepout::{KeepOut, Region},
+        network::{Node, NodeId},
+        polygon::Winding,
+        primitives::Dimensions,
+    };
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn shapes() {
+        let mut chamber = Module {
+            id: 0,
+            position: Point([10., 10.]),
+            size: Dimensions([10., 10.]),
+            nodes: vec![],
+            implementation: None,
+            model: None,
+            footprint: Some(Footprint {
+                shape: FootprintShape::Circle {
+                    center: Point([5., 0.]),
+                    radius: 5.,
+                },
+                rotation: FRAC_PI_2,
+            }),
+            orientation: Orientation::default(),
+            kind: Default::default(),
+            metadata: Default::default(),
+        };
+        let Rect { min, max } = chamber.bounding_box();
+        assert!(min.approx_eq(&Point([5., 10.]), &Default::default()));
+        assert!(max.approx_eq(&Point([15., 20.]), &Default::default()));
+        assert!(chamber.contains(Point([10., 15.])));
+        assert!(!chamber.contains(Point([15., 10.])));
+        assert_eq!(chamber.outline().0.len(), CIRCLE_SEGMENTS);
+
+        // The rectangle of the position and size would overlap the keep-out, the circle doesn't
+        let network = |chamber: &Module| Network {
+            modules: vec![chamber.clone()],
+            keep_outs: vec![KeepOut {
+                id: 0,
+                region: Region::Rectangle(Rect {
+                    min: Point([18., 10.]),
+                    max: Point([19., 11.]),
+                }),
+                layer: None,
+            }],
+            ..Default::default()
+        };
+        assert!(network(&chamber).keep_out_violations().is_empty());
+
+        chamber.footprint = Some(Footprint {
+            shape: FootprintShape::Polygon(Polygon(vec![
+                Point([0., 0.]),
+                Point([10., 0.]),
+                Point([0., 10.]),
+            ])),
+            rotation: 0.,
+        });
+        assert_eq!(network(&chamber).keep_out_violations().len(), 1);
+        assert!(!chamber.contains(Point([19., 19.])));
+    }
+
+    #[test]
+    fn orientation() {
+        let mut network = Network {
+            nodes: vec![Node::at(NodeId(0), Point([14., 12.]))],
+            modules: vec![Module {
+                id: 0,
+                position: Point([10., 10.]),
+                size: Dimensions([4., 2.]),
+                nodes: vec![NodeId(0)],
+                implementation: None,
+                model: None,
+                footprint: None,
+                orientation: Orientation::default(),
+                kind: Default::default(),
+                metadata: Default::default(),
+            }],
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(0),
+                shape: Shape::Cylindrical(CylindricalShape { radius: 1. }),
+                path: Some(ChannelPath {
+                    pieces: vec![PathPiece::LineSegment(LineSegment {
+                        start: Point([14., 12.]),
+                        end: Point([20., 12.]),
+                    })],
+                    closed: false,
+                }),
+                length: None,
+                layer: 0,
+                metadata: Default::default(),
+            }],
+            ..Default::default()
+        };
+        let mirrored = Orientation {
+            rotation: FRAC_PI_2,
+            mirrored: true,
+        };
+        assert!(network.orient_module(0, mirrored));
+        let module = &network.modules[0];
+        let Rect { min, max } = module.bounding_box();
+        let tolerance = Default::default();
+        assert!(min.approx_eq(&Point([8., 6.]), &tolerance));
+        assert!(max.approx_eq(&Point([10., 10.]), &tolerance));
+        assert_eq!(module.outline().winding(), Some(Winding::Counterclockwise));
+        assert!((mirrored.apply_heading(0.) - 3. * FRAC_PI_2).abs() < 1e-12);
+
+        // The port moved with the module and the channel follows it
+        let port = network.node_position(NodeId(0)).unwrap();
+        assert!(port.approx_eq(&Point([8., 6.]), &tolerance));
+        let path = network.channels[0].path.as_ref().unwrap();
+        assert_eq!(path.pieces[0].start(), port);
+        assert!(module
+            .to_local(port)
+            .approx_eq(&Point([4., 2.]), &tolerance));
+    }
+}
@@ -0,0 +1,216 @@
+//! Procedural networks for benchmarks and property tests. Nodes are placed in the plane and
+//! connected by straight channels with randomized widths, so all code paths from solving to
+//! exporting can be exercised with large inputs.
+
+use super::{
+    channel::{Channel, ChannelPath, LineSegment, PathPiece, RectangularShape, Shape},
+    network::{Network, Node, NodeId},
+    primitives::Point,
+};
+use crate::random::Rng;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Connectivity of a generated network
+pub enum Topology {
+    /// Rectangular lattice with channels between horizontal and vertical neighbors
+    Grid { columns: usize, rows: usize },
+
+    /// Tree with levels along x, every node splitting into `branching` children
+    Tree { depth: usize, branching: usize },
+
+    /// Randomly placed nodes: a random spanning tree keeps the network connected, every other
+    /// pair is connected with the given probability
+    ErdosRenyi { nodes: usize, probability: f64 },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Parameters of a generated network
+pub struct RandomNetworkSpec {
+    pub topology: Topology,
+
+    /// Distance between neighboring nodes
+    pub pitch: f64,
+
+    /// Range channel widths are drawn from
+    pub width: [f64; 2],
+
+    /// Height of all channels
+    pub height: f64,
+
+    #[serde(default)]
+    pub rng: Rng,
+}
+
+impl Default for RandomNetworkSpec {
+    fn default() -> Self {
+        RandomNetworkSpec {
+            topology: Topology::Grid {
+                columns: 10,
+                rows: 10,
+            },
+            pitch: 2000.,
+            width: [50., 200.],
+            height: 50.,
+            rng: Rng::default(),
+        }
+    }
+}
+
+impl Network {
+    /// Generates a network; the same spec always yields the same network
+    pub fn random(spec: &RandomNetworkSpec) -> Network {
+        let mut rng = spec.rng.generator();
+        let mut positions = Vec::new();
+        let mut edges = Vec::new();
+        match spec.topology {
+            Topology::Grid { columns, rows } => {
+                for row in 0..rows {
+                    for column in 0..columns {
+                        let index = positions.len();
+                        positions.push([column as f64, row as f64]);
+                        if column > 0 {
+                            edges.push((index - 1, index));
+                        }
+                        if row > 0 {
+                            edges.push((index - columns, index));
+                        }
+                    }
+                }
+            }
+            Topology::Tree { depth, branching } => {
+                positions.push([0., 0.]);
+                let mut level = vec![0];
+                for d in 1..=depth {
+                    let count = level.len() * branching;
+                    let mut next = Vec::with_capacity(count);
+                    for (i, parent) in level.iter().enumerate() {
+                        for j in 0..branching {
+                            let k = i * branching + j;
+                            next.push(positions.len());
+                            edges.push((*parent, positions.len()));
+                            positions.push([d as f64, k as f64 - (count - 1) as f64 / 2.]);
+                        }
+                    }
+                    level = next;
+                }
+            }
+            Topology::ErdosRenyi { nodes, probability } => {
+                let side = (nodes as f64).sqrt();
+                let mut parents = vec![usize::MAX; nodes];
+                for (i, parent) in parents.iter_mut().enumerate() {
+                    positions.push([rng.next_f64() * side, rng.next_f64() * side]);
+                    if i > 0 {
+                        *parent = (rng.next_u64() % i as u64) as usize;
+                        edges.push((*parent, i));
+                    }
+                }
+                for i in 0..nodes {
+                    for (j, parent) in parents.iter().enumerate().skip(i + 1) {
+                        if *parent != i && rng.next_f64() < probability {
+                            edges.push((i, j));
+                        }
+                    }
+                }
+            }
+        }
+
+        let point = |i: usize| Point(positions[i].map(|c| c * spec.pitch));
+        let [min_width, max_width] = spec.width;
+        Network {
+            nodes: (0..positions.len())
+                .map(|i| Node::at(NodeId(i), point(i)))
+                .collect(),
+            channels: edges
+                .iter()
+                .enumerate()
+                .map(|(id, (a, b))| {
+                    let mut path = ChannelPath::new();
+                    path.add(PathPiece::LineSegment(LineSegment {
+                        start: point(*a),
+                        end: point(*b),
+                    }));
+                    Channel {
+                        id,
+                        node_a: NodeId(*a),
+                        node_b: NodeId(*b),
+                        shape: Shape::Rectangular(RectangularShape {
+                            width: min_width + rng.next_f64() * (max_width - min_width),
+                            height: spec.height,
+                        }),
+                        path: Some(path),
+                        length: None,
+                        layer: 0,
+                        metadata: Default::default(),
+                    }
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulation::{
+        fluid::Fluid,
+        solver::{solve, Boundary, BoundaryCondition},
+    };
+
+    #[test]
+    fn topologies_solve() {
+        for (topology, nodes) in [
+            (
+                Topology::Grid {
+                    columns: 6,
+                    rows: 4,
+                },
+                24,
+            ),
+            (
+                Topology::Tree {
+                    depth: 3,
+                    branching: 2,
+                },
+                15,
+            ),
+            (
+                Topology::ErdosRenyi {
+                    nodes: 30,
+                    probability: 0.05,
+                },
+                30,
+            ),
+        ] {
+            let spec = RandomNetworkSpec {
+                topology,
+                rng: Rng::new(3),
+                ..Default::default()
+            };
+            let network = Network::random(&spec);
+            assert_eq!(network.nodes.len(), nodes);
+            assert!(network.channels.len() >= nodes - 1);
+            assert_eq!(network, Network::random(&spec));
+
+            let boundaries = [
+                Boundary {
+                    node: NodeId(0),
+                    condition: BoundaryCondition::Pressure(100.),
+                },
+                Boundary {
+                    node: NodeId(nodes - 1),
+                    condition: BoundaryCondition::Pressure(0.),
+                },
+            ];
+            let solution = solve(&network, &Fluid::water(), &boundaries).unwrap();
+            assert!(solution
+                .pressures
+                .values()
+                .all(|p| (0. ..=100.).contains(p)));
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Named collections of entities for organizing large designs, e.g., all channels of a mixer
+//! stage. Exporters map groups to layers and styles: SVG elements carry the group names as
+//! classes and take the color of their first colored group, DXF entities move to a layer of
+//! their first group.
+
+use super::network::{EntityRef, Network};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Named set of entities; members that aren't part of the network are ignored
+pub struct Group {
+    /// Unique name of the group
+    pub name: String,
+
+    /// Human-readable description, e.g., shown in an editor's layer panel
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    /// Display color as a hex code, e.g., "#ff7f0e"; styles may override it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+
+    /// Entities in the group in insertion order
+    pub members: Vec<EntityRef>,
+}
+
+impl Group {
+    /// Empty group without label and color
+    pub fn new(name: &str) -> Group {
+        Group {
+            name: name.into(),
+            label: None,
+            color: None,
+            members: Vec::new(),
+        }
+    }
+
+    pub fn contains(&self, entity: EntityRef) -> bool {
+        self.members.contains(&entity)
+    }
+
+    /// Name restricted to ASCII letters, digits, and underscores, e.g., for DXF layer names
+    /// and CSS classes
+    pub fn identifier(&self) -> String {
+        self.name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect()
+    }
+}
+
+impl Network {
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+
+    /// Adds the entity to the named group, creating the group if necessary; returns false if it
+    /// was already a member
+    pub fn add_to_group(&mut self, name: &str, entity: EntityRef) -> bool {
+        let index = match self.groups.iter().position(|g| g.name == name) {
+            Some(index) => index,
+            None => {
+                self.groups.push(Group::new(name));
+                self.groups.len() - 1
+            }
+        };
+        let group = &mut self.groups[index];
+        if group.contains(entity) {
+            return false;
+        }
+        group.members.push(entity);
+        true
+    }
+
+    /// Removes the entity from the named group; returns false if it wasn't a member
+    pub fn remove_from_group(&mut self, name: &str, entity: EntityRef) -> bool {
+        let Some(group) = self.groups.iter_mut().find(|g| g.name == name) else {
+            return false;
+        };
+        let before = group.members.len();
+        group.members.retain(|e| *e != entity);
+        group.members.len() < before
+    }
+
+    /// Groups the entity is a member of, in group order
+    pub fn groups_of(&self, entity: EntityRef) -> impl Iterator<Item = &Group> {
+        self.groups.iter().filter(move |g| g.contains(entity))
+    }
+
+    /// Existing members of the group, or None if there is no group with the name
+    pub fn group_members(&self, name: &str) -> Option<Vec<EntityRef>> {
+        let group = self.group(name)?;
+        Some(
+            group
+                .members
+                .iter()
+                .copied()
+                .filter(|e| self.contains_entity(*e))
+                .collect(),
+        )
+    }
+
+    /// Removes members that aren't part of the network anymore, e.g., after edits; returns
+    /// their number
+    pub fn prune_groups(&mut self) -> usize {
+        let mut groups = std::mem::take(&mut self.groups);
+        let mut removed = 0;
+        for group in &mut groups {
+            let before = group.members.len();
+            group.members.retain(|e| self.contains_entity(*e));
+            removed += before - group.members.len();
+        }
+        self.groups = groups;
+        removed
+    }
+
+    /// Index of the first group of every grouped entity, the style and layer they are exported
+    /// with
+    pub(crate) fn primary_groups(&self) -> BTreeMap<EntityRef, &Group> {
+        let mut primary = BTreeMap::new();
+        for group in &self.groups {
+            for member in &group.members {
+                primary.entry(*member).or_insert(group);
+            }
+        }
+        primary
+    }
+
+    fn contains_entity(&self, entity: EntityRef) -> bool {
+        match entity {
+            EntityRef::Node(id) => self.nodes.iter().any(|n| n.id == id),
+            EntityRef::Channel(id) => self.channels.iter().any(|c| c.id == id),
+            EntityRef::Module(id) => self.modules.iter().any(|m| m.id == id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{Channel, ChannelPath, CylindricalShape, LineSegment, PathPiece, Shape},
+            network::{Metadata, Node, NodeId},
+            primitives::Point,
+        },
+        export::{dxf::network_to_dxf, svg::network_to_svg},
+    };
+
+    #[test]
+    fn groups() {
+        let mut path = ChannelPath::new();
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: Point([0., 0.]),
+            end: Point([10., 0.]),
+        }));
+        let channel = |id| Channel {
+            id,
+            node_a: NodeId(0),
+            node_b: NodeId(1),
+            shape: Shape::Cylindrical(CylindricalShape { radius: 1. }),
+            path: Some(path.clone()),
+            length: None,
+            layer: 0,
+            metadata: Metadata::new(),
+        };
+        let mut network = Network {
+            nodes: vec![
+                Node::at(NodeId(0), Point([0., 0.])),
+                Node::at(NodeId(1), Point([10., 0.])),
+            ],
+            channels: vec![channel(0), channel(1)],
+            ..Default::default()
+        };
+        assert!(network.add_to_group("mixer stage", EntityRef::Channel(0)));
+        assert!(!network.add_to_group("mixer stage", EntityRef::Channel(0)));
+        assert!(network.add_to_group("mixer stage", EntityRef::Channel(7)));
+        network.groups[0].color = Some("#ff7f0e".into());
+        network.add_to_group("outlets", EntityRef::Channel(0));
+        assert_eq!(network.groups_of(EntityRef::Channel(0)).count(), 2);
+        assert_eq!(
+            network.group_members("mixer stage"),
+            Some(vec![EntityRef::Channel(0)])
+        );
+
+        let json = serde_json::to_string(&network).unwrap();
+        assert_eq!(serde_json::from_str::<Network>(&json).unwrap(), network);
+
+        let svg = network_to_svg(&network);
+        assert!(svg.contains(
+            r##"<path id="channel-0" class="group-mixer_stage group-outlets" stroke="#ff7f0e""##
+        ));
+        let dxf = network_to_dxf(&network);
+        assert!(dxf.contains("CHANNELS_mixer_stage"));
+
+        assert_eq!(network.prune_groups(), 1);
+        assert!(network.remove_from_group("outlets", EntityRef::Channel(0)));
+        assert_eq!(network.groups_of(EntityRef::Channel(1)).count(), 0);
+    }
+}
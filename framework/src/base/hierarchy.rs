@@ -0,0 +1,406 @@
+use super::{
+    channel::Channel,
+    feature::SurfaceFeature,
+    footprint::Orientation,
+    keepout::KeepOut,
+    marking::Marking,
+    network::{EntityRef, Module, Network, Node, NodeId},
+    primitives::Point,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Implementation of a module by a nested network
+pub struct Subcircuit {
+    /// The network instantiated by the module, positioned relative to the module
+    pub network: Box<Network>,
+
+    /// Mapping of the module's interface nodes to nodes of the nested network
+    pub ports: Vec<PortMapping>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Connection of an interface node of the enclosing network to a node of the nested network
+pub struct PortMapping {
+    /// Interface node in the enclosing network
+    pub outer: NodeId,
+
+    /// Node in the nested network
+    pub inner: NodeId,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Linear black-box model of a module: the flows into the module at its interface nodes are
+/// the conductance matrix times their pressures, rows and columns in the order of the nodes.
+/// It describes steady flow only and has no compliance.
+pub struct PortModel {
+    pub conductance: Vec<Vec<f64>>,
+}
+
+impl Network {
+    /// Replaces the module with the given id by the (recursively flattened) contents of its
+    /// subcircuit, placed in the module's position and orientation. Returns false if there is no such module or it has no implementation.
+    pub fn expand(&mut self, module_id: usize) -> bool {
+        let index = match self
+            .modules
+            .iter()
+            .position(|m| m.id == module_id && m.implementation.is_some())
+        {
+            Some(index) => index,
+            None => return false,
+        };
+        let module = self.modules.remove(index);
+        let subcircuit = module.implementation.unwrap();
+        self.instantiate(
+            &subcircuit.network.flattened(),
+            &subcircuit.ports,
+            module.position,
+            &module.orientation,
+        );
+        true
+    }
+
+    /// Expands all modules with an implementation until the network is flat
+    pub fn flatten(&mut self) {
+        while let Some(id) = self
+            .modules
+            .iter()
+            .find(|m| m.implementation.is_some())
+            .map(|m| m.id)
+        {
+            self.expand(id);
+        }
+    }
+
+    /// Returns a flattened copy of the network
+    pub fn flattened(&self) -> Network {
+        let mut network = self.clone();
+        network.flatten();
+        network
+    }
+
+    /// Copies a flat network into this one, placed in the orientation at the position. Port
+    /// nodes are merged with their outer counterparts, all other entities receive fresh ids.
+    pub(crate) fn instantiate(
+        &mut self,
+        inner: &Network,
+        ports: &[PortMapping],
+        position: Point,
+        orientation: &Orientation,
+    ) {
+        let place = |point| orientation.place(position, point);
+        let mut node_map: HashMap<NodeId, NodeId> =
+            ports.iter().map(|p| (p.inner, p.outer)).collect();
+
+        let mut next_node = self.next_node_id();
+        for node in &inner.nodes {
+            node_map.entry(node.id).or_insert_with(|| {
+                let id = next_node;
+                next_node = NodeId(next_node.0 + 1);
+                self.nodes.push(Node {
+                    id,
+                    position: node.position.map(place),
+                    ..node.clone()
+                });
+                id
+            });
+        }
+
+        let first_channel = self.next_channel_id();
+        self.channels
+            .extend(inner.channels.iter().enumerate().map(|(i, channel)| {
+                Channel {
+                    id: first_channel + i,
+                    node_a: node_map[&channel.node_a],
+                    node_b: node_map[&channel.node_b],
+                    path: channel
+                        .path
+                        .as_ref()
+                        .map(|p| p.placed(position, orientation)),
+                    ..channel.clone()
+                }
+            }));
+
+        let first_module = self.next_module_id();
+        self.modules
+            .extend(inner.modules.iter().enumerate().map(|(i, module)| Module {
+                id: first_module + i,
+                position: place(module.position),
+                orientation: orientation.compose(&module.orientation),
+                nodes: module.nodes.iter().map(|n| node_map[n]).collect(),
+                ..module.clone()
+            }));
+
+        let first_keep_out = self.keep_outs.iter().map(|k| k.id + 1).max().unwrap_or(0);
+        self.keep_outs.extend(
+            inner
+                .keep_outs
+                .iter()
+                .enumerate()
+                .map(|(i, keep_out)| KeepOut {
+                    id: first_keep_out + i,
+                    region: keep_out.region.placed(position, orientation),
+                    ..keep_out.clone()
+                }),
+        );
+
+        self.markings
+            .extend(inner.markings.iter().map(|marking| Marking {
+                path: marking.path.placed(position, orientation),
+                ..marking.clone()
+            }));
+
+        // Groups of the nested network are merged into equally named ones
+        for group in &inner.groups {
+            for member in &group.members {
+                let member = match *member {
+                    EntityRef::Node(id) => node_map.get(&id).map(|n| EntityRef::Node(*n)),
+                    EntityRef::Channel(id) => inner
+                        .channels
+                        .iter()
+                        .position(|c| c.id == id)
+                        .map(|i| EntityRef::Channel(first_channel + i)),
+                    EntityRef::Module(id) => inner
+                        .modules
+                        .iter()
+                        .position(|m| m.id == id)
+                        .map(|i| EntityRef::Module(first_module + i)),
+                };
+                if let Some(member) = member {
+                    self.add_to_group(&group.name, member);
+                }
+            }
+            if let Some(merged) = self.groups.iter_mut().find(|g| g.name == group.name) {
+                merged.label = merged.label.take().or_else(|| group.label.clone());
+                merged.color = merged.color.take().or_else(|| group.color.clone());
+            }
+        }
+
+        self.surface_features
+            .extend(inner.surface_features.iter().filter_map(|feature| {
+                let index = inner
+                    .channels
+                    .iter()
+                    .position(|c| c.id == feature.channel)?;
+                Some(SurfaceFeature {
+                    channel: first_channel + index,
+                    ..*feature
+                })
+            }));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::{Arc, ChannelPath, LineSegment, PathPiece, RectangularShape, Shape},
+        keepout::Region,
+        primitives::{Dimensions, Rect},
+    };
+    use std::f64::consts::FRAC_PI_2;
+
+    fn channel(id: usize, a: usize, b: usize) -> Channel {
+        Channel {
+            id,
+            node_a: NodeId(a),
+            node_b: NodeId(b),
+            shape: Shape::Rectangular(RectangularShape {
+                width: 100.,
+                height: 50.,
+            }),
+            path: None,
+            length: None,
+            layer: 0,
+            metadata: Default::default(),
+        }
+    }
+
+    fn module(id: usize, nodes: Vec<NodeId>, implementation: Option<Subcircuit>) -> Module {
+        Module {
+            id,
+            position: Point([10., 20.]),
+            size: Dimensions([5., 5.]),
+            nodes,
+            implementation,
+            model: None,
+            footprint: None,
+            orientation: Default::default(),
+            kind: Default::default(),
+            metadata: Default::default(),
+        }
+    }
+
+    /// Two nodes joined by a single channel, with an inner module at the second node
+    fn unit() -> Network {
+        Network {
+            nodes: vec![Node::new(NodeId(0)), Node::new(NodeId(1))],
+            channels: vec![channel(0, 0, 1)],
+            modules: vec![module(0, vec![NodeId(1)], None)],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn flatten_nested() {
+        let ports = |a, b| {
+            vec![
+                PortMapping {
+                    outer: NodeId(a),
+                    inner: NodeId(0),
+                },
+                PortMapping {
+                    outer: NodeId(b),
+                    inner: NodeId(1),
+                },
+            ]
+        };
+        let middle = Network {
+            nodes: vec![
+                Node::new(NodeId(0)),
+                Node::new(NodeId(1)),
+                Node::new(NodeId(2)),
+            ],
+            channels: vec![],
+            modules: vec![
+                module(
+                    0,
+                    vec![NodeId(0), NodeId(2)],
+                    Some(Subcircuit {
+                        network: Box::new(unit()),
+                        ports: ports(0, 2),
+                    }),
+                ),
+                module(
+                    1,
+                    vec![NodeId(2), NodeId(1)],
+                    Some(Subcircuit {
+                        network: Box::new(unit()),
+                        ports: ports(2, 1),
+                    }),
+                ),
+            ],
+            ..Default::default()
+        };
+        let top = Network {
+            nodes: vec![Node::new(NodeId(0)), Node::new(NodeId(1))],
+            channels: vec![],
+            modules: vec![module(
+                7,
+                vec![NodeId(0), NodeId(1)],
+                Some(Subcircuit {
+                    network: Box::new(middle),
+                    ports: ports(0, 1),
+                }),
+            )],
+            ..Default::default()
+        };
+
+        let flat = top.flattened();
+        assert_eq!(flat.nodes.len(), 3);
+        assert_eq!(flat.channels.len(), 2);
+        assert_eq!(flat.modules.len(), 2);
+        assert!(flat.modules.iter().all(|m| m.implementation.is_none()));
+        assert!(flat.modules.iter().all(|m| m.position == Point([30., 60.])));
+        let ends: Vec<_> = flat.channels.iter().map(|c| (c.node_a, c.node_b)).collect();
+        assert_eq!(ends, vec![(NodeId(0), NodeId(2)), (NodeId(2), NodeId(1))]);
+    }
+
+    #[test]
+    fn oriented_expansion() {
+        let line = |start, end| PathPiece::LineSegment(LineSegment { start, end });
+        let path = |pieces| ChannelPath {
+            pieces,
+            closed: false,
+        };
+        // Port 0 at the module position, port 1 five units to the right, and a clockwise
+        // arc over (3, 1) between them
+        let mut inner = Network {
+            nodes: [[0., 0.], [5., 0.], [2., 0.]]
+                .iter()
+                .enumerate()
+                .map(|(i, p)| Node {
+                    position: Some(Point(*p)),
+                    ..Node::new(NodeId(i))
+                })
+                .collect(),
+            channels: vec![channel(0, 0, 2), channel(1, 2, 1)],
+            keep_outs: vec![KeepOut {
+                id: 0,
+                region: Region::Rectangle(Rect {
+                    min: Point([1., 1.]),
+                    max: Point([2., 2.]),
+                }),
+                layer: None,
+            }],
+            ..Default::default()
+        };
+        inner.channels[0].path = Some(path(vec![line(Point([0., 0.]), Point([2., 0.]))]));
+        inner.channels[1].path = Some(path(vec![
+            PathPiece::Arc(Arc {
+                right: true,
+                start: Point([2., 0.]),
+                end: Point([4., 0.]),
+                center: Point([3., 0.]),
+            }),
+            line(Point([4., 0.]), Point([5., 0.])),
+        ]));
+        let ports = [(0, 0), (1, 1)].map(|(outer, inner)| PortMapping {
+            outer: NodeId(outer),
+            inner: NodeId(inner),
+        });
+        let mut network = Network {
+            nodes: [[10., 20.], [15., 20.]]
+                .iter()
+                .enumerate()
+                .map(|(i, p)| Node {
+                    position: Some(Point(*p)),
+                    ..Node::new(NodeId(i))
+                })
+                .collect(),
+            modules: vec![module(
+                0,
+                vec![NodeId(0), NodeId(1)],
+                Some(Subcircuit {
+                    network: Box::new(inner),
+                    ports: ports.to_vec(),
+                }),
+            )],
+            ..Default::default()
+        };
+
+        for mirrored in [false, true] {
+            let orientation = Orientation {
+                rotation: FRAC_PI_2,
+                mirrored,
+            };
+            assert!(network.orient_module(0, orientation));
+            let module = network.modules[0].clone();
+            let flat = network.flattened();
+            let close = |a: Point, b: Point| (a - b).length() < 1e-9;
+            for channel in &flat.channels {
+                let path = channel.path.as_ref().unwrap();
+                let start = flat.node_position(channel.node_a).unwrap();
+                let end = flat.node_position(channel.node_b).unwrap();
+                assert!(close(path.pieces[0].start(), start));
+                assert!(close(path.pieces.last().unwrap().end(), end));
+            }
+            assert!(close(
+                flat.node_position(NodeId(1)).unwrap(),
+                module.to_layout(Point([5., 0.]))
+            ));
+            let PathPiece::Arc(arc) = flat.channels[1].path.as_ref().unwrap().pieces[0] else {
+                unreachable!()
+            };
+            assert!(arc.distance(module.to_layout(Point([3., 1.]))) < 1e-9);
+            let region = flat.keep_outs[0].region.polygon();
+            assert!(region.contains(module.to_layout(Point([1.5, 1.5]))));
+            assert!(region.signed_area() > 0.);
+        }
+    }
+}
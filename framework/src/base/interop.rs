@@ -0,0 +1,297 @@
+//! Interoperability rules for chip outlines and port positions following ISO 22916, so chips
+//! fit standard holders and connectors. Ports sit on a square grid anchored at the outline's
+//! corner with the smallest coordinates and keep a clearance to the chip edges.
+
+use super::{
+    network::{Network, NodeId},
+    primitives::{Dimensions, Point, Rect},
+};
+use crate::progress::ProgressHandle;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Dimensional rules for outlines and ports, lengths in layout units
+pub struct InteropRules {
+    /// Allowed outline sizes, in either orientation
+    pub footprints: Vec<Dimensions>,
+
+    /// Allowed deviation of the outline size
+    pub footprint_tolerance: f64,
+
+    /// Pitch of the port grid
+    pub port_pitch: f64,
+
+    /// Distance of the first grid line from the outline's edges
+    pub grid_offset: f64,
+
+    /// Allowed deviation of a port from its grid position
+    pub position_tolerance: f64,
+
+    /// Smallest distance of port centers from the outline's edges
+    pub edge_clearance: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Violation of the interoperability rules
+pub enum InteropIssue {
+    /// The outline size matches none of the footprints
+    NonStandardOutline(Dimensions),
+
+    /// The port has no position
+    UnplacedPort(NodeId),
+
+    /// The port is outside of the outline or too close to an edge
+    EdgeClearance {
+        /// Id of the port's node
+        node: NodeId,
+
+        /// Distance to the closest edge, negative outside of the outline
+        distance: f64,
+    },
+
+    /// The port is off the grid
+    OffGrid {
+        /// Id of the port's node
+        node: NodeId,
+
+        /// Distance to the closest grid position
+        deviation: f64,
+    },
+
+    /// All grid positions are taken, the port can't be placed
+    NoFreePosition(NodeId),
+
+    /// Placement was cancelled through its progress handle
+    Cancelled,
+}
+
+impl InteropRules {
+    /// Rules of ISO 22916: footprints of 15 mm multiples and the microscope slide format, ports
+    /// on a 1.5 mm grid
+    pub fn iso_22916(units_per_mm: f64) -> Self {
+        let mm = |v: f64| v * units_per_mm;
+        InteropRules {
+            footprints: [
+                [15., 15.],
+                [15., 30.],
+                [15., 45.],
+                [30., 30.],
+                [30., 45.],
+                [45., 45.],
+                [25.5, 75.5],
+            ]
+            .map(|[w, h]| Dimensions([mm(w), mm(h)]))
+            .to_vec(),
+            footprint_tolerance: mm(0.1),
+            port_pitch: mm(1.5),
+            grid_offset: mm(0.75),
+            position_tolerance: mm(0.05),
+            edge_clearance: mm(1.5),
+        }
+    }
+
+    /// Whether the outline matches a footprint
+    pub fn standard_outline(&self, outline: &Rect) -> bool {
+        let (Point([x0, y0]), Point([x1, y1])) = (outline.min, outline.max);
+        let (w, h) = (x1 - x0, y1 - y0);
+        let close = |a: f64, b: f64| (a - b).abs() <= self.footprint_tolerance;
+        self.footprints.iter().any(|Dimensions([fw, fh])| {
+            (close(w, *fw) && close(h, *fh)) || (close(w, *fh) && close(h, *fw))
+        })
+    }
+
+    /// Closest grid position and its distance
+    fn snap(&self, outline: &Rect, Point([x, y]): Point) -> (Point, f64) {
+        let Point([x0, y0]) = outline.min;
+        let grid = |v: f64, origin: f64| {
+            let origin = origin + self.grid_offset;
+            origin + ((v - origin) / self.port_pitch).round() * self.port_pitch
+        };
+        let (gx, gy) = (grid(x, x0), grid(y, y0));
+        (Point([gx, gy]), f64::hypot(gx - x, gy - y))
+    }
+
+    /// Distance to the closest edge, negative outside
+    fn edge_distance(outline: &Rect, Point([x, y]): Point) -> f64 {
+        let (Point([x0, y0]), Point([x1, y1])) = (outline.min, outline.max);
+        (x - x0).min(x1 - x).min(y - y0).min(y1 - y)
+    }
+
+    /// Checks the outline and the positions of the port nodes
+    pub fn check(&self, network: &Network, outline: &Rect, ports: &[NodeId]) -> Vec<InteropIssue> {
+        let mut issues = Vec::new();
+        if !self.standard_outline(outline) {
+            let (Point([x0, y0]), Point([x1, y1])) = (outline.min, outline.max);
+            issues.push(InteropIssue::NonStandardOutline(Dimensions([
+                x1 - x0,
+                y1 - y0,
+            ])));
+        }
+        for node in ports {
+            let Some(position) = network.node_position(*node) else {
+                issues.push(InteropIssue::UnplacedPort(*node));
+                continue;
+            };
+            let distance = Self::edge_distance(outline, position);
+            if distance < self.edge_clearance {
+                issues.push(InteropIssue::EdgeClearance {
+                    node: *node,
+                    distance,
+                });
+            }
+            let (_, deviation) = self.snap(outline, position);
+            if deviation > self.position_tolerance {
+                issues.push(InteropIssue::OffGrid {
+                    node: *node,
+                    deviation,
+                });
+            }
+        }
+        issues
+    }
+
+    /// Moves every port to the closest free grid position within the edge clearance whose hole
+    /// stays clear of all keep-out regions, in the order given. Channel paths attached to a
+    /// moved port are extended by a straight lead to the new position.
+    pub fn place_ports(
+        &self,
+        network: &mut Network,
+        outline: &Rect,
+        ports: &[NodeId],
+    ) -> Result<(), InteropIssue> {
+        self.place_ports_with_progress(network, outline, ports, &ProgressHandle::default())
+    }
+
+    /// Places like [InteropRules::place_ports], reporting the placed ports out of all ports;
+    /// ports placed before a cancellation keep their new positions
+    pub fn place_ports_with_progress(
+        &self,
+        network: &mut Network,
+        outline: &Rect,
+        ports: &[NodeId],
+        progress: &ProgressHandle,
+    ) -> Result<(), InteropIssue> {
+        let (Point([x0, y0]), Point([x1, y1])) = (outline.min, outline.max);
+        let axis = |from: f64, to: f64| {
+            let first = ((self.edge_clearance - self.grid_offset) / self.port_pitch)
+                .ceil()
+                .max(0.) as usize;
+            (first..)
+                .map(move |i| from + self.grid_offset + i as f64 * self.port_pitch)
+                .take_while(move |v| *v <= to - self.edge_clearance)
+        };
+        let mut free: Vec<Point> = axis(x0, x1)
+            .flat_map(|x| axis(y0, y1).map(move |y| Point([x, y])))
+            .collect();
+
+        for (i, node) in ports.iter().enumerate() {
+            progress
+                .report("place_ports", i, Some(ports.len()))
+                .map_err(|_| InteropIssue::Cancelled)?;
+            let position = network
+                .node_position(*node)
+                .ok_or(InteropIssue::UnplacedPort(*node))?;
+            let Point([x, y]) = position;
+            let diameter = (network.nodes.iter().find(|n| n.id == *node))
+                .and_then(|n| n.port.as_ref())
+                .map_or(0., |port| port.diameter);
+            let blocked =
+                |p: Point| (network.keep_outs.iter()).any(|k| k.overlaps_hole(p, diameter));
+            let closest = (0..free.len())
+                .filter(|i| !blocked(free[*i]))
+                .min_by(|a, b| {
+                    let distance = |Point([px, py]): Point| f64::hypot(px - x, py - y);
+                    distance(free[*a]).total_cmp(&distance(free[*b]))
+                })
+                .ok_or(InteropIssue::NoFreePosition(*node))?;
+            let target = free.swap_remove(closest);
+            if target == position {
+                continue;
+            }
+
+            network.move_node(*node, target);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        generator::{RandomNetworkSpec, Topology},
+        keepout::{KeepOut, Region},
+        port::{ConnectorType, PortHole},
+    };
+
+    #[test]
+    fn place_and_check() {
+        let rules = InteropRules::iso_22916(1000.);
+        let mut network = Network::random(&RandomNetworkSpec {
+            topology: Topology::Grid {
+                columns: 3,
+                rows: 1,
+            },
+            pitch: 5000.,
+            ..Default::default()
+        });
+        let outline = Rect {
+            min: Point([-2000., -7000.]),
+            max: Point([13000., 8000.]),
+        };
+        let ports = [NodeId(0), NodeId(2)];
+        assert_eq!(
+            rules.check(&network, &outline, &ports),
+            [
+                InteropIssue::OffGrid {
+                    node: NodeId(0),
+                    deviation: f64::hypot(250., 250.)
+                },
+                InteropIssue::OffGrid {
+                    node: NodeId(2),
+                    deviation: f64::hypot(750., 250.)
+                }
+            ]
+        );
+
+        let mut guarded = network.clone();
+        rules.place_ports(&mut network, &outline, &ports).unwrap();
+        assert_eq!(rules.check(&network, &outline, &ports), []);
+
+        // A keep-out next to the closest grid position pushes the port to another one
+        let placed = network.node_position(NodeId(0)).unwrap();
+        guarded.keep_outs.push(KeepOut {
+            id: 0,
+            region: Region::Rectangle(Rect {
+                min: placed.translated(Point([100., -100.])),
+                max: placed.translated(Point([300., 100.])),
+            }),
+            layer: None,
+        });
+        guarded.nodes[0].port = Some(PortHole {
+            diameter: 400.,
+            connector: ConnectorType::PressFit,
+        });
+        rules.place_ports(&mut guarded, &outline, &ports).unwrap();
+        let moved = guarded.node_position(NodeId(0)).unwrap();
+        assert_ne!(moved, placed);
+        assert!(!guarded.keep_outs[0].overlaps_hole(moved, 400.));
+        assert_eq!(rules.check(&guarded, &outline, &ports), []);
+        let path = network.channels[0].path.as_ref().unwrap();
+        assert_eq!(
+            path.pieces[0].start(),
+            network.node_position(NodeId(0)).unwrap()
+        );
+        assert!(path.check_invariants().is_ok());
+
+        let slide = Rect {
+            min: Point([0., 0.]),
+            max: Point([75500., 25500.]),
+        };
+        assert!(rules.standard_outline(&slide));
+        assert!(!rules.standard_outline(&slide.inflate(200.)));
+    }
+}
@@ -0,0 +1,245 @@
+//! Intersection tests between path pieces. Crossing decisions for segments use exact
+//! orientation predicates; contacts closer than the given tolerance (touching endpoints,
+//! tangents, near misses) are reported as intersections as well.
+
+use super::{
+    channel::{Arc, LineSegment, PathPiece},
+    primitives::Point,
+};
+use geometry_predicates::orient2d;
+
+fn sub(Point([ax, ay]): Point, Point([bx, by]): Point) -> [f64; 2] {
+    [ax - bx, ay - by]
+}
+
+fn cross([ax, ay]: [f64; 2], [bx, by]: [f64; 2]) -> f64 {
+    ax * by - ay * bx
+}
+
+fn dot([ax, ay]: [f64; 2], [bx, by]: [f64; 2]) -> f64 {
+    ax * bx + ay * by
+}
+
+fn along(Point([x, y]): Point, [dx, dy]: [f64; 2], t: f64) -> Point {
+    Point([x + t * dx, y + t * dy])
+}
+
+fn distance(a: Point, b: Point) -> f64 {
+    let [dx, dy] = sub(a, b);
+    f64::hypot(dx, dy)
+}
+
+/// Removes points closer than the tolerance to an earlier point
+fn dedup(points: Vec<Point>, tolerance: f64) -> Vec<Point> {
+    let mut unique: Vec<Point> = Vec::with_capacity(points.len());
+    for point in points {
+        if unique.iter().all(|p| distance(*p, point) > tolerance) {
+            unique.push(point);
+        }
+    }
+    unique
+}
+
+/// Whether two segments share at least one point, decided exactly
+pub fn segments_intersect(a: &LineSegment, b: &LineSegment) -> bool {
+    let o1 = orient2d(a.start.0, a.end.0, b.start.0);
+    let o2 = orient2d(a.start.0, a.end.0, b.end.0);
+    let o3 = orient2d(b.start.0, b.end.0, a.start.0);
+    let o4 = orient2d(b.start.0, b.end.0, a.end.0);
+    if o1 * o2 < 0. && o3 * o4 < 0. {
+        return true;
+    }
+    let on = |s: &LineSegment, o: f64, p: Point| o == 0. && s.bounding_box().contains(p);
+    on(a, o1, b.start) || on(a, o2, b.end) || on(b, o3, a.start) || on(b, o4, a.end)
+}
+
+/// Intersection points of two segments. Collinear overlaps are reported by their end points.
+pub fn segment_segment(a: &LineSegment, b: &LineSegment, tolerance: f64) -> Vec<Point> {
+    let o1 = orient2d(a.start.0, a.end.0, b.start.0);
+    let o2 = orient2d(a.start.0, a.end.0, b.end.0);
+    let o3 = orient2d(b.start.0, b.end.0, a.start.0);
+    let o4 = orient2d(b.start.0, b.end.0, a.end.0);
+    if o1 * o2 < 0. && o3 * o4 < 0. {
+        let r = sub(a.end, a.start);
+        let s = sub(b.end, b.start);
+        let t = cross(sub(b.start, a.start), s) / cross(r, s);
+        return vec![along(a.start, r, t)];
+    }
+
+    let mut points = Vec::new();
+    points.extend(
+        [a.start, a.end]
+            .into_iter()
+            .filter(|p| b.distance(*p) <= tolerance),
+    );
+    points.extend(
+        [b.start, b.end]
+            .into_iter()
+            .filter(|p| a.distance(*p) <= tolerance),
+    );
+    dedup(points, tolerance)
+}
+
+/// Intersection points of a segment with the full circle of an arc, as (segment parameter,
+/// point) pairs
+fn segment_circle(segment: &LineSegment, arc: &Arc, tolerance: f64) -> Vec<(f64, Point)> {
+    let r = sub(segment.end, segment.start);
+    let f = sub(segment.start, arc.center);
+    let a = dot(r, r);
+    if a == 0. {
+        return vec![];
+    }
+    let radius = arc.radius();
+    let foot = -dot(f, r) / a;
+    let gap = distance(along(segment.start, r, foot), arc.center) - radius;
+    if gap > tolerance {
+        return vec![];
+    }
+    if gap >= 0. {
+        // Tangent or near miss within tolerance
+        return vec![(foot, along(segment.start, r, foot))];
+    }
+    let discriminant = (dot(f, r) * dot(f, r) - a * (dot(f, f) - radius * radius)).max(0.);
+    let root = discriminant.sqrt() / a;
+    [foot - root, foot + root]
+        .into_iter()
+        .map(|t| (t, along(segment.start, r, t)))
+        .collect()
+}
+
+fn on_arc(arc: &Arc, Point([x, y]): Point, tolerance: f64) -> bool {
+    let Point([cx, cy]) = arc.center;
+    let radius = arc.radius();
+    let slack = if radius > 0. { tolerance / radius } else { 0. };
+    arc.covers_angle(f64::atan2(y - cy, x - cx), slack)
+}
+
+/// Intersection points of a segment and an arc
+pub fn segment_arc(segment: &LineSegment, arc: &Arc, tolerance: f64) -> Vec<Point> {
+    let length = distance(segment.start, segment.end);
+    let slack = if length > 0. { tolerance / length } else { 0. };
+    let mut points: Vec<Point> = segment_circle(segment, arc, tolerance)
+        .into_iter()
+        .filter(|(t, p)| -slack <= *t && *t <= 1. + slack && on_arc(arc, *p, tolerance))
+        .map(|(_, p)| p)
+        .collect();
+    points.extend(
+        [segment.start, segment.end]
+            .into_iter()
+            .filter(|p| arc.distance(*p) <= tolerance),
+    );
+    points.extend(
+        [arc.start, arc.end]
+            .into_iter()
+            .filter(|p| segment.distance(*p) <= tolerance),
+    );
+    dedup(points, tolerance)
+}
+
+/// Intersection points of two arcs. Overlapping arcs of the same circle are reported by the
+/// end points lying on the other arc.
+pub fn arc_arc(a: &Arc, b: &Arc, tolerance: f64) -> Vec<Point> {
+    let (ra, rb) = (a.radius(), b.radius());
+    let offset = sub(b.center, a.center);
+    let d = f64::hypot(offset[0], offset[1]);
+
+    let mut points = Vec::new();
+    if d > tolerance && d <= ra + rb + tolerance && d >= (ra - rb).abs() - tolerance {
+        let along_axis = (d * d + ra * ra - rb * rb) / (2. * d);
+        let h = (ra * ra - along_axis * along_axis).max(0.).sqrt();
+        let [ux, uy] = [offset[0] / d, offset[1] / d];
+        let base = along(a.center, [ux, uy], along_axis);
+        points.extend(
+            [h, -h]
+                .into_iter()
+                .map(|h| along(base, [-uy, ux], h))
+                .filter(|p| on_arc(a, *p, tolerance) && on_arc(b, *p, tolerance)),
+        );
+    }
+    points.extend(
+        [a.start, a.end]
+            .into_iter()
+            .filter(|p| b.distance(*p) <= tolerance),
+    );
+    points.extend(
+        [b.start, b.end]
+            .into_iter()
+            .filter(|p| a.distance(*p) <= tolerance),
+    );
+    dedup(points, tolerance)
+}
+
+impl PathPiece {
+    /// Intersection points with another piece, contacts within tolerance included
+    pub fn intersections(&self, other: &PathPiece, tolerance: f64) -> Vec<Point> {
+        match (self, other) {
+            (PathPiece::LineSegment(a), PathPiece::LineSegment(b)) => {
+                segment_segment(a, b, tolerance)
+            }
+            (PathPiece::LineSegment(s), PathPiece::Arc(a))
+            | (PathPiece::Arc(a), PathPiece::LineSegment(s)) => segment_arc(s, a, tolerance),
+            (PathPiece::Arc(a), PathPiece::Arc(b)) => arc_arc(a, b, tolerance),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn segment(a: [f64; 2], b: [f64; 2]) -> LineSegment {
+        LineSegment {
+            start: Point(a),
+            end: Point(b),
+        }
+    }
+
+    fn close(a: &[Point], b: &[[f64; 2]]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(p, q)| distance(*p, Point(*q)) < 1e-9)
+    }
+
+    #[test]
+    fn segments() {
+        let a = segment([0., 0.], [2., 2.]);
+        assert!(close(
+            &segment_segment(&a, &segment([0., 2.], [2., 0.]), 1e-9),
+            &[[1., 1.]]
+        ));
+        assert!(segment_segment(&a, &segment([3., 0.], [3., 5.]), 1e-9).is_empty());
+        assert!(close(
+            &segment_segment(&a, &segment([1., 1.], [3., 3.]), 1e-9),
+            &[[2., 2.], [1., 1.]]
+        ));
+        assert!(segments_intersect(&a, &segment([2., 2.], [5., 0.])));
+        assert!(!segments_intersect(&a, &segment([2.1, 2.], [5., 0.])));
+    }
+
+    #[test]
+    fn arcs() {
+        // Upper half of the unit circle, counterclockwise
+        let upper = Arc {
+            start: Point([1., 0.]),
+            end: Point([-1., 0.]),
+            center: Point([0., 0.]),
+            right: false,
+        };
+        assert!(close(
+            &segment_arc(&segment([0., -2.], [0., 2.]), &upper, 1e-9),
+            &[[0., 1.]]
+        ));
+        assert!(close(
+            &segment_arc(&segment([-2., 1.], [2., 1.]), &upper, 1e-9),
+            &[[0., 1.]]
+        ));
+        assert!(segment_arc(&segment([-2., -0.5], [2., -0.5]), &upper, 1e-9).is_empty());
+
+        let shifted = Arc {
+            start: Point([2., 0.]),
+            end: Point([0., 0.]),
+            center: Point([1., 0.]),
+            right: false,
+        };
+        let s = 3f64.sqrt() / 2.;
+        assert!(close(&arc_arc(&upper, &shifted, 1e-9), &[[0.5, s]]));
+    }
+}
@@ -0,0 +1,253 @@
+//! Well-formedness checks and repair of channel paths, meant for generators and importers to
+//! assert and fix their output.
+
+use super::{
+    channel::{ChannelPath, LineSegment, PathPiece, SVGPath},
+    primitives::{Point, Rect, Tolerance},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Violated path invariant, pieces are referenced by their index
+pub enum PathDefect {
+    /// The piece doesn't start where the previous one ended
+    Gap { piece: usize, distance: f64 },
+
+    /// The piece has (almost) no length
+    Degenerate { piece: usize },
+
+    /// Start and end of the arc are at different distances from its center
+    RadiusMismatch {
+        piece: usize,
+        start_radius: f64,
+        end_radius: f64,
+    },
+
+    /// Two pieces cross or touch away from their shared end point
+    SelfIntersection {
+        first: usize,
+        second: usize,
+        point: Point,
+    },
+}
+
+fn distance(Point([ax, ay]): Point, Point([bx, by]): Point) -> f64 {
+    f64::hypot(ax - bx, ay - by)
+}
+
+impl ChannelPath {
+    /// Checks the invariants with the default tolerance
+    pub fn check_invariants(&self) -> Result<(), Vec<PathDefect>> {
+        self.check_invariants_within(&Tolerance::default())
+    }
+
+    /// Checks the invariants with a tolerance scaled by the path's extent
+    pub fn check_invariants_within(&self, tolerance: &Tolerance) -> Result<(), Vec<PathDefect>> {
+        self.check_invariants_with(tolerance.at_scale(self.extent()))
+    }
+
+    /// Larger side of the bounding box, 0 for empty paths
+    pub fn extent(&self) -> f64 {
+        self.bounding_box().map_or(0., |Rect { min, max }| {
+            f64::max(max.0[0] - min.0[0], max.0[1] - min.0[1])
+        })
+    }
+
+    /// Checks continuity, arc consistency, and that the path doesn't intersect itself; distances
+    /// up to the tolerance are accepted
+    pub fn check_invariants_with(&self, tolerance: f64) -> Result<(), Vec<PathDefect>> {
+        let mut defects = Vec::new();
+        for (i, piece) in self.pieces.iter().enumerate() {
+            if i > 0 {
+                let gap = distance(self.pieces[i - 1].end(), piece.start());
+                if gap > tolerance {
+                    defects.push(PathDefect::Gap {
+                        piece: i,
+                        distance: gap,
+                    });
+                }
+            }
+            if let PathPiece::Arc(arc) = piece {
+                let (start_radius, end_radius) = (
+                    distance(arc.center, arc.start),
+                    distance(arc.center, arc.end),
+                );
+                if (start_radius - end_radius).abs() > tolerance {
+                    defects.push(PathDefect::RadiusMismatch {
+                        piece: i,
+                        start_radius,
+                        end_radius,
+                    });
+                }
+            }
+            if piece.length().0 <= tolerance {
+                defects.push(PathDefect::Degenerate { piece: i });
+            }
+        }
+
+        for (i, first) in self.pieces.iter().enumerate() {
+            for (j, second) in self.pieces.iter().enumerate().skip(i + 1) {
+                for point in first.intersections(second, tolerance) {
+                    // Consecutive pieces share their joint, so do the ends of closed paths
+                    if j == i + 1 && distance(point, first.end()) <= tolerance
+                        || self.closed
+                            && i == 0
+                            && j == self.pieces.len() - 1
+                            && distance(point, first.start()) <= tolerance
+                    {
+                        continue;
+                    }
+                    defects.push(PathDefect::SelfIntersection {
+                        first: i,
+                        second: j,
+                        point,
+                    });
+                }
+            }
+        }
+
+        if defects.is_empty() {
+            Ok(())
+        } else {
+            Err(defects)
+        }
+    }
+
+    /// Repairs common defects of imported and hand-built paths: removes pieces shorter than the
+    /// tolerance, reverses pieces connected the wrong way round if `reorient` is set, closes
+    /// gaps up to the tolerance, and merges consecutive collinear segments. Returns the number
+    /// of repairs.
+    pub fn heal(&mut self, tolerance: f64, reorient: bool) -> usize {
+        let before = self.pieces.len();
+        self.pieces.retain(|p| p.length().0 > tolerance);
+        let mut repairs = before - self.pieces.len();
+
+        if reorient && self.pieces.len() > 1 {
+            let (first, second) = (self.pieces[0], self.pieces[1]);
+            let connects = |p: Point| {
+                distance(p, second.start()) <= tolerance || distance(p, second.end()) <= tolerance
+            };
+            if !connects(first.end()) && connects(first.start()) {
+                self.pieces[0] = first.reversed();
+                repairs += 1;
+            }
+            for i in 1..self.pieces.len() {
+                let end = self.pieces[i - 1].end();
+                let piece = self.pieces[i];
+                if distance(end, piece.start()) > tolerance
+                    && distance(end, piece.end()) <= tolerance
+                {
+                    self.pieces[i] = piece.reversed();
+                    repairs += 1;
+                }
+            }
+        }
+
+        let mut healed: Vec<PathPiece> = Vec::with_capacity(self.pieces.len());
+        for mut piece in self.pieces.drain(..) {
+            if let Some(previous) = healed.last_mut() {
+                let gap = distance(previous.end(), piece.start());
+                if gap > 0. && gap <= tolerance {
+                    piece.set_start(previous.end());
+                    repairs += 1;
+                }
+                if let (PathPiece::LineSegment(a), PathPiece::LineSegment(b)) = (*previous, piece) {
+                    let merged = LineSegment {
+                        start: a.start,
+                        end: b.end,
+                    };
+                    let Point([ax, ay]) = a.end;
+                    let Point([sx, sy]) = a.start;
+                    let Point([ex, ey]) = b.end;
+                    // Collinear and continuing in the same direction
+                    if a.end == b.start
+                        && merged.distance(a.end) <= tolerance
+                        && (ax - sx) * (ex - ax) + (ay - sy) * (ey - ay) > 0.
+                    {
+                        *previous = PathPiece::LineSegment(merged);
+                        repairs += 1;
+                        continue;
+                    }
+                }
+            }
+            healed.push(piece);
+        }
+        self.pieces = healed;
+        repairs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::channel::{Arc, LineSegment};
+
+    fn line(a: [f64; 2], b: [f64; 2]) -> PathPiece {
+        PathPiece::LineSegment(LineSegment {
+            start: Point(a),
+            end: Point(b),
+        })
+    }
+
+    #[test]
+    fn defects() {
+        let mut path = ChannelPath::new();
+        path.add(line([0., 0.], [10., 0.]));
+        path.add(PathPiece::Arc(Arc {
+            right: false,
+            start: Point([10., 0.]),
+            end: Point([10., 10.]),
+            center: Point([10., 5.]),
+        }));
+        path.add(line([10., 10.], [0., 10.]));
+        assert_eq!(path.check_invariants(), Ok(()));
+
+        path.add(line([0., 11.], [5., -1.]));
+        path.add(line([5., -1.], [5., -1.]));
+        let defects = path.check_invariants().unwrap_err();
+        assert!(defects.contains(&PathDefect::Gap {
+            piece: 3,
+            distance: 1.
+        }));
+        assert!(defects.contains(&PathDefect::Degenerate { piece: 4 }));
+        assert!(defects.iter().any(|d| matches!(
+            d,
+            PathDefect::SelfIntersection {
+                first: 0,
+                second: 3,
+                ..
+            }
+        )));
+
+        let mut skewed = ChannelPath::new();
+        skewed.add(PathPiece::Arc(Arc {
+            right: true,
+            start: Point([0., 0.]),
+            end: Point([4., 1.]),
+            center: Point([2., 0.]),
+        }));
+        assert!(matches!(
+            skewed.check_invariants().unwrap_err()[..],
+            [PathDefect::RadiusMismatch { piece: 0, .. }]
+        ));
+    }
+
+    #[test]
+    fn heal() {
+        let mut path = ChannelPath::new();
+        path.add(line([5., 0.], [0., 0.]));
+        path.add(line([5., 0.], [5., 0.]));
+        path.add(line([5., 1e-4], [10., 0.]));
+        path.add(line([10., 0.], [10., 10.]));
+        path.add(line([10., 20.], [10., 10.]));
+        assert_eq!(path.heal(1e-3, true), 6);
+        assert_eq!(
+            path.pieces,
+            [line([0., 0.], [10., 0.]), line([10., 0.], [10., 20.])]
+        );
+        assert_eq!(path.check_invariants(), Ok(()));
+        assert_eq!(path.heal(1e-3, true), 0);
+    }
+}
@@ -0,0 +1,224 @@
+//! Topology inference for imported geometry. Importers produce one channel with its own two
+//! nodes per drawn path; [Network::infer_junctions] merges coinciding end points into shared
+//! nodes and splits channels where another channel ends on them, so the network connects the
+//! way it is drawn.
+
+use super::{
+    channel::SVGPath,
+    network::{EntityRef, Network, NodeId},
+    pick::project,
+    primitives::Point,
+    topology::move_end,
+};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+/// Changes made by [Network::infer_junctions]
+pub struct JunctionReport {
+    /// Nodes merged into a coinciding node and removed
+    pub merged_nodes: usize,
+
+    /// Channels split where another channel ends on them
+    pub split_channels: usize,
+
+    /// Channels removed for being no longer than the tolerance after merging their end nodes
+    pub removed_channels: usize,
+}
+
+impl Network {
+    /// Connects channels that meet within the tolerance. Nodes closer than the tolerance are
+    /// merged into the first of them in node order, and channel paths are moved onto the
+    /// shared node. A channel passing a node it isn't attached to within the tolerance, away
+    /// from its ends, is split at the closest point into two channels meeting at the node.
+    /// Short channels whose ends merged into the same node are removed.
+    pub fn infer_junctions(&mut self, tolerance: f64) -> JunctionReport {
+        let mut report = JunctionReport::default();
+
+        // Merge nodes bucketed by a grid of the tolerance, comparing with neighboring cells
+        let cell = |Point([x, y]): Point| {
+            (
+                (x / tolerance).floor() as i64,
+                (y / tolerance).floor() as i64,
+            )
+        };
+        let mut cells: HashMap<(i64, i64), Vec<(NodeId, Point)>> = HashMap::new();
+        let mut merged: BTreeMap<NodeId, (NodeId, Point)> = BTreeMap::new();
+        for node in &self.nodes {
+            let Some(position) = self.node_position(node.id) else {
+                continue;
+            };
+            let (cx, cy) = cell(position);
+            let representative = (cx - 1..=cx + 1)
+                .flat_map(|x| (cy - 1..=cy + 1).map(move |y| (x, y)))
+                .filter_map(|key| cells.get(&key))
+                .flatten()
+                .filter(|(_, p)| (*p - position).length() <= tolerance)
+                .min_by_key(|(id, _)| *id)
+                .copied();
+            match representative {
+                Some(representative) => {
+                    merged.insert(node.id, representative);
+                }
+                None => cells.entry((cx, cy)).or_default().push((node.id, position)),
+            }
+        }
+        let resolve = |id: NodeId| merged.get(&id).map_or(id, |(id, _)| *id);
+        for channel in &mut self.channels {
+            for (start, node) in [(true, &mut channel.node_a), (false, &mut channel.node_b)] {
+                if let Some((representative, position)) = merged.get(node) {
+                    *node = *representative;
+                    if let Some(path) = &mut channel.path {
+                        move_end(path, start, *position);
+                    }
+                }
+            }
+        }
+        for module in &mut self.modules {
+            for node in &mut module.nodes {
+                *node = resolve(*node);
+            }
+        }
+        for group in &mut self.groups {
+            for member in &mut group.members {
+                if let EntityRef::Node(id) = member {
+                    *id = resolve(*id);
+                }
+            }
+            let mut seen = Vec::new();
+            group.members.retain(|m| {
+                let new = !seen.contains(m);
+                seen.push(*m);
+                new
+            });
+        }
+        for (id, (representative, position)) in &merged {
+            let port = self
+                .nodes
+                .iter()
+                .find(|n| n.id == *id)
+                .and_then(|n| n.port.clone());
+            if let Some(node) = self.nodes.iter_mut().find(|n| n.id == *representative) {
+                node.position = Some(*position);
+                if node.port.is_none() {
+                    node.port = port;
+                }
+            }
+        }
+        self.nodes.retain(|n| !merged.contains_key(&n.id));
+        report.merged_nodes = merged.len();
+        let before = self.channels.len();
+        self.channels
+            .retain(|c| c.node_a != c.node_b || c.length().is_some_and(|l| l > tolerance));
+        report.removed_channels = before - self.channels.len();
+
+        // Split channels at nodes lying on them
+        let nodes: Vec<(NodeId, Point)> = self
+            .nodes
+            .iter()
+            .filter_map(|n| Some((n.id, self.node_position(n.id)?)))
+            .collect();
+        for (node, point) in nodes {
+            let mut index = 0;
+            while index < self.channels.len() {
+                let channel = &self.channels[index];
+                index += 1;
+                if channel.node_a == node || channel.node_b == node {
+                    continue;
+                }
+                let Some(path) = &channel.path else {
+                    continue;
+                };
+                if !channel
+                    .bounding_box()
+                    .is_some_and(|b| b.inflate(tolerance).contains(point))
+                {
+                    continue;
+                }
+                let mut offset = 0.;
+                let mut closest = (f64::INFINITY, 0.);
+                for piece in &path.pieces {
+                    let (distance, along) = project(piece, point);
+                    if distance < closest.0 {
+                        closest = (distance, offset + along);
+                    }
+                    offset += piece.length().0;
+                }
+                let (distance, along) = closest;
+                if distance > tolerance || along <= tolerance || along >= offset - tolerance {
+                    continue;
+                }
+
+                let id = channel.id;
+                let (inserted, _) = self.split_channel(id, along).unwrap();
+                self.merge_nodes(node, inserted);
+                report.split_channels += 1;
+                // The head is checked again, the tail once the loop reaches it
+                index -= 1;
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::{Channel, ChannelPath, CylindricalShape, LineSegment, PathPiece, Shape},
+        network::{Metadata, Node},
+    };
+
+    #[test]
+    fn t_junction() {
+        // Three separately drawn lines: a bar, a stem ending on the middle of the bar slightly
+        // off, and a continuation of the bar
+        let segments = [
+            (Point([0., 0.]), Point([100., 0.])),
+            (Point([50., 40.]), Point([50., 0.01])),
+            (Point([100.01, 0.]), Point([150., 0.])),
+        ];
+        let mut network = Network::default();
+        for (i, (start, end)) in segments.into_iter().enumerate() {
+            let mut path = ChannelPath::new();
+            path.add(PathPiece::LineSegment(LineSegment { start, end }));
+            network.nodes.push(Node::at(NodeId(2 * i), start));
+            network.nodes.push(Node::at(NodeId(2 * i + 1), end));
+            network.channels.push(Channel {
+                id: i,
+                node_a: NodeId(2 * i),
+                node_b: NodeId(2 * i + 1),
+                shape: Shape::Cylindrical(CylindricalShape { radius: 1. }),
+                path: Some(path),
+                length: None,
+                layer: 0,
+                metadata: Metadata::new(),
+            });
+        }
+        network.add_to_group("bar", EntityRef::Channel(0));
+
+        let report = network.infer_junctions(0.1);
+        assert_eq!(
+            report,
+            JunctionReport {
+                merged_nodes: 1,
+                split_channels: 1,
+                removed_channels: 0,
+            }
+        );
+        assert_eq!(network.nodes.len(), 5);
+        assert_eq!(network.channels[2].node_a, NodeId(1));
+        assert_eq!(
+            network.channels[2].path.as_ref().unwrap().pieces[0].start(),
+            Point([100., 0.])
+        );
+        let (head, tail) = (&network.channels[0], &network.channels[3]);
+        assert_eq!((head.node_b, tail.node_a), (NodeId(3), NodeId(3)));
+        assert_eq!(
+            head.path.as_ref().unwrap().pieces[0].end(),
+            Point([50., 0.01])
+        );
+        assert!((head.length().unwrap() - 50.).abs() < 1e-6);
+        assert!((tail.length().unwrap() - 50.).abs() < 1e-6);
+        assert_eq!(network.group_members("bar").unwrap().len(), 2);
+    }
+}
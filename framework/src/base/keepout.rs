@@ -0,0 +1,261 @@
+use super::{
+    channel::{Channel, LineSegment, PathPiece},
+    footprint::Orientation,
+    network::{EntityRef, Module, Network},
+    polygon::Polygon,
+    primitives::{Point, Rect},
+};
+use crate::parallel;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Area of a keep-out region
+pub enum Region {
+    /// Axis-aligned rectangular region
+    Rectangle(Rect),
+
+    /// Polygonal region
+    Polygon(Polygon),
+}
+
+impl Region {
+    /// Copy of the region moved by the given offset
+    pub fn translated(&self, offset: Point) -> Region {
+        match self {
+            Region::Rectangle(rect) => Region::Rectangle(Rect {
+                min: rect.min.translated(offset),
+                max: rect.max.translated(offset),
+            }),
+            Region::Polygon(Polygon(points)) => Region::Polygon(Polygon(
+                points.iter().map(|p| p.translated(offset)).collect(),
+            )),
+        }
+    }
+
+    /// Copy of the region placed in the orientation at the position, like the contents of a
+    /// module; rectangles become polygons unless the orientation is the identity
+    pub fn placed(&self, position: Point, orientation: &Orientation) -> Region {
+        if orientation.is_identity() {
+            return self.translated(position);
+        }
+        let polygon = Polygon(
+            (self.polygon().0.iter())
+                .map(|p| orientation.place(position, *p))
+                .collect(),
+        );
+        // Keep the winding of the region
+        Region::Polygon(match orientation.mirrored {
+            true => polygon.reversed(),
+            false => polygon,
+        })
+    }
+
+    pub fn polygon(&self) -> Polygon {
+        match self {
+            Region::Rectangle(rect) => Polygon::rectangle(rect),
+            Region::Polygon(polygon) => polygon.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Region that must stay free of channels and modules, e.g., for screws, sensors, or bonding areas
+pub struct KeepOut {
+    /// Unique id of the keep-out region
+    pub id: usize,
+
+    /// Reserved area
+    pub region: Region,
+
+    /// Layer the region applies to, all layers if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// An entity overlapping a keep-out region
+pub struct KeepOutViolation {
+    /// Id of the violated keep-out region
+    pub keep_out: usize,
+
+    /// Offending entity
+    pub entity: EntityRef,
+}
+
+impl KeepOut {
+    /// Whether the region applies to the given layer
+    pub fn applies_to(&self, layer: usize) -> bool {
+        self.layer.is_none_or(|l| l == layer)
+    }
+
+    fn edges(polygon: &Polygon) -> impl Iterator<Item = LineSegment> + '_ {
+        polygon
+            .edges()
+            .map(|(start, end)| LineSegment { start, end })
+    }
+
+    /// Whether the channel outline reaches into the region. Channels without path are ignored.
+    pub fn overlaps_channel(&self, channel: &Channel) -> bool {
+        let path = match &channel.path {
+            Some(path) => path,
+            None => return false,
+        };
+        let polygon = self.region.polygon();
+        let half_width = channel.shape.width() / 2.;
+        path.pieces.iter().any(|piece| {
+            let start = match piece {
+                PathPiece::Arc(arc) => arc.start,
+                PathPiece::LineSegment(line) => line.start,
+            };
+            polygon.contains(start)
+                || Self::edges(&polygon).any(|edge| {
+                    !piece
+                        .intersections(&PathPiece::LineSegment(edge), half_width)
+                        .is_empty()
+                })
+        })
+    }
+
+    /// Whether a hole of the diameter at the position, e.g., of a port, reaches into the region
+    pub fn overlaps_hole(&self, center: Point, diameter: f64) -> bool {
+        let polygon = self.region.polygon();
+        polygon.contains(center)
+            || Self::edges(&polygon).any(|e| e.distance(center) < diameter / 2.)
+    }
+
+    /// Whether the module footprint overlaps the region
+    pub fn overlaps_module(&self, module: &Module) -> bool {
+        let polygon = self.region.polygon();
+        let footprint = module.outline();
+        footprint.0.iter().any(|p| polygon.contains(*p))
+            || polygon.0.iter().any(|p| footprint.contains(*p))
+            || Self::edges(&polygon).any(|a| {
+                Self::edges(&footprint).any(|b| super::intersection::segments_intersect(&a, &b))
+            })
+    }
+}
+
+impl Network {
+    /// All channels overlapping a keep-out region of their layer and all modules overlapping
+    /// any keep-out region, ordered by region. The entities are checked in parallel.
+    pub fn keep_out_violations(&self) -> Vec<KeepOutViolation> {
+        let channels = parallel::map(&self.channels, |channel| {
+            (self.keep_outs.iter())
+                .map(|k| k.applies_to(channel.layer) && k.overlaps_channel(channel))
+                .collect::<Vec<_>>()
+        });
+        let modules = parallel::map(&self.modules, |module| {
+            (self.keep_outs.iter())
+                .map(|k| k.overlaps_module(module))
+                .collect::<Vec<_>>()
+        });
+        let channels = self
+            .channels
+            .iter()
+            .map(|c| EntityRef::Channel(c.id))
+            .zip(channels);
+        let modules = self
+            .modules
+            .iter()
+            .map(|m| EntityRef::Module(m.id))
+            .zip(modules);
+        let entities: Vec<_> = channels.chain(modules).collect();
+        (self.keep_outs.iter().enumerate())
+            .flat_map(|(i, keep_out)| {
+                (entities.iter())
+                    .filter(move |(_, overlaps)| overlaps[i])
+                    .map(|(entity, _)| KeepOutViolation {
+                        keep_out: keep_out.id,
+                        entity: *entity,
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::{ChannelPath, RectangularShape, Shape},
+        network::NodeId,
+        primitives::Dimensions,
+    };
+
+    #[test]
+    fn violations() {
+        let channel = |id, y: f64, layer| {
+            let mut path = ChannelPath::new();
+            path.add(PathPiece::LineSegment(LineSegment {
+                start: Point([0., y]),
+                end: Point([100., y]),
+            }));
+            Channel {
+                id,
+                node_a: NodeId(0),
+                node_b: NodeId(1),
+                shape: Shape::Rectangular(RectangularShape {
+                    width: 10.,
+                    height: 10.,
+                }),
+                path: Some(path),
+                length: None,
+                layer,
+                metadata: Default::default(),
+            }
+        };
+        let keep_out = |id, layer| KeepOut {
+            id,
+            region: Region::Rectangle(Rect {
+                min: Point([40., 4.]),
+                max: Point([60., 20.]),
+            }),
+            layer,
+        };
+        let mut network = Network {
+            // The walls of channel 0 reach into the region, channel 2 runs clear of it
+            channels: vec![channel(0, 0., 0), channel(1, 10., 1), channel(2, -10., 0)],
+            modules: vec![Module {
+                id: 0,
+                position: Point([55., 15.]),
+                size: Dimensions([10., 10.]),
+                nodes: Vec::new(),
+                implementation: None,
+                model: None,
+                footprint: None,
+                orientation: Default::default(),
+                kind: Default::default(),
+                metadata: Default::default(),
+            }],
+            keep_outs: vec![keep_out(0, Some(1)), keep_out(1, None)],
+            ..Default::default()
+        };
+        let violation = |keep_out, entity| KeepOutViolation { keep_out, entity };
+        assert_eq!(
+            network.keep_out_violations(),
+            [
+                violation(0, EntityRef::Channel(1)),
+                violation(0, EntityRef::Module(0)),
+                violation(1, EntityRef::Channel(0)),
+                violation(1, EntityRef::Channel(1)),
+                violation(1, EntityRef::Module(0)),
+            ]
+        );
+        network.channels[0].path = None;
+        assert!(!network.keep_outs[1].overlaps_channel(&network.channels[0]));
+
+        let region = &network.keep_outs[0];
+        assert!(region.overlaps_hole(Point([50., 10.]), 0.));
+        assert!(region.overlaps_hole(Point([35., 10.]), 12.));
+        assert!(!region.overlaps_hole(Point([35., 10.]), 8.));
+        let moved = Module {
+            position: Point([70., 0.]),
+            ..network.modules[0].clone()
+        };
+        assert!(!region.overlaps_module(&moved));
+    }
+}
@@ -0,0 +1,240 @@
+//! Vertical build-up of a chip from physical layers, e.g., laminate sheets or a molded channel
+//! layer bonded to a substrate. The layer stack is the reference for everything along the z
+//! axis: where channels sit, how thick the chip is, and which sheets a laminate consists of.
+
+use super::{channel::Shape, network::Network};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Material of a physical layer
+pub enum Material {
+    /// Polydimethylsiloxane
+    Pdms,
+    Glass,
+    Silicon,
+    /// Polymethyl methacrylate
+    Pmma,
+    Polycarbonate,
+    /// Cyclic olefin copolymer
+    Coc,
+    /// Double-sided pressure-sensitive adhesive tape
+    Adhesive,
+    /// Any other material, by name
+    Other(String),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// How a layer is joined to the layer below it
+pub enum Bonding {
+    /// Oxygen plasma activation, e.g., PDMS on glass
+    Plasma,
+    /// Thermal fusion bonding
+    Thermal,
+    /// Solvent-assisted bonding
+    Solvent,
+    /// Adhesive film or glue
+    Adhesive,
+    /// Reversible mechanical clamping
+    Clamped,
+}
+
+impl Bonding {
+    /// Rough burst pressure in Pa of the bonding between the materials, from reported ranges;
+    /// measure it for designs close to the limit
+    pub fn typical_strength(&self, lower: &Material, upper: &Material) -> f64 {
+        let thermoplastic =
+            |m: &Material| matches!(m, Material::Pmma | Material::Polycarbonate | Material::Coc);
+        match self {
+            Bonding::Plasma if thermoplastic(lower) || thermoplastic(upper) => 1e5,
+            Bonding::Plasma => 3e5,
+            Bonding::Thermal if lower == upper => 1e6,
+            Bonding::Thermal => 5e5,
+            Bonding::Solvent => 8e5,
+            Bonding::Adhesive => 2e5,
+            Bonding::Clamped => 1e5,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// A physical layer of the chip
+pub struct Layer {
+    /// Name of the layer, e.g., used for file names of per-layer exports
+    pub name: String,
+
+    pub material: Material,
+
+    /// Thickness of the layer
+    pub thickness: f64,
+
+    /// Network layer whose channels are cut through this layer, none for solid layers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_layer: Option<usize>,
+
+    /// Bonding to the layer below, none for the bottom layer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bonding: Option<Bonding>,
+
+    /// Pressure the bond to the layer below withstands, the typical one of the bonding and
+    /// materials if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bond_strength: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Physical layers of the chip
+pub struct LayerStack {
+    /// Layers from bottom to top
+    pub layers: Vec<Layer>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Mismatch between a network and its layer stack
+pub enum LayerIssue {
+    /// No physical layer holds the channel's network layer
+    Unmapped {
+        /// Id of the channel
+        channel: usize,
+
+        /// Network layer of the channel
+        layer: usize,
+    },
+
+    /// The channel is deeper than the physical layer holding it
+    TooDeep {
+        /// Id of the channel
+        channel: usize,
+
+        /// Height of the channel's cross-section
+        depth: f64,
+
+        /// Thickness of the physical layer
+        thickness: f64,
+    },
+}
+
+impl LayerStack {
+    /// Total thickness of the chip
+    pub fn thickness(&self) -> f64 {
+        self.layers.iter().map(|l| l.thickness).sum()
+    }
+
+    /// Bottom and top z coordinate of a physical layer, z = 0 at the bottom of the chip
+    pub fn z_range(&self, index: usize) -> Option<[f64; 2]> {
+        let layer = self.layers.get(index)?;
+        let bottom: f64 = self.layers[..index].iter().map(|l| l.thickness).sum();
+        Some([bottom, bottom + layer.thickness])
+    }
+
+    /// Index of the lowest physical layer holding the network layer
+    pub fn physical_layer(&self, network_layer: usize) -> Option<usize> {
+        self.layers
+            .iter()
+            .position(|l| l.network_layer == Some(network_layer))
+    }
+
+    /// Pressure the bond of the physical layer to the one below withstands, none for the bottom
+    /// layer and layers without bonding
+    pub fn bond_strength(&self, index: usize) -> Option<f64> {
+        let lower = self.layers.get(index.checked_sub(1)?)?;
+        let layer = self.layers.get(index)?;
+        let bonding = layer.bonding?;
+        Some(
+            (layer.bond_strength)
+                .unwrap_or_else(|| bonding.typical_strength(&lower.material, &layer.material)),
+        )
+    }
+
+    /// Checks that every channel's network layer is mapped to a physical layer deep enough for
+    /// the channel's cross-section
+    pub fn check(&self, network: &Network) -> Vec<LayerIssue> {
+        let mut issues = Vec::new();
+        for channel in &network.channels {
+            let Some(index) = self.physical_layer(channel.layer) else {
+                issues.push(LayerIssue::Unmapped {
+                    channel: channel.id,
+                    layer: channel.layer,
+                });
+                continue;
+            };
+            let depth = match &channel.shape {
+                Shape::Rectangular(shape) => shape.height,
+                Shape::Cylindrical(shape) => 2. * shape.radius,
+            };
+            let thickness = self.layers[index].thickness;
+            if depth > thickness {
+                issues.push(LayerIssue::TooDeep {
+                    channel: channel.id,
+                    depth,
+                    thickness,
+                });
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::generator::{RandomNetworkSpec, Topology};
+
+    #[test]
+    fn stack() {
+        let layer = |material, thickness, network_layer, bonding| Layer {
+            name: String::new(),
+            material,
+            thickness,
+            network_layer,
+            bonding,
+            bond_strength: None,
+        };
+        let stack = LayerStack {
+            layers: vec![
+                layer(Material::Glass, 1000., None, None),
+                layer(Material::Pdms, 40., Some(0), Some(Bonding::Plasma)),
+                layer(Material::Pdms, 3000., None, None),
+            ],
+        };
+        assert_eq!(stack.thickness(), 4040.);
+        assert_eq!(stack.z_range(1), Some([1000., 1040.]));
+        assert_eq!(stack.z_range(3), None);
+        assert_eq!(stack.physical_layer(0), Some(1));
+        assert_eq!(stack.bond_strength(0), None);
+        assert_eq!(stack.bond_strength(1), Some(3e5));
+        assert_eq!(stack.bond_strength(2), None);
+        assert_eq!(stack.bond_strength(3), None);
+
+        let mut network = Network::random(&RandomNetworkSpec {
+            topology: Topology::Grid {
+                columns: 2,
+                rows: 1,
+            },
+            height: 50.,
+            ..Default::default()
+        });
+        network.channels.push(network.channels[0].clone());
+        network.channels[1].id = 1;
+        network.channels[1].layer = 1;
+        assert_eq!(
+            stack.check(&network),
+            [
+                LayerIssue::TooDeep {
+                    channel: 0,
+                    depth: 50.,
+                    thickness: 40.
+                },
+                LayerIssue::Unmapped {
+                    channel: 1,
+                    layer: 1
+                }
+            ]
+        );
+    }
+}
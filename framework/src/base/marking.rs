@@ -0,0 +1,29 @@
+//! Markings: geometry structured into the chip without fluidic function, e.g., dicing lanes
+//! of panels. Exporters emit them next to the channels, on output layers named by the marking.
+
+use super::{channel::ChannelPath, primitives::Rect};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Non-fluidic geometry of the chip
+pub struct Marking {
+    /// Output layer, e.g., the DXF layer or SVG group
+    pub layer: String,
+
+    /// Closed paths are filled outlines, open paths are stroked
+    pub path: ChannelPath,
+
+    /// Stroke width of open paths
+    #[serde(default)]
+    pub width: f64,
+}
+
+impl Marking {
+    /// Extent of the marking including its stroke
+    pub fn bounding_box(&self) -> Option<Rect> {
+        let path = self.path.bounding_box()?;
+        Some(path.inflate(self.width / 2.))
+    }
+}
@@ -1,3 +1,41 @@
+pub mod annotation;
+pub mod cache;
+pub mod centerline;
 pub mod channel;
+pub mod compact;
+pub mod control;
+pub mod diff;
+pub mod edit;
+pub mod engraving;
+pub mod events;
+pub mod feature;
+pub mod fingerprint;
+pub mod footprint;
+pub mod generator;
+pub mod group;
+pub mod hierarchy;
+pub mod interop;
+pub mod intersection;
+pub mod invariants;
+pub mod junction;
+pub mod keepout;
+pub mod layers;
+pub mod marking;
+pub mod netlist;
 pub mod network;
+pub mod normalize;
+pub mod panel;
+pub mod pick;
+pub mod polygon;
+pub mod port;
 pub mod primitives;
+pub mod render;
+pub mod river;
+pub mod simplify;
+pub mod smoothing;
+pub mod snap;
+pub mod spatial;
+pub mod stream;
+pub mod template;
+pub mod topology;
+pub mod units;
@@ -0,0 +1,191 @@
+//! Compact textual netlist for entering connectivity by hand. Every line describes one channel:
+//!
+//! ```text
+//! # node node shape dimensions [len length]
+//! inlet n1 rect 100 50 len 2000
+//! n1 outlet cyl 25
+//! ```
+//!
+//! Node names are arbitrary tokens and numbered in order of appearance. The resulting network has
+//! no geometry; placement and routing produce it afterwards.
+
+use super::{
+    channel::{Channel, CylindricalShape, RectangularShape, Shape},
+    network::{Network, Node, NodeId},
+};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Syntax error in a netlist, with the 1-based line number
+pub enum NetlistError {
+    /// The line ends before the named field
+    Missing { line: usize, field: &'static str },
+
+    /// A dimension isn't a positive number
+    InvalidNumber { line: usize, token: String },
+
+    /// The shape is neither `rect` nor `cyl`
+    UnknownShape { line: usize, token: String },
+
+    /// Text after the end of the channel description
+    UnexpectedToken { line: usize, token: String },
+}
+
+impl std::fmt::Display for NetlistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetlistError::Missing { line, field } => write!(f, "line {line}: missing {field}"),
+            NetlistError::InvalidNumber { line, token } => {
+                write!(f, "line {line}: `{token}` is not a positive number")
+            }
+            NetlistError::UnknownShape { line, token } => {
+                write!(f, "line {line}: unknown shape `{token}`")
+            }
+            NetlistError::UnexpectedToken { line, token } => {
+                write!(f, "line {line}: unexpected `{token}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetlistError {}
+
+impl Network {
+    /// Parses a netlist; lines are channels, `#` starts a comment. Node names are stored in the
+    /// node metadata under `name`.
+    pub fn from_netlist(text: &str) -> Result<Network, NetlistError> {
+        let mut network = Network::default();
+        let mut names: BTreeMap<String, NodeId> = BTreeMap::new();
+
+        for (index, content) in text.lines().enumerate() {
+            let line = index + 1;
+            let content = content.split('#').next().unwrap_or_default();
+            let mut tokens = content.split_whitespace().peekable();
+            if tokens.peek().is_none() {
+                continue;
+            }
+
+            let mut next = |field| tokens.next().ok_or(NetlistError::Missing { line, field });
+            let mut node = |name: &str| {
+                *names.entry(name.to_string()).or_insert_with(|| {
+                    let id = network.next_node_id();
+                    let mut node = Node::new(id);
+                    node.metadata.insert("name".into(), name.into());
+                    network.nodes.push(node);
+                    id
+                })
+            };
+            let node_a = node(next("first node")?);
+            let node_b = node(next("second node")?);
+
+            let number = |token: &str| {
+                token
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|v| *v > 0. && v.is_finite())
+                    .ok_or_else(|| NetlistError::InvalidNumber {
+                        line,
+                        token: token.to_string(),
+                    })
+            };
+            let shape = match next("shape")? {
+                "rect" => Shape::Rectangular(RectangularShape {
+                    width: number(next("width")?)?,
+                    height: number(next("height")?)?,
+                }),
+                "cyl" => Shape::Cylindrical(CylindricalShape {
+                    radius: number(next("radius")?)?,
+                }),
+                token => {
+                    return Err(NetlistError::UnknownShape {
+                        line,
+                        token: token.to_string(),
+                    })
+                }
+            };
+
+            let mut length = None;
+            while let Some(token) = tokens.next() {
+                match token {
+                    "len" if length.is_none() => {
+                        let token = tokens.next().ok_or(NetlistError::Missing {
+                            line,
+                            field: "length",
+                        })?;
+                        length = Some(number(token)?);
+                    }
+                    token => {
+                        return Err(NetlistError::UnexpectedToken {
+                            line,
+                            token: token.to_string(),
+                        })
+                    }
+                }
+            }
+
+            network.channels.push(Channel {
+                id: network.next_channel_id(),
+                node_a,
+                node_b,
+                shape,
+                path: None,
+                length,
+                layer: 0,
+                metadata: Default::default(),
+            });
+        }
+        Ok(network)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let network = Network::from_netlist(
+            "# T-junction\n\
+             a m rect 100 50 len 2000\n\
+             b m rect 100 50 len 2000 # second inlet\n\
+             \n\
+             m out cyl 25",
+        )
+        .unwrap();
+        assert_eq!(network.nodes.len(), 4);
+        assert_eq!(network.nodes[1].metadata["name"], "m");
+        assert_eq!(network.channels.len(), 3);
+        assert_eq!(network.channels[1].node_b, NodeId(1));
+        assert_eq!(network.channels[1].length, Some(2000.));
+        assert_eq!(network.channels[2].length, None);
+
+        assert_eq!(
+            Network::from_netlist("a b rect 100"),
+            Err(NetlistError::Missing {
+                line: 1,
+                field: "height"
+            })
+        );
+        assert_eq!(
+            Network::from_netlist("\na b hex 1"),
+            Err(NetlistError::UnknownShape {
+                line: 2,
+                token: "hex".into()
+            })
+        );
+        assert_eq!(
+            Network::from_netlist("a b cyl -1"),
+            Err(NetlistError::InvalidNumber {
+                line: 1,
+                token: "-1".into()
+            })
+        );
+        assert_eq!(
+            Network::from_netlist("a b cyl 1 len 5 len 6"),
+            Err(NetlistError::UnexpectedToken {
+                line: 1,
+                token: "len".into()
+            })
+        );
+    }
+}
@@ -1,9 +1,27 @@
-use schemars::{JsonSchema};
+use self::channel::{Channel, LineSegment, PathPiece, SVGPath};
+use super::{
+    annotation::Annotation,
+    cache::ChannelCache,
+    channel,
+    feature::SurfaceFeature,
+    footprint::{Footprint, Orientation},
+    group::Group,
+    hierarchy::{PortModel, Subcircuit},
+    keepout::KeepOut,
+    layers::LayerStack,
+    marking::Marking,
+    port::PortHole,
+    primitives::{Dimensions, Point, Rect},
+    units::LengthUnit,
+};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use super::{channel, primitives::{Point, Dimensions}};
-use self::channel::Channel;
+use std::collections::BTreeMap;
 
-#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+/// Free-form, tool-specific data attached to an entity
+pub type Metadata = BTreeMap<String, serde_json::Value>;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
 #[serde(rename_all = "snake_case")]
 /// A microfluidic channel network
 pub struct Network {
@@ -15,14 +33,185 @@ pub struct Network {
 
     /// The set of modules in the network
     pub modules: Vec<Module>,
+
+    /// Labels and dimension lines documenting the layout
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+
+    /// Regions that must stay free of channels and modules
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keep_outs: Vec<KeepOut>,
+
+    /// Grooves structured into channel walls, e.g., of herringbone mixers
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub surface_features: Vec<SurfaceFeature>,
+
+    /// Non-fluidic geometry, e.g., dicing lanes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub markings: Vec<Marking>,
+
+    /// Named collections of entities
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Group>,
+
+    /// Physical layers the chip is built from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer_stack: Option<LayerStack>,
+
+    /// Physical unit of layout coordinates and lengths. Exporters convert from it to the
+    /// units of their files; without it, they rely on the scale they are configured with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length_unit: Option<LengthUnit>,
+
+    /// Tool-specific data attached to the network
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: Metadata,
+
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub(crate) cache: ChannelCache,
 }
 
-#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+impl Network {
+    /// Smallest node id larger than all ids in use
+    pub fn next_node_id(&self) -> NodeId {
+        NodeId(self.nodes.iter().map(|n| n.id.0 + 1).max().unwrap_or(0))
+    }
+
+    /// Smallest channel id larger than all ids in use
+    pub fn next_channel_id(&self) -> usize {
+        self.channels.iter().map(|c| c.id + 1).max().unwrap_or(0)
+    }
+
+    /// Smallest module id larger than all ids in use
+    pub fn next_module_id(&self) -> usize {
+        self.modules.iter().map(|m| m.id + 1).max().unwrap_or(0)
+    }
+
+    /// Reverses the channel with the id and the surface features along it. Returns false if
+    /// there is no such channel.
+    pub fn reverse_channel(&mut self, id: usize) -> bool {
+        let Some(channel) = self.channels.iter_mut().find(|c| c.id == id) else {
+            return false;
+        };
+        channel.reverse();
+        if let Some(length) = channel.path.as_ref().map(|p| p.length().0) {
+            for feature in self.surface_features.iter_mut().filter(|f| f.channel == id) {
+                (feature.start, feature.end) = (length - feature.end, length - feature.start);
+            }
+        }
+        true
+    }
+
+    /// Moves a node, extending the paths of attached channels by straight leads to the new
+    /// position
+    pub fn move_node(&mut self, id: NodeId, target: Point) {
+        for channel in &mut self.channels {
+            let Some(path) = &mut channel.path else {
+                continue;
+            };
+            if channel.node_a == id {
+                if let Some(start) = path.pieces.first().map(|p| p.start()) {
+                    let lead = LineSegment {
+                        start: target,
+                        end: start,
+                    };
+                    path.pieces.insert(0, PathPiece::LineSegment(lead));
+                }
+            }
+            if channel.node_b == id {
+                if let Some(end) = path.pieces.last().map(|p| p.end()) {
+                    path.add(PathPiece::LineSegment(LineSegment {
+                        start: end,
+                        end: target,
+                    }));
+                }
+            }
+        }
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == id) {
+            node.position = Some(target);
+        }
+    }
+
+    /// Location of a node: its explicit position, or else the end of an attached channel's path
+    pub fn node_position(&self, id: NodeId) -> Option<Point> {
+        let node = self.nodes.iter().find(|n| n.id == id)?;
+        node.position.or_else(|| {
+            self.channels.iter().find_map(|c| {
+                let path = c.path.as_ref()?;
+                if c.node_a == id {
+                    path.pieces.first().map(|p| p.start())
+                } else if c.node_b == id {
+                    path.pieces.last().map(|p| p.end())
+                } else {
+                    None
+                }
+            })
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 /// Microfluidic network node
 pub struct Node {
     /// Unique id of the node
     pub id: NodeId,
+
+    /// Location of the node in the layout, if placed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<Point>,
+
+    /// Through hole making the node an inlet or outlet of the chip
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<PortHole>,
+
+    /// Tool-specific data attached to the node
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: Metadata,
+}
+
+impl Node {
+    pub fn new(id: NodeId) -> Self {
+        Node {
+            id,
+            position: None,
+            port: None,
+            metadata: Metadata::new(),
+        }
+    }
+
+    /// Node placed at the position
+    pub fn at(id: NodeId, position: Point) -> Self {
+        Node {
+            position: Some(position),
+            ..Node::new(id)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Function of a module
+pub enum ModuleKind {
+    /// Fluidic component without further behavior
+    #[default]
+    Generic,
+
+    /// Heater keeping the walls of channels within its footprint at the temperature
+    Heater {
+        /// Wall temperature
+        temperature: f64,
+    },
+
+    /// Valve pinching off the channels passing through its footprint when closed
+    Valve,
+}
+
+impl ModuleKind {
+    pub(crate) fn is_generic(&self) -> bool {
+        *self == ModuleKind::Generic
+    }
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
@@ -32,16 +221,71 @@ pub struct Module {
     /// Unique id of the module
     pub id: usize,
 
-    /// Position of the module
+    /// Position of the module's corner with the smallest coordinates in its canonical
+    /// orientation, the origin of its footprint
     pub position: Point,
 
     /// Size of the module
     pub size: Dimensions,
 
     /// Node ids that are part of the interface of this module
-    pub nodes: Vec<NodeId>
+    pub nodes: Vec<NodeId>,
+
+    /// Optional nested network implementing this module
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub implementation: Option<Subcircuit>,
+
+    /// Condensed port-level model, which the solver uses in place of the implementation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<PortModel>,
+
+    /// Shape of the module relative to its position, the rectangle of its size if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footprint: Option<Footprint>,
+
+    /// Rotation and mirroring of the module about its position
+    #[serde(default, skip_serializing_if = "Orientation::is_identity")]
+    pub orientation: Orientation,
+
+    /// Function of the module beyond its fluidic interface
+    #[serde(default, skip_serializing_if = "ModuleKind::is_generic")]
+    pub kind: ModuleKind,
+
+    /// Tool-specific data attached to the module
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: Metadata,
+}
+
+impl Module {
+    /// Extent of the module: of its footprint, or spanning from its position by its size
+    pub fn bounding_box(&self) -> Rect {
+        if !self.is_plain_rectangle() {
+            return self.outline_bounding_box();
+        }
+        let Point([x, y]) = self.position;
+        let Dimensions([w, h]) = self.size;
+        Rect::enclosing([self.position, Point([x + w, y + h])]).unwrap()
+    }
 }
 
-#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[derive(
+    Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
 /// Identifier of a node
 pub struct NodeId(pub usize);
+
+#[derive(
+    Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+/// Reference to a single entity of a network
+pub enum EntityRef {
+    /// Node with the given id
+    Node(NodeId),
+
+    /// Channel with the given id
+    Channel(usize),
+
+    /// Module with the given id
+    Module(usize),
+}
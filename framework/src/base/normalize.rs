@@ -0,0 +1,189 @@
+//! Canonical form of networks, so that equal designs built in different orders compare,
+//! diff, and fingerprint equal.
+
+use super::{
+    annotation::{Anchor, Annotation},
+    channel::SVGPath,
+    network::{EntityRef, Network, NodeId},
+    snap::Grid,
+};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// New ids by old id, e.g., to carry simulation results over to a normalized network
+pub struct Renumbering {
+    pub nodes: BTreeMap<NodeId, NodeId>,
+    pub channels: BTreeMap<usize, usize>,
+    pub modules: BTreeMap<usize, usize>,
+}
+
+impl Network {
+    /// Brings the network into canonical form: entities sorted by id and renumbered densely
+    /// from zero with all references updated, channels oriented from the lower to the higher
+    /// node id with their paths and surface features reversed, group members sorted, and
+    /// coordinates snapped to multiples of the tolerance unless it is zero. Nested
+    /// subcircuits are normalized as well. Returns the renumbering.
+    pub fn normalize(&mut self, tolerance: f64) -> Renumbering {
+        self.nodes.sort_by_key(|n| n.id);
+        self.channels.sort_by_key(|c| c.id);
+        self.modules.sort_by_key(|m| m.id);
+        self.keep_outs.sort_by_key(|k| k.id);
+        let renumbering = Renumbering {
+            nodes: (self.nodes.iter().enumerate())
+                .map(|(i, n)| (n.id, NodeId(i)))
+                .collect(),
+            channels: (self.channels.iter().enumerate())
+                .map(|(i, c)| (c.id, i))
+                .collect(),
+            modules: (self.modules.iter().enumerate())
+                .map(|(i, m)| (m.id, i))
+                .collect(),
+        };
+        let node = |id: &mut NodeId| *id = renumbering.nodes.get(id).copied().unwrap_or(*id);
+        let channel = |id: &mut usize| *id = renumbering.channels.get(id).copied().unwrap_or(*id);
+        let module = |id: &mut usize| *id = renumbering.modules.get(id).copied().unwrap_or(*id);
+
+        for n in &mut self.nodes {
+            node(&mut n.id);
+        }
+        for (i, keep_out) in self.keep_outs.iter_mut().enumerate() {
+            keep_out.id = i;
+        }
+        let mut reversed = Vec::new();
+        for c in &mut self.channels {
+            channel(&mut c.id);
+            node(&mut c.node_a);
+            node(&mut c.node_b);
+            if c.node_a > c.node_b {
+                c.reverse();
+                if let Some(path) = &c.path {
+                    reversed.push((c.id, path.length().0));
+                }
+            }
+        }
+        for m in &mut self.modules {
+            module(&mut m.id);
+            m.nodes.iter_mut().for_each(node);
+            if let Some(subcircuit) = &mut m.implementation {
+                let inner = subcircuit.network.normalize(tolerance);
+                for port in &mut subcircuit.ports {
+                    node(&mut port.outer);
+                    port.inner = inner.nodes.get(&port.inner).copied().unwrap_or(port.inner);
+                }
+            }
+        }
+        for feature in &mut self.surface_features {
+            channel(&mut feature.channel);
+            if let Some((_, length)) = reversed.iter().find(|(id, _)| *id == feature.channel) {
+                (feature.start, feature.end) = (length - feature.end, length - feature.start);
+            }
+        }
+        let anchor = |anchor: &mut Anchor| match anchor {
+            Anchor::Point(_) => {}
+            Anchor::Channel(id) => channel(id),
+            Anchor::Module(id) => module(id),
+        };
+        for annotation in &mut self.annotations {
+            match annotation {
+                Annotation::Label(label) => anchor(&mut label.anchor),
+                Annotation::Dimension(dimension) => {
+                    anchor(&mut dimension.from);
+                    anchor(&mut dimension.to);
+                }
+            }
+        }
+        for group in &mut self.groups {
+            for member in &mut group.members {
+                match member {
+                    EntityRef::Node(id) => node(id),
+                    EntityRef::Channel(id) => channel(id),
+                    EntityRef::Module(id) => module(id),
+                }
+            }
+            group.members.sort();
+        }
+
+        if tolerance > 0. {
+            let grid = Grid::new(tolerance);
+            self.snap_to_grid(&grid);
+            for annotation in &mut self.annotations {
+                let anchors = match annotation {
+                    Annotation::Label(label) => vec![&mut label.anchor],
+                    Annotation::Dimension(dimension) => {
+                        vec![&mut dimension.from, &mut dimension.to]
+                    }
+                };
+                for anchor in anchors {
+                    if let Anchor::Point(point) = anchor {
+                        *point = grid.snap(*point);
+                    }
+                }
+            }
+        }
+        renumbering
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::{Channel, ChannelPath, CylindricalShape, LineSegment, PathPiece, Shape},
+        network::{Metadata, Node},
+        primitives::Point,
+    };
+
+    #[test]
+    fn canonical_form() {
+        let mut path = ChannelPath::new();
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: Point([10.0000004, 0.]),
+            end: Point([0., 0.]),
+        }));
+        let channel = |id, node_a, node_b, path| Channel {
+            id,
+            node_a: NodeId(node_a),
+            node_b: NodeId(node_b),
+            shape: Shape::Cylindrical(CylindricalShape { radius: 1. }),
+            path,
+            length: None,
+            layer: 0,
+            metadata: Metadata::new(),
+        };
+        let mut network = Network {
+            nodes: vec![
+                Node::at(NodeId(7), Point([10., 0.])),
+                Node::at(NodeId(3), Point([0., 0.])),
+                Node::new(NodeId(5)),
+            ],
+            channels: vec![channel(4, 7, 3, Some(path)), channel(2, 5, 3, None)],
+            ..Default::default()
+        };
+        network.add_to_group("g", EntityRef::Channel(4));
+        network.add_to_group("g", EntityRef::Node(NodeId(3)));
+
+        // Built in another order
+        let mut other = network.clone();
+        other.nodes.reverse();
+        other.channels.reverse();
+        other.groups[0].members.reverse();
+
+        let renumbering = network.normalize(1e-3);
+        assert_eq!(renumbering.nodes[&NodeId(7)], NodeId(2));
+        assert_eq!(network.channels[1].id, 1);
+        assert_eq!(
+            (network.channels[1].node_a, network.channels[1].node_b),
+            (NodeId(0), NodeId(2))
+        );
+        let pieces = &network.channels[1].path.as_ref().unwrap().pieces;
+        assert_eq!(pieces[0].end(), Point([10., 0.]));
+        assert_eq!(
+            network.groups[0].members,
+            [EntityRef::Node(NodeId(0)), EntityRef::Channel(1)]
+        );
+
+        other.normalize(1e-3);
+        assert_eq!(other, network);
+        assert_eq!(other.fingerprint(), network.fingerprint());
+    }
+}
@@ -0,0 +1,222 @@
+//! Panels: arrays of copies of a chip on a wafer or slide, separated by dicing lanes and
+//! labeled per copy. The panel is a single network, so every exporter produces the combined
+//! layout without per-format scripting.
+
+use super::{
+    annotation::{Anchor, Annotation, Label},
+    channel::ChannelPath,
+    marking::Marking,
+    network::Network,
+    primitives::{Dimensions, Point, Rect},
+};
+use crate::export::layout_bounds;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Marking layer of dicing lanes
+pub const DICING_LAYER: &str = "DICING";
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Arrangement of the copies of a panel
+pub struct PanelConfig {
+    /// Number of copies side by side
+    pub columns: usize,
+
+    /// Number of copies on top of each other
+    pub rows: usize,
+
+    /// Chip outline, the extent of the layout if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outline: Option<Rect>,
+
+    /// Gap between the outlines of neighboring copies, also left around the panel
+    pub spacing: f64,
+
+    /// Width of the dicing lanes in the middle of the gaps, no lanes if zero
+    #[serde(default)]
+    pub lane_width: f64,
+
+    /// Text height of the copy labels, no labels if zero
+    #[serde(default)]
+    pub label_height: f64,
+}
+
+/// Letters of a row: A to Z, then AA, AB, and so on
+fn row_name(mut row: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (row % 26) as u8);
+        if row < 26 {
+            break;
+        }
+        row = row / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+impl Network {
+    /// Panel of copies of the network, each with fresh ids; annotations are copied and
+    /// referenced entities renumbered. Rows are lettered from the top, columns numbered from
+    /// the left, and each copy is labeled with its row and column, e.g., "B3", in the lower
+    /// left corner of its outline. Dicing lanes span the panel between and around the copies.
+    pub fn panelize(&self, config: &PanelConfig) -> Network {
+        let outline = config
+            .outline
+            .or_else(|| layout_bounds(self))
+            .unwrap_or(Rect {
+                min: Point([0., 0.]),
+                max: Point([0., 0.]),
+            });
+        let (Point([x0, y0]), Point([x1, y1])) = (outline.min, outline.max);
+        let pitch = [x1 - x0 + config.spacing, y1 - y0 + config.spacing];
+
+        let mut panel = Network {
+            layer_stack: self.layer_stack.clone(),
+            length_unit: self.length_unit,
+            metadata: self.metadata.clone(),
+            ..Default::default()
+        };
+        for row in 0..config.rows {
+            for column in 0..config.columns {
+                let offset = Point([
+                    column as f64 * pitch[0],
+                    (config.rows - 1 - row) as f64 * pitch[1],
+                ]);
+                let first_channel = panel.next_channel_id();
+                let first_module = panel.next_module_id();
+                panel.instantiate(self, &[], offset, &Default::default());
+
+                let anchor = |anchor: &Anchor| match *anchor {
+                    Anchor::Point(point) => Some(Anchor::Point(point.translated(offset))),
+                    Anchor::Channel(id) => (self.channels.iter())
+                        .position(|c| c.id == id)
+                        .map(|i| Anchor::Channel(first_channel + i)),
+                    Anchor::Module(id) => (self.modules.iter())
+                        .position(|m| m.id == id)
+                        .map(|i| Anchor::Module(first_module + i)),
+                };
+                for annotation in &self.annotations {
+                    let copy = match annotation {
+                        Annotation::Label(label) => anchor(&label.anchor).map(|anchor| {
+                            Annotation::Label(Label {
+                                anchor,
+                                ..label.clone()
+                            })
+                        }),
+                        Annotation::Dimension(dimension) => {
+                            match (anchor(&dimension.from), anchor(&dimension.to)) {
+                                (Some(from), Some(to)) => {
+                                    let mut dimension = dimension.clone();
+                                    (dimension.from, dimension.to) = (from, to);
+                                    Some(Annotation::Dimension(dimension))
+                                }
+                                _ => None,
+                            }
+                        }
+                    };
+                    panel.annotations.extend(copy);
+                }
+
+                if config.label_height > 0. {
+                    let inset = config.label_height / 2.;
+                    panel.annotations.push(Annotation::Label(Label {
+                        text: format!("{}{}", row_name(row), column + 1),
+                        anchor: Anchor::Point(outline.min.translated(offset)),
+                        offset: Dimensions([inset, inset]),
+                        height: config.label_height,
+                    }));
+                }
+            }
+        }
+
+        if config.lane_width > 0. {
+            let gap = config.spacing / 2.;
+            let extent = Rect {
+                min: Point([x0 - gap, y0 - gap]),
+                max: Point([
+                    x0 - gap + config.columns as f64 * pitch[0],
+                    y0 - gap + config.rows as f64 * pitch[1],
+                ]),
+            };
+            let half = config.lane_width / 2.;
+            let vertical = (0..=config.columns).map(|i| {
+                let x = extent.min.0[0] + i as f64 * pitch[0];
+                Rect {
+                    min: Point([x - half, extent.min.0[1] - half]),
+                    max: Point([x + half, extent.max.0[1] + half]),
+                }
+            });
+            let horizontal = (0..=config.rows).map(|i| {
+                let y = extent.min.0[1] + i as f64 * pitch[1];
+                Rect {
+                    min: Point([extent.min.0[0] - half, y - half]),
+                    max: Point([extent.max.0[0] + half, y + half]),
+                }
+            });
+            panel
+                .markings
+                .extend(vertical.chain(horizontal).map(|lane| Marking {
+                    layer: DICING_LAYER.into(),
+                    path: ChannelPath::rectangle(&lane),
+                    width: 0.,
+                }));
+        }
+        panel
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{export::dxf::network_to_dxf, fixtures::trap_array};
+
+    #[test]
+    fn panel() {
+        let chip = trap_array(1, 2).network;
+        let outline = layout_bounds(&chip).unwrap();
+        let config = PanelConfig {
+            columns: 3,
+            rows: 2,
+            outline: None,
+            spacing: 1e-3,
+            lane_width: 2e-4,
+            label_height: 2e-4,
+        };
+        let panel = chip.panelize(&config);
+        assert_eq!(panel.nodes.len(), 6 * chip.nodes.len());
+        assert_eq!(panel.channels.len(), 6 * chip.channels.len());
+        assert_eq!(panel.markings.len(), 4 + 3);
+
+        let labels: Vec<(&str, Point)> = (panel.annotations.iter())
+            .filter_map(|a| match a {
+                Annotation::Label(label) => match label.anchor {
+                    Anchor::Point(point) => Some((label.text.as_str(), point)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        assert_eq!(labels.len(), 6);
+        // Row A is on top
+        assert_eq!(labels[0].0, "A1");
+        assert!(labels[0].1 .0[1] > outline.min.0[1]);
+        assert_eq!(
+            labels[5],
+            (
+                "B3",
+                outline.min.translated(Point([
+                    2. * (outline.max.0[0] - outline.min.0[0] + 1e-3),
+                    0.
+                ]))
+            )
+        );
+
+        let bounds = layout_bounds(&panel).unwrap();
+        let width = 3. * (outline.max.0[0] - outline.min.0[0]) + 3e-3 + 2e-4;
+        assert!((bounds.max.0[0] - bounds.min.0[0] - width).abs() < 1e-12);
+        assert!(network_to_dxf(&panel).contains(&format!("8\n{DICING_LAYER}\n")));
+        assert_eq!(row_name(27), "AB");
+    }
+}
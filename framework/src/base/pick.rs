@@ -0,0 +1,269 @@
+//! Hit-testing of layout positions for interactive editors, e.g., selecting the entity under the
+//! cursor. Candidates are looked up in the spatial index and measured against their exact
+//! geometry.
+
+use super::{
+    channel::{ArcAngles, LineSegment, PathPiece, SVGPath},
+    network::{EntityRef, Network},
+    primitives::{Point, Rect},
+    spatial::SpatialIndex,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Entity found at a position
+pub struct Pick {
+    pub entity: EntityRef,
+
+    /// Distance of the position from the entity, zero inside channels and modules
+    pub distance: f64,
+
+    /// Arc length along the channel path to the point closest to the position; only set for
+    /// channels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameter: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Input of [pick], for bindings passing the network along with the position
+pub struct PickQuery {
+    pub network: Network,
+    pub point: Point,
+    pub tolerance: f64,
+}
+
+/// Distance of the point from the piece and the arc length from the piece's start to the
+/// closest point
+pub(crate) fn project(piece: &PathPiece, point: Point) -> (f64, f64) {
+    match piece {
+        PathPiece::LineSegment(line) => {
+            let direction = line.end - line.start;
+            let squared = direction.dot(direction);
+            let t = if squared == 0. {
+                0.
+            } else {
+                ((point - line.start).dot(direction) / squared).clamp(0., 1.)
+            };
+            (line.distance(point), t * squared.sqrt())
+        }
+        PathPiece::Arc(arc) => {
+            let ArcAngles { start, sweep, .. } = arc.angles();
+            let angle = (point - arc.center).angle();
+            let length = arc.length().0;
+            let along = if arc.covers_angle(angle, 0.) {
+                ((angle - start) * sweep.signum()).rem_euclid(std::f64::consts::TAU) * arc.radius()
+            } else if (point - arc.start).length() <= (point - arc.end).length() {
+                0.
+            } else {
+                length
+            };
+            (arc.distance(point), along.min(length))
+        }
+    }
+}
+
+impl SpatialIndex {
+    /// Nearest entity within the tolerance of the point. Nodes lie on top of channels and
+    /// modules and are preferred whenever one is within the tolerance; among channels and
+    /// modules the closest wins. Ties are broken by entity order. Nodes without a position are
+    /// found at the ends of their channel paths, cf. [Network::node_position].
+    pub fn pick(&self, network: &Network, point: Point, tolerance: f64) -> Option<Pick> {
+        let window = Rect {
+            min: point,
+            max: point,
+        }
+        .inflate(tolerance);
+        let candidates = self.query_rect(&window);
+        // A path end within the tolerance lies in the path's bounding box, so the nodes
+        // positioned by paths are among the ends of the candidate channels
+        let path_ends = candidates
+            .iter()
+            .filter_map(|entity| match entity {
+                EntityRef::Channel(id) => network.channels.iter().find(|c| c.id == *id),
+                _ => None,
+            })
+            .flat_map(|c| [c.node_a, c.node_b]);
+        let node = network
+            .nodes
+            .iter()
+            .filter_map(|n| Some((n.id, n.position?)))
+            .chain(path_ends.filter_map(|id| Some((id, network.node_position(id)?))))
+            .map(|(id, position)| (id, (position - point).length()))
+            .filter(|(_, distance)| *distance <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        if let Some((id, distance)) = node {
+            return Some(Pick {
+                entity: EntityRef::Node(id),
+                distance,
+                parameter: None,
+            });
+        }
+
+        let mut best: Option<Pick> = None;
+        for entity in candidates {
+            let candidate = match entity {
+                EntityRef::Channel(id) => {
+                    let Some(channel) = network.channels.iter().find(|c| c.id == id) else {
+                        continue;
+                    };
+                    let Some(path) = &channel.path else {
+                        continue;
+                    };
+                    let mut offset = 0.;
+                    let mut closest = (f64::INFINITY, 0.);
+                    for piece in &path.pieces {
+                        let (distance, along) = project(piece, point);
+                        if distance < closest.0 {
+                            closest = (distance, offset + along);
+                        }
+                        offset += piece.length().0;
+                    }
+                    Pick {
+                        entity,
+                        distance: (closest.0 - channel.shape.width() / 2.).max(0.),
+                        parameter: Some(closest.1),
+                    }
+                }
+                EntityRef::Module(id) => {
+                    let Some(module) = network.modules.iter().find(|m| m.id == id) else {
+                        continue;
+                    };
+                    let distance = match module.contains(point) {
+                        true => 0.,
+                        false => module
+                            .outline()
+                            .edges()
+                            .map(|(start, end)| LineSegment { start, end }.distance(point))
+                            .fold(f64::INFINITY, f64::min),
+                    };
+                    Pick {
+                        entity,
+                        distance,
+                        parameter: None,
+                    }
+                }
+                EntityRef::Node(_) => continue,
+            };
+            if candidate.distance <= tolerance
+                && best.is_none_or(|b| (candidate.distance, entity) < (b.distance, b.entity))
+            {
+                best = Some(candidate);
+            }
+        }
+        best
+    }
+}
+
+impl Network {
+    /// Nearest entity within the tolerance of the point, see [SpatialIndex::pick]. Builds a
+    /// temporary index; editors picking repeatedly should keep an index in sync instead.
+    pub fn pick(&self, point: Point, tolerance: f64) -> Option<Pick> {
+        let cell_size = self
+            .channels
+            .iter()
+            .filter_map(|c| c.bounding_box())
+            .chain(self.modules.iter().map(|m| m.bounding_box()))
+            .map(|r| (r.max.0[0] - r.min.0[0]).max(r.max.0[1] - r.min.0[1]))
+            .fold(tolerance, f64::max);
+        if cell_size <= 0. {
+            return None;
+        }
+        SpatialIndex::from_network(self, cell_size).pick(self, point, tolerance)
+    }
+}
+
+/// [Network::pick] of the query
+pub fn pick(query: PickQuery) -> Option<Pick> {
+    query.network.pick(query.point, query.tolerance)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::{Arc, Channel, ChannelPath, RectangularShape, Shape},
+        network::{Metadata, Module, Node, NodeId},
+        primitives::Dimensions,
+    };
+    use std::f64::consts::PI;
+
+    #[test]
+    fn picking() {
+        let mut path = ChannelPath::new();
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: Point([0., 0.]),
+            end: Point([100., 0.]),
+        }));
+        path.add(PathPiece::Arc(Arc::from_center_angles(
+            Point([100., 50.]),
+            50.,
+            -PI / 2.,
+            PI,
+        )));
+        let mut network = Network {
+            nodes: vec![
+                Node::at(NodeId(0), Point([0., 0.])),
+                Node::at(NodeId(1), Point([100., 100.])),
+            ],
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(1),
+                shape: Shape::Rectangular(RectangularShape {
+                    width: 10.,
+                    height: 5.,
+                }),
+                path: Some(path),
+                length: None,
+                layer: 0,
+                metadata: Metadata::new(),
+            }],
+            modules: vec![Module {
+                id: 3,
+                position: Point([200., 0.]),
+                size: Dimensions([50., 50.]),
+                nodes: vec![],
+                implementation: None,
+                model: None,
+                footprint: None,
+                orientation: Default::default(),
+                kind: Default::default(),
+                metadata: Metadata::new(),
+            }],
+            ..Default::default()
+        };
+
+        let pick = network.pick(Point([40., 8.]), 5.).unwrap();
+        assert_eq!(pick.entity, EntityRef::Channel(0));
+        assert_eq!(pick.distance, 3.);
+        assert_eq!(pick.parameter, Some(40.));
+
+        // On the arc, a quarter turn in
+        let pick = network.pick(Point([160., 50.]), 5.).unwrap();
+        assert!((pick.distance - 5.).abs() < 1e-12);
+        assert!((pick.parameter.unwrap() - 100. - 25. * PI).abs() < 1e-9);
+
+        // Nodes take precedence over the channels they terminate
+        assert_eq!(
+            network.pick(Point([2., 2.]), 5.).unwrap().entity,
+            EntityRef::Node(NodeId(0))
+        );
+        let pick = network.pick(Point([210., 20.]), 5.).unwrap();
+        assert_eq!((pick.entity, pick.distance), (EntityRef::Module(3), 0.));
+        assert_eq!(network.pick(Point([260., 20.]), 11.).unwrap().distance, 10.);
+        assert_eq!(network.pick(Point([60., 60.]), 5.), None);
+
+        // Unplaced nodes are at the ends of the channel path
+        network.nodes.iter_mut().for_each(|n| n.position = None);
+        let pick = network.pick(Point([102., 99.]), 5.).unwrap();
+        assert_eq!(pick.entity, EntityRef::Node(NodeId(1)));
+        assert!((pick.distance - 5f64.sqrt()).abs() < 1e-12);
+        assert_eq!(
+            network.pick(Point([2., 2.]), 5.).unwrap().entity,
+            EntityRef::Node(NodeId(0))
+        );
+    }
+}
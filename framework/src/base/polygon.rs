@@ -0,0 +1,191 @@
+use super::primitives::{Point, Rect};
+use geometry_predicates::orient2d;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Orientation of a closed outline, mathematical Y axis assumed
+pub enum Winding {
+    /// Vertices ordered clockwise, negative signed area
+    Clockwise,
+
+    /// Vertices ordered counterclockwise, positive signed area
+    Counterclockwise,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+/// A polygon given by its vertices; the closing edge from the last to the first vertex is implicit
+pub struct Polygon(pub Vec<Point>);
+
+impl Polygon {
+    /// Axis-aligned rectangle as a counterclockwise polygon
+    pub fn rectangle(rect: &Rect) -> Polygon {
+        let (Point([x0, y0]), Point([x1, y1])) = (rect.min, rect.max);
+        Polygon(vec![
+            Point([x0, y0]),
+            Point([x1, y0]),
+            Point([x1, y1]),
+            Point([x0, y1]),
+        ])
+    }
+
+    /// Edges as (start, end) pairs, including the closing edge
+    pub fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        let n = self.0.len();
+        (0..n).map(move |i| (self.0[i], self.0[(i + 1) % n]))
+    }
+
+    /// Area, positive for counterclockwise vertex order
+    pub fn signed_area(&self) -> f64 {
+        self.edges()
+            .map(|(Point([x0, y0]), Point([x1, y1]))| x0 * y1 - x1 * y0)
+            .sum::<f64>()
+            / 2.
+    }
+
+    pub fn area(&self) -> f64 {
+        self.signed_area().abs()
+    }
+
+    /// Area centroid, None for degenerate polygons without area
+    pub fn centroid(&self) -> Option<Point> {
+        let area = self.signed_area();
+        if area == 0. {
+            return None;
+        }
+        let (cx, cy) =
+            self.edges()
+                .fold((0., 0.), |(cx, cy), (Point([x0, y0]), Point([x1, y1]))| {
+                    let cross = x0 * y1 - x1 * y0;
+                    (cx + (x0 + x1) * cross, cy + (y0 + y1) * cross)
+                });
+        Some(Point([cx / (6. * area), cy / (6. * area)]))
+    }
+
+    /// Vertex order, None for degenerate polygons without area
+    pub fn winding(&self) -> Option<Winding> {
+        let area = self.signed_area();
+        if area > 0. {
+            Some(Winding::Counterclockwise)
+        } else if area < 0. {
+            Some(Winding::Clockwise)
+        } else {
+            None
+        }
+    }
+
+    /// Copy of the polygon with reversed vertex order
+    pub fn reversed(&self) -> Polygon {
+        Polygon(self.0.iter().rev().copied().collect())
+    }
+
+    pub fn bounding_box(&self) -> Option<Rect> {
+        Rect::enclosing(self.0.iter().copied())
+    }
+
+    /// Number of counterclockwise turns of the outline around the point
+    pub fn winding_number(&self, point: Point) -> i32 {
+        let Point([_, py]) = point;
+        self.edges()
+            .map(|(a, b)| {
+                let (Point([_, ay]), Point([_, by])) = (a, b);
+                if ay <= py && by > py && orient2d(a.0, b.0, point.0) > 0. {
+                    1
+                } else if ay > py && by <= py && orient2d(a.0, b.0, point.0) < 0. {
+                    -1
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    /// Whether the point lies on the outline, decided exactly
+    pub fn on_boundary(&self, point: Point) -> bool {
+        self.edges().any(|(a, b)| {
+            orient2d(a.0, b.0, point.0) == 0. && Rect::enclosing([a, b]).unwrap().contains(point)
+        })
+    }
+
+    /// Whether the point lies inside the polygon or on its outline (nonzero rule)
+    pub fn contains(&self, point: Point) -> bool {
+        self.on_boundary(point) || self.winding_number(point) != 0
+    }
+
+    /// Convex hull of a point set as counterclockwise polygon without collinear vertices
+    pub fn convex_hull(points: &[Point]) -> Polygon {
+        let mut points = points.to_vec();
+        points.sort_by(|Point(a), Point(b)| a.partial_cmp(b).unwrap());
+        points.dedup();
+        if points.len() < 3 {
+            return Polygon(points);
+        }
+
+        fn half(points: impl Iterator<Item = Point>) -> Vec<Point> {
+            let mut hull: Vec<Point> = Vec::new();
+            for p in points {
+                while hull.len() >= 2
+                    && orient2d(hull[hull.len() - 2].0, hull[hull.len() - 1].0, p.0) <= 0.
+                {
+                    hull.pop();
+                }
+                hull.push(p);
+            }
+            hull.pop();
+            hull
+        }
+
+        let mut hull = half(points.iter().copied());
+        hull.extend(half(points.iter().rev().copied()));
+        Polygon(hull)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn measures() {
+        let l_shape = Polygon(vec![
+            Point([0., 0.]),
+            Point([2., 0.]),
+            Point([2., 1.]),
+            Point([1., 1.]),
+            Point([1., 2.]),
+            Point([0., 2.]),
+        ]);
+        assert_eq!(l_shape.area(), 3.);
+        assert_eq!(l_shape.winding(), Some(Winding::Counterclockwise));
+        assert_eq!(l_shape.reversed().winding(), Some(Winding::Clockwise));
+        let Point([cx, cy]) = l_shape.centroid().unwrap();
+        assert!((cx - 5. / 6.).abs() < 1e-12 && (cy - 5. / 6.).abs() < 1e-12);
+
+        assert!(l_shape.contains(Point([0.5, 1.5])));
+        assert!(l_shape.contains(Point([1.5, 1.])));
+        assert!(!l_shape.contains(Point([1.5, 1.5])));
+        assert!(l_shape.reversed().contains(Point([0.5, 0.5])));
+    }
+
+    #[test]
+    fn hull() {
+        let points = [
+            Point([0., 0.]),
+            Point([1., 1.]),
+            Point([2., 0.]),
+            Point([2., 2.]),
+            Point([1., 0.]),
+            Point([0., 2.]),
+        ];
+        assert_eq!(
+            Polygon::convex_hull(&points),
+            Polygon(vec![
+                Point([0., 0.]),
+                Point([2., 0.]),
+                Point([2., 2.]),
+                Point([0., 2.])
+            ])
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Physical inlets and outlets: nodes marked as ports are drilled or punched through the chip
+//! and fitted with a connector, and exporters emit the holes at the node positions.
+
+use super::network::{Network, NodeId};
+use super::primitives::Point;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Connector fitted into a port hole
+pub enum ConnectorType {
+    /// Tubing pressed directly into a punched hole
+    PressFit,
+
+    /// Luer lock fitting
+    Luer,
+
+    /// Bonded port with a threaded fitting, e.g., NanoPort
+    Bonded,
+
+    /// Any other connector, by name
+    Other(String),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Through hole connecting a node to the outside of the chip
+pub struct PortHole {
+    /// Hole diameter in layout units
+    pub diameter: f64,
+
+    pub connector: ConnectorType,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Through hole, e.g., an inlet or outlet, at a node
+pub struct DrillHole {
+    /// Id of the node
+    pub node: NodeId,
+
+    /// Hole diameter in layout units
+    pub diameter: f64,
+}
+
+impl Network {
+    /// Positions and holes of all nodes marked as ports, in node order
+    pub fn port_holes(&self) -> Vec<(NodeId, Option<Point>, &PortHole)> {
+        self.nodes
+            .iter()
+            .filter_map(|node| {
+                let port = node.port.as_ref()?;
+                Some((node.id, self.node_position(node.id), port))
+            })
+            .collect()
+    }
+
+    /// Drill holes of all ports
+    pub fn drill_holes(&self) -> Vec<DrillHole> {
+        self.port_holes()
+            .into_iter()
+            .map(|(node, _, port)| DrillHole {
+                node,
+                diameter: port.diameter,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{Channel, ChannelPath, LineSegment, PathPiece, RectangularShape, Shape},
+            network::Node,
+        },
+        export::{dxf::network_to_dxf, gerber::ports_to_excellon, svg::network_to_svg},
+    };
+
+    #[test]
+    fn holes() {
+        let mut network = Network {
+            nodes: vec![Node::new(NodeId(0)), Node::at(NodeId(1), Point([20., 0.]))],
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(1),
+                shape: Shape::Rectangular(RectangularShape {
+                    width: 1.,
+                    height: 1.,
+                }),
+                path: Some(ChannelPath {
+                    pieces: vec![PathPiece::LineSegment(LineSegment {
+                        start: Point([0., 0.]),
+                        end: Point([20., 0.]),
+                    })],
+                    closed: false,
+                }),
+                length: None,
+                layer: 0,
+                metadata: Default::default(),
+            }],
+            ..Default::default()
+        };
+        for node in &mut network.nodes {
+            node.port = Some(PortHole {
+                diameter: 1.5,
+                connector: ConnectorType::PressFit,
+            });
+        }
+        // The unplaced inlet sits at the start of its channel
+        assert_eq!(network.port_holes()[0].1, Some(Point([0., 0.])));
+
+        let svg = network_to_svg(&network);
+        assert!(svg.contains(r#"<circle id="port-1" cx="20" cy="0" r="0.75"/>"#));
+        let dxf = network_to_dxf(&network);
+        assert_eq!(dxf.matches("CIRCLE\n8\nPORTS").count(), 2);
+        let drill = ports_to_excellon(&network, 1.).unwrap();
+        assert!(drill.contains("T1C1.500\n"));
+        assert!(drill.contains("X20.0000Y0.0000\n"));
+    }
+}
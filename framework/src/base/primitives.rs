@@ -5,6 +5,373 @@ use serde::{Deserialize, Serialize};
 /// A two-dimensional point in space
 pub struct Point(pub [f64; 2]);
 
+impl Point {
+    /// Point moved by the coordinates of `offset`
+    pub fn translated(&self, Point([dx, dy]): Point) -> Point {
+        let Point([x, y]) = self;
+        Point([x + dx, y + dy])
+    }
+
+    /// Whether the points coincide within the tolerance, relative to their distance from the
+    /// origin
+    pub fn approx_eq(&self, other: &Point, tolerance: &Tolerance) -> bool {
+        let (Point([ax, ay]), Point([bx, by])) = (self, other);
+        let scale = [ax, ay, bx, by]
+            .into_iter()
+            .fold(0., |m: f64, v| m.max(v.abs()));
+        f64::hypot(ax - bx, ay - by) <= tolerance.at_scale(scale)
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Policy for comparing lengths and coordinates: two values are considered equal if they differ
+/// by at most `absolute + relative * scale`, where the scale is the magnitude of the values or
+/// the extent of the compared geometry
+pub struct Tolerance {
+    pub absolute: f64,
+
+    pub relative: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Tolerance {
+            absolute: 1e-9,
+            relative: 1e-9,
+        }
+    }
+}
+
+impl Tolerance {
+    /// Only an absolute tolerance
+    pub fn absolute(absolute: f64) -> Tolerance {
+        Tolerance {
+            absolute,
+            relative: 0.,
+        }
+    }
+
+    /// Largest accepted deviation for values of the given magnitude
+    pub fn at_scale(&self, scale: f64) -> f64 {
+        self.absolute + self.relative * scale.abs()
+    }
+
+    /// Whether the values are equal within the tolerance
+    pub fn eq(&self, a: f64, b: f64) -> bool {
+        (a - b).abs() <= self.at_scale(a.abs().max(b.abs()))
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+/// A three-dimensional point in space, z pointing out of the layout plane
+pub struct Point3(pub [f64; 3]);
+
+impl Point3 {
+    /// Point of the layout plane at the height z
+    pub fn from_point(Point([x, y]): Point, z: f64) -> Point3 {
+        Point3([x, y, z])
+    }
+
+    /// Projection onto the layout plane
+    pub fn xy(&self) -> Point {
+        let Point3([x, y, _]) = self;
+        Point([*x, *y])
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Default)]
+/// Displacement in the layout plane
+pub struct Vector2(pub [f64; 2]);
+
+impl Vector2 {
+    /// Unit vector at the angle in radians, counterclockwise from the positive x axis
+    pub fn from_angle(angle: f64) -> Vector2 {
+        Vector2([angle.cos(), angle.sin()])
+    }
+
+    pub fn dot(&self, Vector2([bx, by]): Vector2) -> f64 {
+        let Vector2([ax, ay]) = self;
+        ax * bx + ay * by
+    }
+
+    /// z component of the cross product, positive if `other` is counterclockwise of `self`
+    pub fn cross(&self, Vector2([bx, by]): Vector2) -> f64 {
+        let Vector2([ax, ay]) = self;
+        ax * by - ay * bx
+    }
+
+    pub fn length(&self) -> f64 {
+        let Vector2([x, y]) = self;
+        f64::hypot(*x, *y)
+    }
+
+    /// Vector of length 1 in the same direction, None for the zero vector
+    pub fn normalized(&self) -> Option<Vector2> {
+        let length = self.length();
+        (length > 0.).then(|| *self * (1. / length))
+    }
+
+    /// Angle in radians in (-pi, pi], counterclockwise from the positive x axis
+    pub fn angle(&self) -> f64 {
+        let Vector2([x, y]) = self;
+        f64::atan2(*y, *x)
+    }
+
+    /// Vector rotated counterclockwise by the angle in radians
+    pub fn rotated(&self, angle: f64) -> Vector2 {
+        let Vector2([x, y]) = self;
+        let (sin, cos) = angle.sin_cos();
+        Vector2([cos * x - sin * y, sin * x + cos * y])
+    }
+
+    /// Vector rotated counterclockwise by a quarter turn
+    pub fn perpendicular(&self) -> Vector2 {
+        let Vector2([x, y]) = self;
+        Vector2([-y, *x])
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Default)]
+/// Displacement in space
+pub struct Vector3(pub [f64; 3]);
+
+impl Vector3 {
+    pub fn dot(&self, Vector3([bx, by, bz]): Vector3) -> f64 {
+        let Vector3([ax, ay, az]) = self;
+        ax * bx + ay * by + az * bz
+    }
+
+    pub fn cross(&self, Vector3([bx, by, bz]): Vector3) -> Vector3 {
+        let Vector3([ax, ay, az]) = self;
+        Vector3([ay * bz - az * by, az * bx - ax * bz, ax * by - ay * bx])
+    }
+
+    pub fn length(&self) -> f64 {
+        self.dot(*self).sqrt()
+    }
+
+    /// Vector of length 1 in the same direction, None for the zero vector
+    pub fn normalized(&self) -> Option<Vector3> {
+        let length = self.length();
+        (length > 0.).then(|| *self * (1. / length))
+    }
+}
+
+macro_rules! vector_ops {
+    ($vector:ident, $point:ident) => {
+        impl std::ops::Add for $vector {
+            type Output = $vector;
+
+            fn add(self, other: $vector) -> $vector {
+                $vector(std::array::from_fn(|i| self.0[i] + other.0[i]))
+            }
+        }
+
+        impl std::ops::Sub for $vector {
+            type Output = $vector;
+
+            fn sub(self, other: $vector) -> $vector {
+                $vector(std::array::from_fn(|i| self.0[i] - other.0[i]))
+            }
+        }
+
+        impl std::ops::Neg for $vector {
+            type Output = $vector;
+
+            fn neg(self) -> $vector {
+                $vector(self.0.map(|v| -v))
+            }
+        }
+
+        impl std::ops::Mul<f64> for $vector {
+            type Output = $vector;
+
+            fn mul(self, factor: f64) -> $vector {
+                $vector(self.0.map(|v| v * factor))
+            }
+        }
+
+        impl std::ops::Add<$vector> for $point {
+            type Output = $point;
+
+            fn add(self, offset: $vector) -> $point {
+                $point(std::array::from_fn(|i| self.0[i] + offset.0[i]))
+            }
+        }
+
+        impl std::ops::Sub<$vector> for $point {
+            type Output = $point;
+
+            fn sub(self, offset: $vector) -> $point {
+                $point(std::array::from_fn(|i| self.0[i] - offset.0[i]))
+            }
+        }
+
+        /// Displacement from the other point to this one
+        impl std::ops::Sub for $point {
+            type Output = $vector;
+
+            fn sub(self, other: $point) -> $vector {
+                $vector(std::array::from_fn(|i| self.0[i] - other.0[i]))
+            }
+        }
+    };
+}
+
+vector_ops!(Vector2, Point);
+vector_ops!(Vector3, Point3);
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Position with a direction in the layout plane, e.g., of a port or a path end
+pub struct Pose {
+    pub position: Point,
+
+    /// Direction in radians, counterclockwise from the positive x axis
+    pub heading: f64,
+}
+
+impl Pose {
+    pub fn new(position: Point, heading: f64) -> Pose {
+        Pose { position, heading }
+    }
+
+    /// Unit vector of the heading
+    pub fn direction(&self) -> Vector2 {
+        Vector2::from_angle(self.heading)
+    }
+
+    /// Position at the distance ahead
+    pub fn forward(&self, distance: f64) -> Point {
+        self.position + self.direction() * distance
+    }
+
+    /// Pose facing the opposite direction
+    pub fn reversed(&self) -> Pose {
+        Pose {
+            heading: self.heading + std::f64::consts::PI,
+            ..*self
+        }
+    }
+
+    /// Point given in the pose's frame (x ahead, y to the left) in layout coordinates
+    pub fn transform(&self, Point(local): Point) -> Point {
+        self.position + Vector2(local).rotated(self.heading)
+    }
+
+    /// Point given in layout coordinates in the pose's frame
+    pub fn to_local(&self, point: Point) -> Point {
+        Point((point - self.position).rotated(-self.heading).0)
+    }
+
+    /// Pose given in this pose's frame in layout coordinates
+    pub fn compose(&self, local: &Pose) -> Pose {
+        Pose {
+            position: self.transform(local.position),
+            heading: self.heading + local.heading,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Copy, Clone)]
 /// Dimensions in x and y direction
 pub struct Dimensions(pub [f64; 2]);
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, PartialEq, Copy, Clone)]
+#[serde(rename_all = "snake_case")]
+/// Axis-aligned rectangle
+pub struct Rect {
+    /// Corner with the smallest coordinates
+    pub min: Point,
+
+    /// Corner with the largest coordinates
+    pub max: Point,
+}
+
+impl Rect {
+    /// Smallest rectangle containing all given points
+    pub fn enclosing(points: impl IntoIterator<Item = Point>) -> Option<Rect> {
+        points.into_iter().fold(None, |rect, p| match rect {
+            None => Some(Rect { min: p, max: p }),
+            Some(rect) => Some(rect.union(&Rect { min: p, max: p })),
+        })
+    }
+
+    pub fn union(&self, other: &Rect) -> Rect {
+        let (Point([ax0, ay0]), Point([ax1, ay1])) = (self.min, self.max);
+        let (Point([bx0, by0]), Point([bx1, by1])) = (other.min, other.max);
+        Rect {
+            min: Point([ax0.min(bx0), ay0.min(by0)]),
+            max: Point([ax1.max(bx1), ay1.max(by1)]),
+        }
+    }
+
+    /// Rectangle grown by the given margin on all sides
+    pub fn inflate(&self, margin: f64) -> Rect {
+        let (Point([x0, y0]), Point([x1, y1])) = (self.min, self.max);
+        Rect {
+            min: Point([x0 - margin, y0 - margin]),
+            max: Point([x1 + margin, y1 + margin]),
+        }
+    }
+
+    pub fn contains(&self, Point([x, y]): Point) -> bool {
+        let (Point([x0, y0]), Point([x1, y1])) = (self.min, self.max);
+        x0 <= x && x <= x1 && y0 <= y && y <= y1
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        let (Point([ax0, ay0]), Point([ax1, ay1])) = (self.min, self.max);
+        let (Point([bx0, by0]), Point([bx1, by1])) = (other.min, other.max);
+        ax0 <= bx1 && bx0 <= ax1 && ay0 <= by1 && by0 <= ay1
+    }
+
+    /// Euclidean distance from a point to the rectangle, zero inside
+    pub fn distance(&self, Point([x, y]): Point) -> f64 {
+        let (Point([x0, y0]), Point([x1, y1])) = (self.min, self.max);
+        let dx = (x0 - x).max(0.).max(x - x1);
+        let dy = (y0 - y).max(0.).max(y - y1);
+        f64::hypot(dx, dy)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    #[test]
+    fn tolerance() {
+        let tolerance = Tolerance::default();
+        assert!(tolerance.eq(1e6, 1e6 + 1e-4));
+        assert!(!tolerance.eq(1., 1. + 1e-6));
+        assert!(Point([0.1 + 0.2, 0.]).approx_eq(&Point([0.3, 0.]), &tolerance));
+        assert!(!Point([0., 0.]).approx_eq(&Point([0., 1e-3]), &Tolerance::absolute(1e-4)));
+    }
+
+    #[test]
+    fn vectors_and_poses() {
+        let tolerance = Tolerance::default();
+        let v = Point([3., 4.]) - Point([0., 0.]);
+        assert_eq!(v.length(), 5.);
+        assert_eq!(v.cross(v.perpendicular()), 25.);
+        assert_eq!(Point([1., 1.]) + v * 2., Point([7., 9.]));
+        let z = Vector3([1., 0., 0.]).cross(Vector3([0., 1., 0.]));
+        assert_eq!(z, Vector3([0., 0., 1.]));
+        assert_eq!(
+            Point3::from_point(Point([1., 2.]), 3.).xy(),
+            Point([1., 2.])
+        );
+
+        let port = Pose::new(Point([10., 0.]), FRAC_PI_2);
+        assert!(port.forward(2.).approx_eq(&Point([10., 2.]), &tolerance));
+        let local = Point([1., 1.]);
+        let global = port.transform(local);
+        assert!(global.approx_eq(&Point([9., 1.]), &tolerance));
+        assert!(port.to_local(global).approx_eq(&local, &tolerance));
+        let composed = port.compose(&Pose::new(local, FRAC_PI_2));
+        assert!((composed.heading - PI).abs() < 1e-12);
+        assert!((port.reversed().direction() + port.direction()).length() < 1e-12);
+    }
+}
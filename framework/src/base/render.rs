@@ -0,0 +1,218 @@
+//! Mapping from layout coordinates, which use a mathematical Y axis, to the coordinates and
+//! number formatting of an output format.
+
+use super::{
+    primitives::{Point, Rect},
+    units::LengthUnit,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Direction of the output's Y axis
+pub enum YAxis {
+    /// Y grows upwards like in the layout, e.g., DXF and Gerber
+    #[default]
+    Up,
+
+    /// Y grows downwards, e.g., SVG and raster images
+    Down,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Output coordinate system relative to the layout
+pub struct CoordinateSystem {
+    /// Layout point mapped to the output origin
+    pub origin: Point,
+
+    /// Direction of the output's Y axis
+    pub y_axis: YAxis,
+
+    /// Output units per layout unit
+    pub scale: f64,
+}
+
+impl Default for CoordinateSystem {
+    fn default() -> Self {
+        CoordinateSystem {
+            origin: Point([0., 0.]),
+            y_axis: YAxis::Up,
+            scale: 1.,
+        }
+    }
+}
+
+impl CoordinateSystem {
+    /// Whether the mapping mirrors the layout, turning counterclockwise into clockwise
+    pub fn flips_y(&self) -> bool {
+        self.y_axis == YAxis::Down
+    }
+
+    /// Output coordinates of a layout point
+    pub fn apply(&self, Point([x, y]): Point) -> Point {
+        let Point([ox, oy]) = self.origin;
+        let y = (y - oy) * self.scale;
+        Point([(x - ox) * self.scale, if self.flips_y() { -y } else { y }])
+    }
+
+    /// Output length of a layout distance
+    pub fn length(&self, length: f64) -> f64 {
+        length * self.scale
+    }
+
+    /// Output direction of a layout angle in radians
+    pub fn angle(&self, angle: f64) -> f64 {
+        if self.flips_y() {
+            -angle
+        } else {
+            angle
+        }
+    }
+
+    /// Output extent of a layout rectangle
+    pub fn apply_rect(&self, rect: &Rect) -> Rect {
+        Rect::enclosing([self.apply(rect.min), self.apply(rect.max)]).unwrap()
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Rounding of output numbers
+pub enum Precision {
+    /// Shortest representation that reads back exactly
+    #[default]
+    Full,
+
+    /// Fixed number of decimal places
+    Decimals(usize),
+
+    /// Number of significant digits
+    Significant(usize),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Formatting of output numbers
+pub struct NumberFormat {
+    pub precision: Precision,
+
+    /// Drops trailing zeros after the decimal point, and the point itself if nothing remains
+    #[serde(default)]
+    pub trim_zeros: bool,
+}
+
+impl NumberFormat {
+    /// Compact, diff-friendly formatting with the given number of decimal places
+    pub fn compact(decimals: usize) -> Self {
+        NumberFormat {
+            precision: Precision::Decimals(decimals),
+            trim_zeros: true,
+        }
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        let mut text = match self.precision {
+            Precision::Full => format!("{value}"),
+            Precision::Decimals(decimals) => format!("{value:.decimals$}"),
+            Precision::Significant(digits) => {
+                if value == 0. || !value.is_finite() {
+                    format!("{value}")
+                } else {
+                    let magnitude = value.abs().log10().floor() as i32;
+                    let decimals = digits.max(1) as i32 - 1 - magnitude;
+                    if decimals >= 0 {
+                        format!("{value:.0$}", decimals as usize)
+                    } else {
+                        let unit = 10f64.powi(-decimals);
+                        format!("{:.0}", (value / unit).round() * unit)
+                    }
+                }
+            }
+        };
+        if self.trim_zeros && text.contains('.') {
+            text.truncate(text.trim_end_matches('0').trim_end_matches('.').len());
+        }
+        // Rounding leaves negative zeros of tiny negative values
+        if text.starts_with('-') && text[1..].chars().all(|c| c == '0' || c == '.') {
+            text.remove(0);
+        }
+        text
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Settings shared by all exporters
+pub struct RenderConfig {
+    pub coordinate_system: CoordinateSystem,
+
+    #[serde(default)]
+    pub number_format: NumberFormat,
+
+    /// Whether SVG path data uses relative commands, which are shorter for detailed paths
+    #[serde(default)]
+    pub relative_commands: bool,
+
+    /// Unit of output coordinates. If the network declares its length unit, the scale of
+    /// the coordinate system is replaced by the conversion between the two.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<LengthUnit>,
+}
+
+impl RenderConfig {
+    /// Configuration of formats with a downward Y axis, e.g., SVG
+    pub fn y_down() -> Self {
+        RenderConfig {
+            coordinate_system: CoordinateSystem {
+                y_axis: YAxis::Down,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Configuration converting from the given layout unit to the output unit, and the output
+    /// unit if it is known: the configured one, or the layout unit at a scale of one
+    pub fn resolve(&self, layout: Option<LengthUnit>) -> (RenderConfig, Option<LengthUnit>) {
+        let mut config = *self;
+        match (self.unit, layout) {
+            (Some(output), Some(layout)) => {
+                config.coordinate_system.scale = output.per(layout);
+                (config, Some(output))
+            }
+            (None, Some(layout)) if self.coordinate_system.scale == 1. => (config, Some(layout)),
+            (output, _) => (config, output),
+        }
+    }
+
+    /// Formats an output number
+    pub fn number(&self, value: f64) -> String {
+        self.number_format.format(value)
+    }
+
+    /// Output coordinates of a layout point as they appear in the formatted output
+    fn rounded(&self, point: Point) -> [f64; 2] {
+        let Point(coordinates) = self.coordinate_system.apply(point);
+        coordinates.map(|c| self.number(c).parse().unwrap_or(c))
+    }
+
+    /// Formats the output offset between two layout points as "dx dy"; offsets are taken
+    /// between the rounded positions, so that rounding errors don't accumulate
+    pub fn offset(&self, from: Point, to: Point) -> String {
+        let ([fx, fy], [tx, ty]) = (self.rounded(from), self.rounded(to));
+        format!("{} {}", self.number(tx - fx), self.number(ty - fy))
+    }
+
+    /// Formats the output coordinates of a layout point as "x y"
+    pub fn point(&self, point: Point) -> String {
+        let Point([x, y]) = self.coordinate_system.apply(point);
+        format!("{} {}", self.number(x), self.number(y))
+    }
+
+    /// Formats the output length of a layout distance
+    pub fn length(&self, length: f64) -> String {
+        self.number(self.coordinate_system.length(length))
+    }
+}
@@ -0,0 +1,290 @@
+//! River routing of channel arrays, e.g., for trap arrays and parallelized assays: ordered
+//! sources are connected to equally ordered targets across a free band without crossings.
+//!
+//! Sources lie below the band and targets above it. Every channel runs up from its source to
+//! a horizontal track, along it, and up to its target. Channels shifting right take tracks
+//! from the top down in order, channels shifting left from the bottom up, which keeps all of
+//! them apart by the spacing.
+
+use super::{
+    channel::{Channel, ChannelPath, LineSegment, PathPiece, Shape},
+    network::{Metadata, Network, NodeId},
+    primitives::Point,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Parameters of a routed channel array
+pub struct RiverRouting {
+    /// Distance between neighboring tracks and from the band's borders
+    pub spacing: f64,
+
+    /// Radius of the arcs replacing the corners, sharp corners if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bend_radius: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Reasons a channel array can't be routed
+pub enum RiverError {
+    /// Different numbers of sources and targets
+    CountMismatch { sources: usize, targets: usize },
+
+    /// Sources or targets aren't ordered by increasing x
+    Unordered,
+
+    /// A port node has no position
+    UnplacedNode(NodeId),
+
+    /// The band between the highest source and the lowest target is too narrow for the tracks
+    InsufficientSpace { required: f64, available: f64 },
+
+    /// The channel with the given id would cross the keep-out region with the given id
+    KeepOut { channel: usize, keep_out: usize },
+}
+
+impl std::fmt::Display for RiverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RiverError::CountMismatch { sources, targets } => {
+                write!(
+                    f,
+                    "{sources} sources can't be connected to {targets} targets"
+                )
+            }
+            RiverError::Unordered => write!(f, "ports must be ordered by increasing x"),
+            RiverError::UnplacedNode(NodeId(id)) => write!(f, "node {id} has no position"),
+            RiverError::InsufficientSpace {
+                required,
+                available,
+            } => write!(
+                f,
+                "routing needs a band of {required}, but only {available} is free"
+            ),
+            RiverError::KeepOut { channel, keep_out } => {
+                write!(f, "channel {channel} crosses keep-out region {keep_out}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RiverError {}
+
+fn segment(start: Point, end: Point) -> PathPiece {
+    PathPiece::LineSegment(LineSegment { start, end })
+}
+
+impl RiverRouting {
+    /// Paths from each source to the target with the same index
+    pub fn route(
+        &self,
+        sources: &[Point],
+        targets: &[Point],
+    ) -> Result<Vec<ChannelPath>, RiverError> {
+        let _span = crate::trace::span!("river_route");
+        if sources.len() != targets.len() {
+            return Err(RiverError::CountMismatch {
+                sources: sources.len(),
+                targets: targets.len(),
+            });
+        }
+        let increasing = |points: &[Point]| points.windows(2).all(|w| w[0].0[0] < w[1].0[0]);
+        if !increasing(sources) || !increasing(targets) {
+            return Err(RiverError::Unordered);
+        }
+        let bottom = sources.iter().map(|p| p.0[1]).fold(f64::MIN, f64::max);
+        let top = targets.iter().map(|p| p.0[1]).fold(f64::MAX, f64::min);
+
+        let shift = |i: usize| targets[i].0[0] - sources[i].0[0];
+        let right: Vec<usize> = (0..sources.len()).filter(|i| shift(*i) > 0.).collect();
+        let left: Vec<usize> = (0..sources.len()).filter(|i| shift(*i) < 0.).collect();
+        let tracks = right.len().max(left.len());
+        let required = (tracks + 1) as f64 * self.spacing;
+        if tracks > 0 && top - bottom < required {
+            return Err(RiverError::InsufficientSpace {
+                required,
+                available: top - bottom,
+            });
+        }
+
+        // Track 0 is the lowest
+        let mut track = vec![0; sources.len()];
+        for (k, i) in right.iter().rev().enumerate() {
+            track[*i] = k;
+        }
+        for (k, i) in left.iter().enumerate() {
+            track[*i] = k;
+        }
+        Ok((0..sources.len())
+            .map(|i| {
+                let (source, target) = (sources[i], targets[i]);
+                let mut path = ChannelPath::new();
+                if shift(i) == 0. {
+                    path.add(segment(source, target));
+                    return path;
+                }
+                let y = bottom + (track[i] + 1) as f64 * self.spacing;
+                let corners = [Point([source.0[0], y]), Point([target.0[0], y])];
+                path.add(segment(source, corners[0]));
+                path.add(segment(corners[0], corners[1]));
+                path.add(segment(corners[1], target));
+                if let Some(radius) = self.bend_radius {
+                    path.smooth_kinks(radius, 0.);
+                }
+                path
+            })
+            .collect())
+    }
+}
+
+impl Network {
+    /// Adds a routed channel from each source node to the target node with the same index on
+    /// layer 0; returns the ids of the new channels. Nothing is added if a channel would cross
+    /// a keep-out region.
+    pub fn add_channel_array(
+        &mut self,
+        sources: &[NodeId],
+        targets: &[NodeId],
+        shape: &Shape,
+        routing: &RiverRouting,
+    ) -> Result<Vec<usize>, RiverError> {
+        let positions = |nodes: &[NodeId]| {
+            nodes
+                .iter()
+                .map(|n| self.node_position(*n).ok_or(RiverError::UnplacedNode(*n)))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let paths = routing.route(&positions(sources)?, &positions(targets)?)?;
+        let first = self.next_channel_id();
+        let channels: Vec<Channel> = (paths.into_iter().enumerate())
+            .map(|(i, path)| Channel {
+                id: first + i,
+                node_a: sources[i],
+                node_b: targets[i],
+                shape: *shape,
+                path: Some(path),
+                length: None,
+                layer: 0,
+                metadata: Metadata::new(),
+            })
+            .collect();
+        for channel in &channels {
+            let crossed = (self.keep_outs.iter())
+                .find(|k| k.applies_to(channel.layer) && k.overlaps_channel(channel));
+            if let Some(keep_out) = crossed {
+                return Err(RiverError::KeepOut {
+                    channel: channel.id,
+                    keep_out: keep_out.id,
+                });
+            }
+        }
+        self.channels.extend(channels);
+        Ok((first..first + sources.len()).collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::RectangularShape,
+        keepout::{KeepOut, Region},
+        network::Node,
+        primitives::Rect,
+    };
+
+    #[test]
+    fn array() {
+        // Four traps fanning in from a 1000 pitch to a 300 pitch
+        let mut network = Network::default();
+        for i in 0..4 {
+            let x = i as f64;
+            network
+                .nodes
+                .push(Node::at(NodeId(i), Point([1000. * x, 0.])));
+            network
+                .nodes
+                .push(Node::at(NodeId(4 + i), Point([1050. + 300. * x, 5000.])));
+        }
+        let routing = RiverRouting {
+            spacing: 200.,
+            bend_radius: Some(50.),
+        };
+        let shape = Shape::Rectangular(RectangularShape {
+            width: 100.,
+            height: 50.,
+        });
+        let sources: Vec<NodeId> = (0..4).map(NodeId).collect();
+        let targets: Vec<NodeId> = (4..8).map(NodeId).collect();
+        let ids = network
+            .add_channel_array(&sources, &targets, &shape, &routing)
+            .unwrap();
+        assert_eq!(ids, [0, 1, 2, 3]);
+
+        // A keep-out on the upper track of the first channel blocks a second array
+        let mut blocked = Network {
+            channels: Vec::new(),
+            ..network.clone()
+        };
+        let region = Region::Rectangle(Rect {
+            min: Point([400., 350.]),
+            max: Point([600., 450.]),
+        });
+        blocked.keep_outs.push(KeepOut {
+            id: 7,
+            region,
+            layer: Some(1),
+        });
+        assert!(blocked
+            .add_channel_array(&sources, &targets, &shape, &routing)
+            .is_ok());
+        blocked.channels.clear();
+        blocked.keep_outs[0].layer = None;
+        assert_eq!(
+            blocked.add_channel_array(&sources, &targets, &shape, &routing),
+            Err(RiverError::KeepOut {
+                channel: 0,
+                keep_out: 7
+            })
+        );
+        assert!(blocked.channels.is_empty());
+
+        let paths: Vec<&ChannelPath> = network
+            .channels
+            .iter()
+            .map(|c| c.path.as_ref().unwrap())
+            .collect();
+        for (i, path) in paths.iter().enumerate() {
+            assert_eq!(path.check_invariants(), Ok(()));
+            assert!(path.kinks(1e-9).is_empty());
+            for other in &paths[i + 1..] {
+                for a in &path.pieces {
+                    assert!(other
+                        .pieces
+                        .iter()
+                        .all(|b| a.intersections(b, 1e-9).is_empty()));
+                }
+            }
+        }
+        // The outer channels run on the upper track, the inner ones on the lower
+        let track = |path: &ChannelPath| path.pieces[2].start().0[1];
+        assert_eq!(
+            paths.iter().map(|p| track(p)).collect::<Vec<_>>(),
+            [400., 200., 200., 400.]
+        );
+
+        assert_eq!(
+            routing.route(&[Point([0., 0.])], &[Point([100., 300.])]),
+            Err(RiverError::InsufficientSpace {
+                required: 400.,
+                available: 300.
+            })
+        );
+        assert_eq!(
+            routing.route(&[Point([10., 0.]), Point([0., 0.])], &[Point([0., 1.]); 2]),
+            Err(RiverError::Unordered)
+        );
+    }
+}
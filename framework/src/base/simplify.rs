@@ -0,0 +1,181 @@
+//! Reduction of finely segmented paths, e.g., from imported polylines, to few segments and arcs.
+
+use super::{
+    channel::{Arc, ChannelPath, LineSegment, PathPiece},
+    primitives::Point,
+};
+
+/// Center of the circle through three points, if they aren't collinear
+fn circumcenter(
+    Point([ax, ay]): Point,
+    Point([bx, by]): Point,
+    Point([cx, cy]): Point,
+) -> Option<Point> {
+    let d = 2. * (ax * (by - cy) + bx * (cy - ay) + cx * (ay - by));
+    if d.abs() < f64::EPSILON {
+        return None;
+    }
+    let (a2, b2, c2) = (ax * ax + ay * ay, bx * bx + by * by, cx * cx + cy * cy);
+    Some(Point([
+        (a2 * (by - cy) + b2 * (cy - ay) + c2 * (ay - by)) / d,
+        (a2 * (cx - bx) + b2 * (ax - cx) + c2 * (bx - ax)) / d,
+    ]))
+}
+
+fn midpoint(Point([ax, ay]): Point, Point([bx, by]): Point) -> Point {
+    Point([(ax + bx) / 2., (ay + by) / 2.])
+}
+
+/// Arc from the first to the last point through the middle one, if all vertices and segment
+/// midpoints of the polyline lie within the tolerance
+fn fit_arc(points: &[Point], tolerance: f64) -> Option<Arc> {
+    let (start, middle, end) = (
+        points[0],
+        points[points.len() / 2],
+        points[points.len() - 1],
+    );
+    let center = circumcenter(start, middle, end)?;
+    let Point([sx, sy]) = start;
+    let Point([mx, my]) = middle;
+    let Point([ex, ey]) = end;
+    let arc = Arc {
+        // Clockwise if the polyline turns right
+        right: (mx - sx) * (ey - my) - (my - sy) * (ex - mx) < 0.,
+        start,
+        end,
+        center,
+    };
+    let deviates = |p: Point| arc.distance(p) > tolerance;
+    if points.iter().any(|p| deviates(*p))
+        || points.windows(2).any(|w| deviates(midpoint(w[0], w[1])))
+    {
+        None
+    } else {
+        Some(arc)
+    }
+}
+
+/// Single segment or arc within the tolerance of all points
+fn fit(points: &[Point], tolerance: f64) -> Option<PathPiece> {
+    let chord = LineSegment {
+        start: points[0],
+        end: points[points.len() - 1],
+    };
+    if points.iter().all(|p| chord.distance(*p) <= tolerance) {
+        Some(PathPiece::LineSegment(chord))
+    } else if points.len() > 3 {
+        fit_arc(points, tolerance).map(PathPiece::Arc)
+    } else {
+        None
+    }
+}
+
+/// Douglas–Peucker on a polyline, trying an arc before splitting a span. Returns the pieces
+/// with the index ranges of the points they replace.
+fn split(points: &[Point], first: usize, tolerance: f64, out: &mut Vec<(PathPiece, [usize; 2])>) {
+    let last = first + points.len() - 1;
+    if let Some(piece) = fit(points, tolerance) {
+        out.push((piece, [first, last]));
+        return;
+    }
+    let chord = LineSegment {
+        start: points[0],
+        end: points[points.len() - 1],
+    };
+    let farthest = (1..points.len() - 1)
+        .max_by(|a, b| {
+            chord
+                .distance(points[*a])
+                .total_cmp(&chord.distance(points[*b]))
+        })
+        .unwrap();
+    split(&points[..=farthest], first, tolerance, out);
+    split(&points[farthest..], first + farthest, tolerance, out);
+}
+
+fn simplify_polyline(points: &[Point], tolerance: f64, out: &mut Vec<PathPiece>) {
+    let mut pieces = Vec::new();
+    split(points, 0, tolerance, &mut pieces);
+    // Splitting at the farthest point rarely hits the transitions between straight and curved
+    // parts, so neighbors are refitted as one piece against the original points
+    let mut merged: Vec<(PathPiece, [usize; 2])> = Vec::with_capacity(pieces.len());
+    for (piece, [from, to]) in pieces {
+        if let Some((last, [start, end])) = merged.last_mut() {
+            if let Some(combined) = fit(&points[*start..=to], tolerance) {
+                *last = combined;
+                *end = to;
+                continue;
+            }
+        }
+        merged.push((piece, [from, to]));
+    }
+    out.extend(merged.into_iter().map(|(piece, _)| piece));
+}
+
+impl ChannelPath {
+    /// Replaces runs of connected line segments by fewer segments and arcs deviating at most by
+    /// the tolerance from the original. Existing arcs and the end points of runs are kept.
+    pub fn simplify(&mut self, tolerance: f64) {
+        let mut simplified = Vec::with_capacity(self.pieces.len());
+        let mut run: Vec<Point> = Vec::new();
+        let flush = |run: &mut Vec<Point>, out: &mut Vec<PathPiece>| {
+            if run.len() > 1 {
+                simplify_polyline(run, tolerance, out);
+            }
+            run.clear();
+        };
+        for piece in &self.pieces {
+            match piece {
+                PathPiece::LineSegment(line) => {
+                    if run.last() != Some(&line.start) {
+                        flush(&mut run, &mut simplified);
+                        run.push(line.start);
+                    }
+                    run.push(line.end);
+                }
+                PathPiece::Arc(_) => {
+                    flush(&mut run, &mut simplified);
+                    simplified.push(*piece);
+                }
+            }
+        }
+        flush(&mut run, &mut simplified);
+        self.pieces = simplified;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::channel::SVGPath;
+    use std::f64::consts::FRAC_PI_2;
+
+    #[test]
+    fn polyline_to_line_and_arc() {
+        let mut points: Vec<Point> = (0..=10).map(|i| Point([i as f64, 0.])).collect();
+        // Quarter circle of radius 10 turning left around (10, 10)
+        points.extend((1..=64).map(|i| {
+            let angle = -FRAC_PI_2 + FRAC_PI_2 * i as f64 / 64.;
+            Point([10. + 10. * angle.cos(), 10. + 10. * angle.sin()])
+        }));
+        let mut path = ChannelPath::new();
+        for w in points.windows(2) {
+            path.add(PathPiece::LineSegment(LineSegment {
+                start: w[0],
+                end: w[1],
+            }));
+        }
+        let length = path.length().0;
+        path.simplify(0.01);
+
+        assert_eq!(path.pieces.len(), 2);
+        assert!(matches!(path.pieces[0], PathPiece::LineSegment(_)));
+        let PathPiece::Arc(arc) = path.pieces[1] else {
+            panic!("expected an arc");
+        };
+        assert!(!arc.right);
+        assert!((arc.radius() - 10.).abs() < 0.01);
+        assert!((path.length().0 - length).abs() < 0.01);
+        assert_eq!(path.check_invariants(), Ok(()));
+    }
+}
@@ -0,0 +1,209 @@
+//! Tangent continuity (G1) of paths. Kinks between pieces cause stress concentrations in the
+//! chip and stitching artifacts in fabrication; they are found by comparing the directions of
+//! travel at the joints and can be replaced by blend arcs.
+
+use super::{
+    channel::{Arc, ChannelPath, LineSegment, PathPiece, SVGPath},
+    primitives::{Point, Tolerance},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Tangent discontinuity at the start of a piece
+pub struct Kink {
+    /// Index of the piece after the joint; 0 is the joint closing a closed path
+    pub piece: usize,
+
+    /// Signed turn in radians from the incoming to the outgoing direction, positive to the left
+    pub angle: f64,
+}
+
+fn unit([x, y]: [f64; 2]) -> [f64; 2] {
+    let length = f64::hypot(x, y);
+    [x / length, y / length]
+}
+
+/// Direction of travel of an arc at one of its points
+fn arc_tangent(arc: &Arc, Point([px, py]): Point) -> [f64; 2] {
+    let Point([cx, cy]) = arc.center;
+    let (rx, ry) = (px - cx, py - cy);
+    match arc.right {
+        true => unit([ry, -rx]),
+        false => unit([-ry, rx]),
+    }
+}
+
+impl PathPiece {
+    /// Unit direction of travel at the start
+    pub fn start_tangent(&self) -> [f64; 2] {
+        match self {
+            PathPiece::Arc(arc) => arc_tangent(arc, arc.start),
+            PathPiece::LineSegment(LineSegment {
+                start: Point([sx, sy]),
+                end: Point([ex, ey]),
+            }) => unit([ex - sx, ey - sy]),
+        }
+    }
+
+    /// Unit direction of travel at the end
+    pub fn end_tangent(&self) -> [f64; 2] {
+        match self {
+            PathPiece::Arc(arc) => arc_tangent(arc, arc.end),
+            PathPiece::LineSegment(_) => self.start_tangent(),
+        }
+    }
+
+    /// Unit direction of travel at the parameter in [0, 1]
+    pub fn tangent_at(&self, t: f64) -> [f64; 2] {
+        match self {
+            PathPiece::Arc(arc) => arc_tangent(arc, arc.point_at(t)),
+            PathPiece::LineSegment(_) => self.start_tangent(),
+        }
+    }
+}
+
+/// Signed angle turning direction `a` into `b`
+fn turn([ax, ay]: [f64; 2], [bx, by]: [f64; 2]) -> f64 {
+    f64::atan2(ax * by - ay * bx, ax * bx + ay * by)
+}
+
+impl ChannelPath {
+    /// Indices of incoming and outgoing pieces of all joints, including the closing one of
+    /// closed paths if the ends touch
+    fn joints(&self) -> Vec<(usize, usize)> {
+        let n = self.pieces.len();
+        let mut joints: Vec<(usize, usize)> = (1..n).map(|i| (i - 1, i)).collect();
+        if self.closed && n > 1 {
+            let (end, start) = (self.pieces[n - 1].end(), self.pieces[0].start());
+            if end.approx_eq(&start, &Tolerance::default()) {
+                joints.push((n - 1, 0));
+            }
+        }
+        joints
+    }
+
+    /// Joints where the direction of travel changes by more than the tolerance (radians)
+    pub fn kinks(&self, tolerance: f64) -> Vec<Kink> {
+        self.joints()
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let angle = turn(self.pieces[a].end_tangent(), self.pieces[b].start_tangent());
+                (angle.abs() > tolerance).then_some(Kink { piece: b, angle })
+            })
+            .collect()
+    }
+
+    /// Replaces kinks between two straight segments by tangent arcs of the radius, trimming
+    /// the segments. Blends that would consume more than half of a segment are skipped, as are
+    /// kinks involving arcs. Returns the number of inserted arcs.
+    pub fn smooth_kinks(&mut self, radius: f64, tolerance: f64) -> usize {
+        let n = self.pieces.len();
+        let length = |piece: &PathPiece| match piece {
+            PathPiece::LineSegment(_) => Some(piece.length().0),
+            PathPiece::Arc(_) => None,
+        };
+        let mut trim_start = vec![0.; n];
+        let mut trim_end = vec![0.; n];
+        let mut blends: Vec<Option<Arc>> = vec![None; n];
+        for Kink { piece: b, angle } in self.kinks(tolerance) {
+            let a = (b + n - 1) % n;
+            let (Some(length_a), Some(length_b)) =
+                (length(&self.pieces[a]), length(&self.pieces[b]))
+            else {
+                continue;
+            };
+            let trim = radius * (angle.abs() / 2.).tan();
+            if trim > length_a / 2. || trim > length_b / 2. {
+                continue;
+            }
+            let ([ux, uy], [vx, vy]) =
+                (self.pieces[a].end_tangent(), self.pieces[b].start_tangent());
+            let Point([px, py]) = self.pieces[b].start();
+            let [nx, ny] = match angle > 0. {
+                true => [-uy, ux],
+                false => [uy, -ux],
+            };
+            let start = Point([px - ux * trim, py - uy * trim]);
+            blends[a] = Some(Arc {
+                right: angle < 0.,
+                start,
+                end: Point([px + vx * trim, py + vy * trim]),
+                center: Point([start.0[0] + nx * radius, start.0[1] + ny * radius]),
+            });
+            trim_end[a] = trim;
+            trim_start[b] = trim;
+        }
+
+        let mut smoothed = Vec::with_capacity(n);
+        let mut count = 0;
+        for (i, piece) in self.pieces.iter().enumerate() {
+            let mut piece = *piece;
+            let [dx, dy] = piece.start_tangent();
+            if let PathPiece::LineSegment(line) = &mut piece {
+                let (Point([sx, sy]), Point([ex, ey])) = (line.start, line.end);
+                line.start = Point([sx + dx * trim_start[i], sy + dy * trim_start[i]]);
+                line.end = Point([ex - dx * trim_end[i], ey - dy * trim_end[i]]);
+            }
+            if piece.length().0 > 0. {
+                smoothed.push(piece);
+            }
+            if let Some(blend) = blends[i] {
+                smoothed.push(PathPiece::Arc(blend));
+                count += 1;
+            }
+        }
+        // The blend of the closing joint belongs before the first piece
+        if self.closed && blends[n.saturating_sub(1)].is_some() {
+            smoothed.rotate_right(1);
+        }
+        self.pieces = smoothed;
+        count
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    fn line(a: [f64; 2], b: [f64; 2]) -> PathPiece {
+        PathPiece::LineSegment(LineSegment {
+            start: Point(a),
+            end: Point(b),
+        })
+    }
+
+    #[test]
+    fn blends() {
+        let mut path = ChannelPath::new();
+        path.add(line([0., 0.], [10., 0.]));
+        path.add(line([10., 0.], [10., 10.]));
+        path.add(line([10., 10.], [20., 0.]));
+        let kinks = path.kinks(1e-9);
+        assert_eq!(kinks.len(), 2);
+        assert_eq!(kinks[0].piece, 1);
+        assert!((kinks[0].angle - FRAC_PI_2).abs() < 1e-12);
+        assert!((kinks[1].angle + 3. * PI / 4.).abs() < 1e-12);
+
+        // The second blend would need more than half of the last segment
+        assert_eq!(path.smooth_kinks(3., 1e-9), 1);
+        assert_eq!(path.pieces.len(), 4);
+        assert_eq!(path.kinks(1e-9).len(), 1);
+        assert_eq!(path.check_invariants(), Ok(()));
+        assert!((path.length().0 - (14. + 1.5 * PI + f64::hypot(10., 10.))).abs() < 1e-9);
+
+        // Closed square: all four corners, including the closing one
+        let mut square = ChannelPath::closed(vec![
+            line([0., 0.], [4., 0.]),
+            line([4., 0.], [4., 4.]),
+            line([4., 4.], [0., 4.]),
+            line([0., 4.], [0., 0.]),
+        ]);
+        assert_eq!(square.smooth_kinks(1., 1e-9), 4);
+        assert!(square.kinks(1e-9).is_empty());
+        assert!(matches!(square.pieces[0], PathPiece::Arc(_)));
+        assert!((square.area().unwrap() - (16. - 4. + PI)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,186 @@
+//! Snapping of layouts to the resolution of lithography or milling equipment, which also keeps
+//! serialized coordinates free of floating-point noise.
+
+use super::{
+    channel::{Arc, ChannelPath, LineSegment, PathPiece},
+    network::Network,
+    primitives::{Dimensions, Point},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Square grid of positions
+pub struct Grid {
+    /// Distance of neighboring grid positions
+    pub pitch: f64,
+
+    /// A grid position
+    pub origin: Point,
+}
+
+impl Grid {
+    /// Grid through the origin
+    pub fn new(pitch: f64) -> Grid {
+        Grid {
+            pitch,
+            origin: Point([0., 0.]),
+        }
+    }
+
+    /// Closest grid position
+    pub fn snap(&self, Point([x, y]): Point) -> Point {
+        let Point([ox, oy]) = self.origin;
+        Point([
+            ox + quantize(x - ox, self.pitch),
+            oy + quantize(y - oy, self.pitch),
+        ])
+    }
+}
+
+/// Closest multiple of the step
+pub fn quantize(value: f64, step: f64) -> f64 {
+    // Adding 0 turns -0 into 0
+    (value / step).round() * step + 0.
+}
+
+impl Point {
+    /// Closest position of the grid
+    pub fn snapped(&self, grid: &Grid) -> Point {
+        grid.snap(*self)
+    }
+}
+
+impl Arc {
+    /// Arc between the new end points that stays as close as possible to the current one: the
+    /// center moves perpendicular to the chord, keeping start and end on one circle
+    fn refitted(&self, start: Point, end: Point) -> Arc {
+        let (Point([sx, sy]), Point([ex, ey])) = (start, end);
+        let chord = f64::hypot(ex - sx, ey - sy);
+        let center = if chord == 0. {
+            // Full circle, its center snaps like any other point
+            self.center
+                .translated(Point([sx - self.start.0[0], sy - self.start.0[1]]))
+        } else {
+            let (mx, my) = ((sx + ex) / 2., (sy + ey) / 2.);
+            let (nx, ny) = (-(ey - sy) / chord, (ex - sx) / chord);
+            let Point([cx, cy]) = self.center;
+            let along = (cx - mx) * nx + (cy - my) * ny;
+            Point([mx + along * nx, my + along * ny])
+        };
+        Arc {
+            start,
+            end,
+            center,
+            ..*self
+        }
+    }
+}
+
+impl ChannelPath {
+    /// Snaps the end points of all pieces to the grid and refits arcs to the moved ends. Pieces
+    /// collapsing to a point are removed; returns their number.
+    pub fn snap_to_grid(&mut self, grid: &Grid) -> usize {
+        let before = self.pieces.len();
+        self.pieces = self
+            .pieces
+            .iter()
+            .filter_map(|piece| {
+                let (start, end) = (grid.snap(piece.start()), grid.snap(piece.end()));
+                match piece {
+                    PathPiece::Arc(arc) if start != end || arc.start == arc.end => {
+                        let mut arc = arc.refitted(start, end);
+                        if arc.start == arc.end {
+                            arc.center = grid.snap(arc.center);
+                        }
+                        Some(PathPiece::Arc(arc))
+                    }
+                    PathPiece::LineSegment(_) if start != end => {
+                        Some(PathPiece::LineSegment(LineSegment { start, end }))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        before - self.pieces.len()
+    }
+}
+
+impl Dimensions {
+    /// Dimensions rounded to multiples of the pitch, but at least one pitch
+    pub fn snapped(&self, grid: &Grid) -> Dimensions {
+        let Dimensions([w, h]) = self;
+        let snap = |v: f64| quantize(v, grid.pitch).max(grid.pitch);
+        Dimensions([snap(*w), snap(*h)])
+    }
+}
+
+impl Network {
+    /// Snaps node positions, channel paths, and module positions and sizes to the grid;
+    /// returns the number of path pieces removed because they collapsed
+    pub fn snap_to_grid(&mut self, grid: &Grid) -> usize {
+        for node in &mut self.nodes {
+            node.position = node.position.map(|p| grid.snap(p));
+        }
+        for module in &mut self.modules {
+            module.position = grid.snap(module.position);
+            module.size = module.size.snapped(grid);
+        }
+        self.channels
+            .iter_mut()
+            .filter_map(|c| c.path.as_mut())
+            .map(|path| path.snap_to_grid(grid))
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::generator::{RandomNetworkSpec, Topology};
+
+    #[test]
+    fn snapping() {
+        let grid = Grid {
+            pitch: 0.5,
+            origin: Point([0.1, 0.]),
+        };
+        assert_eq!(Point([0.36, -0.2]).snapped(&grid), Point([0.6, 0.]));
+        assert_eq!(quantize(-0.1, 1.).to_string(), "0");
+
+        let mut path = ChannelPath::new();
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: Point([0.1, 0.05]),
+            end: Point([10.12, 0.]),
+        }));
+        path.add(PathPiece::Arc(Arc {
+            right: false,
+            start: Point([10.12, 0.]),
+            end: Point([10.12, 9.93]),
+            center: Point([10.12, 4.965]),
+        }));
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: Point([10.12, 9.93]),
+            end: Point([10.2, 10.]),
+        }));
+        assert_eq!(path.snap_to_grid(&grid), 1);
+        assert_eq!(path.pieces.len(), 2);
+        assert_eq!(path.pieces[1].end(), Point([10.1, 10.]));
+        assert_eq!(path.check_invariants(), Ok(()));
+
+        let mut network = Network::random(&RandomNetworkSpec {
+            topology: Topology::Grid {
+                columns: 3,
+                rows: 2,
+            },
+            rng: crate::random::Rng::new(7),
+            ..Default::default()
+        });
+        network.snap_to_grid(&Grid::new(10.));
+        for node in &network.nodes {
+            let Point([x, y]) = node.position.unwrap();
+            assert_eq!((x % 10., y % 10.), (0., 0.));
+        }
+    }
+}
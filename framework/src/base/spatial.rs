@@ -0,0 +1,255 @@
+use super::{
+    events::NetworkEvent,
+    network::{EntityRef, Network},
+    primitives::{Point, Rect},
+};
+use crate::parallel;
+use std::collections::{HashMap, HashSet};
+
+type Cell = (i64, i64);
+
+/// Uniform grid index over the bounding boxes of channel outlines and module footprints
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    cell_size: f64,
+    cells: HashMap<Cell, Vec<EntityRef>>,
+    bounds: HashMap<EntityRef, Rect>,
+}
+
+impl SpatialIndex {
+    /// Creates an empty index, `cell_size` should be in the order of typical entity extents
+    pub fn new(cell_size: f64) -> Self {
+        assert!(cell_size > 0.);
+        SpatialIndex {
+            cell_size,
+            cells: HashMap::new(),
+            bounds: HashMap::new(),
+        }
+    }
+
+    /// Index over all channels with a path and all modules of the network
+    pub fn from_network(network: &Network, cell_size: f64) -> Self {
+        let mut index = SpatialIndex::new(cell_size);
+        let channel_bounds = parallel::map(&network.channels, |c| (c.id, c.bounding_box()));
+        for (id, rect) in channel_bounds {
+            if let Some(rect) = rect {
+                index.insert(EntityRef::Channel(id), rect);
+            }
+        }
+        for module in &network.modules {
+            index.insert(EntityRef::Module(module.id), module.bounding_box());
+        }
+        index
+    }
+
+    fn cell(&self, Point([x, y]): Point) -> Cell {
+        (
+            (x / self.cell_size).floor() as i64,
+            (y / self.cell_size).floor() as i64,
+        )
+    }
+
+    fn cells(&self, rect: &Rect) -> impl Iterator<Item = Cell> {
+        let (x0, y0) = self.cell(rect.min);
+        let (x1, y1) = self.cell(rect.max);
+        (x0..=x1).flat_map(move |x| (y0..=y1).map(move |y| (x, y)))
+    }
+
+    /// Inserts or replaces the bounding box of an entity
+    pub fn insert(&mut self, entity: EntityRef, rect: Rect) {
+        self.remove(entity);
+        for cell in self.cells(&rect).collect::<Vec<_>>() {
+            self.cells.entry(cell).or_default().push(entity);
+        }
+        self.bounds.insert(entity, rect);
+    }
+
+    /// Removes an entity, returns false if it wasn't indexed
+    pub fn remove(&mut self, entity: EntityRef) -> bool {
+        let rect = match self.bounds.remove(&entity) {
+            Some(rect) => rect,
+            None => return false,
+        };
+        for cell in self.cells(&rect).collect::<Vec<_>>() {
+            if let Some(entities) = self.cells.get_mut(&cell) {
+                entities.retain(|e| *e != entity);
+                if entities.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+        true
+    }
+
+    /// Keeps the index in sync with a network mutation, cf. `ObservableNetwork::subscribe`
+    pub fn update(&mut self, network: &Network, event: &NetworkEvent) {
+        let entity = match event {
+            NetworkEvent::Added(entity) | NetworkEvent::Modified(entity) => entity,
+            NetworkEvent::Removed(entity) => {
+                self.remove(*entity);
+                return;
+            }
+        };
+        let rect = match entity {
+            EntityRef::Channel(id) => network
+                .channels
+                .iter()
+                .find(|c| c.id == *id)
+                .and_then(|c| c.bounding_box()),
+            EntityRef::Module(id) => network
+                .modules
+                .iter()
+                .find(|m| m.id == *id)
+                .map(|m| m.bounding_box()),
+            EntityRef::Node(_) => None,
+        };
+        match rect {
+            Some(rect) => self.insert(*entity, rect),
+            None => {
+                self.remove(*entity);
+            }
+        }
+    }
+
+    pub fn bounds(&self, entity: EntityRef) -> Option<Rect> {
+        self.bounds.get(&entity).copied()
+    }
+
+    /// Entities whose bounding box contains the point
+    pub fn query_point(&self, point: Point) -> Vec<EntityRef> {
+        let mut result: Vec<_> = self
+            .cells
+            .get(&self.cell(point))
+            .into_iter()
+            .flatten()
+            .filter(|e| self.bounds[e].contains(point))
+            .copied()
+            .collect();
+        result.sort();
+        result
+    }
+
+    /// Entities whose bounding box intersects the rectangle
+    pub fn query_rect(&self, rect: &Rect) -> Vec<EntityRef> {
+        let mut found = HashSet::new();
+        for cell in self.cells(rect) {
+            for entity in self.cells.get(&cell).into_iter().flatten() {
+                if self.bounds[entity].intersects(rect) {
+                    found.insert(*entity);
+                }
+            }
+        }
+        let mut result: Vec<_> = found.into_iter().collect();
+        result.sort();
+        result
+    }
+
+    /// Entity whose bounding box is closest to the point, ties are broken by entity order.
+    /// Rings of cells are searched outwards from the point until they would cover more cells
+    /// than are occupied, then all entities are compared directly.
+    pub fn nearest(&self, point: Point) -> Option<EntityRef> {
+        if self.bounds.is_empty() {
+            return None;
+        }
+        let (cx, cy) = self.cell(point);
+        let extent = self
+            .cells
+            .keys()
+            .map(|(x, y)| (x - cx).abs().max((y - cy).abs()))
+            .max()
+            .unwrap_or(0);
+
+        let mut best: Option<(f64, EntityRef)> = None;
+        for ring in 0..=extent {
+            // Every entity outside of the searched rings is at least this far away
+            if let Some((distance, _)) = best {
+                if distance < (ring - 1) as f64 * self.cell_size {
+                    break;
+                }
+            }
+            if (2 * ring + 1).pow(2) as usize > self.cells.len() {
+                return (self.bounds.iter())
+                    .map(|(entity, rect)| (rect.distance(point), *entity))
+                    .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+                    .map(|(_, entity)| entity);
+            }
+            let ring_cells = (-ring..=ring).flat_map(|dx| {
+                (-ring..=ring)
+                    .filter(move |dy| dx.abs() == ring || dy.abs() == ring)
+                    .map(move |dy| (cx + dx, cy + dy))
+            });
+            for cell in ring_cells {
+                for entity in self.cells.get(&cell).into_iter().flatten() {
+                    let candidate = (self.bounds[entity].distance(point), *entity);
+                    if best.is_none_or(|b| candidate < b) {
+                        best = Some(candidate);
+                    }
+                }
+            }
+        }
+        best.map(|(_, entity)| entity)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn square(x: f64, y: f64) -> Rect {
+        Rect {
+            min: Point([x, y]),
+            max: Point([x + 1., y + 1.]),
+        }
+    }
+
+    #[test]
+    fn queries() {
+        let mut index = SpatialIndex::new(2.);
+        index.insert(EntityRef::Module(0), square(0., 0.));
+        index.insert(EntityRef::Module(1), square(10., 10.));
+        index.insert(EntityRef::Channel(0), square(-7.5, 3.));
+
+        assert_eq!(
+            index.query_point(Point([0.5, 0.5])),
+            vec![EntityRef::Module(0)]
+        );
+        assert!(index.query_point(Point([5., 5.])).is_empty());
+        assert_eq!(
+            index.query_rect(&Rect {
+                min: Point([0.5, 0.5]),
+                max: Point([10.5, 10.5])
+            }),
+            vec![EntityRef::Module(0), EntityRef::Module(1)]
+        );
+        assert_eq!(index.nearest(Point([8., 8.])), Some(EntityRef::Module(1)));
+        assert_eq!(index.nearest(Point([-6., 0.])), Some(EntityRef::Channel(0)));
+
+        index.insert(EntityRef::Module(1), square(-6., -1.));
+        assert_eq!(index.nearest(Point([-6., 0.])), Some(EntityRef::Module(1)));
+        assert!(index.remove(EntityRef::Module(1)));
+        assert_eq!(index.nearest(Point([8., 8.])), Some(EntityRef::Module(0)));
+
+        // Far from all entities, the search doesn't walk the empty cells in between
+        index.insert(EntityRef::Module(2), square(1e9, 0.));
+        assert_eq!(index.nearest(Point([2e9, 0.])), Some(EntityRef::Module(2)));
+        assert_eq!(
+            index.nearest(Point([-1e9, 5.])),
+            Some(EntityRef::Channel(0))
+        );
+
+        // The ring search over a dense index agrees with comparing all entities
+        let mut dense = SpatialIndex::new(1.);
+        for i in 0..100 {
+            let (x, y) = ((i % 10) as f64 * 3., (i / 10) as f64 * 3.);
+            dense.insert(EntityRef::Channel(i), square(x, y));
+        }
+        assert_eq!(
+            dense.nearest(Point([13.9, 14.2])),
+            Some(EntityRef::Channel(54))
+        );
+        assert_eq!(
+            dense.nearest(Point([-50., 0.])),
+            Some(EntityRef::Channel(0))
+        );
+    }
+}
@@ -0,0 +1,382 @@
+//! Incremental (de)serialization of networks in the regular JSON format, so that huge
+//! networks can be produced or consumed entity by entity without an in-memory copy. Only the
+//! node, channel, and module lists are streamed; the remaining sections, e.g., groups and the
+//! layer stack, stay small and are passed as a whole.
+
+use super::{
+    channel::Channel,
+    network::{Module, Network, Node},
+};
+use serde::{
+    de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize,
+};
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    marker::PhantomData,
+};
+
+/// Receiver of the entities of a streamed network
+pub trait NetworkSink {
+    fn node(&mut self, node: Node);
+    fn channel(&mut self, channel: Channel);
+    fn module(&mut self, module: Module);
+
+    /// Receives the remaining sections as a network without entities, after the whole
+    /// document was read
+    fn sections(&mut self, sections: Network);
+}
+
+impl NetworkSink for Network {
+    fn node(&mut self, node: Node) {
+        self.nodes.push(node)
+    }
+
+    fn channel(&mut self, channel: Channel) {
+        self.channels.push(channel)
+    }
+
+    fn module(&mut self, module: Module) {
+        self.modules.push(module)
+    }
+
+    fn sections(&mut self, sections: Network) {
+        *self = Network {
+            nodes: std::mem::take(&mut self.nodes),
+            channels: std::mem::take(&mut self.channels),
+            modules: std::mem::take(&mut self.modules),
+            ..sections
+        };
+    }
+}
+
+/// Reads a JSON network and hands every entity to the sink as soon as it is parsed. Entity
+/// lists may appear in any order; the other sections are collected and handed over at the
+/// end, unknown fields are skipped as when reading the network at once.
+pub fn read_network<R: Read, S: NetworkSink>(reader: R, sink: &mut S) -> serde_json::Result<()> {
+    let mut deserializer = serde_json::Deserializer::from_reader(io::BufReader::new(reader));
+    deserializer.deserialize_map(NetworkVisitor(sink))?;
+    deserializer.end()
+}
+
+struct NetworkVisitor<'a, S>(&'a mut S);
+
+impl<'de, S: NetworkSink> Visitor<'de> for NetworkVisitor<'_, S> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a network object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let mut sections = serde_json::Map::new();
+        for key in [Section::Nodes, Section::Channels, Section::Modules] {
+            sections.insert(key.key().into(), serde_json::Value::Array(Vec::new()));
+        }
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "nodes" => map.next_value_seed(Each::new(|n| self.0.node(n)))?,
+                "channels" => map.next_value_seed(Each::new(|c| self.0.channel(c)))?,
+                "modules" => map.next_value_seed(Each::new(|m| self.0.module(m)))?,
+                _ => {
+                    sections.insert(key, map.next_value()?);
+                }
+            }
+        }
+        let sections = serde_json::from_value(serde_json::Value::Object(sections))
+            .map_err(de::Error::custom)?;
+        self.0.sections(sections);
+        Ok(())
+    }
+}
+
+/// Deserializes a sequence, passing each element to a callback instead of collecting it
+struct Each<T, F>(F, PhantomData<T>);
+
+impl<T, F: FnMut(T)> Each<T, F> {
+    fn new(f: F) -> Self {
+        Each(f, PhantomData)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, F: FnMut(T)> DeserializeSeed<'de> for Each<T, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>, F: FnMut(T)> Visitor<'de> for Each<T, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of entities")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(element) = seq.next_element()? {
+            (self.0)(element);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+enum Section {
+    Start,
+    Nodes,
+    Channels,
+    Modules,
+    Sections,
+}
+
+impl Section {
+    fn key(&self) -> &'static str {
+        match self {
+            Section::Start => unreachable!(),
+            Section::Nodes => "nodes",
+            Section::Channels => "channels",
+            Section::Modules => "modules",
+            Section::Sections => "sections",
+        }
+    }
+}
+
+/// Writes a network as JSON entity by entity. Entities must be written in the order nodes,
+/// channels, modules, followed by the remaining sections; lists without entities are emitted
+/// empty.
+pub struct NetworkWriter<W: Write> {
+    writer: W,
+    section: Section,
+    empty: bool,
+}
+
+impl<W: Write> NetworkWriter<W> {
+    pub fn new(writer: W) -> Self {
+        NetworkWriter {
+            writer,
+            section: Section::Start,
+            empty: true,
+        }
+    }
+
+    fn enter(&mut self, section: Section) -> io::Result<()> {
+        if section < self.section || section == Section::Sections && self.section == section {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "cannot write {} after {}",
+                    section.key(),
+                    self.section.key()
+                ),
+            ));
+        }
+        while self.section < section {
+            self.writer.write_all(match self.section {
+                Section::Start => b"{",
+                Section::Modules => b"]",
+                _ => b"],",
+            })?;
+            self.section = match self.section {
+                Section::Start => Section::Nodes,
+                Section::Nodes => Section::Channels,
+                Section::Channels => Section::Modules,
+                _ => Section::Sections,
+            };
+            if self.section != Section::Sections {
+                write!(self.writer, "\"{}\":[", self.section.key())?;
+            }
+            self.empty = true;
+        }
+        Ok(())
+    }
+
+    fn write<T: Serialize>(&mut self, section: Section, entity: &T) -> io::Result<()> {
+        self.enter(section)?;
+        if !self.empty {
+            self.writer.write_all(b",")?;
+        }
+        self.empty = false;
+        serde_json::to_writer(&mut self.writer, entity).map_err(io::Error::from)
+    }
+
+    pub fn node(&mut self, node: &Node) -> io::Result<()> {
+        self.write(Section::Nodes, node)
+    }
+
+    pub fn channel(&mut self, channel: &Channel) -> io::Result<()> {
+        self.write(Section::Channels, channel)
+    }
+
+    pub fn module(&mut self, module: &Module) -> io::Result<()> {
+        self.write(Section::Modules, module)
+    }
+
+    /// Writes the sections of the network other than its entities, which are ignored
+    pub fn sections(&mut self, network: &Network) -> io::Result<()> {
+        self.enter(Section::Sections)?;
+        let sections = Network {
+            nodes: Vec::new(),
+            channels: Vec::new(),
+            modules: Vec::new(),
+            annotations: network.annotations.clone(),
+            keep_outs: network.keep_outs.clone(),
+            surface_features: network.surface_features.clone(),
+            markings: network.markings.clone(),
+            groups: network.groups.clone(),
+            layer_stack: network.layer_stack.clone(),
+            length_unit: network.length_unit,
+            metadata: network.metadata.clone(),
+            cache: Default::default(),
+        };
+        let serde_json::Value::Object(mut sections) = serde_json::to_value(sections)? else {
+            unreachable!()
+        };
+        for key in [Section::Nodes, Section::Channels, Section::Modules] {
+            sections.remove(key.key());
+        }
+        for (key, value) in sections {
+            write!(self.writer, ",{}:", serde_json::Value::String(key))?;
+            serde_json::to_writer(&mut self.writer, &value)?;
+        }
+        Ok(())
+    }
+
+    /// Closes the JSON document and returns the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.section < Section::Sections {
+            self.enter(Section::Sections)?;
+        }
+        self.writer.write_all(b"}")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        annotation::{Anchor, Annotation, Label},
+        channel::{ChannelPath, CylindricalShape, Shape},
+        feature::{GroovePattern, SurfaceFeature},
+        hierarchy::PortModel,
+        keepout::{KeepOut, Region},
+        layers::{Layer, LayerStack, Material},
+        marking::Marking,
+        network::{EntityRef, NodeId},
+        primitives::{Dimensions, Point, Rect},
+        units::LengthUnit,
+    };
+
+    #[test]
+    fn round_trip() {
+        let mut network = Network {
+            nodes: (0..3).map(|id| Node::new(NodeId(id))).collect(),
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(2),
+                shape: Shape::Cylindrical(CylindricalShape { radius: 20. }),
+                path: None,
+                length: None,
+                layer: 0,
+                metadata: Default::default(),
+            }],
+            modules: vec![Module {
+                id: 0,
+                position: Point([0., 0.]),
+                size: Dimensions([1., 1.]),
+                nodes: vec![NodeId(1), NodeId(2)],
+                implementation: None,
+                model: Some(PortModel {
+                    conductance: vec![vec![1., -1.], vec![-1., 1.]],
+                }),
+                footprint: None,
+                orientation: Default::default(),
+                kind: Default::default(),
+                metadata: Default::default(),
+            }],
+            annotations: vec![Annotation::Label(Label {
+                text: "inlet".into(),
+                anchor: Anchor::Channel(0),
+                offset: Dimensions([0., 1.]),
+                height: 2.,
+            })],
+            keep_outs: vec![KeepOut {
+                id: 0,
+                region: Region::Rectangle(Rect {
+                    min: Point([0., 0.]),
+                    max: Point([5., 5.]),
+                }),
+                layer: Some(0),
+            }],
+            surface_features: vec![SurfaceFeature {
+                channel: 0,
+                pattern: GroovePattern::Slanted,
+                start: 0.,
+                end: 10.,
+                groove_width: 1.,
+                pitch: 2.,
+                angle: 0.7,
+                depth: 0.5,
+            }],
+            markings: vec![Marking {
+                layer: "dicing".into(),
+                path: ChannelPath::new(),
+                width: 0.1,
+            }],
+            layer_stack: Some(LayerStack {
+                layers: vec![Layer {
+                    name: "flow".into(),
+                    material: Material::Pdms,
+                    thickness: 1e-3,
+                    network_layer: Some(0),
+                    bonding: None,
+                    bond_strength: Some(3e5),
+                }],
+            }),
+            length_unit: Some(LengthUnit::Micrometer),
+            ..Default::default()
+        };
+        network.add_to_group("inlets", EntityRef::Channel(0));
+        network.metadata.insert("revision".into(), 2.into());
+
+        let mut writer = NetworkWriter::new(Vec::new());
+        for node in &network.nodes {
+            writer.node(node).unwrap();
+        }
+        writer.channel(&network.channels[0]).unwrap();
+        assert!(writer.node(&Node::new(NodeId(3))).is_err());
+        writer.module(&network.modules[0]).unwrap();
+        writer.sections(&network).unwrap();
+        assert!(writer.sections(&network).is_err());
+        assert!(writer.module(&network.modules[0]).is_err());
+        let json = writer.finish().unwrap();
+        assert_eq!(serde_json::from_slice::<Network>(&json).unwrap(), network);
+
+        let mut streamed = Network::default();
+        read_network(json.as_slice(), &mut streamed).unwrap();
+        assert_eq!(streamed, network);
+
+        // Sorted keys interleave the sections with the entity lists
+        let sorted = serde_json::to_value(&network).unwrap().to_string();
+        assert!(sorted.starts_with("{\"annotations\""));
+        let mut streamed = Network::default();
+        read_network(sorted.as_bytes(), &mut streamed).unwrap();
+        assert_eq!(streamed, network);
+
+        // Entities alone form a valid document
+
+        let mut writer = NetworkWriter::new(Vec::new());
+        writer.node(&network.nodes[0]).unwrap();
+        let json = writer.finish().unwrap();
+        let expected = Network {
+            nodes: vec![network.nodes[0].clone()],
+            ..Default::default()
+        };
+        assert_eq!(serde_json::from_slice::<Network>(&json).unwrap(), expected);
+    }
+}
@@ -0,0 +1,559 @@
+//! Parametric module templates: a library component is defined once by expressions over its
+//! parameters and stamped into networks as modules. Instances remember their template and
+//! bindings in their metadata, so they can be re-evaluated when the bindings change.
+//!
+//! The internal resistance model is a set of channels between the ports; instances carry it
+//! as their subcircuit, so flattening the network makes them part of the simulation.
+
+use super::{
+    channel::{Channel, RectangularShape, Shape},
+    footprint::{Footprint, FootprintShape, Orientation},
+    hierarchy::{PortMapping, Subcircuit},
+    network::{Metadata, Module, ModuleKind, Network, Node, NodeId},
+    polygon::Polygon,
+    primitives::{Dimensions, Point},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Metadata key of the template name of an instance
+pub const TEMPLATE_KEY: &str = "template";
+
+/// Metadata key of the parameter values of an instance
+pub const PARAMETERS_KEY: &str = "parameters";
+
+/// Values of template parameters by name
+pub type Bindings = BTreeMap<String, f64>;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Reasons a template can't be instantiated
+pub enum TemplateError {
+    /// The binding refers to a parameter the template doesn't declare
+    UnknownParameter(String),
+
+    /// The expression is malformed
+    Syntax { expression: String, message: String },
+
+    /// The expression uses a name that is no parameter
+    UnboundName(String),
+
+    /// An internal channel refers to a port the template doesn't have
+    UnknownPort(usize),
+
+    /// The module isn't an instance of this template
+    NotAnInstance(usize),
+}
+
+impl std::fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateError::UnknownParameter(name) => write!(f, "unknown parameter {name}"),
+            TemplateError::Syntax {
+                expression,
+                message,
+            } => write!(f, "invalid expression \"{expression}\": {message}"),
+            TemplateError::UnboundName(name) => write!(f, "{name} is not a parameter"),
+            TemplateError::UnknownPort(index) => write!(f, "port {index} does not exist"),
+            TemplateError::NotAnInstance(id) => {
+                write!(f, "module {id} is not an instance of the template")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+/// Arithmetic over parameters with `+ - * /`, parentheses, and numbers, e.g.,
+/// `"2 * radius + 100"`
+pub struct Expression(pub String);
+
+impl From<f64> for Expression {
+    fn from(value: f64) -> Self {
+        Expression(value.to_string())
+    }
+}
+
+impl From<&str> for Expression {
+    fn from(text: &str) -> Self {
+        Expression(text.into())
+    }
+}
+
+/// Recursive descent evaluation of an expression
+struct Evaluator<'a> {
+    text: &'a str,
+    rest: &'a str,
+    values: &'a Bindings,
+}
+
+impl Evaluator<'_> {
+    fn error(&self, message: impl Into<String>) -> TemplateError {
+        TemplateError::Syntax {
+            expression: self.text.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Consumes the character if it is next
+    fn eat(&mut self, c: char) -> bool {
+        self.rest = self.rest.trim_start();
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn sum(&mut self) -> Result<f64, TemplateError> {
+        let mut value = self.product()?;
+        loop {
+            if self.eat('+') {
+                value += self.product()?;
+            } else if self.eat('-') {
+                value -= self.product()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<f64, TemplateError> {
+        let mut value = self.factor()?;
+        loop {
+            if self.eat('*') {
+                value *= self.factor()?;
+            } else if self.eat('/') {
+                value /= self.factor()?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn factor(&mut self) -> Result<f64, TemplateError> {
+        if self.eat('-') {
+            return Ok(-self.factor()?);
+        }
+        if self.eat('(') {
+            let value = self.sum()?;
+            return match self.eat(')') {
+                true => Ok(value),
+                false => Err(self.error("missing )")),
+            };
+        }
+        let rest = self.rest.trim_start();
+        let first = rest
+            .chars()
+            .next()
+            .ok_or_else(|| self.error("unexpected end"))?;
+        let length = if first.is_ascii_digit() || first == '.' {
+            let mut previous = ' ';
+            rest.find(|c: char| {
+                let exponent_sign = (c == '+' || c == '-') && matches!(previous, 'e' | 'E');
+                previous = c;
+                !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign)
+            })
+            .unwrap_or(rest.len())
+        } else if first.is_alphabetic() || first == '_' {
+            rest.find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len())
+        } else {
+            return Err(self.error(format!("unexpected {first}")));
+        };
+        let (token, rest) = rest.split_at(length);
+        self.rest = rest;
+        match first.is_ascii_digit() || first == '.' {
+            true => token
+                .parse()
+                .map_err(|_| self.error(format!("invalid number {token}"))),
+            false => self
+                .values
+                .get(token)
+                .copied()
+                .ok_or_else(|| TemplateError::UnboundName(token.into())),
+        }
+    }
+}
+
+impl Expression {
+    /// Value of the expression for the parameter values
+    pub fn evaluate(&self, values: &Bindings) -> Result<f64, TemplateError> {
+        let mut evaluator = Evaluator {
+            text: &self.0,
+            rest: &self.0,
+            values,
+        };
+        let value = evaluator.sum()?;
+        match evaluator.rest.trim().is_empty() {
+            true => Ok(value),
+            false => Err(evaluator.error(format!("unexpected {}", evaluator.rest.trim()))),
+        }
+    }
+}
+
+fn point(values: &Bindings, [x, y]: &[Expression; 2]) -> Result<Point, TemplateError> {
+    Ok(Point([x.evaluate(values)?, y.evaluate(values)?]))
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Parametric shape of a template's footprint
+pub enum TemplateFootprint {
+    /// Polygon with the vertices relative to the module's position
+    Polygon(Vec<[Expression; 2]>),
+
+    /// Circle relative to the module's position
+    Circle {
+        center: [Expression; 2],
+        radius: Expression,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Interface node of a template
+pub struct TemplatePort {
+    pub name: String,
+
+    /// Position relative to the module's position
+    pub position: [Expression; 2],
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Rectangular channel of the internal resistance model
+pub struct TemplateChannel {
+    /// Indices of the connected ports
+    pub ports: [usize; 2],
+
+    pub width: Expression,
+
+    pub height: Expression,
+
+    pub length: Expression,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Reusable parametric module definition
+pub struct ModuleTemplate {
+    /// Name identifying the template, stored with its instances
+    pub name: String,
+
+    /// Declared parameters with their default values
+    pub parameters: Bindings,
+
+    /// Size of the module's rectangle
+    pub size: [Expression; 2],
+
+    /// Shape of the module, the rectangle of its size if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footprint: Option<TemplateFootprint>,
+
+    pub ports: Vec<TemplatePort>,
+
+    /// Internal resistance model between the ports
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub channels: Vec<TemplateChannel>,
+}
+
+/// Geometry and model of an instance for concrete parameter values
+struct Evaluated {
+    size: Dimensions,
+    footprint: Option<Footprint>,
+    ports: Vec<Point>,
+    implementation: Option<Subcircuit>,
+}
+
+impl ModuleTemplate {
+    /// Default values overridden by the bindings
+    pub fn resolve(&self, bindings: &Bindings) -> Result<Bindings, TemplateError> {
+        let mut values = self.parameters.clone();
+        for (name, value) in bindings {
+            match values.get_mut(name) {
+                Some(v) => *v = *value,
+                None => return Err(TemplateError::UnknownParameter(name.clone())),
+            }
+        }
+        Ok(values)
+    }
+
+    fn evaluate(&self, values: &Bindings) -> Result<Evaluated, TemplateError> {
+        let [w, h] = &self.size;
+        let size = Dimensions([w.evaluate(values)?, h.evaluate(values)?]);
+        let footprint = match &self.footprint {
+            None => None,
+            Some(TemplateFootprint::Polygon(vertices)) => {
+                let vertices = vertices.iter().map(|v| point(values, v));
+                Some(FootprintShape::Polygon(Polygon(
+                    vertices.collect::<Result<_, _>>()?,
+                )))
+            }
+            Some(TemplateFootprint::Circle { center, radius }) => Some(FootprintShape::Circle {
+                center: point(values, center)?,
+                radius: radius.evaluate(values)?,
+            }),
+        }
+        .map(|shape| Footprint {
+            shape,
+            rotation: 0.,
+        });
+        let ports = self
+            .ports
+            .iter()
+            .map(|p| point(values, &p.position))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let implementation = match self.channels.is_empty() {
+            true => None,
+            false => {
+                let mut inner = Network {
+                    nodes: ports
+                        .iter()
+                        .enumerate()
+                        .map(|(i, p)| Node::at(NodeId(i), *p))
+                        .collect(),
+                    ..Default::default()
+                };
+                for (id, channel) in self.channels.iter().enumerate() {
+                    let [a, b] = channel.ports;
+                    for port in [a, b] {
+                        if port >= ports.len() {
+                            return Err(TemplateError::UnknownPort(port));
+                        }
+                    }
+                    inner.channels.push(Channel {
+                        id,
+                        node_a: NodeId(a),
+                        node_b: NodeId(b),
+                        shape: Shape::Rectangular(RectangularShape {
+                            width: channel.width.evaluate(values)?,
+                            height: channel.height.evaluate(values)?,
+                        }),
+                        path: None,
+                        length: Some(channel.length.evaluate(values)?),
+                        layer: 0,
+                        metadata: Metadata::new(),
+                    });
+                }
+                Some(Subcircuit {
+                    network: Box::new(inner),
+                    ports: Vec::new(),
+                })
+            }
+        };
+        Ok(Evaluated {
+            size,
+            footprint,
+            ports,
+            implementation,
+        })
+    }
+
+    /// Stamps an instance into the network at the position, adding a node per port; returns
+    /// the id of the new module
+    pub fn instantiate(
+        &self,
+        network: &mut Network,
+        position: Point,
+        bindings: &Bindings,
+    ) -> Result<usize, TemplateError> {
+        let values = self.resolve(bindings)?;
+        let Evaluated {
+            size,
+            footprint,
+            ports,
+            mut implementation,
+        } = self.evaluate(&values)?;
+        let mut module = Module {
+            id: network.next_module_id(),
+            position,
+            size,
+            nodes: Vec::new(),
+            implementation: None,
+            model: None,
+            footprint,
+            orientation: Orientation::default(),
+            kind: ModuleKind::Generic,
+            metadata: Metadata::new(),
+        };
+        let first = network.next_node_id().0;
+        for (i, (port, local)) in self.ports.iter().zip(&ports).enumerate() {
+            let mut node = Node::at(NodeId(first + i), module.to_layout(*local));
+            node.metadata
+                .insert("name".into(), port.name.clone().into());
+            module.nodes.push(node.id);
+            network.nodes.push(node);
+        }
+        if let Some(subcircuit) = &mut implementation {
+            subcircuit.ports = Self::mapping(&module.nodes);
+        }
+        module.implementation = implementation;
+        module.metadata = self.metadata(&values);
+        let id = module.id;
+        network.modules.push(module);
+        Ok(id)
+    }
+
+    /// Re-evaluates an instance with changed bindings, keeping its id, nodes, and placement;
+    /// ports move along with their attached channels
+    pub fn update(
+        &self,
+        network: &mut Network,
+        module_id: usize,
+        bindings: &Bindings,
+    ) -> Result<(), TemplateError> {
+        let index = network
+            .modules
+            .iter()
+            .position(|m| m.id == module_id && self.is_instance(m))
+            .ok_or(TemplateError::NotAnInstance(module_id))?;
+        let values = self.resolve(bindings)?;
+        let evaluated = self.evaluate(&values)?;
+        if evaluated.ports.len() != network.modules[index].nodes.len() {
+            return Err(TemplateError::NotAnInstance(module_id));
+        }
+
+        let module = &mut network.modules[index];
+        module.size = evaluated.size;
+        module.footprint = evaluated.footprint;
+        module.implementation = evaluated.implementation.map(|subcircuit| Subcircuit {
+            ports: Self::mapping(&module.nodes),
+            ..subcircuit
+        });
+        module.model = None;
+        module.metadata.extend(self.metadata(&values));
+        let module = module.clone();
+        for (node, local) in module.nodes.iter().zip(evaluated.ports) {
+            network.move_node(*node, module.to_layout(local));
+        }
+        Ok(())
+    }
+
+    /// Whether the module was stamped from this template
+    pub fn is_instance(&self, module: &Module) -> bool {
+        module.metadata.get(TEMPLATE_KEY) == Some(&self.name.clone().into())
+    }
+
+    /// Parameter values of an instance, as stored in its metadata
+    pub fn bindings_of(module: &Module) -> Option<Bindings> {
+        serde_json::from_value(module.metadata.get(PARAMETERS_KEY)?.clone()).ok()
+    }
+
+    fn metadata(&self, values: &Bindings) -> Metadata {
+        Metadata::from([
+            (TEMPLATE_KEY.into(), self.name.clone().into()),
+            (PARAMETERS_KEY.into(), serde_json::to_value(values).unwrap()),
+        ])
+    }
+
+    /// Ports of the internal model, numbered like the template's ports
+    fn mapping(nodes: &[NodeId]) -> Vec<PortMapping> {
+        nodes
+            .iter()
+            .enumerate()
+            .map(|(i, outer)| PortMapping {
+                outer: *outer,
+                inner: NodeId(i),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn chamber() -> ModuleTemplate {
+        ModuleTemplate {
+            name: "chamber".into(),
+            parameters: Bindings::from([("radius".into(), 500.), ("lead".into(), 100.)]),
+            size: ["2 * (radius + lead)".into(), "2 * (radius + lead)".into()],
+            footprint: Some(TemplateFootprint::Circle {
+                center: ["radius + lead".into(), "radius + lead".into()],
+                radius: "radius".into(),
+            }),
+            ports: vec![
+                TemplatePort {
+                    name: "in".into(),
+                    position: [0.0.into(), "radius + lead".into()],
+                },
+                TemplatePort {
+                    name: "out".into(),
+                    position: ["2 * (radius + lead)".into(), "radius + lead".into()],
+                },
+            ],
+            channels: vec![TemplateChannel {
+                ports: [0, 1],
+                width: "2 * radius".into(),
+                height: 50.0.into(),
+                length: "2 * radius".into(),
+            }],
+        }
+    }
+
+    #[test]
+    fn expressions() {
+        let values = Bindings::from([("a".into(), 2.)]);
+        let value = |text: &str| Expression(text.into()).evaluate(&values);
+        assert_eq!(value("1 + 2 * a - (4 - a) / 2"), Ok(4.));
+        assert_eq!(value("-a * 1.5e2"), Ok(-300.));
+        assert_eq!(value("b"), Err(TemplateError::UnboundName("b".into())));
+        assert!(matches!(value("a +"), Err(TemplateError::Syntax { .. })));
+        assert!(matches!(value("(a"), Err(TemplateError::Syntax { .. })));
+    }
+
+    #[test]
+    fn instances() {
+        let template = chamber();
+        let mut network = Network::default();
+        let id = template
+            .instantiate(&mut network, Point([0., 0.]), &Bindings::new())
+            .unwrap();
+        let big = template
+            .instantiate(
+                &mut network,
+                Point([5000., 0.]),
+                &Bindings::from([("radius".into(), 1000.)]),
+            )
+            .unwrap();
+        assert_eq!(network.nodes.len(), 4);
+        assert_eq!(
+            network.node_position(NodeId(3)),
+            Some(Point([7200., 1100.]))
+        );
+        assert_eq!(network.nodes[2].metadata["name"], "in");
+        assert!(template.is_instance(&network.modules[big]));
+        assert_eq!(
+            ModuleTemplate::bindings_of(&network.modules[big]).unwrap()["radius"],
+            1000.
+        );
+        assert_eq!(
+            template.instantiate(
+                &mut network,
+                Point([0., 0.]),
+                &Bindings::from([("widht".into(), 1.)])
+            ),
+            Err(TemplateError::UnknownParameter("widht".into()))
+        );
+
+        template
+            .update(&mut network, id, &Bindings::from([("lead".into(), 0.)]))
+            .unwrap();
+        assert_eq!(network.modules[id].size, Dimensions([1000., 1000.]));
+        assert_eq!(network.node_position(NodeId(1)), Some(Point([1000., 500.])));
+
+        // The internal model joins the ports after flattening
+        let flat = network.flattened();
+        assert!(flat.modules.is_empty());
+        assert_eq!(flat.channels.len(), 2);
+        assert_eq!(
+            (flat.channels[0].node_a, flat.channels[0].node_b),
+            (NodeId(0), NodeId(1))
+        );
+    }
+}
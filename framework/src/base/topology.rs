@@ -0,0 +1,425 @@
+//! Topology edits keeping the geometry consistent: splitting channels, merging and collapsing
+//! nodes, and deleting entities together with what they leave dangling. Each edit is also
+//! available as an undoable [Command](super::edit::Command).
+
+use super::{
+    channel::{ChannelPath, LineSegment, PathPiece, SVGPath},
+    feature::SurfaceFeature,
+    network::{EntityRef, Network, Node, NodeId},
+    primitives::Point,
+};
+
+/// Moves the start or end of the path to the point: the end of a straight piece is moved,
+/// an arc is extended by a straight lead
+pub(crate) fn move_end(path: &mut ChannelPath, start: bool, point: Point) {
+    let piece = match start {
+        true => path.pieces.first_mut(),
+        false => path.pieces.last_mut(),
+    };
+    match piece {
+        Some(PathPiece::LineSegment(line)) if start => line.start = point,
+        Some(PathPiece::LineSegment(line)) => line.end = point,
+        Some(PathPiece::Arc(arc)) if start && arc.start != point => {
+            let end = arc.start;
+            path.pieces
+                .insert(0, PathPiece::LineSegment(LineSegment { start: point, end }));
+        }
+        Some(PathPiece::Arc(arc)) if !start && arc.end != point => {
+            let lead = LineSegment {
+                start: arc.end,
+                end: point,
+            };
+            path.add(PathPiece::LineSegment(lead));
+        }
+        _ => {}
+    }
+}
+
+impl Network {
+    /// Splits a channel at the arc length along its path by inserting a node there. The
+    /// channel keeps its id and start node and ends at the new node; a new channel with the
+    /// same properties and group memberships continues to the old end node. A nominal length
+    /// is divided in proportion, surface features beyond the split move to the new channel and
+    /// those across it are cut in two. Returns the ids of the new node and channel, or None if
+    /// the channel has no path or the arc length isn't inside it.
+    pub fn split_channel(&mut self, id: usize, length: f64) -> Option<(NodeId, usize)> {
+        let channel_id = self.next_channel_id();
+        let node_id = self.next_node_id();
+        let channel = self.channels.iter_mut().find(|c| c.id == id)?;
+        let path = channel.path.as_ref()?;
+        let total = path.length().0;
+        if length <= 0. || length >= total {
+            return None;
+        }
+        let point = path.point_at_length(length)?;
+        let (head, tail) = path.split_at(length);
+
+        let mut second = channel.clone();
+        second.id = channel_id;
+        second.node_a = node_id;
+        second.length = channel.length.map(|l| l * (1. - length / total));
+        second.path = Some(tail);
+        channel.node_b = node_id;
+        channel.length = channel.length.map(|l| l * length / total);
+        channel.path = Some(head);
+        self.channels.push(second);
+        self.nodes.push(Node::at(node_id, point));
+        for group in &mut self.groups {
+            if group.contains(EntityRef::Channel(id)) {
+                group.members.push(EntityRef::Channel(channel_id));
+            }
+        }
+        let features = std::mem::take(&mut self.surface_features);
+        for feature in features {
+            if feature.channel != id || feature.end <= length {
+                self.surface_features.push(feature);
+                continue;
+            }
+            if feature.start < length {
+                self.surface_features.push(SurfaceFeature {
+                    end: length,
+                    ..feature
+                });
+            }
+            self.surface_features.push(SurfaceFeature {
+                channel: channel_id,
+                start: (feature.start - length).max(0.),
+                end: feature.end - length,
+                ..feature
+            });
+        }
+        Some((node_id, channel_id))
+    }
+
+    /// Merges a node into another: channels and modules attached to the removed node attach
+    /// to the kept one, their paths moved onto its position, and channels between the two
+    /// are removed with their surface features. The kept node takes over the port if it has
+    /// none. Returns false if either node is missing or both are the same.
+    pub fn merge_nodes(&mut self, keep: NodeId, remove: NodeId) -> bool {
+        if keep == remove {
+            return false;
+        }
+        let Some(removed) = self.nodes.iter().position(|n| n.id == remove) else {
+            return false;
+        };
+        if !self.nodes.iter().any(|n| n.id == keep) {
+            return false;
+        }
+        let position = self
+            .node_position(keep)
+            .or_else(|| self.node_position(remove));
+        let removed = self.nodes.remove(removed);
+        let node = self.nodes.iter_mut().find(|n| n.id == keep).unwrap();
+        node.position = node.position.or(position);
+        if node.port.is_none() {
+            node.port = removed.port;
+        }
+
+        self.channels.retain(|c| {
+            !(c.node_a == keep && c.node_b == remove || c.node_a == remove && c.node_b == keep)
+        });
+        for channel in &mut self.channels {
+            for (start, node) in [(true, &mut channel.node_a), (false, &mut channel.node_b)] {
+                if *node != remove {
+                    continue;
+                }
+                *node = keep;
+                if let (Some(path), Some(position)) = (&mut channel.path, position) {
+                    move_end(path, start, position);
+                }
+            }
+        }
+        for module in &mut self.modules {
+            for node in &mut module.nodes {
+                if *node == remove {
+                    *node = keep;
+                }
+            }
+            let mut seen = Vec::new();
+            module.nodes.retain(|n| {
+                let new = !seen.contains(n);
+                seen.push(*n);
+                new
+            });
+        }
+        for group in &mut self.groups {
+            let kept = group.contains(EntityRef::Node(keep));
+            group
+                .members
+                .retain(|m| *m != EntityRef::Node(remove) || !kept);
+            for member in &mut group.members {
+                if *member == EntityRef::Node(remove) {
+                    *member = EntityRef::Node(keep);
+                }
+            }
+        }
+        self.prune_features();
+        self.prune_groups();
+        true
+    }
+
+    /// Removes a node joining exactly two channels, neither of them a loop, replacing them by
+    /// one channel with the concatenated path and the properties of the first in channel
+    /// order. Nominal lengths are summed if either channel has one and surface features move
+    /// along onto the joined path. Nodes with ports or in module interfaces are kept. Returns
+    /// the id of the joined channel.
+    pub fn collapse_node(&mut self, id: NodeId) -> Option<usize> {
+        let node = self.nodes.iter().find(|n| n.id == id)?;
+        if node.port.is_some() || self.modules.iter().any(|m| m.nodes.contains(&id)) {
+            return None;
+        }
+        let attached: Vec<usize> = (0..self.channels.len())
+            .filter(|i| {
+                let c = &self.channels[*i];
+                c.node_a == id || c.node_b == id
+            })
+            .collect();
+        let [first, second] = attached[..] else {
+            return None;
+        };
+        if self.channels[first].node_a == self.channels[first].node_b
+            || self.channels[second].node_a == self.channels[second].node_b
+        {
+            return None;
+        }
+
+        let removed = self.channels.remove(second);
+        let channel = &mut self.channels[first];
+        let path_length = |path: &Option<ChannelPath>| path.as_ref().map_or(0., |p| p.length().0);
+        let (first_length, second_length) =
+            (path_length(&channel.path), path_length(&removed.path));
+        let length = match (channel.length, removed.length) {
+            (None, None) => None,
+            _ => Some(channel.length().unwrap_or(0.) + removed.length().unwrap_or(0.)),
+        };
+        // Traverse the first channel towards the node and the second away from it
+        let reversed = channel.node_a == id;
+        if reversed {
+            std::mem::swap(&mut channel.node_a, &mut channel.node_b);
+            if let Some(path) = &mut channel.path {
+                path.reverse();
+            }
+        }
+        let (far, mut tail) = match removed.node_a == id {
+            true => (removed.node_b, removed.path),
+            false => {
+                let mut path = removed.path;
+                if let Some(path) = &mut path {
+                    path.reverse();
+                }
+                (removed.node_a, path)
+            }
+        };
+        channel.node_b = far;
+        channel.length = length;
+        channel.path = match (channel.path.take(), tail.take()) {
+            (Some(mut head), Some(tail)) => {
+                head.concat(&tail);
+                Some(head)
+            }
+            _ => None,
+        };
+        if reversed {
+            std::mem::swap(&mut channel.node_a, &mut channel.node_b);
+            if let Some(path) = &mut channel.path {
+                path.reverse();
+            }
+        }
+        let joined = channel.id;
+        // The joined path runs along the first channel unless that one was reversed
+        if reversed {
+            self.move_features(joined, joined, None, second_length);
+        }
+        let second_reversed = (removed.node_a == id) == reversed;
+        self.move_features(
+            removed.id,
+            joined,
+            second_reversed.then_some(second_length),
+            if reversed { 0. } else { first_length },
+        );
+        self.nodes.retain(|n| n.id != id);
+        self.prune_groups();
+        Some(joined)
+    }
+
+    /// Removes a channel with its surface features and the end nodes it leaves unattached,
+    /// except for ports and module interface nodes; returns false if there is no such channel
+    pub fn delete_channel(&mut self, id: usize) -> bool {
+        let Some(index) = self.channels.iter().position(|c| c.id == id) else {
+            return false;
+        };
+        let channel = self.channels.remove(index);
+        self.remove_dangling_nodes(&[channel.node_a, channel.node_b]);
+        self.prune_features();
+        self.prune_groups();
+        true
+    }
+
+    /// Removes a node with its channels, the neighboring nodes left unattached as in
+    /// [Network::delete_channel], and its module interface entries; returns false if there is
+    /// no such node
+    pub fn delete_node(&mut self, id: NodeId) -> bool {
+        let Some(index) = self.nodes.iter().position(|n| n.id == id) else {
+            return false;
+        };
+        self.nodes.remove(index);
+        let mut neighbors = Vec::new();
+        self.channels.retain(|c| {
+            let attached = c.node_a == id || c.node_b == id;
+            if attached {
+                neighbors.extend([c.node_a, c.node_b]);
+            }
+            !attached
+        });
+        for module in &mut self.modules {
+            module.nodes.retain(|n| *n != id);
+        }
+        self.remove_dangling_nodes(&neighbors);
+        self.prune_features();
+        self.prune_groups();
+        true
+    }
+
+    /// Moves the surface features of a channel to another, first reversed along a path of the
+    /// given length, then shifted by the offset along the new path
+    fn move_features(&mut self, from: usize, to: usize, reverse: Option<f64>, offset: f64) {
+        for feature in self
+            .surface_features
+            .iter_mut()
+            .filter(|f| f.channel == from)
+        {
+            if let Some(length) = reverse {
+                (feature.start, feature.end) = (length - feature.end, length - feature.start);
+            }
+            feature.channel = to;
+            feature.start += offset;
+            feature.end += offset;
+        }
+    }
+
+    /// Removes the surface features of channels that aren't part of the network
+    fn prune_features(&mut self) {
+        let channels = &self.channels;
+        self.surface_features
+            .retain(|f| channels.iter().any(|c| c.id == f.channel));
+    }
+
+    fn remove_dangling_nodes(&mut self, candidates: &[NodeId]) {
+        let channels = &self.channels;
+        let modules = &self.modules;
+        self.nodes.retain(|n| {
+            !candidates.contains(&n.id)
+                || n.port.is_some()
+                || channels
+                    .iter()
+                    .any(|c| c.node_a == n.id || c.node_b == n.id)
+                || modules.iter().any(|m| m.nodes.contains(&n.id))
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::{Channel, CylindricalShape, Shape},
+        edit::{Command, EditSession},
+        feature::GroovePattern,
+        network::Metadata,
+        primitives::Tolerance,
+    };
+
+    #[test]
+    fn split_merge_collapse_delete() {
+        let mut path = ChannelPath::new();
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: Point([0., 0.]),
+            end: Point([100., 0.]),
+        }));
+        let mut network = Network {
+            nodes: vec![
+                Node::at(NodeId(0), Point([0., 0.])),
+                Node::at(NodeId(1), Point([100., 0.])),
+            ],
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(1),
+                shape: Shape::Cylindrical(CylindricalShape { radius: 1. }),
+                path: Some(path),
+                length: Some(200.),
+                layer: 0,
+                metadata: Metadata::new(),
+            }],
+            ..Default::default()
+        };
+        network.add_to_group("all", EntityRef::Channel(0));
+        let grooves = |channel, start, end| SurfaceFeature {
+            channel,
+            pattern: GroovePattern::Slanted,
+            start,
+            end,
+            groove_width: 1.,
+            pitch: 2.,
+            angle: std::f64::consts::FRAC_PI_4,
+            depth: 0.5,
+        };
+        network.surface_features = vec![grooves(0, 10., 60.), grooves(0, 70., 90.)];
+        let original = network.clone();
+
+        // Splitting through the edit session carries the group and features along
+        let mut session = EditSession::new(network.clone());
+        assert!(session.execute(&Command::SplitChannel { id: 0, length: 25. }));
+        let members = [EntityRef::Channel(0), EntityRef::Channel(1)];
+        assert_eq!(session.network.groups[0].members, members);
+        assert!(session.undo());
+        assert_eq!(session.network, original);
+
+        assert_eq!(network.split_channel(0, 100.), None);
+        assert_eq!(network.split_channel(0, 25.), Some((NodeId(2), 1)));
+        assert_eq!(network.node_position(NodeId(2)), Some(Point([25., 0.])));
+        assert_eq!(network.channels[0].length, Some(50.));
+        assert_eq!(network.channels[1].length, Some(150.));
+        assert_eq!(network.group_members("all").unwrap().len(), 2);
+        let features = [
+            grooves(0, 10., 25.),
+            grooves(1, 0., 35.),
+            grooves(1, 45., 65.),
+        ];
+        assert_eq!(network.surface_features, features);
+
+        // Collapsing the inserted node restores the channel
+        assert_eq!(network.collapse_node(NodeId(0)), None);
+        assert_eq!(network.collapse_node(NodeId(2)), Some(0));
+        let path = network.channels[0].path.as_ref().unwrap();
+        assert_eq!(path.pieces.len(), 2);
+        assert!(Tolerance::default().eq(path.length().0, 100.));
+        assert_eq!(network.nodes, original.nodes);
+        assert_eq!(network.channels[0].length, Some(200.));
+        assert_eq!(network.groups, original.groups);
+        let features = [
+            grooves(0, 10., 25.),
+            grooves(0, 25., 60.),
+            grooves(0, 70., 90.),
+        ];
+        assert_eq!(network.surface_features, features);
+
+        // A node dropped onto another moves the channel end along
+        network.nodes.push(Node::at(NodeId(5), Point([100., 10.])));
+        assert!(network.merge_nodes(NodeId(5), NodeId(1)));
+        assert_eq!(network.channels[0].node_b, NodeId(5));
+        let end = network.channels[0].path.as_ref().unwrap().pieces[1].end();
+        assert_eq!(end, Point([100., 10.]));
+        assert!(!network.merge_nodes(NodeId(5), NodeId(1)));
+
+        let mut session = EditSession::new(network.clone());
+        assert!(session.execute(&Command::DeleteChannel(0)));
+        assert!(session.network.nodes.is_empty());
+        assert!(session.network.groups[0].members.is_empty());
+        assert!(session.network.surface_features.is_empty());
+        assert!(session.undo());
+        assert_eq!(session.network, network);
+        assert!(network.delete_channel(0));
+        assert!(network.groups[0].members.is_empty());
+    }
+}
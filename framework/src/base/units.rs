@@ -0,0 +1,195 @@
+//! Physical length units of layouts and of exchange formats. Layout coordinates are plain
+//! numbers; a network may declare the unit they are in, so that exporters convert to the units
+//! of their files instead of assuming micrometers.
+
+use super::network::Network;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Unit of lengths
+pub enum LengthUnit {
+    Meter,
+    Millimeter,
+    Micrometer,
+    Inch,
+
+    /// CSS pixel, 1/96 inch
+    Pixel,
+}
+
+impl LengthUnit {
+    /// Length of the unit in micrometers, integral but for pixels, so that conversions between
+    /// metric units and inches are exact up to rounding of the ratio
+    pub fn micrometers(self) -> f64 {
+        match self {
+            LengthUnit::Meter => 1e6,
+            LengthUnit::Millimeter => 1e3,
+            LengthUnit::Micrometer => 1.,
+            LengthUnit::Inch => 25400.,
+            LengthUnit::Pixel => 25400. / 96.,
+        }
+    }
+
+    /// Number of these units in one of the other unit, e.g., 1000 micrometers per millimeter
+    pub fn per(self, other: LengthUnit) -> f64 {
+        if self == other {
+            1.
+        } else {
+            other.micrometers() / self.micrometers()
+        }
+    }
+
+    /// Unit of which `units` make one `reference` unit, e.g., millimeters for 0.001 per
+    /// micrometer, to declare the unit of a network imported with that scale
+    pub fn with_scale(units: f64, reference: LengthUnit) -> Option<LengthUnit> {
+        let micrometers = reference.micrometers() / units;
+        [
+            LengthUnit::Meter,
+            LengthUnit::Millimeter,
+            LengthUnit::Micrometer,
+            LengthUnit::Inch,
+            LengthUnit::Pixel,
+        ]
+        .into_iter()
+        .find(|unit| (unit.micrometers() / micrometers - 1.).abs() < 1e-9)
+    }
+
+    /// Unit of a DXF `$INSUNITS` code; 0 and codes of other units give none
+    pub fn from_insunits(code: u16) -> Option<LengthUnit> {
+        match code {
+            1 => Some(LengthUnit::Inch),
+            4 => Some(LengthUnit::Millimeter),
+            6 => Some(LengthUnit::Meter),
+            13 => Some(LengthUnit::Micrometer),
+            _ => None,
+        }
+    }
+
+    /// DXF `$INSUNITS` code, 0 (unitless) for pixels
+    pub fn insunits(self) -> u16 {
+        match self {
+            LengthUnit::Inch => 1,
+            LengthUnit::Millimeter => 4,
+            LengthUnit::Meter => 6,
+            LengthUnit::Micrometer => 13,
+            LengthUnit::Pixel => 0,
+        }
+    }
+
+    /// CSS unit and factor of an SVG length in this unit. CSS lacks meters and micrometers,
+    /// which are given in millimeters.
+    pub fn css(self) -> (&'static str, f64) {
+        match self {
+            LengthUnit::Meter => ("mm", 1e3),
+            LengthUnit::Millimeter => ("mm", 1.),
+            LengthUnit::Micrometer => ("mm", 1e-3),
+            LengthUnit::Inch => ("in", 1.),
+            LengthUnit::Pixel => ("px", 1.),
+        }
+    }
+
+    /// Value and unit of a CSS length, e.g., an SVG width of "85.6mm". Lengths without a
+    /// unit are in user units and give no unit; centimeters and points are converted.
+    pub fn parse_css(text: &str) -> Option<(f64, Option<LengthUnit>)> {
+        let text = text.trim();
+        let split = text
+            .find(|c: char| c.is_ascii_alphabetic() && c != 'e' && c != 'E')
+            .unwrap_or(text.len());
+        let value: f64 = text[..split].trim().parse().ok()?;
+        let (factor, unit) = match &text[split..] {
+            "" => (1., None),
+            "px" => (1., Some(LengthUnit::Pixel)),
+            "mm" => (1., Some(LengthUnit::Millimeter)),
+            "cm" => (10., Some(LengthUnit::Millimeter)),
+            "in" => (1., Some(LengthUnit::Inch)),
+            "pt" => (1. / 72., Some(LengthUnit::Inch)),
+            _ => return None,
+        };
+        Some((value * factor, unit))
+    }
+}
+
+impl Network {
+    /// Layout units per unit of the given kind, if the network declares its length unit
+    pub fn units_per(&self, unit: LengthUnit) -> Option<f64> {
+        self.length_unit.map(|layout| layout.per(unit))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn conversions() {
+        assert_eq!(LengthUnit::Micrometer.per(LengthUnit::Millimeter), 1000.);
+        assert_eq!(LengthUnit::Millimeter.per(LengthUnit::Inch), 25.4);
+        assert!((LengthUnit::Pixel.per(LengthUnit::Inch) - 96.).abs() < 1e-12);
+        for unit in [
+            LengthUnit::Meter,
+            LengthUnit::Millimeter,
+            LengthUnit::Micrometer,
+            LengthUnit::Inch,
+        ] {
+            assert_eq!(LengthUnit::from_insunits(unit.insunits()), Some(unit));
+        }
+        assert_eq!(LengthUnit::from_insunits(0), None);
+        assert_eq!(
+            LengthUnit::parse_css(" 8.5cm"),
+            Some((85., Some(LengthUnit::Millimeter)))
+        );
+        assert_eq!(LengthUnit::parse_css("1e2"), Some((100., None)));
+        assert_eq!(LengthUnit::parse_css("3em"), None);
+
+        let network = Network {
+            length_unit: Some(LengthUnit::Millimeter),
+            ..Default::default()
+        };
+        assert_eq!(network.units_per(LengthUnit::Meter), Some(1000.));
+        assert_eq!(Network::default().units_per(LengthUnit::Meter), None);
+    }
+
+    #[test]
+    fn export_boundaries() {
+        use crate::{
+            base::render::RenderConfig,
+            export::{dxf::network_to_dxf_with, gerber::network_to_gerber, svg::network_to_svg},
+            fixtures::trap_array,
+        };
+
+        // The fixture is laid out in meters
+        let undeclared = trap_array(1, 1).network;
+        let network = Network {
+            length_unit: Some(LengthUnit::Meter),
+            ..undeclared.clone()
+        };
+        assert_eq!(
+            network_to_gerber(&network, 1000.),
+            network_to_gerber(&undeclared, 1e-3)
+        );
+
+        let config = RenderConfig {
+            unit: Some(LengthUnit::Millimeter),
+            ..Default::default()
+        };
+        let dxf = network_to_dxf_with(&network, &config);
+        assert!(dxf.contains("$INSUNITS\n70\n4\n"));
+        let mut scaled = config;
+        scaled.coordinate_system.scale = 1000.;
+        assert_eq!(dxf, network_to_dxf_with(&undeclared, &scaled));
+        assert!(!network_to_dxf_with(&undeclared, &Default::default()).contains("$INSUNITS"));
+
+        let svg = network_to_svg(&network);
+        let width = svg.split(r#"width=""#).nth(1).unwrap();
+        let (value, unit) = LengthUnit::parse_css(width.split('"').next().unwrap()).unwrap();
+        assert_eq!(unit, Some(LengthUnit::Millimeter));
+        assert!(value > 0.);
+        assert_eq!(
+            LengthUnit::with_scale(1e6, LengthUnit::Meter),
+            Some(LengthUnit::Micrometer)
+        );
+        assert_eq!(LengthUnit::with_scale(7., LengthUnit::Meter), None);
+    }
+}
@@ -0,0 +1,60 @@
+//! Command line tools of the framework
+//!
+//! ```text
+//! mmft schemas [FILE]     writes the schema bundle to FILE or stdout
+//! mmft designers          lists the designer functions
+//! mmft run NAME [FILE]    calls a designer function with the JSON input in FILE or stdin
+//! ```
+
+use mmft_framework::{designer, interfaces::schema::schemas};
+use std::{io::Read, process::ExitCode};
+
+const USAGE: &str = "usage: mmft schemas [FILE] | mmft designers | mmft run NAME [FILE]";
+
+fn run(name: &str, path: Option<&str>) -> ExitCode {
+    let input = match path {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| format!("can't read {path}: {e}")),
+        None => {
+            let mut input = String::new();
+            std::io::stdin()
+                .read_to_string(&mut input)
+                .map(|_| input)
+                .map_err(|e| format!("can't read stdin: {e}"))
+        }
+    };
+    match input.and_then(|input| designer::dispatch(name, &input).map_err(|e| e.to_string())) {
+        Ok(output) => {
+            println!("{output}");
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["schemas"] => println!("{}", schemas().bundle_json()),
+        ["schemas", path] => {
+            if let Err(error) = std::fs::write(path, schemas().bundle_json()) {
+                eprintln!("can't write {path}: {error}");
+                return ExitCode::FAILURE;
+            }
+        }
+        ["designers"] => {
+            for function in designer::REGISTRY {
+                println!("{}", function.name);
+            }
+        }
+        ["run", name] => return run(name, None),
+        ["run", name, path] => return run(name, Some(path)),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,292 @@
+//! Alignment marks for multi-layer lithography. Each mark is a pair: the reference geometry on
+//! the layer exposed first and the complementary geometry on a layer aligned to it later.
+//!
+//! Crosses are framed by four squares on the aligned layer, so that a misalignment shows as
+//! unequal gaps. Verniers read the misalignment along both axes: the reference scale has lines
+//! at the pitch, the aligned scale at a pitch shorter by one part in the number of lines, so
+//! the lines that coincide tell the offset in multiples of the pitch over the number of lines.
+
+use crate::base::{
+    channel::ChannelPath,
+    marking::Marking,
+    primitives::{Point, Rect},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Geometry of an alignment mark
+pub enum MarkShape {
+    /// Cross of two bars; the aligned layer gets squares in its quadrants
+    Cross {
+        /// Length of the bars
+        size: f64,
+
+        /// Width of the bars
+        width: f64,
+
+        /// Gap between the bars and the squares
+        clearance: f64,
+    },
+
+    /// Vernier scales along both axes, the zero lines elongated
+    Vernier {
+        /// Line pitch of the reference scale
+        pitch: f64,
+
+        /// Number of lines per scale
+        lines: usize,
+
+        /// Line length
+        length: f64,
+
+        /// Line width
+        width: f64,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Corner of the chip outline
+pub enum Corner {
+    LowerLeft,
+    LowerRight,
+    UpperRight,
+    UpperLeft,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Marks placed relative to the chip outline
+pub struct AlignmentMarkParameters {
+    /// Chip outline
+    pub outline: Rect,
+
+    /// Corners with a mark
+    pub corners: Vec<Corner>,
+
+    /// Distance of the mark centers from their corner along both axes
+    pub inset: f64,
+
+    pub shape: MarkShape,
+
+    /// Marking layer of the reference geometry
+    pub layer: String,
+
+    /// Marking layer of the complementary geometry, none if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aligned_layer: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Generated marks, to be added to the network's markings
+pub struct AlignmentMarks {
+    pub markings: Vec<Marking>,
+
+    /// Mark center per requested corner
+    pub centers: Vec<Point>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Reasons alignment marks can't be generated
+pub enum AlignmentMarkError {
+    /// A length isn't positive, a vernier has fewer than two lines, or the squares of a cross
+    /// vanish behind the clearance
+    InvalidShape,
+
+    /// The mark at the corner reaches beyond the outline
+    OutsideOutline(Corner),
+}
+
+impl std::fmt::Display for AlignmentMarkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlignmentMarkError::InvalidShape => write!(f, "invalid alignment mark dimensions"),
+            AlignmentMarkError::OutsideOutline(corner) => {
+                write!(
+                    f,
+                    "the alignment mark at the {corner:?} corner leaves the outline"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for AlignmentMarkError {}
+
+fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Rect {
+    Rect::enclosing([Point([x0, y0]), Point([x1, y1])]).unwrap()
+}
+
+impl MarkShape {
+    /// Reference and aligned rectangles of a mark centered at the origin. Verniers extend
+    /// towards positive coordinates, which mirrored placement turns towards the chip.
+    fn rectangles(&self) -> Option<(Vec<Rect>, Vec<Rect>)> {
+        match *self {
+            MarkShape::Cross {
+                size,
+                width,
+                clearance,
+            } => {
+                let (half, inner) = (size / 2., width / 2. + clearance);
+                if !(width > 0. && clearance >= 0. && half > inner) {
+                    return None;
+                }
+                let bars = vec![
+                    rect(-half, -width / 2., half, width / 2.),
+                    rect(-width / 2., -half, width / 2., half),
+                ];
+                let squares = [(1., 1.), (-1., 1.), (-1., -1.), (1., -1.)]
+                    .map(|(sx, sy)| rect(sx * inner, sy * inner, sx * half, sy * half));
+                Some((bars, squares.to_vec()))
+            }
+            MarkShape::Vernier {
+                pitch,
+                lines,
+                length,
+                width,
+            } => {
+                if !(pitch > width && width > 0. && length > 0. && lines >= 2) {
+                    return None;
+                }
+                let middle = (lines - 1) as f64 / 2.;
+                let zero = 2. * length + middle * pitch;
+                let fine = pitch * (1. - 1. / lines as f64);
+                let (mut reference, mut aligned) = (Vec::new(), Vec::new());
+                for k in 0..lines {
+                    let i = k as f64 - middle;
+                    let extent = if i == 0. { 1.5 * length } else { length };
+                    let x = zero + i * pitch;
+                    reference.push(rect(x - width / 2., 0., x + width / 2., extent));
+                    let x = zero + i * fine;
+                    aligned.push(rect(x - width / 2., -extent, x + width / 2., 0.));
+                }
+                // The scale along y is the one along x mirrored at the diagonal
+                let swap = |r: &Rect| rect(r.min.0[1], r.min.0[0], r.max.0[1], r.max.0[0]);
+                let swapped: Vec<Rect> = reference.iter().map(swap).collect();
+                reference.extend(swapped);
+                let swapped: Vec<Rect> = aligned.iter().map(swap).collect();
+                aligned.extend(swapped);
+                Some((reference, aligned))
+            }
+        }
+    }
+}
+
+/// Generates the marks inset from the requested corners of the outline, oriented towards the
+/// chip's interior
+pub fn design_alignment_marks(
+    parameters: AlignmentMarkParameters,
+) -> Result<AlignmentMarks, AlignmentMarkError> {
+    let AlignmentMarkParameters {
+        outline,
+        corners,
+        inset,
+        shape,
+        layer,
+        aligned_layer,
+    } = parameters;
+    let (reference, aligned) = shape.rectangles().ok_or(AlignmentMarkError::InvalidShape)?;
+    let (Point([x0, y0]), Point([x1, y1])) = (outline.min, outline.max);
+
+    let mut marks = AlignmentMarks {
+        markings: Vec::new(),
+        centers: Vec::new(),
+    };
+    for corner in corners {
+        let (center, [sx, sy]) = match corner {
+            Corner::LowerLeft => (Point([x0 + inset, y0 + inset]), [1., 1.]),
+            Corner::LowerRight => (Point([x1 - inset, y0 + inset]), [-1., 1.]),
+            Corner::UpperRight => (Point([x1 - inset, y1 - inset]), [-1., -1.]),
+            Corner::UpperLeft => (Point([x0 + inset, y1 - inset]), [1., -1.]),
+        };
+        let place = |r: &Rect| {
+            let Point([cx, cy]) = center;
+            rect(
+                cx + sx * r.min.0[0],
+                cy + sy * r.min.0[1],
+                cx + sx * r.max.0[0],
+                cy + sy * r.max.0[1],
+            )
+        };
+        let layers = [Some(&layer), aligned_layer.as_ref()];
+        for (rectangles, layer) in [&reference, &aligned].into_iter().zip(layers) {
+            let Some(layer) = layer else {
+                continue;
+            };
+            for placed in rectangles.iter().map(place) {
+                if outline.union(&placed) != outline {
+                    return Err(AlignmentMarkError::OutsideOutline(corner));
+                }
+                marks.markings.push(Marking {
+                    layer: layer.clone(),
+                    path: ChannelPath::rectangle(&placed),
+                    width: 0.,
+                });
+            }
+        }
+        marks.centers.push(center);
+    }
+    Ok(marks)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn marks() {
+        let parameters = AlignmentMarkParameters {
+            outline: rect(0., 0., 20e3, 10e3),
+            corners: vec![Corner::LowerLeft, Corner::UpperRight],
+            inset: 1e3,
+            shape: MarkShape::Cross {
+                size: 400.,
+                width: 40.,
+                clearance: 10.,
+            },
+            layer: "FLOW".into(),
+            aligned_layer: Some("CONTROL".into()),
+        };
+        let marks = design_alignment_marks(parameters.clone()).unwrap();
+        assert_eq!(marks.centers, [Point([1e3, 1e3]), Point([19e3, 9e3])]);
+        assert_eq!(marks.markings.len(), 2 * (2 + 4));
+        assert_eq!(
+            (marks.markings.iter())
+                .filter(|m| m.layer == "CONTROL")
+                .count(),
+            8
+        );
+
+        let vernier = AlignmentMarkParameters {
+            shape: MarkShape::Vernier {
+                pitch: 10.,
+                lines: 5,
+                length: 50.,
+                width: 4.,
+            },
+            aligned_layer: None,
+            ..parameters.clone()
+        };
+        let marks = design_alignment_marks(vernier.clone()).unwrap();
+        assert_eq!(marks.markings.len(), 2 * 2 * 5);
+        // Lines next to the zero lines differ by a fifth of the pitch
+        let (reference, aligned) = vernier.shape.rectangles().unwrap();
+        let x = |r: &Rect| (r.min.0[0] + r.max.0[0]) / 2.;
+        assert!((x(&reference[3]) - x(&aligned[3]) - 2.).abs() < 1e-9);
+        assert_eq!(x(&reference[2]), x(&aligned[2]));
+
+        let tight = AlignmentMarkParameters {
+            inset: 50.,
+            aligned_layer: Some("CONTROL".into()),
+            ..vernier
+        };
+        assert_eq!(
+            design_alignment_marks(tight),
+            Err(AlignmentMarkError::OutsideOutline(Corner::LowerLeft))
+        );
+    }
+}
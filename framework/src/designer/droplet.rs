@@ -0,0 +1,193 @@
+//! Droplet generators in the squeezing regime, after Garstecki et al. (2006): the continuous
+//! phase pinches the dispersed thread off once it blocks the channel, giving plugs of length
+//! L = w (1 + α Q_d / Q_c). Flow-focusing junctions follow the same scaling with the orifice
+//! width. The plug volume is approximated by a straight body with semicircular caps.
+//!
+//! ```ignore
+//! mmft_framework::wasm_interface_function!(
+//!     predict_droplets,
+//!     mmft_framework::designer::droplet::predict_droplets
+//! );
+//! ```
+
+use crate::simulation::fluid::Fluid;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::FRAC_PI_4;
+
+/// Largest capillary number of the continuous phase for which the squeezing law holds
+pub const SQUEEZING_CAPILLARY: f64 = 0.01;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Geometry at which the droplets form
+pub enum JunctionType {
+    /// Dispersed phase entering the continuous phase channel from the side
+    TJunction,
+
+    /// Dispersed phase focused through an orifice by two continuous phase streams
+    FlowFocusing,
+}
+
+fn default_alpha() -> f64 {
+    1.
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Droplet forming junction
+pub struct DropletJunction {
+    pub kind: JunctionType,
+
+    /// Width of the main channel of a T-junction or of the orifice of a flow-focusing junction
+    pub width: f64,
+
+    /// Channel height
+    pub height: f64,
+
+    /// Fitting constant α of the squeezing law, of order one and depending on the inlet widths
+    #[serde(default = "default_alpha")]
+    pub alpha: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Junction and operating point of a droplet generator
+pub struct DropletGeneratorParameters {
+    pub junction: DropletJunction,
+
+    /// Flow rate of the dispersed phase
+    pub dispersed_flow: f64,
+
+    /// Flow rate of the continuous phase, summed over both inlets of flow-focusing junctions
+    pub continuous_flow: f64,
+
+    /// Continuous phase, with its surface tension against the dispersed phase, to check the
+    /// regime
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub continuous_phase: Option<Fluid>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Predicted droplets
+pub struct DropletPrediction {
+    /// Plug length along the channel
+    pub length: f64,
+
+    pub volume: f64,
+
+    /// Droplets generated per unit time
+    pub frequency: f64,
+
+    /// Capillary number of the continuous phase, if its properties are given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capillary: Option<f64>,
+
+    /// Whether the capillary number is low enough for the squeezing law, assumed if unknown;
+    /// droplets are smaller than predicted otherwise
+    pub squeezing: bool,
+}
+
+/// Plug volume of the length in the junction's channel
+fn plug_volume(junction: &DropletJunction, length: f64) -> f64 {
+    let w = junction.width;
+    junction.height * (w * (length - w) + FRAC_PI_4 * w * w)
+}
+
+/// Predicts droplet size and generation frequency
+pub fn predict_droplets(parameters: DropletGeneratorParameters) -> DropletPrediction {
+    let DropletGeneratorParameters {
+        junction,
+        dispersed_flow,
+        continuous_flow,
+        continuous_phase,
+    } = parameters;
+    let length = junction.width * (1. + junction.alpha * dispersed_flow / continuous_flow);
+    let volume = plug_volume(&junction, length);
+    let capillary = continuous_phase.and_then(|fluid| {
+        let velocity = continuous_flow / (junction.width * junction.height);
+        Some(fluid.viscosity * velocity / fluid.surface_tension?)
+    });
+    DropletPrediction {
+        length,
+        volume,
+        frequency: dispersed_flow / volume,
+        capillary,
+        squeezing: capillary.is_none_or(|ca| ca <= SQUEEZING_CAPILLARY),
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Droplet volume to design a junction for
+pub struct DropletTarget {
+    pub kind: JunctionType,
+
+    pub volume: f64,
+
+    /// Channel height, usually fixed by the fabrication process
+    pub height: f64,
+
+    pub dispersed_flow: f64,
+    pub continuous_flow: f64,
+
+    #[serde(default = "default_alpha")]
+    pub alpha: f64,
+}
+
+/// Junction producing droplets of the target volume at the flow rates: with the squeezing law
+/// the volume is h w² (α Q_d / Q_c + π / 4), solved for the width
+pub fn suggest_junction(target: DropletTarget) -> DropletJunction {
+    let ratio = target.dispersed_flow / target.continuous_flow;
+    DropletJunction {
+        kind: target.kind,
+        width: (target.volume / (target.height * (target.alpha * ratio + FRAC_PI_4))).sqrt(),
+        height: target.height,
+        alpha: target.alpha,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn squeezing() {
+        let junction = DropletJunction {
+            kind: JunctionType::TJunction,
+            width: 100e-6,
+            height: 50e-6,
+            alpha: 1.,
+        };
+        let parameters = DropletGeneratorParameters {
+            junction,
+            dispersed_flow: 1e-11,
+            continuous_flow: 2e-11,
+            continuous_phase: Some(Fluid {
+                surface_tension: Some(0.005),
+                ..Fluid::water()
+            }),
+        };
+        let prediction = predict_droplets(parameters);
+        assert!((prediction.length - 150e-6).abs() < 1e-18);
+        assert!(prediction.squeezing);
+        assert!((prediction.frequency * prediction.volume - 1e-11).abs() < 1e-24);
+
+        let suggested = suggest_junction(DropletTarget {
+            kind: JunctionType::TJunction,
+            volume: prediction.volume,
+            height: 50e-6,
+            dispersed_flow: 1e-11,
+            continuous_flow: 2e-11,
+            alpha: 1.,
+        });
+        assert!((suggested.width - 100e-6).abs() < 1e-15);
+
+        let fast = predict_droplets(DropletGeneratorParameters {
+            continuous_flow: 1e-7,
+            ..parameters
+        });
+        assert!(!fast.squeezing && fast.volume < prediction.volume);
+    }
+}
@@ -0,0 +1,17 @@
+//! Parametric designers of common microfluidic components. Each takes a serde parameter struct
+//! and returns a serde result, so it can be bound directly with the interface macros.
+
+pub mod alignment;
+pub mod droplet;
+pub mod multiplexer;
+pub mod tesla;
+pub mod trap;
+
+crate::dispatch_functions!(
+    design_alignment_marks => alignment::design_alignment_marks,
+    predict_droplets => droplet::predict_droplets,
+    suggest_junction => droplet::suggest_junction,
+    design_multiplexer => multiplexer::design_multiplexer,
+    design_tesla_valve => tesla::design_tesla_valve,
+    design_trap_array => trap::design_trap_array,
+);
@@ -0,0 +1,379 @@
+//! Binary multiplexers after Thorsen et al. (2002): N parallel flow channels are addressed by
+//! 2 ⌈log2 N⌉ control lines, a complementary pair per bit of the channel index. Each line
+//! crosses all flow channels and has a valve on those whose bit matches its value, so
+//! pressurizing one line of every pair closes all flow channels but the addressed one.
+//!
+//! Flow channels run in positive x from a shared manifold at x = 0 to an outlet each and are
+//! stacked upwards; the control lines run across them on the control layer, with their ports
+//! above the last flow channel.
+
+use crate::base::{
+    channel::{Channel, ChannelPath, LineSegment, PathPiece, Shape},
+    control::ControlLayerConfig,
+    network::{Metadata, ModuleKind, Network, Node, NodeId},
+    port::PortHole,
+    primitives::{Dimensions, Point, Vector2},
+    template::{Bindings, ModuleTemplate},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Dimensions of a multiplexer
+pub struct MultiplexerParameters {
+    /// Number of addressed flow channels, at least 2
+    pub flow_channels: usize,
+
+    /// Cross-section of the flow channels and the manifold
+    pub flow: Shape,
+
+    /// Distance between the centerlines of neighboring flow channels
+    pub pitch: f64,
+
+    /// Length of the flow channels from the manifold to their outlet
+    pub length: f64,
+
+    /// Distance between neighboring control lines
+    pub line_pitch: f64,
+
+    /// Hole of the inlet and outlet ports
+    pub port: PortHole,
+
+    /// Control channels and membranes; the lead length is measured from the last flow channel
+    pub control: ControlLayerConfig,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Control line of a multiplexer
+pub struct MultiplexerLine {
+    /// Bit of the flow channel index the line decodes
+    pub bit: usize,
+
+    /// Value of the bit of the flow channels the line closes
+    pub value: bool,
+
+    /// Id of the control channel
+    pub channel: usize,
+
+    /// Port node of the control channel
+    pub port: NodeId,
+
+    /// Ids of the valve modules on the line
+    pub valves: Vec<usize>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Generated multiplexer with its layout
+pub struct Multiplexer {
+    pub network: Network,
+
+    /// Node of the shared inlet
+    pub inlet: NodeId,
+
+    /// Outlet node per flow channel
+    pub outlets: Vec<NodeId>,
+
+    /// Channel ids per flow channel in flow order, split where the control lines cross
+    pub flow_channels: Vec<Vec<usize>>,
+
+    /// Control lines, both lines of each bit in a row with the bits ascending
+    pub control_lines: Vec<MultiplexerLine>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Reasons a multiplexer can't be generated
+pub enum MultiplexerError {
+    /// A single flow channel needs no addressing
+    TooFewChannels(usize),
+
+    /// The control lines must be on a layer other than the flow channels
+    ControlOnFlowLayer,
+
+    /// Membranes reach the neighboring flow channel or control line
+    Overlap,
+
+    /// The flow channels are too short for all control lines
+    TooShort { length: f64, minimum: f64 },
+}
+
+impl std::fmt::Display for MultiplexerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MultiplexerError::TooFewChannels(n) => {
+                write!(f, "{n} flow channels are too few to multiplex")
+            }
+            MultiplexerError::ControlOnFlowLayer => {
+                write!(
+                    f,
+                    "control lines must be on another layer than the flow channels"
+                )
+            }
+            MultiplexerError::Overlap => {
+                write!(f, "membranes overlap neighboring channels or lines")
+            }
+            MultiplexerError::TooShort { length, minimum } => write!(
+                f,
+                "flow channel length {length} is shorter than the {minimum} of the control lines"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MultiplexerError {}
+
+/// Bits needed to address the flow channels
+pub fn address_bits(flow_channels: usize) -> usize {
+    (usize::BITS - flow_channels.saturating_sub(1).leading_zeros()) as usize
+}
+
+impl Multiplexer {
+    /// Open state per valve module id that opens only the flow channel of the index
+    pub fn address(&self, index: usize) -> BTreeMap<usize, bool> {
+        (self.control_lines.iter())
+            .flat_map(|line| {
+                let pressurized = ((index >> line.bit) & 1 == 1) != line.value;
+                line.valves.iter().map(move |valve| (*valve, !pressurized))
+            })
+            .collect()
+    }
+}
+
+/// Template of the valve modules, a rectangle of the membrane's extent
+fn valve_template() -> ModuleTemplate {
+    ModuleTemplate {
+        name: "multiplexer_valve".into(),
+        parameters: Bindings::from([("across".into(), 0.), ("along".into(), 0.)]),
+        size: ["along".into(), "across".into()],
+        footprint: None,
+        ports: Vec::new(),
+        channels: Vec::new(),
+    }
+}
+
+fn segment(start: Point, end: Point) -> ChannelPath {
+    let mut path = ChannelPath::new();
+    path.add(PathPiece::LineSegment(LineSegment { start, end }));
+    path
+}
+
+/// Generates the two-layer network and layout
+pub fn design_multiplexer(
+    parameters: MultiplexerParameters,
+) -> Result<Multiplexer, MultiplexerError> {
+    let MultiplexerParameters {
+        flow_channels,
+        flow,
+        pitch,
+        length,
+        line_pitch,
+        port,
+        control,
+    } = parameters;
+    if flow_channels < 2 {
+        return Err(MultiplexerError::TooFewChannels(flow_channels));
+    }
+    if control.layer == 0 {
+        return Err(MultiplexerError::ControlOnFlowLayer);
+    }
+    let Dimensions([across, along]) = control.membrane;
+    if across >= pitch || along >= line_pitch {
+        return Err(MultiplexerError::Overlap);
+    }
+    let bits = address_bits(flow_channels);
+    let lines = 2 * bits;
+    let minimum = lines as f64 * line_pitch;
+    if length <= minimum {
+        return Err(MultiplexerError::TooShort { length, minimum });
+    }
+    // Control lines are centered along the flow channels, each on a segment of its own
+    let first = (length - (lines - 1) as f64 * line_pitch) / 2.;
+    let xs: Vec<f64> = (0..lines).map(|k| first + k as f64 * line_pitch).collect();
+    let mut breaks = vec![0.];
+    breaks.extend(xs.iter().map(|x| x - line_pitch / 2.));
+    breaks.extend([xs[lines - 1] + line_pitch / 2., length]);
+
+    let mut network = Network::default();
+    let add_node = |network: &mut Network, position: Point, hole: Option<&PortHole>| {
+        let id = network.next_node_id();
+        network.nodes.push(Node {
+            port: hole.cloned(),
+            ..Node::at(id, position)
+        });
+        id
+    };
+    let add_channel = |network: &mut Network, a, b, shape, path, layer| {
+        let id = network.next_channel_id();
+        network.channels.push(Channel {
+            id,
+            node_a: a,
+            node_b: b,
+            shape,
+            path: Some(path),
+            length: None,
+            layer,
+            metadata: Metadata::new(),
+        });
+        id
+    };
+
+    let inlet = add_node(&mut network, Point([0., 0.]), Some(&port));
+    let (mut outlets, mut segments) = (Vec::new(), Vec::new());
+    let mut manifold = inlet;
+    for i in 0..flow_channels {
+        let y = i as f64 * pitch;
+        if i > 0 {
+            let start = network.node_position(manifold).unwrap();
+            let next = add_node(&mut network, Point([0., y]), None);
+            add_channel(
+                &mut network,
+                manifold,
+                next,
+                flow,
+                segment(start, Point([0., y])),
+                0,
+            );
+            manifold = next;
+        }
+        let mut previous = manifold;
+        let mut channels = Vec::new();
+        for (j, x) in breaks.iter().enumerate().skip(1) {
+            let hole = (j == breaks.len() - 1).then_some(&port);
+            let node = add_node(&mut network, Point([*x, y]), hole);
+            let path = segment(Point([breaks[j - 1], y]), Point([*x, y]));
+            channels.push(add_channel(&mut network, previous, node, flow, path, 0));
+            previous = node;
+        }
+        outlets.push(previous);
+        segments.push(channels);
+    }
+
+    let template = valve_template();
+    let bindings = Bindings::from([("across".into(), across), ("along".into(), along)]);
+    let top = (flow_channels - 1) as f64 * pitch;
+    let mut control_lines = Vec::new();
+    for (k, x) in xs.iter().enumerate() {
+        let (bit, value) = (k / 2, k % 2 == 1);
+        let port_position = Point([*x, top + control.lead_length]);
+        let end_position = Point([*x, -pitch / 2.]);
+        let port = add_node(&mut network, port_position, Some(&control.port));
+        let end = add_node(&mut network, end_position, None);
+        let path = segment(port_position, end_position);
+        let channel = add_channel(&mut network, port, end, control.shape, path, control.layer);
+
+        let mut valves = Vec::new();
+        for i in (0..flow_channels).filter(|i| ((i >> bit) & 1 == 1) == value) {
+            let center = Point([*x, i as f64 * pitch]);
+            let corner = center + Vector2([-along / 2., -across / 2.]);
+            let valve = template
+                .instantiate(&mut network, corner, &bindings)
+                .unwrap();
+            let module = network.modules.iter_mut().find(|m| m.id == valve).unwrap();
+            module.kind = ModuleKind::Valve;
+            network.add_membrane(
+                center,
+                Vector2([1., 0.]),
+                control.membrane,
+                [0, control.layer],
+            );
+            valves.push(valve);
+        }
+        control_lines.push(MultiplexerLine {
+            bit,
+            value,
+            channel,
+            port,
+            valves,
+        });
+    }
+
+    Ok(Multiplexer {
+        network,
+        inlet,
+        outlets,
+        flow_channels: segments,
+        control_lines,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{channel::RectangularShape, port::ConnectorType},
+        simulation::{
+            fluid::Fluid,
+            scenario::{evaluate_valve_states, ValveStates},
+            solver::{Boundary, BoundaryCondition},
+        },
+    };
+
+    #[test]
+    fn addresses_each_channel() {
+        assert_eq!(
+            (1..=9).map(address_bits).collect::<Vec<_>>(),
+            [0, 1, 2, 2, 3, 3, 3, 3, 4]
+        );
+        let shape = Shape::Rectangular(RectangularShape {
+            width: 100.,
+            height: 25.,
+        });
+        let port = PortHole {
+            diameter: 750.,
+            connector: ConnectorType::PressFit,
+        };
+        let mut parameters = MultiplexerParameters {
+            flow_channels: 5,
+            flow: shape,
+            pitch: 500.,
+            length: 6000.,
+            line_pitch: 400.,
+            port: port.clone(),
+            control: ControlLayerConfig {
+                layer: 1,
+                shape,
+                membrane: Dimensions([300., 200.]),
+                lead_length: 1500.,
+                port,
+            },
+        };
+        let multiplexer = design_multiplexer(parameters.clone()).unwrap();
+        assert_eq!(multiplexer.control_lines.len(), 6);
+        assert_eq!(multiplexer.network.modules.len(), 5 * 3);
+        assert_eq!(multiplexer.network.markings.len(), 2 * 5 * 3);
+        assert_eq!(multiplexer.control_lines[5].valves.len(), 1);
+
+        let mut boundaries = vec![Boundary {
+            node: multiplexer.inlet,
+            condition: BoundaryCondition::Pressure(1e4),
+        }];
+        boundaries.extend(multiplexer.outlets.iter().map(|node| Boundary {
+            node: *node,
+            condition: BoundaryCondition::Pressure(0.),
+        }));
+        let states = ValveStates::Sequence((0..5).map(|i| multiplexer.address(i)).collect());
+        let results =
+            evaluate_valve_states(&multiplexer.network, &Fluid::water(), &boundaries, &states)
+                .unwrap();
+        for (i, result) in results.iter().enumerate() {
+            let flows = &result.solution.as_ref().unwrap().flows;
+            for (j, channels) in multiplexer.flow_channels.iter().enumerate() {
+                let q = flows[channels.last().unwrap()];
+                assert_eq!(q > 0., i == j, "{i} {j}");
+            }
+        }
+
+        parameters.length = 2000.;
+        assert_eq!(
+            design_multiplexer(parameters),
+            Err(MultiplexerError::TooShort {
+                length: 2000.,
+                minimum: 2400.
+            })
+        );
+    }
+}
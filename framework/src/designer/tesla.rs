@@ -0,0 +1,313 @@
+//! Chains of Tesla valves, fixed-geometry fluidic diodes. Every stage adds a loop beside the
+//! main channel. In forward direction the loop branches off against the flow and receives
+//! little of it; in reverse direction it takes the flow smoothly and returns it against the
+//! main stream, where the colliding streams dissipate the inertial pressure.
+//!
+//! The diodicity, the ratio of reverse to forward pressure drop at equal flow, is estimated
+//! with a lumped model: the viscous split between loop and main channel sets the loop's share
+//! of the flow, and the merging streams lose the kinetic energy of their relative velocity.
+//! Since the split ignores inertia the estimate is conservative at higher Reynolds numbers.
+
+use crate::{
+    base::{
+        channel::{Arc, Channel, ChannelPath, LineSegment, PathPiece, RectangularShape, Shape},
+        network::{Metadata, Network, Node, NodeId},
+        primitives::Point,
+    },
+    simulation::{fluid::Fluid, resistance::resistance_for_length},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{FRAC_PI_2, PI};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Dimensions of a Tesla valve chain
+pub struct TeslaValveParameters {
+    /// Number of loops, alternating between both sides of the main channel
+    pub stages: usize,
+
+    /// Width of main channel and loops
+    pub width: f64,
+
+    pub height: f64,
+
+    /// Angle in radians between the main channel and the loop where the loop branches off in
+    /// reverse direction, in (0, π/2)
+    pub angle: f64,
+
+    /// Flow rate at which to estimate the diodicity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flow: Option<f64>,
+
+    /// Fluid for the estimate, water if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fluid: Option<Fluid>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Generated valve chain; forward flow runs from the inlet in positive x
+pub struct TeslaValve {
+    pub network: Network,
+    pub inlet: NodeId,
+    pub outlet: NodeId,
+
+    /// Loop channel ids in flow order
+    pub loops: Vec<usize>,
+
+    /// Estimated diodicity at the given flow rate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diodicity: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Reasons a Tesla valve chain can't be generated
+pub enum TeslaValveError {
+    /// The angle isn't in (0, π/2)
+    InvalidAngle(f64),
+}
+
+impl std::fmt::Display for TeslaValveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TeslaValveError::InvalidAngle(angle) => {
+                write!(f, "stage angle {angle} must be between 0 and π/2")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TeslaValveError {}
+
+/// Lengths of one stage: the main channel between the junctions and the loop
+struct Stage {
+    main: f64,
+    radius: f64,
+    outgoing: f64,
+    returning: f64,
+}
+
+impl Stage {
+    fn new(width: f64, angle: f64) -> Stage {
+        // The loop turns around with a centerline radius of one width and returns parallel to
+        // its outgoing leg, reaching two widths away from the main channel
+        let radius = width;
+        let (sin, cos) = angle.sin_cos();
+        Stage {
+            main: 2. * radius / sin,
+            radius,
+            outgoing: (2. * width + 2. * radius * cos) / sin,
+            returning: 2. * width / sin,
+        }
+    }
+
+    fn loop_length(&self) -> f64 {
+        self.outgoing + PI * self.radius + self.returning
+    }
+
+    /// Loop pieces in reverse flow direction, branching off at the origin at the angle,
+    /// turning clockwise, and returning to the main channel at (main, 0)
+    fn loop_pieces(&self, angle: f64) -> Vec<PathPiece> {
+        let (sin, cos) = angle.sin_cos();
+        let turn = Point([self.outgoing * cos, self.outgoing * sin]);
+        let center = Point([turn.0[0] + self.radius * sin, turn.0[1] - self.radius * cos]);
+        let back = Point([
+            turn.0[0] + 2. * self.radius * sin,
+            turn.0[1] - 2. * self.radius * cos,
+        ]);
+        vec![
+            PathPiece::LineSegment(LineSegment {
+                start: Point([0., 0.]),
+                end: turn,
+            }),
+            PathPiece::Arc(Arc {
+                right: true,
+                start: turn,
+                end: back,
+                center,
+            }),
+            PathPiece::LineSegment(LineSegment {
+                start: back,
+                end: Point([self.main, 0.]),
+            }),
+        ]
+    }
+}
+
+fn line(start: Point, end: Point) -> ChannelPath {
+    let mut path = ChannelPath::new();
+    path.add(PathPiece::LineSegment(LineSegment { start, end }));
+    path
+}
+
+/// Estimated ratio of reverse to forward pressure drop at the flow rate
+pub fn diodicity(parameters: &TeslaValveParameters, fluid: &Fluid, flow: f64) -> f64 {
+    let stage = Stage::new(parameters.width, parameters.angle);
+    let shape = Shape::Rectangular(RectangularShape {
+        width: parameters.width,
+        height: parameters.height,
+    });
+    let main = resistance_for_length(&shape, fluid.viscosity, stage.main);
+    let loop_resistance = resistance_for_length(&shape, fluid.viscosity, stage.loop_length());
+    let share = main / (main + loop_resistance);
+    let viscous = main * loop_resistance / (main + loop_resistance) * flow.abs();
+
+    // Kinetic energy of the relative velocity of the loop's stream, merging at the angle
+    let area = shape.area();
+    let (jet, stream) = (share * flow.abs() / area, (1. - share) * flow.abs() / area);
+    let collision = |angle: f64| {
+        let relative = jet * jet + stream * stream - 2. * jet * stream * angle.cos();
+        0.5 * fluid.density * share * relative
+    };
+    (viscous + collision(PI - parameters.angle)) / (viscous + collision(parameters.angle))
+}
+
+/// Generates the valve chain with straight leads of two widths at both ends
+pub fn design_tesla_valve(parameters: TeslaValveParameters) -> Result<TeslaValve, TeslaValveError> {
+    let TeslaValveParameters {
+        stages,
+        width,
+        height,
+        angle,
+        flow,
+        fluid,
+    } = parameters;
+    if !(angle > 0. && angle < FRAC_PI_2) {
+        return Err(TeslaValveError::InvalidAngle(angle));
+    }
+    let stage = Stage::new(width, angle);
+    let shape = Shape::Rectangular(RectangularShape { width, height });
+    let lead = 2. * width;
+
+    let mut network = Network::default();
+    let add_node = |network: &mut Network, x: f64| {
+        let id = NodeId(network.nodes.len());
+        network.nodes.push(Node::at(id, Point([x, 0.])));
+        id
+    };
+    let add_channel = |network: &mut Network, a: NodeId, b: NodeId, path: ChannelPath| {
+        let id = network.channels.len();
+        network.channels.push(Channel {
+            id,
+            node_a: a,
+            node_b: b,
+            shape,
+            path: Some(path),
+            length: None,
+            layer: 0,
+            metadata: Metadata::new(),
+        });
+        id
+    };
+    let inlet = add_node(&mut network, 0.);
+    let mut previous = inlet;
+    let mut loops = Vec::with_capacity(stages);
+    for k in 0..stages {
+        let x = lead + k as f64 * (stage.main + lead);
+        let (upstream, downstream) = (
+            add_node(&mut network, x),
+            add_node(&mut network, x + stage.main),
+        );
+        add_channel(
+            &mut network,
+            previous,
+            upstream,
+            line(Point([x - lead, 0.]), Point([x, 0.])),
+        );
+        add_channel(
+            &mut network,
+            upstream,
+            downstream,
+            line(Point([x, 0.]), Point([x + stage.main, 0.])),
+        );
+
+        // The loop is built for reverse flow and mirrored onto the chain, alternating sides
+        let side = if k % 2 == 0 { 1. } else { -1. };
+        let place = |Point([px, py]): Point| Point([x + stage.main - px, side * py]);
+        let mut path = ChannelPath::new();
+        for piece in stage.loop_pieces(angle) {
+            path.add(match piece {
+                PathPiece::LineSegment(l) => PathPiece::LineSegment(LineSegment {
+                    start: place(l.start),
+                    end: place(l.end),
+                }),
+                PathPiece::Arc(a) => PathPiece::Arc(Arc {
+                    right: side < 0.,
+                    start: place(a.start),
+                    end: place(a.end),
+                    center: place(a.center),
+                }),
+            });
+        }
+        loops.push(add_channel(&mut network, downstream, upstream, path));
+        previous = downstream;
+    }
+    let end = network.node_position(previous).unwrap();
+    let outlet = add_node(&mut network, end.0[0] + lead);
+    add_channel(
+        &mut network,
+        previous,
+        outlet,
+        line(end, Point([end.0[0] + lead, 0.])),
+    );
+
+    let diodicity =
+        flow.map(|flow| diodicity(&parameters, &fluid.unwrap_or_else(Fluid::water), flow));
+    Ok(TeslaValve {
+        network,
+        inlet,
+        outlet,
+        loops,
+        diodicity,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn chain() {
+        let parameters = TeslaValveParameters {
+            stages: 3,
+            width: 100e-6,
+            height: 100e-6,
+            angle: PI / 4.,
+            flow: Some(1e-7),
+            fluid: None,
+        };
+        let valve = design_tesla_valve(parameters).unwrap();
+        assert_eq!(valve.loops.len(), 3);
+        assert_eq!(valve.network.channels.len(), 3 * 3 + 1);
+        for channel in &valve.network.channels {
+            let path = channel.path.as_ref().unwrap();
+            assert_eq!(path.check_invariants(), Ok(()));
+            let ends = [path.pieces[0].start(), path.pieces.last().unwrap().end()];
+            let nodes = [channel.node_a, channel.node_b].map(|n| valve.network.node_position(n));
+            for (end, node) in ends.iter().zip(nodes) {
+                assert!(end.approx_eq(&node.unwrap(), &Default::default()));
+            }
+        }
+        // The second loop lies below the main channel
+        let second = valve.network.channels[valve.loops[1]]
+            .path
+            .as_ref()
+            .unwrap();
+        assert!(second.bounding_box().unwrap().max.0[1] <= 1e-12);
+
+        let water = Fluid::water();
+        assert!((diodicity(&parameters, &water, 1e-15) - 1.).abs() < 1e-6);
+        let estimate = valve.diodicity.unwrap();
+        assert!(estimate > 1. && diodicity(&parameters, &water, 1e-6) > estimate);
+
+        assert_eq!(
+            design_tesla_valve(TeslaValveParameters {
+                angle: 2.,
+                ..parameters
+            }),
+            Err(TeslaValveError::InvalidAngle(2.))
+        );
+    }
+}
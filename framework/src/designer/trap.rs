@@ -0,0 +1,255 @@
+//! Hydrodynamic trap arrays after Tan and Takeuchi (2007): every unit splits the flow into a
+//! short trap channel ending in a constriction and a long bypass loop. While a trap is empty
+//! the bypass resistance is larger, so a particle follows the trap path and gets caught; the
+//! caught particle plugs the constriction and diverts the following ones to the next unit.
+//!
+//! Units are chained along rows, each row between its own inlet and outlet node.
+
+use crate::{
+    base::{
+        channel::{Channel, ChannelPath, LineSegment, PathPiece, RectangularShape, Shape},
+        network::{Metadata, Network, Node, NodeId},
+        primitives::Point,
+    },
+    simulation::resistance::{length_for_resistance, resistance_for_length},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Dimensions of a trap array
+pub struct TrapArrayParameters {
+    pub rows: usize,
+    pub columns: usize,
+
+    /// Cross-section of the trap constriction, narrower than the trapped particles
+    pub trap: RectangularShape,
+
+    /// Length of the trap constriction
+    pub trap_length: f64,
+
+    /// Cross-section of the bypass loops and connecting channels
+    pub channel: RectangularShape,
+
+    /// Resistance of a bypass loop over the one of its trap channel, above 1 for capture
+    pub bypass_ratio: f64,
+
+    /// Clearance between neighboring channels
+    pub spacing: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Generated trap array with its layout
+pub struct TrapArray {
+    pub network: Network,
+
+    /// Inlet node per row
+    pub inlets: Vec<NodeId>,
+
+    /// Outlet node per row
+    pub outlets: Vec<NodeId>,
+
+    /// Trap channel ids per row, in flow order
+    pub traps: Vec<Vec<usize>>,
+
+    /// Bypass channel ids per row, in flow order
+    pub bypasses: Vec<Vec<usize>>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Reasons a trap array can't be generated
+pub enum TrapArrayError {
+    /// Bypass ratios of at most 1 send particles past empty traps
+    NoCapture { bypass_ratio: f64 },
+
+    /// The bypass length for the ratio is too short to loop around the trap
+    BypassTooShort { length: f64, minimum: f64 },
+}
+
+impl std::fmt::Display for TrapArrayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TrapArrayError::NoCapture { bypass_ratio } => {
+                write!(f, "bypass ratio {bypass_ratio} must be above 1 to capture")
+            }
+            TrapArrayError::BypassTooShort { length, minimum } => write!(
+                f,
+                "bypass length {length} is shorter than the {minimum} needed to loop around the trap"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TrapArrayError {}
+
+fn polyline(points: &[Point]) -> ChannelPath {
+    let mut path = ChannelPath::new();
+    for pair in points.windows(2) {
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: pair[0],
+            end: pair[1],
+        }));
+    }
+    path
+}
+
+/// Length of the bypass loops, whose resistance is the ratio times the trap's
+pub fn bypass_length(parameters: &TrapArrayParameters) -> f64 {
+    // The viscosity cancels out in the ratio
+    let trap = resistance_for_length(
+        &Shape::Rectangular(parameters.trap),
+        1.,
+        parameters.trap_length,
+    );
+    length_for_resistance(
+        &Shape::Rectangular(parameters.channel),
+        1.,
+        parameters.bypass_ratio * trap,
+    )
+}
+
+/// Generates the network and layout; rows stack upwards, flow runs in positive x
+pub fn design_trap_array(parameters: TrapArrayParameters) -> Result<TrapArray, TrapArrayError> {
+    let TrapArrayParameters {
+        rows,
+        columns,
+        trap,
+        trap_length,
+        channel,
+        bypass_ratio,
+        spacing,
+    } = parameters;
+    if bypass_ratio <= 1. {
+        return Err(TrapArrayError::NoCapture { bypass_ratio });
+    }
+    // The loop runs up, along the trap, and down again
+    let length = bypass_length(&parameters);
+    let clearance = spacing + (channel.width + trap.width) / 2.;
+    let minimum = trap_length + 2. * clearance;
+    if length < minimum {
+        return Err(TrapArrayError::BypassTooShort { length, minimum });
+    }
+    let rise = (length - trap_length) / 2.;
+    let pitch = Point([trap_length + spacing + channel.width, rise + clearance]);
+
+    let mut array = TrapArray {
+        network: Network::default(),
+        inlets: Vec::new(),
+        outlets: Vec::new(),
+        traps: Vec::new(),
+        bypasses: Vec::new(),
+    };
+    let network = &mut array.network;
+    let add_node = |network: &mut Network, position: Point| {
+        let id = network.next_node_id();
+        network.nodes.push(Node::at(id, position));
+        id
+    };
+    let add_channel = |network: &mut Network, a, b, shape, path: ChannelPath| {
+        let id = network.next_channel_id();
+        network.channels.push(Channel {
+            id,
+            node_a: a,
+            node_b: b,
+            shape: Shape::Rectangular(shape),
+            path: Some(path),
+            length: None,
+            layer: 0,
+            metadata: Metadata::new(),
+        });
+        id
+    };
+    for row in 0..rows {
+        let y = row as f64 * pitch.0[1];
+        let (mut traps, mut bypasses) = (Vec::new(), Vec::new());
+        let mut previous = add_node(network, Point([0., y]));
+        array.inlets.push(previous);
+        for column in 0..columns {
+            let x = spacing + column as f64 * pitch.0[0];
+            let (start, end) = (Point([x, y]), Point([x + trap_length, y]));
+            let (a, b) = (add_node(network, start), add_node(network, end));
+            let lead = polyline(&[network.node_position(previous).unwrap(), start]);
+            add_channel(network, previous, a, channel, lead);
+            traps.push(add_channel(network, a, b, trap, polyline(&[start, end])));
+            let corners = [Point([x, y + rise]), Point([x + trap_length, y + rise])];
+            let bypass = polyline(&[start, corners[0], corners[1], end]);
+            bypasses.push(add_channel(network, a, b, channel, bypass));
+            previous = b;
+        }
+        let end = network.node_position(previous).unwrap();
+        let outlet = add_node(network, Point([end.0[0] + spacing, y]));
+        add_channel(
+            network,
+            previous,
+            outlet,
+            channel,
+            polyline(&[end, Point([end.0[0] + spacing, y])]),
+        );
+        array.outlets.push(outlet);
+        array.traps.push(traps);
+        array.bypasses.push(bypasses);
+    }
+    Ok(array)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulation::{
+        fluid::Fluid,
+        solver::{solve, Boundary, BoundaryCondition},
+    };
+
+    #[test]
+    fn capture_ratio() {
+        let parameters = TrapArrayParameters {
+            rows: 2,
+            columns: 3,
+            trap: RectangularShape {
+                width: 5e-6,
+                height: 20e-6,
+            },
+            trap_length: 50e-6,
+            channel: RectangularShape {
+                width: 30e-6,
+                height: 20e-6,
+            },
+            bypass_ratio: 2.,
+            spacing: 20e-6,
+        };
+        let array = design_trap_array(parameters).unwrap();
+        assert_eq!(array.network.nodes.len(), 2 * (2 + 6));
+        assert_eq!(array.traps[1].len(), 3);
+        for channel in &array.network.channels {
+            assert_eq!(channel.path.as_ref().unwrap().check_invariants(), Ok(()));
+        }
+
+        // Empty traps take twice the flow of their bypass
+        let pressure = |node, p| Boundary {
+            node,
+            condition: BoundaryCondition::Pressure(p),
+        };
+        let boundaries: Vec<Boundary> = (0..2)
+            .flat_map(|r| {
+                [
+                    pressure(array.inlets[r], 100.),
+                    pressure(array.outlets[r], 0.),
+                ]
+            })
+            .collect();
+        let solution = solve(&array.network, &Fluid::water(), &boundaries).unwrap();
+        let ratio = solution.flows[&array.traps[0][1]] / solution.flows[&array.bypasses[0][1]];
+        assert!((ratio - 2.).abs() < 1e-9, "{ratio}");
+
+        assert_eq!(
+            design_trap_array(TrapArrayParameters {
+                bypass_ratio: 1.,
+                ..parameters
+            }),
+            Err(TrapArrayError::NoCapture { bypass_ratio: 1. })
+        );
+    }
+}
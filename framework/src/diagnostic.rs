@@ -0,0 +1,295 @@
+//! Uniform reporting of findings from validation, design rule checks, and the solver. The text
+//! rendering doesn't depend on the environment: numbers are printed by Rust's formatting, which
+//! ignores the locale, and diagnostics are ordered by severity, then entity.
+
+use crate::{
+    analysis::regime::{RegimeIssue, RegimeWarning},
+    base::{
+        interop::InteropIssue,
+        keepout::KeepOutViolation,
+        layers::LayerIssue,
+        network::{EntityRef, NodeId},
+        primitives::{Dimensions, Point},
+    },
+    interfaces::validation::ValidationError,
+    simulation::SimulationError,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+/// Importance of a diagnostic, most severe first
+pub enum Severity {
+    /// The design is invalid or can't be processed
+    Error,
+
+    /// The design works, but likely not as intended
+    Warning,
+
+    /// Information without need for action
+    Note,
+}
+
+impl Severity {
+    fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Where a diagnostic applies, beyond the entity
+pub enum Location {
+    /// Position in the layout
+    Point(Point),
+
+    /// JSON pointer into the input
+    Pointer(String),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Single finding about a network
+pub struct Diagnostic {
+    pub severity: Severity,
+
+    /// Entity the finding is about, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity: Option<EntityRef>,
+
+    pub message: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity,
+            entity: None,
+            message: message.into(),
+            location: None,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Diagnostic::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Diagnostic::new(Severity::Warning, message)
+    }
+
+    pub fn note(message: impl Into<String>) -> Self {
+        Diagnostic::new(Severity::Note, message)
+    }
+
+    /// The diagnostic, referring to the entity
+    pub fn on(self, entity: EntityRef) -> Self {
+        Diagnostic {
+            entity: Some(entity),
+            ..self
+        }
+    }
+
+    /// The diagnostic, located at the given place
+    pub fn at(self, location: Location) -> Self {
+        Diagnostic {
+            location: Some(location),
+            ..self
+        }
+    }
+}
+
+fn entity_name(entity: &EntityRef) -> String {
+    match entity {
+        EntityRef::Node(NodeId(id)) => format!("node {id}"),
+        EntityRef::Channel(id) => format!("channel {id}"),
+        EntityRef::Module(id) => format!("module {id}"),
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.severity.name())?;
+        if let Some(entity) = &self.entity {
+            write!(f, "[{}]", entity_name(entity))?;
+        }
+        write!(f, ": {}", self.message)?;
+        match &self.location {
+            Some(Location::Point(Point([x, y]))) => write!(f, " at ({x}, {y})"),
+            Some(Location::Pointer(pointer)) => write!(f, " at {pointer}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Diagnostics sorted by severity, then entity, keeping the order of equal ones
+pub fn sorted(diagnostics: &[Diagnostic]) -> Vec<Diagnostic> {
+    let mut sorted = diagnostics.to_vec();
+    sorted.sort_by_key(|d| (d.severity, d.entity));
+    sorted
+}
+
+/// One line per diagnostic, followed by a summary of the counts
+pub fn render_text(diagnostics: &[Diagnostic]) -> String {
+    let mut text = String::new();
+    for diagnostic in sorted(diagnostics) {
+        text += &format!("{diagnostic}\n");
+    }
+    let count = |severity| {
+        diagnostics
+            .iter()
+            .filter(|d| d.severity == severity)
+            .count()
+    };
+    let counts: Vec<String> = [Severity::Error, Severity::Warning, Severity::Note]
+        .into_iter()
+        .map(|severity| {
+            let n = count(severity);
+            let plural = if n == 1 { "" } else { "s" };
+            format!("{n} {}{plural}", severity.name())
+        })
+        .collect();
+    text + &counts.join(", ")
+}
+
+/// Sorted diagnostics as a JSON array
+pub fn render_json(diagnostics: &[Diagnostic]) -> String {
+    serde_json::to_string_pretty(&sorted(diagnostics)).unwrap()
+}
+
+impl From<&ValidationError> for Diagnostic {
+    fn from(error: &ValidationError) -> Self {
+        let pointer = match error.pointer.is_empty() {
+            true => "/".into(),
+            false => error.pointer.clone(),
+        };
+        Diagnostic::error(error.message.clone()).at(Location::Pointer(pointer))
+    }
+}
+
+impl From<&SimulationError> for Diagnostic {
+    fn from(error: &SimulationError) -> Self {
+        let diagnostic = Diagnostic::error(error.to_string());
+        match error {
+            SimulationError::MissingLength(id)
+            | SimulationError::UnknownChannel(id)
+            | SimulationError::InvalidTolerance(id) => diagnostic.on(EntityRef::Channel(*id)),
+            SimulationError::UnknownNode(id) => diagnostic.on(EntityRef::Node(*id)),
+            SimulationError::InvalidPortModel(id) => diagnostic.on(EntityRef::Module(*id)),
+            _ => diagnostic,
+        }
+    }
+}
+
+impl From<&KeepOutViolation> for Diagnostic {
+    fn from(violation: &KeepOutViolation) -> Self {
+        Diagnostic::error(format!("overlaps keep-out region {}", violation.keep_out))
+            .on(violation.entity)
+    }
+}
+
+impl From<&LayerIssue> for Diagnostic {
+    fn from(issue: &LayerIssue) -> Self {
+        match *issue {
+            LayerIssue::Unmapped { channel, layer } => {
+                Diagnostic::error(format!("no physical layer holds network layer {layer}"))
+                    .on(EntityRef::Channel(channel))
+            }
+            LayerIssue::TooDeep {
+                channel,
+                depth,
+                thickness,
+            } => Diagnostic::error(format!(
+                "depth {depth} exceeds the layer thickness {thickness}"
+            ))
+            .on(EntityRef::Channel(channel)),
+        }
+    }
+}
+
+impl From<&InteropIssue> for Diagnostic {
+    fn from(issue: &InteropIssue) -> Self {
+        match *issue {
+            InteropIssue::NonStandardOutline(Dimensions([w, h])) => {
+                Diagnostic::error(format!("outline {w} x {h} isn't a standard footprint"))
+            }
+            InteropIssue::UnplacedPort(node) => {
+                Diagnostic::error("port has no position").on(EntityRef::Node(node))
+            }
+            InteropIssue::EdgeClearance { node, distance } => {
+                Diagnostic::error(format!("port is {distance} from the outline's edge"))
+                    .on(EntityRef::Node(node))
+            }
+            InteropIssue::OffGrid { node, deviation } => {
+                Diagnostic::error(format!("port is {deviation} off the grid"))
+                    .on(EntityRef::Node(node))
+            }
+            InteropIssue::NoFreePosition(node) => {
+                Diagnostic::error("no free grid position for the port").on(EntityRef::Node(node))
+            }
+            InteropIssue::Cancelled => Diagnostic::error("port placement was cancelled"),
+        }
+    }
+}
+
+impl From<&RegimeWarning> for Diagnostic {
+    fn from(warning: &RegimeWarning) -> Self {
+        let message = match warning.issue {
+            RegimeIssue::Turbulent { reynolds } => {
+                format!("Reynolds number {reynolds} exceeds the laminar limit")
+            }
+            RegimeIssue::DevelopingFlow {
+                entrance_length,
+                length,
+            } => format!("entrance length {entrance_length} is significant for length {length}"),
+            RegimeIssue::HighPeclet { peclet } => format!("high Péclet number {peclet}"),
+            RegimeIssue::HighCapillary { capillary } => {
+                format!("high capillary number {capillary}")
+            }
+        };
+        Diagnostic::warning(message).on(EntityRef::Channel(warning.channel))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rendering() {
+        let diagnostics = vec![
+            Diagnostic::from(&RegimeWarning {
+                channel: 2,
+                issue: RegimeIssue::Turbulent { reynolds: 2500. },
+            }),
+            Diagnostic::from(&ValidationError {
+                pointer: "/channels/0/width".into(),
+                message: "must be > 0".into(),
+            }),
+            Diagnostic::from(&SimulationError::MissingLength(1)),
+            Diagnostic::note("placed 3 ports").at(Location::Point(Point([1.5, 0.]))),
+        ];
+        assert_eq!(
+            render_text(&diagnostics),
+            "error: must be > 0 at /channels/0/width\n\
+             error[channel 1]: channel 1 has no length\n\
+             warning[channel 2]: Reynolds number 2500 exceeds the laminar limit\n\
+             note: placed 3 ports at (1.5, 0)\n\
+             2 errors, 1 warning, 1 note"
+        );
+
+        let parsed: Vec<Diagnostic> = serde_json::from_str(&render_json(&diagnostics)).unwrap();
+        assert_eq!(parsed, sorted(&diagnostics));
+        assert_eq!(parsed[1].entity, Some(EntityRef::Channel(1)));
+    }
+}
@@ -0,0 +1,133 @@
+//! Digital microfluidics (DMF): droplets moved across a grid of individually actuated electrodes.
+//! The representation is independent of the channel network.
+
+pub mod routing;
+
+use crate::base::{network::Metadata, primitives::Point};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+/// Electrode as [column, row] of the grid
+pub struct Cell(pub [usize; 2]);
+
+impl Cell {
+    /// Chebyshev distance; droplets closer than two cells merge
+    pub fn distance(&self, other: &Cell) -> usize {
+        let [a, b] = self.0;
+        let [c, d] = other.0;
+        a.abs_diff(c).max(b.abs_diff(d))
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Rectangular array of square electrodes
+pub struct ElectrodeGrid {
+    /// Number of electrodes along x
+    pub columns: usize,
+
+    /// Number of electrodes along y
+    pub rows: usize,
+
+    /// Center-to-center distance of neighboring electrodes
+    pub pitch: f64,
+
+    /// Missing or defective electrodes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked: Vec<Cell>,
+}
+
+impl ElectrodeGrid {
+    /// Whether the cell lies on the grid and has a working electrode
+    pub fn contains(&self, cell: &Cell) -> bool {
+        let [column, row] = cell.0;
+        column < self.columns && row < self.rows && !self.blocked.contains(cell)
+    }
+
+    /// Center of the electrode in layout coordinates, the grid starts at the origin
+    pub fn center(&self, cell: &Cell) -> Point {
+        let [column, row] = cell.0;
+        Point([
+            (column as f64 + 0.5) * self.pitch,
+            (row as f64 + 0.5) * self.pitch,
+        ])
+    }
+
+    /// Usable electrodes a droplet can move to in one step
+    pub fn neighbors(&self, cell: &Cell) -> Vec<Cell> {
+        let [column, row] = cell.0;
+        let mut neighbors = Vec::with_capacity(4);
+        if column > 0 {
+            neighbors.push(Cell([column - 1, row]));
+        }
+        if row > 0 {
+            neighbors.push(Cell([column, row - 1]));
+        }
+        neighbors.push(Cell([column + 1, row]));
+        neighbors.push(Cell([column, row + 1]));
+        neighbors.retain(|c| self.contains(c));
+        neighbors
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Droplet resting on an electrode
+pub struct Droplet {
+    /// Unique id of the droplet
+    pub id: usize,
+
+    /// Electrode the droplet is centered on
+    pub position: Cell,
+
+    /// Volume of the droplet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Electrodes switched on during one time step
+pub struct ActuationStep {
+    pub electrodes: Vec<Cell>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Digital microfluidic chip: electrode grid, initial droplets, and actuation sequence
+pub struct DmfChip {
+    pub grid: ElectrodeGrid,
+    pub droplets: Vec<Droplet>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sequence: Vec<ActuationStep>,
+    #[serde(default, skip_serializing_if = "Metadata::is_empty")]
+    pub metadata: Metadata,
+}
+
+impl DmfChip {
+    /// Droplet positions after the given number of actuation steps. A droplet follows an active
+    /// electrode under or next to it and stays in place otherwise.
+    pub fn positions_at(&self, step: usize) -> Vec<Droplet> {
+        let mut droplets = self.droplets.clone();
+        for actuation in self.sequence.iter().take(step) {
+            for droplet in &mut droplets {
+                let active = |cell: &Cell| actuation.electrodes.contains(cell);
+                if !active(&droplet.position) {
+                    if let Some(next) = self
+                        .grid
+                        .neighbors(&droplet.position)
+                        .into_iter()
+                        .find(active)
+                    {
+                        droplet.position = next;
+                    }
+                }
+            }
+        }
+        droplets
+    }
+}
@@ -0,0 +1,322 @@
+//! Prioritized droplet routing. Droplets are routed one after another in space and time, each
+//! avoiding the trajectories of those routed before it and the start electrodes of those not
+//! routed yet under the static and dynamic fluidic constraints: droplets never occupy adjacent
+//! electrodes, neither within a time step nor across consecutive ones.
+
+use super::{ActuationStep, Cell, DmfChip};
+use crate::progress::ProgressHandle;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Electrode a droplet has to reach
+pub struct RouteRequest {
+    /// Id of the droplet
+    pub droplet: usize,
+
+    /// Destination electrode
+    pub target: Cell,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Routing failure
+pub enum RoutingError {
+    /// The request references a droplet that isn't on the chip
+    UnknownDroplet(usize),
+
+    /// A droplet or target is outside the grid or on a blocked electrode
+    InvalidCell(Cell),
+
+    /// Two droplets start too close to each other and would merge
+    Conflict { a: usize, b: usize },
+
+    /// No collision-free route within the step limit exists given the earlier routes and the
+    /// droplets waiting on their start
+    Unreachable { droplet: usize },
+
+    /// Routing was cancelled through its progress handle
+    Cancelled,
+}
+
+impl std::fmt::Display for RoutingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoutingError::UnknownDroplet(id) => write!(f, "droplet {id} does not exist"),
+            RoutingError::InvalidCell(Cell([column, row])) => {
+                write!(f, "electrode [{column}, {row}] is not usable")
+            }
+            RoutingError::Conflict { a, b } => {
+                write!(f, "droplets {a} and {b} are adjacent")
+            }
+            RoutingError::Unreachable { droplet } => {
+                write!(f, "no route found for droplet {droplet}")
+            }
+            RoutingError::Cancelled => write!(f, "routing cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for RoutingError {}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Position of every droplet at every time step
+pub struct Schedule {
+    /// Trajectory per droplet id, all of the same length
+    pub trajectories: BTreeMap<usize, Vec<Cell>>,
+}
+
+impl Schedule {
+    /// Number of time steps including the initial state
+    pub fn len(&self) -> usize {
+        self.trajectories.values().map(Vec::len).max().unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Electrodes to switch on for each move; the electrode under a droplet holds it in place
+    pub fn actuations(&self) -> Vec<ActuationStep> {
+        (1..self.len())
+            .map(|t| ActuationStep {
+                electrodes: self.trajectories.values().map(|cells| cells[t]).collect(),
+            })
+            .collect()
+    }
+}
+
+/// Position of a routed droplet at a time, it rests on its target after arriving
+fn at(trajectory: &[Cell], time: usize) -> Cell {
+    trajectory[time.min(trajectory.len() - 1)]
+}
+
+/// Whether a droplet may occupy the cell at the time, given the neighboring steps of the others
+fn is_free(others: &[&[Cell]], cell: Cell, time: usize) -> bool {
+    others
+        .iter()
+        .all(|other| (time.saturating_sub(1)..=time + 1).all(|t| cell.distance(&at(other, t)) > 1))
+}
+
+/// Routes the requested droplets in order of the requests, other droplets stay in place. Droplets
+/// requested later are obstacles on their start electrodes while the earlier ones are routed,
+/// so droplets can't swap places. At most `max_steps` time steps are used.
+pub fn route(
+    chip: &DmfChip,
+    requests: &[RouteRequest],
+    max_steps: usize,
+) -> Result<Schedule, RoutingError> {
+    route_with_progress(chip, requests, max_steps, &ProgressHandle::default())
+}
+
+/// Routes like [route], reporting the routed requests out of all requests
+pub fn route_with_progress(
+    chip: &DmfChip,
+    requests: &[RouteRequest],
+    max_steps: usize,
+    progress: &ProgressHandle,
+) -> Result<Schedule, RoutingError> {
+    let _span = crate::trace::span!("route");
+    for (i, a) in chip.droplets.iter().enumerate() {
+        if !chip.grid.contains(&a.position) {
+            return Err(RoutingError::InvalidCell(a.position));
+        }
+        if let Some(b) = chip.droplets[i + 1..]
+            .iter()
+            .find(|b| a.position.distance(&b.position) <= 1)
+        {
+            return Err(RoutingError::Conflict { a: a.id, b: b.id });
+        }
+    }
+
+    let mut trajectories: BTreeMap<usize, Vec<Cell>> = chip
+        .droplets
+        .iter()
+        .map(|d| (d.id, vec![d.position]))
+        .collect();
+    for request in requests {
+        if !trajectories.contains_key(&request.droplet) {
+            return Err(RoutingError::UnknownDroplet(request.droplet));
+        }
+        if !chip.grid.contains(&request.target) {
+            return Err(RoutingError::InvalidCell(request.target));
+        }
+    }
+
+    for (i, request) in requests.iter().enumerate() {
+        progress
+            .report("route", i, Some(requests.len()))
+            .map_err(|_| RoutingError::Cancelled)?;
+        // Droplets not routed yet have their start as single-step trajectory and stay on it
+        let others: Vec<&[Cell]> = trajectories
+            .iter()
+            .filter(|(id, _)| **id != request.droplet)
+            .map(|(_, trajectory)| trajectory.as_slice())
+            .collect();
+        // Time after which all earlier droplets rest, the target must stay free from then on
+        let settled = others.iter().map(|t| t.len()).max().unwrap_or(0);
+        let start = trajectories[&request.droplet][0];
+        let unreachable = RoutingError::Unreachable {
+            droplet: request.droplet,
+        };
+        if !is_free(&others, start, 0) {
+            return Err(unreachable);
+        }
+
+        let mut previous = BTreeMap::new();
+        let mut queue = VecDeque::from([(start, 0)]);
+        let mut found = None;
+        while let Some((cell, time)) = queue.pop_front() {
+            if cell == request.target
+                && (time..=settled.max(time)).all(|t| is_free(&others, cell, t))
+            {
+                found = Some((cell, time));
+                break;
+            }
+            if time == max_steps {
+                continue;
+            }
+            for next in std::iter::once(cell).chain(chip.grid.neighbors(&cell)) {
+                if !previous.contains_key(&(next, time + 1)) && is_free(&others, next, time + 1) {
+                    previous.insert((next, time + 1), (cell, time));
+                    queue.push_back((next, time + 1));
+                }
+            }
+        }
+
+        let (mut cell, mut time) = found.ok_or(unreachable)?;
+        let mut trajectory = vec![cell];
+        while time > 0 {
+            (cell, time) = previous[&(cell, time)];
+            trajectory.push(cell);
+        }
+        trajectory.reverse();
+        trajectories.insert(request.droplet, trajectory);
+    }
+
+    let len = trajectories.values().map(Vec::len).max().unwrap_or(0);
+    for trajectory in trajectories.values_mut() {
+        let last = *trajectory.last().unwrap();
+        trajectory.resize(len, last);
+    }
+    Ok(Schedule { trajectories })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dmf::{Droplet, ElectrodeGrid};
+
+    fn chip(rows: usize, droplets: &[[usize; 2]]) -> DmfChip {
+        DmfChip {
+            grid: ElectrodeGrid {
+                columns: 8,
+                rows,
+                pitch: 1.,
+                blocked: vec![],
+            },
+            droplets: droplets
+                .iter()
+                .enumerate()
+                .map(|(id, position)| Droplet {
+                    id,
+                    position: Cell(*position),
+                    volume: None,
+                })
+                .collect(),
+            sequence: vec![],
+            metadata: Default::default(),
+        }
+    }
+
+    fn requests_to(targets: &[[usize; 2]]) -> Vec<RouteRequest> {
+        (targets.iter().enumerate())
+            .map(|(droplet, target)| RouteRequest {
+                droplet,
+                target: Cell(*target),
+            })
+            .collect()
+    }
+
+    /// Checks the fluidic constraints between and the single steps of all droplets
+    fn assert_separated(schedule: &Schedule) {
+        let trajectories: Vec<_> = schedule.trajectories.values().collect();
+        for (i, a) in trajectories.iter().enumerate() {
+            for t in 0..schedule.len() {
+                if t + 1 < schedule.len() {
+                    assert!(a[t].distance(&a[t + 1]) <= 1);
+                }
+                for b in &trajectories[i + 1..] {
+                    assert!(a[t].distance(&b[t]) > 1);
+                    if t + 1 < schedule.len() {
+                        assert!(a[t].distance(&b[t + 1]) > 1 && a[t + 1].distance(&b[t]) > 1);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn crossing_droplets() {
+        let chip = chip(5, &[[0, 2], [4, 0]]);
+        let requests = requests_to(&[[7, 2], [4, 4]]);
+        // Droplet 1 has to wait on its start until droplet 0 has passed its column
+        let schedule = route(&chip, &requests, 40).unwrap();
+
+        // Cancelling after the first request stops before the second
+        let reports = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let token = crate::progress::CancellationToken::default();
+        let progress = ProgressHandle::new({
+            let (reports, token) = (reports.clone(), token.clone());
+            move |p| {
+                reports.borrow_mut().push(p.fraction());
+                if p.completed == 1 {
+                    token.cancel();
+                }
+            }
+        })
+        .with_token(token);
+        assert_eq!(
+            route_with_progress(&chip, &requests, 40, &progress),
+            Err(RoutingError::Cancelled)
+        );
+        assert_eq!(*reports.borrow(), [Some(0.), Some(0.5)]);
+        let (a, b) = (&schedule.trajectories[&0], &schedule.trajectories[&1]);
+        assert_eq!(a.last(), Some(&Cell([7, 2])));
+        assert_eq!(b.last(), Some(&Cell([4, 4])));
+        assert_eq!(b[1], Cell([4, 0]));
+        assert_separated(&schedule);
+
+        // Replaying the actuation sequence moves the droplets along their trajectories
+        let chip = DmfChip {
+            sequence: schedule.actuations(),
+            ..chip
+        };
+        let end: Vec<_> = chip
+            .positions_at(chip.sequence.len())
+            .iter()
+            .map(|d| d.position)
+            .collect();
+        assert_eq!(end, [Cell([7, 2]), Cell([4, 4])]);
+
+        // Droplets are obstacles on their start until routed, so they can't swap places
+        let swap = requests_to(&[[4, 1], [0, 2]]);
+        assert_eq!(
+            route(&chip, &swap, 40),
+            Err(RoutingError::Unreachable { droplet: 0 })
+        );
+    }
+
+    #[test]
+    fn waiting_droplets() {
+        // Droplet 0 detours around droplet 1, which starts on its straight path
+        let chip = chip(6, &[[0, 0], [2, 0]]);
+        let schedule = route(&chip, &requests_to(&[[5, 0], [2, 4]]), 30).unwrap();
+        assert_eq!(schedule.trajectories[&0].last(), Some(&Cell([5, 0])));
+        assert_eq!(schedule.trajectories[&1].last(), Some(&Cell([2, 4])));
+        assert_separated(&schedule);
+    }
+}
@@ -0,0 +1,310 @@
+//! ASCII DXF (R12) export. Channels become wide polylines along their centerline, so CAD tools
+//! display the channel outline without any offsetting. Channels of network layers other than
+//! the first go to separate layers, e.g., control channels to "CHANNELS_L1". Markings are
+//! written to the layers they name.
+
+use crate::{
+    base::{
+        annotation::Annotation,
+        channel::{ArcAngles, ChannelPath, PathPiece},
+        network::{EntityRef, Network},
+        primitives::Point,
+        render::RenderConfig,
+        units::LengthUnit,
+    },
+    parallel,
+};
+use std::f64::consts::PI;
+
+/// Layer of channel geometry
+pub const CHANNEL_LAYER: &str = "CHANNELS";
+
+/// Layer of module footprints
+pub const MODULE_LAYER: &str = "MODULES";
+
+/// Layer of grooves, fabricated in a second lithography step
+pub const GROOVE_LAYER: &str = "GROOVES";
+
+/// Layer of port holes
+pub const PORT_LAYER: &str = "PORTS";
+
+/// Layer of labels and dimensions
+pub const ANNOTATION_LAYER: &str = "ANNOTATIONS";
+
+/// Minimal writer for DXF group code/value pairs
+pub(crate) struct DxfWriter {
+    out: String,
+    config: RenderConfig,
+}
+
+impl DxfWriter {
+    /// Writer of a document in the given output unit, unitless if not known
+    pub(crate) fn new(config: &RenderConfig, unit: Option<LengthUnit>) -> Self {
+        let mut writer = DxfWriter {
+            out: String::new(),
+            config: *config,
+        };
+        writer.group(0, "SECTION");
+        writer.group(2, "HEADER");
+        writer.group(9, "$ACADVER");
+        writer.group(1, "AC1009");
+        if let Some(unit) = unit {
+            writer.group(9, "$INSUNITS");
+            writer.group(70, unit.insunits());
+        }
+        writer.group(0, "ENDSEC");
+        writer.group(0, "SECTION");
+        writer.group(2, "ENTITIES");
+        writer
+    }
+
+    pub(crate) fn group(&mut self, code: u16, value: impl std::fmt::Display) {
+        self.out.push_str(&format!("{code}\n{value}\n"));
+    }
+
+    fn number(&mut self, code: u16, value: f64) {
+        let value = self.config.number(value);
+        self.group(code, value);
+    }
+
+    /// Layout point in output coordinates
+    fn point(&mut self, point: Point, offset: u16) {
+        let Point([x, y]) = self.config.coordinate_system.apply(point);
+        self.number(10 + offset, x);
+        self.number(20 + offset, y);
+    }
+
+    /// Layout distance in output units
+    fn length(&mut self, code: u16, length: f64) {
+        let length = self.config.coordinate_system.length(length);
+        self.number(code, length);
+    }
+
+    pub(crate) fn line(&mut self, layer: &str, a: Point, b: Point) {
+        self.group(0, "LINE");
+        self.group(8, layer);
+        self.point(a, 0);
+        self.point(b, 1);
+    }
+
+    pub(crate) fn circle(&mut self, layer: &str, center: Point, radius: f64) {
+        self.group(0, "CIRCLE");
+        self.group(8, layer);
+        self.point(center, 0);
+        self.length(40, radius);
+    }
+
+    /// Text starting at the position, or centered on it
+    pub(crate) fn text(
+        &mut self,
+        layer: &str,
+        position: Point,
+        height: f64,
+        angle: f64,
+        text: &str,
+        centered: bool,
+    ) {
+        self.group(0, "TEXT");
+        self.group(8, layer);
+        self.point(position, 0);
+        self.length(40, height);
+        self.group(1, text);
+        if angle != 0. {
+            let angle = self.config.coordinate_system.angle(angle).to_degrees();
+            self.number(50, angle);
+        }
+        if centered {
+            self.group(72, 1);
+            self.point(position, 1);
+        }
+    }
+
+    /// Polyline through (vertex, bulge) pairs; bulge is tan(sweep / 4) of the arc towards the
+    /// next vertex
+    pub(crate) fn polyline(
+        &mut self,
+        layer: &str,
+        vertices: &[(Point, f64)],
+        width: f64,
+        closed: bool,
+    ) {
+        self.group(0, "POLYLINE");
+        self.group(8, layer);
+        self.group(66, 1);
+        self.group(10, 0);
+        self.group(20, 0);
+        self.group(70, if closed { 1 } else { 0 });
+        if width > 0. {
+            self.length(40, width);
+            self.length(41, width);
+        }
+        // Mirroring the layout turns the arcs the other way
+        let bulge_sign = if self.config.coordinate_system.flips_y() {
+            -1.
+        } else {
+            1.
+        };
+        for (vertex, bulge) in vertices {
+            self.group(0, "VERTEX");
+            self.group(8, layer);
+            self.point(*vertex, 0);
+            if *bulge != 0. {
+                self.number(42, bulge_sign * bulge);
+            }
+        }
+        self.group(0, "SEQEND");
+    }
+
+    /// Polylines of a path, a single closed one for closed paths without gaps
+    pub(crate) fn path(&mut self, layer: &str, path: &ChannelPath, width: f64) {
+        self.path_polylines(layer, &path_polylines(path), width);
+    }
+
+    fn path_polylines(&mut self, layer: &str, (runs, closed): &PathPolylines, width: f64) {
+        for run in runs {
+            self.polyline(layer, run, width, *closed);
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> String {
+        self.group(0, "ENDSEC");
+        self.group(0, "EOF");
+        self.out
+    }
+}
+
+/// Polyline vertices of a path's runs and whether they form a single closed outline
+type PathPolylines = (Vec<Vec<(Point, f64)>>, bool);
+
+fn path_polylines(path: &ChannelPath) -> PathPolylines {
+    let mut runs = polylines(&path.pieces);
+    // A closed outline is a single closed polyline without the repeated start vertex
+    let closed = path.closed && runs.len() == 1;
+    if closed && runs[0].len() > 1 && runs[0].last().map(|v| v.0) == Some(runs[0][0].0) {
+        runs[0].pop();
+    }
+    (runs, closed)
+}
+
+/// Splits a path into runs of connected pieces and converts each into polyline vertices
+fn polylines(pieces: &[PathPiece]) -> Vec<Vec<(Point, f64)>> {
+    let mut runs: Vec<Vec<(Point, f64)>> = Vec::new();
+    let mut previous_end: Option<Point> = None;
+    for piece in pieces {
+        if previous_end != Some(piece.start()) {
+            if let (Some(run), Some(end)) = (runs.last_mut(), previous_end) {
+                run.push((end, 0.));
+            }
+            runs.push(Vec::new());
+        }
+        let run = runs.last_mut().unwrap();
+        match piece {
+            PathPiece::LineSegment(line) => run.push((line.start, 0.)),
+            PathPiece::Arc(arc) => {
+                let ArcAngles { sweep, .. } = arc.angles();
+                // Bulges of (nearly) full circles are unbounded, such arcs are split in half
+                if sweep.abs() > PI {
+                    run.push((arc.start, (sweep / 8.).tan()));
+                    run.push((arc.point_at(0.5), (sweep / 8.).tan()));
+                } else {
+                    run.push((arc.start, (sweep / 4.).tan()));
+                }
+            }
+        }
+        previous_end = Some(piece.end());
+    }
+    if let (Some(run), Some(end)) = (runs.last_mut(), previous_end) {
+        run.push((end, 0.));
+    }
+    runs
+}
+
+/// Exports channels, modules, grooves, port holes, and annotations to an ASCII DXF document
+pub fn network_to_dxf(network: &Network) -> String {
+    network_to_dxf_with(network, &RenderConfig::default())
+}
+
+/// Exports the network with explicit output coordinates and number formatting. With a length
+/// unit declared by the network and one configured for the output, coordinates are converted
+/// between them; the output unit is written as `$INSUNITS`.
+pub fn network_to_dxf_with(network: &Network, config: &RenderConfig) -> String {
+    let _span = crate::trace::span!("network_to_dxf");
+    let (config, unit) = config.resolve(network.length_unit);
+    let mut dxf = DxfWriter::new(&config, unit);
+
+    // Grouped entities are drawn on a sublayer of their first group
+    let groups = network.primary_groups();
+    let layer = |base: &str, entity| match groups.get(&entity) {
+        Some(group) => format!("{base}_{}", group.identifier()),
+        None => base.to_string(),
+    };
+    // Arcs are converted to polyline bulges in parallel, written in channel order
+    let channel_polylines =
+        parallel::map(&network.channels, |c| c.path.as_ref().map(path_polylines));
+    for (channel, polylines) in network.channels.iter().zip(channel_polylines) {
+        if let Some(polylines) = polylines {
+            let base = match channel.layer {
+                0 => CHANNEL_LAYER.to_string(),
+                n => format!("{CHANNEL_LAYER}_L{n}"),
+            };
+            let layer = layer(&base, EntityRef::Channel(channel.id));
+            dxf.path_polylines(&layer, &polylines, channel.shape.width());
+        }
+    }
+
+    for module in &network.modules {
+        let corners: Vec<(Point, f64)> = module.outline().0.into_iter().map(|p| (p, 0.)).collect();
+        let layer = layer(MODULE_LAYER, EntityRef::Module(module.id));
+        dxf.polyline(&layer, &corners, 0., true);
+    }
+
+    for marking in &network.markings {
+        dxf.path(&marking.layer, &marking.path, marking.width);
+    }
+
+    for groove in network.groove_outlines() {
+        let corners: Vec<(Point, f64)> = groove.0.into_iter().map(|p| (p, 0.)).collect();
+        dxf.polyline(GROOVE_LAYER, &corners, 0., true);
+    }
+
+    for (_, position, port) in network.port_holes() {
+        if let Some(center) = position {
+            dxf.circle(PORT_LAYER, center, port.diameter / 2.);
+        }
+    }
+
+    for annotation in &network.annotations {
+        match annotation {
+            Annotation::Label(label) => {
+                if let Some(anchor) = network.resolve_anchor(&label.anchor) {
+                    let [dx, dy] = label.offset.0;
+                    dxf.text(
+                        ANNOTATION_LAYER,
+                        anchor.translated(Point([dx, dy])),
+                        label.height,
+                        0.,
+                        &label.text,
+                        false,
+                    );
+                }
+            }
+            Annotation::Dimension(dimension) => {
+                if let Some(geometry) = dimension.geometry(network, &dxf.config.number_format) {
+                    for (a, b) in geometry.extensions.iter().chain([&geometry.line]) {
+                        dxf.line(ANNOTATION_LAYER, *a, *b);
+                    }
+                    dxf.text(
+                        ANNOTATION_LAYER,
+                        geometry.text_position,
+                        dimension.height,
+                        geometry.text_angle,
+                        &geometry.text,
+                        true,
+                    );
+                }
+            }
+        }
+    }
+
+    dxf.finish()
+}
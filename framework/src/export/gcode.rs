@@ -0,0 +1,300 @@
+//! Milling toolpaths for rectangular channels. The width is cleared by parallel passes of the
+//! tool, offset from the centerline so that the tool's edge follows the channel walls, and the
+//! height is reached in several depth passes.
+
+use super::ExportError;
+use crate::base::{
+    channel::{ChannelPath, PathPiece, Shape},
+    network::Network,
+    primitives::Point,
+    units::LengthUnit,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Tool and machine parameters, lengths in layout units
+pub struct MachineProfile {
+    /// Diameter of the flat end mill
+    pub tool_diameter: f64,
+
+    /// Largest lateral distance between neighboring passes as fraction of the tool diameter
+    pub stepover: f64,
+
+    /// Largest depth removed per pass
+    pub step_down: f64,
+
+    /// Height above the stock for rapid moves
+    pub safe_height: f64,
+
+    /// Cutting feed in mm/min
+    pub feed_rate: f64,
+
+    /// Plunging feed in mm/min
+    pub plunge_rate: f64,
+
+    /// Spindle speed in rpm
+    pub spindle_speed: f64,
+
+    /// Layout units per millimeter, 1000 for micrometers; the network's declared length
+    /// unit takes precedence
+    pub units_per_mm: f64,
+
+    /// Depth of markings, e.g., engraved text, milled in a single pass along their paths;
+    /// markings aren't milled if zero
+    #[serde(default)]
+    pub engraving_depth: f64,
+}
+
+impl MachineProfile {
+    /// Reports the first parameter the passes are derived from that isn't positive
+    fn check(&self) -> Result<(), ExportError> {
+        let parameters = [
+            ("tool_diameter", self.tool_diameter),
+            ("stepover", self.stepover),
+            ("step_down", self.step_down),
+        ];
+        match parameters
+            .into_iter()
+            .find(|(_, value)| value.is_nan() || *value <= 0.)
+        {
+            Some((name, _)) => Err(ExportError::InvalidProfile(name)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for MachineProfile {
+    fn default() -> Self {
+        MachineProfile {
+            tool_diameter: 100.,
+            stepover: 0.5,
+            step_down: 25.,
+            safe_height: 2000.,
+            feed_rate: 100.,
+            plunge_rate: 20.,
+            spindle_speed: 20000.,
+            units_per_mm: 1000.,
+            engraving_depth: 0.,
+        }
+    }
+}
+
+struct Program<'a> {
+    out: String,
+    profile: &'a MachineProfile,
+    units_per_mm: f64,
+}
+
+impl Program<'_> {
+    fn mm(&self, value: f64) -> String {
+        format!("{:.4}", value / self.units_per_mm)
+    }
+
+    fn xy(&self, Point([x, y]): Point) -> String {
+        format!("X{} Y{}", self.mm(x), self.mm(y))
+    }
+
+    fn line(&mut self, text: &str) {
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    /// Mills along the path at the depth below the surface
+    fn pass(&mut self, path: &ChannelPath, depth: f64) {
+        let Some(first) = path.pieces.first() else {
+            return;
+        };
+        let safe = self.mm(self.profile.safe_height);
+        let start = self.xy(first.start());
+        let z = self.mm(-depth);
+        let (plunge, feed) = (self.profile.plunge_rate, self.profile.feed_rate);
+        self.line(&format!(
+            "G0 Z{safe}\nG0 {start}\nG1 Z{z} F{plunge}\nF{feed}"
+        ));
+        let mut position = first.start();
+        for piece in &path.pieces {
+            if piece.start() != position {
+                let start = self.xy(piece.start());
+                self.line(&format!("G1 {start}"));
+            }
+            match piece {
+                PathPiece::LineSegment(line) => {
+                    let end = self.xy(line.end);
+                    self.line(&format!("G1 {end}"));
+                }
+                PathPiece::Arc(arc) => {
+                    let Point([sx, sy]) = arc.start;
+                    let Point([cx, cy]) = arc.center;
+                    let command = if arc.right { "G2" } else { "G3" };
+                    let text = format!(
+                        "{command} {} I{} J{}",
+                        self.xy(arc.end),
+                        self.mm(cx - sx),
+                        self.mm(cy - sy)
+                    );
+                    self.line(&text);
+                }
+            }
+            position = piece.end();
+        }
+        if path.closed && position != first.start() {
+            let start = self.xy(first.start());
+            self.line(&format!("G1 {start}"));
+        }
+    }
+}
+
+/// Lateral offsets of the tool center from the centerline that clear the width
+fn pass_offsets(width: f64, profile: &MachineProfile) -> Vec<f64> {
+    let span = width - profile.tool_diameter;
+    let passes = (span / (profile.stepover * profile.tool_diameter))
+        .ceil()
+        .max(0.) as usize
+        + 1;
+    if passes == 1 {
+        return vec![0.];
+    }
+    (0..passes)
+        .map(|i| -span / 2. + span * i as f64 / (passes - 1) as f64)
+        .collect()
+}
+
+/// Depths of the passes down to the full height
+fn pass_depths(height: f64, profile: &MachineProfile) -> Vec<f64> {
+    let passes = (height / profile.step_down).ceil().max(1.) as usize;
+    (1..=passes)
+        .map(|i| height * i as f64 / passes as f64)
+        .collect()
+}
+
+/// G-code milling all channels with paths, then engraving the markings if the profile has an
+/// engraving depth. Channels narrower than the tool, arcs tighter than the outermost pass, and
+/// cylindrical channels can't be milled and are reported, as are profiles whose tool diameter,
+/// stepover, or step-down isn't positive.
+pub fn network_to_gcode(
+    network: &Network,
+    profile: &MachineProfile,
+) -> Result<String, ExportError> {
+    let _span = crate::trace::span!("network_to_gcode");
+    profile.check()?;
+    let mut program = Program {
+        out: String::new(),
+        profile,
+        units_per_mm: network
+            .units_per(LengthUnit::Millimeter)
+            .unwrap_or(profile.units_per_mm),
+    };
+    program.line("(mmft-framework channel milling)");
+    program.line(&format!(
+        "(tool diameter {} mm)\nG21\nG90\nG17\nM3 S{}",
+        program.mm(profile.tool_diameter),
+        profile.spindle_speed
+    ));
+
+    for channel in &network.channels {
+        let Some(path) = &channel.path else {
+            continue;
+        };
+        let Shape::Rectangular(shape) = channel.shape else {
+            return Err(ExportError::Unmachinable(channel.id));
+        };
+        if shape.width < profile.tool_diameter {
+            return Err(ExportError::Unmachinable(channel.id));
+        }
+        let passes = pass_offsets(shape.width, profile)
+            .into_iter()
+            .map(|offset| path.offset(offset))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(ExportError::Unmachinable(channel.id))?;
+        program.line(&format!("(channel {})", channel.id));
+        for depth in pass_depths(shape.height, profile) {
+            for pass in &passes {
+                program.pass(pass, depth);
+            }
+        }
+    }
+
+    if profile.engraving_depth > 0. {
+        for marking in &network.markings {
+            program.line(&format!("(marking {})", marking.layer));
+            program.pass(&marking.path, profile.engraving_depth);
+        }
+    }
+
+    let safe = program.mm(profile.safe_height);
+    program.line(&format!("G0 Z{safe}\nM5\nM30"));
+    Ok(program.out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn passes() {
+        let profile = MachineProfile::default();
+        assert_eq!(pass_offsets(100., &profile), [0.]);
+        assert_eq!(pass_offsets(200., &profile), [-50., 0., 50.]);
+        assert_eq!(pass_offsets(150., &profile), [-25., 25.]);
+        assert_eq!(pass_depths(50., &profile), [25., 50.]);
+        assert_eq!(pass_depths(60., &profile), [20., 40., 60.]);
+    }
+
+    #[test]
+    fn program() {
+        let network = Network::random(&crate::base::generator::RandomNetworkSpec {
+            topology: crate::base::generator::Topology::Grid {
+                columns: 2,
+                rows: 1,
+            },
+            width: [200., 200.],
+            height: 50.,
+            ..Default::default()
+        });
+        let gcode = network_to_gcode(&network, &MachineProfile::default()).unwrap();
+        // Three lateral passes at two depths
+        assert_eq!(gcode.matches("G1 Z").count(), 6);
+        assert!(gcode.contains("G0 X0.0000 Y-0.0500\nG1 Z-0.0250 F20"));
+        assert!(gcode.ends_with("M5\nM30\n"));
+
+        let narrow = MachineProfile {
+            tool_diameter: 300.,
+            ..Default::default()
+        };
+        assert_eq!(
+            network_to_gcode(&network, &narrow),
+            Err(ExportError::Unmachinable(0))
+        );
+
+        for (profile, name) in [
+            (
+                MachineProfile {
+                    stepover: 0.,
+                    ..Default::default()
+                },
+                "stepover",
+            ),
+            (
+                MachineProfile {
+                    tool_diameter: -1.,
+                    ..Default::default()
+                },
+                "tool_diameter",
+            ),
+            (
+                MachineProfile {
+                    step_down: f64::NAN,
+                    ..Default::default()
+                },
+                "step_down",
+            ),
+        ] {
+            assert_eq!(
+                network_to_gcode(&network, &profile),
+                Err(ExportError::InvalidProfile(name))
+            );
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! Fabrication output for CNC micromilling: RS-274X Gerber tracks along the channel centerlines,
+//! drawn with round apertures of the channel widths, and Excellon drill files for ports.
+
+use super::ExportError;
+pub use crate::base::port::DrillHole;
+use crate::base::{
+    channel::{PathPiece, SVGPath},
+    network::Network,
+    primitives::Point,
+    units::LengthUnit,
+};
+use std::fmt::Write;
+
+/// Gerber coordinate in the 4.6 format, millimeters with six decimals
+fn coordinate(value: f64, units_per_mm: f64) -> i64 {
+    (value / units_per_mm * 1e6).round() as i64
+}
+
+/// Layout units per millimeter of the network, from its declared length unit if any
+fn resolve_units(network: &Network, units_per_mm: f64) -> f64 {
+    network
+        .units_per(LengthUnit::Millimeter)
+        .unwrap_or(units_per_mm)
+}
+
+/// Gerber file of the channels; each distinct channel width becomes a circular aperture.
+/// Layout units per millimeter are only used if the network doesn't declare its length unit.
+pub fn network_to_gerber(network: &Network, units_per_mm: f64) -> String {
+    let _span = crate::trace::span!("network_to_gerber");
+    let units_per_mm = resolve_units(network, units_per_mm);
+    let c = |value| coordinate(value, units_per_mm);
+    let xy = |Point([x, y]): Point| format!("X{}Y{}", c(x), c(y));
+
+    let mut apertures: Vec<f64> = Vec::new();
+    for channel in &network.channels {
+        let width = channel.shape.width();
+        if channel.path.is_some() && !apertures.contains(&width) {
+            apertures.push(width);
+        }
+    }
+
+    let mut out = String::new();
+    out.push_str("G04 mmft-framework channel tracks*\n%FSLAX46Y46*%\n%MOMM*%\n%LPD*%\nG75*\n");
+    for (i, width) in apertures.iter().enumerate() {
+        writeln!(out, "%ADD{}C,{:.6}*%", 10 + i, width / units_per_mm).unwrap();
+    }
+    for channel in &network.channels {
+        let Some(path) = &channel.path else {
+            continue;
+        };
+        let aperture = apertures
+            .iter()
+            .position(|w| *w == channel.shape.width())
+            .unwrap();
+        writeln!(out, "G04 channel {}*\nD{}*", channel.id, 10 + aperture).unwrap();
+        let mut position = None;
+        for piece in &path.pieces {
+            if piece.length().0 == 0. {
+                continue;
+            }
+            if position != Some(piece.start()) {
+                writeln!(out, "{}D02*", xy(piece.start())).unwrap();
+            }
+            match piece {
+                PathPiece::LineSegment(line) => {
+                    writeln!(out, "G01*\n{}D01*", xy(line.end)).unwrap();
+                }
+                PathPiece::Arc(arc) => {
+                    let Point([sx, sy]) = arc.start;
+                    let Point([cx, cy]) = arc.center;
+                    let direction = if arc.right { "G02" } else { "G03" };
+                    writeln!(
+                        out,
+                        "{direction}*\n{}I{}J{}D01*",
+                        xy(arc.end),
+                        c(cx - sx),
+                        c(cy - sy)
+                    )
+                    .unwrap();
+                }
+            }
+            position = Some(piece.end());
+        }
+    }
+    out.push_str("M02*\n");
+    out
+}
+
+/// Excellon drill file with one tool per distinct hole diameter; units per millimeter as in
+/// [network_to_gerber]
+pub fn holes_to_excellon(
+    network: &Network,
+    holes: &[DrillHole],
+    units_per_mm: f64,
+) -> Result<String, ExportError> {
+    let units_per_mm = resolve_units(network, units_per_mm);
+    let mut tools: Vec<f64> = Vec::new();
+    for hole in holes {
+        if !tools.contains(&hole.diameter) {
+            tools.push(hole.diameter);
+        }
+    }
+
+    let mut out = String::from("M48\nMETRIC\n");
+    for (i, diameter) in tools.iter().enumerate() {
+        writeln!(out, "T{}C{:.3}", i + 1, diameter / units_per_mm).unwrap();
+    }
+    out.push_str("%\nG90\nG05\n");
+    for (i, diameter) in tools.iter().enumerate() {
+        writeln!(out, "T{}", i + 1).unwrap();
+        for hole in holes.iter().filter(|h| h.diameter == *diameter) {
+            let Point([x, y]) = network
+                .node_position(hole.node)
+                .ok_or(ExportError::UnplacedNode(hole.node))?;
+            writeln!(out, "X{:.4}Y{:.4}", x / units_per_mm, y / units_per_mm).unwrap();
+        }
+    }
+    out.push_str("M30\n");
+    Ok(out)
+}
+
+/// Excellon drill file of the holes of all port nodes
+pub fn ports_to_excellon(network: &Network, units_per_mm: f64) -> Result<String, ExportError> {
+    holes_to_excellon(network, &network.drill_holes(), units_per_mm)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::{Arc, Channel, ChannelPath, LineSegment, RectangularShape, Shape},
+        network::{Node, NodeId},
+    };
+
+    #[test]
+    fn tracks_and_drills() {
+        let channel = |id, nodes: [usize; 2], width, pieces: Vec<PathPiece>| Channel {
+            id,
+            node_a: NodeId(nodes[0]),
+            node_b: NodeId(nodes[1]),
+            shape: Shape::Rectangular(RectangularShape { width, height: 50. }),
+            path: (!pieces.is_empty()).then_some(ChannelPath {
+                pieces,
+                closed: false,
+            }),
+            length: Some(1000.),
+            layer: 0,
+            metadata: Default::default(),
+        };
+        let line = |start, end| PathPiece::LineSegment(LineSegment { start, end });
+        let arc = |right, start, end, center| {
+            PathPiece::Arc(Arc {
+                right,
+                start,
+                end,
+                center,
+            })
+        };
+        // Micrometers; two channels share an aperture and the one without path has none
+        let mut network = Network {
+            nodes: (0..7).map(|i| Node::new(NodeId(i))).collect(),
+            channels: vec![
+                channel(
+                    0,
+                    [0, 1],
+                    100.,
+                    vec![
+                        line(Point([0., 0.]), Point([1000., 0.])),
+                        arc(
+                            true,
+                            Point([1000., 0.]),
+                            Point([2000., 0.]),
+                            Point([1500., 0.]),
+                        ),
+                    ],
+                ),
+                channel(
+                    1,
+                    [2, 3],
+                    200.,
+                    vec![arc(
+                        false,
+                        Point([0., 1000.]),
+                        Point([600., 1000.]),
+                        Point([300., 1400.]),
+                    )],
+                ),
+                channel(
+                    2,
+                    [4, 5],
+                    100.,
+                    vec![line(Point([0., 2000.]), Point([500., 2000.]))],
+                ),
+                channel(3, [1, 2], 300., vec![]),
+            ],
+            ..Default::default()
+        };
+        let gerber = network_to_gerber(&network, 1000.);
+        assert_eq!(
+            gerber,
+            "G04 mmft-framework channel tracks*\n%FSLAX46Y46*%\n%MOMM*%\n%LPD*%\nG75*\n\
+             %ADD10C,0.100000*%\n%ADD11C,0.200000*%\n\
+             G04 channel 0*\nD10*\nX0Y0D02*\nG01*\nX1000000Y0D01*\n\
+             G02*\nX2000000Y0I500000J0D01*\n\
+             G04 channel 1*\nD11*\nX0Y1000000D02*\nG03*\nX600000Y1000000I300000J400000D01*\n\
+             G04 channel 2*\nD10*\nX0Y2000000D02*\nG01*\nX500000Y2000000D01*\nM02*\n"
+        );
+
+        // Holes of the same diameter share a tool, the positions come from the paths
+        let hole = |node, diameter| DrillHole {
+            node: NodeId(node),
+            diameter,
+        };
+        let holes = [hole(0, 1000.), hole(1, 500.), hole(4, 1000.)];
+        assert_eq!(
+            holes_to_excellon(&network, &holes, 1000.).unwrap(),
+            "M48\nMETRIC\nT1C1.000\nT2C0.500\n%\nG90\nG05\n\
+             T1\nX0.0000Y0.0000\nX0.0000Y2.0000\nT2\nX2.0000Y0.0000\nM30\n"
+        );
+        assert_eq!(
+            holes_to_excellon(&network, &[hole(6, 1000.)], 1000.),
+            Err(ExportError::UnplacedNode(NodeId(6)))
+        );
+
+        // The declared length unit takes precedence over the given scale
+        network.length_unit = Some(LengthUnit::Micrometer);
+        assert_eq!(network_to_gerber(&network, 1.), gerber);
+        network.length_unit = Some(LengthUnit::Millimeter);
+        assert!(network_to_gerber(&network, 1000.).contains("X1000000000Y0D01*"));
+    }
+}
@@ -0,0 +1,231 @@
+//! Cut files for laser-cut laminate chips. Every layer of the network's layer stack becomes a
+//! separate DXF file with the chip outline, registration holes for stacking the sheets, and the
+//! outlines of the channels cut through the layer. All cut lines are compensated for the kerf,
+//! so the cut parts and openings end up at their nominal size.
+
+use super::{dxf::DxfWriter, layout_bounds, ExportError};
+use crate::base::{
+    channel::{Arc, ChannelPath, LineSegment, PathPiece, Shape},
+    network::Network,
+    primitives::{Point, Rect},
+    render::RenderConfig,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Layer of all cut lines
+pub const CUT_LAYER: &str = "CUT";
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Holes in the corners of every sheet for aligning the layers with pins
+pub struct RegistrationHoles {
+    /// Hole diameter, larger than the kerf
+    pub diameter: f64,
+
+    /// Distance of the hole centers from the outline's edges
+    pub inset: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Settings of the laser-cut export, lengths in layout units
+pub struct LaserConfig {
+    /// Width of the material removed by the beam
+    pub kerf: f64,
+
+    /// Chip outline, the extent of the layout plus the margin if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outline: Option<Rect>,
+
+    /// Border around the layout if no outline is given
+    pub margin: f64,
+
+    /// Registration holes, none if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registration: Option<RegistrationHoles>,
+}
+
+impl Default for LaserConfig {
+    fn default() -> Self {
+        LaserConfig {
+            kerf: 100.,
+            outline: None,
+            margin: 10000.,
+            registration: Some(RegistrationHoles {
+                diameter: 3000.,
+                inset: 4000.,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Cut file of a single laminate layer
+pub struct LaserFile {
+    /// Name of the layer in the layer stack
+    pub layer: String,
+
+    /// DXF document with the cut lines
+    pub dxf: String,
+}
+
+fn line(start: Point, end: Point) -> PathPiece {
+    PathPiece::LineSegment(LineSegment { start, end })
+}
+
+/// Closed outlines of the opening cut for a channel, with walls at the given distance from the
+/// centerline: a ring for closed paths, a single contour with flat ends otherwise
+fn channel_outlines(path: &ChannelPath, distance: f64) -> Option<Vec<ChannelPath>> {
+    let left = path.offset(distance)?;
+    let mut right = path.offset(-distance)?;
+    if path.closed {
+        return Some(vec![left, right]);
+    }
+    right.reverse();
+    let mut outline = left;
+    outline.concat(&right);
+    let (end, start) = (
+        outline.pieces.last()?.end(),
+        outline.pieces.first()?.start(),
+    );
+    outline.add(line(end, start));
+    outline.closed = true;
+    Some(vec![outline])
+}
+
+/// Cut files for all layers of the network's layer stack, in stacking order. Channels are cut
+/// through the layers mapped to their network layer; cylindrical channels, channels not wider
+/// than the kerf, and paths that can't be offset are reported as unmachinable.
+pub fn network_to_laser(
+    network: &Network,
+    config: &LaserConfig,
+) -> Result<Vec<LaserFile>, ExportError> {
+    let _span = crate::trace::span!("network_to_laser");
+    let stack = network
+        .layer_stack
+        .as_ref()
+        .ok_or(ExportError::MissingLayerStack)?;
+    let outline = config
+        .outline
+        .or_else(|| layout_bounds(network).map(|b| b.inflate(config.margin)))
+        .unwrap_or(Rect {
+            min: Point([0., 0.]),
+            max: Point([0., 0.]),
+        });
+    let half_kerf = config.kerf / 2.;
+
+    // The same sheet outline and holes are cut in every layer
+    let mut common = vec![ChannelPath::rectangle(&outline.inflate(half_kerf))];
+    if let Some(RegistrationHoles { diameter, inset }) = config.registration {
+        let radius = diameter / 2. - half_kerf;
+        let (Point([x0, y0]), Point([x1, y1])) = (outline.min, outline.max);
+        for [x, y] in [
+            [x0 + inset, y0 + inset],
+            [x1 - inset, y0 + inset],
+            [x1 - inset, y1 - inset],
+            [x0 + inset, y1 - inset],
+        ] {
+            let start = Point([x + radius, y]);
+            common.push(ChannelPath::closed(vec![PathPiece::Arc(Arc {
+                right: false,
+                start,
+                end: start,
+                center: Point([x, y]),
+            })]));
+        }
+    }
+
+    let mut openings = Vec::new();
+    for channel in &network.channels {
+        let Some(path) = &channel.path else {
+            continue;
+        };
+        let Shape::Rectangular(shape) = channel.shape else {
+            return Err(ExportError::Unmachinable(channel.id));
+        };
+        let distance = shape.width / 2. - half_kerf;
+        let outlines = (distance > 0.)
+            .then(|| channel_outlines(path, distance))
+            .flatten()
+            .ok_or(ExportError::Unmachinable(channel.id))?;
+        openings.push((channel.layer, outlines));
+    }
+
+    Ok(stack
+        .layers
+        .iter()
+        .map(|layer| {
+            let mut dxf = DxfWriter::new(&RenderConfig::default(), network.length_unit);
+            for path in &common {
+                dxf.path(CUT_LAYER, path, 0.);
+            }
+            for (_, outlines) in openings
+                .iter()
+                .filter(|(l, _)| layer.network_layer == Some(*l))
+            {
+                for path in outlines {
+                    dxf.path(CUT_LAYER, path, 0.);
+                }
+            }
+            LaserFile {
+                layer: layer.name.clone(),
+                dxf: dxf.finish(),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        generator::{RandomNetworkSpec, Topology},
+        layers::{Layer, LayerStack, Material},
+    };
+
+    #[test]
+    fn layers() {
+        let mut network = Network::random(&RandomNetworkSpec {
+            topology: Topology::Grid {
+                columns: 2,
+                rows: 1,
+            },
+            width: [200., 200.],
+            ..Default::default()
+        });
+        let config = LaserConfig::default();
+        assert_eq!(
+            network_to_laser(&network, &config),
+            Err(ExportError::MissingLayerStack)
+        );
+
+        let layer = |name: &str, network_layer| Layer {
+            name: name.into(),
+            material: Material::Pmma,
+            thickness: 100.,
+            network_layer,
+            bonding: None,
+            bond_strength: None,
+        };
+        network.layer_stack = Some(LayerStack {
+            layers: vec![
+                layer("bottom", None),
+                layer("channels", Some(0)),
+                layer("top", None),
+            ],
+        });
+        let files = network_to_laser(&network, &config).unwrap();
+        let polylines: Vec<_> = files
+            .iter()
+            .map(|f| f.dxf.matches("POLYLINE").count())
+            .collect();
+        assert_eq!(polylines, [5, 6, 5]);
+        assert_eq!(files[1].layer, "channels");
+
+        // The opening of the straight channel is narrowed by half the kerf on each side
+        let channel_cut = files[1].dxf.rsplit("POLYLINE").next().unwrap();
+        assert!(channel_cut.contains("\n20\n-50\n"));
+        assert!(channel_cut.contains("\n20\n50\n"));
+    }
+}
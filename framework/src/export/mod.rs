@@ -0,0 +1,132 @@
+use crate::base::{
+    annotation::{Annotation, DimensionAnnotation},
+    network::{Network, NodeId},
+    primitives::{Point, Rect},
+    render::NumberFormat,
+};
+
+pub mod checksum;
+pub mod dxf;
+pub mod gcode;
+pub mod gerber;
+pub mod laser;
+pub mod package;
+pub mod pdf;
+#[cfg(feature = "raster")]
+pub mod raster;
+pub mod style;
+pub mod svg;
+pub mod vtk;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Reasons an export can't be produced
+pub enum ExportError {
+    /// The node has neither a position nor an attached channel path
+    UnplacedNode(NodeId),
+
+    /// The channel can't be produced with the tool, e.g., it is narrower than the tool
+    Unmachinable(usize),
+
+    /// The export needs the network's layer stack, which isn't set
+    MissingLayerStack,
+
+    /// The image would have more pixels than allowed
+    ImageTooLarge { width: usize, height: usize },
+
+    /// The named parameter of the machine profile must be positive
+    InvalidProfile(&'static str),
+}
+
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::UnplacedNode(NodeId(id)) => write!(f, "node {id} has no position"),
+            ExportError::Unmachinable(id) => {
+                write!(f, "channel {id} can't be machined with the tool")
+            }
+            ExportError::MissingLayerStack => write!(f, "the network has no layer stack"),
+            ExportError::ImageTooLarge { width, height } => {
+                write!(f, "a {width} x {height} pixel image is too large")
+            }
+            ExportError::InvalidProfile(name) => {
+                write!(f, "the machine profile's {name} must be positive")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+/// Drawable lines and text placement of a dimension annotation
+pub(crate) struct DimensionGeometry {
+    /// The dimension line itself
+    pub line: (Point, Point),
+
+    /// Extension lines from the measured positions to the dimension line
+    pub extensions: [(Point, Point); 2],
+
+    /// Center of the text, on the far side of the dimension line
+    pub text_position: Point,
+
+    /// Text direction in radians
+    pub text_angle: f64,
+
+    /// The annotation's text, or the measured length in layout units
+    pub text: String,
+}
+
+impl DimensionAnnotation {
+    /// Placement of the dimension, measured lengths are written in the exporter's format
+    pub(crate) fn geometry(
+        &self,
+        network: &Network,
+        format: &NumberFormat,
+    ) -> Option<DimensionGeometry> {
+        let from = network.resolve_anchor(&self.from)?;
+        let to = network.resolve_anchor(&self.to)?;
+        let (Point([x0, y0]), Point([x1, y1])) = (from, to);
+        let length = f64::hypot(x1 - x0, y1 - y0);
+        if length == 0. {
+            return None;
+        }
+        let normal = [-(y1 - y0) / length, (x1 - x0) / length];
+        let shift = |Point([x, y]): Point, distance: f64| {
+            Point([x + distance * normal[0], y + distance * normal[1]])
+        };
+        let (a, b) = (shift(from, self.offset), shift(to, self.offset));
+        let middle = Point([(a.0[0] + b.0[0]) / 2., (a.0[1] + b.0[1]) / 2.]);
+        Some(DimensionGeometry {
+            line: (a, b),
+            extensions: [(from, a), (to, b)],
+            text_position: shift(middle, self.offset.signum() * self.height * 0.75),
+            text_angle: f64::atan2(y1 - y0, x1 - x0),
+            text: self.text.clone().unwrap_or_else(|| format.format(length)),
+        })
+    }
+}
+
+/// Extent of all drawable entities, None for an empty layout
+pub(crate) fn layout_bounds(network: &Network) -> Option<Rect> {
+    let channels = network.channels.iter().filter_map(|c| c.bounding_box());
+    let modules = network.modules.iter().map(|m| m.bounding_box());
+    let markings = network.markings.iter().filter_map(|m| m.bounding_box());
+    let anchors = network
+        .annotations
+        .iter()
+        .flat_map(|annotation| match annotation {
+            Annotation::Label(label) => {
+                vec![network.resolve_anchor(&label.anchor)]
+            }
+            Annotation::Dimension(dimension) => vec![
+                network.resolve_anchor(&dimension.from),
+                network.resolve_anchor(&dimension.to),
+            ],
+        })
+        .flatten()
+        .map(|p| Rect { min: p, max: p });
+    channels
+        .chain(modules)
+        .chain(markings)
+        .chain(anchors)
+        .reduce(|a, b| a.union(&b))
+}
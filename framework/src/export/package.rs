@@ -0,0 +1,249 @@
+//! Fabrication packages: one zip archive with the layout as SVG and DXF, Gerber tracks and
+//! Excellon drill files, a bill of materials of modules and connectors, and a manifest with
+//! SHA-256 checksums of all files. Entries are stored uncompressed with a fixed timestamp, so
+//! identical networks give identical archives.
+
+use super::{
+    checksum::{crc32, hex, sha256},
+    dxf::network_to_dxf,
+    gerber::{network_to_gerber, ports_to_excellon},
+    svg::network_to_svg,
+    ExportError,
+};
+use crate::base::{
+    network::Network,
+    port::ConnectorType,
+    primitives::{Dimensions, Point},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Settings of a fabrication package
+pub struct PackageConfig {
+    /// Base name of the layout and fabrication files
+    pub name: String,
+
+    /// Layout units per millimeter, for the Gerber and Excellon files of networks without a
+    /// declared length unit
+    pub units_per_mm: f64,
+}
+
+impl Default for PackageConfig {
+    fn default() -> Self {
+        PackageConfig {
+            name: "chip".into(),
+            units_per_mm: 1000.,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// File of a package as listed in its manifest
+pub struct PackageFile {
+    pub name: String,
+
+    /// Size in bytes
+    pub size: usize,
+
+    /// SHA-256 digest as lowercase hex digits
+    pub sha256: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Contents of manifest.json, the last entry of a package, listing all other entries
+pub struct PackageManifest {
+    /// Name and version of the framework that wrote the package
+    pub generator: String,
+
+    pub files: Vec<PackageFile>,
+}
+
+/// Bill of materials as CSV: one row per module and per port connector, with positions and
+/// sizes in layout units
+fn bill_of_materials(network: &Network) -> Result<String, ExportError> {
+    let mut csv = String::from("kind,id,part,x,y,width,height\n");
+    for module in &network.modules {
+        let Point([x, y]) = module.position;
+        let Dimensions([width, height]) = module.size;
+        let part = match module.implementation {
+            Some(_) => "subcircuit",
+            None => "module",
+        };
+        writeln!(
+            csv,
+            "module,{},{part},{x:?},{y:?},{width:?},{height:?}",
+            module.id
+        )
+        .unwrap();
+    }
+    for (node, position, port) in network.port_holes() {
+        let Point([x, y]) = position.ok_or(ExportError::UnplacedNode(node))?;
+        let part = match &port.connector {
+            ConnectorType::PressFit => "press_fit",
+            ConnectorType::Luer => "luer",
+            ConnectorType::Bonded => "bonded",
+            ConnectorType::Other(name) => name,
+        };
+        let diameter = port.diameter;
+        writeln!(
+            csv,
+            "port,{},{part},{x:?},{y:?},{diameter:?},{diameter:?}",
+            node.0
+        )
+        .unwrap();
+    }
+    Ok(csv)
+}
+
+/// Zip archive of the files, stored without compression
+fn zip(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    // Version 2.0, UTF-8 names, stored, 1980-01-01 00:00
+    let header = |crc: u32, size: u32, name: &str| {
+        let mut fields = Vec::new();
+        fields.extend(20u16.to_le_bytes());
+        fields.extend(0x0800u16.to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+        fields.extend(0x0021u16.to_le_bytes());
+        fields.extend(crc.to_le_bytes());
+        fields.extend(size.to_le_bytes());
+        fields.extend(size.to_le_bytes());
+        fields.extend((name.len() as u16).to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+        fields
+    };
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+    for (name, data) in files {
+        let offset = archive.len() as u32;
+        let fields = header(crc32(data.iter().copied()), data.len() as u32, name);
+        archive.extend(0x0403_4b50u32.to_le_bytes());
+        archive.extend(&fields);
+        archive.extend(name.as_bytes());
+        archive.extend(data);
+
+        directory.extend(0x0201_4b50u32.to_le_bytes());
+        directory.extend(20u16.to_le_bytes());
+        directory.extend(&fields);
+        // Comment length, disk, internal and external attributes
+        directory.extend([0; 10]);
+        directory.extend(offset.to_le_bytes());
+        directory.extend(name.as_bytes());
+    }
+    let (offset, size) = (archive.len() as u32, directory.len() as u32);
+    archive.extend(directory);
+    archive.extend(0x0605_4b50u32.to_le_bytes());
+    archive.extend([0; 4]);
+    archive.extend((files.len() as u16).to_le_bytes());
+    archive.extend((files.len() as u16).to_le_bytes());
+    archive.extend(size.to_le_bytes());
+    archive.extend(offset.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive
+}
+
+/// Zip archive with everything a fab needs to produce the chip: `<name>.svg`, `<name>.dxf`,
+/// `<name>.gbr`, `<name>.drl` if the network has ports, `bom.csv`, and `manifest.json`
+pub fn export_package(network: &Network, config: &PackageConfig) -> Result<Vec<u8>, ExportError> {
+    let _span = crate::trace::span!("export_package");
+    let name = &config.name;
+    let mut files: Vec<(String, Vec<u8>)> = vec![
+        (format!("{name}.svg"), network_to_svg(network).into_bytes()),
+        (format!("{name}.dxf"), network_to_dxf(network).into_bytes()),
+        (
+            format!("{name}.gbr"),
+            network_to_gerber(network, config.units_per_mm).into_bytes(),
+        ),
+    ];
+    if network.nodes.iter().any(|n| n.port.is_some()) {
+        let drill = ports_to_excellon(network, config.units_per_mm)?;
+        files.push((format!("{name}.drl"), drill.into_bytes()));
+    }
+    files.push(("bom.csv".into(), bill_of_materials(network)?.into_bytes()));
+
+    let manifest = PackageManifest {
+        generator: concat!("mmft-framework ", env!("CARGO_PKG_VERSION")).into(),
+        files: files
+            .iter()
+            .map(|(name, data)| PackageFile {
+                name: name.clone(),
+                size: data.len(),
+                sha256: hex(&sha256(data)),
+            })
+            .collect(),
+    };
+    let manifest = serde_json::to_string_pretty(&manifest).unwrap();
+    files.push(("manifest.json".into(), manifest.into_bytes()));
+    Ok(zip(&files))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{base::port::PortHole, fixtures::trap_array};
+
+    /// Names and contents of the entries of a stored zip archive, from its local headers
+    fn entries(archive: &[u8]) -> Vec<(String, &[u8])> {
+        let field = |at: usize, width: usize| {
+            (0..width).fold(0usize, |v, i| v | (archive[at + i] as usize) << (8 * i))
+        };
+        let mut entries = Vec::new();
+        let mut at = 0;
+        while field(at, 4) == 0x0403_4b50 {
+            let (size, name_length) = (field(at + 22, 4), field(at + 26, 2));
+            let name = &archive[at + 30..at + 30 + name_length];
+            let start = at + 30 + name_length;
+            entries.push((
+                String::from_utf8(name.to_vec()).unwrap(),
+                &archive[start..start + size],
+            ));
+            at = start + size;
+        }
+        entries
+    }
+
+    #[test]
+    fn package() {
+        let mut network = trap_array(1, 2).network;
+        network.nodes[0].port = Some(PortHole {
+            diameter: 750.,
+            connector: ConnectorType::Luer,
+        });
+        let archive = export_package(&network, &PackageConfig::default()).unwrap();
+        assert_eq!(
+            archive,
+            export_package(&network, &PackageConfig::default()).unwrap()
+        );
+
+        let entries = entries(&archive);
+        let names: Vec<&str> = entries.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "chip.svg",
+                "chip.dxf",
+                "chip.gbr",
+                "chip.drl",
+                "bom.csv",
+                "manifest.json"
+            ]
+        );
+        let bom = std::str::from_utf8(entries[4].1).unwrap();
+        assert!(bom.lines().nth(1).unwrap().starts_with("port,0,luer,"));
+
+        let manifest: PackageManifest = serde_json::from_slice(entries[5].1).unwrap();
+        assert_eq!(manifest.files.len(), 5);
+        for ((_, data), file) in entries.iter().zip(&manifest.files) {
+            assert_eq!(file.sha256, hex(&sha256(data)));
+        }
+        // End of central directory with six entries
+        let end = &archive[archive.len() - 22..];
+        assert_eq!(end[..4], 0x0605_4b50u32.to_le_bytes());
+        assert_eq!(end[10..12], 6u16.to_le_bytes());
+    }
+}
@@ -0,0 +1,518 @@
+//! Vector PDF export at true scale, e.g., for printing photolithography masks on transparencies.
+//! Outline, channels, modules, markings, annotations, and the title block are separate optional
+//! content groups (layers) that viewers can toggle.
+
+use super::{
+    layout_bounds,
+    style::{Color, FillRule, Paint, Style},
+};
+use crate::base::{
+    annotation::Annotation,
+    channel::{Arc, ArcAngles, ChannelPath, PathPiece},
+    network::{EntityRef, Network},
+    primitives::{Point, Rect},
+    render::{CoordinateSystem, NumberFormat, RenderConfig},
+    units::LengthUnit,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{f64::consts::FRAC_PI_2, fmt::Write};
+
+/// PDF points per millimeter
+const POINTS_PER_MM: f64 = 72. / 25.4;
+
+/// Names of the layers in drawing order
+const LAYERS: [&str; 6] = [
+    "Outline",
+    "Modules",
+    "Channels",
+    "Markings",
+    "Annotations",
+    "Title block",
+];
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Page layout of the exported document
+pub struct PdfConfig {
+    /// Layout units per millimeter, 1000 for micrometers; the network's declared length
+    /// unit takes precedence
+    pub units_per_mm: f64,
+
+    /// Border around the chip outline in millimeters; holds the scale bar and title block
+    pub margin: f64,
+
+    /// Chip outline in layout units, the extent of the layout if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outline: Option<Rect>,
+
+    /// Title shown in the title block
+    #[serde(default)]
+    pub title: String,
+
+    /// Whether to draw a scale bar for checking the print scale
+    pub scale_bar: bool,
+
+    #[serde(default = "PdfConfig::compact")]
+    pub number_format: NumberFormat,
+
+    /// Appearance of the drawing; colors are drawn opaque
+    #[serde(default = "Style::print")]
+    pub style: Style,
+}
+
+impl PdfConfig {
+    fn compact() -> NumberFormat {
+        NumberFormat::compact(3)
+    }
+}
+
+impl Default for PdfConfig {
+    fn default() -> Self {
+        PdfConfig {
+            units_per_mm: 1000.,
+            margin: 15.,
+            outline: None,
+            title: String::new(),
+            scale_bar: true,
+            number_format: PdfConfig::compact(),
+            style: Style::print(),
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_ascii())
+        .flat_map(|c| match c {
+            '(' | ')' | '\\' => vec!['\\', c],
+            c => vec![c],
+        })
+        .collect()
+}
+
+/// Cubic Bézier segments (two control points and end) approximating an arc, at most a quarter
+/// circle each
+fn beziers(arc: &Arc) -> Vec<[Point; 3]> {
+    let ArcAngles { start, sweep, .. } = arc.angles();
+    let parts = (sweep.abs() / FRAC_PI_2).ceil().max(1.) as usize;
+    let step = sweep / parts as f64;
+    let k = 4. / 3. * (step / 4.).tan();
+    let (Point([cx, cy]), r) = (arc.center, arc.radius());
+    let at = |angle: f64| (angle.cos(), angle.sin());
+    (0..parts)
+        .map(|i| {
+            let (a, b) = (start + i as f64 * step, start + (i + 1) as f64 * step);
+            let ((ca, sa), (cb, sb)) = (at(a), at(b));
+            [
+                Point([cx + r * (ca - k * sa), cy + r * (sa + k * ca)]),
+                Point([cx + r * (cb + k * sb), cy + r * (sb - k * cb)]),
+                Point([cx + r * cb, cy + r * sb]),
+            ]
+        })
+        .collect()
+}
+
+struct Content<'a> {
+    out: String,
+    config: &'a RenderConfig,
+}
+
+impl Content<'_> {
+    fn op(&mut self, text: &str) {
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn move_to(&mut self, p: Point) {
+        let p = self.config.point(p);
+        self.op(&format!("{p} m"));
+    }
+
+    fn line_to(&mut self, p: Point) {
+        let p = self.config.point(p);
+        self.op(&format!("{p} l"));
+    }
+
+    /// Subpaths of the path's pieces, without painting them
+    fn path(&mut self, path: &ChannelPath) {
+        let mut position = None;
+        for piece in &path.pieces {
+            if position != Some(piece.start()) {
+                self.move_to(piece.start());
+            }
+            match piece {
+                PathPiece::LineSegment(line) => self.line_to(line.end),
+                PathPiece::Arc(arc) => {
+                    for controls in beziers(arc) {
+                        let [a, b, c] = controls.map(|p| self.config.point(p));
+                        self.op(&format!("{a} {b} {c} c"));
+                    }
+                }
+            }
+            position = Some(piece.end());
+        }
+    }
+
+    fn line(&mut self, a: Point, b: Point) {
+        self.move_to(a);
+        self.line_to(b);
+        self.op("S");
+    }
+
+    fn rect(&mut self, rect: &Rect, operator: &str) {
+        let Rect {
+            min: Point([x0, y0]),
+            max: Point([x1, y1]),
+        } = self.config.coordinate_system.apply_rect(rect);
+        let n = |v| self.config.number(v);
+        let text = format!(
+            "{} {} {} {} re {operator}",
+            n(x0),
+            n(y0),
+            n(x1 - x0),
+            n(y1 - y0)
+        );
+        self.op(&text);
+    }
+
+    fn color(&mut self, color: Color, operator: &str) {
+        let [r, g, b, _] = color.0.map(|c| self.config.number(c as f64 / 255.));
+        self.op(&format!("{r} {g} {b} {operator}"));
+    }
+
+    /// Sets the colors of the paint; returns the painting operator of a closed outline, or None
+    /// if the paint draws nothing
+    fn paint(&mut self, paint: &Paint, fill_rule: FillRule) -> Option<&'static str> {
+        if let Some(fill) = paint.fill {
+            self.color(fill, "rg");
+        }
+        if let Some(stroke) = paint.stroke {
+            self.color(stroke, "RG");
+        }
+        let even_odd = fill_rule == FillRule::EvenOdd;
+        match (paint.fill.is_some(), paint.stroke.is_some(), even_odd) {
+            (true, true, false) => Some("b"),
+            (true, true, true) => Some("b*"),
+            (true, false, false) => Some("f"),
+            (true, false, true) => Some("f*"),
+            (false, true, _) => Some("s"),
+            (false, false, _) => None,
+        }
+    }
+
+    fn width(&mut self, width: f64) {
+        let width = self.config.length(width);
+        self.op(&format!("{width} w"));
+    }
+
+    /// Text with its baseline start at the position, rotated by the angle in radians
+    fn text(&mut self, position: Point, height: f64, angle: f64, text: &str, centered: bool) {
+        // Helvetica averages about half the font size per character
+        let shift = if centered {
+            0.25 * height * text.len() as f64
+        } else {
+            0.
+        };
+        let (cos, sin) = (angle.cos(), angle.sin());
+        let Point([x, y]) = position;
+        let start = Point([x - shift * cos, y - shift * sin]);
+        let n = |v| self.config.number(v);
+        let Point([px, py]) = self.config.coordinate_system.apply(start);
+        let size = self.config.length(height);
+        let text = format!(
+            "BT /F1 {size} Tf {} {} {} {} {} {} Tm ({}) Tj ET",
+            n(cos),
+            n(sin),
+            n(-sin),
+            n(cos),
+            n(px),
+            n(py),
+            escape(text)
+        );
+        self.op(&text);
+    }
+}
+
+/// Largest 1, 2, 5 × 10^k millimeters not exceeding the length
+fn nice_length(max: f64) -> f64 {
+    let magnitude = 10f64.powf(max.log10().floor());
+    [5., 2., 1.]
+        .into_iter()
+        .map(|f| f * magnitude)
+        .find(|l| *l <= max)
+        .unwrap_or(magnitude)
+}
+
+/// Exports the network as a single-page PDF at 1:1 scale
+pub fn network_to_pdf(network: &Network, config: &PdfConfig) -> Vec<u8> {
+    let _span = crate::trace::span!("network_to_pdf");
+    let units_per_mm = network
+        .units_per(LengthUnit::Millimeter)
+        .unwrap_or(config.units_per_mm);
+    let outline = config
+        .outline
+        .or_else(|| layout_bounds(network))
+        .unwrap_or(Rect {
+            min: Point([0., 0.]),
+            max: Point([units_per_mm, units_per_mm]),
+        });
+    let margin = config.margin * units_per_mm;
+    let page = outline.inflate(margin);
+    let render = RenderConfig {
+        coordinate_system: CoordinateSystem {
+            origin: page.min,
+            scale: POINTS_PER_MM / units_per_mm,
+            ..Default::default()
+        },
+        number_format: config.number_format,
+        ..Default::default()
+    };
+    let [page_width, page_height] = [0, 1].map(|i| {
+        render
+            .coordinate_system
+            .length(page.max.0[i] - page.min.0[i])
+    });
+
+    let mut content = Content {
+        out: String::new(),
+        config: &render,
+    };
+    let unit = units_per_mm;
+    let style = &config.style;
+    if let Some(background) = style.background {
+        content.color(background, "rg");
+        content.rect(&page, "f");
+    }
+    for (layer, name) in LAYERS.iter().enumerate() {
+        content.op(&format!("/OC /L{layer} BDC"));
+        match *name {
+            "Outline" => {
+                content.width(0.2 * unit);
+                content.rect(&outline, "S");
+            }
+            "Modules" => {
+                for module in &network.modules {
+                    let paint = style.paint(network, EntityRef::Module(module.id));
+                    content.width(paint.stroke_width.unwrap_or(0.1 * unit));
+                    let Some(operator) = content.paint(&paint, style.fill_rule) else {
+                        continue;
+                    };
+                    if module.is_plain_rectangle() {
+                        content.rect(&module.bounding_box(), operator);
+                        continue;
+                    }
+                    let outline = module.outline();
+                    for (i, p) in outline.0.iter().enumerate() {
+                        match i {
+                            0 => content.move_to(*p),
+                            _ => content.line_to(*p),
+                        }
+                    }
+                    content.op(operator);
+                }
+            }
+            "Channels" => {
+                content.op("1 J 1 j");
+                for channel in &network.channels {
+                    let Some(path) = &channel.path else {
+                        continue;
+                    };
+                    let paint = style.paint(network, EntityRef::Channel(channel.id));
+                    let Some(stroke) = paint.stroke else {
+                        continue;
+                    };
+                    content.color(stroke, "RG");
+                    content.width(channel.shape.width());
+                    content.path(path);
+                    content.op(if path.closed { "s" } else { "S" });
+                }
+            }
+            "Markings" => {
+                let paint = &style.markings;
+                for marking in &network.markings {
+                    let operator = match (marking.path.closed, paint.fill, paint.stroke) {
+                        (true, Some(fill), _) => {
+                            content.color(fill, "rg");
+                            "f"
+                        }
+                        (false, _, Some(stroke)) => {
+                            content.color(stroke, "RG");
+                            content.width(marking.width);
+                            "S"
+                        }
+                        _ => continue,
+                    };
+                    content.path(&marking.path);
+                    content.op(operator);
+                }
+            }
+            "Annotations" => {
+                let annotations = &style.annotations;
+                content.color(annotations.fill.unwrap_or(Color::BLACK), "rg");
+                content.color(annotations.stroke.unwrap_or(Color::BLACK), "RG");
+                for annotation in &network.annotations {
+                    match annotation {
+                        Annotation::Label(label) => {
+                            if let Some(anchor) = network.resolve_anchor(&label.anchor) {
+                                let [dx, dy] = label.offset.0;
+                                let position = anchor.translated(Point([dx, dy]));
+                                content.text(position, label.height, 0., &label.text, false);
+                            }
+                        }
+                        Annotation::Dimension(dimension) => {
+                            if let Some(geometry) =
+                                dimension.geometry(network, &render.number_format)
+                            {
+                                let width = annotations.stroke_width;
+                                content.width(width.unwrap_or(dimension.height / 10.));
+                                for (a, b) in geometry.extensions.iter().chain([&geometry.line]) {
+                                    content.line(*a, *b);
+                                }
+                                content.text(
+                                    geometry.text_position,
+                                    dimension.height,
+                                    geometry.text_angle,
+                                    &geometry.text,
+                                    true,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {
+                // Scale bar bottom left and title block bottom right, both in the margin
+                content.op("0 g 0 G");
+                let base = page.min.0[1] + margin / 3.;
+                let text_height = 2.5 * unit;
+                if config.scale_bar {
+                    let length = nice_length(0.25 * (outline.max.0[0] - outline.min.0[0]) / unit);
+                    let x = outline.min.0[0];
+                    let bar = Rect {
+                        min: Point([x, base]),
+                        max: Point([x + length * unit, base + unit]),
+                    };
+                    content.rect(&bar, "f");
+                    content.text(
+                        Point([x, base + 1.5 * unit]),
+                        text_height,
+                        0.,
+                        &format!("{length} mm"),
+                        false,
+                    );
+                }
+                let block = Rect {
+                    min: Point([outline.max.0[0] - 60. * unit, base]),
+                    max: Point([outline.max.0[0], base + 8. * unit]),
+                };
+                content.width(0.2 * unit);
+                content.rect(&block, "S");
+                let left = block.min.0[0] + unit;
+                content.text(
+                    Point([left, base + 4.5 * unit]),
+                    text_height,
+                    0.,
+                    &config.title,
+                    false,
+                );
+                content.text(
+                    Point([left, base + unit]),
+                    text_height,
+                    0.,
+                    "Scale 1:1 - print without scaling",
+                    false,
+                );
+            }
+        }
+        content.op("EMC");
+    }
+
+    let layer_refs: Vec<String> = (0..LAYERS.len())
+        .map(|i| format!("{} 0 R", 7 + i))
+        .collect();
+    let mut objects = vec![
+        format!(
+            "<< /Type /Catalog /Pages 2 0 R /OCProperties << /OCGs [{0}] /D << /Order [{0}] >> >> >>",
+            layer_refs.join(" ")
+        ),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> /Properties << {} >> >> >>",
+            render.number(page_width),
+            render.number(page_height),
+            (0..LAYERS.len())
+                .map(|i| format!("/L{i} {}", layer_refs[i]))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.out.len(),
+            content.out
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_string(),
+        format!("<< /Title ({}) /Producer (mmft-framework) >>", escape(&config.title)),
+    ];
+    objects.extend(
+        LAYERS
+            .iter()
+            .map(|name| format!("<< /Type /OCG /Name ({name}) >>")),
+    );
+
+    let mut pdf = String::from("%PDF-1.5\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        writeln!(pdf, "{} 0 obj\n{object}\nendobj", i + 1).unwrap();
+    }
+    let xref = pdf.len();
+    writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1).unwrap();
+    for offset in offsets {
+        writeln!(pdf, "{offset:010} 00000 n ").unwrap();
+    }
+    write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 6 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    )
+    .unwrap();
+    pdf.into_bytes()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quarter_circle_bezier() {
+        let arc = Arc {
+            start: Point([1., 0.]),
+            end: Point([0., 1.]),
+            center: Point([0., 0.]),
+            right: false,
+        };
+        let [[a, b, c]] = beziers(&arc)[..] else {
+            panic!("expected a single segment");
+        };
+        let k = 4. / 3. * (2f64.sqrt() - 1.);
+        let close = |p: Point, q: [f64; 2]| (p.0[0] - q[0]).abs() + (p.0[1] - q[1]).abs() < 1e-12;
+        assert!(close(a, [1., k]) && close(b, [k, 1.]) && close(c, [0., 1.]));
+        assert_eq!(beziers(&Arc { right: true, ..arc }).len(), 3);
+    }
+
+    #[test]
+    fn document_structure() {
+        let network = Network::random(&Default::default());
+        let pdf = String::from_utf8(network_to_pdf(&network, &PdfConfig::default())).unwrap();
+        assert!(pdf.starts_with("%PDF-1.5"));
+        let xref: usize = pdf.lines().rev().nth(1).unwrap().parse().unwrap();
+        assert!(pdf[xref..].starts_with("xref"));
+        // The object offsets in the cross-reference table point at the objects
+        for (i, line) in pdf[xref..].lines().skip(3).take(11).enumerate() {
+            let offset: usize = line[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+        assert_eq!(pdf.matches("/Type /OCG").count(), LAYERS.len());
+    }
+}
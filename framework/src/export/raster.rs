@@ -0,0 +1,349 @@
+//! Raster rendering to PNG for thumbnails and visual checks. Shapes are drawn with coverage
+//! from their exact distance fields, which anti-aliases edges without a rasterization library;
+//! text isn't rendered.
+
+use super::{
+    checksum::crc32,
+    layout_bounds,
+    style::{Color, FillRule, Style},
+    ExportError,
+};
+use crate::base::{
+    annotation::Annotation,
+    channel::{LineSegment, PathPiece},
+    network::{EntityRef, Network},
+    polygon::Polygon,
+    primitives::{Point, Rect},
+    render::NumberFormat,
+    units::LengthUnit,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+/// Resolution and appearance of the image
+pub struct RasterConfig {
+    /// Pixels per inch
+    pub dpi: f64,
+
+    /// Layout units per inch, 25400 for micrometers; the network's declared length unit takes
+    /// precedence
+    pub units_per_inch: f64,
+
+    /// Blank border around the layout in layout units
+    pub margin: f64,
+
+    /// Largest number of pixels to allocate, 4 bytes each
+    pub max_pixels: usize,
+
+    pub style: Style,
+}
+
+impl Default for RasterConfig {
+    fn default() -> Self {
+        RasterConfig {
+            dpi: 600.,
+            units_per_inch: 25400.,
+            margin: 500.,
+            max_pixels: 1 << 26,
+            style: Style::default(),
+        }
+    }
+}
+
+/// RGBA image with rows from top to bottom
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    fn blend(&mut self, x: usize, y: usize, color: [u8; 4], coverage: f64) {
+        let alpha = coverage.clamp(0., 1.) * color[3] as f64 / 255.;
+        let pixel = &mut self.pixels[y * self.width + x];
+        for c in 0..3 {
+            pixel[c] = (pixel[c] as f64 * (1. - alpha) + color[c] as f64 * alpha).round() as u8;
+        }
+        pixel[3] = pixel[3].max((alpha * 255.).round() as u8);
+    }
+
+    /// Encodes the image as PNG with uncompressed deflate blocks
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut raw = Vec::with_capacity(self.height * (4 * self.width + 1));
+        for row in self.pixels.chunks(self.width) {
+            raw.push(0);
+            raw.extend(row.iter().flatten());
+        }
+
+        let mut zlib = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(u16::MAX as usize).peekable();
+        if blocks.peek().is_none() {
+            zlib.extend([1, 0, 0, 0xFF, 0xFF]);
+        }
+        while let Some(block) = blocks.next() {
+            zlib.push(blocks.peek().is_none() as u8);
+            let len = block.len() as u16;
+            zlib.extend(len.to_le_bytes());
+            zlib.extend((!len).to_le_bytes());
+            zlib.extend(block);
+        }
+        zlib.extend(adler32(&raw).to_be_bytes());
+
+        let mut png = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        header.extend([8, 6, 0, 0, 0]);
+        chunk(&mut png, b"IHDR", &header);
+        chunk(&mut png, b"IDAT", &zlib);
+        chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    png.extend(kind);
+    png.extend(data);
+    png.extend(crc32(kind.iter().chain(data).copied()).to_be_bytes());
+}
+
+struct Canvas {
+    image: Image,
+    origin: Point,
+    /// Layout units per pixel
+    pixel: f64,
+}
+
+impl Canvas {
+    /// Pixel centers within the layout rectangle with their layout coordinates
+    fn pixels(&self, rect: &Rect) -> impl Iterator<Item = (usize, usize, Point)> + '_ {
+        let Point([ox, oy]) = self.origin;
+        let column = |x: f64| ((x - ox) / self.pixel).floor().max(0.) as usize;
+        let row = |y: f64| ((oy - y) / self.pixel).floor().max(0.) as usize;
+        let columns = column(rect.min.0[0])..(column(rect.max.0[0]) + 1).min(self.image.width);
+        let rows = row(rect.max.0[1])..(row(rect.min.0[1]) + 1).min(self.image.height);
+        rows.flat_map(move |y| {
+            columns.clone().map(move |x| {
+                let center = Point([
+                    ox + (x as f64 + 0.5) * self.pixel,
+                    oy - (y as f64 + 0.5) * self.pixel,
+                ]);
+                (x, y, center)
+            })
+        })
+    }
+
+    /// Draws everything within half the width of the distance field's zero set
+    fn stroke(
+        &mut self,
+        bounds: Rect,
+        width: f64,
+        color: [u8; 4],
+        distance: impl Fn(Point) -> f64,
+    ) {
+        let half = width.max(self.pixel) / 2.;
+        let covered: Vec<_> = self
+            .pixels(&bounds.inflate(half + self.pixel))
+            .map(|(x, y, p)| (x, y, 0.5 + (half - distance(p)) / self.pixel))
+            .filter(|(_, _, coverage)| *coverage > 0.)
+            .collect();
+        for (x, y, coverage) in covered {
+            self.image.blend(x, y, color, coverage);
+        }
+    }
+
+    /// Fills the pixels whose centers are inside the polygon
+    fn fill_polygon(&mut self, polygon: &Polygon, color: [u8; 4], rule: FillRule) {
+        let Some(bounds) = polygon.bounding_box() else {
+            return;
+        };
+        let inside = |p: Point| match rule {
+            FillRule::NonZero => polygon.contains(p),
+            FillRule::EvenOdd => polygon.on_boundary(p) || polygon.winding_number(p) % 2 != 0,
+        };
+        let covered: Vec<_> = self
+            .pixels(&bounds)
+            .filter(|(_, _, p)| inside(*p))
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        for (x, y) in covered {
+            self.image.blend(x, y, color, 1.);
+        }
+    }
+
+    fn fill_rect(&mut self, rect: &Rect, color: [u8; 4]) {
+        let Rect { min, max } = *rect;
+        let covered: Vec<_> = self
+            .pixels(rect)
+            .map(|(x, y, Point([px, py]))| {
+                let inside = (px - min.0[0])
+                    .min(max.0[0] - px)
+                    .min(py - min.0[1])
+                    .min(max.0[1] - py);
+                (x, y, 0.5 + inside / self.pixel)
+            })
+            .collect();
+        for (x, y, coverage) in covered {
+            self.image.blend(x, y, color, coverage);
+        }
+    }
+}
+
+fn piece_distance(piece: &PathPiece, point: Point) -> f64 {
+    match piece {
+        PathPiece::Arc(arc) => arc.distance(point),
+        PathPiece::LineSegment(line) => line.distance(point),
+    }
+}
+
+/// Draws modules, channels, and dimension lines; fails if the image would have more than the
+/// configured maximum of pixels
+pub fn network_to_image(network: &Network, config: &RasterConfig) -> Result<Image, ExportError> {
+    let _span = crate::trace::span!("network_to_image");
+    let bounds = layout_bounds(network)
+        .unwrap_or(Rect {
+            min: Point([0., 0.]),
+            max: Point([0., 0.]),
+        })
+        .inflate(config.margin);
+    let units_per_inch = network
+        .units_per(LengthUnit::Inch)
+        .unwrap_or(config.units_per_inch);
+    let pixel = units_per_inch / config.dpi;
+    let size = |extent: f64| ((extent / pixel).ceil() as usize).max(1);
+    let (width, height) = (
+        size(bounds.max.0[0] - bounds.min.0[0]),
+        size(bounds.max.0[1] - bounds.min.0[1]),
+    );
+    if width.saturating_mul(height) > config.max_pixels {
+        return Err(ExportError::ImageTooLarge { width, height });
+    }
+    let style = &config.style;
+    let background = style.background.unwrap_or(Color::WHITE).0;
+    let mut canvas = Canvas {
+        image: Image {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        },
+        origin: Point([bounds.min.0[0], bounds.max.0[1]]),
+        pixel,
+    };
+
+    for module in &network.modules {
+        let paint = style.paint(network, EntityRef::Module(module.id));
+        let outline = module.outline();
+        if let Some(Color(fill)) = paint.fill {
+            match module.is_plain_rectangle() {
+                true => canvas.fill_rect(&module.bounding_box(), fill),
+                false => canvas.fill_polygon(&outline, fill, style.fill_rule),
+            }
+        }
+        let Some(Color(stroke)) = paint.stroke else {
+            continue;
+        };
+        let width = paint.stroke_width.unwrap_or(pixel);
+        for (start, end) in outline.edges() {
+            let edge = LineSegment { start, end };
+            canvas.stroke(edge.bounding_box(), width, stroke, |p| edge.distance(p));
+        }
+    }
+
+    for channel in &network.channels {
+        let paint = style.paint(network, EntityRef::Channel(channel.id));
+        if let (Some(path), Some(Color(stroke))) = (&channel.path, paint.stroke) {
+            for piece in &path.pieces {
+                canvas.stroke(piece.bounding_box(), channel.shape.width(), stroke, |p| {
+                    piece_distance(piece, p)
+                });
+            }
+        }
+    }
+
+    let dimension_color = style.annotations.stroke.unwrap_or(Color::BLACK).0;
+    let width = style.annotations.stroke_width.unwrap_or(pixel);
+    for annotation in &network.annotations {
+        if let Annotation::Dimension(dimension) = annotation {
+            if let Some(geometry) = dimension.geometry(network, &NumberFormat::default()) {
+                for (a, b) in geometry.extensions.iter().chain([&geometry.line]) {
+                    let line = LineSegment { start: *a, end: *b };
+                    canvas.stroke(line.bounding_box(), width, dimension_color, |p| {
+                        line.distance(p)
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(canvas.image)
+}
+
+/// Renders the network to a PNG file's bytes
+pub fn network_to_png(network: &Network, config: &RasterConfig) -> Result<Vec<u8>, ExportError> {
+    Ok(network_to_image(network, config)?.to_png())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn straight_channel() {
+        let network = Network::random(&crate::base::generator::RandomNetworkSpec {
+            topology: crate::base::generator::Topology::Grid {
+                columns: 2,
+                rows: 1,
+            },
+            width: [100., 100.],
+            ..Default::default()
+        });
+        let config = RasterConfig {
+            dpi: 254.,
+            ..Default::default()
+        };
+        let image = network_to_image(&network, &config).unwrap();
+        // 100 µm per pixel: 2.1 mm wide channel outline plus 0.5 mm margins on each side
+        assert_eq!((image.width, image.height), (31, 11));
+        assert_eq!(
+            image.pixels[5 * 31 + 15],
+            config.style.channels.stroke.unwrap().0
+        );
+        assert_eq!(image.pixels[3 * 31 + 15], Color::WHITE.0);
+        assert_eq!(image.pixels[0], Color::WHITE.0);
+
+        let png = image.to_png();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+
+        // An image beyond the pixel budget fails instead of allocating
+        let config = RasterConfig {
+            max_pixels: 31 * 11 - 1,
+            ..config
+        };
+        assert_eq!(
+            network_to_png(&network, &config),
+            Err(ExportError::ImageTooLarge {
+                width: 31,
+                height: 11
+            })
+        );
+    }
+}
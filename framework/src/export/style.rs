@@ -0,0 +1,318 @@
+//! Appearance of rendered figures, shared by the SVG, PNG, and PDF renderers: colors and stroke
+//! widths per kind of entity, the fill rule of outlines, and overrides for groups of entities.
+
+use crate::base::network::{EntityRef, Network};
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Schema, SchemaObject, StringValidation},
+    JsonSchema,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// RGBA color, serialized as a hex code "#rrggbb" or "#rrggbbaa"
+pub struct Color(pub [u8; 4]);
+
+impl Color {
+    pub const BLACK: Color = Color([0, 0, 0, 255]);
+    pub const WHITE: Color = Color([255, 255, 255, 255]);
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color([r, g, b, 255])
+    }
+
+    /// Hex code without the alpha channel
+    pub fn rgb_hex(&self) -> String {
+        let [r, g, b, _] = self.0;
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+
+    /// Alpha channel in [0, 1]
+    pub fn opacity(&self) -> f64 {
+        self.0[3] as f64 / 255.
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0[3] {
+            255 => write!(f, "{}", self.rgb_hex()),
+            alpha => write!(f, "{}{alpha:02x}", self.rgb_hex()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A string that isn't a hex color code
+pub struct ColorError(pub String);
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} is not a color of the form #rgb, #rrggbb, or #rrggbbaa",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ColorError {}
+
+impl FromStr for Color {
+    type Err = ColorError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ColorError(text.into());
+        let digits = text.strip_prefix('#').ok_or_else(error)?;
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(error());
+        }
+        let channel = |i: usize, width: usize| {
+            let value = u8::from_str_radix(&digits[i * width..(i + 1) * width], 16).unwrap();
+            if width == 1 {
+                value * 17
+            } else {
+                value
+            }
+        };
+        match digits.len() {
+            3 => Ok(Color([channel(0, 1), channel(1, 1), channel(2, 1), 255])),
+            6 | 8 => Ok(Color([
+                channel(0, 2),
+                channel(1, 2),
+                channel(2, 2),
+                if digits.len() == 8 {
+                    channel(3, 2)
+                } else {
+                    255
+                },
+            ])),
+            _ => Err(error()),
+        }
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl JsonSchema for Color {
+    fn schema_name() -> String {
+        "Color".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        Schema::Object(SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                pattern: Some("^#([0-9a-fA-F]{3}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})$".into()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Colors and stroke width of an entity; unset properties aren't drawn, or fall back to the
+/// underlying paint when used as an override
+pub struct Paint {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fill: Option<Color>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke: Option<Color>,
+
+    /// Stroke width in layout units, the renderer's default if not set; channels are always
+    /// stroked with their width
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stroke_width: Option<f64>,
+}
+
+impl Paint {
+    /// The set properties of this paint on top of the base paint
+    pub fn over(&self, base: &Paint) -> Paint {
+        Paint {
+            fill: self.fill.or(base.fill),
+            stroke: self.stroke.or(base.stroke),
+            stroke_width: self.stroke_width.or(base.stroke_width),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Rule deciding which points are inside a self-overlapping outline
+pub enum FillRule {
+    /// Points the outline winds around at least once
+    #[default]
+    NonZero,
+
+    /// Points the outline winds around an odd number of times
+    EvenOdd,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Appearance of a rendered network
+pub struct Style {
+    /// Color behind the layout, transparent in vector output if not set; raster images are
+    /// white then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<Color>,
+
+    /// Channels are drawn with the stroke color
+    pub channels: Paint,
+
+    /// Module outlines
+    pub modules: Paint,
+
+    /// Port holes
+    pub ports: Paint,
+
+    /// Labels are drawn with the fill color, dimension lines with the stroke color
+    pub annotations: Paint,
+
+    /// Closed markings are drawn with the fill color, open ones with the stroke color
+    #[serde(default = "Style::markings")]
+    pub markings: Paint,
+
+    /// Fill rule of module outlines
+    #[serde(default)]
+    pub fill_rule: FillRule,
+
+    /// Paints of groups by name, applied over the paint of the entity's kind; the first group
+    /// of an entity takes precedence
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, Paint>,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        let gray = Color::rgb(0x33, 0x33, 0x33);
+        Style {
+            background: None,
+            channels: Paint {
+                fill: None,
+                stroke: Some(Color::rgb(0x1f, 0x77, 0xb4)),
+                stroke_width: None,
+            },
+            modules: Paint {
+                fill: Some(Color::rgb(0xdd, 0xdd, 0xdd)),
+                stroke: Some(gray),
+                stroke_width: None,
+            },
+            ports: Paint {
+                fill: Some(Color::WHITE),
+                stroke: Some(gray),
+                stroke_width: None,
+            },
+            annotations: Paint {
+                fill: Some(Color::BLACK),
+                stroke: Some(Color::BLACK),
+                stroke_width: None,
+            },
+            markings: Style::markings(),
+            fill_rule: FillRule::NonZero,
+            groups: BTreeMap::new(),
+        }
+    }
+}
+
+impl Style {
+    fn markings() -> Paint {
+        let gray = Color::rgb(0x88, 0x88, 0x88);
+        Paint {
+            fill: Some(gray),
+            stroke: Some(gray),
+            stroke_width: None,
+        }
+    }
+
+    /// Black channels for printed documents, otherwise the default style
+    pub fn print() -> Style {
+        let mut style = Style::default();
+        style.channels.stroke = Some(Color::BLACK);
+        style
+    }
+
+    /// Paint of an entity's kind; nodes are drawn as ports
+    pub fn kind(&self, entity: EntityRef) -> &Paint {
+        match entity {
+            EntityRef::Node(_) => &self.ports,
+            EntityRef::Channel(_) => &self.channels,
+            EntityRef::Module(_) => &self.modules,
+        }
+    }
+
+    /// Paint of an entity: its kind's paint overridden by its groups. A group's color sets the
+    /// stroke of channels and the fill of other entities, the group's paint in the style
+    /// overrides that in turn.
+    pub fn paint(&self, network: &Network, entity: EntityRef) -> Paint {
+        let groups: Vec<_> = network.groups_of(entity).collect();
+        let mut paint = *self.kind(entity);
+        for group in groups.into_iter().rev() {
+            if let Some(color) = group.color.as_ref().and_then(|c| c.parse().ok()) {
+                match entity {
+                    EntityRef::Channel(_) => paint.stroke = Some(color),
+                    _ => paint.fill = Some(color),
+                }
+            }
+            if let Some(group_paint) = self.groups.get(&group.name) {
+                paint = group_paint.over(&paint);
+            }
+        }
+        paint
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn colors_and_overrides() {
+        assert_eq!("#1F77b4".parse(), Ok(Color::rgb(0x1f, 0x77, 0xb4)));
+        assert_eq!("#fff".parse(), Ok(Color::WHITE));
+        assert_eq!(Color([1, 2, 3, 128]).to_string(), "#01020380");
+        assert!("1f77b4".parse::<Color>().is_err());
+        assert!("#12345".parse::<Color>().is_err());
+
+        let mut network = Network::default();
+        network.add_to_group("highlight", EntityRef::Channel(0));
+        network.add_to_group("faded", EntityRef::Channel(0));
+        network.groups[0].color = Some("#ff0000".into());
+        let mut style = Style::default();
+        assert_eq!(
+            style.paint(&network, EntityRef::Channel(0)).stroke,
+            Some(Color::rgb(255, 0, 0))
+        );
+        style.groups.insert(
+            "faded".into(),
+            Paint {
+                stroke: Some(Color([0, 0, 0, 64])),
+                stroke_width: Some(2.),
+                ..Default::default()
+            },
+        );
+        let paint = style.paint(&network, EntityRef::Channel(0));
+        assert_eq!(paint.stroke, Some(Color::rgb(255, 0, 0)));
+        assert_eq!(paint.stroke_width, Some(2.));
+        assert_eq!(style.paint(&network, EntityRef::Channel(1)), style.channels);
+
+        let json = serde_json::to_string(&style).unwrap();
+        assert!(json.contains(r##""stroke":"#00000040""##));
+        assert_eq!(serde_json::from_str::<Style>(&json).unwrap(), style);
+    }
+}
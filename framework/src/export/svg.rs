@@ -0,0 +1,486 @@
+//! SVG document export. Layout coordinates use a mathematical Y axis and are mapped to SVG's
+//! downward Y axis by the render configuration, so that the drawing appears upright.
+
+use super::{
+    layout_bounds,
+    style::{Color, FillRule, Paint, Style},
+};
+use crate::{
+    base::{
+        annotation::Annotation,
+        channel::SVGPath,
+        network::{EntityRef, Network, NodeId},
+        primitives::{Point, Rect},
+        render::RenderConfig,
+        units::LengthUnit,
+    },
+    dmf::{Cell, DmfChip},
+    parallel,
+};
+use std::fmt::Write;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn text(
+    out: &mut String,
+    config: &RenderConfig,
+    position: Point,
+    angle: f64,
+    height: f64,
+    content: &str,
+    anchor: &str,
+) {
+    let Point([x, y]) = config.coordinate_system.apply(position);
+    let (x, y) = (config.number(x), config.number(y));
+    // SVG rotates clockwise for positive angles on its downward Y axis
+    let angle = -config.coordinate_system.angle(angle).to_degrees();
+    let rotation = if angle == 0. {
+        String::new()
+    } else {
+        format!(r#" transform="rotate({} {x} {y})""#, config.number(angle))
+    };
+    writeln!(
+        out,
+        r#"<text x="{x}" y="{y}" font-size="{}" text-anchor="{anchor}"{rotation}>{}</text>"#,
+        config.length(height),
+        escape(content)
+    )
+    .unwrap();
+}
+
+/// Opens the document with a view box around the layout rectangle. With a known output unit,
+/// the document gets the physical size of the view box, so that a user unit is one output unit.
+fn header(
+    out: &mut String,
+    config: &RenderConfig,
+    bounds: &Rect,
+    margin: f64,
+    unit: Option<LengthUnit>,
+) {
+    let Rect {
+        min: Point([x0, y0]),
+        max: Point([x1, y1]),
+    } = config.coordinate_system.apply_rect(&bounds.inflate(margin));
+    let size = match unit.map(LengthUnit::css) {
+        Some((symbol, factor)) => format!(
+            r#" width="{}{symbol}" height="{}{symbol}""#,
+            config.number((x1 - x0) * factor),
+            config.number((y1 - y0) * factor)
+        ),
+        None => String::new(),
+    };
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg"{size} viewBox="{} {} {} {}">"#,
+        config.number(x0),
+        config.number(y0),
+        config.number(x1 - x0),
+        config.number(y1 - y0)
+    )
+    .unwrap();
+}
+
+/// Writes a rect element covering the layout rectangle
+fn rect(out: &mut String, config: &RenderConfig, id: &str, rect: &Rect, attributes: &str) {
+    let Rect {
+        min: Point([x, y]),
+        max: Point([mx, my]),
+    } = config.coordinate_system.apply_rect(rect);
+    writeln!(
+        out,
+        r#"<rect id="{id}" x="{}" y="{}" width="{}" height="{}"{attributes}/>"#,
+        config.number(x),
+        config.number(y),
+        config.number(mx - x),
+        config.number(my - y)
+    )
+    .unwrap();
+}
+
+/// Presentation attribute of a color, with its opacity if translucent
+fn color_attribute(out: &mut String, name: &str, color: Option<Color>) {
+    match color {
+        Some(color) => {
+            write!(out, r#" {name}="{}""#, color.rgb_hex()).unwrap();
+            if color.0[3] < 255 {
+                write!(out, r#" {name}-opacity="{}""#, color.opacity()).unwrap();
+            }
+        }
+        None => write!(out, r#" {name}="none""#).unwrap(),
+    }
+}
+
+/// Presentation attributes of the paint's set properties; an unset fill is written as none
+fn paint_attributes(paint: &Paint, config: &RenderConfig) -> String {
+    let mut attributes = String::new();
+    color_attribute(&mut attributes, "fill", paint.fill);
+    if paint.stroke.is_some() {
+        color_attribute(&mut attributes, "stroke", paint.stroke);
+    }
+    if let Some(width) = paint.stroke_width {
+        write!(attributes, r#" stroke-width="{}""#, config.length(width)).unwrap();
+    }
+    attributes
+}
+
+/// Class attribute of an entity's groups and the presentation attributes in which its paint
+/// differs from the paint of its kind
+fn entity_attributes(
+    network: &Network,
+    style: &Style,
+    entity: EntityRef,
+    config: &RenderConfig,
+) -> String {
+    let mut attributes = String::new();
+    let classes: Vec<String> = network
+        .groups_of(entity)
+        .map(|g| format!("group-{}", g.identifier()))
+        .collect();
+    if !classes.is_empty() {
+        write!(attributes, r#" class="{}""#, classes.join(" ")).unwrap();
+    }
+    let (paint, kind) = (style.paint(network, entity), style.kind(entity));
+    if paint.fill != kind.fill {
+        color_attribute(&mut attributes, "fill", paint.fill);
+    }
+    if paint.stroke != kind.stroke {
+        color_attribute(&mut attributes, "stroke", paint.stroke);
+    }
+    if paint.stroke_width != kind.stroke_width && !matches!(entity, EntityRef::Channel(_)) {
+        if let Some(width) = paint.stroke_width {
+            write!(attributes, r#" stroke-width="{}""#, config.length(width)).unwrap();
+        }
+    }
+    attributes
+}
+
+/// Writes the paths of the channels on the network layer, formatted in parallel
+fn channel_paths(
+    out: &mut String,
+    network: &Network,
+    style: &Style,
+    config: &RenderConfig,
+    layer: usize,
+) {
+    let channels: Vec<_> = network
+        .channels
+        .iter()
+        .filter(|c| c.layer == layer)
+        .collect();
+    let elements = parallel::map(&channels, |channel| {
+        let path = channel.path.as_ref()?;
+        Some(format!(
+            r#"<path id="channel-{}"{} stroke-width="{}" d="{}"/>"#,
+            channel.id,
+            entity_attributes(network, style, EntityRef::Channel(channel.id), config),
+            config.length(channel.shape.width()),
+            path.svg_path_command(config).trim_end()
+        ))
+    });
+    for element in elements.into_iter().flatten() {
+        writeln!(out, "{element}").unwrap();
+    }
+}
+
+/// Renders channels (stroked with their width), modules, markings, port holes, and annotations
+/// to an SVG document
+pub fn network_to_svg(network: &Network) -> String {
+    network_to_svg_with(network, &RenderConfig::y_down())
+}
+
+/// Renders the network with explicit output coordinates and number formatting
+pub fn network_to_svg_with(network: &Network, config: &RenderConfig) -> String {
+    network_to_svg_styled(network, config, &Style::default())
+}
+
+/// Renders the network with explicit output coordinates, number formatting, and style. Length
+/// units are converted as in [network_to_dxf_with](super::dxf::network_to_dxf_with); with a
+/// known output unit, the document gets its physical size.
+pub fn network_to_svg_styled(network: &Network, config: &RenderConfig, style: &Style) -> String {
+    let _span = crate::trace::span!("network_to_svg");
+    let (config, unit) = config.resolve(network.length_unit);
+    let config = &config;
+    let bounds = layout_bounds(network).unwrap_or(Rect {
+        min: Point([0., 0.]),
+        max: Point([1., 1.]),
+    });
+    let Rect {
+        min: Point([x0, y0]),
+        max: Point([x1, y1]),
+    } = bounds;
+    let margin = 0.05 * f64::max(x1 - x0, y1 - y0).max(1.);
+    let mut out = String::new();
+    header(&mut out, config, &bounds, margin, unit);
+    if let Some(background) = style.background {
+        let mut fill = String::new();
+        color_attribute(&mut fill, "fill", Some(background));
+        rect(
+            &mut out,
+            config,
+            "background",
+            &bounds.inflate(margin),
+            &fill,
+        );
+    }
+
+    let fill_rule = match style.fill_rule {
+        FillRule::NonZero => "",
+        FillRule::EvenOdd => r#" fill-rule="evenodd""#,
+    };
+    writeln!(
+        out,
+        r#"<g id="modules"{}{fill_rule}>"#,
+        paint_attributes(&style.modules, config)
+    )
+    .unwrap();
+    for module in &network.modules {
+        let id = format!("module-{}", module.id);
+        let attributes = entity_attributes(network, style, EntityRef::Module(module.id), config);
+        if !module.is_plain_rectangle() {
+            let points: Vec<String> = module
+                .outline()
+                .0
+                .iter()
+                .map(|p| config.point(*p))
+                .collect();
+            writeln!(
+                out,
+                r#"<polygon id="{id}" points="{}"{attributes}/>"#,
+                points.join(" ")
+            )
+            .unwrap();
+        } else {
+            rect(&mut out, config, &id, &module.bounding_box(), &attributes);
+        }
+    }
+    out.push_str("</g>\n");
+
+    let channels = Paint {
+        stroke_width: None,
+        ..style.channels
+    };
+    writeln!(
+        out,
+        r#"<g id="channels"{}>"#,
+        paint_attributes(&channels, config)
+    )
+    .unwrap();
+    channel_paths(&mut out, network, style, config, 0);
+    out.push_str("</g>\n");
+    // Channels of further network layers, e.g., control channels, in groups on top
+    let mut channel_layers: Vec<usize> = network.channels.iter().map(|c| c.layer).collect();
+    channel_layers.sort();
+    channel_layers.dedup();
+    for layer in channel_layers.into_iter().filter(|l| *l > 0) {
+        writeln!(
+            out,
+            r#"<g id="channels-l{layer}"{}>"#,
+            paint_attributes(&channels, config)
+        )
+        .unwrap();
+        channel_paths(&mut out, network, style, config, layer);
+        out.push_str("</g>\n");
+    }
+
+    // One group per marking layer, in order of appearance
+    let mut layers: Vec<&str> = Vec::new();
+    for marking in &network.markings {
+        if !layers.contains(&marking.layer.as_str()) {
+            layers.push(&marking.layer);
+        }
+    }
+    out.push_str("<g id=\"markings\">\n");
+    for layer in layers {
+        writeln!(out, r#"<g id="markings-{}">"#, escape(layer)).unwrap();
+        for marking in network.markings.iter().filter(|m| m.layer == layer) {
+            let mut attributes = String::new();
+            if marking.path.closed {
+                color_attribute(&mut attributes, "fill", style.markings.fill);
+            } else {
+                color_attribute(&mut attributes, "fill", None);
+                color_attribute(&mut attributes, "stroke", style.markings.stroke);
+                write!(
+                    attributes,
+                    r#" stroke-width="{}""#,
+                    config.length(marking.width)
+                )
+                .unwrap();
+            }
+            writeln!(
+                out,
+                r#"<path{attributes} d="{}"/>"#,
+                marking.path.svg_path_command(config).trim_end()
+            )
+            .unwrap();
+        }
+        out.push_str("</g>\n");
+    }
+    out.push_str("</g>\n");
+
+    writeln!(
+        out,
+        r#"<g id="ports"{}>"#,
+        paint_attributes(&style.ports, config)
+    )
+    .unwrap();
+    for (NodeId(id), position, port) in network.port_holes() {
+        if let Some(center) = position {
+            let Point([x, y]) = config.coordinate_system.apply(center);
+            writeln!(
+                out,
+                r#"<circle id="port-{id}"{} cx="{}" cy="{}" r="{}"/>"#,
+                entity_attributes(network, style, EntityRef::Node(NodeId(id)), config),
+                config.number(x),
+                config.number(y),
+                config.length(port.diameter / 2.)
+            )
+            .unwrap();
+        }
+    }
+    out.push_str("</g>\n");
+
+    let mut stroke = String::new();
+    color_attribute(&mut stroke, "stroke", style.annotations.stroke);
+    writeln!(out, r#"<g id="dimensions"{stroke} fill="none">"#).unwrap();
+    let dimensions: Vec<_> = network
+        .annotations
+        .iter()
+        .filter_map(|a| match a {
+            Annotation::Dimension(dimension) => dimension
+                .geometry(network, &config.number_format)
+                .map(|geometry| (dimension.height, geometry)),
+            Annotation::Label(_) => None,
+        })
+        .collect();
+    for (height, geometry) in &dimensions {
+        for (a, b) in geometry.extensions.iter().chain([&geometry.line]) {
+            let Point([ax, ay]) = config.coordinate_system.apply(*a);
+            let Point([bx, by]) = config.coordinate_system.apply(*b);
+            writeln!(
+                out,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke-width="{}"/>"#,
+                config.number(ax),
+                config.number(ay),
+                config.number(bx),
+                config.number(by),
+                config.length(style.annotations.stroke_width.unwrap_or(height / 10.))
+            )
+            .unwrap();
+        }
+    }
+    out.push_str("</g>\n");
+
+    let mut fill = String::new();
+    color_attribute(&mut fill, "fill", style.annotations.fill);
+    writeln!(
+        out,
+        r#"<g id="annotations"{fill} font-family="sans-serif">"#
+    )
+    .unwrap();
+    for annotation in &network.annotations {
+        if let Annotation::Label(label) = annotation {
+            if let Some(anchor) = network.resolve_anchor(&label.anchor) {
+                let [dx, dy] = label.offset.0;
+                text(
+                    &mut out,
+                    config,
+                    anchor.translated(Point([dx, dy])),
+                    0.,
+                    label.height,
+                    &label.text,
+                    "start",
+                );
+            }
+        }
+    }
+    for (height, geometry) in &dimensions {
+        text(
+            &mut out,
+            config,
+            geometry.text_position,
+            geometry.text_angle,
+            *height,
+            &geometry.text,
+            "middle",
+        );
+    }
+    out.push_str("</g>\n</svg>\n");
+    out
+}
+
+/// Renders the electrode grid of a DMF chip with the droplets after the given number of actuation
+/// steps; electrodes active in the following step are highlighted
+pub fn dmf_to_svg(chip: &DmfChip, step: usize) -> String {
+    dmf_to_svg_with(chip, step, &RenderConfig::y_down())
+}
+
+/// Renders a DMF chip with explicit output coordinates and number formatting
+pub fn dmf_to_svg_with(chip: &DmfChip, step: usize, config: &RenderConfig) -> String {
+    let grid = &chip.grid;
+    let bounds = Rect {
+        min: Point([0., 0.]),
+        max: Point([
+            grid.columns as f64 * grid.pitch,
+            grid.rows as f64 * grid.pitch,
+        ]),
+    };
+    let mut out = String::new();
+    header(&mut out, config, &bounds, 0.5 * grid.pitch, None);
+
+    let active = chip
+        .sequence
+        .get(step)
+        .map(|s| s.electrodes.as_slice())
+        .unwrap_or_default();
+    let size = 0.9 * grid.pitch;
+    out.push_str("<g id=\"electrodes\" stroke=\"#333333\">\n");
+    for row in 0..grid.rows {
+        for column in 0..grid.columns {
+            let cell = Cell([column, row]);
+            if !grid.contains(&cell) {
+                continue;
+            }
+            let fill = if active.contains(&cell) {
+                "#ffcc00"
+            } else {
+                "#dddddd"
+            };
+            let center = grid.center(&cell);
+            rect(
+                &mut out,
+                config,
+                &format!("electrode-{column}-{row}"),
+                &Rect {
+                    min: center,
+                    max: center,
+                }
+                .inflate(size / 2.),
+                &format!(r#" fill="{fill}""#),
+            );
+        }
+    }
+    out.push_str("</g>\n");
+
+    out.push_str("<g id=\"droplets\" fill=\"#1f77b4\" fill-opacity=\"0.8\">\n");
+    for droplet in chip.positions_at(step) {
+        let Point([x, y]) = config
+            .coordinate_system
+            .apply(grid.center(&droplet.position));
+        writeln!(
+            out,
+            r#"<circle id="droplet-{}" cx="{}" cy="{}" r="{}"/>"#,
+            droplet.id,
+            config.number(x),
+            config.number(y),
+            config.length(0.4 * grid.pitch)
+        )
+        .unwrap();
+    }
+    out.push_str("</g>\n</svg>\n");
+    out
+}
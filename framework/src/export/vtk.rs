@@ -0,0 +1,212 @@
+//! Legacy ASCII VTK polydata of simulation results on the layout, e.g., for ParaView. Every
+//! channel becomes a polyline along its centerline, arcs discretized into short chords. Node
+//! pressures are interpolated linearly along the channels, as in Poiseuille flow, and given as
+//! point data; flows and concentrations are constant per channel and given as cell data.
+
+use super::ExportError;
+use crate::{
+    base::{
+        channel::{ArcAngles, PathPiece},
+        network::Network,
+        primitives::Point,
+    },
+    simulation::result::SimulationResult,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, f64::consts::PI, fmt::Write};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Settings of the VTK export
+pub struct VtkConfig {
+    /// Largest angle in radians an arc sweeps per chord
+    pub max_chord_angle: f64,
+}
+
+impl Default for VtkConfig {
+    fn default() -> Self {
+        VtkConfig {
+            max_chord_angle: PI / 16.,
+        }
+    }
+}
+
+/// Centerline vertices of a piece without its end point
+fn vertices(piece: &PathPiece, config: &VtkConfig) -> Vec<Point> {
+    let chords = match piece {
+        PathPiece::Arc(arc) => {
+            let ArcAngles { sweep, .. } = arc.angles();
+            (sweep.abs() / config.max_chord_angle).ceil().max(1.) as usize
+        }
+        PathPiece::LineSegment(_) => 1,
+    };
+    (0..chords)
+        .map(|i| piece.point_at(i as f64 / chords as f64))
+        .collect()
+}
+
+/// Writes a scalar field of point or cell data
+fn scalars(out: &mut String, name: &str, values: impl IntoIterator<Item = f64>) {
+    writeln!(out, "SCALARS {name} double 1\nLOOKUP_TABLE default").unwrap();
+    for value in values {
+        writeln!(out, "{value}").unwrap();
+    }
+}
+
+/// Polydata of the channels with the pressures, flows, and concentrations of the result.
+/// Channels without a path are drawn straight between their nodes; quantities missing from the
+/// result are written as NaN.
+pub fn network_to_vtk(
+    network: &Network,
+    result: &SimulationResult,
+    config: &VtkConfig,
+) -> Result<String, ExportError> {
+    let _span = crate::trace::span!("network_to_vtk");
+    let mut points: Vec<Point> = Vec::new();
+    let mut pressures: Vec<f64> = Vec::new();
+    let mut lines: Vec<std::ops::Range<usize>> = Vec::new();
+    for channel in &network.channels {
+        let centerline = match &channel.path {
+            Some(path) if !path.pieces.is_empty() => {
+                let mut centerline: Vec<Point> = path
+                    .pieces
+                    .iter()
+                    .flat_map(|p| vertices(p, config))
+                    .collect();
+                centerline.push(path.pieces.last().unwrap().end());
+                centerline
+            }
+            _ => [channel.node_a, channel.node_b]
+                .iter()
+                .map(|n| {
+                    network
+                        .node_position(*n)
+                        .ok_or(ExportError::UnplacedNode(*n))
+                })
+                .collect::<Result<_, _>>()?,
+        };
+
+        // Pressure falls linearly with the distance along the centerline
+        let mut distances = vec![0.];
+        for w in centerline.windows(2) {
+            distances.push(distances.last().unwrap() + (w[1] - w[0]).length());
+        }
+        let total = *distances.last().unwrap();
+        let pressure = |n| result.pressures.get(&n).copied().unwrap_or(f64::NAN);
+        let (a, b) = (pressure(channel.node_a), pressure(channel.node_b));
+        let first = points.len();
+        for (point, distance) in centerline.into_iter().zip(distances) {
+            let t = if total > 0. { distance / total } else { 0. };
+            points.push(point);
+            pressures.push(a + (b - a) * t);
+        }
+        lines.push(first..points.len());
+    }
+
+    let mut out = String::new();
+    out.push_str("# vtk DataFile Version 3.0\nmmft-framework simulation result\nASCII\n");
+    out.push_str("DATASET POLYDATA\n");
+    writeln!(out, "POINTS {} double", points.len()).unwrap();
+    for Point([x, y]) in &points {
+        writeln!(out, "{x} {y} 0").unwrap();
+    }
+    let size: usize = lines.iter().map(|l| l.len() + 1).sum();
+    writeln!(out, "LINES {} {size}", lines.len()).unwrap();
+    for line in &lines {
+        let indices: Vec<String> = line.clone().map(|i| i.to_string()).collect();
+        writeln!(out, "{} {}", line.len(), indices.join(" ")).unwrap();
+    }
+
+    let per_channel = |values: &BTreeMap<usize, f64>| {
+        network
+            .channels
+            .iter()
+            .map(|c| values.get(&c.id).copied().unwrap_or(f64::NAN))
+            .collect::<Vec<_>>()
+    };
+    writeln!(out, "CELL_DATA {}", lines.len()).unwrap();
+    let ids = network.channels.iter().map(|c| c.id as f64);
+    scalars(&mut out, "channel", ids);
+    scalars(&mut out, "flow", per_channel(&result.flows));
+    if !result.concentrations.is_empty() {
+        scalars(
+            &mut out,
+            "concentration",
+            per_channel(&result.concentrations),
+        );
+    }
+    writeln!(out, "POINT_DATA {}", points.len()).unwrap();
+    scalars(&mut out, "pressure", pressures);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{Arc, Channel, ChannelPath, CylindricalShape, LineSegment, Shape},
+            network::{Metadata, Node, NodeId},
+        },
+        simulation::solver::FlowSolution,
+    };
+
+    #[test]
+    fn polydata() {
+        let mut path = ChannelPath::new();
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: Point([0., 0.]),
+            end: Point([10., 0.]),
+        }));
+        path.add(PathPiece::Arc(Arc::from_center_angles(
+            Point([10., 5.]),
+            5.,
+            -PI / 2.,
+            PI,
+        )));
+        let channel = |id, node_b, path| Channel {
+            id,
+            node_a: NodeId(0),
+            node_b: NodeId(node_b),
+            shape: Shape::Cylindrical(CylindricalShape { radius: 1. }),
+            path,
+            length: None,
+            layer: 0,
+            metadata: Metadata::new(),
+        };
+        let network = Network {
+            nodes: vec![
+                Node::at(NodeId(0), Point([0., 0.])),
+                Node::at(NodeId(1), Point([10., 10.])),
+                Node::at(NodeId(2), Point([0., -10.])),
+            ],
+            channels: vec![channel(0, 1, Some(path)), channel(1, 2, None)],
+            ..Default::default()
+        };
+        let mut result = SimulationResult::from(FlowSolution {
+            pressures: BTreeMap::from([(NodeId(0), 10.), (NodeId(1), 0.), (NodeId(2), 0.)]),
+            flows: BTreeMap::from([(0, 1.), (1, 2.)]),
+        });
+        result.concentrations.insert(1, 0.5);
+        let vtk = network_to_vtk(&network, &result, &VtkConfig::default()).unwrap();
+
+        // Line start, 16 arc chords, and the end; two points of the straight channel
+        assert!(vtk.contains("POINTS 20 double\n"));
+        assert!(vtk.contains("LINES 2 22\n18 0 1 2"));
+        assert!(vtk.contains("\n2 18 19\n"));
+        assert!(vtk.contains("SCALARS flow double 1\nLOOKUP_TABLE default\n1\n2\n"));
+        assert!(vtk.contains("SCALARS concentration double 1\nLOOKUP_TABLE default\nNaN\n0.5\n"));
+        let pressures: Vec<f64> = vtk
+            .split("SCALARS pressure double 1\nLOOKUP_TABLE default\n")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .map(|l| l.parse().unwrap())
+            .collect();
+        assert_eq!(pressures.len(), 20);
+        assert_eq!((pressures[0], pressures[17]), (10., 0.));
+        let total = 10. + 16. * 10. * (PI / 32.).sin();
+        assert!((pressures[1] - 10. * (1. - 10. / total)).abs() < 1e-12);
+    }
+}
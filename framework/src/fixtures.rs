@@ -0,0 +1,180 @@
+//! Representative networks for benchmarks and regression tests of the solver, routers, and
+//! exporters. Every fixture is deterministic and scales with its size parameters.
+
+use crate::{
+    base::{
+        channel::{Channel, ChannelPath, LineSegment, PathPiece, RectangularShape, Shape},
+        network::{Network, Node, NodeId},
+        primitives::Point,
+    },
+    designer::trap::{design_trap_array, TrapArrayParameters},
+    dmf::{routing::RouteRequest, Cell, DmfChip, Droplet, ElectrodeGrid},
+    simulation::solver::{Boundary, BoundaryCondition},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+/// Network with boundary conditions under which it can be solved
+pub struct Fixture {
+    pub network: Network,
+    pub boundaries: Vec<Boundary>,
+}
+
+fn pressure(node: NodeId, pressure: f64) -> Boundary {
+    Boundary {
+        node,
+        condition: BoundaryCondition::Pressure(pressure),
+    }
+}
+
+/// Hydrodynamic trap array of `rows` × `columns` units, every row driven by 100 Pa
+pub fn trap_array(rows: usize, columns: usize) -> Fixture {
+    let array = design_trap_array(TrapArrayParameters {
+        rows,
+        columns,
+        trap: RectangularShape {
+            width: 5e-6,
+            height: 20e-6,
+        },
+        trap_length: 50e-6,
+        channel: RectangularShape {
+            width: 30e-6,
+            height: 20e-6,
+        },
+        bypass_ratio: 2.,
+        spacing: 20e-6,
+    })
+    .unwrap();
+    let boundaries = (0..rows)
+        .flat_map(|r| {
+            [
+                pressure(array.inlets[r], 100.),
+                pressure(array.outlets[r], 0.),
+            ]
+        })
+        .collect();
+    Fixture {
+        network: array.network,
+        boundaries,
+    }
+}
+
+/// Christmas-tree gradient generator of `depth` mixing levels: two inlets at 100 Pa and 50 Pa
+/// feed level 0, every node of a level splits into the two closest nodes of the next level,
+/// which has one node more, and the nodes of the last level are outlets at 0 Pa
+pub fn gradient_tree(depth: usize) -> Fixture {
+    let pitch = 1e-3;
+    let shape = Shape::Rectangular(RectangularShape {
+        width: 100e-6,
+        height: 50e-6,
+    });
+    let mut network = Network::default();
+    let mut levels: Vec<Vec<NodeId>> = Vec::new();
+    for level in 0..=depth + 1 {
+        let count = level + 2;
+        let ids = (0..count)
+            .map(|j| {
+                let id = NodeId(network.nodes.len());
+                let x = (j as f64 - (count - 1) as f64 / 2.) * pitch;
+                network
+                    .nodes
+                    .push(Node::at(id, Point([x, -(level as f64) * pitch])));
+                id
+            })
+            .collect();
+        levels.push(ids);
+    }
+    for pair in levels.windows(2) {
+        let (upper, lower) = (&pair[0], &pair[1]);
+        for (j, node_a) in upper.iter().enumerate() {
+            for node_b in &lower[j..j + 2] {
+                let start = network.node_position(*node_a).unwrap();
+                let end = network.node_position(*node_b).unwrap();
+                network.channels.push(Channel {
+                    id: network.channels.len(),
+                    node_a: *node_a,
+                    node_b: *node_b,
+                    shape,
+                    path: Some(ChannelPath {
+                        pieces: vec![PathPiece::LineSegment(LineSegment { start, end })],
+                        closed: false,
+                    }),
+                    length: None,
+                    layer: 0,
+                    metadata: Default::default(),
+                });
+            }
+        }
+    }
+    let mut boundaries = vec![pressure(levels[0][0], 100.), pressure(levels[0][1], 50.)];
+    boundaries.extend(levels[depth + 1].iter().map(|n| pressure(*n, 0.)));
+    Fixture {
+        network,
+        boundaries,
+    }
+}
+
+/// Digital microfluidic chip with `pairs` pairs of droplets crossing each other in their own
+/// row band, one along the band and one across it; every crossing forces the second droplet
+/// to wait for the first
+pub fn droplet_crossings(pairs: usize) -> (DmfChip, Vec<RouteRequest>) {
+    let (columns, band) = (8, 6);
+    let mut droplets = Vec::new();
+    let mut requests = Vec::new();
+    for pair in 0..pairs {
+        let bottom = pair * band;
+        let moves = [
+            (Cell([0, bottom + 2]), Cell([columns - 1, bottom + 2])),
+            (Cell([columns / 2, bottom]), Cell([columns / 2, bottom + 4])),
+        ];
+        for (position, target) in moves {
+            let id = droplets.len();
+            droplets.push(Droplet {
+                id,
+                position,
+                volume: None,
+            });
+            requests.push(RouteRequest {
+                droplet: id,
+                target,
+            });
+        }
+    }
+    let chip = DmfChip {
+        grid: ElectrodeGrid {
+            columns,
+            rows: pairs * band,
+            pitch: 1.,
+            blocked: vec![],
+        },
+        droplets,
+        sequence: vec![],
+        metadata: Default::default(),
+    };
+    (chip, requests)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        dmf::routing::route,
+        simulation::{fluid::Fluid, solver::solve},
+    };
+
+    #[test]
+    fn fixtures_solve() {
+        let tree = gradient_tree(3);
+        assert_eq!(tree.network.nodes.len(), 2 + 3 + 4 + 5 + 6);
+        let solution = solve(&tree.network, &Fluid::water(), &tree.boundaries).unwrap();
+        // The last mixing level is graded from the side of the higher inlet pressure
+        let last = &tree.network.nodes[9..14];
+        let pressures: Vec<f64> = last.iter().map(|n| solution.pressures[&n.id]).collect();
+        assert!((0..2).all(|j| pressures[j] > pressures[4 - j]));
+
+        let traps = trap_array(3, 4);
+        assert!(solve(&traps.network, &Fluid::water(), &traps.boundaries).is_ok());
+
+        let (chip, requests) = droplet_crossings(2);
+        assert_eq!(route(&chip, &requests, 60).unwrap().trajectories.len(), 4);
+    }
+}
@@ -0,0 +1,155 @@
+//! Calling designer functions by name with JSON text, the common entry point of the command
+//! line, HTTP, and batch interfaces. [`dispatch_functions!`](crate::dispatch_functions) generates
+//! the registry and the dispatch function from a list of designer functions.
+
+use super::validation::{describe, from_json_validated};
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::{de::DeserializeOwned, Serialize};
+
+#[derive(Debug, Clone, PartialEq)]
+/// Reasons a designer function can't be called
+pub enum DispatchError {
+    /// No function is registered under the name
+    UnknownFunction(String),
+
+    /// The input doesn't match the function's input type, holds the validation messages
+    InvalidInput(String),
+}
+
+impl std::fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DispatchError::UnknownFunction(name) => write!(f, "unknown function {name}"),
+            DispatchError::InvalidInput(message) => write!(f, "invalid input:\n{message}"),
+        }
+    }
+}
+
+impl std::error::Error for DispatchError {}
+
+#[derive(Debug, Clone, Copy)]
+/// Designer function callable by name
+pub struct DesignerFunction {
+    pub name: &'static str,
+
+    /// Validates and deserializes the input, calls the function, and serializes the output
+    pub call: fn(&str) -> Result<String, DispatchError>,
+
+    pub input_schema: fn(&mut SchemaGenerator) -> Schema,
+    pub output_schema: fn(&mut SchemaGenerator) -> Schema,
+}
+
+/// Calls the function with the JSON input, validated against the schema of the input type
+pub fn call_json<I, O>(function: fn(I) -> O, json: &str) -> Result<String, DispatchError>
+where
+    I: JsonSchema + DeserializeOwned,
+    O: Serialize,
+{
+    let input = from_json_validated(json).map_err(|e| DispatchError::InvalidInput(describe(&e)))?;
+    Ok(serde_json::to_string(&function(input)).unwrap())
+}
+
+/// Schema of the function's input type
+pub fn input_schema<I: JsonSchema, O>(_: fn(I) -> O, gen: &mut SchemaGenerator) -> Schema {
+    gen.subschema_for::<I>()
+}
+
+/// Schema of the function's output type
+pub fn output_schema<I, O: JsonSchema>(_: fn(I) -> O, gen: &mut SchemaGenerator) -> Schema {
+    gen.subschema_for::<O>()
+}
+
+/// Calls the function registered under the name
+pub fn dispatch_in(
+    registry: &[DesignerFunction],
+    name: &str,
+    json: &str,
+) -> Result<String, DispatchError> {
+    let function = registry
+        .iter()
+        .find(|f| f.name == name)
+        .ok_or_else(|| DispatchError::UnknownFunction(name.into()))?;
+    (function.call)(json)
+}
+
+#[macro_export]
+/// Generates a `REGISTRY` of designer functions and a `dispatch(name, json)` function calling
+/// them by name. Every function takes a serde and `JsonSchema` compatible input by value and
+/// returns a serde and `JsonSchema` compatible output.
+///
+/// # Arguments
+///
+/// * `name => function` - call name and path of each function
+///
+/// # Examples
+///
+/// ```ignore
+/// mmft_framework::dispatch_functions!(
+///     create_meander => meander_designer_lib::meander_designer::create_meander,
+///     create_splitter => meander_designer_lib::splitter_designer::create_splitter,
+/// );
+/// ```
+macro_rules! dispatch_functions {
+    ($($name: ident => $function: path),* $(,)?) => {
+        /// Designer functions callable by name
+        pub const REGISTRY: &[$crate::interfaces::dispatch::DesignerFunction] = &[$(
+            $crate::interfaces::dispatch::DesignerFunction {
+                name: stringify!($name),
+                call: |json| $crate::interfaces::dispatch::call_json($function as fn(_) -> _, json),
+                input_schema: |gen| {
+                    $crate::interfaces::dispatch::input_schema($function as fn(_) -> _, gen)
+                },
+                output_schema: |gen| {
+                    $crate::interfaces::dispatch::output_schema($function as fn(_) -> _, gen)
+                },
+            },
+        )*];
+
+        /// Calls the designer function registered under the name with the JSON input and
+        /// returns its JSON output
+        pub fn dispatch(
+            name: &str,
+            json: &str,
+        ) -> Result<String, $crate::interfaces::dispatch::DispatchError> {
+            $crate::interfaces::dispatch::dispatch_in(REGISTRY, name, json)
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::designer::{self, droplet::DropletJunction};
+
+    #[test]
+    fn designers() {
+        let target = r#"{
+            "kind": "t_junction",
+            "volume": 1e-12,
+            "height": 5e-5,
+            "dispersed_flow": 1e-11,
+            "continuous_flow": 2e-11
+        }"#;
+        let output = designer::dispatch("suggest_junction", target).unwrap();
+        let junction: DropletJunction = serde_json::from_str(&output).unwrap();
+        assert_eq!(junction.alpha, 1.);
+        assert_eq!(junction.height, 5e-5);
+
+        assert_eq!(
+            designer::dispatch("create_mixer", target),
+            Err(DispatchError::UnknownFunction("create_mixer".into()))
+        );
+        assert!(matches!(
+            designer::dispatch("suggest_junction", r#"{"kind": "t_junction"}"#),
+            Err(DispatchError::InvalidInput(_))
+        ));
+
+        let mut gen = SchemaGenerator::default();
+        let function = designer::REGISTRY
+            .iter()
+            .find(|f| f.name == "design_trap_array")
+            .unwrap();
+        (function.input_schema)(&mut gen);
+        assert!(gen.definitions().contains_key("TrapArrayParameters"));
+    }
+}
@@ -0,0 +1,108 @@
+//! C ABI of registered designer functions for bindings from languages without a serde bridge.
+//! Inputs and outputs are NUL-terminated JSON strings; every returned string is owned by the
+//! library and released with the generated free function.
+
+use super::dispatch::{dispatch_in, DesignerFunction};
+use std::ffi::{c_char, CStr, CString};
+
+/// Calls the designer function and returns `{"ok": output}` or `{"error": message}`
+///
+/// # Safety
+///
+/// `name` and `json` must be valid NUL-terminated strings. The result must be released with
+/// [`free_string`].
+pub unsafe fn call(
+    registry: &[DesignerFunction],
+    name: *const c_char,
+    json: *const c_char,
+) -> *mut c_char {
+    let text = |pointer: *const c_char| CStr::from_ptr(pointer).to_string_lossy().into_owned();
+    let result = match dispatch_in(registry, &text(name), &text(json)) {
+        Ok(output) => {
+            serde_json::json!({ "ok": serde_json::from_str::<serde_json::Value>(&output).unwrap() })
+        }
+        Err(error) => serde_json::json!({ "error": error.to_string() }),
+    };
+    CString::new(result.to_string()).unwrap().into_raw()
+}
+
+/// Releases a string returned by [`call`]
+///
+/// # Safety
+///
+/// `string` must have been returned by [`call`] and not been released before, or be null.
+pub unsafe fn free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[macro_export]
+/// Generates C functions calling the designer functions of a registry by name, see
+/// `dispatch_functions!`
+///
+/// # Arguments
+///
+/// * `call_name` - name of the exported function taking the function name and the JSON input
+/// * `free_name` - name of the exported function releasing returned strings
+/// * `registry` - path of the registry
+///
+/// # Examples
+///
+/// ```ignore
+/// mmft_framework::ffi_dispatch!(mmft_call, mmft_free, mmft_framework::designer::REGISTRY);
+/// ```
+macro_rules! ffi_dispatch {
+    ($call_name: ident, $free_name: ident, $registry: path) => {
+        /// # Safety
+        ///
+        /// See `mmft_framework::interfaces::ffi::call`
+        #[no_mangle]
+        pub unsafe extern "C" fn $call_name(
+            name: *const std::ffi::c_char,
+            json: *const std::ffi::c_char,
+        ) -> *mut std::ffi::c_char {
+            $crate::interfaces::ffi::call($registry, name, json)
+        }
+
+        /// # Safety
+        ///
+        /// See `mmft_framework::interfaces::ffi::free_string`
+        #[no_mangle]
+        pub unsafe extern "C" fn $free_name(string: *mut std::ffi::c_char) {
+            $crate::interfaces::ffi::free_string(string)
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::designer::REGISTRY;
+    use std::ffi::{CStr, CString};
+
+    crate::ffi_dispatch!(test_call, test_free, REGISTRY);
+
+    #[test]
+    fn round_trip() {
+        let output = |name: &str, json: &str| {
+            let (name, json) = (CString::new(name).unwrap(), CString::new(json).unwrap());
+            unsafe {
+                let result = test_call(name.as_ptr(), json.as_ptr());
+                let value: serde_json::Value =
+                    serde_json::from_str(CStr::from_ptr(result).to_str().unwrap()).unwrap();
+                test_free(result);
+                value
+            }
+        };
+        let target = r#"{"kind": "flow_focusing", "volume": 1e-12, "height": 5e-5,
+            "dispersed_flow": 1e-11, "continuous_flow": 2e-11}"#;
+        assert_eq!(
+            output("suggest_junction", target)["ok"]["kind"],
+            "flow_focusing"
+        );
+        assert_eq!(
+            output("create_mixer", target)["error"],
+            "unknown function create_mixer"
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! Minimal HTTP/1.1 service of registered designer functions, e.g., for web front ends or
+//! batch runners on other machines. It only depends on the standard library and serves one
+//! connection at a time; deployments expecting load should put a reverse proxy in front.
+//! Heads above [MAX_HEADER_SIZE] and bodies above [MAX_BODY_SIZE] are rejected and requests
+//! not received within [TIMEOUT] are dropped, so a single client can't exhaust the memory or
+//! block the service. A panicking function fails its request only.
+//!
+//! * `GET /functions` lists the names of the functions
+//! * `GET /functions/{name}/schema` returns the JSON schema of the function's input
+//! * `POST /functions/{name}` calls the function with the JSON body
+
+use super::dispatch::{dispatch_in, DesignerFunction, DispatchError};
+use schemars::{gen::SchemaSettings, schema::RootSchema};
+use std::{
+    io::{BufRead, BufReader, ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    panic::{catch_unwind, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
+/// Largest accepted request line and headers in bytes
+pub const MAX_HEADER_SIZE: usize = 64 << 10;
+
+/// Largest accepted request body in bytes
+pub const MAX_BODY_SIZE: usize = 16 << 20;
+
+/// Time to receive a whole request, and the time writing the response may stall
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq)]
+/// Status code and JSON body of a response
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    fn error(status: u16, message: impl std::fmt::Display) -> Response {
+        Response {
+            status,
+            body: serde_json::json!({ "error": message.to_string() }).to_string(),
+        }
+    }
+}
+
+/// Response to a request
+pub fn respond(registry: &[DesignerFunction], method: &str, path: &str, body: &str) -> Response {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let function = |name: &str| registry.iter().find(|f| f.name == name);
+    match (method, &segments[..]) {
+        ("GET", ["functions"]) => Response {
+            status: 200,
+            body: serde_json::to_string(&registry.iter().map(|f| f.name).collect::<Vec<_>>())
+                .unwrap(),
+        },
+        ("GET", ["functions", name, "schema"]) => match function(name) {
+            Some(function) => {
+                let mut gen = SchemaSettings::draft07().into_generator();
+                let mut schema = (function.input_schema)(&mut gen).into_object();
+                let mut definitions = gen.take_definitions();
+                // Inline the referenced input type at the root
+                let name = schema
+                    .reference
+                    .as_ref()
+                    .map(|r| r.rsplit('/').next().unwrap());
+                if let Some(referenced) = name.and_then(|n| definitions.remove(n)) {
+                    schema = referenced.into_object();
+                }
+                let root = RootSchema {
+                    meta_schema: gen.settings().meta_schema.clone(),
+                    schema,
+                    definitions,
+                };
+                Response {
+                    status: 200,
+                    body: serde_json::to_string(&root).unwrap(),
+                }
+            }
+            None => Response::error(404, DispatchError::UnknownFunction(name.to_string())),
+        },
+        ("POST", ["functions", name]) => match dispatch_in(registry, name, body) {
+            Ok(body) => Response { status: 200, body },
+            Err(error @ DispatchError::UnknownFunction(_)) => Response::error(404, error),
+            Err(error @ DispatchError::InvalidInput(_)) => Response::error(400, error),
+        },
+        (_, ["functions", ..]) => Response::error(405, format!("{method} isn't supported")),
+        _ => Response::error(404, format!("no resource at {path}")),
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        _ => "",
+    }
+}
+
+/// Stream whose reads fail once the deadline has passed, however slowly data trickles in
+struct Deadline {
+    stream: TcpStream,
+    until: Instant,
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = (self.until.checked_duration_since(Instant::now()))
+            .filter(|d| !d.is_zero())
+            .ok_or(ErrorKind::TimedOut)?;
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+/// Reads the request and answers it
+fn request(registry: &[DesignerFunction], reader: &mut impl BufRead) -> std::io::Result<Response> {
+    let mut lines = Vec::new();
+    let mut head = reader.take(MAX_HEADER_SIZE as u64);
+    loop {
+        let mut line = String::new();
+        head.read_line(&mut line)?;
+        if !line.ends_with('\n') && head.limit() == 0 {
+            let message = format!("request heads are limited to {MAX_HEADER_SIZE} bytes");
+            return Ok(Response::error(431, message));
+        }
+        if line.trim().is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+    let request_line = lines.first().map_or("", String::as_str);
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let mut length = 0;
+    for header in lines.iter().skip(1) {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                match value.trim().parse() {
+                    Ok(value) => length = value,
+                    Err(_) => return Ok(Response::error(400, "invalid Content-Length")),
+                }
+            }
+        }
+    }
+    if length > MAX_BODY_SIZE {
+        let message = format!("bodies are limited to {MAX_BODY_SIZE} bytes");
+        return Ok(Response::error(413, message));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    let body = String::from_utf8_lossy(&body);
+    let response = catch_unwind(AssertUnwindSafe(|| respond(registry, method, path, &body)));
+    Ok(response.unwrap_or_else(|_| Response::error(500, format!("{method} {path} panicked"))))
+}
+
+fn handle(registry: &[DesignerFunction], stream: TcpStream) -> std::io::Result<()> {
+    handle_within(registry, stream, TIMEOUT)
+}
+
+/// Handles the connection, giving up if the request isn't received within the timeout
+fn handle_within(
+    registry: &[DesignerFunction],
+    mut stream: TcpStream,
+    timeout: Duration,
+) -> std::io::Result<()> {
+    stream.set_write_timeout(Some(timeout))?;
+    let mut reader = BufReader::new(Deadline {
+        stream: stream.try_clone()?,
+        until: Instant::now() + timeout,
+    });
+    let response = request(registry, &mut reader)?;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        response.body.len(),
+        response.body
+    )
+}
+
+/// Answers requests on the listener until accepting a connection fails. Errors of single
+/// connections, e.g., clients closing early, are skipped.
+pub fn serve(registry: &[DesignerFunction], listener: &TcpListener) -> std::io::Result<()> {
+    loop {
+        let (stream, _) = listener.accept()?;
+        let _ = handle(registry, stream);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::designer::REGISTRY;
+
+    #[test]
+    fn routes() {
+        let names = respond(REGISTRY, "GET", "/functions", "");
+        assert_eq!(names.status, 200);
+        assert!(names.body.contains("\"design_tesla_valve\""));
+
+        let schema = respond(REGISTRY, "GET", "/functions/suggest_junction/schema", "");
+        let schema: serde_json::Value = serde_json::from_str(&schema.body).unwrap();
+        assert!(schema["properties"]["volume"].is_object());
+        assert!(schema["definitions"]["JunctionType"].is_object());
+
+        let target = r#"{"kind": "t_junction", "volume": 1e-12, "height": 5e-5,
+            "dispersed_flow": 1e-11, "continuous_flow": 2e-11}"#;
+        assert_eq!(
+            respond(REGISTRY, "POST", "/functions/suggest_junction", target).status,
+            200
+        );
+        assert_eq!(
+            respond(REGISTRY, "POST", "/functions/suggest_junction", "{}").status,
+            400
+        );
+        assert_eq!(
+            respond(REGISTRY, "POST", "/functions/mix", target).status,
+            404
+        );
+        assert_eq!(respond(REGISTRY, "DELETE", "/functions", "").status, 405);
+    }
+
+    /// Status line of the response to the raw request over a loopback connection
+    fn exchange(registry: &[DesignerFunction], request: &[u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        client.write_all(request).unwrap();
+        handle(registry, stream).unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        response.lines().next().unwrap_or("").to_string()
+    }
+
+    #[test]
+    fn malformed_requests() {
+        let length = MAX_BODY_SIZE + 1;
+        let request = format!("POST /functions/x HTTP/1.1\r\nContent-Length: {length}\r\n\r\n");
+        let status = exchange(REGISTRY, request.as_bytes());
+        assert_eq!(status, "HTTP/1.1 413 Payload Too Large");
+
+        let request = b"POST /functions/x HTTP/1.1\r\nContent-Length: many\r\n\r\n";
+        assert_eq!(exchange(REGISTRY, request), "HTTP/1.1 400 Bad Request");
+
+        // A header without end is cut off at the limit
+        let mut request = b"GET /functions HTTP/1.1\r\nX-Filler: ".to_vec();
+        request.resize(MAX_HEADER_SIZE, b'a');
+        let status = exchange(REGISTRY, &request);
+        assert_eq!(status, "HTTP/1.1 431 Request Header Fields Too Large");
+
+        // A panicking function fails its own request only
+        let registry = [DesignerFunction {
+            name: "fail",
+            call: |_| panic!("designer failure"),
+            input_schema: |gen| gen.subschema_for::<()>(),
+            output_schema: |gen| gen.subschema_for::<()>(),
+        }];
+        let request = b"POST /functions/fail HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}";
+        assert_eq!(
+            exchange(&registry, request),
+            "HTTP/1.1 500 Internal Server Error"
+        );
+        assert_eq!(
+            exchange(&registry, b"GET /functions HTTP/1.1\r\n\r\n"),
+            "HTTP/1.1 200 OK"
+        );
+    }
+
+    #[test]
+    fn request_deadline() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        // A byte every 10 ms never stalls a single read, but the request takes too long
+        let trickle = std::thread::spawn(move || {
+            for byte in b"GET /functions HTTP/1.1\r\nX-Filler: "
+                .iter()
+                .cycle()
+                .take(500)
+            {
+                if client.write_all(&[*byte]).is_err() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        });
+        let start = Instant::now();
+        let error = handle_within(REGISTRY, stream, Duration::from_millis(200)).unwrap_err();
+        // Sockets report their read timeouts as WouldBlock on some platforms
+        assert!(matches!(
+            error.kind(),
+            ErrorKind::TimedOut | ErrorKind::WouldBlock
+        ));
+        assert!(start.elapsed() < Duration::from_secs(2));
+        trickle.join().unwrap();
+    }
+}
@@ -1,6 +1,139 @@
-// Not used at the moment, cf. macros crate
+// Implemented by the derive of the macros crate
 pub trait MMFTInterface {
+    /// Units of fields by field name, from `#[mmft(unit = "...")]` attributes
+    const UNITS: &'static [(&'static str, &'static str)] = &[];
+
     fn schema() -> String;
     fn from_json(str: &str) -> Self;
     fn to_json(&self) -> String;
+
+    /// Adds the units to the field schemas of the type's schema: as `unit` keyword, for tools
+    /// that know it, and to the description, for those that don't
+    fn annotate_schema(schema: &mut serde_json::Value) {
+        for (field, unit) in Self::UNITS {
+            let Some(property) = schema
+                .pointer_mut(&format!("/properties/{field}"))
+                .and_then(|p| p.as_object_mut())
+            else {
+                continue;
+            };
+            let description = match property.get("description").and_then(|d| d.as_str()) {
+                Some(description) => format!("{description} [{unit}]"),
+                None => format!("[{unit}]"),
+            };
+            property.insert("description".into(), description.into());
+            property.insert("unit".into(), (*unit).into());
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    fn from_yaml(str: &str) -> Self
+    where
+        Self: serde::de::DeserializeOwned,
+    {
+        super::yaml::from_str(str).unwrap()
+    }
+
+    #[cfg(feature = "yaml")]
+    fn to_yaml(&self) -> String
+    where
+        Self: serde::Serialize,
+    {
+        super::yaml::to_string(self).unwrap()
+    }
+
+    #[cfg(feature = "toml")]
+    fn from_toml(str: &str) -> Self
+    where
+        Self: serde::de::DeserializeOwned,
+    {
+        super::toml::from_str(str).unwrap()
+    }
+
+    #[cfg(feature = "toml")]
+    fn to_toml(&self) -> String
+    where
+        Self: serde::Serialize,
+    {
+        super::toml::to_string(self).unwrap()
+    }
+}
+
+/// Example instances of a type, e.g., for interactive documentation of the bindings; implemented
+/// by the derive of the macros crate
+pub trait MMFTExample: Sized {
+    fn examples() -> Vec<Self>;
+
+    /// Examples as JSON values, as they appear in the schema
+    fn example_values() -> Vec<serde_json::Value>
+    where
+        Self: serde::Serialize,
+    {
+        Self::examples()
+            .iter()
+            .map(|example| serde_json::to_value(example).unwrap())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mmft_macros::{MMFTExample, MMFTInterface};
+    use schemars::{schema_for, JsonSchema};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, JsonSchema, MMFTInterface, MMFTExample, Debug, PartialEq)]
+    #[mmft(example = "Meander::short")]
+    #[mmft(example = "self::long_meander")]
+    struct Meander {
+        /// Channel width
+        #[mmft(unit = "um")]
+        width: f64,
+
+        #[mmft(unit = "mm")]
+        length: f64,
+
+        turns: usize,
+    }
+
+    impl Meander {
+        fn short() -> Meander {
+            Meander {
+                width: 100.,
+                length: 5.,
+                turns: 2,
+            }
+        }
+    }
+
+    fn long_meander() -> Meander {
+        Meander {
+            length: 50.,
+            turns: 20,
+            ..Meander::short()
+        }
+    }
+
+    #[test]
+    fn examples() {
+        assert_eq!(Meander::examples(), [Meander::short(), long_meander()]);
+        let schema: serde_json::Value = serde_json::from_str(&Meander::schema()).unwrap();
+        assert_eq!(
+            schema["examples"],
+            serde_json::Value::Array(Meander::example_values())
+        );
+        assert_eq!(schema["examples"][1]["turns"], 20);
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(Meander::UNITS, [("width", "um"), ("length", "mm")]);
+        let schema: serde_json::Value = serde_json::from_str(&Meander::schema()).unwrap();
+        let width = &schema["properties"]["width"];
+        assert_eq!(width["unit"], "um");
+        assert_eq!(width["description"], "Channel width [um]");
+        assert_eq!(schema["properties"]["length"]["description"], "[mm]");
+        assert!(schema["properties"]["turns"].get("unit").is_none());
+    }
 }
@@ -1,3 +1,51 @@
+pub mod dispatch;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "http")]
+pub mod http;
 pub mod json;
+pub mod parchmint;
+#[cfg(feature = "python")]
 pub mod python;
+pub mod schema;
+pub mod simulator;
+#[cfg(feature = "toml")]
+pub mod toml;
+pub mod validation;
+#[cfg(feature = "wasm")]
 pub mod wasm;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+
+#[cfg(any(feature = "yaml", feature = "toml"))]
+#[derive(Debug, Clone, PartialEq)]
+/// Error reading or writing a text format other than JSON
+pub struct TextFormatError {
+    /// 1-based line of the input, 0 if the error isn't tied to a line
+    pub line: usize,
+
+    pub message: String,
+}
+
+#[cfg(any(feature = "yaml", feature = "toml"))]
+impl TextFormatError {
+    pub(crate) fn new(line: usize, message: impl std::fmt::Display) -> Self {
+        TextFormatError {
+            line,
+            message: message.to_string(),
+        }
+    }
+}
+
+#[cfg(any(feature = "yaml", feature = "toml"))]
+impl std::fmt::Display for TextFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            0 => write!(f, "{}", self.message),
+            line => write!(f, "line {line}: {}", self.message),
+        }
+    }
+}
+
+#[cfg(any(feature = "yaml", feature = "toml"))]
+impl std::error::Error for TextFormatError {}
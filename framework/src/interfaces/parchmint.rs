@@ -0,0 +1,1333 @@
+//! ParchMint, the JSON interchange format of 3DuF and the Fluigi toolchain: typed documents,
+//! their validation, and conversion from and to networks. A ParchMint device consists of
+//! components with ports, e.g., inlets and mixers, and connections routing channels from a
+//! source port to sink ports, on named layers and in integer micrometers.
+//!
+//! Nodes become `NODE` components, or `PORT` components if they have a port hole. Modules
+//! become components with one port per interface node and channels connections with a single
+//! sink. Arcs are written as chords, which the import merges back into arcs.
+//!
+//! Conversions keep what the other side can't represent so that round trips are lossless for
+//! layouts on the micrometer grid: ParchMint data without a framework counterpart, e.g.,
+//! entities, labels, and unknown parameters, is kept as [PARCHMINT_KEY] metadata, and
+//! framework data without a ParchMint counterpart, e.g., ids, nominal lengths, and metadata,
+//! as the [MMFT_PARAMETER] parameter. What neither can hold is listed as [DroppedInfo].
+
+use crate::{
+    base::{
+        channel::{
+            Channel, ChannelPath, CylindricalShape, LineSegment, PathPiece, RectangularShape, Shape,
+        },
+        footprint::Orientation,
+        network::{Metadata, Module, ModuleKind, Network, Node, NodeId},
+        port::{ConnectorType, PortHole},
+        primitives::{Dimensions, Point},
+        units::LengthUnit,
+    },
+    export::layout_bounds,
+};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize, Serializer};
+use serde_json::{json, Value};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    f64::consts::PI,
+};
+
+/// Metadata key of the ParchMint data of an imported entity or device, e.g., its entity,
+/// such as `MIXER`, and parameters unknown to the framework
+pub const PARCHMINT_KEY: &str = "parchmint";
+
+/// Parameter of exported components, connections, and devices holding framework data
+pub const MMFT_PARAMETER: &str = "mmft";
+
+/// Largest angle swept by the chord written for an arc
+const CHORD_ANGLE: f64 = PI / 36.;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Reasons a network or device can't be converted
+pub enum ParchmintError {
+    /// Malformed JSON or a structure that doesn't match the format
+    Json(String),
+
+    /// The device violates the format's rules
+    Invalid(Vec<ParchmintIssue>),
+
+    /// The node has no position
+    UnplacedNode(NodeId),
+
+    /// The component or connection lacks a parameter the conversion needs
+    MissingParameter { entity: String, name: &'static str },
+}
+
+impl std::fmt::Display for ParchmintError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParchmintError::Json(message) => write!(f, "invalid ParchMint JSON: {message}"),
+            ParchmintError::Invalid(issues) => {
+                write!(f, "invalid ParchMint device with {} issues", issues.len())
+            }
+            ParchmintError::UnplacedNode(NodeId(id)) => write!(f, "node {id} has no position"),
+            ParchmintError::MissingParameter { entity, name } => {
+                write!(f, "{entity} has no parameter {name}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParchmintError {}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Violation of the rules of the ParchMint format
+pub enum ParchmintIssue {
+    /// The major version isn't 1
+    UnsupportedVersion(String),
+
+    /// Two layers, components, or connections share the id
+    DuplicateId(String),
+
+    /// A component, port, or connection refers to a layer that isn't defined
+    UnknownLayer { entity: String, layer: String },
+
+    /// A connection refers to a component that isn't defined
+    UnknownComponent {
+        connection: String,
+        component: String,
+    },
+
+    /// A connection refers to a port the component doesn't have
+    UnknownPort {
+        connection: String,
+        component: String,
+        port: String,
+    },
+
+    /// The connection has no sinks
+    NoSinks(String),
+
+    /// The component has a negative span
+    NegativeSpan(String),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Part of a network without a ParchMint counterpart, left out of the device
+pub enum DroppedInfo {
+    /// Labels and dimension lines, by count
+    Annotations(usize),
+
+    /// Keep-out regions, by count
+    KeepOuts(usize),
+
+    /// Grooves in channel walls, by count
+    SurfaceFeatures(usize),
+
+    /// Non-fluidic geometry, e.g., panel labels and dicing lanes, by count
+    Markings(usize),
+
+    /// Named entity groups, by count
+    Groups(usize),
+
+    LayerStack,
+
+    /// Footprint of the module with the id
+    Footprint(usize),
+
+    /// Nested network implementing the module with the id
+    Subcircuit(usize),
+
+    /// Condensed port model of the module with the id
+    PortModel(usize),
+
+    /// Function of the imported module with the id, e.g., a heater or valve
+    ModuleKind(usize),
+}
+
+/// ParchMint coordinates are integers, fractions of micrometers are rounded
+fn integer<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_i64(value.round() as i64)
+}
+
+fn integers<S: Serializer>(points: &[[f64; 2]], serializer: S) -> Result<S::Ok, S::Error> {
+    let points: Vec<[i64; 2]> = (points.iter())
+        .map(|[x, y]| [x.round() as i64, y.round() as i64])
+        .collect();
+    points.serialize(serializer)
+}
+
+fn micrometers(value: f64) -> Value {
+    (value.round() as i64).into()
+}
+
+/// Parameter value, integral values without fraction as written by 3DuF
+fn number(value: f64) -> Value {
+    match (value - value.round()).abs() < 1e-9 {
+        true => micrometers(value),
+        false => value.into(),
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+/// Layer of a device, e.g., a flow or control layer of a multilayer soft lithography chip
+pub struct ParchmintLayer {
+    pub id: String,
+    pub name: String,
+
+    /// `FLOW` or `CONTROL`
+    #[serde(rename = "type")]
+    pub kind: String,
+
+    #[serde(default)]
+    pub group: String,
+
+    #[serde(default)]
+    pub params: Metadata,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+/// Port of a component, relative to the component's position
+pub struct ParchmintPort {
+    pub label: String,
+    pub layer: String,
+
+    #[serde(serialize_with = "integer")]
+    pub x: f64,
+
+    #[serde(serialize_with = "integer")]
+    pub y: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+/// Component of a device, e.g., a port, a mixer, or a valve
+pub struct ParchmintComponent {
+    pub id: String,
+    pub name: String,
+
+    /// Kind of the component, e.g., `PORT` or `MIXER`
+    pub entity: String,
+
+    #[serde(default)]
+    pub layers: Vec<String>,
+
+    #[serde(rename = "x-span", serialize_with = "integer")]
+    pub x_span: f64,
+
+    #[serde(rename = "y-span", serialize_with = "integer")]
+    pub y_span: f64,
+
+    /// Entity parameters; `position` of the corner with the smallest coordinates and
+    /// `rotation` in degrees are common to all components
+    #[serde(default)]
+    pub params: Metadata,
+
+    #[serde(default)]
+    pub ports: Vec<ParchmintPort>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+/// Port a connection starts or ends at, the component's first port if not given
+pub struct ParchmintTarget {
+    pub component: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+/// Route of a connection from its source to one sink
+pub struct ParchmintPath {
+    pub source: ParchmintTarget,
+    pub sink: ParchmintTarget,
+
+    #[serde(rename = "wayPoints", serialize_with = "integers")]
+    pub way_points: Vec<[f64; 2]>,
+
+    /// Ids of the device features drawing the path
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+/// Channel from a source port to one or more sink ports
+pub struct ParchmintConnection {
+    pub id: String,
+    pub name: String,
+
+    #[serde(default)]
+    pub entity: String,
+
+    pub layer: String,
+    pub source: ParchmintTarget,
+    pub sinks: Vec<ParchmintTarget>,
+
+    /// Connection parameters, e.g., `channelWidth` and `height`
+    #[serde(default)]
+    pub params: Metadata,
+
+    #[serde(default)]
+    pub paths: Vec<ParchmintPath>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+/// ParchMint document describing one chip
+pub struct ParchmintDevice {
+    pub name: String,
+
+    /// Device parameters, e.g., `x-span` and `y-span` of the chip
+    #[serde(default)]
+    pub params: Metadata,
+
+    #[serde(default)]
+    pub layers: Vec<ParchmintLayer>,
+
+    #[serde(default)]
+    pub components: Vec<ParchmintComponent>,
+
+    #[serde(default)]
+    pub connections: Vec<ParchmintConnection>,
+
+    /// Geometry of the device as drawn by 3DuF, kept verbatim
+    #[serde(default)]
+    pub features: Vec<Value>,
+
+    #[serde(default)]
+    pub version: String,
+
+    /// Members of later format versions or other tools, kept verbatim
+    #[serde(flatten)]
+    pub other: Metadata,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct PortStash {
+    label: String,
+    layer: String,
+}
+
+/// ParchMint data of an imported component; only the entity of exported ones
+#[derive(Serialize, Deserialize, Default)]
+struct ComponentStash {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entity: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    layers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ports: Option<Vec<PortStash>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    params: Metadata,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConnectionStash {
+    id: String,
+    name: String,
+    entity: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    params: Metadata,
+    /// Features drawing the path of the sink, None if it has no path
+    path: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DeviceStash {
+    name: String,
+    params: Metadata,
+    layers: Vec<ParchmintLayer>,
+    features: Vec<Value>,
+    version: String,
+    other: Metadata,
+}
+
+/// Framework data of an exported component, connection, or device
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct Extras {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    nodes: Vec<NodeId>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    connector: Option<ConnectorType>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    mirrored: bool,
+    #[serde(default, skip_serializing_if = "ModuleKind::is_generic")]
+    kind: ModuleKind,
+    /// Nominal length in micrometers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    length: Option<f64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    circular: bool,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: Metadata,
+}
+
+fn stashed<T: DeserializeOwned>(metadata: &Metadata) -> Option<T> {
+    serde_json::from_value(metadata.get(PARCHMINT_KEY)?.clone()).ok()
+}
+
+fn without_stash(metadata: &Metadata) -> Metadata {
+    let mut metadata = metadata.clone();
+    metadata.remove(PARCHMINT_KEY);
+    metadata
+}
+
+fn stash(metadata: &mut Metadata, stash: impl Serialize) {
+    metadata.insert(PARCHMINT_KEY.into(), serde_json::to_value(stash).unwrap());
+}
+
+fn extras(params: &Metadata) -> Option<Extras> {
+    serde_json::from_value(params.get(MMFT_PARAMETER)?.clone()).ok()
+}
+
+/// Channel layer of the device layer with the id `layer_<n>` or at the index
+fn layer_index(layer: &ParchmintLayer, index: usize) -> usize {
+    (layer.id.strip_prefix("layer_"))
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(index)
+}
+
+fn is_node(component: &ParchmintComponent) -> bool {
+    let entity = &component.entity;
+    (entity.eq_ignore_ascii_case("PORT") || entity.eq_ignore_ascii_case("NODE"))
+        && component.ports.len() <= 1
+}
+
+/// Start, corners, and end of the path, with arcs divided into chords
+fn way_points(path: &ChannelPath) -> Vec<Point> {
+    let mut points: Vec<Point> = path.pieces.first().map(|p| p.start()).into_iter().collect();
+    for piece in &path.pieces {
+        match piece {
+            PathPiece::LineSegment(line) => points.push(line.end),
+            PathPiece::Arc(arc) => {
+                let chords = (arc.angles().sweep.abs() / CHORD_ANGLE).ceil().max(1.) as usize;
+                points.extend((1..=chords).map(|i| piece.point_at(i as f64 / chords as f64)));
+            }
+        }
+    }
+    points
+}
+
+/// Ids kept from an export are taken as they are, all others assigned around them
+struct Ids {
+    reserved: BTreeSet<usize>,
+    next: usize,
+}
+
+impl Ids {
+    fn new(reserved: impl IntoIterator<Item = usize>) -> Ids {
+        Ids {
+            reserved: reserved.into_iter().collect(),
+            next: 0,
+        }
+    }
+
+    fn take(&mut self, kept: Option<usize>) -> usize {
+        if let Some(id) = kept {
+            return id;
+        }
+        while self.reserved.contains(&self.next) {
+            self.next += 1;
+        }
+        self.next += 1;
+        self.next - 1
+    }
+}
+
+impl ParchmintDevice {
+    /// Reads a ParchMint document and validates it
+    pub fn from_json(json: &str) -> Result<ParchmintDevice, ParchmintError> {
+        let device: ParchmintDevice =
+            serde_json::from_str(json).map_err(|e| ParchmintError::Json(e.to_string()))?;
+        match device.validate() {
+            issues if issues.is_empty() => Ok(device),
+            issues => Err(ParchmintError::Invalid(issues)),
+        }
+    }
+
+    /// Indented JSON of the device
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Checks the rules of the format beyond its structure: version, unique ids, and
+    /// references between layers, components, ports, and connections
+    pub fn validate(&self) -> Vec<ParchmintIssue> {
+        let mut issues = Vec::new();
+        if !self.version.is_empty() && self.version.split('.').next() != Some("1") {
+            issues.push(ParchmintIssue::UnsupportedVersion(self.version.clone()));
+        }
+        let ids = [
+            self.layers.iter().map(|l| &l.id).collect::<Vec<_>>(),
+            self.components.iter().map(|c| &c.id).collect(),
+            self.connections.iter().map(|c| &c.id).collect(),
+        ];
+        for ids in ids {
+            let mut seen = BTreeSet::new();
+            for id in ids {
+                if !seen.insert(id) {
+                    issues.push(ParchmintIssue::DuplicateId(id.clone()));
+                }
+            }
+        }
+
+        let layers: BTreeSet<&str> = self.layers.iter().map(|l| l.id.as_str()).collect();
+        let mut layer = |entity: &str, layer: &str| {
+            if !layers.contains(layer) {
+                issues.push(ParchmintIssue::UnknownLayer {
+                    entity: entity.into(),
+                    layer: layer.into(),
+                });
+            }
+        };
+        for component in &self.components {
+            let ports = component.ports.iter().map(|p| &p.layer);
+            for id in component.layers.iter().chain(ports) {
+                layer(&component.id, id);
+            }
+        }
+        for connection in &self.connections {
+            layer(&connection.id, &connection.layer);
+        }
+
+        let components: BTreeMap<&str, &ParchmintComponent> = (self.components.iter())
+            .map(|c| (c.id.as_str(), c))
+            .collect();
+        for connection in &self.connections {
+            if connection.sinks.is_empty() {
+                issues.push(ParchmintIssue::NoSinks(connection.id.clone()));
+            }
+            let paths = connection.paths.iter().flat_map(|p| [&p.source, &p.sink]);
+            let targets = [&connection.source].into_iter().chain(&connection.sinks);
+            for target in targets.chain(paths) {
+                let Some(component) = components.get(target.component.as_str()) else {
+                    issues.push(ParchmintIssue::UnknownComponent {
+                        connection: connection.id.clone(),
+                        component: target.component.clone(),
+                    });
+                    continue;
+                };
+                if let Some(port) = &target.port {
+                    if !component.ports.iter().any(|p| &p.label == port) {
+                        issues.push(ParchmintIssue::UnknownPort {
+                            connection: connection.id.clone(),
+                            component: target.component.clone(),
+                            port: port.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        for component in &self.components {
+            if component.x_span < 0. || component.y_span < 0. {
+                issues.push(ParchmintIssue::NegativeSpan(component.id.clone()));
+            }
+        }
+        issues
+    }
+
+    /// Device of the network; lengths are converted from layout units to micrometers, by the
+    /// network's declared length unit or else by the given scale.
+    /// Components are written in node order, each module at its first interface node.
+    /// Channel layers become flow layers unless imported otherwise. Also returns what the
+    /// device can't hold; circular cross-sections are written as squares and restored on
+    /// import.
+    pub fn from_network(
+        network: &Network,
+        units_per_micrometer: f64,
+    ) -> Result<(ParchmintDevice, Vec<DroppedInfo>), ParchmintError> {
+        let units_per_micrometer = network
+            .units_per(LengthUnit::Micrometer)
+            .unwrap_or(units_per_micrometer);
+        let um = |value: f64| value / units_per_micrometer;
+        let position =
+            |id: NodeId| (network.node_position(id)).ok_or(ParchmintError::UnplacedNode(id));
+        let device_stash: Option<DeviceStash> = stashed(&network.metadata);
+        let layer_ids: BTreeMap<usize, &str> = (device_stash.iter())
+            .flat_map(|s| s.layers.iter().enumerate())
+            .map(|(i, layer)| (layer_index(layer, i), layer.id.as_str()))
+            .collect();
+        let layer_id = |layer: usize| match layer_ids.get(&layer) {
+            Some(id) => id.to_string(),
+            None => format!("layer_{layer}"),
+        };
+        let node_layer = |id: NodeId| {
+            (network.channels.iter())
+                .find(|c| c.node_a == id || c.node_b == id)
+                .map_or(0, |c| c.layer)
+        };
+        let mut dropped = Vec::new();
+
+        let module_stashes: Vec<ComponentStash> = (network.modules.iter())
+            .map(|m| stashed(&m.metadata).unwrap_or_default())
+            .collect();
+        let mut targets: BTreeMap<NodeId, ParchmintTarget> = BTreeMap::new();
+        for (module, stash) in network.modules.iter().zip(&module_stashes) {
+            let id = (stash.id.clone()).unwrap_or(format!("module_{}", module.id));
+            for (i, node) in module.nodes.iter().enumerate() {
+                let label = (stash.ports.as_ref().and_then(|p| p.get(i)))
+                    .map_or((i + 1).to_string(), |p| p.label.clone());
+                targets.entry(*node).or_insert(ParchmintTarget {
+                    component: id.clone(),
+                    port: Some(label),
+                });
+            }
+        }
+
+        let mut module_component = |index: usize| -> Result<ParchmintComponent, ParchmintError> {
+            let (module, stash) = (&network.modules[index], &module_stashes[index]);
+            if module.footprint.is_some() {
+                dropped.push(DroppedInfo::Footprint(module.id));
+            }
+            if module.implementation.is_some() {
+                dropped.push(DroppedInfo::Subcircuit(module.id));
+            }
+            if module.model.is_some() {
+                dropped.push(DroppedInfo::PortModel(module.id));
+            }
+            let id = (stash.id.clone()).unwrap_or(format!("module_{}", module.id));
+            let mut ports = Vec::new();
+            for (i, &node) in module.nodes.iter().enumerate() {
+                let stashed = stash.ports.as_ref().and_then(|p| p.get(i)).cloned();
+                let PortStash { label, layer } = stashed.unwrap_or(PortStash {
+                    label: (i + 1).to_string(),
+                    layer: layer_id(node_layer(node)),
+                });
+                let Point([x, y]) = module.to_local(position(node)?);
+                ports.push(ParchmintPort {
+                    label,
+                    layer,
+                    x: um(x),
+                    y: um(y),
+                });
+            }
+            // The node of a component without ports
+            if stash.ports.as_ref().is_some_and(|p| p.is_empty()) && module.nodes.len() == 1 {
+                ports.clear();
+            }
+            let mut layers = stash.layers.clone();
+            if layers.is_empty() {
+                layers = ports.iter().map(|p| p.layer.clone()).collect();
+                layers.dedup();
+            }
+            if layers.is_empty() {
+                layers.push(layer_id(0));
+            }
+
+            let Point([x, y]) = module.position;
+            let Dimensions([width, height]) = module.size;
+            let mut params = stash.params.clone();
+            params.insert(
+                "position".into(),
+                json!([micrometers(um(x)), micrometers(um(y))]),
+            );
+            let rotation = module.orientation.rotation;
+            if rotation != 0. || params.contains_key("rotation") {
+                params.insert("rotation".into(), number(rotation.to_degrees()));
+            }
+            if stash.id.is_none() {
+                let extras = Extras {
+                    id: Some(module.id),
+                    nodes: module.nodes.clone(),
+                    mirrored: module.orientation.mirrored,
+                    kind: module.kind,
+                    metadata: without_stash(&module.metadata),
+                    ..Default::default()
+                };
+                params.insert(MMFT_PARAMETER.into(), serde_json::to_value(extras).unwrap());
+            } else if !module.kind.is_generic() {
+                // Imported components keep their ParchMint parameters only
+                dropped.push(DroppedInfo::ModuleKind(module.id));
+            }
+            Ok(ParchmintComponent {
+                name: stash.name.clone().unwrap_or(id.clone()),
+                id,
+                entity: stash.entity.clone().unwrap_or("MODULE".into()),
+                layers,
+                x_span: um(width),
+                y_span: um(height),
+                params,
+                ports,
+            })
+        };
+
+        let mut components = Vec::new();
+        let mut written = BTreeSet::new();
+        for node in &network.nodes {
+            let module = (network.modules.iter()).position(|m| m.nodes.contains(&node.id));
+            if let Some(index) = module {
+                if written.insert(index) {
+                    components.push(module_component(index)?);
+                }
+                continue;
+            }
+            let stash: ComponentStash = stashed(&node.metadata).unwrap_or_default();
+            let id = stash.id.clone().unwrap_or(format!("node_{}", node.id.0));
+            let first_port = stash.ports.as_ref().and_then(|p| p.first()).cloned();
+            let PortStash { label, layer } = first_port.unwrap_or(PortStash {
+                label: "1".into(),
+                layer: layer_id(node_layer(node.id)),
+            });
+            targets.insert(
+                node.id,
+                ParchmintTarget {
+                    component: id.clone(),
+                    port: Some(label.clone()),
+                },
+            );
+            let Point([x, y]) = position(node.id)?;
+            let diameter = node.port.as_ref().map_or(0., |p| um(p.diameter));
+            let mut params = stash.params.clone();
+            params.insert(
+                "position".into(),
+                json!([
+                    micrometers(um(x) - diameter / 2.),
+                    micrometers(um(y) - diameter / 2.)
+                ]),
+            );
+            if node.port.is_some() {
+                params.insert("portRadius".into(), number(diameter / 2.));
+            }
+            if stash.id.is_none() {
+                let extras = Extras {
+                    id: Some(node.id.0),
+                    connector: (node.port.as_ref())
+                        .map(|p| p.connector.clone())
+                        .filter(|c| *c != ConnectorType::PressFit),
+                    metadata: without_stash(&node.metadata),
+                    ..Default::default()
+                };
+                params.insert(MMFT_PARAMETER.into(), serde_json::to_value(extras).unwrap());
+            }
+            let entity = match node.port {
+                Some(_) => "PORT",
+                None => "NODE",
+            };
+            let no_ports = stash.ports.as_ref().is_some_and(|p| p.is_empty());
+            components.push(ParchmintComponent {
+                name: stash.name.clone().unwrap_or(id.clone()),
+                id,
+                entity: stash.entity.clone().unwrap_or(entity.into()),
+                layers: match stash.layers.is_empty() {
+                    true => vec![layer.clone()],
+                    false => stash.layers.clone(),
+                },
+                x_span: diameter,
+                y_span: diameter,
+                params,
+                ports: match no_ports {
+                    true => Vec::new(),
+                    false => vec![ParchmintPort {
+                        label,
+                        layer,
+                        x: diameter / 2.,
+                        y: diameter / 2.,
+                    }],
+                },
+            });
+        }
+        for index in 0..network.modules.len() {
+            if !written.contains(&index) {
+                components.push(module_component(index)?);
+            }
+        }
+
+        let target =
+            |id: NodeId| (targets.get(&id).cloned()).ok_or(ParchmintError::UnplacedNode(id));
+        let mut connections: Vec<ParchmintConnection> = Vec::new();
+        for channel in &network.channels {
+            let stash: Option<ConnectionStash> = stashed(&channel.metadata);
+            let (source, sink) = (target(channel.node_a)?, target(channel.node_b)?);
+            let features = match &stash {
+                Some(stash) => stash.path.clone(),
+                None => Some(Vec::new()),
+            };
+            let path =
+                (channel.path.as_ref().zip(features)).map(|(path, features)| ParchmintPath {
+                    source: source.clone(),
+                    sink: sink.clone(),
+                    way_points: (way_points(path).into_iter())
+                        .map(|Point([x, y])| [um(x), um(y)])
+                        .collect(),
+                    features,
+                });
+            // Sinks of one imported connection are joined again
+            let joined = stash.as_ref().and_then(|stash| {
+                (connections.iter_mut()).find(|c| c.id == stash.id && c.source == source)
+            });
+            if let Some(connection) = joined {
+                connection.sinks.push(sink);
+                connection.paths.extend(path);
+                continue;
+            }
+
+            let (width, height, circular) = match channel.shape {
+                Shape::Rectangular(shape) => (shape.width, shape.height, false),
+                Shape::Cylindrical(shape) => (2. * shape.radius, 2. * shape.radius, true),
+            };
+            let mut params = stash
+                .as_ref()
+                .map_or_else(Metadata::new, |s| s.params.clone());
+            params.insert("channelWidth".into(), micrometers(um(width)));
+            params.insert("height".into(), micrometers(um(height)));
+            if stash.is_none() {
+                let extras = Extras {
+                    id: Some(channel.id),
+                    length: channel.length.map(um),
+                    circular,
+                    metadata: without_stash(&channel.metadata),
+                    ..Default::default()
+                };
+                params.insert(MMFT_PARAMETER.into(), serde_json::to_value(extras).unwrap());
+            }
+            let id = format!("channel_{}", channel.id);
+            connections.push(ParchmintConnection {
+                id: stash.as_ref().map_or(id.clone(), |s| s.id.clone()),
+                name: stash.as_ref().map_or(id, |s| s.name.clone()),
+                entity: stash
+                    .as_ref()
+                    .map_or("CHANNEL".into(), |s| s.entity.clone()),
+                layer: layer_id(channel.layer),
+                source,
+                sinks: vec![sink],
+                params,
+                paths: path.into_iter().collect(),
+            });
+        }
+
+        let mut layers = (device_stash.as_ref()).map_or_else(Vec::new, |s| s.layers.clone());
+        let used =
+            (components.iter().flat_map(|c| &c.layers)).chain(connections.iter().map(|c| &c.layer));
+        let mut missing: Vec<String> = Vec::new();
+        for id in used {
+            if !layers.iter().any(|l| &l.id == id) && !missing.contains(id) {
+                missing.push(id.clone());
+            }
+        }
+        layers.extend(missing.into_iter().map(|id| ParchmintLayer {
+            name: id.clone(),
+            id,
+            kind: "FLOW".into(),
+            group: String::new(),
+            params: Metadata::new(),
+        }));
+
+        let Point([x_span, y_span]) = layout_bounds(network).map_or(Point([0., 0.]), |b| b.max);
+        let mut params = (device_stash.as_ref()).map_or_else(Metadata::new, |s| s.params.clone());
+        for (key, span) in [("x-span", um(x_span)), ("y-span", um(y_span))] {
+            // Margins of imported devices are kept
+            let stashed = params.get(key).and_then(Value::as_f64).unwrap_or(0.);
+            params.insert(key.into(), micrometers(span.max(stashed)));
+        }
+        if device_stash.is_none() {
+            let extras = Extras {
+                metadata: without_stash(&network.metadata),
+                ..Default::default()
+            };
+            params.insert(MMFT_PARAMETER.into(), serde_json::to_value(extras).unwrap());
+        }
+
+        let counts = [
+            (
+                network.annotations.len(),
+                DroppedInfo::Annotations as fn(usize) -> DroppedInfo,
+            ),
+            (network.keep_outs.len(), DroppedInfo::KeepOuts),
+            (network.surface_features.len(), DroppedInfo::SurfaceFeatures),
+            (network.markings.len(), DroppedInfo::Markings),
+            (network.groups.len(), DroppedInfo::Groups),
+        ];
+        for (count, info) in counts {
+            if count > 0 {
+                dropped.push(info(count));
+            }
+        }
+        if network.layer_stack.is_some() {
+            dropped.push(DroppedInfo::LayerStack);
+        }
+
+        let device = match device_stash {
+            Some(stash) => ParchmintDevice {
+                name: stash.name,
+                params,
+                layers,
+                components,
+                connections,
+                features: stash.features,
+                version: stash.version,
+                other: stash.other,
+            },
+            None => ParchmintDevice {
+                name: "device".into(),
+                params,
+                layers,
+                components,
+                connections,
+                features: Vec::new(),
+                version: "1.2".into(),
+                other: Metadata::new(),
+            },
+        };
+        Ok((device, dropped))
+    }
+
+    /// Network of the device, converting micrometers to layout units; the network declares
+    /// the length unit of that scale, if any. `PORT` and `NODE`
+    /// components with at most one port become nodes, all other components modules with one
+    /// interface node per port; components without ports get one at their center. Every
+    /// sink of a connection becomes a rectangular channel from the source, along the way
+    /// points of its path simplified to segments and arcs. Layers `layer_<n>` map to channel
+    /// layer n, others to their index in the device.
+    pub fn to_network(&self, units_per_micrometer: f64) -> Result<Network, ParchmintError> {
+        let issues = self.validate();
+        if !issues.is_empty() {
+            return Err(ParchmintError::Invalid(issues));
+        }
+        let layout = |value: f64| value * units_per_micrometer;
+        let layers: BTreeMap<&str, usize> = (self.layers.iter().enumerate())
+            .map(|(i, layer)| (layer.id.as_str(), layer_index(layer, i)))
+            .collect();
+        let component_extras: Vec<Option<Extras>> =
+            self.components.iter().map(|c| extras(&c.params)).collect();
+        let connection_extras: Vec<Option<Extras>> =
+            self.connections.iter().map(|c| extras(&c.params)).collect();
+        let components = || self.components.iter().zip(&component_extras);
+        let mut node_ids = Ids::new(components().flat_map(|(component, extras)| {
+            match (is_node(component), extras) {
+                (true, Some(extras)) => extras.id.into_iter().collect(),
+                (false, Some(extras)) => extras.nodes.iter().map(|n| n.0).collect(),
+                (_, None) => Vec::new(),
+            }
+        }));
+        let mut module_ids =
+            Ids::new(components().filter_map(|(component, extras)| {
+                extras.as_ref().filter(|_| !is_node(component))?.id
+            }));
+        let mut channel_ids = Ids::new(connection_extras.iter().filter_map(|e| e.as_ref()?.id));
+
+        let mut network = Network {
+            length_unit: LengthUnit::with_scale(units_per_micrometer, LengthUnit::Micrometer),
+            ..Default::default()
+        };
+        let mut ports: BTreeMap<(&str, &str), NodeId> = BTreeMap::new();
+        let mut first_ports: BTreeMap<&str, NodeId> = BTreeMap::new();
+        for (component, extras) in components() {
+            let [x, y]: [f64; 2] = (component.params.get("position"))
+                .and_then(|p| serde_json::from_value(p.clone()).ok())
+                .ok_or_else(|| ParchmintError::MissingParameter {
+                    entity: component.id.clone(),
+                    name: "position",
+                })?;
+            let rotation = (component.params.get("rotation"))
+                .and_then(Value::as_f64)
+                .unwrap_or(0.);
+            let is_node = is_node(component);
+            let Extras {
+                id,
+                nodes,
+                connector,
+                mirrored,
+                kind,
+                mut metadata,
+                ..
+            } = extras.clone().unwrap_or_default();
+            if extras.is_none() {
+                let mut params = component.params.clone();
+                params.remove("position");
+                let ports = (component.ports.iter())
+                    .map(|p| PortStash {
+                        label: p.label.clone(),
+                        layer: p.layer.clone(),
+                    })
+                    .collect();
+                let data = ComponentStash {
+                    id: Some(component.id.clone()),
+                    name: Some(component.name.clone()),
+                    entity: Some(component.entity.clone()),
+                    layers: component.layers.clone(),
+                    ports: Some(ports),
+                    params,
+                };
+                stash(&mut metadata, data);
+            } else if !is_node && component.entity != "MODULE" {
+                let data = ComponentStash {
+                    entity: Some(component.entity.clone()),
+                    ..Default::default()
+                };
+                stash(&mut metadata, data);
+            }
+
+            let mut module = Module {
+                id: 0,
+                position: Point([layout(x), layout(y)]),
+                size: Dimensions([layout(component.x_span), layout(component.y_span)]),
+                nodes: Vec::new(),
+                implementation: None,
+                model: None,
+                footprint: None,
+                orientation: Orientation {
+                    rotation: rotation.to_radians(),
+                    mirrored,
+                },
+                kind,
+                metadata,
+            };
+            let locals: Vec<(&str, Point)> = match component.ports.is_empty() {
+                true => vec![("", Point([component.x_span / 2., component.y_span / 2.]))],
+                false => (component.ports.iter())
+                    .map(|p| (p.label.as_str(), Point([p.x, p.y])))
+                    .collect(),
+            };
+            let kept = (nodes.len() == locals.len()).then_some(nodes);
+            for (i, (label, Point([x, y]))) in locals.into_iter().enumerate() {
+                let kept = match is_node {
+                    true => id,
+                    false => kept.as_ref().map(|nodes| nodes[i].0),
+                };
+                let id = NodeId(node_ids.take(kept));
+                let position = module.to_layout(Point([layout(x), layout(y)]));
+                network.nodes.push(Node::at(id, position));
+                module.nodes.push(id);
+                ports.insert((component.id.as_str(), label), id);
+                first_ports.entry(component.id.as_str()).or_insert(id);
+            }
+            if is_node {
+                let node = network.nodes.last_mut().unwrap();
+                if component.entity.eq_ignore_ascii_case("PORT") {
+                    let radius = (component.params.get("portRadius"))
+                        .and_then(Value::as_f64)
+                        .unwrap_or(component.x_span / 2.);
+                    node.port = Some(PortHole {
+                        diameter: layout(2. * radius),
+                        connector: connector.unwrap_or(ConnectorType::PressFit),
+                    });
+                }
+                node.metadata = module.metadata;
+            } else {
+                module.id = module_ids.take(id);
+                network.modules.push(module);
+            }
+        }
+
+        // Validated
+        let node = |target: &ParchmintTarget| {
+            let component = target.component.as_str();
+            match &target.port {
+                None => first_ports[component],
+                Some(port) => ports[&(component, port.as_str())],
+            }
+        };
+        for (connection, extras) in self.connections.iter().zip(connection_extras) {
+            let parameter = |name: &'static str| {
+                (connection.params.get(name))
+                    .and_then(Value::as_f64)
+                    .ok_or_else(|| ParchmintError::MissingParameter {
+                        entity: connection.id.clone(),
+                        name,
+                    })
+            };
+            let (width, height) = (parameter("channelWidth")?, parameter("height")?);
+            let shape = match extras.as_ref().is_some_and(|e| e.circular) {
+                true => Shape::Cylindrical(CylindricalShape {
+                    radius: layout(width / 2.),
+                }),
+                false => Shape::Rectangular(RectangularShape {
+                    width: layout(width),
+                    height: layout(height),
+                }),
+            };
+            let layer = layers[connection.layer.as_str()];
+            let node_a = node(&connection.source);
+            for sink in &connection.sinks {
+                let node_b = node(sink);
+                let route = (connection.paths.iter()).find(|p| node(&p.sink) == node_b);
+                let way_points = route.map_or(&[][..], |p| &p.way_points[..]);
+                let mut points = vec![network.node_position(node_a).unwrap()];
+                points.extend(
+                    way_points
+                        .iter()
+                        .map(|[x, y]| Point([layout(*x), layout(*y)])),
+                );
+                points.push(network.node_position(node_b).unwrap());
+                points.dedup();
+                let mut path = ChannelPath::new();
+                for pair in points.windows(2) {
+                    path.add(PathPiece::LineSegment(LineSegment {
+                        start: pair[0],
+                        end: pair[1],
+                    }));
+                }
+                // Rounded way points of chords deviate by up to about a micrometer each from arcs
+                path.simplify(layout(2.));
+
+                let metadata = match &extras {
+                    Some(extras) => extras.metadata.clone(),
+                    None => {
+                        let mut params = connection.params.clone();
+                        params.remove("channelWidth");
+                        params.remove("height");
+                        let data = ConnectionStash {
+                            id: connection.id.clone(),
+                            name: connection.name.clone(),
+                            entity: connection.entity.clone(),
+                            params,
+                            path: route.map(|p| p.features.clone()),
+                        };
+                        let mut metadata = Metadata::new();
+                        stash(&mut metadata, data);
+                        metadata
+                    }
+                };
+                network.channels.push(Channel {
+                    id: channel_ids.take(extras.as_ref().and_then(|e| e.id)),
+                    node_a,
+                    node_b,
+                    shape,
+                    path: (!path.pieces.is_empty()).then_some(path),
+                    length: extras.as_ref().and_then(|e| e.length).map(layout),
+                    layer,
+                    metadata,
+                });
+            }
+        }
+
+        match extras(&self.params) {
+            Some(extras) => network.metadata = extras.metadata,
+            None => stash(
+                &mut network.metadata,
+                DeviceStash {
+                    name: self.name.clone(),
+                    params: self.params.clone(),
+                    layers: self.layers.clone(),
+                    features: self.features.clone(),
+                    version: self.version.clone(),
+                    other: self.other.clone(),
+                },
+            ),
+        }
+        Ok(network)
+    }
+}
+
+impl Network {
+    /// ParchMint document of the network named `name`, see [ParchmintDevice::from_network]
+    pub fn to_parchmint(
+        &self,
+        name: &str,
+        units_per_micrometer: f64,
+    ) -> Result<String, ParchmintError> {
+        let (mut device, _) = ParchmintDevice::from_network(self, units_per_micrometer)?;
+        device.name = name.into();
+        Ok(device.to_json())
+    }
+
+    /// Network of a ParchMint document, see [ParchmintDevice::to_network]
+    pub fn from_parchmint(
+        json: &str,
+        units_per_micrometer: f64,
+    ) -> Result<Network, ParchmintError> {
+        ParchmintDevice::from_json(json)?.to_network(units_per_micrometer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::Arc, hierarchy::PortModel, marking::Marking, network::EntityRef,
+        primitives::Tolerance,
+    };
+
+    #[test]
+    fn network_round_trip() {
+        let mut bend = ChannelPath::new();
+        bend.add(PathPiece::LineSegment(LineSegment {
+            start: Point([0., 0.]),
+            end: Point([1000., 0.]),
+        }));
+        bend.add(PathPiece::Arc(Arc::from_center_angles(
+            Point([1000., 500.]),
+            500.,
+            -PI / 2.,
+            PI / 2.,
+        )));
+        let channel = |id, node_a, node_b, path| Channel {
+            id,
+            node_a: NodeId(node_a),
+            node_b: NodeId(node_b),
+            shape: Shape::Rectangular(RectangularShape {
+                width: 100.,
+                height: 50.,
+            }),
+            path,
+            length: None,
+            layer: 0,
+            metadata: Metadata::new(),
+        };
+        let mut inlet = Node::at(NodeId(4), Point([0., 0.]));
+        inlet.port = Some(PortHole {
+            diameter: 700.,
+            connector: ConnectorType::Luer,
+        });
+        let mut mixer = Metadata::new();
+        stash(
+            &mut mixer,
+            ComponentStash {
+                entity: Some("MIXER".into()),
+                ..Default::default()
+            },
+        );
+        let mut network = Network {
+            length_unit: Some(LengthUnit::Micrometer),
+            nodes: vec![
+                inlet,
+                Node::at(NodeId(1), Point([1500., 500.])),
+                Node::at(NodeId(2), Point([1500., 1500.])),
+            ],
+            channels: vec![channel(3, 4, 1, Some(bend)), channel(1, 1, 2, None)],
+            modules: vec![Module {
+                id: 7,
+                position: Point([1000., 500.]),
+                size: Dimensions([1000., 1000.]),
+                nodes: vec![NodeId(1), NodeId(2)],
+                implementation: None,
+                model: None,
+                footprint: None,
+                orientation: Orientation::default(),
+                kind: ModuleKind::Heater { temperature: 350. },
+                metadata: mixer,
+            }],
+            ..Default::default()
+        };
+        network.channels[1].shape = Shape::Cylindrical(CylindricalShape { radius: 20. });
+        network.channels[1].length = Some(1234.5);
+        (network.channels[1].metadata).insert("tool".into(), "router".into());
+        network.add_to_group("mixers", EntityRef::Module(7));
+        network.markings.push(Marking {
+            layer: "dicing".into(),
+            path: ChannelPath::new(),
+            width: 100.,
+        });
+
+        let json = network.to_parchmint("bend", 1.).unwrap();
+        assert!(json.contains("\"entity\": \"MIXER\""));
+        assert!(json.contains("\"portRadius\": 350"));
+        let (_, dropped) = ParchmintDevice::from_network(&network, 1.).unwrap();
+        assert_eq!(dropped, [DroppedInfo::Markings(1), DroppedInfo::Groups(1)]);
+
+        let mut imported = Network::from_parchmint(&json, 1.).unwrap();
+        assert_eq!(imported.nodes, network.nodes);
+        assert_eq!(imported.modules, network.modules);
+        let pieces = &imported.channels[0].path.as_ref().unwrap().pieces;
+        assert!(matches!(pieces[0], PathPiece::LineSegment(_)));
+        assert!(pieces[1..].iter().all(|p| matches!(p, PathPiece::Arc(_))));
+        let tolerance = Tolerance::absolute(1.);
+        let length = imported.channels[0].length().unwrap();
+        assert!(tolerance.eq(length, network.channels[0].length().unwrap()));
+
+        // Equal up to the paths resampled from way points
+        for channel in (imported.channels.iter_mut()).chain(&mut network.channels) {
+            channel.path = None;
+        }
+        network.groups.clear();
+        network.markings.clear();
+        assert_eq!(imported, network);
+    }
+
+    #[test]
+    fn device_round_trip() {
+        let json = r#"{
+            "name": "splitter",
+            "params": {"x-span": 5000, "y-span": 3000},
+            "layers": [
+                {"id": "flow", "name": "flow", "type": "FLOW", "group": "0", "params": {}},
+                {"id": "control", "name": "control", "type": "CONTROL", "group": "0", "params": {}}
+            ],
+            "components": [
+                {"id": "in", "name": "Inlet", "entity": "PORT", "layers": ["flow"],
+                 "x-span": 1000, "y-span": 1000,
+                 "params": {"position": [0, 1000], "portRadius": 500, "rotation": 0},
+                 "ports": [{"label": "1", "layer": "flow", "x": 500, "y": 500}]},
+                {"id": "split", "name": "Split", "entity": "MIXER", "layers": ["flow"],
+                 "x-span": 1000, "y-span": 2000,
+                 "params": {"position": [2000, 500], "rotation": 0, "numberOfBends": 5},
+                 "ports": [
+                     {"label": "in", "layer": "flow", "x": 0, "y": 1000},
+                     {"label": "a", "layer": "flow", "x": 1000, "y": 0},
+                     {"label": "b", "layer": "flow", "x": 1000, "y": 2000}
+                 ]}
+            ],
+            "connections": [
+                {"id": "c1", "name": "feed", "entity": "CHANNEL", "layer": "flow",
+                 "source": {"component": "in", "port": "1"},
+                 "sinks": [{"component": "split", "port": "in"}],
+                 "params": {"channelWidth": 100, "height": 50},
+                 "paths": [{"source": {"component": "in", "port": "1"},
+                            "sink": {"component": "split", "port": "in"},
+                            "wayPoints": [[500, 1500], [1000, 1500], [1000, 1500],
+                                          [1000, 1800], [2000, 1800], [2000, 1500]],
+                            "features": ["f1"]}]},
+                {"id": "c2", "name": "loop", "entity": "CHANNEL", "layer": "flow",
+                 "source": {"component": "split", "port": "a"},
+                 "sinks": [{"component": "split", "port": "b"}, {"component": "in", "port": "1"}],
+                 "params": {"channelWidth": 80, "height": 50, "connectionSpacing": 1000},
+                 "paths": [{"source": {"component": "split", "port": "a"},
+                            "sink": {"component": "split", "port": "b"},
+                            "wayPoints": [[3000, 500], [4000, 500], [4000, 2500], [3000, 2500]],
+                            "features": []}]}
+            ],
+            "features": [{"id": "f1", "type": "channel"}],
+            "version": "1.2",
+            "valves": []
+        }"#;
+        let device = ParchmintDevice::from_json(json).unwrap();
+        assert_eq!(device.other["valves"], json!([]));
+        let network = device.to_network(1.).unwrap();
+        assert_eq!(network.nodes.len(), 4);
+        assert_eq!(network.modules.len(), 1);
+        assert_eq!(network.channels.len(), 3);
+        assert_eq!(network.channels[1].path.as_ref().unwrap().pieces.len(), 3);
+
+        let (exported, dropped) = ParchmintDevice::from_network(&network, 1.).unwrap();
+        assert!(dropped.is_empty());
+        // Repeated way points aren't kept
+        let mut expected = device.clone();
+        expected.connections[0].paths[0].way_points.remove(1);
+        assert_eq!(exported, expected);
+
+        // Imported components keep their parameters, a function set since is reported
+        let mut valve = network.clone();
+        valve.modules[0].kind = ModuleKind::Valve;
+        valve.modules[0].model = Some(PortModel {
+            conductance: vec![vec![0.; 3]; 3],
+        });
+        let (_, dropped) = ParchmintDevice::from_network(&valve, 1.).unwrap();
+        let id = valve.modules[0].id;
+        assert_eq!(
+            dropped,
+            [DroppedInfo::PortModel(id), DroppedInfo::ModuleKind(id)]
+        );
+
+        let mut invalid = device;
+        invalid.layers[1].id = "flow".into();
+        invalid.connections[1].sinks[0].port = Some("c".into());
+        assert_eq!(
+            invalid.validate(),
+            [
+                ParchmintIssue::DuplicateId("flow".into()),
+                ParchmintIssue::UnknownPort {
+                    connection: "c2".into(),
+                    component: "split".into(),
+                    port: "c".into(),
+                },
+            ]
+        );
+        assert!(matches!(
+            invalid.to_network(1.),
+            Err(ParchmintError::Invalid(_))
+        ));
+    }
+}
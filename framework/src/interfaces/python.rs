@@ -11,7 +11,7 @@
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// mmft_framework::py_interface_function!(
 ///     module,
 ///     create_meander,
@@ -45,3 +45,211 @@ macro_rules! py_interface_function {
         }
     };
 }
+
+#[macro_export]
+/// Generates a python binding like `py_interface_function!` that validates the input against the
+/// schema of its type first. Violations raise a `ValueError` listing every offending value by
+/// its JSON pointer.
+///
+/// # Arguments
+///
+/// * `module` - the python module parameter (see pyo3)
+/// * `function_name` - the call name of the function
+/// * `call_function` - the function to be bound
+/// * `input_type` - type of the input, must implement `JsonSchema`
+///
+/// # Examples
+///
+/// ```ignore
+/// mmft_framework::py_validated_function!(
+///     module,
+///     create_meander,
+///     meander_designer_lib::meander_designer::create_meander,
+///     meander_designer_lib::meander_designer::MeanderParameters
+/// );
+/// ```
+macro_rules! py_validated_function {
+    ($module: ident, $function_name: ident, $call_function: path, $input_type: ty) => {
+        paste::item! {
+            #[pyfunction]
+            fn [<$function_name>](py: Python, input: PyObject) -> PyResult<Py<PyAny>> {
+                let value: serde_json::Value = pythonize::depythonize(input.as_ref(py))
+                    .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+                let parameters: $input_type =
+                    $crate::interfaces::validation::from_value_validated(value).map_err(|e| {
+                        pyo3::exceptions::PyValueError::new_err(
+                            $crate::interfaces::validation::describe(&e),
+                        )
+                    })?;
+                let result = $call_function(parameters);
+                Ok(pythonize::pythonize(py, &result).unwrap())
+            }
+
+            $module.add_function(wrap_pyfunction!($function_name, $module)?)?;
+        }
+    };
+}
+
+#[macro_export]
+/// Generates a python binding that tunes the parameters of a figure of merit. The function
+/// takes an `OptimizationProblem` dict and returns the `OptimizationResult`.
+///
+/// # Arguments
+///
+/// * `module` - the python module parameter (see pyo3)
+/// * `function_name` - the call name of the function
+/// * `objective` - function from the parameters to the figure of merit to be minimized
+/// * `parameter_type` - serde compatible type of the parameters
+///
+/// # Examples
+///
+/// ```ignore
+/// mmft_framework::py_optimize_function!(
+///     module,
+///     optimize_split,
+///     splitter_designer::flow_split_error,
+///     splitter_designer::SplitterParameters
+/// );
+/// ```
+macro_rules! py_optimize_function {
+    ($module: ident, $function_name: ident, $objective: path, $parameter_type: ty) => {
+        paste::item! {
+            #[pyfunction]
+            fn [<$function_name>](py: Python, input: PyObject) -> PyResult<Py<PyAny>> {
+                let problem: $crate::optimize::OptimizationProblem<$parameter_type> =
+                    pythonize::depythonize(input.as_ref(py)).unwrap();
+                let result = $crate::optimize::optimize(&problem, $objective)
+                    .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+                Ok(pythonize::pythonize(py, &result).unwrap())
+            }
+
+            $module.add_function(wrap_pyfunction!($function_name, $module)?)?;
+        }
+    };
+}
+
+#[macro_export]
+/// Generates a python function forwarding the framework's spans and events to the `logging`
+/// module. The logger of each event is named after its module path, e.g.,
+/// `mmft_framework.simulation.solver`; span durations are logged at the debug level. Requires
+/// the `tracing` feature.
+///
+/// # Arguments
+///
+/// * `module` - the python module parameter (see pyo3)
+/// * `function_name` - the call name of the function
+///
+/// # Examples
+///
+/// ```ignore
+/// mmft_framework::py_logging!(module, enable_logging);
+/// ```
+macro_rules! py_logging {
+    ($module: ident, $function_name: ident) => {
+        paste::item! {
+            #[pyfunction]
+            fn [<$function_name>]() {
+                use $crate::trace::Level;
+
+                struct Logging;
+
+                impl Logging {
+                    fn log(&self, level: Level, target: &str, message: &str) {
+                        // Numeric levels of the logging module, TRACE below DEBUG
+                        let number = match level {
+                            Level::Error => 40,
+                            Level::Warn => 30,
+                            Level::Info => 20,
+                            Level::Debug => 10,
+                            Level::Trace => 5,
+                        };
+                        Python::with_gil(|py| {
+                            let _ = py.import("logging").and_then(|logging| {
+                                logging
+                                    .call_method1("getLogger", (target.replace("::", "."),))?
+                                    .call_method1("log", (number, message))
+                            });
+                        });
+                    }
+                }
+
+                impl $crate::trace::Subscriber for Logging {
+                    fn event(&self, level: Level, target: &str, message: &str) {
+                        self.log(level, target, message);
+                    }
+
+                    fn exit(&self, target: &str, name: &str, elapsed: std::time::Duration) {
+                        self.log(Level::Debug, target, &format!("{name} took {elapsed:?}"));
+                    }
+                }
+
+                $crate::trace::set_subscriber(Logging);
+            }
+
+            $module.add_function(wrap_pyfunction!($function_name, $module)?)?;
+        }
+    };
+}
+
+#[macro_export]
+/// Generates a python binding like `py_interface_function!` for a function taking a
+/// `ProgressHandle` after its input. The binding accepts an optional callback called with the
+/// stage, the completed, and the total units of work; returning `False` cancels the run,
+/// which raises an `InterruptedError`. The function returns a `Result` whose errors are raised
+/// as `ValueError`.
+///
+/// # Arguments
+///
+/// * `module` - the python module parameter (see pyo3)
+/// * `function_name` - the call name of the function
+/// * `call_function` - the function to be bound
+///
+/// # Examples
+///
+/// ```ignore
+/// mmft_framework::py_progress_function!(
+///     module,
+///     route_droplets,
+///     dmf_designer::route_droplets
+/// );
+/// ```
+macro_rules! py_progress_function {
+    ($module: ident, $function_name: ident, $call_function: path) => {
+        paste::item! {
+            #[pyfunction]
+            #[pyo3(signature = (input, callback = None))]
+            fn [<$function_name>](
+                py: Python,
+                input: PyObject,
+                callback: Option<PyObject>,
+            ) -> PyResult<Py<PyAny>> {
+                let parameters = pythonize::depythonize(input.as_ref(py)).unwrap();
+                let token = $crate::progress::CancellationToken::default();
+                let progress = match callback {
+                    Some(callback) => $crate::progress::ProgressHandle::new({
+                        let token = token.clone();
+                        move |p: &$crate::progress::Progress| {
+                            Python::with_gil(|py| {
+                                let result = callback.call1(py, (p.stage, p.completed, p.total));
+                                if matches!(result.map(|r| r.extract::<bool>(py)), Ok(Ok(false))) {
+                                    token.cancel();
+                                }
+                            });
+                        }
+                    }),
+                    None => $crate::progress::ProgressHandle::default(),
+                }
+                .with_token(token.clone());
+                let result = $call_function(parameters, &progress);
+                if token.is_cancelled() {
+                    return Err(pyo3::exceptions::PyInterruptedError::new_err("cancelled"));
+                }
+                let result =
+                    result.map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+                Ok(pythonize::pythonize(py, &result).unwrap())
+            }
+
+            $module.add_function(wrap_pyfunction!($function_name, $module)?)?;
+        }
+    };
+}
@@ -0,0 +1,177 @@
+//! Registry of the JSON schemas of input and output types. The framework registers its own
+//! types; designer crates add theirs with [`register`]. All schemas are exported as a single
+//! bundle whose definitions reference each other, e.g., for documentation sites or validating
+//! inputs on the client.
+
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::{Metadata, RootSchema, Schema, SchemaObject},
+    JsonSchema,
+};
+use std::sync::Mutex;
+
+type SchemaFn = fn(&mut SchemaGenerator) -> Schema;
+
+#[derive(Debug, Clone, Default)]
+/// Named types whose schemas are exported together
+pub struct SchemaRegistry {
+    entries: Vec<(String, SchemaFn)>,
+}
+
+/// Types registered by downstream crates
+static REGISTERED: Mutex<Vec<(String, SchemaFn)>> = Mutex::new(Vec::new());
+
+impl SchemaRegistry {
+    /// Adds the type under its schema name; registering a name again has no effect
+    pub fn register<T: JsonSchema>(&mut self) {
+        self.insert(T::schema_name(), |gen| gen.subschema_for::<T>());
+    }
+
+    fn insert(&mut self, name: String, schema: SchemaFn) {
+        if !self.entries.iter().any(|(n, _)| *n == name) {
+            self.entries.push((name, schema));
+        }
+    }
+
+    /// Names of the registered types in registration order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Single schema holding all registered types and their dependencies as definitions. The
+    /// names of the registered types are listed in `x-registered`.
+    pub fn bundle(&self) -> RootSchema {
+        let mut gen = SchemaSettings::draft07().into_generator();
+        let mut inline = Vec::new();
+        for (name, schema) in &self.entries {
+            // Types that aren't referenceable, e.g., collections, are defined under their name
+            let schema = schema(&mut gen);
+            if !matches!(&schema, Schema::Object(o) if o.is_ref()) {
+                inline.push((name.clone(), schema));
+            }
+        }
+        let mut definitions = gen.take_definitions();
+        definitions.extend(inline);
+
+        let mut schema = SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                title: Some("mmft-framework schemas".into()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        schema
+            .extensions
+            .insert("x-registered".into(), self.names().collect());
+        RootSchema {
+            meta_schema: gen.settings().meta_schema.clone(),
+            schema,
+            definitions,
+        }
+    }
+
+    /// Pretty-printed JSON of the bundle
+    pub fn bundle_json(&self) -> String {
+        serde_json::to_string_pretty(&self.bundle()).unwrap()
+    }
+}
+
+/// Registers a type with the global registry returned by [`schemas`]
+pub fn register<T: JsonSchema>() {
+    let mut registered = REGISTERED.lock().unwrap();
+    if !registered.iter().any(|(name, _)| *name == T::schema_name()) {
+        registered.push((T::schema_name(), |gen| gen.subschema_for::<T>()));
+    }
+}
+
+/// The framework's types followed by all types registered with [`register`]
+pub fn schemas() -> SchemaRegistry {
+    use crate::{analysis, base, designer, dmf, export, project, simulation};
+
+    let mut registry = SchemaRegistry::default();
+    registry.register::<base::network::Network>();
+    registry.register::<base::edit::Command>();
+    registry.register::<base::centerline::Centerline>();
+    registry.register::<base::generator::RandomNetworkSpec>();
+    registry.register::<base::interop::InteropRules>();
+    registry.register::<base::pick::PickQuery>();
+    registry.register::<base::pick::Pick>();
+    registry.register::<base::panel::PanelConfig>();
+    registry.register::<base::control::ControlLayerConfig>();
+    registry.register::<base::control::ControlLine>();
+    registry.register::<base::control::ControlLayerError>();
+    registry.register::<base::render::RenderConfig>();
+    registry.register::<simulation::fluid::Fluid>();
+    registry.register::<simulation::fluid::ThermalFluid>();
+    registry.register::<simulation::solver::Boundary>();
+    registry.register::<simulation::solver::IterationSettings>();
+    registry.register::<simulation::losses::LossCoefficients>();
+    registry.register::<simulation::plugs::PlugTrain>();
+    registry.register::<simulation::plugs::PlugModel>();
+    registry.register::<simulation::scenario::ValveStates>();
+    registry.register::<simulation::scenario::ValveStateResult>();
+    registry.register::<simulation::solver::FlowSolution>();
+    registry.register::<simulation::result::SimulationResult>();
+    registry.register::<simulation::result::NodeTable>();
+    registry.register::<simulation::result::ChannelTable>();
+    registry.register::<simulation::capillary::CapillaryFilling>();
+    registry.register::<simulation::capillary::FillingResult>();
+    registry.register::<simulation::thermal::ThermalSetup>();
+    registry.register::<simulation::thermal::ThermalSolution>();
+    registry.register::<analysis::burst::BurstReport>();
+    registry.register::<analysis::regime::RegimeLimits>();
+    registry.register::<analysis::regime::RegimeReport>();
+    registry.register::<analysis::volume::VolumeReport>();
+    registry.register::<analysis::sensitivity::SensitivityReport>();
+    registry.register::<analysis::tolerance::DimensionTolerance>();
+    registry.register::<analysis::tolerance::ToleranceReport>();
+    registry.register::<analysis::synthesis::SynthesisProblem>();
+    registry.register::<analysis::synthesis::SynthesisResult>();
+    registry.register::<designer::alignment::AlignmentMarkParameters>();
+    registry.register::<designer::alignment::AlignmentMarks>();
+    registry.register::<designer::droplet::DropletGeneratorParameters>();
+    registry.register::<designer::droplet::DropletPrediction>();
+    registry.register::<designer::droplet::DropletTarget>();
+    registry.register::<designer::multiplexer::MultiplexerParameters>();
+    registry.register::<designer::multiplexer::Multiplexer>();
+    registry.register::<designer::tesla::TeslaValveParameters>();
+    registry.register::<designer::trap::TrapArrayParameters>();
+    registry.register::<dmf::DmfChip>();
+    registry.register::<dmf::routing::RouteRequest>();
+    registry.register::<dmf::routing::Schedule>();
+    registry.register::<export::style::Style>();
+    registry.register::<export::pdf::PdfConfig>();
+    registry.register::<export::gcode::MachineProfile>();
+    registry.register::<export::laser::LaserConfig>();
+    registry.register::<export::vtk::VtkConfig>();
+    registry.register::<export::package::PackageConfig>();
+    registry.register::<export::package::PackageManifest>();
+    registry.register::<project::Project>();
+    registry.register::<super::parchmint::ParchmintDevice>();
+    for (name, schema) in REGISTERED.lock().unwrap().iter() {
+        registry.insert(name.clone(), *schema);
+    }
+    registry
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bundle() {
+        register::<Vec<crate::base::primitives::Point>>();
+        let registry = schemas();
+        assert_eq!(registry.names().next(), Some("Network"));
+        assert_eq!(registry.names().last(), Some("Array_of_Point"));
+
+        let bundle = serde_json::to_value(registry.bundle()).unwrap();
+        let definitions = &bundle["definitions"];
+        assert_eq!(
+            definitions["Network"]["properties"]["channels"]["items"]["$ref"],
+            "#/definitions/Channel"
+        );
+        assert!(definitions["Array_of_Point"].is_object());
+        assert_eq!(bundle["x-registered"][0], "Network");
+    }
+}
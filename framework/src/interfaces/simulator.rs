@@ -0,0 +1,433 @@
+//! Conversion from and to the JSON input of the MMFT Droplet Simulator. The simulator describes a
+//! chip by positioned nodes, rectangular channels with explicit lengths, pumps between two nodes,
+//! and the fluids, all in SI units. Ground nodes are the pressure reference.
+
+use crate::{
+    base::{
+        channel::{Channel, RectangularShape, Shape},
+        network::{Network, Node, NodeId},
+        primitives::Point,
+        units::LengthUnit,
+    },
+    simulation::{
+        fluid::{Fluid, Rheology},
+        solver::{Boundary, BoundaryCondition},
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+/// A network together with its fluids and boundary conditions
+pub struct SimulatorCase {
+    pub network: Network,
+
+    /// Fluids, the first one is the continuous phase
+    pub fluids: Vec<Fluid>,
+
+    pub boundaries: Vec<Boundary>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Reasons a case can't be converted
+pub enum SimulatorFormatError {
+    /// Malformed JSON or a structure that doesn't match the format
+    Json(String),
+
+    /// The node has no position
+    UnplacedNode(NodeId),
+
+    /// The channel has neither a length nor a path
+    MissingLength(usize),
+
+    /// The simulator only supports rectangular channels
+    UnsupportedShape(usize),
+
+    /// A channel or pump refers to a node index out of range
+    UnknownNode(usize),
+
+    /// The pump at the index connects two nodes that are both not ground
+    UnsupportedPump(usize),
+}
+
+impl std::fmt::Display for SimulatorFormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimulatorFormatError::Json(message) => write!(f, "invalid simulator JSON: {message}"),
+            SimulatorFormatError::UnplacedNode(NodeId(id)) => {
+                write!(f, "node {id} has no position")
+            }
+            SimulatorFormatError::MissingLength(id) => write!(f, "channel {id} has no length"),
+            SimulatorFormatError::UnsupportedShape(id) => {
+                write!(f, "channel {id} is not rectangular")
+            }
+            SimulatorFormatError::UnknownNode(index) => write!(f, "no node with index {index}"),
+            SimulatorFormatError::UnsupportedPump(index) => {
+                write!(f, "pump {index} isn't connected to ground")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimulatorFormatError {}
+
+#[derive(Serialize, Deserialize)]
+struct SimulatorNode {
+    x: f64,
+    y: f64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    ground: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SimulatorChannel {
+    node1: usize,
+    node2: usize,
+    width: f64,
+    height: f64,
+    length: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum SimulatorPump {
+    PumpPressure {
+        node1: usize,
+        node2: usize,
+        pressure: f64,
+    },
+    PumpFlowRate {
+        node1: usize,
+        node2: usize,
+        #[serde(rename = "flowRate")]
+        flow_rate: f64,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct SimulatorFluid {
+    name: String,
+    viscosity: f64,
+    density: f64,
+    #[serde(default)]
+    concentration: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SimulatorNetwork {
+    nodes: Vec<SimulatorNode>,
+    channels: Vec<SimulatorChannel>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulatorSetup {
+    fluids: Vec<SimulatorFluid>,
+    pumps: Vec<SimulatorPump>,
+    #[serde(default)]
+    continuous_phase: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SimulatorFile {
+    network: SimulatorNetwork,
+    simulation: SimulatorSetup,
+}
+
+impl SimulatorCase {
+    /// JSON input of the simulator; lengths are converted from layout units to meters, by the
+    /// network's declared length unit or else by the given scale. Nodes with zero pressure
+    /// become ground nodes, all other boundary conditions pumps from ground.
+    /// Non-Newtonian fluids are exported with their reference viscosity.
+    pub fn to_simulator_format(
+        &self,
+        units_per_meter: f64,
+    ) -> Result<String, SimulatorFormatError> {
+        let network = &self.network;
+        let units_per_meter = network
+            .units_per(LengthUnit::Meter)
+            .unwrap_or(units_per_meter);
+        let index: BTreeMap<NodeId, usize> = network
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.id, i))
+            .collect();
+        let lookup = |id: NodeId| {
+            index
+                .get(&id)
+                .copied()
+                .ok_or(SimulatorFormatError::UnknownNode(id.0))
+        };
+
+        let mut nodes = network
+            .nodes
+            .iter()
+            .map(|node| {
+                let Point([x, y]) = network
+                    .node_position(node.id)
+                    .ok_or(SimulatorFormatError::UnplacedNode(node.id))?;
+                Ok(SimulatorNode {
+                    x: x / units_per_meter,
+                    y: y / units_per_meter,
+                    ground: false,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let channels = network
+            .channels
+            .iter()
+            .map(|channel| {
+                let Shape::Rectangular(shape) = channel.shape else {
+                    return Err(SimulatorFormatError::UnsupportedShape(channel.id));
+                };
+                let length = channel
+                    .length()
+                    .ok_or(SimulatorFormatError::MissingLength(channel.id))?;
+                Ok(SimulatorChannel {
+                    node1: lookup(channel.node_a)?,
+                    node2: lookup(channel.node_b)?,
+                    width: shape.width / units_per_meter,
+                    height: shape.height / units_per_meter,
+                    length: length / units_per_meter,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut ground = None;
+        for boundary in &self.boundaries {
+            if boundary.condition == BoundaryCondition::Pressure(0.) {
+                let i = lookup(boundary.node)?;
+                nodes[i].ground = true;
+                ground.get_or_insert(i);
+            }
+        }
+        let mut pumps = Vec::new();
+        for boundary in &self.boundaries {
+            let node2 = lookup(boundary.node)?;
+            if nodes[node2].ground {
+                continue;
+            }
+            // Without a grounded node, the reference is an extra node on top of the first pump
+            let node1 = *ground.get_or_insert_with(|| {
+                let (x, y) = (nodes[node2].x, nodes[node2].y);
+                nodes.push(SimulatorNode { x, y, ground: true });
+                nodes.len() - 1
+            });
+            pumps.push(match boundary.condition {
+                BoundaryCondition::Pressure(pressure) => SimulatorPump::PumpPressure {
+                    node1,
+                    node2,
+                    pressure,
+                },
+                BoundaryCondition::Flow(flow_rate) => SimulatorPump::PumpFlowRate {
+                    node1,
+                    node2,
+                    flow_rate,
+                },
+            });
+        }
+
+        let fluids = self
+            .fluids
+            .iter()
+            .enumerate()
+            .map(|(i, fluid)| SimulatorFluid {
+                name: format!("fluid{i}"),
+                viscosity: fluid.viscosity,
+                density: fluid.density,
+                concentration: 0.,
+            })
+            .collect();
+
+        let file = SimulatorFile {
+            network: SimulatorNetwork { nodes, channels },
+            simulation: SimulatorSetup {
+                fluids,
+                pumps,
+                continuous_phase: 0,
+            },
+        };
+        Ok(serde_json::to_string_pretty(&file).unwrap())
+    }
+
+    /// Reads the JSON input of the simulator, converting meters to layout units; the network
+    /// declares the length unit of that scale, if any. Nodes and
+    /// channels are numbered in the order of the file; ground nodes become zero-pressure
+    /// boundaries. The continuous phase is moved to the front of the fluids.
+    pub fn from_simulator_format(
+        json: &str,
+        units_per_meter: f64,
+    ) -> Result<SimulatorCase, SimulatorFormatError> {
+        let file: SimulatorFile =
+            serde_json::from_str(json).map_err(|e| SimulatorFormatError::Json(e.to_string()))?;
+        let SimulatorFile {
+            network: SimulatorNetwork { nodes, channels },
+            simulation,
+        } = file;
+        let node = |index: usize| {
+            (index < nodes.len())
+                .then_some(NodeId(index))
+                .ok_or(SimulatorFormatError::UnknownNode(index))
+        };
+
+        let mut boundaries: Vec<Boundary> = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.ground)
+            .map(|(i, _)| Boundary {
+                node: NodeId(i),
+                condition: BoundaryCondition::Pressure(0.),
+            })
+            .collect();
+        for (i, pump) in simulation.pumps.iter().enumerate() {
+            let (node1, node2, condition) = match *pump {
+                SimulatorPump::PumpPressure {
+                    node1,
+                    node2,
+                    pressure,
+                } => (node1, node2, BoundaryCondition::Pressure(pressure)),
+                SimulatorPump::PumpFlowRate {
+                    node1,
+                    node2,
+                    flow_rate,
+                } => (node1, node2, BoundaryCondition::Flow(flow_rate)),
+            };
+            let (a, b) = (node(node1)?, node(node2)?);
+            let boundary = match (nodes[node1].ground, nodes[node2].ground) {
+                (true, false) => Boundary { node: b, condition },
+                (false, true) => Boundary {
+                    node: a,
+                    condition: match condition {
+                        BoundaryCondition::Pressure(p) => BoundaryCondition::Pressure(-p),
+                        BoundaryCondition::Flow(q) => BoundaryCondition::Flow(-q),
+                    },
+                },
+                _ => return Err(SimulatorFormatError::UnsupportedPump(i)),
+            };
+            boundaries.push(boundary);
+        }
+
+        let network = Network {
+            nodes: nodes
+                .iter()
+                .enumerate()
+                .map(|(i, n)| {
+                    Node::at(
+                        NodeId(i),
+                        Point([n.x * units_per_meter, n.y * units_per_meter]),
+                    )
+                })
+                .collect(),
+            channels: channels
+                .iter()
+                .enumerate()
+                .map(|(id, c)| {
+                    Ok(Channel {
+                        id,
+                        node_a: node(c.node1)?,
+                        node_b: node(c.node2)?,
+                        shape: Shape::Rectangular(RectangularShape {
+                            width: c.width * units_per_meter,
+                            height: c.height * units_per_meter,
+                        }),
+                        path: None,
+                        length: Some(c.length * units_per_meter),
+                        layer: 0,
+                        metadata: Default::default(),
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            length_unit: LengthUnit::with_scale(units_per_meter, LengthUnit::Meter),
+            ..Default::default()
+        };
+
+        let mut fluids: Vec<Fluid> = simulation
+            .fluids
+            .iter()
+            .map(|f| Fluid {
+                viscosity: f.viscosity,
+                rheology: Rheology::Newtonian,
+                density: f.density,
+                diffusivity: None,
+                surface_tension: None,
+            })
+            .collect();
+        if simulation.continuous_phase < fluids.len() {
+            let continuous = fluids.remove(simulation.continuous_phase);
+            fluids.insert(0, continuous);
+        }
+
+        Ok(SimulatorCase {
+            network,
+            fluids,
+            boundaries,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::generator::{RandomNetworkSpec, Topology},
+        simulation::solver::solve,
+    };
+
+    #[test]
+    fn round_trip() {
+        let network = Network::random(&RandomNetworkSpec {
+            topology: Topology::Grid {
+                columns: 3,
+                rows: 2,
+            },
+            rng: crate::random::Rng::new(1),
+            ..Default::default()
+        });
+        let boundaries = vec![
+            Boundary {
+                node: NodeId(0),
+                condition: BoundaryCondition::Flow(1e-12),
+            },
+            Boundary {
+                node: NodeId(5),
+                condition: BoundaryCondition::Pressure(0.),
+            },
+        ];
+        let case = SimulatorCase {
+            network,
+            fluids: vec![Fluid::water()],
+            boundaries,
+        };
+        let json = case.to_simulator_format(1e6).unwrap();
+        assert!(json.contains("\"type\": \"PumpFlowRate\""));
+
+        let imported = SimulatorCase::from_simulator_format(&json, 1e6).unwrap();
+        assert_eq!(imported.network.nodes.len(), 6);
+        assert_eq!(imported.boundaries.len(), 2);
+
+        // Scaled to meters, both descriptions have the same flows
+        let to_si = |case: &SimulatorCase| {
+            let mut network = case.network.clone();
+            for channel in &mut network.channels {
+                channel.length = channel.length().map(|l| l * 1e-6);
+                channel.path = None;
+                if let Shape::Rectangular(shape) = &mut channel.shape {
+                    shape.width *= 1e-6;
+                    shape.height *= 1e-6;
+                }
+            }
+            solve(&network, &case.fluids[0], &case.boundaries).unwrap()
+        };
+        let (original, imported) = (to_si(&case), to_si(&imported));
+        for (id, q) in &original.flows {
+            assert!((q - imported.flows[id]).abs() < 1e-9 * 1e-12);
+        }
+
+        assert!(matches!(
+            SimulatorCase::from_simulator_format("{}", 1e6),
+            Err(SimulatorFormatError::Json(_))
+        ));
+    }
+}
@@ -0,0 +1,451 @@
+//! TOML for hand-written design inputs, converted through `serde_json::Value` like YAML. Nested
+//! objects become tables and arrays of objects arrays of tables. TOML has no null, so null
+//! values in objects are left out and are rejected in arrays. Dates aren't supported.
+
+use super::TextFormatError;
+use crate::base::network::Network;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Number, Value};
+
+/// TOML document of the value, which must serialize to an object
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, TextFormatError> {
+    let value = serde_json::to_value(value).map_err(|e| TextFormatError::new(0, e))?;
+    let Value::Object(map) = value else {
+        return Err(TextFormatError::new(0, "TOML documents must be tables"));
+    };
+    let mut out = String::new();
+    write_table(&mut out, &mut Vec::new(), &map)?;
+    Ok(out.trim_start().to_string())
+}
+
+/// Value read from a TOML document
+pub fn from_str<T: DeserializeOwned>(text: &str) -> Result<T, TextFormatError> {
+    let mut root = Map::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut lines = text.lines().enumerate();
+    while let Some((i, line)) = lines.next() {
+        let number = i + 1;
+        let error = |message: String| TextFormatError::new(number, message);
+        let mut statement = line.trim().to_string();
+        if statement.is_empty() || statement.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = statement.strip_prefix("[[") {
+            let header = strip_comment(header).trim();
+            let path = header
+                .strip_suffix("]]")
+                .ok_or(error("expected `]]`".into()))?;
+            let path = keys(path).map_err(error)?;
+            let (last, parent) = path.split_last().ok_or(error("empty table name".into()))?;
+            let parent = table(&mut root, parent).map_err(error)?;
+            match parent
+                .entry(last.clone())
+                .or_insert_with(|| Value::Array(Vec::new()))
+            {
+                Value::Array(items) => items.push(Value::Object(Map::new())),
+                _ => return Err(error(format!("`{last}` is not an array of tables"))),
+            }
+            current = path;
+            continue;
+        }
+        if let Some(header) = statement.strip_prefix('[') {
+            let header = strip_comment(header).trim();
+            let path = header
+                .strip_suffix(']')
+                .ok_or(error("expected `]`".into()))?;
+            current = keys(path).map_err(error)?;
+            table(&mut root, &current).map_err(error)?;
+            continue;
+        }
+
+        // Arrays and inline tables may continue on the following lines
+        while !balanced(&statement) {
+            let (_, next) = lines.next().ok_or(error("unterminated value".into()))?;
+            statement.push('\n');
+            statement.push_str(next);
+        }
+        let mut cursor = Cursor::new(&statement);
+        let path = cursor.keys().map_err(error)?;
+        if !cursor.eat('=') {
+            return Err(error("expected `=`".into()));
+        }
+        let value = cursor.value().map_err(error)?;
+        cursor.skip();
+        if !cursor.rest().is_empty() {
+            return Err(error(format!("unexpected `{}`", cursor.rest())));
+        }
+        let (last, parents) = path.split_last().unwrap();
+        let full: Vec<String> = current.iter().chain(parents).cloned().collect();
+        let table = table(&mut root, &full).map_err(error)?;
+        if table.insert(last.clone(), value).is_some() {
+            return Err(error(format!("duplicate key `{last}`")));
+        }
+    }
+    serde_json::from_value(Value::Object(root)).map_err(|e| TextFormatError::new(0, e))
+}
+
+impl Network {
+    /// Network from a TOML document
+    pub fn from_toml(text: &str) -> Result<Network, TextFormatError> {
+        from_str(text)
+    }
+
+    /// TOML document of the network
+    pub fn to_toml(&self) -> String {
+        to_string(self).unwrap()
+    }
+}
+
+/// Whether the value is written as `key = value` rather than in a table section
+fn is_inline(value: &Value) -> bool {
+    match value {
+        Value::Object(_) => false,
+        Value::Array(items) => {
+            items.is_empty() || !items.iter().all(|v| matches!(v, Value::Object(_)))
+        }
+        _ => true,
+    }
+}
+
+fn write_table(
+    out: &mut String,
+    path: &mut Vec<String>,
+    map: &Map<String, Value>,
+) -> Result<(), TextFormatError> {
+    for (key, value) in map {
+        if is_inline(value) && !value.is_null() {
+            out.push_str(&format!("{} = {}\n", key_name(key), inline(value)?));
+        }
+    }
+    for (key, value) in map {
+        path.push(key_name(key));
+        match value {
+            Value::Object(table) => {
+                out.push_str(&format!("\n[{}]\n", path.join(".")));
+                write_table(out, path, table)?;
+            }
+            Value::Array(items) if !is_inline(value) => {
+                for item in items {
+                    out.push_str(&format!("\n[[{}]]\n", path.join(".")));
+                    if let Value::Object(table) = item {
+                        write_table(out, path, table)?;
+                    }
+                }
+            }
+            _ => (),
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+fn inline(value: &Value) -> Result<String, TextFormatError> {
+    Ok(match value {
+        Value::Null => return Err(TextFormatError::new(0, "TOML can't represent null")),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        // JSON escapes are valid in TOML basic strings
+        Value::String(s) => Value::String(s.clone()).to_string(),
+        Value::Array(items) => {
+            let items = items.iter().map(inline).collect::<Result<Vec<_>, _>>()?;
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(map) => {
+            let entries: Vec<String> = map
+                .iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| Ok(format!("{} = {}", key_name(k), inline(v)?)))
+                .collect::<Result<_, TextFormatError>>()?;
+            if entries.is_empty() {
+                "{}".into()
+            } else {
+                format!("{{ {} }}", entries.join(", "))
+            }
+        }
+    })
+}
+
+fn key_name(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        key.into()
+    } else {
+        Value::String(key.into()).to_string()
+    }
+}
+
+/// Text before a comment of a table header
+fn strip_comment(text: &str) -> &str {
+    text.split('#').next().unwrap_or_default()
+}
+
+/// Dotted key of a table header
+fn keys(text: &str) -> Result<Vec<String>, String> {
+    let mut cursor = Cursor::new(text);
+    let keys = cursor.keys()?;
+    cursor.skip();
+    if !cursor.rest().is_empty() {
+        return Err(format!("unexpected `{}`", cursor.rest()));
+    }
+    Ok(keys)
+}
+
+/// Table at the path, created if missing; arrays of tables resolve to their last element
+fn table<'a>(
+    root: &'a mut Map<String, Value>,
+    path: &[String],
+) -> Result<&'a mut Map<String, Value>, String> {
+    let mut table = root;
+    for key in path {
+        let value = table
+            .entry(key.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        let value = match value {
+            Value::Array(items) => items.last_mut().ok_or(format!("`{key}` is empty"))?,
+            value => value,
+        };
+        table = match value {
+            Value::Object(table) => table,
+            _ => return Err(format!("`{key}` is not a table")),
+        };
+    }
+    Ok(table)
+}
+
+/// Whether all brackets outside of strings and comments are closed
+fn balanced(text: &str) -> bool {
+    let mut depth = 0i32;
+    let mut cursor = Cursor::new(text);
+    while let Some(c) = cursor.rest().chars().next() {
+        match c {
+            '"' | '\'' => {
+                if cursor.string().is_err() {
+                    return true;
+                }
+                continue;
+            }
+            '#' => {
+                let line_end = cursor.rest().find('\n').unwrap_or(cursor.rest().len());
+                cursor.position += line_end;
+                continue;
+            }
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth -= 1,
+            _ => (),
+        }
+        cursor.position += c.len_utf8();
+    }
+    depth <= 0
+}
+
+struct Cursor<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(text: &'a str) -> Self {
+        Cursor { text, position: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    /// Skips whitespace, newlines, and comments
+    fn skip(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.position += rest.len() - trimmed.len();
+            if trimmed.starts_with('#') {
+                self.position += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip();
+        if self.rest().starts_with(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn keys(&mut self) -> Result<Vec<String>, String> {
+        let mut keys = vec![self.key()?];
+        while self.eat('.') {
+            keys.push(self.key()?);
+        }
+        Ok(keys)
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        self.skip();
+        if self.rest().starts_with(['"', '\'']) {
+            return self.string();
+        }
+        let rest = self.rest();
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(rest.len());
+        if end == 0 {
+            return Err("expected a key".into());
+        }
+        self.position += end;
+        Ok(rest[..end].into())
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let rest = self.rest();
+        if let Some(literal) = rest.strip_prefix('\'') {
+            let end = literal.find('\'').ok_or("unterminated string")?;
+            self.position += end + 2;
+            return Ok(literal[..end].into());
+        }
+        let mut escaped = false;
+        let end = rest[1..]
+            .char_indices()
+            .find(|(_, c)| {
+                let end = *c == '"' && !escaped;
+                escaped = *c == '\\' && !escaped;
+                end
+            })
+            .map(|(i, _)| i + 2)
+            .ok_or("unterminated string")?;
+        self.position += end;
+        serde_json::from_str(&rest[..end]).map_err(|e| e.to_string())
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip();
+        let rest = self.rest();
+        if rest.starts_with(['"', '\'']) {
+            return self.string().map(Value::String);
+        }
+        if rest.starts_with('[') {
+            self.position += 1;
+            let mut items = Vec::new();
+            loop {
+                if self.eat(']') {
+                    break;
+                }
+                items.push(self.value()?);
+                if !self.eat(',') {
+                    if self.eat(']') {
+                        break;
+                    }
+                    return Err("expected `,` or `]`".into());
+                }
+            }
+            return Ok(Value::Array(items));
+        }
+        if rest.starts_with('{') {
+            self.position += 1;
+            let mut map = Map::new();
+            if self.eat('}') {
+                return Ok(Value::Object(map));
+            }
+            loop {
+                let path = self.keys()?;
+                if !self.eat('=') {
+                    return Err("expected `=`".into());
+                }
+                let value = self.value()?;
+                let (last, parents) = path.split_last().unwrap();
+                table(&mut map, parents)?.insert(last.clone(), value);
+                if self.eat('}') {
+                    return Ok(Value::Object(map));
+                }
+                if !self.eat(',') {
+                    return Err("expected `,` or `}`".into());
+                }
+            }
+        }
+
+        let end = rest
+            .find(|c: char| c.is_whitespace() || ",]}#".contains(c))
+            .unwrap_or(rest.len());
+        let token = &rest[..end];
+        self.position += end;
+        match token {
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            _ => (),
+        }
+        let digits = token.replace('_', "");
+        if let Ok(i) = digits.parse::<i64>() {
+            return Ok(Value::from(i));
+        }
+        let numeric = digits.starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-');
+        digits
+            .parse::<f64>()
+            .ok()
+            .filter(|_| numeric && !digits.contains(['i', 'n', 'I', 'N']))
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or(format!("invalid value `{token}`"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::generator::{RandomNetworkSpec, Topology};
+
+    #[test]
+    fn round_trip() {
+        let network = Network::random(&RandomNetworkSpec {
+            topology: Topology::Grid {
+                columns: 2,
+                rows: 2,
+            },
+            rng: crate::random::Rng::new(5),
+            ..Default::default()
+        });
+        let toml = network.to_toml();
+        assert!(toml.contains("[[channels]]"));
+        assert_eq!(Network::from_toml(&toml).unwrap(), network);
+    }
+
+    #[test]
+    fn hand_written() {
+        let value: Value = from_str(
+            "title = 'chip' # comment\n\
+             size = [\n  15_000,\n  3.5e1, # last\n]\n\
+             shape.rectangular = { width = 100, height = 50 }\n\
+             \n\
+             [[nodes]]\n\
+             id = 0\n\
+             [nodes.metadata]\n\
+             \"a key\" = true\n\
+             [[nodes]]\n\
+             id = 1\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "title": "chip",
+                "size": [15000, 35.0],
+                "shape": {"rectangular": {"width": 100, "height": 50}},
+                "nodes": [{"id": 0, "metadata": {"a key": true}}, {"id": 1}]
+            })
+        );
+        assert_eq!(
+            from_str::<Value>(&to_string(&value).unwrap()).unwrap(),
+            value
+        );
+
+        assert_eq!(from_str::<Value>("a = 1\na = 2").unwrap_err().line, 2);
+        assert!(to_string(&serde_json::json!({"a": [null]})).is_err());
+    }
+}
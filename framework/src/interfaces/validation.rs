@@ -0,0 +1,505 @@
+//! Validation of JSON inputs against the `JsonSchema` of the target type before deserializing.
+//! Errors name the offending value by its JSON pointer, e.g.,
+//! `/channels/3/shape/rectangular/width must be > 0`, and all violations are reported at once.
+//!
+//! The strict variants additionally reject fields the type doesn't know, which serde otherwise
+//! silently ignores, so typos like `widht` surface instead of falling back to defaults.
+
+use schemars::{
+    schema::{InstanceType, ObjectValidation, RootSchema, Schema, SchemaObject, SingleOrVec},
+    schema_for, JsonSchema,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Violation of the schema at a location of the input
+pub struct ValidationError {
+    /// JSON pointer of the offending value, empty for the whole input
+    pub pointer: String,
+
+    pub message: String,
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(f, "{pointer} {}", self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// All messages, one per line
+pub fn describe(errors: &[ValidationError]) -> String {
+    let lines: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    lines.join("\n")
+}
+
+/// Checks the value against the schema of the type
+pub fn validate<T: JsonSchema>(value: &Value) -> Result<(), Vec<ValidationError>> {
+    run::<T>(value, false)
+}
+
+/// Checks the value against the schema of the type, rejecting fields the schema doesn't list
+pub fn validate_strict<T: JsonSchema>(value: &Value) -> Result<(), Vec<ValidationError>> {
+    run::<T>(value, true)
+}
+
+/// Validates the value and deserializes it; remaining serde errors are reported for the whole
+/// input
+pub fn from_value_validated<T: JsonSchema + DeserializeOwned>(
+    value: Value,
+) -> Result<T, Vec<ValidationError>> {
+    validate::<T>(&value)?;
+    deserialize(value)
+}
+
+/// Like [from_value_validated], but unknown fields are errors
+pub fn from_value_strict<T: JsonSchema + DeserializeOwned>(
+    value: Value,
+) -> Result<T, Vec<ValidationError>> {
+    validate_strict::<T>(&value)?;
+    deserialize(value)
+}
+
+/// Parses, validates, and deserializes the JSON text
+pub fn from_json_validated<T: JsonSchema + DeserializeOwned>(
+    json: &str,
+) -> Result<T, Vec<ValidationError>> {
+    from_value_validated(parse(json)?)
+}
+
+/// Like [from_json_validated], but unknown fields are errors
+pub fn from_json_strict<T: JsonSchema + DeserializeOwned>(
+    json: &str,
+) -> Result<T, Vec<ValidationError>> {
+    from_value_strict(parse(json)?)
+}
+
+fn run<T: JsonSchema>(value: &Value, strict: bool) -> Result<(), Vec<ValidationError>> {
+    let root = schema_for!(T);
+    let mut validator = Validator {
+        root: &root,
+        strict,
+        errors: Vec::new(),
+    };
+    validator.object(&root.schema, value, &mut String::new());
+    match validator.errors.is_empty() {
+        true => Ok(()),
+        false => Err(validator.errors),
+    }
+}
+
+fn parse(json: &str) -> Result<Value, Vec<ValidationError>> {
+    serde_json::from_str(json).map_err(|e| {
+        vec![ValidationError {
+            pointer: String::new(),
+            message: format!("is not valid JSON: {e}"),
+        }]
+    })
+}
+
+fn deserialize<T: DeserializeOwned>(value: Value) -> Result<T, Vec<ValidationError>> {
+    serde_json::from_value(value).map_err(|e| {
+        vec![ValidationError {
+            pointer: String::new(),
+            message: e.to_string(),
+        }]
+    })
+}
+
+struct Validator<'a> {
+    root: &'a RootSchema,
+
+    /// Whether objects without `additionalProperties` are closed to unlisted fields
+    strict: bool,
+
+    errors: Vec<ValidationError>,
+}
+
+fn type_name(instance: &InstanceType) -> &'static str {
+    match instance {
+        InstanceType::Null => "null",
+        InstanceType::Boolean => "a boolean",
+        InstanceType::Object => "an object",
+        InstanceType::Array => "an array",
+        InstanceType::Number => "a number",
+        InstanceType::String => "a string",
+        InstanceType::Integer => "an integer",
+    }
+}
+
+fn has_type(value: &Value, instance: &InstanceType) -> bool {
+    match instance {
+        InstanceType::Null => value.is_null(),
+        InstanceType::Boolean => value.is_boolean(),
+        InstanceType::Object => value.is_object(),
+        InstanceType::Array => value.is_array(),
+        InstanceType::Number => value.is_number(),
+        InstanceType::String => value.is_string(),
+        InstanceType::Integer => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|v| v.fract() == 0.)
+        }
+    }
+}
+
+/// Appends a reference token to a JSON pointer
+fn push_token(pointer: &str, token: &str) -> String {
+    format!("{pointer}/{}", token.replace('~', "~0").replace('/', "~1"))
+}
+
+/// Schema behind a reference
+fn resolve<'s>(root: &'s RootSchema, schema: &'s SchemaObject) -> Option<&'s SchemaObject> {
+    match &schema.reference {
+        None => Some(schema),
+        Some(reference) => {
+            let name = reference.strip_prefix("#/definitions/")?;
+            match root.definitions.get(name)? {
+                Schema::Object(object) => resolve(root, object),
+                Schema::Bool(_) => None,
+            }
+        }
+    }
+}
+
+impl Validator<'_> {
+    /// Reports a field missing from the schema, suggesting a known field with a similar name
+    fn unknown_field(&mut self, pointer: &str, key: &str, object: &ObjectValidation) {
+        let closest = object
+            .properties
+            .keys()
+            .map(|name| (edit_distance(key, name), name))
+            .filter(|(distance, name)| {
+                *distance <= name.chars().count().min(key.chars().count()) / 2
+            })
+            .min();
+        let message = match closest {
+            Some((_, name)) => format!("is not a known field, did you mean `{name}`?"),
+            None => "is not a known field".into(),
+        };
+        self.error(pointer, message);
+    }
+
+    fn error(&mut self, pointer: &str, message: String) {
+        self.errors.push(ValidationError {
+            pointer: pointer.into(),
+            message,
+        });
+    }
+
+    fn schema(&mut self, schema: &Schema, value: &Value, pointer: &mut String) {
+        match schema {
+            Schema::Bool(true) => (),
+            Schema::Bool(false) => self.error(pointer, "is not allowed".into()),
+            Schema::Object(object) => self.object(object, value, pointer),
+        }
+    }
+
+    /// Errors of the value against a schema, without recording them
+    fn errors_of(&self, schema: &Schema, value: &Value) -> Vec<ValidationError> {
+        let mut validator = Validator {
+            root: self.root,
+            strict: self.strict,
+            errors: Vec::new(),
+        };
+        validator.schema(schema, value, &mut String::new());
+        validator.errors
+    }
+
+    /// Whether a variant is meant for the value even if it doesn't validate: its type matches
+    /// and, for objects, all required fields are present
+    fn intended(&self, schema: &Schema, value: &Value) -> bool {
+        let Schema::Object(object) = schema else {
+            return false;
+        };
+        let Some(object) = resolve(self.root, object) else {
+            return false;
+        };
+        let typed = match &object.instance_type {
+            Some(SingleOrVec::Single(instance)) => has_type(value, instance),
+            Some(SingleOrVec::Vec(instances)) => instances.iter().any(|i| has_type(value, i)),
+            None => object.enum_values.is_none() && object.const_value.is_none(),
+        };
+        let required = match (&object.object, value) {
+            (Some(validation), Value::Object(map)) => {
+                validation.required.iter().all(|key| map.contains_key(key))
+            }
+            _ => true,
+        };
+        typed && required
+    }
+
+    /// Variants of `oneOf`/`anyOf`: valid if any matches, otherwise the errors of the variant
+    /// the value was meant for
+    fn variants(&mut self, variants: &[Schema], value: &Value, pointer: &mut String) {
+        let results: Vec<Vec<ValidationError>> =
+            variants.iter().map(|v| self.errors_of(v, value)).collect();
+        if results.iter().any(|errors| errors.is_empty()) {
+            return;
+        }
+        match variants.iter().position(|v| self.intended(v, value)) {
+            Some(index) => {
+                for error in &results[index] {
+                    let pointer = format!("{pointer}{}", error.pointer);
+                    self.error(&pointer, error.message.clone());
+                }
+            }
+            None => {
+                let mut names: Vec<String> = Vec::new();
+                for variant in variants {
+                    if let Schema::Object(object) = variant {
+                        if let Some(object) = resolve(self.root, object) {
+                            names.extend(variant_names(object));
+                        }
+                    }
+                }
+                let message = match names.is_empty() {
+                    true => "doesn't match any of the allowed variants".into(),
+                    false => format!("must be one of {}", names.join(", ")),
+                };
+                self.error(pointer, message);
+            }
+        }
+    }
+
+    fn object(&mut self, schema: &SchemaObject, value: &Value, pointer: &mut String) {
+        let Some(schema) = resolve(self.root, schema) else {
+            self.error(pointer, "refers to an unknown definition".into());
+            return;
+        };
+
+        if let Some(types) = &schema.instance_type {
+            let types: &[InstanceType] = match types {
+                SingleOrVec::Single(instance) => std::slice::from_ref(instance),
+                SingleOrVec::Vec(instances) => instances,
+            };
+            if !types.iter().any(|t| has_type(value, t)) {
+                let names: Vec<&str> = types.iter().map(type_name).collect();
+                self.error(pointer, format!("must be {}", names.join(" or ")));
+                return;
+            }
+        }
+        if let Some(values) = &schema.enum_values {
+            if !values.contains(value) {
+                let names: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                self.error(pointer, format!("must be one of {}", names.join(", ")));
+            }
+        }
+        if let Some(constant) = &schema.const_value {
+            if constant != value {
+                self.error(pointer, format!("must be {constant}"));
+            }
+        }
+
+        if let Some(subschemas) = &schema.subschemas {
+            for schema in subschemas.all_of.iter().flatten() {
+                self.schema(schema, value, pointer);
+            }
+            for variants in [&subschemas.any_of, &subschemas.one_of]
+                .into_iter()
+                .flatten()
+            {
+                self.variants(variants, value, pointer);
+            }
+        }
+
+        if let (Some(number), Some(v)) = (&schema.number, value.as_f64()) {
+            let bounds = [
+                (number.minimum, ">="),
+                (number.exclusive_minimum, ">"),
+                (number.maximum, "<="),
+                (number.exclusive_maximum, "<"),
+            ];
+            for (bound, relation) in bounds {
+                let Some(bound) = bound else {
+                    continue;
+                };
+                let satisfied = match relation {
+                    ">=" => v >= bound,
+                    ">" => v > bound,
+                    "<=" => v <= bound,
+                    _ => v < bound,
+                };
+                if !satisfied {
+                    self.error(pointer, format!("must be {relation} {bound}"));
+                }
+            }
+        }
+
+        if let (Some(string), Some(s)) = (&schema.string, value.as_str()) {
+            let length = s.chars().count() as u32;
+            if string.min_length.is_some_and(|min| length < min) {
+                self.error(
+                    pointer,
+                    format!(
+                        "must have at least {} characters",
+                        string.min_length.unwrap()
+                    ),
+                );
+            }
+            if string.max_length.is_some_and(|max| length > max) {
+                self.error(
+                    pointer,
+                    format!(
+                        "must have at most {} characters",
+                        string.max_length.unwrap()
+                    ),
+                );
+            }
+        }
+
+        if let (Some(array), Some(items)) = (&schema.array, value.as_array()) {
+            let length = items.len() as u32;
+            if array.min_items.is_some_and(|min| length < min) {
+                self.error(
+                    pointer,
+                    format!("must have at least {} items", array.min_items.unwrap()),
+                );
+            }
+            if array.max_items.is_some_and(|max| length > max) {
+                self.error(
+                    pointer,
+                    format!("must have at most {} items", array.max_items.unwrap()),
+                );
+            }
+            for (i, item) in items.iter().enumerate() {
+                let item_schema = match &array.items {
+                    Some(SingleOrVec::Single(schema)) => Some(&**schema),
+                    Some(SingleOrVec::Vec(schemas)) => {
+                        schemas.get(i).or(array.additional_items.as_deref())
+                    }
+                    None => None,
+                };
+                if let Some(item_schema) = item_schema {
+                    let mut pointer = push_token(pointer, &i.to_string());
+                    self.schema(item_schema, item, &mut pointer);
+                }
+            }
+        }
+
+        if let (Some(object), Some(map)) = (&schema.object, value.as_object()) {
+            for key in &object.required {
+                if !map.contains_key(key) {
+                    self.error(&push_token(pointer, key), "is required".into());
+                }
+            }
+            for (key, item) in map {
+                let mut pointer = push_token(pointer, key);
+                match (object.properties.get(key), &object.additional_properties) {
+                    (Some(schema), _) => self.schema(schema, item, &mut pointer),
+                    (None, Some(schema)) => match &**schema {
+                        Schema::Bool(false) => self.unknown_field(&pointer, key, object),
+                        schema => self.schema(schema, item, &mut pointer),
+                    },
+                    (None, None) if self.strict => self.unknown_field(&pointer, key, object),
+                    (None, None) => (),
+                }
+            }
+        }
+    }
+}
+
+/// Number of single-character insertions, deletions, and substitutions turning one word into
+/// the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Names of an enum variant for messages: its values, or the tag of externally tagged variants
+fn variant_names(schema: &SchemaObject) -> Vec<String> {
+    if let Some(values) = &schema.enum_values {
+        return values.iter().map(|v| v.to_string()).collect();
+    }
+    match &schema.object {
+        Some(object) if object.required.len() == 1 => object
+            .required
+            .iter()
+            .map(|k| format!("{{\"{k}\": ...}}"))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::network::Network;
+    use serde_json::json;
+
+    #[test]
+    fn pointers() {
+        let network = json!({
+            "nodes": [{"id": 0}, {"id": 1}],
+            "channels": [{
+                "id": 0,
+                "node_a": 0,
+                "node_b": -1,
+                "shape": {"rectangular": {"width": 0.0, "height": 50.0}},
+            }, {
+                "id": 1,
+                "node_a": 0,
+                "node_b": 1,
+                "shape": {"hexagonal": {}},
+            }],
+        });
+        let errors = validate::<Network>(&network).unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "/modules is required",
+                "/channels/0/node_b must be >= 0",
+                "/channels/0/shape/rectangular/width must be > 0",
+                "/channels/1/shape must be one of {\"rectangular\": ...}, {\"cylindrical\": ...}",
+            ]
+        );
+
+        let valid = json!({"nodes": [], "channels": [], "modules": []});
+        assert!(from_value_validated::<Network>(valid).is_ok());
+        assert_eq!(
+            from_json_validated::<Network>("{").unwrap_err()[0].pointer,
+            ""
+        );
+    }
+
+    #[test]
+    fn strict() {
+        let network = json!({
+            "nodes": [{"id": 0}, {"id": 1, "colour": "red"}],
+            "channels": [{
+                "id": 0,
+                "node_a": 0,
+                "node_b": 1,
+                "shape": {"rectangular": {"widht": 100.0, "width": 100.0, "height": 50.0}},
+            }],
+            "modules": [],
+            "metadata": {"anything": 1},
+        });
+        assert!(validate::<Network>(&network).is_ok());
+        let errors = validate_strict::<Network>(&network).unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "/channels/0/shape/rectangular/widht is not a known field, did you mean `width`?",
+                "/nodes/1/colour is not a known field",
+            ]
+        );
+        assert_eq!(edit_distance("widht", "width"), 2);
+    }
+}
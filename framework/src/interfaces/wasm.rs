@@ -8,7 +8,7 @@
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// mmft_framework::wasm_interface_function!(
 ///     create_meander,
 ///     meander_designer::meander_designer::create_meander
@@ -26,3 +26,262 @@ macro_rules! wasm_interface_function {
         }
     };
 }
+
+#[macro_export]
+/// Generates a wasm binding like `wasm_interface_function!` that validates the input against the
+/// schema of its type first. Violations are thrown as a string listing every offending value by
+/// its JSON pointer.
+///
+/// # Arguments
+///
+/// * `function_name` - the call name of the function
+/// * `call_function` - the function to be bound
+/// * `input_type` - type of the input, must implement `JsonSchema`
+///
+/// # Examples
+///
+/// ```ignore
+/// mmft_framework::wasm_validated_function!(
+///     create_meander,
+///     meander_designer::meander_designer::create_meander,
+///     meander_designer::meander_designer::MeanderParameters
+/// );
+/// ```
+macro_rules! wasm_validated_function {
+    ($function_name: ident, $call_function: path, $input_type: ty) => {
+        paste::item! {
+            #[wasm_bindgen]
+            pub fn [<$function_name>](
+                input: wasm_bindgen::prelude::JsValue,
+            ) -> Result<JsValue, JsValue> {
+                std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+                let value: serde_json::Value = serde_wasm_bindgen::from_value(input)
+                    .map_err(|e| JsValue::from_str(&e.to_string()))?;
+                let parameters: $input_type =
+                    $crate::interfaces::validation::from_value_validated(value).map_err(|e| {
+                        JsValue::from_str(&$crate::interfaces::validation::describe(&e))
+                    })?;
+                let output = $call_function(parameters);
+                Ok(serde_wasm_bindgen::to_value(&output).unwrap())
+            }
+        }
+    };
+}
+
+#[macro_export]
+/// Generates a wasm binding that tunes the parameters of a figure of merit. The function takes
+/// an `OptimizationProblem` and returns the `OptimizationResult`, errors are thrown as strings.
+///
+/// # Arguments
+///
+/// * `function_name` - the call name of the function
+/// * `objective` - function from the parameters to the figure of merit to be minimized
+/// * `parameter_type` - serde compatible type of the parameters
+///
+/// # Examples
+///
+/// ```ignore
+/// mmft_framework::wasm_optimize_function!(
+///     optimize_split,
+///     splitter_designer::flow_split_error,
+///     splitter_designer::SplitterParameters
+/// );
+/// ```
+macro_rules! wasm_optimize_function {
+    ($function_name: ident, $objective: path, $parameter_type: ty) => {
+        paste::item! {
+            #[wasm_bindgen]
+            pub fn [<$function_name>](
+                input: wasm_bindgen::prelude::JsValue,
+            ) -> Result<JsValue, JsValue> {
+                std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+                let problem: $crate::optimize::OptimizationProblem<$parameter_type> =
+                    serde_wasm_bindgen::from_value(input).unwrap();
+                let result = $crate::optimize::optimize(&problem, $objective)
+                    .map_err(|e| JsValue::from_str(&e.to_string()))?;
+                Ok(serde_wasm_bindgen::to_value(&result).unwrap())
+            }
+        }
+    };
+}
+
+#[macro_export]
+/// Generates a wasm class wrapping an `EditSession`, so browser editors share the framework's
+/// undo/redo history and hit-testing. Commands and networks are passed as serde compatible
+/// JsValues.
+///
+/// # Arguments
+///
+/// * `class_name` - name of the generated class
+///
+/// # Examples
+///
+/// ```ignore
+/// mmft_framework::wasm_edit_session!(NetworkEditor);
+/// ```
+macro_rules! wasm_edit_session {
+    ($class_name: ident) => {
+        #[wasm_bindgen]
+        pub struct $class_name {
+            session: $crate::base::edit::EditSession,
+        }
+
+        #[wasm_bindgen]
+        impl $class_name {
+            #[wasm_bindgen(constructor)]
+            pub fn new(network: wasm_bindgen::prelude::JsValue) -> $class_name {
+                std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+                $class_name {
+                    session: $crate::base::edit::EditSession::new(
+                        serde_wasm_bindgen::from_value(network).unwrap(),
+                    ),
+                }
+            }
+
+            pub fn execute(&mut self, command: wasm_bindgen::prelude::JsValue) -> bool {
+                self.session
+                    .execute(&serde_wasm_bindgen::from_value(command).unwrap())
+            }
+
+            pub fn undo(&mut self) -> bool {
+                self.session.undo()
+            }
+
+            pub fn redo(&mut self) -> bool {
+                self.session.redo()
+            }
+
+            pub fn can_undo(&self) -> bool {
+                self.session.can_undo()
+            }
+
+            pub fn can_redo(&self) -> bool {
+                self.session.can_redo()
+            }
+
+            pub fn network(&self) -> wasm_bindgen::prelude::JsValue {
+                serde_wasm_bindgen::to_value(&self.session.network).unwrap()
+            }
+
+            /// Nearest entity within the tolerance of the position, undefined if there is none
+            pub fn pick(&self, x: f64, y: f64, tolerance: f64) -> wasm_bindgen::prelude::JsValue {
+                let point = $crate::base::primitives::Point([x, y]);
+                serde_wasm_bindgen::to_value(&self.session.network.pick(point, tolerance)).unwrap()
+            }
+        }
+    };
+}
+
+#[macro_export]
+/// Generates a wasm function forwarding the framework's spans and events up to a level, e.g.,
+/// "debug", to the browser console. Span durations are logged at the debug level. Requires the
+/// `tracing` feature and web-sys with the `console` feature in the binding crate.
+///
+/// # Arguments
+///
+/// * `function_name` - the call name of the function
+///
+/// # Examples
+///
+/// ```ignore
+/// mmft_framework::wasm_console_logging!(enable_logging);
+/// ```
+macro_rules! wasm_console_logging {
+    ($function_name: ident) => {
+        #[wasm_bindgen]
+        pub fn $function_name(max_level: &str) -> Result<(), JsValue> {
+            use $crate::trace::Level;
+
+            struct Console(Level);
+
+            impl $crate::trace::Subscriber for Console {
+                fn event(&self, level: Level, target: &str, message: &str) {
+                    if level > self.0 {
+                        return;
+                    }
+                    let text = JsValue::from_str(&format!("{target}: {message}"));
+                    match level {
+                        Level::Error => web_sys::console::error_1(&text),
+                        Level::Warn => web_sys::console::warn_1(&text),
+                        Level::Info => web_sys::console::info_1(&text),
+                        Level::Debug | Level::Trace => web_sys::console::debug_1(&text),
+                    }
+                }
+
+                fn exit(&self, target: &str, name: &str, elapsed: std::time::Duration) {
+                    if Level::Debug <= self.0 {
+                        let text = format!("{target}: {name} took {elapsed:?}");
+                        web_sys::console::debug_1(&JsValue::from_str(&text));
+                    }
+                }
+            }
+
+            let level = max_level
+                .parse()
+                .map_err(|e: String| JsValue::from_str(&e))?;
+            $crate::trace::set_subscriber(Console(level));
+            Ok(())
+        }
+    };
+}
+
+#[macro_export]
+/// Generates a wasm binding like `wasm_interface_function!` for a function taking a
+/// `ProgressHandle` after its input. The binding accepts an optional callback called with the
+/// progress object and an optional `AbortSignal`-like object whose `aborted` property cancels
+/// the run when set; cancellations are thrown as "cancelled". The function returns a `Result`
+/// whose errors are thrown as strings. Requires js-sys in the binding crate.
+///
+/// The run is synchronous and blocks the JavaScript event loop until it returns, so no timer,
+/// event handler, or promise can abort the signal in the meantime. The signal is only read at
+/// the progress reports, and it only flips during the run if the progress callback aborts it
+/// itself, e.g., once a deadline has passed. A cancel button has to run the binding in a worker
+/// and terminate the worker instead.
+///
+/// # Arguments
+///
+/// * `function_name` - the call name of the function
+/// * `call_function` - the function to be bound
+///
+/// # Examples
+///
+/// ```ignore
+/// mmft_framework::wasm_progress_function!(route_droplets, dmf_designer::route_droplets);
+/// ```
+macro_rules! wasm_progress_function {
+    ($function_name: ident, $call_function: path) => {
+        #[wasm_bindgen]
+        pub fn $function_name(
+            input: wasm_bindgen::prelude::JsValue,
+            on_progress: Option<js_sys::Function>,
+            signal: Option<wasm_bindgen::prelude::JsValue>,
+        ) -> Result<JsValue, JsValue> {
+            std::panic::set_hook(Box::new(console_error_panic_hook::hook));
+            let parameters = serde_wasm_bindgen::from_value(input)
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+            let token = $crate::progress::CancellationToken::default();
+            let progress = $crate::progress::ProgressHandle::new({
+                let token = token.clone();
+                move |p: &$crate::progress::Progress| {
+                    if let Some(callback) = &on_progress {
+                        let _ = callback
+                            .call1(&JsValue::NULL, &serde_wasm_bindgen::to_value(p).unwrap());
+                    }
+                    let aborted = signal.as_ref().and_then(|signal| {
+                        js_sys::Reflect::get(signal, &JsValue::from_str("aborted")).ok()
+                    });
+                    if aborted.is_some_and(|a| a.is_truthy()) {
+                        token.cancel();
+                    }
+                }
+            })
+            .with_token(token.clone());
+            let result = $call_function(parameters, &progress);
+            if token.is_cancelled() {
+                return Err(JsValue::from_str("cancelled"));
+            }
+            let result = result.map_err(|e| JsValue::from_str(&e.to_string()))?;
+            Ok(serde_wasm_bindgen::to_value(&result).unwrap())
+        }
+    };
+}
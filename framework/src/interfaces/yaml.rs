@@ -0,0 +1,509 @@
+//! YAML for hand-written design inputs. Values are converted through `serde_json::Value`, so
+//! every type with a JSON representation can be read and written. The reader covers the block
+//! and flow styles commonly written by hand: mappings, sequences, plain and quoted scalars, and
+//! comments; anchors, tags, and multi-line scalars aren't supported.
+
+use super::TextFormatError;
+use crate::base::network::Network;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Number, Value};
+
+/// YAML document of the value
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, TextFormatError> {
+    let value = serde_json::to_value(value).map_err(|e| TextFormatError::new(0, e))?;
+    let mut out = String::new();
+    match &value {
+        Value::Object(map) if !map.is_empty() => write_mapping(&mut out, map, 0),
+        Value::Array(items) if !items.is_empty() && !is_flat(items) => {
+            write_sequence(&mut out, items, 0)
+        }
+        value => {
+            out.push_str(&scalar(value));
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+/// Value read from a YAML document
+pub fn from_str<T: DeserializeOwned>(text: &str) -> Result<T, TextFormatError> {
+    let lines: Vec<Line> = text
+        .lines()
+        .enumerate()
+        .filter_map(|(i, content)| {
+            let content = strip_comment(content);
+            let trimmed = content.trim_start();
+            if trimmed.is_empty() || trimmed == "---" {
+                return None;
+            }
+            Some(Line {
+                number: i + 1,
+                indent: content.len() - trimmed.len(),
+                content: trimmed.trim_end().to_string(),
+            })
+        })
+        .collect();
+    let mut parser = Parser { lines, position: 0 };
+    let value = match parser.lines.first() {
+        None => Value::Null,
+        Some(line) => {
+            let indent = line.indent;
+            parser.block(indent)?
+        }
+    };
+    if let Some(line) = parser.lines.get(parser.position) {
+        return Err(TextFormatError::new(line.number, "unexpected indentation"));
+    }
+    serde_json::from_value(value).map_err(|e| TextFormatError::new(0, e))
+}
+
+impl Network {
+    /// Network from a YAML document
+    pub fn from_yaml(text: &str) -> Result<Network, TextFormatError> {
+        from_str(text)
+    }
+
+    /// YAML document of the network
+    pub fn to_yaml(&self) -> String {
+        to_string(self).unwrap()
+    }
+}
+
+/// Sequences of scalars, e.g., points, are written in flow style
+fn is_flat(items: &[Value]) -> bool {
+    items
+        .iter()
+        .all(|v| !matches!(v, Value::Object(_) | Value::Array(_)))
+}
+
+fn write_mapping(out: &mut String, map: &Map<String, Value>, indent: usize) {
+    for (key, value) in map {
+        out.push_str(&" ".repeat(indent));
+        out.push_str(&string(key));
+        out.push(':');
+        write_nested(out, value, indent);
+    }
+}
+
+fn write_sequence(out: &mut String, items: &[Value], indent: usize) {
+    for item in items {
+        out.push_str(&" ".repeat(indent));
+        out.push('-');
+        write_nested(out, item, indent);
+    }
+}
+
+/// Remainder of a line after `key:` or `-`, with nested collections on the following lines
+fn write_nested(out: &mut String, value: &Value, indent: usize) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            out.push('\n');
+            write_mapping(out, map, indent + 2);
+        }
+        Value::Array(items) if !items.is_empty() && !is_flat(items) => {
+            out.push('\n');
+            write_sequence(out, items, indent + 2);
+        }
+        value => {
+            out.push(' ');
+            out.push_str(&scalar(value));
+            out.push('\n');
+        }
+    }
+}
+
+/// Scalars and flow collections on a single line
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::Null => "null".into(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => string(s),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(scalar).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(map) => {
+            let entries: Vec<String> = map
+                .iter()
+                .map(|(k, v)| format!("{}: {}", string(k), scalar(v)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+    }
+}
+
+/// Plain string if it reads back as the same string, double-quoted otherwise
+fn string(s: &str) -> String {
+    let plain = !s.is_empty()
+        && !s.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@` ".contains(c))
+        && !s.ends_with(' ')
+        && !s.contains(": ")
+        && !s.contains(" #")
+        && !s.ends_with(':')
+        && !s.contains(|c: char| c.is_control() || ",[]{}".contains(c))
+        && plain_scalar(s) == Value::String(s.into());
+    if plain {
+        s.into()
+    } else {
+        // JSON strings are valid double-quoted YAML scalars
+        Value::String(s.into()).to_string()
+    }
+}
+
+/// Removes a comment, i.e., `#` at the start or after whitespace outside of quotes
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some('"') if c == '\\' && previous == '\\' => {
+                previous = ' ';
+                continue;
+            }
+            Some(q) if c == q && !(q == '"' && previous == '\\') => quote = None,
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => return &line[..i],
+            _ => (),
+        }
+        previous = c;
+    }
+    line
+}
+
+struct Line {
+    number: usize,
+    indent: usize,
+    content: String,
+}
+
+struct Parser {
+    lines: Vec<Line>,
+    position: usize,
+}
+
+/// Position of the `:` separating a mapping key, if the text is a mapping entry
+fn key_separator(text: &str) -> Option<usize> {
+    if text.starts_with(['[', '{']) {
+        return None;
+    }
+    let mut quote = None;
+    let bytes = text.as_bytes();
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => (),
+            None if (c == '"' || c == '\'') && i == 0 => quote = Some(c),
+            None if c == ':' && (i + 1 == bytes.len() || bytes[i + 1] == b' ') => return Some(i),
+            None => (),
+        }
+    }
+    None
+}
+
+fn is_item(content: &str) -> bool {
+    content == "-" || content.starts_with("- ")
+}
+
+impl Parser {
+    fn error(&self, message: &str) -> TextFormatError {
+        let line = self
+            .lines
+            .get(self.position)
+            .or(self.lines.last())
+            .map_or(0, |l| l.number);
+        TextFormatError::new(line, message)
+    }
+
+    /// Collection or scalar starting at the current line with the given indentation
+    fn block(&mut self, indent: usize) -> Result<Value, TextFormatError> {
+        let line = &self.lines[self.position];
+        if is_item(&line.content) {
+            self.sequence(indent)
+        } else if key_separator(&line.content).is_some() {
+            self.mapping(indent)
+        } else {
+            let number = line.number;
+            let content = line.content.clone();
+            self.position += 1;
+            flow(&content).map_err(|m| TextFormatError::new(number, m))
+        }
+    }
+
+    fn current(&self, indent: usize) -> Option<&Line> {
+        self.lines
+            .get(self.position)
+            .filter(|line| line.indent == indent)
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value, TextFormatError> {
+        let mut items = Vec::new();
+        while let Some(line) = self.current(indent) {
+            if !is_item(&line.content) {
+                break;
+            }
+            let rest = line.content[1..].trim_start().to_string();
+            if rest.is_empty() {
+                self.position += 1;
+                items.push(self.nested(indent, false)?);
+            } else {
+                // The item's content continues as a block indented past the dash
+                let line = &mut self.lines[self.position];
+                line.indent += line.content.len() - rest.len();
+                line.content = rest;
+                let indent = line.indent;
+                items.push(self.block(indent)?);
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value, TextFormatError> {
+        let mut map = Map::new();
+        while let Some(line) = self.current(indent) {
+            let Some(separator) = key_separator(&line.content) else {
+                return Err(self.error("expected a mapping entry"));
+            };
+            let number = line.number;
+            let key = match flow(&line.content[..separator]) {
+                Ok(Value::String(key)) => key,
+                Ok(value) => value.to_string(),
+                Err(message) => return Err(TextFormatError::new(number, message)),
+            };
+            let rest = line.content[separator + 1..].trim().to_string();
+            self.position += 1;
+            let value = if rest.is_empty() {
+                self.nested(indent, true)?
+            } else {
+                flow(&rest).map_err(|m| TextFormatError::new(number, m))?
+            };
+            if map.insert(key, value).is_some() {
+                return Err(TextFormatError::new(number, "duplicate key"));
+            }
+        }
+        Ok(Value::Object(map))
+    }
+
+    /// Block below a `key:` or `-` line; mapping values may be sequences at the same indentation
+    fn nested(&mut self, indent: usize, in_mapping: bool) -> Result<Value, TextFormatError> {
+        match self.lines.get(self.position) {
+            Some(line) if line.indent > indent => {
+                let indent = line.indent;
+                self.block(indent)
+            }
+            Some(line) if in_mapping && line.indent == indent && is_item(&line.content) => {
+                self.sequence(indent)
+            }
+            _ => Ok(Value::Null),
+        }
+    }
+}
+
+/// Scalar or flow collection spanning the whole text
+fn flow(text: &str) -> Result<Value, String> {
+    let mut cursor = Cursor { text, position: 0 };
+    let value = cursor.value(false)?;
+    cursor.skip_spaces();
+    if cursor.position != text.len() {
+        return Err(format!("unexpected `{}`", &text[cursor.position..]));
+    }
+    Ok(value)
+}
+
+struct Cursor<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn skip_spaces(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_spaces();
+        if self.rest().starts_with(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Value at the cursor; inside flow collections, plain scalars end at `,` `]` `}` and `:`
+    fn value(&mut self, in_flow: bool) -> Result<Value, String> {
+        self.skip_spaces();
+        let rest = self.rest();
+        if rest.starts_with('[') {
+            self.position += 1;
+            let mut items = Vec::new();
+            if !self.eat(']') {
+                loop {
+                    items.push(self.value(true)?);
+                    if self.eat(']') {
+                        break;
+                    }
+                    if !self.eat(',') {
+                        return Err("expected `,` or `]`".into());
+                    }
+                }
+            }
+            Ok(Value::Array(items))
+        } else if rest.starts_with('{') {
+            self.position += 1;
+            let mut map = Map::new();
+            if !self.eat('}') {
+                loop {
+                    let key = match self.value(true)? {
+                        Value::String(key) => key,
+                        value => value.to_string(),
+                    };
+                    if !self.eat(':') {
+                        return Err("expected `:`".into());
+                    }
+                    map.insert(key, self.value(true)?);
+                    if self.eat('}') {
+                        break;
+                    }
+                    if !self.eat(',') {
+                        return Err("expected `,` or `}`".into());
+                    }
+                }
+            }
+            Ok(Value::Object(map))
+        } else if let Some(quoted) = rest.strip_prefix('"') {
+            let mut escaped = false;
+            let end = quoted
+                .char_indices()
+                .find(|(_, c)| {
+                    let end = *c == '"' && !escaped;
+                    escaped = *c == '\\' && !escaped;
+                    end
+                })
+                .map(|(i, _)| i + 2)
+                .ok_or("unterminated string")?;
+            self.position += end;
+            serde_json::from_str(&rest[..end]).map_err(|e| e.to_string())
+        } else if let Some(quoted) = rest.strip_prefix('\'') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices().peekable();
+            while let Some((i, c)) = chars.next() {
+                if c == '\'' {
+                    if chars.peek().is_some_and(|(_, c)| *c == '\'') {
+                        chars.next();
+                    } else {
+                        self.position += i + 2;
+                        return Ok(Value::String(value));
+                    }
+                }
+                value.push(c);
+            }
+            Err("unterminated string".into())
+        } else {
+            let end = if in_flow {
+                rest.find([',', ']', '}', ':']).unwrap_or(rest.len())
+            } else {
+                rest.len()
+            };
+            self.position += end;
+            if rest.starts_with(['|', '>', '&', '*', '!']) {
+                return Err("block scalars, anchors, and tags aren't supported".into());
+            }
+            Ok(plain_scalar(rest[..end].trim()))
+        }
+    }
+}
+
+/// Value of an unquoted scalar
+fn plain_scalar(text: &str) -> Value {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => (),
+    }
+    let number = text.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c));
+    if number {
+        if let Ok(i) = text.parse::<i64>() {
+            return Value::from(i);
+        }
+        if let Ok(u) = text.parse::<u64>() {
+            return Value::from(u);
+        }
+        let finite = !text.contains(|c: char| c.is_ascii_alphabetic() && c != 'e' && c != 'E');
+        if let Some(n) = text
+            .parse::<f64>()
+            .ok()
+            .filter(|_| finite)
+            .and_then(Number::from_f64)
+        {
+            return Value::Number(n);
+        }
+    }
+    Value::String(text.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::generator::{RandomNetworkSpec, Topology};
+
+    #[test]
+    fn round_trip() {
+        let network = Network::random(&RandomNetworkSpec {
+            topology: Topology::Tree {
+                depth: 2,
+                branching: 2,
+            },
+            rng: crate::random::Rng::new(3),
+            ..Default::default()
+        });
+        let yaml = network.to_yaml();
+        assert_eq!(Network::from_yaml(&yaml).unwrap(), network);
+    }
+
+    #[test]
+    fn hand_written() {
+        let value: Value = from_str(
+            "# comment\n\
+             name: 'it''s'\n\
+             list:\n\
+             - 1\n\
+             - -2.5e-3\n\
+             - text # trailing\n\
+             nested:\n  \
+               - a: [0, 1]\n    \
+                 b: {c: \"x: y\", d: ~}\n  \
+               -\n    \
+                 - true\n\
+             empty: []\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "name": "it's",
+                "list": [1, -2.5e-3, "text"],
+                "nested": [{"a": [0, 1], "b": {"c": "x: y", "d": null}}, [true]],
+                "empty": []
+            })
+        );
+        assert_eq!(
+            from_str::<Value>(&to_string(&value).unwrap()).unwrap(),
+            value
+        );
+
+        let strings = serde_json::json!(["", "1", "true", "- a", "a: b", "#", "x\ny", "ok"]);
+        assert_eq!(
+            from_str::<Value>(&to_string(&strings).unwrap()).unwrap(),
+            strings
+        );
+
+        assert_eq!(from_str::<Value>("a: 1\n b: 2").unwrap_err().line, 2);
+    }
+}
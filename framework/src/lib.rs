@@ -1,2 +1,15 @@
+pub mod analysis;
 pub mod base;
+pub mod designer;
+pub mod diagnostic;
+pub mod dmf;
+pub mod export;
+pub mod fixtures;
 pub mod interfaces;
+pub mod optimize;
+pub mod parallel;
+pub mod progress;
+pub mod project;
+pub mod random;
+pub mod simulation;
+pub mod trace;
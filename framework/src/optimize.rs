@@ -0,0 +1,395 @@
+//! Parameter search around designer functions. Any serde parameter struct can be tuned: the
+//! variables address numeric fields by JSON pointer, every candidate is deserialized back into
+//! the struct and scored by a figure of merit, which is minimized.
+
+use crate::{parallel, random::Rng};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Numeric parameter to vary
+pub struct Variable {
+    /// JSON pointer to the field, e.g., "/channels/0/width"
+    pub pointer: String,
+
+    /// Lower bound
+    pub min: f64,
+
+    /// Upper bound
+    pub max: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Search algorithm
+pub enum Strategy {
+    /// Full factorial sweep with the given number of equidistant values per variable
+    Grid { steps: usize },
+
+    /// Uniformly distributed samples within the bounds
+    Random {
+        samples: usize,
+        #[serde(default)]
+        rng: Rng,
+    },
+
+    /// Nelder–Mead simplex search starting from the given parameters, restricted to the bounds
+    NelderMead {
+        max_iterations: usize,
+        tolerance: f64,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Parameters to start from, what to vary, and how to search
+pub struct OptimizationProblem<P> {
+    pub parameters: P,
+    pub variables: Vec<Variable>,
+    pub strategy: Strategy,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Figure of merit of one candidate
+pub struct Evaluation {
+    /// Variable values in order of the variables
+    pub values: Vec<f64>,
+    pub objective: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Best candidate and the history of all evaluations
+pub struct OptimizationResult<P> {
+    pub best: P,
+    pub best_values: Vec<f64>,
+    pub objective: f64,
+    pub evaluations: Vec<Evaluation>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Reasons an optimization can't run
+pub enum OptimizationError {
+    /// The pointer doesn't address a number in the parameters
+    InvalidPointer(String),
+
+    /// Parameters with substituted values don't deserialize, holds the serde message
+    InvalidParameters(String),
+}
+
+impl std::fmt::Display for OptimizationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptimizationError::InvalidPointer(pointer) => {
+                write!(f, "{pointer} does not point to a number")
+            }
+            OptimizationError::InvalidParameters(message) => {
+                write!(f, "invalid parameters: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OptimizationError {}
+
+struct Evaluator<'a, P, F> {
+    base: Value,
+    variables: &'a [Variable],
+    objective: F,
+    evaluations: Vec<Evaluation>,
+    marker: std::marker::PhantomData<fn() -> P>,
+}
+
+impl<P, F> Evaluator<'_, P, F>
+where
+    P: DeserializeOwned,
+    F: Fn(&P) -> f64 + Sync,
+{
+    fn instantiate(&self, values: &[f64]) -> Result<P, OptimizationError> {
+        let mut parameters = self.base.clone();
+        for (variable, value) in self.variables.iter().zip(values) {
+            *parameters.pointer_mut(&variable.pointer).unwrap() = (*value).into();
+        }
+        serde_json::from_value(parameters)
+            .map_err(|e| OptimizationError::InvalidParameters(e.to_string()))
+    }
+
+    /// Scores the candidates in parallel; failing figures of merit (NaN) rank last
+    fn evaluate(&mut self, candidates: Vec<Vec<f64>>) -> Result<Vec<f64>, OptimizationError> {
+        let objectives = parallel::map(&candidates, |values| {
+            self.instantiate(values).map(|parameters| {
+                let objective = (self.objective)(&parameters);
+                if objective.is_nan() {
+                    f64::INFINITY
+                } else {
+                    objective
+                }
+            })
+        })
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+        self.evaluations
+            .extend(
+                candidates
+                    .into_iter()
+                    .zip(&objectives)
+                    .map(|(values, objective)| Evaluation {
+                        values,
+                        objective: *objective,
+                    }),
+            );
+        Ok(objectives)
+    }
+}
+
+fn nelder_mead<P, F>(
+    evaluator: &mut Evaluator<P, F>,
+    start: Vec<f64>,
+    max_iterations: usize,
+    tolerance: f64,
+) -> Result<(), OptimizationError>
+where
+    P: DeserializeOwned,
+    F: Fn(&P) -> f64 + Sync,
+{
+    // The search runs on coordinates normalized to the unit cube
+    let variables = evaluator.variables;
+    let to_values = |x: &[f64]| -> Vec<f64> {
+        variables
+            .iter()
+            .zip(x)
+            .map(|(v, x)| v.min + x.clamp(0., 1.) * (v.max - v.min))
+            .collect()
+    };
+    let n = start.len();
+    let origin: Vec<f64> = variables
+        .iter()
+        .zip(&start)
+        .map(|(v, value)| ((value - v.min) / (v.max - v.min)).clamp(0., 1.))
+        .collect();
+    let mut simplex = vec![origin.clone()];
+    for i in 0..n {
+        let mut vertex = origin.clone();
+        vertex[i] += if vertex[i] > 0.5 { -0.1 } else { 0.1 };
+        simplex.push(vertex);
+    }
+    let values = evaluator.evaluate(simplex.iter().map(|x| to_values(x)).collect())?;
+    let mut simplex: Vec<(Vec<f64>, f64)> = simplex.into_iter().zip(values).collect();
+
+    let score = |evaluator: &mut Evaluator<P, F>, x: Vec<f64>| {
+        let x: Vec<f64> = x.into_iter().map(|x| x.clamp(0., 1.)).collect();
+        evaluator
+            .evaluate(vec![to_values(&x)])
+            .map(|values| (x, values[0]))
+    };
+    for _ in 0..max_iterations {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        if simplex[n].1 - simplex[0].1 <= tolerance {
+            break;
+        }
+        let centroid: Vec<f64> = (0..n)
+            .map(|i| simplex[..n].iter().map(|(x, _)| x[i]).sum::<f64>() / n as f64)
+            .collect();
+        let along = |t: f64| -> Vec<f64> {
+            centroid
+                .iter()
+                .zip(&simplex[n].0)
+                .map(|(c, w)| c + t * (w - c))
+                .collect()
+        };
+        let reflected = score(evaluator, along(-1.))?;
+        if reflected.1 < simplex[0].1 {
+            let expanded = score(evaluator, along(-2.))?;
+            simplex[n] = if expanded.1 < reflected.1 {
+                expanded
+            } else {
+                reflected
+            };
+        } else if reflected.1 < simplex[n - 1].1 {
+            simplex[n] = reflected;
+        } else {
+            let contracted = if reflected.1 < simplex[n].1 {
+                score(evaluator, along(-0.5))?
+            } else {
+                score(evaluator, along(0.5))?
+            };
+            if contracted.1 < simplex[n].1.min(reflected.1) {
+                simplex[n] = contracted;
+            } else {
+                let best = simplex[0].0.clone();
+                let shrunk: Vec<Vec<f64>> = simplex[1..]
+                    .iter()
+                    .map(|(x, _)| best.iter().zip(x).map(|(b, x)| b + 0.5 * (x - b)).collect())
+                    .collect();
+                let values = evaluator.evaluate(shrunk.iter().map(|x| to_values(x)).collect())?;
+                for (vertex, entry) in simplex[1..].iter_mut().zip(shrunk.into_iter().zip(values)) {
+                    *vertex = entry;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Searches the variables for the smallest figure of merit; negate it to maximize instead
+pub fn optimize<P, F>(
+    problem: &OptimizationProblem<P>,
+    objective: F,
+) -> Result<OptimizationResult<P>, OptimizationError>
+where
+    P: Serialize + DeserializeOwned,
+    F: Fn(&P) -> f64 + Sync,
+{
+    let base = serde_json::to_value(&problem.parameters)
+        .map_err(|e| OptimizationError::InvalidParameters(e.to_string()))?;
+    let start = problem
+        .variables
+        .iter()
+        .map(|v| {
+            base.pointer(&v.pointer)
+                .and_then(Value::as_f64)
+                .ok_or_else(|| OptimizationError::InvalidPointer(v.pointer.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut evaluator = Evaluator {
+        base,
+        variables: &problem.variables,
+        objective,
+        evaluations: Vec::new(),
+        marker: std::marker::PhantomData,
+    };
+
+    let variables = &problem.variables;
+    match &problem.strategy {
+        Strategy::Grid { steps } => {
+            let ticks: Vec<Vec<f64>> = variables
+                .iter()
+                .map(|v| match steps {
+                    0 | 1 => vec![0.5 * (v.min + v.max)],
+                    _ => (0..*steps)
+                        .map(|i| v.min + (v.max - v.min) * i as f64 / (steps - 1) as f64)
+                        .collect(),
+                })
+                .collect();
+            let candidates = ticks.iter().fold(vec![Vec::new()], |candidates, ticks| {
+                candidates
+                    .iter()
+                    .flat_map(|c| {
+                        ticks.iter().map(move |t| {
+                            let mut c = c.clone();
+                            c.push(*t);
+                            c
+                        })
+                    })
+                    .collect()
+            });
+            evaluator.evaluate(candidates)?;
+        }
+        Strategy::Random { samples, rng } => {
+            let mut rng = rng.generator();
+            let candidates = (0..*samples)
+                .map(|_| {
+                    variables
+                        .iter()
+                        .map(|v| v.min + rng.next_f64() * (v.max - v.min))
+                        .collect()
+                })
+                .collect();
+            evaluator.evaluate(candidates)?;
+        }
+        Strategy::NelderMead {
+            max_iterations,
+            tolerance,
+        } => nelder_mead(&mut evaluator, start.clone(), *max_iterations, *tolerance)?,
+    }
+
+    let (best_values, objective) = evaluator
+        .evaluations
+        .iter()
+        .min_by(|a, b| a.objective.total_cmp(&b.objective))
+        .map_or((start, f64::INFINITY), |e| (e.values.clone(), e.objective));
+    Ok(OptimizationResult {
+        best: evaluator.instantiate(&best_values)?,
+        best_values,
+        objective,
+        evaluations: evaluator.evaluations,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    struct Widths {
+        name: String,
+        widths: Vec<f64>,
+    }
+
+    fn problem(strategy: Strategy) -> OptimizationProblem<Widths> {
+        OptimizationProblem {
+            parameters: Widths {
+                name: "split".into(),
+                widths: vec![50., 50.],
+            },
+            variables: vec![
+                Variable {
+                    pointer: "/widths/0".into(),
+                    min: 0.,
+                    max: 100.,
+                },
+                Variable {
+                    pointer: "/widths/1".into(),
+                    min: 0.,
+                    max: 100.,
+                },
+            ],
+            strategy,
+        }
+    }
+
+    fn objective(p: &Widths) -> f64 {
+        (p.widths[0] - 30.).powi(2) + (p.widths[1] - 70.).powi(2)
+    }
+
+    #[test]
+    fn strategies() {
+        let grid = optimize(&problem(Strategy::Grid { steps: 11 }), objective).unwrap();
+        assert_eq!(grid.evaluations.len(), 121);
+        assert_eq!(grid.best.widths, [30., 70.]);
+        assert_eq!(grid.best.name, "split");
+
+        let random = optimize(
+            &problem(Strategy::Random {
+                samples: 200,
+                rng: Rng::new(7),
+            }),
+            objective,
+        )
+        .unwrap();
+        assert_eq!(random.evaluations.len(), 200);
+        assert!(random.objective < 100.);
+
+        let simplex = optimize(
+            &problem(Strategy::NelderMead {
+                max_iterations: 200,
+                tolerance: 1e-10,
+            }),
+            objective,
+        )
+        .unwrap();
+        assert!((simplex.best.widths[0] - 30.).abs() < 1e-2);
+        assert!((simplex.best.widths[1] - 70.).abs() < 1e-2);
+
+        let mut invalid = problem(Strategy::Grid { steps: 2 });
+        invalid.variables[0].pointer = "/name".into();
+        assert_eq!(
+            optimize(&invalid, objective).err(),
+            Some(OptimizationError::InvalidPointer("/name".into()))
+        );
+    }
+}
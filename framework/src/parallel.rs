@@ -0,0 +1,118 @@
+//! Order-preserving data parallelism for embarrassingly parallel geometry work. Without the
+//! `parallel` feature everything runs sequentially, e.g., for single-threaded WASM targets.
+
+/// Fewest items per thread, smaller inputs are mapped sequentially since spawning a thread
+/// costs more than the geometry work on a handful of items
+#[cfg(feature = "parallel")]
+const MIN_CHUNK_SIZE: usize = 16;
+
+#[cfg(test)]
+thread_local! {
+    /// Thread count replacing the available parallelism in tests, 1 forces the sequential path
+    static THREADS: std::cell::Cell<Option<usize>> = const { std::cell::Cell::new(None) };
+}
+
+/// Runs `f` with the maps it calls on this thread using `threads` threads, whatever the machine
+#[cfg(test)]
+pub(crate) fn with_threads<R>(threads: usize, f: impl FnOnce() -> R) -> R {
+    let previous = THREADS.replace(Some(threads));
+    let result = f();
+    THREADS.set(previous);
+    result
+}
+
+/// Maps `f` over `items`, returning the results in input order. With the `parallel` feature
+/// the items are split into contiguous chunks of at least `MIN_CHUNK_SIZE` items processed
+/// on scoped threads. There's no pool: every call spawns and joins its own threads, so calls
+/// are meant for a whole network's channels rather than inner loops.
+pub fn map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    #[cfg(feature = "parallel")]
+    {
+        let available = std::thread::available_parallelism().map_or(1, |n| n.get());
+        #[cfg(test)]
+        let available = THREADS.get().unwrap_or(available);
+        let threads = available.min(items.len() / MIN_CHUNK_SIZE);
+        if threads > 1 {
+            let chunk_size = items.len().div_ceil(threads);
+            return std::thread::scope(|scope| {
+                let handles: Vec<_> = items
+                    .chunks(chunk_size)
+                    .map(|chunk| scope.spawn(|| chunk.iter().map(&f).collect::<Vec<_>>()))
+                    .collect();
+                handles
+                    .into_iter()
+                    .flat_map(|handle| handle.join().unwrap())
+                    .collect()
+            });
+        }
+    }
+    items.iter().map(f).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::SVGPath,
+            feature::{GroovePattern, SurfaceFeature},
+            keepout::{KeepOut, Region},
+            network::EntityRef,
+        },
+        export::{dxf::network_to_dxf, svg::network_to_svg},
+        fixtures::trap_array,
+    };
+
+    #[test]
+    fn deterministic_order() {
+        let items: Vec<usize> = (0..1000).collect();
+        for threads in [1, 3, 4] {
+            let squares = with_threads(threads, || map(&items, |i| i * i));
+            assert_eq!(squares, items.iter().map(|i| i * i).collect::<Vec<_>>());
+        }
+        assert_eq!(
+            with_threads(4, || map(&items[..20], |i| i + 1)),
+            items[1..21]
+        );
+        assert!(map(&[] as &[usize], |i| *i).is_empty());
+
+        // The exports match the sequential ones however many threads there are
+        let mut network = trap_array(4, 8).network;
+        let channel = network.channels[0].clone();
+        let length = channel.path.as_ref().unwrap().length().0;
+        network.surface_features.push(SurfaceFeature {
+            channel: channel.id,
+            pattern: GroovePattern::Slanted,
+            start: 0.,
+            end: length / 2.,
+            groove_width: length / 20.,
+            pitch: length / 10.,
+            angle: std::f64::consts::FRAC_PI_4,
+            depth: 5e-6,
+        });
+        network.keep_outs.push(KeepOut {
+            id: 0,
+            region: Region::Rectangle(channel.bounding_box().unwrap()),
+            layer: None,
+        });
+        let exports = || {
+            let violating: Vec<_> = (network.keep_out_violations().iter())
+                .map(|v| v.entity)
+                .collect();
+            (
+                network_to_svg(&network),
+                network_to_dxf(&network),
+                violating,
+            )
+        };
+        let sequential = with_threads(1, exports);
+        assert_eq!(with_threads(4, exports), sequential);
+        assert_eq!(exports(), sequential);
+        assert_eq!(sequential.2, [0, 1, 2].map(EntityRef::Channel));
+    }
+}
@@ -0,0 +1,210 @@
+//! Progress reporting and cancellation of long computations, e.g., for progress bars and cancel
+//! buttons of user interfaces. Computations report their progress through a [`ProgressHandle`]
+//! and stop with a cancellation error at the next report once its token is cancelled.
+
+use serde::Serialize;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+#[derive(Serialize, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// State of a computation
+pub struct Progress {
+    /// Name of the running computation, e.g., "route"
+    pub stage: &'static str,
+
+    /// Completed units of work
+    pub completed: usize,
+
+    /// Total units of work, None if unknown in advance
+    pub total: Option<usize>,
+}
+
+impl Progress {
+    /// Completed fraction of the work
+    pub fn fraction(&self) -> Option<f64> {
+        self.total
+            .map(|total| (self.completed as f64 / total.max(1) as f64).min(1.))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// Shared flag requesting a computation to stop, cancellable from any thread
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+/// A computation stopped because its token was cancelled
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+type Callback = Box<dyn Fn(&Progress)>;
+
+#[derive(Default)]
+/// Receiver of the progress of a computation together with its cancellation token
+pub struct ProgressHandle {
+    callback: Option<Callback>,
+    token: CancellationToken,
+}
+
+impl std::fmt::Debug for ProgressHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgressHandle")
+            .field("callback", &self.callback.is_some())
+            .field("token", &self.token)
+            .finish()
+    }
+}
+
+impl ProgressHandle {
+    /// Handle calling the callback on every report
+    pub fn new(callback: impl Fn(&Progress) + 'static) -> ProgressHandle {
+        ProgressHandle {
+            callback: Some(Box::new(callback)),
+            token: CancellationToken::default(),
+        }
+    }
+
+    /// Handle stopping the computation once the token is cancelled
+    pub fn with_token(mut self, token: CancellationToken) -> ProgressHandle {
+        self.token = token;
+        self
+    }
+
+    /// Token cancelling the computation
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+
+    /// Reports the progress; Err if the computation should stop
+    pub fn report(
+        &self,
+        stage: &'static str,
+        completed: usize,
+        total: Option<usize>,
+    ) -> Result<(), Cancelled> {
+        if let Some(callback) = &self.callback {
+            callback(&Progress {
+                stage,
+                completed,
+                total,
+            });
+        }
+        match self.token.is_cancelled() {
+            true => Err(Cancelled),
+            false => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            generator::{RandomNetworkSpec, Topology},
+            interop::{InteropIssue, InteropRules},
+            network::{Network, NodeId},
+            primitives::{Point, Rect},
+        },
+        dmf::routing::{route_with_progress, RoutingError},
+        fixtures::{droplet_crossings, trap_array},
+        simulation::{
+            fluid::{Fluid, Rheology},
+            solver::{solve_iterative_with_progress, IterationSettings},
+            SimulationError,
+        },
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn reports_and_cancellation() {
+        let reports = Rc::new(RefCell::new(Vec::new()));
+        let progress = ProgressHandle::new({
+            let reports = reports.clone();
+            move |p| reports.borrow_mut().push(*p)
+        });
+        let (chip, requests) = droplet_crossings(2);
+        route_with_progress(&chip, &requests, 100, &progress).unwrap();
+        let total = Some(requests.len());
+        let expected: Vec<Progress> = (0..requests.len())
+            .map(|completed| Progress {
+                stage: "route",
+                completed,
+                total,
+            })
+            .collect();
+        assert_eq!(*reports.borrow(), expected);
+        assert_eq!(expected[1].fraction(), Some(0.25));
+
+        // A token cancelled up front stops every computation at its first report
+        let token = CancellationToken::default();
+        token.cancel();
+        let cancelled = ProgressHandle::default().with_token(token);
+        assert!(cancelled.token().is_cancelled());
+        assert_eq!(
+            route_with_progress(&chip, &requests, 100, &cancelled),
+            Err(RoutingError::Cancelled)
+        );
+
+        let mut network = Network::random(&RandomNetworkSpec {
+            topology: Topology::Grid {
+                columns: 3,
+                rows: 1,
+            },
+            pitch: 5000.,
+            ..Default::default()
+        });
+        let outline = Rect {
+            min: Point([-2000., -7000.]),
+            max: Point([13000., 8000.]),
+        };
+        let unplaced = network.clone();
+        let rules = InteropRules::iso_22916(1000.);
+        let ports = [NodeId(0), NodeId(2)];
+        assert_eq!(
+            rules.place_ports_with_progress(&mut network, &outline, &ports, &cancelled),
+            Err(InteropIssue::Cancelled)
+        );
+        assert_eq!(network, unplaced);
+
+        let fixture = trap_array(1, 2);
+        let fluid = Fluid {
+            rheology: Rheology::PowerLaw {
+                consistency: 1e-2,
+                flow_index: 0.7,
+            },
+            ..Fluid::water()
+        };
+        let settings = IterationSettings::default();
+        let solve = |progress| {
+            solve_iterative_with_progress(
+                &fixture.network,
+                &fluid,
+                &fixture.boundaries,
+                &settings,
+                progress,
+            )
+        };
+        assert_eq!(solve(&cancelled), Err(SimulationError::Cancelled));
+        assert!(solve(&ProgressHandle::default()).is_ok());
+    }
+}
@@ -0,0 +1,184 @@
+//! Project files bundling everything of a design session: the network, the designer parameters
+//! it was generated from, simulation results, and settings of the framework and of the tools
+//! built on it. Projects are versioned JSON; the Python and WASM tools read and write the same
+//! files, and settings of tools unknown to the framework are kept verbatim.
+
+use crate::{
+    base::{network::Network, render::RenderConfig},
+    designer::{
+        droplet::DropletGeneratorParameters, tesla::TeslaValveParameters, trap::TrapArrayParameters,
+    },
+    export::style::Style,
+    simulation::{
+        fluid::Fluid,
+        result::SimulationResult,
+        solver::{Boundary, IterationSettings},
+    },
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Parameters of a designer function that generated part of the network
+pub enum Design {
+    DropletGenerator(DropletGeneratorParameters),
+    TeslaValve(TeslaValveParameters),
+    TrapArray(TrapArrayParameters),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Settings of the simulation and export of a project
+pub struct ProjectSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fluid: Option<Fluid>,
+
+    /// Pumps and outlets of the simulation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub boundaries: Vec<Boundary>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iteration: Option<IterationSettings>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub render: Option<RenderConfig>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<Style>,
+
+    /// Settings of other tools by tool name, e.g., window layouts of an editor
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tools: BTreeMap<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// A saved design session
+pub struct Project {
+    /// Version of the file format, [Project::FORMAT_VERSION] when written by this framework
+    pub format_version: u32,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    pub network: Network,
+
+    /// Designer parameters in the order they were applied
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub designs: Vec<Design>,
+
+    /// Simulation results by name, e.g., one per operating point
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub results: BTreeMap<String, SimulationResult>,
+
+    #[serde(default)]
+    pub settings: ProjectSettings,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Reasons a project file can't be read
+pub enum ProjectError {
+    /// The file was written by a newer framework
+    UnsupportedVersion(u32),
+
+    /// The file isn't a project, holds the parser's message
+    Invalid(String),
+}
+
+impl std::fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectError::UnsupportedVersion(version) => write!(
+                f,
+                "project format version {version} is newer than the supported version {}",
+                Project::FORMAT_VERSION
+            ),
+            ProjectError::Invalid(message) => write!(f, "invalid project: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {}
+
+impl Project {
+    /// Current version of the file format, incremented on incompatible changes
+    pub const FORMAT_VERSION: u32 = 1;
+
+    /// Project of the network without designs, results, and settings
+    pub fn new(network: Network) -> Project {
+        Project {
+            format_version: Project::FORMAT_VERSION,
+            name: None,
+            network,
+            designs: Vec::new(),
+            results: BTreeMap::new(),
+            settings: ProjectSettings::default(),
+        }
+    }
+
+    /// Reads a project file. Files of older format versions are upgraded to the current one,
+    /// newer versions are rejected before their content is interpreted.
+    pub fn from_json(json: &str) -> Result<Project, ProjectError> {
+        let value: Value =
+            serde_json::from_str(json).map_err(|e| ProjectError::Invalid(e.to_string()))?;
+        let version = value
+            .get("format_version")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| ProjectError::Invalid("missing format_version".into()))?;
+        if version > Project::FORMAT_VERSION as u64 {
+            return Err(ProjectError::UnsupportedVersion(version as u32));
+        }
+        let mut project: Project =
+            serde_json::from_value(value).map_err(|e| ProjectError::Invalid(e.to_string()))?;
+        project.format_version = Project::FORMAT_VERSION;
+        Ok(project)
+    }
+
+    /// Indented JSON of the project in the current format version
+    pub fn to_json(&self) -> String {
+        let project = Project {
+            format_version: Project::FORMAT_VERSION,
+            ..self.clone()
+        };
+        serde_json::to_string_pretty(&project).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{fixtures::trap_array, simulation::solver::FlowSolution};
+
+    #[test]
+    fn round_trip() {
+        let fixture = trap_array(2, 3);
+        let mut project = Project::new(fixture.network);
+        project.name = Some("traps".into());
+        project.settings.boundaries = fixture.boundaries;
+        project.settings.style = Some(Style::print());
+        project
+            .settings
+            .tools
+            .insert("editor".into(), serde_json::json!({ "zoom": 2.5 }));
+        project.results.insert(
+            "steady".into(),
+            SimulationResult::from(FlowSolution::default()),
+        );
+
+        let json = project.to_json();
+        assert_eq!(Project::from_json(&json), Ok(project));
+
+        let newer = json.replacen("\"format_version\": 1", "\"format_version\": 2", 1);
+        assert_eq!(
+            Project::from_json(&newer),
+            Err(ProjectError::UnsupportedVersion(2))
+        );
+        assert!(matches!(
+            Project::from_json("{}"),
+            Err(ProjectError::Invalid(_))
+        ));
+    }
+}
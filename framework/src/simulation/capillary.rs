@@ -0,0 +1,343 @@
+//! Capillary-driven (passive) filling of an initially empty network. Filled channels act as
+//! Poiseuille resistances; every advancing meniscus pulls with its Laplace pressure, which for a
+//! single straight channel reproduces the Washburn equation.
+
+use super::{
+    fluid::Fluid,
+    resistance::resistance_for_length,
+    solver::{solve_with_resistances, Boundary, BoundaryCondition},
+    SimulationError,
+};
+use crate::{
+    base::{
+        channel::{Channel, Shape},
+        network::{Network, Node, NodeId},
+    },
+    progress::ProgressHandle,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Initial filled length of a channel as fraction of its length, avoids a zero resistance
+const INITIAL_FRACTION: f64 = 1e-4;
+
+/// Largest relative growth of a filled length per step; front velocities decay with the filled
+/// length, so explicit steps must stay short relative to it
+const MAX_GROWTH: f64 = 5e-3;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Setup of a capillary filling simulation
+pub struct CapillaryFilling {
+    /// Nodes connected to the liquid reservoir
+    pub inlets: Vec<NodeId>,
+
+    /// Static contact angle of the liquid on the channel walls in radians
+    pub contact_angle: f64,
+
+    /// Reservoir pressure relative to the surrounding gas, e.g., a hydrostatic head
+    #[serde(default)]
+    pub inlet_pressure: f64,
+
+    /// Largest time step; steps are shortened to end exactly when a front reaches a node
+    pub time_step: f64,
+
+    /// Simulated duration
+    pub end_time: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Position of a meniscus within a channel
+pub struct FrontPosition {
+    /// Id of the channel
+    pub channel: usize,
+
+    /// Node the liquid entered the channel from
+    pub from: NodeId,
+
+    /// Filled length measured from that node
+    pub position: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// State of all fronts at a point in time
+pub struct FillingSnapshot {
+    pub time: f64,
+    pub fronts: Vec<FrontPosition>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Outcome of a capillary filling simulation
+pub struct FillingResult {
+    /// Front positions after every time step
+    pub snapshots: Vec<FillingSnapshot>,
+
+    /// Time at which each wetted node was reached
+    pub node_fill_times: BTreeMap<NodeId, f64>,
+
+    /// Time at which each channel was completely filled
+    pub channel_fill_times: BTreeMap<usize, f64>,
+
+    /// Whether filling stopped before the end time because no front advances anymore, e.g., at
+    /// hydrophobic walls
+    pub stalled: bool,
+}
+
+/// Laplace pressure of a meniscus pulling liquid into the channel, negative for non-wetting
+/// walls: γ cos θ (2 / w + 2 / h) for rectangular and 2 γ cos θ / r for circular cross-sections
+pub fn capillary_pressure(shape: &Shape, surface_tension: f64, contact_angle: f64) -> f64 {
+    let tension = surface_tension * contact_angle.cos();
+    match shape {
+        Shape::Rectangular(shape) => tension * (2. / shape.width + 2. / shape.height),
+        Shape::Cylindrical(shape) => 2. * tension / shape.radius,
+    }
+}
+
+struct State {
+    node_fill_times: BTreeMap<NodeId, f64>,
+    channel_fill_times: BTreeMap<usize, f64>,
+    /// Partially filled channels: entry node and filled length
+    fronts: BTreeMap<usize, (NodeId, f64)>,
+}
+
+impl State {
+    fn wet(&mut self, network: &Network, lengths: &BTreeMap<usize, f64>, node: NodeId, time: f64) {
+        if self.node_fill_times.contains_key(&node) {
+            return;
+        }
+        self.node_fill_times.insert(node, time);
+        for channel in &network.channels {
+            if (channel.node_a != node && channel.node_b != node)
+                || self.channel_fill_times.contains_key(&channel.id)
+            {
+                continue;
+            }
+            if self.fronts.remove(&channel.id).is_some() {
+                // Liquid enters from both ends, the enclosed gas is neglected
+                self.channel_fill_times.insert(channel.id, time);
+            } else {
+                let entry = (node, INITIAL_FRACTION * lengths[&channel.id]);
+                self.fronts.insert(channel.id, entry);
+            }
+        }
+    }
+}
+
+/// Simulates the advance of the liquid fronts from the inlets through the network
+pub fn simulate_capillary_filling(
+    network: &Network,
+    fluid: &Fluid,
+    setup: &CapillaryFilling,
+) -> Result<FillingResult, SimulationError> {
+    simulate_capillary_filling_with_progress(network, fluid, setup, &ProgressHandle::default())
+}
+
+/// Simulates like [simulate_capillary_filling], reporting the filled channels out of all
+/// channels after every time step
+pub fn simulate_capillary_filling_with_progress(
+    network: &Network,
+    fluid: &Fluid,
+    setup: &CapillaryFilling,
+    progress: &ProgressHandle,
+) -> Result<FillingResult, SimulationError> {
+    let surface_tension = fluid
+        .surface_tension
+        .ok_or(SimulationError::MissingFluidProperty("surface_tension"))?;
+    let lengths = network
+        .channels
+        .iter()
+        .map(|c| {
+            c.length()
+                .map(|l| (c.id, l))
+                .ok_or(SimulationError::MissingLength(c.id))
+        })
+        .collect::<Result<BTreeMap<_, _>, _>>()?;
+    let channels: BTreeMap<usize, _> = network.channels.iter().map(|c| (c.id, c)).collect();
+
+    let mut state = State {
+        node_fill_times: BTreeMap::new(),
+        channel_fill_times: BTreeMap::new(),
+        fronts: BTreeMap::new(),
+    };
+    for inlet in &setup.inlets {
+        state.wet(network, &lengths, *inlet, 0.);
+    }
+
+    let mut snapshots = Vec::new();
+    let mut time = 0.;
+    let mut stalled = false;
+    while time < setup.end_time && !state.fronts.is_empty() {
+        progress.report(
+            "capillary_filling",
+            state.channel_fill_times.len(),
+            Some(network.channels.len()),
+        )?;
+        // Wetted region plus one ghost node per meniscus, held at the capillary pressure
+        let mut wetted = Network {
+            nodes: state
+                .node_fill_times
+                .keys()
+                .map(|id| Node::new(*id))
+                .collect(),
+            ..Default::default()
+        };
+        let mut resistances = BTreeMap::new();
+        let mut boundaries: Vec<Boundary> = setup
+            .inlets
+            .iter()
+            .map(|node| Boundary {
+                node: *node,
+                condition: BoundaryCondition::Pressure(setup.inlet_pressure),
+            })
+            .collect();
+        for id in state.channel_fill_times.keys() {
+            let channel = channels[id];
+            wetted.channels.push((*channel).clone());
+            resistances.insert(
+                *id,
+                resistance_for_length(&channel.shape, fluid.viscosity, lengths[id]),
+            );
+        }
+        let first_ghost = network.next_node_id().0;
+        for (k, (id, (from, position))) in state.fronts.iter().enumerate() {
+            let channel = channels[id];
+            let ghost = NodeId(first_ghost + k);
+            wetted.nodes.push(Node::new(ghost));
+            wetted.channels.push(Channel {
+                node_a: *from,
+                node_b: ghost,
+                ..(*channel).clone()
+            });
+            resistances.insert(
+                *id,
+                resistance_for_length(&channel.shape, fluid.viscosity, *position),
+            );
+            boundaries.push(Boundary {
+                node: ghost,
+                condition: BoundaryCondition::Pressure(-capillary_pressure(
+                    &channel.shape,
+                    surface_tension,
+                    setup.contact_angle,
+                )),
+            });
+        }
+        let solution = solve_with_resistances(&wetted, &resistances, &boundaries)?;
+
+        let velocities: BTreeMap<usize, f64> = state
+            .fronts
+            .keys()
+            .map(|id| (*id, solution.flows[id] / channels[id].shape.area()))
+            .collect();
+        let step = velocities
+            .iter()
+            .filter(|(_, v)| **v > 0.)
+            .map(|(id, v)| {
+                let position = state.fronts[id].1;
+                (MAX_GROWTH * position).min(lengths[id] - position) / v
+            })
+            .fold(setup.time_step.min(setup.end_time - time), f64::min);
+        if velocities.values().all(|v| *v <= 0.) {
+            stalled = true;
+            break;
+        }
+
+        time += step;
+        let mut reached = Vec::new();
+        for (id, (from, position)) in state.fronts.iter_mut() {
+            let length = lengths[id];
+            *position =
+                (*position + velocities[id] * step).clamp(INITIAL_FRACTION * length, length);
+            if *position >= length * (1. - 1e-12) {
+                let channel = channels[id];
+                let to = if channel.node_a == *from {
+                    channel.node_b
+                } else {
+                    channel.node_a
+                };
+                reached.push((*id, to));
+            }
+        }
+        for (id, node) in reached {
+            state.fronts.remove(&id);
+            state.channel_fill_times.insert(id, time);
+            state.wet(network, &lengths, node, time);
+        }
+
+        snapshots.push(FillingSnapshot {
+            time,
+            fronts: state
+                .fronts
+                .iter()
+                .map(|(id, (from, position))| FrontPosition {
+                    channel: *id,
+                    from: *from,
+                    position: *position,
+                })
+                .collect(),
+        });
+    }
+
+    Ok(FillingResult {
+        snapshots,
+        node_fill_times: state.node_fill_times,
+        channel_fill_times: state.channel_fill_times,
+        stalled,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::channel::CylindricalShape;
+
+    #[test]
+    fn washburn() {
+        let (radius, length) = (50e-6, 0.01);
+        let network = Network {
+            nodes: vec![Node::new(NodeId(0)), Node::new(NodeId(1))],
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(1),
+                node_b: NodeId(0),
+                shape: Shape::Cylindrical(CylindricalShape { radius }),
+                path: None,
+                length: Some(length),
+                layer: 0,
+                metadata: Default::default(),
+            }],
+            ..Default::default()
+        };
+        let fluid = Fluid::water();
+        let contact_angle = 0.5;
+        let setup = CapillaryFilling {
+            inlets: vec![NodeId(0)],
+            contact_angle,
+            inlet_pressure: 0.,
+            time_step: 1e-4,
+            end_time: 10.,
+        };
+        let result = simulate_capillary_filling(&network, &fluid, &setup).unwrap();
+
+        // x² = γ cos θ r t / (2 μ)
+        let expected = 2. * fluid.viscosity * length * length
+            / (fluid.surface_tension.unwrap() * contact_angle.cos() * radius);
+        let fill_time = result.channel_fill_times[&0];
+        assert!((fill_time - expected).abs() < 0.01 * expected);
+        assert_eq!(result.node_fill_times[&NodeId(1)], fill_time);
+        assert!(!result.stalled);
+
+        let hydrophobic = CapillaryFilling {
+            contact_angle: 2.,
+            ..setup
+        };
+        assert!(
+            simulate_capillary_filling(&network, &fluid, &hydrophobic)
+                .unwrap()
+                .stalled
+        );
+    }
+}
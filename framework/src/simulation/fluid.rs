@@ -0,0 +1,157 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Dependency of the viscosity on the shear rate
+pub enum Rheology {
+    /// Constant viscosity
+    #[default]
+    Newtonian,
+
+    /// Power-law fluid, μ = K γ̇^(n - 1)
+    PowerLaw {
+        /// Flow consistency index K
+        consistency: f64,
+
+        /// Flow behavior index n, below 1 for shear-thinning fluids
+        flow_index: f64,
+    },
+
+    /// Carreau fluid, μ = μ∞ + (μ0 - μ∞) (1 + (λ γ̇)²)^((n - 1) / 2)
+    Carreau {
+        /// Viscosity at zero shear rate μ0
+        zero_shear_viscosity: f64,
+
+        /// Viscosity at infinite shear rate μ∞
+        infinite_shear_viscosity: f64,
+
+        /// Relaxation time λ
+        relaxation_time: f64,
+
+        /// Power index n
+        power_index: f64,
+    },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Properties of the fluid flowing through the network
+pub struct Fluid {
+    /// Dynamic viscosity; the initial guess of the iteration for non-Newtonian fluids
+    pub viscosity: f64,
+
+    /// Viscosity model, Newtonian if not set
+    #[serde(default, skip_serializing_if = "Fluid::is_newtonian")]
+    pub rheology: Rheology,
+
+    /// Density
+    pub density: f64,
+
+    /// Diffusion coefficient of the transported species, required for Péclet numbers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diffusivity: Option<f64>,
+
+    /// Surface tension against the displaced phase, required for capillary numbers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub surface_tension: Option<f64>,
+}
+
+impl Fluid {
+    /// Water at 20 °C against air in SI units
+    pub fn water() -> Self {
+        Fluid {
+            viscosity: 1.0e-3,
+            rheology: Rheology::Newtonian,
+            density: 998.,
+            diffusivity: None,
+            surface_tension: Some(0.0728),
+        }
+    }
+
+    fn is_newtonian(rheology: &Rheology) -> bool {
+        *rheology == Rheology::Newtonian
+    }
+
+    /// Apparent viscosity at the given shear rate
+    pub fn apparent_viscosity(&self, shear_rate: f64) -> f64 {
+        match self.rheology {
+            Rheology::Newtonian => self.viscosity,
+            Rheology::PowerLaw {
+                consistency,
+                flow_index,
+            } => {
+                if shear_rate > 0. {
+                    consistency * shear_rate.powf(flow_index - 1.)
+                } else {
+                    self.viscosity
+                }
+            }
+            Rheology::Carreau {
+                zero_shear_viscosity,
+                infinite_shear_viscosity,
+                relaxation_time,
+                power_index,
+            } => {
+                infinite_shear_viscosity
+                    + (zero_shear_viscosity - infinite_shear_viscosity)
+                        * (1. + (relaxation_time * shear_rate).powi(2))
+                            .powf((power_index - 1.) / 2.)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Fluid with temperature-dependent properties; temperatures are absolute, e.g., in kelvin
+pub struct ThermalFluid {
+    /// Properties at the reference temperature
+    pub reference: Fluid,
+
+    /// Temperature of the reference properties
+    pub reference_temperature: f64,
+
+    /// Activation temperature B of the Andrade model μ = μ_ref exp(B (1 / T - 1 / T_ref)),
+    /// zero for a constant viscosity
+    #[serde(default)]
+    pub viscosity_activation: f64,
+
+    /// Volumetric expansion coefficient β of ρ = ρ_ref (1 - β (T - T_ref))
+    #[serde(default)]
+    pub thermal_expansion: f64,
+
+    /// Specific heat capacity
+    pub heat_capacity: f64,
+
+    /// Thermal conductivity
+    pub thermal_conductivity: f64,
+}
+
+impl ThermalFluid {
+    /// Water around 20 °C to 95 °C in SI units, the viscosity within about 10 %
+    pub fn water() -> Self {
+        ThermalFluid {
+            reference: Fluid::water(),
+            reference_temperature: 293.15,
+            viscosity_activation: 1900.,
+            thermal_expansion: 2.1e-4,
+            heat_capacity: 4182.,
+            thermal_conductivity: 0.6,
+        }
+    }
+
+    /// Properties at the temperature. The rheology is kept, only the Newtonian viscosity is
+    /// scaled.
+    pub fn at(&self, temperature: f64) -> Fluid {
+        let scale = (self.viscosity_activation
+            * (1. / temperature - 1. / self.reference_temperature))
+            .exp();
+        Fluid {
+            viscosity: self.reference.viscosity * scale,
+            density: self.reference.density
+                * (1. - self.thermal_expansion * (temperature - self.reference_temperature)),
+            ..self.reference
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Incremental re-solving after local edits, e.g., in interactive editors. The conductance
+//! matrix is factorized once; changed channel resistances are low-rank updates of it, which the
+//! Woodbury identity applies with one solve per changed channel instead of a new
+//! factorization. Once more than [MAX_RANK] channels deviate from the factorized state, the
+//! matrix is factorized anew. The factorization is dense, which suits networks of up to a few
+//! thousand nodes.
+
+use super::{
+    solver::{gauss, Boundary, FlowSolution, Lu, PressureSystem},
+    SimulationError,
+};
+use crate::base::network::Network;
+use std::collections::BTreeMap;
+
+/// Largest number of channels deviating from the factorized conductances before refactorizing
+pub const MAX_RANK: usize = 32;
+
+#[derive(Debug, Clone)]
+/// Flow solution of a network that is updated when channel resistances change. Nodes,
+/// channels, and boundaries are fixed; topology edits require a new solver.
+pub struct IncrementalSolver {
+    system: PressureSystem,
+
+    /// Index of every channel id in the system
+    positions: BTreeMap<usize, usize>,
+
+    lu: Lu,
+
+    /// Conductances and right-hand side the factorization was computed for
+    base: Vec<f64>,
+    base_rhs: Vec<f64>,
+
+    conductances: Vec<f64>,
+
+    /// Solutions of the factorized system for the stamp of every changed channel
+    columns: BTreeMap<usize, Vec<f64>>,
+
+    solution: FlowSolution,
+}
+
+impl IncrementalSolver {
+    /// Solves the steady-state flow for the given hydraulic resistance per channel id like
+    /// [solve_with_resistances](super::solver::solve_with_resistances)
+    pub fn new(
+        network: &Network,
+        resistances: &BTreeMap<usize, f64>,
+        boundaries: &[Boundary],
+    ) -> Result<IncrementalSolver, SimulationError> {
+        let system = PressureSystem::new(network, boundaries)?;
+        let conductances = system
+            .channels
+            .iter()
+            .map(|(id, _, _)| {
+                resistances
+                    .get(id)
+                    .map(|r| 1. / r)
+                    .ok_or(SimulationError::MissingLength(*id))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let (matrix, rhs) = system.assemble(&conductances);
+        let lu = Lu::new(matrix).ok_or(SimulationError::Singular)?;
+        let solution = system.solution(&lu.solve(&rhs), &conductances);
+        Ok(IncrementalSolver {
+            positions: system
+                .channels
+                .iter()
+                .enumerate()
+                .map(|(i, (id, _, _))| (*id, i))
+                .collect(),
+            system,
+            lu,
+            base: conductances.clone(),
+            base_rhs: rhs,
+            conductances,
+            columns: BTreeMap::new(),
+            solution,
+        })
+    }
+
+    pub fn solution(&self) -> &FlowSolution {
+        &self.solution
+    }
+
+    /// Number of channels whose resistance changed since the last factorization
+    pub fn rank(&self) -> usize {
+        self.changed().count()
+    }
+
+    fn changed(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.base.len()).filter(|i| self.conductances[*i] != self.base[*i])
+    }
+
+    /// Factorizes the matrix of the current resistances
+    pub fn refactorize(&mut self) -> Result<(), SimulationError> {
+        let (matrix, rhs) = self.system.assemble(&self.conductances);
+        self.lu = Lu::new(matrix).ok_or(SimulationError::Singular)?;
+        self.solution = self
+            .system
+            .solution(&self.lu.solve(&rhs), &self.conductances);
+        self.base.clone_from(&self.conductances);
+        self.base_rhs = rhs;
+        self.columns.clear();
+        Ok(())
+    }
+
+    /// Sets the resistances of the channels and updates the solution
+    pub fn update(
+        &mut self,
+        resistances: &[(usize, f64)],
+    ) -> Result<&FlowSolution, SimulationError> {
+        for (id, resistance) in resistances {
+            let position = *self
+                .positions
+                .get(id)
+                .ok_or(SimulationError::UnknownChannel(*id))?;
+            self.conductances[position] = 1. / resistance;
+        }
+        let changed: Vec<usize> = self.changed().collect();
+        if changed.len() > MAX_RANK {
+            self.refactorize()?;
+            return Ok(&self.solution);
+        }
+
+        // Every changed channel adds Δg u uᵀ to the matrix and Δg times the pressures of fixed
+        // ends to the right-hand side
+        let mut rhs = self.base_rhs.clone();
+        let mut updates = Vec::new();
+        for i in changed {
+            let (_, a, b) = self.system.channels[i];
+            let stamp = self.system.stamp(a, b);
+            if stamp.is_empty() {
+                continue;
+            }
+            let delta = self.conductances[i] - self.base[i];
+            for (row, _, pressure) in &stamp {
+                rhs[*row] += delta * pressure;
+            }
+            if !self.columns.contains_key(&i) {
+                let mut u = vec![0.; rhs.len()];
+                for (row, sign, _) in &stamp {
+                    u[*row] = *sign;
+                }
+                self.columns.insert(i, self.lu.solve(&u));
+            }
+            updates.push((delta, stamp, i));
+        }
+        let updates: Vec<_> = updates
+            .into_iter()
+            .map(|(delta, stamp, i)| (delta, stamp, &self.columns[&i]))
+            .collect();
+
+        let y = self.lu.solve(&rhs);
+        let dot = |stamp: &[(usize, f64, f64)], x: &[f64]| -> f64 {
+            stamp.iter().map(|(row, sign, _)| sign * x[*row]).sum()
+        };
+        let capacitance: Vec<Vec<f64>> = updates
+            .iter()
+            .enumerate()
+            .map(|(i, (delta, stamp, _))| {
+                updates
+                    .iter()
+                    .enumerate()
+                    .map(|(j, (_, _, z))| dot(stamp, z) + if i == j { 1. / delta } else { 0. })
+                    .collect()
+            })
+            .collect();
+        let projected = updates.iter().map(|(_, stamp, _)| dot(stamp, &y)).collect();
+        let Some(t) = gauss(capacitance, projected) else {
+            // The update is ill-conditioned, e.g., a channel was closed off
+            self.refactorize()?;
+            return Ok(&self.solution);
+        };
+        let mut x = y;
+        for ((_, _, z), t) in updates.iter().zip(t) {
+            for (value, z) in x.iter_mut().zip(z.iter()) {
+                *value -= z * t;
+            }
+        }
+        self.solution = self.system.solution(&x, &self.conductances);
+        Ok(&self.solution)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{generator::RandomNetworkSpec, network::NodeId},
+        simulation::solver::{solve_with_resistances, BoundaryCondition},
+    };
+
+    #[test]
+    fn matches_full_solve() {
+        let network = Network::random(&RandomNetworkSpec::default());
+        let mut resistances: BTreeMap<usize, f64> = network
+            .channels
+            .iter()
+            .map(|c| (c.id, network.cached_resistance(c, 1e-3).unwrap()))
+            .collect();
+        let boundaries = [
+            Boundary {
+                node: NodeId(0),
+                condition: BoundaryCondition::Pressure(100.),
+            },
+            Boundary {
+                node: NodeId(99),
+                condition: BoundaryCondition::Pressure(0.),
+            },
+            Boundary {
+                node: NodeId(45),
+                condition: BoundaryCondition::Flow(1e-12),
+            },
+        ];
+        let mut solver = IncrementalSolver::new(&network, &resistances, &boundaries).unwrap();
+        let assert_matches = |solver: &IncrementalSolver, resistances: &BTreeMap<_, _>| {
+            let full = solve_with_resistances(&network, resistances, &boundaries).unwrap();
+            let scale = full.flows.values().fold(0f64, |m, q| m.max(q.abs()));
+            for (id, q) in &solver.solution().flows {
+                assert!((q - full.flows[id]).abs() < 1e-9 * scale);
+            }
+            for (id, p) in &solver.solution().pressures {
+                assert!((p - full.pressures[id]).abs() < 1e-9 * 100.);
+            }
+        };
+        assert_matches(&solver, &resistances);
+
+        // Channel 0 touches the fixed node 0
+        let changes = [
+            (0, resistances[&0] * 3.),
+            (50, resistances[&50] / 2.),
+            (51, 1e30),
+        ];
+        solver.update(&changes).unwrap();
+        resistances.extend(changes);
+        assert_eq!(solver.rank(), 3);
+        assert_matches(&solver, &resistances);
+
+        for id in 60..100 {
+            let change = (id, resistances[&id] * 1.5);
+            solver.update(&[change]).unwrap();
+            resistances.extend([change]);
+        }
+        assert!(solver.rank() <= MAX_RANK);
+        assert_matches(&solver, &resistances);
+
+        assert_eq!(
+            solver.update(&[(1000, 1.)]).err(),
+            Some(SimulationError::UnknownChannel(1000))
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Minor losses: pressure drops at bends and junctions on top of the Poiseuille resistance.
+//! They grow with the square of the flow, Δp = K ρ v² / 2 with the mean velocity v, and become
+//! noticeable in designs with sharp corners at higher flow rates. Loss coefficients K are
+//! estimated from the geometry or given per channel.
+
+use super::{
+    fluid::Fluid,
+    solver::{iterate, Boundary, FlowSolution, IterationSettings},
+    SimulationError,
+};
+use crate::{
+    base::{
+        channel::{Channel, PathPiece},
+        network::{Network, NodeId},
+    },
+    progress::ProgressHandle,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, f64::consts::FRAC_PI_2};
+
+/// Smallest turn in radians between pieces that counts as a corner
+const CORNER_TOLERANCE: f64 = 1e-6;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Loss coefficients of the geometric features of channels, all zero if not set
+pub struct LossCoefficients {
+    /// Coefficient of a 90° arc, scaled with the angle turned by the arcs of a path
+    #[serde(default)]
+    pub bend: f64,
+
+    /// Coefficient of a sharp 90° corner, scaled with the turn at each kink of a path
+    #[serde(default)]
+    pub corner: f64,
+
+    /// Coefficient of each channel end at a junction of three or more channels
+    #[serde(default)]
+    pub junction: f64,
+
+    /// Coefficient per channel id, replacing the geometric estimate
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub channels: BTreeMap<usize, f64>,
+}
+
+impl LossCoefficients {
+    /// Estimated coefficient of the channel, given the number of channels per node
+    fn estimate(&self, channel: &Channel, degrees: &BTreeMap<NodeId, usize>) -> f64 {
+        if let Some(k) = self.channels.get(&channel.id) {
+            return *k;
+        }
+        let mut k = 0.;
+        if let Some(path) = &channel.path {
+            let arcs: f64 = (path.pieces.iter())
+                .map(|piece| match piece {
+                    PathPiece::Arc(arc) => arc.angles().sweep.abs(),
+                    PathPiece::LineSegment(_) => 0.,
+                })
+                .sum();
+            let corners: f64 = (path.kinks(CORNER_TOLERANCE).iter())
+                .map(|kink| kink.angle.abs())
+                .sum();
+            k += (self.bend * arcs + self.corner * corners) / FRAC_PI_2;
+        }
+        let junctions = [channel.node_a, channel.node_b]
+            .iter()
+            .filter(|node| degrees.get(node).is_some_and(|d| *d >= 3))
+            .count();
+        k + self.junction * junctions as f64
+    }
+}
+
+/// Non-zero loss coefficient per channel id
+pub fn loss_coefficients(
+    network: &Network,
+    coefficients: &LossCoefficients,
+) -> BTreeMap<usize, f64> {
+    let mut degrees = BTreeMap::new();
+    for channel in &network.channels {
+        *degrees.entry(channel.node_a).or_default() += 1;
+        *degrees.entry(channel.node_b).or_default() += 1;
+    }
+    (network.channels.iter())
+        .map(|c| (c.id, coefficients.estimate(c, &degrees)))
+        .filter(|(_, k)| *k != 0.)
+        .collect()
+}
+
+/// Minor pressure drop along the channel at the flow, signed like the flow
+pub fn minor_loss(channel: &Channel, fluid: &Fluid, coefficient: f64, flow: f64) -> f64 {
+    let area = channel.shape.area();
+    coefficient * fluid.density * flow * flow.abs() / (2. * area * area)
+}
+
+/// Solves the steady-state flow with the minor losses added to the Poiseuille resistances,
+/// iterating until the flows converge
+pub fn solve_with_losses(
+    network: &Network,
+    fluid: &Fluid,
+    boundaries: &[Boundary],
+    coefficients: &LossCoefficients,
+    settings: &IterationSettings,
+) -> Result<FlowSolution, SimulationError> {
+    let drops: Vec<_> = loss_coefficients(network, coefficients)
+        .into_iter()
+        .filter_map(|(id, k)| {
+            let channel = network.channels.iter().find(|c| c.id == id)?;
+            Some((id, minor_loss(channel, fluid, k, 1.), 2.))
+        })
+        .collect();
+    iterate(
+        network,
+        fluid,
+        boundaries,
+        settings,
+        &ProgressHandle::default(),
+        &drops,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{ChannelPath, LineSegment, RectangularShape, Shape},
+            network::Node,
+            primitives::Point,
+        },
+        simulation::{
+            resistance::channel_resistance,
+            solver::{solve, BoundaryCondition},
+        },
+    };
+
+    #[test]
+    fn corner_losses() {
+        // An L-shaped channel with a sharp corner
+        let mut path = ChannelPath::new();
+        for (start, end) in [([0., 0.], [5e-3, 0.]), ([5e-3, 0.], [5e-3, 5e-3])] {
+            path.add(PathPiece::LineSegment(LineSegment {
+                start: Point(start),
+                end: Point(end),
+            }));
+        }
+        let channel = Channel {
+            id: 0,
+            node_a: NodeId(0),
+            node_b: NodeId(1),
+            shape: Shape::Rectangular(RectangularShape {
+                width: 200e-6,
+                height: 100e-6,
+            }),
+            path: Some(path),
+            length: None,
+            layer: 0,
+            metadata: Default::default(),
+        };
+        let network = Network {
+            nodes: vec![Node::new(NodeId(0)), Node::new(NodeId(1))],
+            channels: vec![channel.clone()],
+            ..Default::default()
+        };
+        let boundaries = [(0, 1e5), (1, 0.)].map(|(node, p)| Boundary {
+            node: NodeId(node),
+            condition: BoundaryCondition::Pressure(p),
+        });
+        let coefficients = LossCoefficients {
+            corner: 1.2,
+            junction: 0.5,
+            ..Default::default()
+        };
+        let k = loss_coefficients(&network, &coefficients);
+        assert!((k[&0] - 1.2).abs() < 1e-9);
+
+        let fluid = Fluid::water();
+        let settings = IterationSettings::default();
+        let q = solve_with_losses(&network, &fluid, &boundaries, &coefficients, &settings)
+            .unwrap()
+            .flows[&0];
+        assert!(q < solve(&network, &fluid, &boundaries).unwrap().flows[&0]);
+        let drop = channel_resistance(&channel, &fluid).unwrap() * q
+            + minor_loss(&channel, &fluid, k[&0], q);
+        assert!((drop - 1e5).abs() < 1e-3);
+
+        let none = LossCoefficients::default();
+        assert!(loss_coefficients(&network, &none).is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+//! One-dimensional (lumped) flow simulation of channel networks. Quantities are unit agnostic
+//! but must be given in a consistent unit system, e.g., SI.
+
+use crate::{base::network::NodeId, progress::Cancelled};
+use std::fmt;
+
+pub mod capillary;
+pub mod fluid;
+pub mod incremental;
+pub mod losses;
+pub mod plugs;
+pub mod reduction;
+pub mod resistance;
+pub mod result;
+pub mod scenario;
+pub mod solver;
+pub mod sparse;
+pub mod thermal;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Reasons a network can't be simulated
+pub enum SimulationError {
+    /// The channel has neither an explicit length nor a path
+    MissingLength(usize),
+
+    /// A boundary condition or channel refers to a node that isn't part of the network
+    UnknownNode(NodeId),
+
+    /// A resistance change refers to a channel that isn't part of the solved network
+    UnknownChannel(usize),
+
+    /// The pressure system has no unique solution, e.g., a subnetwork without pressure reference
+    Singular,
+
+    /// The simulation requires a fluid property that isn't set
+    MissingFluidProperty(&'static str),
+
+    /// The non-Newtonian or minor-loss iteration didn't converge within the given number of
+    /// iterations
+    NotConverged {
+        /// Number of performed iterations
+        iterations: usize,
+    },
+
+    /// The simulation was cancelled through its progress handle
+    Cancelled,
+
+    /// Combinations of the valve states are requested for more valves than can be enumerated
+    TooManyValves(usize),
+
+    /// The port model of the module doesn't match its interface nodes
+    InvalidPortModel(usize),
+
+    /// The tolerance distribution of the channel has inverted bounds or invalid parameters
+    InvalidTolerance(usize),
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SimulationError::MissingLength(id) => write!(f, "channel {id} has no length"),
+            SimulationError::UnknownNode(NodeId(id)) => write!(f, "node {id} does not exist"),
+            SimulationError::UnknownChannel(id) => write!(f, "channel {id} does not exist"),
+            SimulationError::Singular => write!(
+                f,
+                "pressure system is singular, is every subnetwork connected to a pressure boundary?"
+            ),
+            SimulationError::MissingFluidProperty(name) => {
+                write!(f, "fluid property {name} is required")
+            }
+            SimulationError::NotConverged { iterations } => {
+                write!(f, "no convergence within {iterations} iterations")
+            }
+            SimulationError::Cancelled => write!(f, "simulation cancelled"),
+            SimulationError::TooManyValves(count) => {
+                write!(f, "{count} valves have too many state combinations")
+            }
+            SimulationError::InvalidPortModel(id) => {
+                write!(f, "port model of module {id} does not match its nodes")
+            }
+            SimulationError::InvalidTolerance(id) => {
+                write!(f, "tolerance of channel {id} has an invalid distribution")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+impl From<Cancelled> for SimulationError {
+    fn from(_: Cancelled) -> Self {
+        SimulationError::Cancelled
+    }
+}
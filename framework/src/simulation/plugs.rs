@@ -0,0 +1,205 @@
+//! Channels occupied by trains of droplets or plugs. Each plug adds the pressure drop over its
+//! menisci, which after Bretherton (1961) scales as (σ / r) (3 Ca)^(2/3) with the capillary
+//! number Ca = μ U / σ of the continuous phase, and its body replaces continuous phase of the
+//! channel by dispersed phase. The resulting resistance grows with the droplet count and falls
+//! with the flow rate, so the flow field responds to the occupancy of the channels.
+
+use super::{
+    fluid::Fluid,
+    resistance::{channel_resistance, per_length},
+    solver::{iterate, Boundary, FlowSolution, IterationSettings},
+    SimulationError,
+};
+use crate::{
+    base::{channel::Channel, network::Network},
+    progress::ProgressHandle,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Plugs in a channel
+pub struct PlugTrain {
+    /// Number of plugs
+    pub count: usize,
+
+    /// Length of each plug along the channel
+    pub length: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Pressure drop of plugs in a continuous phase
+pub struct PlugModel {
+    /// Factor f of the meniscus pressure drop per plug, f (σ / r) (3 Ca)^(2/3) with r half
+    /// the hydraulic diameter; Bretherton's 3.58 for the front meniscus of a bubble if not set
+    #[serde(default = "PlugModel::bretherton")]
+    pub meniscus_factor: f64,
+
+    /// Viscosity of the dispersed phase, the continuous phase viscosity if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dispersed_viscosity: Option<f64>,
+}
+
+impl Default for PlugModel {
+    fn default() -> Self {
+        PlugModel {
+            meniscus_factor: PlugModel::bretherton(),
+            dispersed_viscosity: None,
+        }
+    }
+}
+
+impl PlugModel {
+    fn bretherton() -> f64 {
+        3.58
+    }
+
+    /// Resistance added by the plug bodies, negative for a dispersed phase thinner than the
+    /// continuous one
+    fn body_resistance(&self, channel: &Channel, fluid: &Fluid, train: &PlugTrain) -> f64 {
+        let viscosity = self.dispersed_viscosity.unwrap_or(fluid.viscosity) - fluid.viscosity;
+        train.count as f64 * train.length * per_length(&channel.shape, viscosity)
+    }
+
+    /// Coefficient c of the meniscus pressure drop c |Q|^(2/3) of the train
+    fn meniscus_coefficient(
+        &self,
+        channel: &Channel,
+        fluid: &Fluid,
+        train: &PlugTrain,
+    ) -> Result<f64, SimulationError> {
+        let tension = fluid
+            .surface_tension
+            .ok_or(SimulationError::MissingFluidProperty("surface_tension"))?;
+        let radius = channel.shape.hydraulic_diameter() / 2.;
+        let capillary_per_flow = fluid.viscosity / (tension * channel.shape.area());
+        Ok(train.count as f64 * self.meniscus_factor * tension / radius
+            * (3. * capillary_per_flow).powf(2. / 3.))
+    }
+}
+
+/// Effective resistance of a channel holding the plug train at the capillary number of the
+/// continuous phase, which must be positive
+pub fn effective_resistance(
+    channel: &Channel,
+    fluid: &Fluid,
+    model: &PlugModel,
+    train: &PlugTrain,
+    capillary: f64,
+) -> Result<f64, SimulationError> {
+    let tension = fluid
+        .surface_tension
+        .ok_or(SimulationError::MissingFluidProperty("surface_tension"))?;
+    let flow = capillary * tension * channel.shape.area() / fluid.viscosity;
+    let menisci = model.meniscus_coefficient(channel, fluid, train)? * flow.powf(-1. / 3.);
+    let body = model.body_resistance(channel, fluid, train);
+    Ok(channel_resistance(channel, fluid)? + body + menisci)
+}
+
+/// Solves the steady-state flow of the continuous phase with the plug trains per channel id,
+/// iterating the flow-dependent resistances until the flows converge
+pub fn solve_with_plugs(
+    network: &Network,
+    fluid: &Fluid,
+    boundaries: &[Boundary],
+    occupancy: &BTreeMap<usize, PlugTrain>,
+    model: &PlugModel,
+    settings: &IterationSettings,
+) -> Result<FlowSolution, SimulationError> {
+    let mut drops = Vec::new();
+    for channel in &network.channels {
+        let Some(train) = occupancy.get(&channel.id).filter(|t| t.count > 0) else {
+            continue;
+        };
+        drops.push((channel.id, model.body_resistance(channel, fluid, train), 1.));
+        drops.push((
+            channel.id,
+            model.meniscus_coefficient(channel, fluid, train)?,
+            2. / 3.,
+        ));
+    }
+    iterate(
+        network,
+        fluid,
+        boundaries,
+        settings,
+        &ProgressHandle::default(),
+        &drops,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{RectangularShape, Shape},
+            network::{Node, NodeId},
+        },
+        simulation::solver::{solve, BoundaryCondition},
+    };
+
+    #[test]
+    fn occupied_channels() {
+        let channel = |id, b| Channel {
+            id,
+            node_a: NodeId(0),
+            node_b: NodeId(b),
+            shape: Shape::Rectangular(RectangularShape {
+                width: 100e-6,
+                height: 50e-6,
+            }),
+            path: None,
+            length: Some(10e-3),
+            layer: 0,
+            metadata: Default::default(),
+        };
+        // Two parallel branches from 0, one of them with plugs
+        let network = Network {
+            nodes: (0..3).map(|i| Node::new(NodeId(i))).collect(),
+            channels: vec![channel(0, 1), channel(1, 2)],
+            ..Default::default()
+        };
+        let boundaries = [(0, 1e4), (1, 0.), (2, 0.)].map(|(node, p)| Boundary {
+            node: NodeId(node),
+            condition: BoundaryCondition::Pressure(p),
+        });
+        let fluid = Fluid::water();
+        let model = PlugModel {
+            dispersed_viscosity: Some(2e-3),
+            ..Default::default()
+        };
+        let settings = IterationSettings::default();
+        let flows = |count| {
+            let train = PlugTrain {
+                count,
+                length: 200e-6,
+            };
+            let occupancy = BTreeMap::from([(1, train)]);
+            solve_with_plugs(&network, &fluid, &boundaries, &occupancy, &model, &settings)
+                .unwrap()
+                .flows
+        };
+        assert_eq!(
+            flows(0),
+            solve(&network, &fluid, &boundaries).unwrap().flows
+        );
+        let (few, many) = (flows(2), flows(10));
+        assert!(many[&1] < few[&1] && few[&1] < few[&0]);
+        assert!((many[&0] - few[&0]).abs() < 1e-9 * few[&0]);
+
+        // The iterated flow balances the pressure drop at its capillary number
+        let train = PlugTrain {
+            count: 10,
+            length: 200e-6,
+        };
+        let capillary = fluid.viscosity * many[&1]
+            / (network.channels[1].shape.area() * fluid.surface_tension.unwrap());
+        let resistance =
+            effective_resistance(&network.channels[1], &fluid, &model, &train, capillary).unwrap();
+        assert!((resistance * many[&1] / 1e4 - 1.).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,264 @@
+//! Model order reduction of subnetworks. Eliminating the interior pressures of the linear
+//! pressure system leaves the Schur complement G_pp - G_pi G_ii⁻¹ G_ip of its conductance
+//! matrix, which relates the pressures at the ports to the flows into them. A module carrying
+//! it as its port model is solved as a black box, so a repeated unit is condensed once and
+//! instantiated many times without growing the outer system.
+//!
+//! Port models are resistive only. The solvers compute steady states, and channels carry no
+//! compliance to condense, so a compliance matrix is out of scope until a transient solver
+//! models wall and fluid compliance.
+
+use super::{fluid::Fluid, solver::Lu, SimulationError};
+use crate::base::{
+    hierarchy::PortModel,
+    network::{Network, NodeId},
+};
+use std::collections::BTreeMap;
+
+/// Condenses the flattened network into the port model between the ports, in their order.
+/// The reduction is linear, so non-Newtonian fluids are condensed at their base viscosity;
+/// parts of the network not connected to any port are ignored.
+pub fn condense(
+    network: &Network,
+    fluid: &Fluid,
+    ports: &[NodeId],
+) -> Result<PortModel, SimulationError> {
+    let network = network.flattened();
+    let index: BTreeMap<NodeId, usize> = (network.nodes.iter().enumerate())
+        .map(|(i, n)| (n.id, i))
+        .collect();
+    let lookup = |id: &NodeId| {
+        index
+            .get(id)
+            .copied()
+            .ok_or(SimulationError::UnknownNode(*id))
+    };
+
+    // Conductance matrix of all nodes
+    let n = network.nodes.len();
+    let mut matrix = vec![vec![0.; n]; n];
+    for channel in &network.channels {
+        let resistance = network
+            .cached_resistance(channel, fluid.viscosity)
+            .ok_or(SimulationError::MissingLength(channel.id))?;
+        let (a, b) = (lookup(&channel.node_a)?, lookup(&channel.node_b)?);
+        for (i, j) in [(a, b), (b, a)] {
+            matrix[i][i] += 1. / resistance;
+            matrix[i][j] -= 1. / resistance;
+        }
+    }
+    for module in &network.modules {
+        let Some(model) = &module.model else {
+            continue;
+        };
+        let nodes = module
+            .nodes
+            .iter()
+            .map(lookup)
+            .collect::<Result<Vec<_>, _>>()?;
+        if model.conductance.len() != nodes.len() {
+            return Err(SimulationError::InvalidPortModel(module.id));
+        }
+        for (i, row) in nodes.iter().zip(&model.conductance) {
+            if row.len() != nodes.len() {
+                return Err(SimulationError::InvalidPortModel(module.id));
+            }
+            for (j, g) in nodes.iter().zip(row) {
+                matrix[*i][*j] += g;
+            }
+        }
+    }
+
+    // Interior nodes connected to a port
+    let ports = ports.iter().map(lookup).collect::<Result<Vec<_>, _>>()?;
+    let mut reached = vec![false; n];
+    let mut stack = ports.clone();
+    while let Some(i) = stack.pop() {
+        if std::mem::replace(&mut reached[i], true) {
+            continue;
+        }
+        stack.extend((0..n).filter(|j| !reached[*j] && matrix[i][*j] != 0.));
+    }
+    let interior: Vec<usize> = (0..n)
+        .filter(|i| reached[*i] && !ports.contains(i))
+        .collect();
+
+    let mut conductance: Vec<Vec<f64>> = (ports.iter())
+        .map(|i| ports.iter().map(|j| matrix[*i][*j]).collect())
+        .collect();
+    if !interior.is_empty() {
+        let block = (interior.iter())
+            .map(|i| interior.iter().map(|j| matrix[*i][*j]).collect())
+            .collect();
+        let lu = Lu::new(block).ok_or(SimulationError::Singular)?;
+        for (k, port) in ports.iter().enumerate() {
+            let column: Vec<f64> = interior.iter().map(|i| matrix[*i][*port]).collect();
+            let eliminated = lu.solve(&column);
+            for (row, i) in conductance.iter_mut().zip(&ports) {
+                let coupling: f64 = (interior.iter().zip(&eliminated))
+                    .map(|(j, x)| matrix[*i][*j] * x)
+                    .sum();
+                row[k] -= coupling;
+            }
+        }
+    }
+    Ok(PortModel { conductance })
+}
+
+impl PortModel {
+    /// Inverse of the conductance matrix with the last port as pressure reference: the
+    /// pressures of the other ports relative to it per unit flow into each of them, leaving
+    /// at the last port; none if the ports aren't all connected
+    pub fn resistance(&self) -> Option<Vec<Vec<f64>>> {
+        let n = self.conductance.len().checked_sub(1)?;
+        let reduced = (self.conductance[..n].iter())
+            .map(|row| row[..n].to_vec())
+            .collect();
+        let lu = Lu::new(reduced)?;
+        let columns: Vec<Vec<f64>> = (0..n)
+            .map(|k| {
+                lu.solve(
+                    &(0..n)
+                        .map(|i| if i == k { 1. } else { 0. })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect();
+        Some(
+            (0..n)
+                .map(|i| columns.iter().map(|c| c[i]).collect())
+                .collect(),
+        )
+    }
+}
+
+impl Network {
+    /// Condenses the implementation of the module into its port model, with the ports of the
+    /// implementation mapped to the module's nodes. Returns false if there is no such module
+    /// or it has no implementation.
+    pub fn condense_module(
+        &mut self,
+        module_id: usize,
+        fluid: &Fluid,
+    ) -> Result<bool, SimulationError> {
+        let Some(module) = self.modules.iter_mut().find(|m| m.id == module_id) else {
+            return Ok(false);
+        };
+        let Some(subcircuit) = &module.implementation else {
+            return Ok(false);
+        };
+        let ports = (module.nodes.iter())
+            .map(|outer| {
+                let mapping = subcircuit.ports.iter().find(|p| p.outer == *outer);
+                mapping
+                    .map(|p| p.inner)
+                    .ok_or(SimulationError::UnknownNode(*outer))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        module.model = Some(condense(&subcircuit.network, fluid, &ports)?);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{Channel, RectangularShape, Shape},
+            hierarchy::{PortMapping, Subcircuit},
+            network::{Module, Node},
+            primitives::{Dimensions, Point},
+        },
+        simulation::{
+            resistance::channel_resistance,
+            solver::{solve, Boundary, BoundaryCondition},
+        },
+    };
+
+    #[test]
+    fn condensed_modules() {
+        let channel = |id, a, b, length| Channel {
+            id,
+            node_a: NodeId(a),
+            node_b: NodeId(b),
+            shape: Shape::Rectangular(RectangularShape {
+                width: 100e-6,
+                height: 50e-6,
+            }),
+            path: None,
+            length: Some(length),
+            layer: 0,
+            metadata: Default::default(),
+        };
+        // Three ports joined at the interior node 3
+        let unit = Network {
+            nodes: (0..4).map(|i| Node::new(NodeId(i))).collect(),
+            channels: vec![
+                channel(0, 0, 3, 10e-3),
+                channel(1, 1, 3, 20e-3),
+                channel(2, 2, 3, 5e-3),
+            ],
+            ..Default::default()
+        };
+        let fluid = Fluid::water();
+        let ports = [0, 1, 2].map(NodeId);
+        let model = condense(&unit, &fluid, &ports).unwrap();
+        for (i, row) in model.conductance.iter().enumerate() {
+            let scale = row[i];
+            assert!(row.iter().sum::<f64>().abs() < 1e-9 * scale);
+            assert!((0..3).all(|j| (row[j] - model.conductance[j][i]).abs() < 1e-9 * scale));
+        }
+        let resistance = |id: usize| channel_resistance(&unit.channels[id], &fluid).unwrap();
+        let r = model.resistance().unwrap();
+        assert!((r[0][0] / (resistance(0) + resistance(2)) - 1.).abs() < 1e-9);
+        assert!((r[0][1] / resistance(2) - 1.).abs() < 1e-9);
+
+        // Two units sharing node 2 of the outer network
+        let mut network = Network {
+            nodes: (0..5).map(|i| Node::new(NodeId(i))).collect(),
+            ..Default::default()
+        };
+        for (id, nodes) in [(0, [0, 1, 2]), (1, [2, 3, 4])] {
+            let nodes = nodes.map(NodeId);
+            network.modules.push(Module {
+                id,
+                position: Point([0., 0.]),
+                size: Dimensions([1e-3, 1e-3]),
+                nodes: nodes.to_vec(),
+                implementation: Some(Subcircuit {
+                    network: Box::new(unit.clone()),
+                    ports: (nodes.iter().zip(ports))
+                        .map(|(outer, inner)| PortMapping {
+                            outer: *outer,
+                            inner,
+                        })
+                        .collect(),
+                }),
+                model: None,
+                footprint: None,
+                orientation: Default::default(),
+                kind: Default::default(),
+                metadata: Default::default(),
+            });
+        }
+        let boundaries = [(0, 1e4), (1, 0.), (3, 5e3), (4, 0.)].map(|(node, p)| Boundary {
+            node: NodeId(node),
+            condition: BoundaryCondition::Pressure(p),
+        });
+        let flat = solve(&network.flattened(), &fluid, &boundaries).unwrap();
+        assert!(network.condense_module(0, &fluid).unwrap());
+        assert!(network.condense_module(1, &fluid).unwrap());
+        assert!(!network.condense_module(2, &fluid).unwrap());
+        let condensed = solve(&network, &fluid, &boundaries).unwrap();
+        let p = condensed.pressures[&NodeId(2)];
+        assert!((p / flat.pressures[&NodeId(2)] - 1.).abs() < 1e-9, "{p}");
+        assert!(condensed.flows.is_empty());
+
+        network.modules[0].nodes.pop();
+        assert_eq!(
+            solve(&network, &fluid, &boundaries),
+            Err(SimulationError::InvalidPortModel(0))
+        );
+    }
+}
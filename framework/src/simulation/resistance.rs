@@ -0,0 +1,84 @@
+//! Hydraulic resistances of fully developed laminar (Poiseuille) flow
+
+use super::{fluid::Fluid, SimulationError};
+use crate::base::channel::{Channel, CylindricalShape, RectangularShape, Shape};
+use std::f64::consts::PI;
+
+/// Resistance per unit length of a rectangular cross-section, using the common approximation
+/// R' = 12 μ / (w h³ (1 - 0.63 h / w)) with h the smaller side
+pub fn rectangular(shape: &RectangularShape, viscosity: f64) -> f64 {
+    let (w, h) = if shape.height <= shape.width {
+        (shape.width, shape.height)
+    } else {
+        (shape.height, shape.width)
+    };
+    12. * viscosity / (w * h.powi(3) * (1. - 0.63 * h / w))
+}
+
+/// Resistance per unit length of a circular cross-section, R' = 8 μ / (π r⁴)
+pub fn cylindrical(shape: &CylindricalShape, viscosity: f64) -> f64 {
+    8. * viscosity / (PI * shape.radius.powi(4))
+}
+
+/// Resistance per unit length of any cross-section
+pub fn per_length(shape: &Shape, viscosity: f64) -> f64 {
+    match shape {
+        Shape::Rectangular(shape) => rectangular(shape, viscosity),
+        Shape::Cylindrical(shape) => cylindrical(shape, viscosity),
+    }
+}
+
+/// Hydraulic resistance of a straight channel of the cross-section and length
+pub fn resistance_for_length(shape: &Shape, viscosity: f64, length: f64) -> f64 {
+    per_length(shape, viscosity) * length
+}
+
+/// Length a channel of the cross-section needs for the hydraulic resistance, e.g., the
+/// centerline length a meander has to reach
+pub fn length_for_resistance(shape: &Shape, viscosity: f64, resistance: f64) -> f64 {
+    resistance / per_length(shape, viscosity)
+}
+
+/// Characteristic wall shear rate of Newtonian flow at the given flow rate, 4 Q / (π r³) for
+/// circular and 6 Q / (w h²) for rectangular cross-sections
+pub fn wall_shear_rate(shape: &Shape, flow: f64) -> f64 {
+    match shape {
+        Shape::Rectangular(shape) => {
+            let (w, h) = if shape.height <= shape.width {
+                (shape.width, shape.height)
+            } else {
+                (shape.height, shape.width)
+            };
+            6. * flow.abs() / (w * h * h)
+        }
+        Shape::Cylindrical(shape) => 4. * flow.abs() / (PI * shape.radius.powi(3)),
+    }
+}
+
+/// Hydraulic resistance of a channel
+pub fn channel_resistance(channel: &Channel, fluid: &Fluid) -> Result<f64, SimulationError> {
+    let length = channel
+        .length()
+        .ok_or(SimulationError::MissingLength(channel.id))?;
+    Ok(resistance_for_length(
+        &channel.shape,
+        fluid.viscosity,
+        length,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn length_conversion() {
+        let shape = Shape::Rectangular(RectangularShape {
+            width: 200e-6,
+            height: 50e-6,
+        });
+        let resistance = resistance_for_length(&shape, 1e-3, 0.02);
+        assert!((length_for_resistance(&shape, 1e-3, resistance) - 0.02).abs() < 1e-15);
+        assert_eq!(length_for_resistance(&shape, 1e-3, 2. * resistance), 0.04);
+    }
+}
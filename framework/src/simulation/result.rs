@@ -0,0 +1,201 @@
+//! Snapshot format of simulation results for data analysis tools. Results convert to columnar
+//! tables, one column per quantity, which map directly onto data frames, e.g., pandas or Arrow
+//! tables built from a dictionary of lists, and export as CSV.
+
+use super::solver::FlowSolution;
+use crate::base::network::NodeId;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Write};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Pressures and flows at a point in time
+pub struct ResultStep {
+    pub time: f64,
+
+    /// Pressure per node id
+    pub pressures: BTreeMap<NodeId, f64>,
+
+    /// Flow rate per channel id, positive from node_a to node_b
+    pub flows: BTreeMap<usize, f64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Outcome of a simulation: the steady or final state and, for transient simulations, the
+/// states over time
+pub struct SimulationResult {
+    /// Pressure per node id
+    pub pressures: BTreeMap<NodeId, f64>,
+
+    /// Flow rate per channel id, positive from node_a to node_b
+    pub flows: BTreeMap<usize, f64>,
+
+    /// Mean solute concentration per channel id, if transport was simulated
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub concentrations: BTreeMap<usize, f64>,
+
+    /// States in order of increasing time
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time_series: Vec<ResultStep>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Node quantities as columns of equal length
+pub struct NodeTable {
+    /// Time of each row, empty for steady states
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time: Vec<f64>,
+
+    pub node: Vec<usize>,
+    pub pressure: Vec<f64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Channel quantities as columns of equal length
+pub struct ChannelTable {
+    /// Time of each row, empty for steady states
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub time: Vec<f64>,
+
+    pub channel: Vec<usize>,
+    pub flow: Vec<f64>,
+}
+
+impl From<FlowSolution> for SimulationResult {
+    fn from(solution: FlowSolution) -> Self {
+        SimulationResult {
+            pressures: solution.pressures,
+            flows: solution.flows,
+            concentrations: BTreeMap::new(),
+            time_series: Vec::new(),
+        }
+    }
+}
+
+impl SimulationResult {
+    /// Appends the state at a time to the time series and makes it the final state
+    pub fn push_step(&mut self, time: f64, solution: &FlowSolution) {
+        self.pressures = solution.pressures.clone();
+        self.flows = solution.flows.clone();
+        self.time_series.push(ResultStep {
+            time,
+            pressures: solution.pressures.clone(),
+            flows: solution.flows.clone(),
+        });
+    }
+
+    /// Steady or final node pressures
+    pub fn node_table(&self) -> NodeTable {
+        NodeTable {
+            time: Vec::new(),
+            node: self.pressures.keys().map(|NodeId(id)| *id).collect(),
+            pressure: self.pressures.values().copied().collect(),
+        }
+    }
+
+    /// Steady or final channel flows
+    pub fn channel_table(&self) -> ChannelTable {
+        ChannelTable {
+            time: Vec::new(),
+            channel: self.flows.keys().copied().collect(),
+            flow: self.flows.values().copied().collect(),
+        }
+    }
+
+    /// Node pressures of all steps in long format, one row per step and node
+    pub fn node_history(&self) -> NodeTable {
+        let mut table = NodeTable::default();
+        for step in &self.time_series {
+            for (NodeId(id), pressure) in &step.pressures {
+                table.time.push(step.time);
+                table.node.push(*id);
+                table.pressure.push(*pressure);
+            }
+        }
+        table
+    }
+
+    /// Channel flows of all steps in long format, one row per step and channel
+    pub fn channel_history(&self) -> ChannelTable {
+        let mut table = ChannelTable::default();
+        for step in &self.time_series {
+            for (id, flow) in &step.flows {
+                table.time.push(step.time);
+                table.channel.push(*id);
+                table.flow.push(*flow);
+            }
+        }
+        table
+    }
+}
+
+/// CSV with a header row; floats are written in their shortest exact representation
+fn csv(time: &[f64], id_name: &str, ids: &[usize], value_name: &str, values: &[f64]) -> String {
+    let mut csv = String::new();
+    if time.is_empty() {
+        writeln!(csv, "{id_name},{value_name}").unwrap();
+        for (id, value) in ids.iter().zip(values) {
+            writeln!(csv, "{id},{value:?}").unwrap();
+        }
+    } else {
+        writeln!(csv, "time,{id_name},{value_name}").unwrap();
+        for ((time, id), value) in time.iter().zip(ids).zip(values) {
+            writeln!(csv, "{time:?},{id},{value:?}").unwrap();
+        }
+    }
+    csv
+}
+
+impl NodeTable {
+    pub fn to_csv(&self) -> String {
+        csv(&self.time, "node", &self.node, "pressure", &self.pressure)
+    }
+}
+
+impl ChannelTable {
+    pub fn to_csv(&self) -> String {
+        csv(&self.time, "channel", &self.channel, "flow", &self.flow)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tables() {
+        let solution = |scale: f64| FlowSolution {
+            pressures: BTreeMap::from([(NodeId(0), 2. * scale), (NodeId(1), 0.)]),
+            flows: BTreeMap::from([(3, 0.5 * scale)]),
+        };
+        let mut result = SimulationResult::from(solution(1.));
+        assert_eq!(
+            result.node_table().to_csv(),
+            "node,pressure\n0,2.0\n1,0.0\n"
+        );
+        assert_eq!(result.channel_table().to_csv(), "channel,flow\n3,0.5\n");
+        let json = serde_json::to_value(&result).unwrap();
+        assert!(json.get("time_series").is_none());
+
+        result.push_step(0.1, &solution(2.));
+        result.push_step(0.2, &solution(3.));
+        assert_eq!(result.flows[&3], 1.5);
+        assert_eq!(
+            result.channel_history().to_csv(),
+            "time,channel,flow\n0.1,3,1.0\n0.2,3,1.5\n"
+        );
+        let nodes = result.node_history();
+        assert_eq!(nodes.time, [0.1, 0.1, 0.2, 0.2]);
+        assert_eq!(nodes.node, [0, 1, 0, 1]);
+
+        let json = serde_json::to_string(&result).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SimulationResult>(&json).unwrap(),
+            result
+        );
+    }
+}
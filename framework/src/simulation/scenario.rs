@@ -0,0 +1,228 @@
+//! Valve scenarios: the flow through a network for combinations of open and closed valve
+//! modules, e.g., to verify that a multiplexer addresses each outlet. A closed valve removes
+//! the channels passing through its footprint; parts of the network then cut off from every
+//! pressure boundary are still and left out of the solve.
+
+use super::{
+    fluid::Fluid,
+    solver::{solve, Boundary, BoundaryCondition, FlowSolution},
+    SimulationError,
+};
+use crate::{
+    base::network::{ModuleKind, Network, NodeId},
+    diagnostic::Diagnostic,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Largest number of valves whose state combinations are enumerated
+pub const MAX_ENUMERATED_VALVES: usize = 16;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Valve states to evaluate, each given as open state per valve module id
+pub enum ValveStates {
+    /// Every combination, counting in binary with the valve of the lowest id as the lowest
+    /// digit and 1 for closed, so that all valves are open in the first state
+    All,
+
+    /// The states in order; valves not listed are open
+    Sequence(Vec<BTreeMap<usize, bool>>),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Flow through the network in one valve state
+pub struct ValveStateResult {
+    /// Open state per valve module id
+    pub valves: BTreeMap<usize, bool>,
+
+    /// Solution with zero flow in closed and still channels and without pressures at still
+    /// nodes, none if the state can't be solved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub solution: Option<FlowSolution>,
+
+    /// Reason the state can't be solved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<Diagnostic>,
+}
+
+/// Solves the network with the closed channels removed
+fn solve_state(
+    network: &Network,
+    fluid: &Fluid,
+    boundaries: &[Boundary],
+    closed: &BTreeSet<usize>,
+) -> Result<FlowSolution, SimulationError> {
+    let mut open = network.clone();
+    open.channels.retain(|c| !closed.contains(&c.id));
+
+    // Nodes connected to a pressure boundary through open channels
+    let mut reached: BTreeSet<NodeId> = (boundaries.iter())
+        .filter(|b| matches!(b.condition, BoundaryCondition::Pressure(_)))
+        .map(|b| b.node)
+        .collect();
+    let mut frontier: Vec<NodeId> = reached.iter().copied().collect();
+    while let Some(node) = frontier.pop() {
+        for channel in &open.channels {
+            let other = match (channel.node_a == node, channel.node_b == node) {
+                (true, _) => channel.node_b,
+                (_, true) => channel.node_a,
+                _ => continue,
+            };
+            if reached.insert(other) {
+                frontier.push(other);
+            }
+        }
+    }
+    let mut active = Vec::new();
+    for boundary in boundaries {
+        match (reached.contains(&boundary.node), boundary.condition) {
+            (true, _) => active.push(*boundary),
+            // Flow into a sealed part of the network
+            (false, BoundaryCondition::Flow(q)) if q != 0. => {
+                return Err(SimulationError::Singular)
+            }
+            _ => {}
+        }
+    }
+    open.nodes.retain(|n| reached.contains(&n.id));
+    open.channels
+        .retain(|c| reached.contains(&c.node_a) && reached.contains(&c.node_b));
+
+    let mut solution = solve(&open, fluid, &active)?;
+    for channel in &network.channels {
+        solution.flows.entry(channel.id).or_insert(0.);
+    }
+    Ok(solution)
+}
+
+/// Solves the network in each of the valve states. Fails only if more valves than
+/// [MAX_ENUMERATED_VALVES] are to be combined; states that can't be solved report the reason.
+pub fn evaluate_valve_states(
+    network: &Network,
+    fluid: &Fluid,
+    boundaries: &[Boundary],
+    states: &ValveStates,
+) -> Result<Vec<ValveStateResult>, SimulationError> {
+    let valves: BTreeMap<usize, Vec<usize>> = (network.modules.iter())
+        .filter(|m| m.kind == ModuleKind::Valve)
+        .map(|m| (m.id, network.pinched_channels(m.id)))
+        .collect();
+    let states = match states {
+        ValveStates::All => {
+            if valves.len() > MAX_ENUMERATED_VALVES {
+                return Err(SimulationError::TooManyValves(valves.len()));
+            }
+            (0..1usize << valves.len())
+                .map(|state| {
+                    (valves.keys().enumerate())
+                        .map(|(bit, id)| (*id, (state >> bit) & 1 == 0))
+                        .collect()
+                })
+                .collect()
+        }
+        ValveStates::Sequence(states) => (states.iter())
+            .map(|state| {
+                (valves.keys())
+                    .map(|id| (*id, state.get(id).copied().unwrap_or(true)))
+                    .collect()
+            })
+            .collect::<Vec<BTreeMap<usize, bool>>>(),
+    };
+
+    Ok(states
+        .into_iter()
+        .map(|state| {
+            let closed = (state.iter())
+                .filter(|(_, open)| !**open)
+                .flat_map(|(id, _)| valves[id].iter().copied())
+                .collect();
+            let (solution, error) = match solve_state(network, fluid, boundaries, &closed) {
+                Ok(solution) => (Some(solution), None),
+                Err(error) => (None, Some(Diagnostic::from(&error))),
+            };
+            ValveStateResult {
+                valves: state,
+                solution,
+                error,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::{
+        channel::{Channel, ChannelPath, LineSegment, PathPiece, RectangularShape, Shape},
+        network::{Module, Node},
+        primitives::{Dimensions, Point},
+    };
+
+    #[test]
+    fn valve_states() {
+        // Inlet 0 feeds node 1, which branches to the outlets 2 and 3, each behind a valve
+        let positions = [[0., 0.], [1e-3, 0.], [2e-3, 1e-3], [2e-3, -1e-3]].map(Point);
+        let mut network = Network {
+            nodes: (0..4).map(|i| Node::at(NodeId(i), positions[i])).collect(),
+            ..Default::default()
+        };
+        for (id, (a, b)) in [(0, 1), (1, 2), (1, 3)].into_iter().enumerate() {
+            let mut path = ChannelPath::new();
+            path.add(PathPiece::LineSegment(LineSegment {
+                start: positions[a],
+                end: positions[b],
+            }));
+            network.channels.push(Channel {
+                id,
+                node_a: NodeId(a),
+                node_b: NodeId(b),
+                shape: Shape::Rectangular(RectangularShape {
+                    width: 100e-6,
+                    height: 50e-6,
+                }),
+                path: Some(path),
+                length: None,
+                layer: 0,
+                metadata: Default::default(),
+            });
+        }
+        for (id, y) in [(10, 0.5e-3), (11, -0.5e-3)] {
+            network.modules.push(Module {
+                id,
+                position: Point([1.4e-3, y - 1e-4]),
+                size: Dimensions([2e-4, 2e-4]),
+                nodes: Vec::new(),
+                implementation: None,
+                model: None,
+                footprint: None,
+                orientation: Default::default(),
+                kind: ModuleKind::Valve,
+                metadata: Default::default(),
+            });
+        }
+        assert_eq!(network.pinched_channels(10), [1]);
+
+        let boundaries = [(0, 1e4), (2, 0.), (3, 0.)].map(|(node, p)| Boundary {
+            node: NodeId(node),
+            condition: BoundaryCondition::Pressure(p),
+        });
+        let fluid = Fluid::water();
+        let results =
+            evaluate_valve_states(&network, &fluid, &boundaries, &ValveStates::All).unwrap();
+        assert_eq!(results.len(), 4);
+        let flows = |i: usize| results[i].solution.as_ref().unwrap().flows.clone();
+        assert_eq!(results[1].valves, BTreeMap::from([(10, false), (11, true)]));
+        assert!(flows(0)[&1] > 0. && flows(0)[&2] > 0.);
+        assert_eq!(flows(1)[&1], 0.);
+        assert!((flows(1)[&0] - flows(1)[&2]).abs() < 1e-9 * flows(1)[&0]);
+        assert!(flows(3).values().all(|q| q.abs() < 1e-20));
+
+        let sequence = ValveStates::Sequence(vec![BTreeMap::from([(11, false)])]);
+        let results = evaluate_valve_states(&network, &fluid, &boundaries, &sequence).unwrap();
+        assert_eq!(results[0].valves, BTreeMap::from([(10, true), (11, false)]));
+        assert!(results[0].error.is_none());
+    }
+}
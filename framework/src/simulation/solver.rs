@@ -0,0 +1,722 @@
+use super::{
+    fluid::{Fluid, Rheology},
+    resistance::wall_shear_rate,
+    sparse::{conjugate_gradient, CsrMatrix},
+    SimulationError,
+};
+use crate::{
+    base::{
+        hierarchy::PortModel,
+        network::{Network, NodeId},
+    },
+    progress::ProgressHandle,
+    trace::{event, span},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Condition imposed on a node
+pub enum BoundaryCondition {
+    /// Fixed pressure at the node
+    Pressure(f64),
+
+    /// Flow rate injected into the network at the node, negative for withdrawal
+    Flow(f64),
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Boundary condition at a node, e.g., a pressure pump or syringe pump
+pub struct Boundary {
+    /// Node the condition applies to
+    pub node: NodeId,
+
+    /// Imposed condition
+    pub condition: BoundaryCondition,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Steady-state flow through a network
+pub struct FlowSolution {
+    /// Pressure per node id
+    pub pressures: BTreeMap<NodeId, f64>,
+
+    /// Flow rate per channel id, positive from node_a to node_b
+    pub flows: BTreeMap<usize, f64>,
+}
+
+impl FlowSolution {
+    /// Reverses the channels with negative flow, negating their flows, so that all flows of
+    /// the solution are positive and channels point downstream, e.g., for droplet tracking.
+    /// Returns the ids of the reversed channels.
+    pub fn orient_channels(&mut self, network: &mut Network) -> Vec<usize> {
+        let reversed: Vec<usize> = (self.flows.iter())
+            .filter(|(_, q)| **q < 0.)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &reversed {
+            if network.reverse_channel(*id) {
+                self.flows.entry(*id).and_modify(|q| *q = -*q);
+            }
+        }
+        reversed
+    }
+}
+
+/// Largest number of unknown pressures solved by dense elimination; larger systems are solved
+/// sparsely
+pub const DENSE_LIMIT: usize = 400;
+
+/// Residual of the conjugate gradient solution relative to the right-hand side
+const CG_TOLERANCE: f64 = 1e-12;
+
+/// Nodal pressure equations of a network: unknown pressures are numbered consecutively, fixed
+/// ones move to the right-hand side
+#[derive(Debug, Clone)]
+pub(crate) struct PressureSystem {
+    nodes: Vec<NodeId>,
+    fixed: Vec<Option<f64>>,
+    injected: Vec<f64>,
+
+    /// Row of every unknown node, usize::MAX for fixed ones
+    row: Vec<usize>,
+
+    /// Id and node indices of every channel
+    pub(crate) channels: Vec<(usize, usize, usize)>,
+
+    /// Node indices and conductance matrix of every module with a port model
+    models: Vec<(Vec<usize>, PortModel)>,
+}
+
+impl PressureSystem {
+    pub(crate) fn new(
+        network: &Network,
+        boundaries: &[Boundary],
+    ) -> Result<PressureSystem, SimulationError> {
+        let index: BTreeMap<NodeId, usize> = network
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.id, i))
+            .collect();
+        let lookup = |id: NodeId| {
+            index
+                .get(&id)
+                .copied()
+                .ok_or(SimulationError::UnknownNode(id))
+        };
+
+        let n = network.nodes.len();
+        let mut fixed: Vec<Option<f64>> = vec![None; n];
+        let mut injected = vec![0.; n];
+        for boundary in boundaries {
+            let i = lookup(boundary.node)?;
+            match boundary.condition {
+                BoundaryCondition::Pressure(p) => fixed[i] = Some(p),
+                BoundaryCondition::Flow(q) => injected[i] += q,
+            }
+        }
+        let mut row = vec![usize::MAX; n];
+        for (r, i) in (0..n).filter(|i| fixed[*i].is_none()).enumerate() {
+            row[i] = r;
+        }
+        let channels = network
+            .channels
+            .iter()
+            .map(|c| Ok((c.id, lookup(c.node_a)?, lookup(c.node_b)?)))
+            .collect::<Result<_, SimulationError>>()?;
+        let mut models = Vec::new();
+        for module in &network.modules {
+            let Some(model) = &module.model else {
+                continue;
+            };
+            let n = module.nodes.len();
+            if model.conductance.len() != n || model.conductance.iter().any(|r| r.len() != n) {
+                return Err(SimulationError::InvalidPortModel(module.id));
+            }
+            let nodes = module.nodes.iter().map(|id| lookup(*id));
+            models.push((nodes.collect::<Result<_, _>>()?, model.clone()));
+        }
+        Ok(PressureSystem {
+            nodes: network.nodes.iter().map(|n| n.id).collect(),
+            fixed,
+            injected,
+            row,
+            channels,
+            models,
+        })
+    }
+
+    /// Number of unknown pressures
+    pub(crate) fn unknowns(&self) -> usize {
+        self.fixed.iter().filter(|p| p.is_none()).count()
+    }
+
+    /// Rows and right-hand side contributions of a conductance between two nodes: the unknown
+    /// ends with their signs and the terms of fixed ends
+    pub(crate) fn stamp(&self, a: usize, b: usize) -> Vec<(usize, f64, f64)> {
+        [(a, b, 1.), (b, a, -1.)]
+            .into_iter()
+            .filter(|(i, _, _)| self.fixed[*i].is_none())
+            .map(|(i, j, sign)| (self.row[i], sign, self.fixed[j].unwrap_or(0.)))
+            .collect()
+    }
+
+    /// Terms of the port models as row, column, and value; terms without column belong to the
+    /// right-hand side
+    fn model_terms(&self) -> Vec<(usize, Option<usize>, f64)> {
+        let mut terms = Vec::new();
+        for (nodes, model) in &self.models {
+            for (i, row) in nodes.iter().zip(&model.conductance) {
+                if self.fixed[*i].is_some() {
+                    continue;
+                }
+                for (j, g) in nodes.iter().zip(row) {
+                    match self.fixed[*j] {
+                        Some(p) => terms.push((self.row[*i], None, -g * p)),
+                        None => terms.push((self.row[*i], Some(self.row[*j]), *g)),
+                    }
+                }
+            }
+        }
+        terms
+    }
+
+    /// Conductance matrix and right-hand side for the conductance of every channel and the
+    /// port models
+    pub(crate) fn assemble(&self, conductances: &[f64]) -> (Vec<Vec<f64>>, Vec<f64>) {
+        let m = self.unknowns();
+        let mut matrix = vec![vec![0.; m]; m];
+        let mut rhs: Vec<f64> = (0..self.nodes.len())
+            .filter(|i| self.fixed[*i].is_none())
+            .map(|i| self.injected[i])
+            .collect();
+        for ((_, a, b), g) in self.channels.iter().zip(conductances) {
+            for (i, j) in [(*a, *b), (*b, *a)] {
+                if self.fixed[i].is_some() {
+                    continue;
+                }
+                matrix[self.row[i]][self.row[i]] += g;
+                match self.fixed[j] {
+                    Some(p) => rhs[self.row[i]] += g * p,
+                    None => matrix[self.row[i]][self.row[j]] -= g,
+                }
+            }
+        }
+        for (r, c, value) in self.model_terms() {
+            match c {
+                Some(c) => matrix[r][c] += value,
+                None => rhs[r] += value,
+            }
+        }
+        (matrix, rhs)
+    }
+
+    /// Conductance matrix in sparse form; see [PressureSystem::assemble]
+    pub(crate) fn assemble_sparse(&self, conductances: &[f64]) -> (CsrMatrix, Vec<f64>) {
+        let mut entries = Vec::with_capacity(4 * self.channels.len());
+        let mut rhs: Vec<f64> = (0..self.nodes.len())
+            .filter(|i| self.fixed[*i].is_none())
+            .map(|i| self.injected[i])
+            .collect();
+        for ((_, a, b), g) in self.channels.iter().zip(conductances) {
+            for (i, j) in [(*a, *b), (*b, *a)] {
+                if self.fixed[i].is_some() {
+                    continue;
+                }
+                entries.push((self.row[i], self.row[i], *g));
+                match self.fixed[j] {
+                    Some(p) => rhs[self.row[i]] += g * p,
+                    None => entries.push((self.row[i], self.row[j], -g)),
+                }
+            }
+        }
+        for (r, c, value) in self.model_terms() {
+            match c {
+                Some(c) => entries.push((r, c, value)),
+                None => rhs[r] += value,
+            }
+        }
+        (CsrMatrix::from_triplets(self.unknowns(), entries), rhs)
+    }
+
+    /// Whether every node is connected to a fixed pressure through channels or port models of
+    /// nonzero conductance, i.e., whether the system is regular
+    pub(crate) fn is_referenced(&self, conductances: &[f64]) -> bool {
+        let n = self.nodes.len();
+        let mut adjacent = vec![Vec::new(); n];
+        for ((_, a, b), g) in self.channels.iter().zip(conductances) {
+            if *g > 0. && g.is_finite() {
+                adjacent[*a].push(*b);
+                adjacent[*b].push(*a);
+            }
+        }
+        for (nodes, model) in &self.models {
+            for (i, row) in nodes.iter().zip(&model.conductance) {
+                for (j, g) in nodes.iter().zip(row) {
+                    if i != j && *g != 0. {
+                        adjacent[*i].push(*j);
+                    }
+                }
+            }
+        }
+        let mut reached: Vec<bool> = self.fixed.iter().map(Option::is_some).collect();
+        let mut stack: Vec<usize> = (0..n).filter(|i| reached[*i]).collect();
+        while let Some(i) = stack.pop() {
+            for j in &adjacent[i] {
+                if !reached[*j] {
+                    reached[*j] = true;
+                    stack.push(*j);
+                }
+            }
+        }
+        reached.into_iter().all(|r| r)
+    }
+
+    /// Unknown pressures, by dense elimination up to [DENSE_LIMIT] unknowns and by conjugate
+    /// gradients beyond
+    pub(crate) fn solve(&self, conductances: &[f64]) -> Result<Vec<f64>, SimulationError> {
+        let m = self.unknowns();
+        if m <= DENSE_LIMIT {
+            let (matrix, rhs) = self.assemble(conductances);
+            return gauss(matrix, rhs).ok_or(SimulationError::Singular);
+        }
+        if !self.is_referenced(conductances) {
+            return Err(SimulationError::Singular);
+        }
+        let (matrix, rhs) = self.assemble_sparse(conductances);
+        let max_iterations = 10 * m;
+        let (unknowns, iterations) =
+            conjugate_gradient(&matrix, &rhs, CG_TOLERANCE, max_iterations).ok_or(
+                SimulationError::NotConverged {
+                    iterations: max_iterations,
+                },
+            )?;
+        event!(
+            Debug,
+            "conjugate gradients converged in {iterations} iterations"
+        );
+        Ok(unknowns)
+    }
+
+    /// Pressures and flows for the unknown pressures
+    pub(crate) fn solution(&self, unknowns: &[f64], conductances: &[f64]) -> FlowSolution {
+        let pressure = |i: usize| self.fixed[i].unwrap_or_else(|| unknowns[self.row[i]]);
+        FlowSolution {
+            pressures: self
+                .nodes
+                .iter()
+                .enumerate()
+                .map(|(i, node)| (*node, pressure(i)))
+                .collect(),
+            flows: self
+                .channels
+                .iter()
+                .zip(conductances)
+                .map(|((id, a, b), g)| (*id, g * (pressure(*a) - pressure(*b))))
+                .collect(),
+        }
+    }
+}
+
+/// Solves the steady-state flow for the given hydraulic resistance per channel id
+pub fn solve_with_resistances(
+    network: &Network,
+    resistances: &BTreeMap<usize, f64>,
+    boundaries: &[Boundary],
+) -> Result<FlowSolution, SimulationError> {
+    let _span = span!("solve_with_resistances");
+    let system = PressureSystem::new(network, boundaries)?;
+    event!(
+        Debug,
+        "{} unknown pressures, {} channels",
+        system.unknowns(),
+        network.channels.len()
+    );
+    let conductances = system
+        .channels
+        .iter()
+        .map(|(id, _, _)| {
+            resistances
+                .get(id)
+                .map(|r| 1. / r)
+                .ok_or(SimulationError::MissingLength(*id))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let unknowns = system.solve(&conductances).inspect_err(|e| {
+        if *e == SimulationError::Singular {
+            event!(
+                Warn,
+                "singular system, a part of the network has no pressure reference"
+            );
+        }
+    })?;
+    Ok(system.solution(&unknowns, &conductances))
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Control of the fixed-point iteration for non-Newtonian fluids
+pub struct IterationSettings {
+    /// Maximum number of linear solves
+    pub max_iterations: usize,
+
+    /// Convergence threshold of the largest flow change relative to the largest flow
+    pub tolerance: f64,
+
+    /// Weight of the new apparent viscosities in each update, in (0, 1]
+    pub relaxation: f64,
+}
+
+impl Default for IterationSettings {
+    fn default() -> Self {
+        IterationSettings {
+            max_iterations: 100,
+            tolerance: 1e-8,
+            relaxation: 0.7,
+        }
+    }
+}
+
+/// Solves the steady-state flow with Poiseuille resistances of all channels, iterating with
+/// default settings for non-Newtonian fluids
+pub fn solve(
+    network: &Network,
+    fluid: &Fluid,
+    boundaries: &[Boundary],
+) -> Result<FlowSolution, SimulationError> {
+    solve_iterative(network, fluid, boundaries, &IterationSettings::default())
+}
+
+/// Solves the steady-state flow. For non-Newtonian fluids the resistances are recomputed from
+/// the apparent viscosity at each channel's wall shear rate until the flows converge.
+pub fn solve_iterative(
+    network: &Network,
+    fluid: &Fluid,
+    boundaries: &[Boundary],
+    settings: &IterationSettings,
+) -> Result<FlowSolution, SimulationError> {
+    solve_iterative_with_progress(
+        network,
+        fluid,
+        boundaries,
+        settings,
+        &ProgressHandle::default(),
+    )
+}
+
+/// Solves like [solve_iterative], reporting every iteration out of the maximum number
+pub fn solve_iterative_with_progress(
+    network: &Network,
+    fluid: &Fluid,
+    boundaries: &[Boundary],
+    settings: &IterationSettings,
+    progress: &ProgressHandle,
+) -> Result<FlowSolution, SimulationError> {
+    iterate(network, fluid, boundaries, settings, progress, &[])
+}
+
+/// Fixed-point iteration of the apparent viscosities and of the resistances of additional
+/// pressure drops c |Q|^e, given as channel id, coefficient c, and exponent e; a channel may
+/// have several
+pub(crate) fn iterate(
+    network: &Network,
+    fluid: &Fluid,
+    boundaries: &[Boundary],
+    settings: &IterationSettings,
+    progress: &ProgressHandle,
+    drops: &[(usize, f64, f64)],
+) -> Result<FlowSolution, SimulationError> {
+    let _span = span!("solve_iterative");
+    if let Some(channel) = network
+        .channels
+        .iter()
+        .find(|c| network.cached_length(c).is_none())
+    {
+        return Err(SimulationError::MissingLength(channel.id));
+    }
+    // Every channel has a length, so there is a resistance for every channel. It is
+    // proportional to the viscosity, so the cache is consulted once instead of per iteration.
+    let unit: BTreeMap<usize, f64> = (network.channels.iter())
+        .map(|c| (c.id, network.cached_resistance(c, 1.).unwrap()))
+        .collect();
+    let resistances = |viscosities: &BTreeMap<usize, f64>, minor: &[f64]| {
+        let mut resistances: BTreeMap<usize, f64> = (unit.iter())
+            .map(|(id, unit)| (*id, viscosities[id] * unit))
+            .collect();
+        for ((id, _, _), resistance) in drops.iter().zip(minor) {
+            *resistances.get_mut(id).unwrap() += resistance;
+        }
+        resistances
+    };
+
+    let mut viscosities: BTreeMap<usize, f64> = network
+        .channels
+        .iter()
+        .map(|c| (c.id, fluid.viscosity))
+        .collect();
+    // Linearized resistances c |Q|^(e - 1) of the additional pressure drops
+    let mut minor = vec![0.; drops.len()];
+    let mut solution =
+        solve_with_resistances(network, &resistances(&viscosities, &minor), boundaries)?;
+    if fluid.rheology == Rheology::Newtonian && drops.is_empty() {
+        return Ok(solution);
+    }
+
+    for iteration in 1..settings.max_iterations {
+        progress.report("solve", iteration, Some(settings.max_iterations))?;
+        for channel in &network.channels {
+            let shear_rate = wall_shear_rate(&channel.shape, solution.flows[&channel.id]);
+            let viscosity = viscosities.get_mut(&channel.id).unwrap();
+            *viscosity += settings.relaxation * (fluid.apparent_viscosity(shear_rate) - *viscosity);
+        }
+        for ((id, c, e), resistance) in drops.iter().zip(&mut minor) {
+            // Resistances of sublinear drops are unbounded at rest, keep the last one
+            let q = solution.flows[id].abs();
+            if q > 0. || *e >= 1. {
+                *resistance += settings.relaxation * (c * q.powf(e - 1.) - *resistance);
+            }
+        }
+        let next = solve_with_resistances(network, &resistances(&viscosities, &minor), boundaries)?;
+        let scale = next.flows.values().fold(0f64, |m, q| m.max(q.abs()));
+        let change = next
+            .flows
+            .iter()
+            .fold(0f64, |m, (id, q)| m.max((q - solution.flows[id]).abs()));
+        solution = next;
+        event!(
+            Trace,
+            "iteration {iteration}: flow change {change:e} of {scale:e}"
+        );
+        if change <= settings.tolerance * scale {
+            return Ok(solution);
+        }
+    }
+    event!(
+        Warn,
+        "not converged in {} iterations",
+        settings.max_iterations
+    );
+    Err(SimulationError::NotConverged {
+        iterations: settings.max_iterations,
+    })
+}
+
+/// LU factorization with partial pivoting of a dense matrix, reusable for several right-hand
+/// sides
+#[derive(Debug, Clone)]
+pub(crate) struct Lu {
+    /// Upper triangle including the diagonal and the multipliers of the unit lower triangle
+    factors: Vec<Vec<f64>>,
+
+    /// Row swapped with each column's row during elimination
+    pivots: Vec<usize>,
+}
+
+impl Lu {
+    /// Factorization, None for singular matrices
+    pub(crate) fn new(mut matrix: Vec<Vec<f64>>) -> Option<Lu> {
+        let n = matrix.len();
+        let scale = matrix.iter().flatten().fold(0f64, |m, v| m.max(v.abs()));
+        let mut pivots = Vec::with_capacity(n);
+        for col in 0..n {
+            let pivot =
+                (col..n).max_by(|a, b| matrix[*a][col].abs().total_cmp(&matrix[*b][col].abs()))?;
+            if matrix[pivot][col].abs() <= scale * 1e-14 {
+                return None;
+            }
+            matrix.swap(col, pivot);
+            pivots.push(pivot);
+            let (upper, lower) = matrix.split_at_mut(col + 1);
+            let pivot_row = &upper[col];
+            for row in lower.iter_mut() {
+                let factor = row[col] / pivot_row[col];
+                row[col] = factor;
+                if factor != 0. {
+                    for (value, pivot_value) in row[col + 1..].iter_mut().zip(&pivot_row[col + 1..])
+                    {
+                        *value -= factor * pivot_value;
+                    }
+                }
+            }
+        }
+        Some(Lu {
+            factors: matrix,
+            pivots,
+        })
+    }
+
+    pub(crate) fn solve(&self, rhs: &[f64]) -> Vec<f64> {
+        let n = rhs.len();
+        let mut x = rhs.to_vec();
+        for (col, pivot) in self.pivots.iter().enumerate() {
+            x.swap(col, *pivot);
+            for r in col + 1..n {
+                x[r] -= self.factors[r][col] * x[col];
+            }
+        }
+        for r in (0..n).rev() {
+            let sum: f64 = (r + 1..n).map(|c| self.factors[r][c] * x[c]).sum();
+            x[r] = (x[r] - sum) / self.factors[r][r];
+        }
+        x
+    }
+}
+
+/// Gaussian elimination with partial pivoting, None for singular systems
+pub(crate) fn gauss(matrix: Vec<Vec<f64>>, rhs: Vec<f64>) -> Option<Vec<f64>> {
+    Some(Lu::new(matrix)?.solve(&rhs))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{Channel, CylindricalShape, Shape},
+            network::Node,
+        },
+        simulation::resistance::resistance_for_length,
+    };
+
+    fn channel(id: usize, a: usize, b: usize, length: f64) -> Channel {
+        Channel {
+            id,
+            node_a: NodeId(a),
+            node_b: NodeId(b),
+            shape: Shape::Cylindrical(CylindricalShape { radius: 1e-4 }),
+            path: None,
+            length: Some(length),
+            layer: 0,
+            metadata: Default::default(),
+        }
+    }
+
+    #[test]
+    fn series_and_parallel() {
+        // 0 -> 1 splits into two channels of different length towards 2
+        let network = Network {
+            nodes: (0..3).map(|i| Node::new(NodeId(i))).collect(),
+            channels: vec![
+                channel(0, 0, 1, 0.01),
+                channel(1, 1, 2, 0.01),
+                channel(2, 2, 1, 0.03),
+            ],
+            ..Default::default()
+        };
+        let boundaries = [
+            Boundary {
+                node: NodeId(0),
+                condition: BoundaryCondition::Flow(1e-9),
+            },
+            Boundary {
+                node: NodeId(2),
+                condition: BoundaryCondition::Pressure(0.),
+            },
+        ];
+        let solution = solve(&network, &Fluid::water(), &boundaries).unwrap();
+        let q = &solution.flows;
+        assert!((q[&0] - 1e-9).abs() < 1e-21);
+        assert!((q[&1] - 0.75e-9).abs() < 1e-21);
+        assert!((q[&2] + 0.25e-9).abs() < 1e-21);
+        assert!(solution.pressures[&NodeId(0)] > solution.pressures[&NodeId(1)]);
+
+        let mut oriented = network.clone();
+        let mut positive = solution.clone();
+        assert_eq!(positive.orient_channels(&mut oriented), [2]);
+        assert_eq!(oriented.channels[2].node_a, NodeId(1));
+        assert_eq!(positive.flows[&2], -q[&2]);
+        assert_eq!(
+            solve(&oriented, &Fluid::water(), &boundaries)
+                .unwrap()
+                .flows[&2],
+            positive.flows[&2]
+        );
+
+        let json = serde_json::to_string(&solution).unwrap();
+        assert_eq!(
+            serde_json::from_str::<FlowSolution>(&json).unwrap(),
+            solution
+        );
+
+        assert_eq!(
+            solve(&network, &Fluid::water(), &boundaries[..1]),
+            Err(SimulationError::Singular)
+        );
+    }
+
+    #[test]
+    fn shear_thinning_fixed_point() {
+        let network = Network {
+            nodes: (0..2).map(|i| Node::new(NodeId(i))).collect(),
+            channels: vec![channel(0, 0, 1, 0.01)],
+            ..Default::default()
+        };
+        let fluid = Fluid {
+            rheology: Rheology::PowerLaw {
+                consistency: 0.02,
+                flow_index: 0.5,
+            },
+            ..Fluid::water()
+        };
+        let boundaries = [
+            Boundary {
+                node: NodeId(0),
+                condition: BoundaryCondition::Pressure(1000.),
+            },
+            Boundary {
+                node: NodeId(1),
+                condition: BoundaryCondition::Pressure(0.),
+            },
+        ];
+        let q = solve(&network, &fluid, &boundaries).unwrap().flows[&0];
+        let channel = &network.channels[0];
+        let viscosity = fluid.apparent_viscosity(wall_shear_rate(&channel.shape, q));
+        let resistance = resistance_for_length(&channel.shape, viscosity, 0.01);
+        assert!((resistance * q - 1000.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn sparse_matches_dense() {
+        use crate::base::generator::{RandomNetworkSpec, Topology};
+
+        let mut network = Network::random(&RandomNetworkSpec {
+            topology: Topology::Grid {
+                columns: 30,
+                rows: 30,
+            },
+            ..Default::default()
+        });
+        let conductances: Vec<f64> = network
+            .channels
+            .iter()
+            .map(|c| 1. / network.cached_resistance(c, 1e-3).unwrap())
+            .collect();
+        let boundaries = [
+            Boundary {
+                node: NodeId(0),
+                condition: BoundaryCondition::Pressure(100.),
+            },
+            Boundary {
+                node: NodeId(899),
+                condition: BoundaryCondition::Pressure(0.),
+            },
+        ];
+        let system = PressureSystem::new(&network, &boundaries).unwrap();
+        assert!(system.unknowns() > DENSE_LIMIT);
+        let sparse = system.solve(&conductances).unwrap();
+        let (matrix, rhs) = system.assemble(&conductances);
+        let dense = gauss(matrix, rhs).unwrap();
+        for (s, d) in sparse.iter().zip(&dense) {
+            assert!((s - d).abs() < 1e-8);
+        }
+
+        network.nodes.push(Node::new(NodeId(900)));
+        let system = PressureSystem::new(&network, &boundaries).unwrap();
+        assert_eq!(system.solve(&conductances), Err(SimulationError::Singular));
+    }
+}
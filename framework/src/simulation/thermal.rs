@@ -0,0 +1,295 @@
+//! Rough one-dimensional heat transport coupled to the flow, e.g., to evaluate PCR or
+//! temperature-gradient chips. The fluid is carried downstream and exchanges heat with the
+//! channel walls, which are at the temperature of the heaters covering them and at ambient
+//! temperature elsewhere; inflows mix perfectly at nodes. Viscosities follow the channel
+//! temperatures, so flow and temperatures are iterated until the flows converge.
+
+use super::{
+    fluid::ThermalFluid,
+    resistance::resistance_for_length,
+    solver::{
+        solve_with_resistances, Boundary, BoundaryCondition, FlowSolution, IterationSettings,
+    },
+    SimulationError,
+};
+use crate::base::{
+    channel::{Channel, SVGPath},
+    network::{ModuleKind, Network, NodeId},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Points per channel path sampled for the heater coverage
+const SAMPLES: usize = 32;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Thermal conditions of a simulation
+pub struct ThermalSetup {
+    pub fluid: ThermalFluid,
+
+    /// Wall temperature outside of heaters, and temperature of inflows without one given
+    pub ambient_temperature: f64,
+
+    /// Temperature of the fluid entering at the node
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inlet_temperatures: BTreeMap<NodeId, f64>,
+
+    /// Nusselt number of the heat transfer between wall and fluid, h D_h / k, e.g., about 4
+    /// for fully developed laminar flow
+    pub nusselt: f64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Steady-state flow and temperatures
+pub struct ThermalSolution {
+    pub flow: FlowSolution,
+
+    /// Temperature per node id, mixed from the inflows
+    pub temperatures: BTreeMap<NodeId, f64>,
+
+    /// Mean fluid temperature per channel id
+    pub channel_temperatures: BTreeMap<usize, f64>,
+}
+
+/// Wall temperature per channel id: the ambient temperature blended with the temperatures of
+/// heaters by the share of the channel path they cover
+pub fn wall_temperatures(network: &Network, ambient: f64) -> BTreeMap<usize, f64> {
+    let heaters: Vec<_> = (network.modules.iter())
+        .filter_map(|m| match m.kind {
+            ModuleKind::Heater { temperature } => Some((m, temperature)),
+            ModuleKind::Generic | ModuleKind::Valve => None,
+        })
+        .collect();
+    (network.channels.iter())
+        .map(|channel| {
+            let Some(path) = channel.path.as_ref().filter(|_| !heaters.is_empty()) else {
+                return (channel.id, ambient);
+            };
+            let length = path.length().0;
+            let heat: f64 = (0..SAMPLES)
+                .filter_map(|i| path.point_at_length((i as f64 + 0.5) / SAMPLES as f64 * length))
+                .map(|point| {
+                    (heaters.iter())
+                        .find(|(module, _)| module.contains(point))
+                        .map_or(ambient, |(_, temperature)| *temperature)
+                })
+                .sum();
+            (channel.id, heat / SAMPLES as f64)
+        })
+        .collect()
+}
+
+/// Carries the heat downstream through the solved flow, given the wall temperature and the
+/// previous mean temperature of each channel. Nodes are visited in order of decreasing
+/// pressure, so all inflows of a node are known when it is reached.
+fn transport(
+    network: &Network,
+    boundaries: &[Boundary],
+    setup: &ThermalSetup,
+    flow: &FlowSolution,
+    walls: &BTreeMap<usize, f64>,
+    previous: &BTreeMap<usize, f64>,
+) -> (BTreeMap<NodeId, f64>, BTreeMap<usize, f64>) {
+    let inlet = |node: &NodeId| {
+        (setup.inlet_temperatures.get(node).copied()).unwrap_or(setup.ambient_temperature)
+    };
+    let mut nodes: Vec<(NodeId, f64)> = flow.pressures.iter().map(|(n, p)| (*n, *p)).collect();
+    nodes.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    // Heat capacity flow and outlet temperature per channel arriving at each node
+    let mut arriving: BTreeMap<NodeId, Vec<(f64, f64)>> = BTreeMap::new();
+    for boundary in boundaries {
+        if let BoundaryCondition::Flow(q) = boundary.condition {
+            if q > 0. {
+                (arriving.entry(boundary.node).or_default()).push((q, inlet(&boundary.node)));
+            }
+        }
+    }
+    let mut by_node: BTreeMap<NodeId, Vec<&Channel>> = BTreeMap::new();
+    for channel in &network.channels {
+        by_node.entry(channel.node_a).or_default().push(channel);
+        by_node.entry(channel.node_b).or_default().push(channel);
+    }
+
+    let mut temperatures = BTreeMap::new();
+    let mut channel_temperatures = BTreeMap::new();
+    for (node, _) in nodes {
+        let inflows = arriving.remove(&node).unwrap_or_default();
+        let total: f64 = inflows.iter().map(|(q, _)| q).sum();
+        let temperature = match total > 0. {
+            true => inflows.iter().map(|(q, t)| q * t).sum::<f64>() / total,
+            false => inlet(&node),
+        };
+        temperatures.insert(node, temperature);
+
+        for channel in by_node.get(&node).into_iter().flatten() {
+            let q = flow.flows[&channel.id];
+            let downstream = match (q > 0., q < 0.) {
+                (true, _) if channel.node_a == node => channel.node_b,
+                (_, true) if channel.node_b == node => channel.node_a,
+                _ => continue,
+            };
+            let wall = walls[&channel.id];
+            let fluid = setup.fluid.at(previous[&channel.id]);
+            let diameter = channel.shape.hydraulic_diameter();
+            let conductance = setup.nusselt * setup.fluid.thermal_conductivity / diameter
+                * (4. * channel.shape.area() / diameter)
+                * network.cached_length(channel).unwrap_or(0.);
+            let units = conductance / (fluid.density * setup.fluid.heat_capacity * q.abs());
+            let decay = (-units).exp();
+            let outlet = wall + (temperature - wall) * decay;
+            let mean = match units > 0. {
+                true => wall + (temperature - wall) * (1. - decay) / units,
+                false => temperature,
+            };
+            channel_temperatures.insert(channel.id, mean);
+            (arriving.entry(downstream).or_default()).push((q.abs(), outlet));
+        }
+    }
+    for channel in &network.channels {
+        (channel_temperatures.entry(channel.id)).or_insert(walls[&channel.id]);
+    }
+    (temperatures, channel_temperatures)
+}
+
+/// Solves flow and temperatures, recomputing the resistances from the viscosity at the mean
+/// temperature of each channel until the flows converge
+pub fn solve_thermal(
+    network: &Network,
+    boundaries: &[Boundary],
+    setup: &ThermalSetup,
+    settings: &IterationSettings,
+) -> Result<ThermalSolution, SimulationError> {
+    let lengths = (network.channels.iter())
+        .map(|c| {
+            let length = network.cached_length(c);
+            length.ok_or(SimulationError::MissingLength(c.id))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let walls = wall_temperatures(network, setup.ambient_temperature);
+    let mut channel_temperatures: BTreeMap<usize, f64> = (network.channels.iter())
+        .map(|c| (c.id, setup.ambient_temperature))
+        .collect();
+
+    let mut previous: Option<FlowSolution> = None;
+    for _ in 0..settings.max_iterations {
+        let resistances = (network.channels.iter())
+            .zip(&lengths)
+            .map(|(c, length)| {
+                let viscosity = setup.fluid.at(channel_temperatures[&c.id]).viscosity;
+                (c.id, resistance_for_length(&c.shape, viscosity, *length))
+            })
+            .collect();
+        let flow = solve_with_resistances(network, &resistances, boundaries)?;
+        let (temperatures, next) = transport(
+            network,
+            boundaries,
+            setup,
+            &flow,
+            &walls,
+            &channel_temperatures,
+        );
+        for (id, temperature) in next {
+            let current = channel_temperatures.get_mut(&id).unwrap();
+            *current += settings.relaxation * (temperature - *current);
+        }
+
+        let scale = flow.flows.values().fold(0f64, |m, q| m.max(q.abs()));
+        let converged = previous.as_ref().is_some_and(|previous| {
+            (flow.flows.iter())
+                .all(|(id, q)| (q - previous.flows[id]).abs() <= settings.tolerance * scale)
+        });
+        if converged {
+            return Ok(ThermalSolution {
+                flow,
+                temperatures,
+                channel_temperatures,
+            });
+        }
+        previous = Some(flow);
+    }
+    Err(SimulationError::NotConverged {
+        iterations: settings.max_iterations,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        base::{
+            channel::{ChannelPath, LineSegment, PathPiece, RectangularShape, Shape},
+            network::{Module, Node},
+            primitives::{Dimensions, Point},
+        },
+        simulation::solver::solve,
+    };
+
+    #[test]
+    fn heated_channel() {
+        let water = ThermalFluid::water();
+        assert_eq!(water.at(293.15), water.reference);
+        let warm = water.at(323.15).viscosity;
+        assert!((warm / 0.547e-3 - 1.).abs() < 0.1, "{warm}");
+
+        let mut path = ChannelPath::new();
+        path.add(PathPiece::LineSegment(LineSegment {
+            start: Point([0., 0.]),
+            end: Point([20e-3, 0.]),
+        }));
+        let mut network = Network {
+            nodes: vec![Node::new(NodeId(0)), Node::new(NodeId(1))],
+            channels: vec![Channel {
+                id: 0,
+                node_a: NodeId(0),
+                node_b: NodeId(1),
+                shape: Shape::Rectangular(RectangularShape {
+                    width: 100e-6,
+                    height: 50e-6,
+                }),
+                path: Some(path),
+                length: None,
+                layer: 0,
+                metadata: Default::default(),
+            }],
+            ..Default::default()
+        };
+        // The heater covers the downstream half of the channel
+        network.modules.push(Module {
+            id: 0,
+            position: Point([10e-3, -1e-3]),
+            size: Dimensions([11e-3, 2e-3]),
+            nodes: Vec::new(),
+            implementation: None,
+            model: None,
+            footprint: None,
+            orientation: Default::default(),
+            kind: ModuleKind::Heater {
+                temperature: 368.15,
+            },
+            metadata: Default::default(),
+        });
+        let boundaries = [(0, 1e4), (1, 0.)].map(|(node, p)| Boundary {
+            node: NodeId(node),
+            condition: BoundaryCondition::Pressure(p),
+        });
+        let setup = ThermalSetup {
+            fluid: water,
+            ambient_temperature: 293.15,
+            inlet_temperatures: BTreeMap::new(),
+            nusselt: 4.,
+        };
+        let walls = wall_temperatures(&network, setup.ambient_temperature);
+        assert!((walls[&0] - (293.15 + 368.15) / 2.).abs() < 1e-9);
+
+        let solution = solve_thermal(&network, &boundaries, &setup, &Default::default()).unwrap();
+        assert_eq!(solution.temperatures[&NodeId(0)], 293.15);
+        let outlet = solution.temperatures[&NodeId(1)];
+        assert!(outlet > 320. && outlet < 368.15, "{outlet}");
+        let isothermal = solve(&network, &water.reference, &boundaries).unwrap();
+        assert!(solution.flow.flows[&0] > isothermal.flows[&0]);
+    }
+}
@@ -0,0 +1,205 @@
+//! Spans and events of the computational subsystems for profiling and debugging long-running
+//! designer calls. Without the `tracing` feature instrumentation compiles to nothing; with it,
+//! spans and events go to the subscriber installed with [`set_subscriber`], e.g., the browser
+//! console or Python's `logging` through the interface macros.
+
+use std::{
+    sync::RwLock,
+    time::{Duration, Instant},
+};
+
+/// Whether instrumentation is compiled in
+pub const ENABLED: bool = cfg!(feature = "tracing");
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+/// Severity of an event, ordered from the most to the least severe
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::str::FromStr for Level {
+    type Err = String;
+
+    /// Parses level names case-insensitively, e.g., "info"
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" | "warning" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            _ => Err(format!("unknown level {name}")),
+        }
+    }
+}
+
+/// Receiver of spans and events. `target` is the module path of the instrumented code.
+pub trait Subscriber: Send + Sync {
+    fn event(&self, level: Level, target: &str, message: &str);
+
+    fn enter(&self, _target: &str, _name: &str) {}
+
+    fn exit(&self, _target: &str, _name: &str, _elapsed: Duration) {}
+}
+
+static SUBSCRIBER: RwLock<Option<Box<dyn Subscriber>>> = RwLock::new(None);
+
+/// Installs the subscriber, replacing the previous one
+pub fn set_subscriber(subscriber: impl Subscriber + 'static) {
+    *SUBSCRIBER.write().unwrap() = Some(Box::new(subscriber));
+}
+
+/// Removes the subscriber
+pub fn clear_subscriber() {
+    *SUBSCRIBER.write().unwrap() = None;
+}
+
+/// Whether a subscriber receives spans and events
+pub fn enabled() -> bool {
+    ENABLED && SUBSCRIBER.read().unwrap().is_some()
+}
+
+fn with_subscriber(f: impl FnOnce(&dyn Subscriber)) {
+    if let Some(subscriber) = SUBSCRIBER.read().unwrap().as_deref() {
+        f(subscriber);
+    }
+}
+
+/// Sends an event to the subscriber
+pub fn emit(level: Level, target: &str, message: std::fmt::Arguments) {
+    with_subscriber(|s| s.event(level, target, &message.to_string()));
+}
+
+#[derive(Debug)]
+/// Section of work, exited with its duration when dropped
+pub struct Span {
+    target: &'static str,
+    name: &'static str,
+    start: Instant,
+}
+
+impl Span {
+    /// Enters the span
+    pub fn enter(target: &'static str, name: &'static str) -> Span {
+        with_subscriber(|s| s.enter(target, name));
+        Span {
+            target,
+            name,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        with_subscriber(|s| s.exit(self.target, self.name, self.start.elapsed()));
+    }
+}
+
+/// Subscriber writing everything up to a level to stderr
+#[derive(Debug, Copy, Clone)]
+pub struct StderrSubscriber {
+    pub max_level: Level,
+}
+
+impl Subscriber for StderrSubscriber {
+    fn event(&self, level: Level, target: &str, message: &str) {
+        if level <= self.max_level {
+            eprintln!("{level} {target}: {message}");
+        }
+    }
+
+    fn exit(&self, target: &str, name: &str, elapsed: Duration) {
+        if Level::Debug <= self.max_level {
+            eprintln!("{} {target}: {name} took {elapsed:?}", Level::Debug);
+        }
+    }
+}
+
+/// Enters a span for the rest of the scope, None if no subscriber is installed
+macro_rules! span {
+    ($name: expr) => {
+        $crate::trace::enabled().then(|| $crate::trace::Span::enter(module_path!(), $name))
+    };
+}
+
+/// Sends an event formatted like `format!` at the level
+macro_rules! event {
+    ($level: ident, $($arg: tt)+) => {
+        if $crate::trace::enabled() {
+            $crate::trace::emit(
+                $crate::trace::Level::$level,
+                module_path!(),
+                format_args!($($arg)+),
+            );
+        }
+    };
+}
+
+pub(crate) use {event, span};
+
+#[cfg(all(test, feature = "tracing"))]
+mod test {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default, Clone)]
+    /// Records spans and events of this module only, other tests may run concurrently
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Recorder {
+        fn record(&self, target: &str, record: String) {
+            if target == module_path!() {
+                self.0.lock().unwrap().push(record);
+            }
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn event(&self, level: Level, target: &str, message: &str) {
+            self.record(target, format!("{level} {message}"));
+        }
+
+        fn enter(&self, target: &str, name: &str) {
+            self.record(target, format!("enter {name}"));
+        }
+
+        fn exit(&self, target: &str, name: &str, _elapsed: Duration) {
+            self.record(target, format!("exit {name}"));
+        }
+    }
+
+    #[test]
+    fn recording() {
+        let recorder = Recorder::default();
+        set_subscriber(recorder.clone());
+        {
+            let _span = span!("work");
+            event!(Info, "{} items", 3);
+        }
+        clear_subscriber();
+        event!(Info, "dropped");
+        assert_eq!("Warning".parse(), Ok(Level::Warn));
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            ["enter work", "INFO 3 items", "exit work"]
+        );
+    }
+}
@@ -4,16 +4,80 @@ extern crate syn;
 extern crate quote;
 
 use proc_macro::TokenStream;
+use syn::{Attribute, Body, Lit, MetaItem, NestedMetaItem, VariantData};
 
-/// Not used atm but could add boilerplate functionality
-#[proc_macro_derive(MMFTInterface)]
+/// String values of `key = "value"` items in the `#[mmft(...)]` attributes
+fn mmft_values(attrs: &[Attribute], key: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    for attr in attrs {
+        if let MetaItem::List(ref name, ref items) = attr.value {
+            if name != "mmft" {
+                continue;
+            }
+            for item in items {
+                if let NestedMetaItem::MetaItem(MetaItem::NameValue(
+                    ref name,
+                    Lit::Str(ref value, _),
+                )) = *item
+                {
+                    if name == key {
+                        values.push(value.clone());
+                    }
+                }
+            }
+        }
+    }
+    values
+}
+
+/// Calls of the functions named by `#[mmft(example = "path::to::function")]` attributes
+fn example_calls(attrs: &[Attribute]) -> Vec<syn::Path> {
+    mmft_values(attrs, "example")
+        .iter()
+        .map(|path| syn::parse_path(path).unwrap())
+        .collect()
+}
+
+/// Implements `MMFTInterface`. Fields can be annotated with `#[mmft(unit = "um")]`; the unit is
+/// added to the field's schema as `unit` keyword and to its description. Examples attached with
+/// `#[mmft(example = "function")]` on the type are added to the schema's `examples`.
+#[proc_macro_derive(MMFTInterface, attributes(mmft))]
 pub fn impl_mmft_interface(s: TokenStream) -> TokenStream {
     let ast = syn::parse_derive_input(&s.to_string()).unwrap();
     let name = &ast.ident;
+    let examples = example_calls(&ast.attrs);
+    let add_examples = if examples.is_empty() {
+        quote! {}
+    } else {
+        quote! {
+            schema["examples"] = serde_json::Value::Array(vec![
+                #(serde_json::to_value(&#examples()).unwrap()),*
+            ]);
+        }
+    };
+
+    let mut fields = Vec::new();
+    let mut units = Vec::new();
+    if let Body::Struct(VariantData::Struct(ref struct_fields)) = ast.body {
+        for field in struct_fields {
+            if let (Some(ident), Some(unit)) =
+                (&field.ident, mmft_values(&field.attrs, "unit").pop())
+            {
+                fields.push(ident.to_string());
+                units.push(unit);
+            }
+        }
+    }
+
     let gen = quote! {
         impl MMFTInterface for #name {
+            const UNITS: &'static [(&'static str, &'static str)] = &[#((#fields, #units)),*];
+
             fn schema() -> String {
-                serde_json::to_string_pretty(&schema_for!(#name)).unwrap()
+                let mut schema = serde_json::to_value(schema_for!(#name)).unwrap();
+                <Self as MMFTInterface>::annotate_schema(&mut schema);
+                #add_examples
+                serde_json::to_string_pretty(&schema).unwrap()
             }
 
             fn from_json(str: &str) -> Self {
@@ -27,3 +91,20 @@ pub fn impl_mmft_interface(s: TokenStream) -> TokenStream {
     };
     gen.parse().unwrap()
 }
+
+/// Implements `MMFTExample` with the instances returned by the functions named in
+/// `#[mmft(example = "path::to::function")]` attributes on the type, in attribute order
+#[proc_macro_derive(MMFTExample, attributes(mmft))]
+pub fn impl_mmft_example(s: TokenStream) -> TokenStream {
+    let ast = syn::parse_derive_input(&s.to_string()).unwrap();
+    let name = &ast.ident;
+    let examples = example_calls(&ast.attrs);
+    let gen = quote! {
+        impl MMFTExample for #name {
+            fn examples() -> Vec<Self> {
+                vec![#(#examples()),*]
+            }
+        }
+    };
+    gen.parse().unwrap()
+}